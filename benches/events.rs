@@ -0,0 +1,83 @@
+// ============================================================================
+// Event Fan-out Benchmark: cloned events against shared `BroadcastEvent`s
+// ============================================================================
+//
+// 10k events go through a broadcast channel to 100 subscribers, and every
+// subscriber turns each event into JSON, as the events endpoint, the FFI and
+// the Python bindings do. `cloned` is how the bus used to deliver (each
+// subscriber receives its own copy of the event and serializes it);
+// `shared` is the current bus (one `Arc<BroadcastEvent>` per event, its JSON
+// serialized once).
+//
+//   cargo bench --bench events
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use pattern_clock::events::{BroadcastEvent, Event, SharedEvent};
+use pattern_clock::payload::Payload;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+const SUBSCRIBERS: usize = 100;
+const EVENTS: usize = 10_000;
+/// Events sent before the subscribers catch up, as many as the bus holds
+const BURST: usize = 256;
+
+fn sample_event(index: usize) -> Event {
+    Event {
+        kind: "agent.handled".to_string(),
+        payload: json!({
+            "agent_id": index % 5 + 1,
+            "message": "process_data",
+            "duration_ms": index % 250,
+            "ok": true,
+        }),
+        ts: index as u64,
+        attachment: Some(Payload::text("sensor reading ".repeat(64))),
+        origin: None,
+    }
+}
+
+/// Send `events` in bursts and have every subscriber `deliver` each; returns the bytes delivered
+fn fan_out<T: Clone>(events: Vec<T>, deliver: impl Fn(T) -> usize) -> usize {
+    let (sender, _) = broadcast::channel::<T>(BURST);
+    let mut receivers: Vec<_> = (0..SUBSCRIBERS).map(|_| sender.subscribe()).collect();
+    let mut events = events.into_iter().peekable();
+    let mut bytes = 0;
+    while events.peek().is_some() {
+        for event in events.by_ref().take(BURST) {
+            let _ = sender.send(event);
+        }
+        for receiver in &mut receivers {
+            while let Ok(event) = receiver.try_recv() {
+                bytes += deliver(event);
+            }
+        }
+    }
+    bytes
+}
+
+fn bench_fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("events/fan-out");
+    group.throughput(Throughput::Elements((EVENTS * SUBSCRIBERS) as u64));
+    group.sample_size(10);
+
+    group.bench_function("cloned", |b| {
+        b.iter_batched(
+            || (0..EVENTS).map(sample_event).collect::<Vec<Event>>(),
+            |events| fan_out(events, |event| serde_json::to_string(&event).map_or(0, |json| json.len())),
+            BatchSize::LargeInput,
+        )
+    });
+    // Fresh events per run, so no JSON is cached from the previous one
+    group.bench_function("shared", |b| {
+        b.iter_batched(
+            || (0..EVENTS).map(|index| BroadcastEvent::new(sample_event(index))).collect::<Vec<SharedEvent>>(),
+            |events| fan_out(events, |event| event.json().len()),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_fan_out);
+criterion_main!(benches);
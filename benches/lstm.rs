@@ -0,0 +1,134 @@
+// ============================================================================
+// LSTM Benchmarks: hand-rolled `LstmCell` against `burn::nn::Lstm`
+// ============================================================================
+//
+// Both implementations run the same weights (`BuiltinLstm::from_lstm`) on the
+// CPU (NdArray) and GPU (Wgpu) backends across model sizes. That they agree
+// (within `lstm::PARITY_TOLERANCE`) is checked by the tests in `src/lstm.rs`;
+// these only time them.
+//
+// The `lstm-fusion` groups time the custom LSTM's sequence-wide input
+// projection (`forward_stateful`) against projecting each timestep on its
+// own (`forward_stepwise`), over sequence lengths.
+//
+// The `lstm-batch` groups time sequences of different lengths run one at a
+// time against the same sequences padded into one batch
+// (`Recurrent::forward_packed`).
+//
+//   cargo bench --bench lstm
+//   PATTERN_CLOCK_BENCH_GPU=0 cargo bench --bench lstm   # CPU only
+
+use burn::backend::wgpu::Wgpu;
+use burn::backend::NdArray;
+use burn::tensor::backend::Backend;
+use burn::tensor::{Distribution, Tensor};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pattern_clock::lstm::{BuiltinLstm, Lstm, LstmConfig, Recurrent};
+
+/// (input_size, hidden_size, num_layers)
+const SIZES: [(usize, usize, usize); 4] = [(32, 64, 1), (128, 256, 1), (128, 256, 2), (256, 512, 1)];
+const BATCH: usize = 8;
+const SEQ_LEN: usize = 32;
+
+fn bench_backend<B: Backend>(c: &mut Criterion, backend: &str) {
+    let device = Default::default();
+    let mut group = c.benchmark_group(format!("lstm/{}", backend));
+    group.throughput(Throughput::Elements((BATCH * SEQ_LEN) as u64));
+
+    for (input_size, hidden_size, num_layers) in SIZES {
+        let size = format!("{}x{}x{}", input_size, hidden_size, num_layers);
+        let config = LstmConfig { input_size, hidden_size, num_layers, ..Default::default() };
+        let custom = Lstm::<B>::new(config, &device);
+        let builtin = BuiltinLstm::from_lstm(&custom);
+        let input = Tensor::<B, 3>::random([BATCH, SEQ_LEN, input_size], Distribution::Default, &device);
+
+        let implementations: [(&str, &dyn Recurrent<B>); 2] = [("custom", &custom), ("burn", &builtin)];
+        for (name, model) in implementations {
+            group.bench_with_input(BenchmarkId::new(name, &size), &input, |b, input| {
+                // Reading the output back waits for the backend to finish
+                b.iter(|| model.forward_stateful(input.clone(), None).0.into_data())
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Sequence lengths of the fusion benchmark, at the default model size
+const FUSION_SEQ_LENS: [usize; 3] = [16, 64, 256];
+
+fn bench_fusion<B: Backend>(c: &mut Criterion, backend: &str) {
+    let device = Default::default();
+    let config = LstmConfig::default();
+    let model = Lstm::<B>::new(config.clone(), &device);
+    let mut group = c.benchmark_group(format!("lstm-fusion/{}", backend));
+
+    for seq_len in FUSION_SEQ_LENS {
+        let input = Tensor::<B, 3>::random([BATCH, seq_len, config.input_size], Distribution::Default, &device);
+        group.throughput(Throughput::Elements((BATCH * seq_len) as u64));
+        group.bench_with_input(BenchmarkId::new("stepwise", seq_len), &input, |b, input| {
+            b.iter(|| model.forward_stepwise(input.clone(), None).0.into_data())
+        });
+        group.bench_with_input(BenchmarkId::new("fused", seq_len), &input, |b, input| {
+            b.iter(|| model.forward_stateful(input.clone(), None).0.into_data())
+        });
+    }
+    group.finish();
+}
+
+/// Sequences of the batching benchmark, with lengths spread over 8..=64 steps
+const BATCHED_SEQUENCES: usize = 16;
+
+fn bench_batching<B: Backend>(c: &mut Criterion, backend: &str) {
+    let device = Default::default();
+    let config = LstmConfig::default();
+    let custom = Lstm::<B>::new(config.clone(), &device);
+    let builtin = BuiltinLstm::from_lstm(&custom);
+
+    let lengths: Vec<usize> = (0..BATCHED_SEQUENCES).map(|i| 8 + i * 56 / (BATCHED_SEQUENCES - 1)).collect();
+    let max_len = lengths.iter().copied().max().unwrap_or(1);
+    let sequences: Vec<Tensor<B, 3>> = lengths
+        .iter()
+        .map(|length| Tensor::random([1, *length, config.input_size], Distribution::Default, &device))
+        .collect();
+    let padded = Tensor::cat(
+        sequences
+            .iter()
+            .map(|sequence| match max_len - sequence.dims()[1] {
+                0 => sequence.clone(),
+                padding => Tensor::cat(vec![sequence.clone(), Tensor::zeros([1, padding, config.input_size], &device)], 1),
+            })
+            .collect(),
+        0,
+    );
+
+    let implementations: [(&str, &dyn Recurrent<B>); 2] = [("custom", &custom), ("burn", &builtin)];
+    let mut group = c.benchmark_group(format!("lstm-batch/{}", backend));
+    group.throughput(Throughput::Elements(lengths.iter().sum::<usize>() as u64));
+    for (name, model) in implementations {
+        group.bench_function(BenchmarkId::new("one-by-one", name), |b| {
+            b.iter(|| {
+                for sequence in &sequences {
+                    let _ = model.forward_stateful(sequence.clone(), None).0.into_data();
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("packed", name), |b| {
+            b.iter(|| model.forward_packed(padded.clone(), &lengths).0.into_data())
+        });
+    }
+    group.finish();
+}
+
+fn lstm_benches(c: &mut Criterion) {
+    bench_backend::<NdArray>(c, "ndarray");
+    bench_fusion::<NdArray>(c, "ndarray");
+    bench_batching::<NdArray>(c, "ndarray");
+    if std::env::var("PATTERN_CLOCK_BENCH_GPU").as_deref() != Ok("0") {
+        bench_backend::<Wgpu>(c, "wgpu");
+        bench_fusion::<Wgpu>(c, "wgpu");
+        bench_batching::<Wgpu>(c, "wgpu");
+    }
+}
+
+criterion_group!(benches, lstm_benches);
+criterion_main!(benches);
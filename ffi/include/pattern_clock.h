@@ -0,0 +1,45 @@
+/* pattern-clock C API (build with `cargo build --release` in ffi/, link -lpattern_clock_ffi) */
+#ifndef PATTERN_CLOCK_H
+#define PATTERN_CLOCK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef _WIN32
+typedef long long pc_ssize_t;
+#else
+#include <sys/types.h>
+typedef ssize_t pc_ssize_t;
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PC_OK 0
+#define PC_ERR_NOT_STARTED -1
+#define PC_ERR_INVALID_ARGUMENT -2
+#define PC_ERR_RUNTIME -3
+#define PC_ERR_BUSY -4        /* the agent's queue is full or it is draining; retry later */
+#define PC_ERR_NOT_RUNNING -5 /* no agent with that id is running */
+
+/* Start the runtime with `agents` agents; no-op if already started.
+ * Fails with PC_ERR_RUNTIME after pc_shutdown: one runtime per process. */
+int32_t pc_init(uint8_t agents);
+
+/* Queue a NUL-terminated UTF-8 payload on agent `agent_id` (1-based).
+ * Returns PC_ERR_BUSY or PC_ERR_NOT_RUNNING when the agent refuses it. */
+int32_t pc_submit(uint8_t agent_id, const char *data);
+
+/* Copy the next event as NUL-terminated JSON into `buffer`.
+ * Returns bytes written, 0 if none pending, or -(required size) if `len` is too small. */
+pc_ssize_t pc_poll_events(char *buffer, size_t len);
+
+/* Stop all agents; events published until then can still be polled. */
+int32_t pc_shutdown(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PATTERN_CLOCK_H */
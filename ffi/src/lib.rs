@@ -0,0 +1,148 @@
+use std::ffi::{c_char, CStr};
+use std::sync::{Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+use pattern_clock::agents::{get_agent, send_work, AgentMessage};
+use pattern_clock::events::{subscribe, SharedEvent};
+use pattern_clock::log_error;
+use pattern_clock::mailbox::{Priority, SendError};
+use pattern_clock::runtime::{background_runtime, PatternClock, RuntimeHandle};
+
+// ============================================================================
+// C FFI
+// ============================================================================
+//
+// Minimal extern-C surface for C/C++/C# hosts; see `include/pattern_clock.h`.
+// A separate crate so the main library stays a plain rlib; link against
+// `libpattern_clock_ffi` (`.so`/`.dylib`/`.dll`, or the static `.a`/`.lib`).
+// All functions return `PC_OK` (0) on success and a negative code on failure.
+
+pub const PC_OK: i32 = 0;
+pub const PC_ERR_NOT_STARTED: i32 = -1;
+pub const PC_ERR_INVALID_ARGUMENT: i32 = -2;
+pub const PC_ERR_RUNTIME: i32 = -3;
+/// The agent's queue is full, or it is draining; retry later
+pub const PC_ERR_BUSY: i32 = -4;
+/// No agent with that id is running
+pub const PC_ERR_NOT_RUNNING: i32 = -5;
+
+/// Set by `pc_init`, cleared by `pc_shutdown`
+static HANDLE: Mutex<Option<RuntimeHandle>> = Mutex::new(None);
+
+/// Event receiver plus an event that did not fit the caller's buffer; set
+/// once the runtime started, and kept after shutdown for its last events
+type Events = (broadcast::Receiver<SharedEvent>, Option<String>);
+static EVENTS: Mutex<Option<Events>> = Mutex::new(None);
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start the runtime with `agents` agents (Ollama and storage from the environment)
+///
+/// Calling it again after a successful start is a no-op; after `pc_shutdown`
+/// it fails, as only one runtime can be started per process.
+#[no_mangle]
+pub extern "C" fn pc_init(agents: u8) -> i32 {
+    let mut handle = lock(&HANDLE);
+    if handle.is_some() {
+        return PC_OK;
+    }
+    if agents == 0 {
+        return PC_ERR_INVALID_ARGUMENT;
+    }
+    // Subscribed first so the events of the start itself are kept
+    let receiver = subscribe();
+    match background_runtime().block_on(PatternClock::builder().agents(agents).build().start()) {
+        Ok(started) => {
+            *handle = Some(started);
+            *lock(&EVENTS) = Some((receiver, None));
+            PC_OK
+        }
+        Err(e) => {
+            log_error!("[FFI] Failed to start runtime: {}", e);
+            PC_ERR_RUNTIME
+        }
+    }
+}
+
+/// Queue a NUL-terminated UTF-8 payload on an agent
+///
+/// Fails with `PC_ERR_BUSY` when the agent's queue is full or it is
+/// draining, and `PC_ERR_NOT_RUNNING` when no agent `agent_id` is running.
+///
+/// # Safety
+/// `data` must be a valid pointer to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pc_submit(agent_id: u8, data: *const c_char) -> i32 {
+    if lock(&HANDLE).is_none() {
+        return PC_ERR_NOT_STARTED;
+    }
+    if data.is_null() {
+        return PC_ERR_INVALID_ARGUMENT;
+    }
+    let Ok(data) = CStr::from_ptr(data).to_str() else {
+        return PC_ERR_INVALID_ARGUMENT;
+    };
+    // Like `RuntimeHandle::submit`, keeping the reason a payload was refused
+    let Some(agent) = get_agent(agent_id) else {
+        return PC_ERR_NOT_RUNNING;
+    };
+    match send_work(&agent, AgentMessage::ProcessData { data: data.to_string(), priority: Priority::Normal }) {
+        Ok(()) => PC_OK,
+        Err(SendError::Full { .. } | SendError::Draining { .. }) => PC_ERR_BUSY,
+        Err(SendError::Closed(_)) => PC_ERR_NOT_RUNNING,
+    }
+}
+
+/// Copy the next pending event as NUL-terminated JSON into `buffer`
+///
+/// Returns the number of bytes written (excluding the NUL), `0` if no event
+/// is pending, or `-(required size)` if `buffer` is too small; the event is
+/// then kept for the next call.
+///
+/// # Safety
+/// `buffer` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn pc_poll_events(buffer: *mut c_char, len: usize) -> isize {
+    let mut events = lock(&EVENTS);
+    let Some((receiver, pending)) = events.as_mut() else {
+        return PC_ERR_NOT_STARTED as isize;
+    };
+    if buffer.is_null() {
+        return PC_ERR_INVALID_ARGUMENT as isize;
+    }
+
+    let json = match pending.take() {
+        Some(json) => json,
+        None => loop {
+            match receiver.try_recv() {
+                Ok(event) => break event.json().to_string(),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => return 0,
+            }
+        },
+    };
+
+    let required = json.len() + 1;
+    if required > len {
+        *pending = Some(json);
+        return -(required as isize);
+    }
+    std::ptr::copy_nonoverlapping(json.as_ptr(), buffer as *mut u8, json.len());
+    *buffer.add(json.len()) = 0;
+    json.len() as isize
+}
+
+/// Stop all agents; events published until then can still be polled
+#[no_mangle]
+pub extern "C" fn pc_shutdown() -> i32 {
+    match lock(&HANDLE).take() {
+        Some(handle) => {
+            handle.shutdown();
+            pattern_clock::telemetry::shutdown();
+            PC_OK
+        }
+        None => PC_ERR_NOT_STARTED,
+    }
+}
@@ -0,0 +1,168 @@
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError};
+use pyo3::prelude::*;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use pattern_clock::connections::{default_provider, OllamaProvider};
+use pattern_clock::events::{subscribe, SharedEvent};
+use pattern_clock::runtime::{background_runtime, PatternClock, RuntimeHandle};
+use pattern_clock::storage::FileStorage;
+
+// ============================================================================
+// Python Bindings
+// ============================================================================
+//
+// A separate crate so the main library stays a plain rlib. Build from this
+// directory with `maturin develop` (or `maturin build --release`), then:
+//
+//     import pattern_clock as pc
+//     pc.start(agents=5)
+//     pc.submit(1, "cpu at 97%")
+//     events = pc.subscribe_events()
+//     print(events.next(timeout=5.0))
+
+static HANDLE: OnceLock<RuntimeHandle> = OnceLock::new();
+
+fn handle() -> PyResult<&'static RuntimeHandle> {
+    HANDLE.get().ok_or_else(|| PyRuntimeError::new_err("pattern_clock is not started; call start() first"))
+}
+
+fn to_py_err(e: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// Start the agent runtime (once per process)
+#[pyfunction]
+#[pyo3(signature = (agents=5, ollama_url=None, model=None, data_dir=None))]
+fn start(
+    py: Python<'_>,
+    agents: u8,
+    ollama_url: Option<String>,
+    model: Option<String>,
+    data_dir: Option<String>,
+) -> PyResult<()> {
+    if HANDLE.get().is_some() {
+        return Ok(());
+    }
+    let mut builder = PatternClock::builder().agents(agents);
+    if ollama_url.is_some() || model.is_some() {
+        let mut provider = OllamaProvider::from_env();
+        if let Some(url) = ollama_url {
+            provider.base_url = url.trim_end_matches('/').to_string();
+        }
+        if let Some(model) = model {
+            provider.model = model;
+        }
+        builder = builder.with_provider(provider);
+    }
+    if let Some(dir) = data_dir {
+        builder = builder.with_storage(FileStorage::new(dir));
+    }
+
+    let handle = py.allow_threads(|| background_runtime().block_on(builder.build().start())).map_err(to_py_err)?;
+    let _ = HANDLE.set(handle);
+    Ok(())
+}
+
+/// Queue a payload for processing by an agent
+#[pyfunction]
+fn submit(agent_id: u8, data: String) -> PyResult<()> {
+    handle()?.submit(agent_id, data).map_err(to_py_err)
+}
+
+/// Send a prompt to the configured LLM and return the generated text
+#[pyfunction]
+fn generate(py: Python<'_>, prompt: String) -> PyResult<String> {
+    py.allow_threads(|| background_runtime().block_on(default_provider().generate(&prompt))).map_err(to_py_err)
+}
+
+/// Send a prompt in JSON mode and return the structured output as a JSON string
+#[pyfunction]
+fn generate_json(py: Python<'_>, prompt: String) -> PyResult<String> {
+    py.allow_threads(|| background_runtime().block_on(default_provider().generate_json(&prompt)))
+        .map(|value| value.to_string())
+        .map_err(to_py_err)
+}
+
+/// Forecast the next `horizon` values of `values` (Holt's linear smoothing)
+#[pyfunction]
+#[pyo3(signature = (values, horizon, alpha=0.5, beta=0.3))]
+fn forecast(values: Vec<f64>, horizon: usize, alpha: f64, beta: f64) -> Vec<f64> {
+    pattern_clock::timeseries::forecast_holt(&values, horizon, alpha, beta)
+}
+
+/// Forecast the next `horizon` values of a stored series
+#[pyfunction]
+fn forecast_series(name: String, horizon: usize) -> PyResult<Vec<f64>> {
+    pattern_clock::timeseries::forecast(&name, horizon).map_err(to_py_err)
+}
+
+/// Iterator over internal events; each item is a JSON string `{"kind", "payload", "ts"}`
+#[pyclass]
+struct EventSubscription {
+    receiver: broadcast::Receiver<SharedEvent>,
+}
+
+impl EventSubscription {
+    fn recv(&mut self, py: Python<'_>, timeout: Option<f64>) -> PyResult<String> {
+        let receiver = &mut self.receiver;
+        let event = py.allow_threads(|| {
+            background_runtime().block_on(async {
+                loop {
+                    let next = match timeout {
+                        Some(secs) => tokio::time::timeout(Duration::from_secs_f64(secs), receiver.recv())
+                            .await
+                            .map_err(|_| PyTimeoutError::new_err("no event within timeout"))?,
+                        None => receiver.recv().await,
+                    };
+                    match next {
+                        Ok(event) => return Ok(event),
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Err(PyRuntimeError::new_err("event bus closed"))
+                        }
+                    }
+                }
+            })
+        })?;
+        Ok(event.json().to_string())
+    }
+}
+
+#[pymethods]
+impl EventSubscription {
+    /// Wait for the next event; raises `TimeoutError` after `timeout` seconds
+    #[pyo3(signature = (timeout=None))]
+    fn next(&mut self, py: Python<'_>, timeout: Option<f64>) -> PyResult<String> {
+        self.recv(py, timeout)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<String> {
+        self.recv(py, None)
+    }
+}
+
+/// Subscribe to internal events (agent activity, alerts, training, ...)
+#[pyfunction]
+fn subscribe_events() -> EventSubscription {
+    EventSubscription { receiver: subscribe() }
+}
+
+#[pymodule]
+#[pyo3(name = "pattern_clock")]
+fn module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(start, m)?)?;
+    m.add_function(wrap_pyfunction!(submit, m)?)?;
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_json, m)?)?;
+    m.add_function(wrap_pyfunction!(forecast, m)?)?;
+    m.add_function(wrap_pyfunction!(forecast_series, m)?)?;
+    m.add_function(wrap_pyfunction!(subscribe_events, m)?)?;
+    m.add_class::<EventSubscription>()?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::storage::now_millis;
+
+// ============================================================================
+// Agent Event Stream
+// ============================================================================
+//
+// Every message an agent handles ends in one `AgentEvent` on this channel:
+// which agent, what kind of message, and how handling it went. Agents in the
+// `llm` role also emit an `llm_output` event with each answer they generate.
+// Unlike the internal event bus, which carries everything from training runs
+// to alerts, this channel only carries agent activity, in a shape clients
+// can render without knowing each subsystem's event names.
+//
+// The MCP result stream (`/api/mcp/receive`) forwards the events of every
+// message that is work rather than bookkeeping, so the web page lists agent
+// activity next to MCP results as it happens. Rust hosts subscribe through
+// `RuntimeHandle::subscribe_agent_events`.
+
+/// Events buffered per subscriber before the slowest ones lag
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// One message handled by an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentEvent {
+    pub agent_id: u8,
+    /// Kind of the handled message, e.g. `process_data` (see `AgentMessage::kind`)
+    pub kind: String,
+    /// Outcome: `ok`, `error`, `duration_ms`, `preview` (redacted), `job`, `processed_count`;
+    /// `llm_output` events carry `model` and the (redacted) `output` instead
+    pub payload: serde_json::Value,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+impl AgentEvent {
+    pub fn new(agent_id: u8, kind: &str, payload: serde_json::Value) -> Self {
+        AgentEvent { agent_id, kind: kind.to_string(), payload, ts: now_millis() }
+    }
+}
+
+/// An event as shared by all subscribers
+pub type SharedAgentEvent = Arc<AgentEvent>;
+
+static CHANNEL: OnceLock<broadcast::Sender<SharedAgentEvent>> = OnceLock::new();
+
+fn channel() -> &'static broadcast::Sender<SharedAgentEvent> {
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Send `event` to all current subscribers (dropped if nobody listens)
+pub fn emit(event: AgentEvent) {
+    let _ = channel().send(Arc::new(event));
+}
+
+/// Subscribe to agent events emitted from now on
+pub fn subscribe() -> broadcast::Receiver<SharedAgentEvent> {
+    channel().subscribe()
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+// ============================================================================
+// Per-Agent Metrics
+// ============================================================================
+//
+// Every agent keeps its own counters in `AgentState::metrics`: messages
+// handled, handler errors and a latency histogram, overall and per message
+// kind, plus per-second message counts for the last `RATE_WINDOW_SECS`.
+// Internal messages (state saves, status requests, probes) are not counted.
+//
+// The counters are saved with the rest of the agent state, so they survive
+// restarts; the per-second counts start over. `GET /api/agents/:id/metrics`
+// reports them as `MetricsReport` for dashboards graphing throughput. The
+// OpenTelemetry instruments (`agent.messages`, `agent.message.duration`)
+// carry the same measurements for the whole pool.
+
+/// Upper bounds of the latency buckets in milliseconds; one more bucket
+/// catches everything slower
+pub const BUCKET_BOUNDS_MS: [u64; 14] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000];
+
+/// Seconds of per-second message counts kept for the rate and throughput series
+pub const RATE_WINDOW_SECS: u64 = 60;
+
+/// Characters of the last error message kept
+const ERROR_CHARS: usize = 200;
+
+/// Latency histogram with fixed buckets (`BUCKET_BOUNDS_MS`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// Count per bucket; the last one is the overflow bucket
+    pub counts: Vec<u64>,
+    pub count: u64,
+    /// Total time in microseconds
+    pub sum_us: u64,
+    pub max_us: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            counts: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+            count: 0,
+            sum_us: 0,
+            max_us: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| micros <= bound * 1000)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        // Histograms saved with a different bucket layout start over
+        if self.counts.len() != BUCKET_BOUNDS_MS.len() + 1 {
+            *self = LatencyHistogram::default();
+        }
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum_us += micros;
+        self.max_us = self.max_us.max(micros);
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum_us as f64 / self.count as f64 / 1000.0
+    }
+
+    /// Upper bound of the bucket holding the `percent` percentile, capped at
+    /// the slowest message seen
+    pub fn percentile_ms(&self, percent: u64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = (self.count * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound_us = BUCKET_BOUNDS_MS.get(bucket).map_or(self.max_us, |bound| bound * 1000);
+                return bound_us.min(self.max_us) as f64 / 1000.0;
+            }
+        }
+        self.max_us as f64 / 1000.0
+    }
+}
+
+/// Counters for one message kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KindMetrics {
+    pub messages: u64,
+    pub errors: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// The most recent handler error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastError {
+    pub kind: String,
+    pub message: String,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+/// Counters of one agent, kept in its state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentMetrics {
+    /// When counting started (milliseconds since the Unix epoch)
+    pub since: u64,
+    pub messages: u64,
+    pub errors: u64,
+    pub latency: LatencyHistogram,
+    pub by_kind: BTreeMap<String, KindMetrics>,
+    pub last_error: Option<LastError>,
+    /// `(second since the Unix epoch, messages)`, oldest first
+    #[serde(skip)]
+    recent: VecDeque<(u64, u64)>,
+}
+
+impl Default for AgentMetrics {
+    fn default() -> Self {
+        AgentMetrics {
+            since: crate::storage::now_millis(),
+            messages: 0,
+            errors: 0,
+            latency: LatencyHistogram::default(),
+            by_kind: BTreeMap::new(),
+            last_error: None,
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+/// Whether messages of `kind` are bookkeeping rather than work
+pub fn is_internal(kind: &str) -> bool {
+    matches!(kind, "save_state" | "get_status" | "probe" | "ping")
+}
+
+impl AgentMetrics {
+    /// Count one handled message; `error` is the handler's error, if it failed
+    pub fn record(&mut self, kind: &str, elapsed: Duration, error: Option<&str>) {
+        let now = crate::storage::now_millis();
+        self.messages += 1;
+        self.latency.record(elapsed);
+        let kind_metrics = self.by_kind.entry(kind.to_string()).or_default();
+        kind_metrics.messages += 1;
+        kind_metrics.latency.record(elapsed);
+        if let Some(error) = error {
+            self.errors += 1;
+            kind_metrics.errors += 1;
+            self.last_error = Some(LastError {
+                kind: kind.to_string(),
+                message: error.chars().take(ERROR_CHARS).collect(),
+                ts: now,
+            });
+        }
+
+        let second = now / 1000;
+        match self.recent.back_mut() {
+            Some((last, count)) if *last == second => *count += 1,
+            _ => self.recent.push_back((second, 1)),
+        }
+        while self.recent.front().is_some_and(|(ts, _)| *ts + RATE_WINDOW_SECS <= second) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Messages per second for each of the last `RATE_WINDOW_SECS` seconds,
+    /// oldest first, ending with the current (partial) second
+    fn throughput(&self, now: u64) -> Vec<ThroughputPoint> {
+        let second = now / 1000;
+        (0..RATE_WINDOW_SECS)
+            .rev()
+            .map(|ago| second - ago)
+            .map(|ts| ThroughputPoint {
+                ts: ts * 1000,
+                messages: self.recent.iter().find(|(s, _)| *s == ts).map_or(0, |(_, count)| *count),
+            })
+            .collect()
+    }
+
+    /// Structured report for `GET /api/agents/:id/metrics`
+    pub fn report(&self, agent_id: u8) -> MetricsReport {
+        let now = crate::storage::now_millis();
+        let throughput = self.throughput(now);
+        // Only the part of the window the counts cover
+        let window_secs = RATE_WINDOW_SECS.min((now.saturating_sub(self.since) / 1000).max(1));
+        let recent_messages: u64 = throughput.iter().rev().take(window_secs as usize).map(|point| point.messages).sum();
+        MetricsReport {
+            agent_id,
+            ts: now,
+            since: self.since,
+            messages: self.messages,
+            errors: self.errors,
+            error_rate: if self.messages == 0 { 0.0 } else { self.errors as f64 / self.messages as f64 },
+            messages_per_sec: recent_messages as f64 / window_secs as f64,
+            latency: LatencyReport::from(&self.latency),
+            by_kind: self
+                .by_kind
+                .iter()
+                .map(|(kind, metrics)| {
+                    (kind.clone(), KindReport {
+                        messages: metrics.messages,
+                        errors: metrics.errors,
+                        mean_ms: metrics.latency.mean_ms(),
+                        p95_ms: metrics.latency.percentile_ms(95),
+                    })
+                })
+                .collect(),
+            throughput,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+/// Messages handled in one second
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThroughputPoint {
+    /// Start of the second, in milliseconds since the Unix epoch
+    pub ts: u64,
+    pub messages: u64,
+}
+
+/// One histogram bucket: messages that took at most `le_ms` (none: slower
+/// than the last bound)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketReport {
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub buckets: Vec<BucketReport>,
+}
+
+impl From<&LatencyHistogram> for LatencyReport {
+    fn from(histogram: &LatencyHistogram) -> Self {
+        LatencyReport {
+            count: histogram.count,
+            mean_ms: histogram.mean_ms(),
+            p50_ms: histogram.percentile_ms(50),
+            p95_ms: histogram.percentile_ms(95),
+            p99_ms: histogram.percentile_ms(99),
+            max_ms: histogram.max_us as f64 / 1000.0,
+            buckets: histogram
+                .counts
+                .iter()
+                .enumerate()
+                .map(|(bucket, count)| BucketReport { le_ms: BUCKET_BOUNDS_MS.get(bucket).copied(), count: *count })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KindReport {
+    pub messages: u64,
+    pub errors: u64,
+    pub mean_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// An agent's metrics as returned by `GET /api/agents/:id/metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub agent_id: u8,
+    pub ts: u64,
+    pub since: u64,
+    pub messages: u64,
+    pub errors: u64,
+    /// Errors per handled message since `since`
+    pub error_rate: f64,
+    /// Average over the last minute (or since `since`, when shorter)
+    pub messages_per_sec: f64,
+    pub latency: LatencyReport,
+    pub by_kind: BTreeMap<String, KindReport>,
+    /// Messages per second over the last minute, oldest first
+    pub throughput: Vec<ThroughputPoint>,
+    pub last_error: Option<LastError>,
+}
@@ -1,5 +1,33 @@
-use ractor::{Actor, ActorProcessingErr, ActorRef};
-use std::sync::OnceLock;
+use ractor::rpc::CallResult;
+use ractor::{Actor, ActorId, ActorProcessingErr, ActorRef, RpcReplyPort, SupervisionEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use crate::agent_events::{self, AgentEvent};
+use crate::agent_metrics::{self, AgentMetrics};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::classifier::Classification;
+use crate::connections::agent_provider;
+use crate::dataset::store_event;
+use crate::deadline::{self, Deadline};
+use crate::extraction::{extract_with_mode, Extraction, ExtractionMode};
+use crate::summarizer::{summarize_history, SummarizerConfig};
+use crate::events::{publish, publish_with_attachment};
+use crate::jobs;
+use crate::latency::{self, Stage};
+use crate::mailbox::{self, AgentRef, Lifecycle, Priority};
+use crate::payload::Payload;
+use crate::redaction::{redact, redact_extraction};
+use crate::request_limits::strip_control_chars;
+use crate::roles::{self, AgentRole};
+use crate::pipeline::{StepOutput, Transform};
+use crate::storage::{get_typed, now_millis, put_typed};
+use crate::monitor::{start_monitor, MonitorConfig};
+use crate::telemetry::{in_span, instruments};
+use opentelemetry::KeyValue;
+use serde_json::json;
 
 // ============================================================================
 // Agent Actor Implementation
@@ -7,28 +35,223 @@ use std::sync::OnceLock;
 
 /// Generic agent actor that can be instantiated with different IDs
 pub struct Agent {
-    /// The unique identifier for this agent (1-5)
+    /// The unique identifier for this agent (1-based)
     pub id: u8,
 }
 
 /// Message types that agents can handle
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum AgentMessage {
-    /// Process data asynchronously
+    /// Process data asynchronously, ahead of or after other waiting data by `priority`
     ProcessData {
         data: String,
+        priority: Priority,
     },
-    /// Get the current status of the agent
+    /// Process a text or binary payload; binary payloads are recorded by
+    /// size and content type and forwarded as an `agent.payload` event
+    ProcessPayload {
+        payload: Payload,
+    },
+    /// Log the current status of the agent
     GetStatus,
+    /// Reply with a snapshot of the agent's state (see `agent_status`)
+    GetStatusReply(RpcReplyPort<AgentState>),
     /// Custom action with parameters
     CustomAction {
         action: String,
         params: Vec<String>,
     },
+    /// Condense old history entries into the rolling summary; the LLM calls
+    /// run in a separate task that answers with `Summarized`
+    Summarize,
+    /// `entries`, the oldest history entries, folded into `previous` (the
+    /// summary at the time) by a task started for `Summarize`
+    Summarized {
+        entries: Vec<String>,
+        previous: Option<String>,
+        summary: Result<String, String>,
+    },
+    /// Run the on-device text classifier over the input and answer with the result
+    #[cfg(not(target_arch = "wasm32"))]
+    Classify {
+        text: String,
+        reply: RpcReplyPort<Result<Classification, String>>,
+    },
+    /// Liveness probe from the self-monitor; answered with an `agent.probe` event
+    Probe,
+    /// Reply once the messages ahead of it are handled (see `change_lifecycle`)
+    Ping(RpcReplyPort<()>),
+    /// Save the state if it changed (sent every `STATE_SAVE_INTERVAL`)
+    SaveState,
+    /// Hand the conversation (history and summary) to agent `to` (see `hand_off`)
+    HandOff {
+        to: u8,
+        reason: String,
+        reply: RpcReplyPort<Result<HandoffReceipt, String>>,
+    },
+    /// Take over a conversation handed off by another agent
+    AcceptHandoff(Box<Handoff>),
+    /// Process `input` as one step of a pipeline and answer with the transformed output
+    RunStep {
+        input: String,
+        transform: Transform,
+        reply: RpcReplyPort<Result<StepOutput, String>>,
+    },
+    /// A payload sent to every agent at once (see `broadcast`); processed
+    /// ahead of normal data, and by routers themselves instead of routed on
+    Broadcast {
+        payload: Payload,
+    },
+    /// Send `payload` to every other agent subscribed to `topic`
+    Publish {
+        topic: String,
+        payload: Payload,
+    },
+    /// Receive what other agents publish on `topic`
+    Subscribe {
+        topic: String,
+    },
+    /// Stop receiving `topic`
+    Unsubscribe {
+        topic: String,
+    },
+    /// Stop taking work off the queue; new work is still queued (see `mailbox`)
+    Pause,
+    /// Take work off the queue again, including what waited while paused,
+    /// and accept new work after a drain
+    Resume,
+    /// Work through the queue while refusing new work; `agent.drained` is
+    /// published once it is empty
+    Drain,
+    /// Handle `message` only while `deadline` has not passed; LLM calls made
+    /// while handling it are cancelled at the deadline
+    WithDeadline {
+        deadline: Deadline,
+        message: Box<AgentMessage>,
+    },
+    /// `message`, queued at `queued_at` and tracked as `job`; the wait counts
+    /// toward the dequeue stage of the latency budget
+    Queued {
+        queued_at: std::time::Instant,
+        job: u64,
+        message: Box<AgentMessage>,
+    },
+    /// Handle the most urgent message waiting in the agent's priority queue
+    /// (sent by `AgentRef` for every message it queues, see `mailbox`)
+    Dequeue,
+    /// Injected failure: panic inside the handler
+    #[cfg(feature = "chaos")]
+    ChaosPanic,
 }
 
+// Cluster builds have no blanket `Message` impl; agent messages stay local
+#[cfg(feature = "cluster")]
+impl ractor::Message for AgentMessage {}
+
+impl AgentMessage {
+    /// Short name of the message variant, used as a telemetry attribute
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AgentMessage::ProcessData { .. } => "process_data",
+            AgentMessage::ProcessPayload { .. } => "process_payload",
+            AgentMessage::GetStatus | AgentMessage::GetStatusReply(_) => "get_status",
+            AgentMessage::CustomAction { .. } => "custom_action",
+            AgentMessage::Summarize => "summarize",
+            AgentMessage::Summarized { .. } => "summarized",
+            #[cfg(not(target_arch = "wasm32"))]
+            AgentMessage::Classify { .. } => "classify",
+            AgentMessage::Probe => "probe",
+            AgentMessage::Ping(_) => "ping",
+            AgentMessage::SaveState => "save_state",
+            AgentMessage::HandOff { .. } => "hand_off",
+            AgentMessage::AcceptHandoff(_) => "accept_handoff",
+            AgentMessage::RunStep { .. } => "run_step",
+            AgentMessage::Broadcast { .. } => "broadcast",
+            AgentMessage::Publish { .. } => "publish",
+            AgentMessage::Subscribe { .. } => "subscribe",
+            AgentMessage::Unsubscribe { .. } => "unsubscribe",
+            AgentMessage::Pause => "pause",
+            AgentMessage::Resume => "resume",
+            AgentMessage::Drain => "drain",
+            AgentMessage::WithDeadline { message, .. } => message.kind(),
+            AgentMessage::Queued { message, .. } => message.kind(),
+            AgentMessage::Dequeue => "dequeue",
+            #[cfg(feature = "chaos")]
+            AgentMessage::ChaosPanic => "chaos_panic",
+        }
+    }
+
+    /// Where the message goes in the agent's priority queue
+    pub fn priority(&self) -> Priority {
+        match self {
+            AgentMessage::ProcessData { priority, .. } => *priority,
+            AgentMessage::Broadcast { .. } => Priority::High,
+            AgentMessage::GetStatus
+            | AgentMessage::GetStatusReply(_)
+            | AgentMessage::Probe
+            | AgentMessage::Ping(_)
+            | AgentMessage::SaveState
+            | AgentMessage::Subscribe { .. }
+            | AgentMessage::Unsubscribe { .. }
+            | AgentMessage::Pause
+            | AgentMessage::Resume
+            | AgentMessage::Drain
+            // The only way `summarizing` is cleared, so it must not be refused
+            | AgentMessage::Summarized { .. } => Priority::Control,
+            AgentMessage::WithDeadline { message, .. } | AgentMessage::Queued { message, .. } => message.priority(),
+            _ => Priority::Normal,
+        }
+    }
+
+    /// Wrap the message so the agent honors `deadline`, if there is one
+    pub fn with_deadline(self, deadline: Option<Deadline>) -> Self {
+        match deadline {
+            Some(deadline) => AgentMessage::WithDeadline { deadline, message: Box::new(self) },
+            None => self,
+        }
+    }
+
+    /// Wrap the message for `agent_id` to measure how long it waits in the
+    /// mailbox and list it in the job queue
+    pub fn queued(self, agent_id: u8) -> Self {
+        let job = jobs::track(agent_id, &self);
+        AgentMessage::Queued { queued_at: std::time::Instant::now(), job, message: Box::new(self) }
+    }
+
+    /// The data payload of the message, if it has one that can be sent again
+    pub fn payload(&self) -> Option<Payload> {
+        match self {
+            AgentMessage::ProcessData { data, .. } => Some(Payload::text(data.clone())),
+            AgentMessage::ProcessPayload { payload } => Some(payload.clone()),
+            AgentMessage::WithDeadline { message, .. } | AgentMessage::Queued { message, .. } => message.payload(),
+            _ => None,
+        }
+    }
+
+    /// What the message is about, for queue listings
+    pub fn preview(&self) -> String {
+        match self {
+            AgentMessage::ProcessData { data, .. } => data.clone(),
+            AgentMessage::ProcessPayload { payload } | AgentMessage::Broadcast { payload } => payload.describe(),
+            #[cfg(not(target_arch = "wasm32"))]
+            AgentMessage::Classify { text, .. } => text.clone(),
+            AgentMessage::RunStep { input, .. } => input.clone(),
+            AgentMessage::Publish { topic, payload } => format!("{}: {}", topic, payload.describe()),
+            AgentMessage::CustomAction { action, params } => format!("{} {}", action, params.join(" ")),
+            AgentMessage::WithDeadline { message, .. } | AgentMessage::Queued { message, .. } => message.preview(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Handled messages kept per agent for `/api/agents/:id/history`; the oldest go first
+pub const MESSAGE_LOG_LEN: usize = 500;
+
+/// Characters of a message kept in the message log
+const MESSAGE_PREVIEW_CHARS: usize = 200;
+
 /// Agent state - maintains internal state for each agent
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentState {
     /// Agent identifier
     pub id: u8,
@@ -36,6 +259,109 @@ pub struct AgentState {
     pub processed_count: u64,
     /// Last processed data
     pub last_data: Option<String>,
+    /// Recent processed data, oldest first
+    pub history: VecDeque<HistoryEntry>,
+    /// Rolling LLM summary of entries dropped from `history`
+    pub summary: Option<String>,
+    /// What the agent does with processed data, taken when it starts (see `roles`)
+    #[serde(default)]
+    pub role: AgentRole,
+    /// Messages handled, errors and latency (see `agent_metrics`)
+    #[serde(default)]
+    pub metrics: AgentMetrics,
+    /// The last `MESSAGE_LOG_LEN` messages handled, oldest first
+    #[serde(default)]
+    pub message_log: VecDeque<HandledMessage>,
+    /// Changed since it was last saved
+    #[serde(skip)]
+    pub dirty: bool,
+    /// A summarization task is running (see `summarize_agent_history`)
+    #[serde(skip)]
+    pub summarizing: bool,
+}
+
+/// A processed payload together with its extracted entities and keywords
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub data: String,
+    pub extraction: Extraction,
+}
+
+/// A message the agent handled, as listed by `/api/agents/:id/history`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandledMessage {
+    /// When handling finished (milliseconds since the Unix epoch)
+    pub ts: u64,
+    /// Message variant, as in telemetry
+    pub kind: String,
+    pub preview: String,
+    pub duration_ms: u64,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Job id, for messages queued through the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job: Option<u64>,
+}
+
+/// A conversation handed from one agent to another
+#[derive(Debug, Clone, Serialize)]
+pub struct Handoff {
+    pub from: u8,
+    pub to: u8,
+    pub reason: String,
+    pub history: Vec<HistoryEntry>,
+    pub summary: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+/// What `hand_off` reports once the conversation was delivered
+#[derive(Debug, Clone, Serialize)]
+pub struct HandoffReceipt {
+    pub from: u8,
+    pub to: u8,
+    pub reason: String,
+    /// History entries handed over
+    pub entries: usize,
+    /// Whether a rolling summary was handed over too
+    pub summary: bool,
+    pub ts: u64,
+}
+
+impl AgentState {
+    /// Context block for future prompts: rolling summary followed by recent history
+    pub fn context(&self) -> String {
+        let mut context = String::new();
+        if let Some(summary) = &self.summary {
+            context.push_str(&format!("Summary of earlier activity:\n{}\n\n", summary));
+        }
+        if !self.history.is_empty() {
+            context.push_str("Recent activity:\n");
+            for entry in &self.history {
+                context.push_str(&entry.data);
+                context.push('\n');
+            }
+        }
+        context
+    }
+
+    /// History entries tagged with the given keyword or entity
+    pub fn search(&self, term: &str) -> Vec<&HistoryEntry> {
+        self.history.iter().filter(|entry| entry.extraction.matches(term)).collect()
+    }
+
+    /// The last `limit` handled messages, newest first
+    pub fn recent_messages(&self, limit: usize) -> Vec<HandledMessage> {
+        self.message_log.iter().rev().take(limit).cloned().collect()
+    }
+
+    fn log_message(&mut self, message: HandledMessage) {
+        if self.message_log.len() >= MESSAGE_LOG_LEN {
+            self.message_log.pop_front();
+        }
+        self.message_log.push_back(message);
+    }
 }
 
 impl Actor for Agent {
@@ -43,18 +369,44 @@ impl Actor for Agent {
     type State = AgentState;
     type Arguments = u8; // Agent ID
 
-    /// Initialize the agent with its ID
+    /// Initialize the agent with its ID, resuming from its saved state
     async fn pre_start(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         agent_id: Self::Arguments,
     ) -> Result<Self::State, ActorProcessingErr> {
-        println!("[Agent{}] Starting agent with ID {}", agent_id, agent_id);
-        Ok(AgentState {
+        log_info!("[Agent{}] Starting agent with ID {}", agent_id, agent_id);
+        // Whatever waited for a previous run of this agent went with its mailbox
+        mailbox::clear(agent_id);
+        // Ends with the actor
+        myself.send_interval(STATE_SAVE_INTERVAL, || AgentMessage::SaveState);
+        let mut state = load_state(agent_id).unwrap_or_else(|| AgentState {
             id: agent_id,
             processed_count: 0,
             last_data: None,
-        })
+            history: VecDeque::new(),
+            summary: None,
+            role: AgentRole::General,
+            metrics: AgentMetrics::default(),
+            message_log: VecDeque::new(),
+            dirty: false,
+            summarizing: false,
+        });
+        state.role = roles::role_of(agent_id);
+        if state.role != AgentRole::General {
+            log_info!("[Agent{}] Role: {}", agent_id, state.role.name());
+        }
+        Ok(state)
+    }
+
+    /// Save the state on the way out
+    async fn post_stop(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        save_state(state);
+        Ok(())
     }
 
     /// Handle messages asynchronously
@@ -64,29 +416,461 @@ impl Actor for Agent {
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
-        match message {
-            AgentMessage::ProcessData { data } => {
+        let message = match message {
+            AgentMessage::Dequeue => match mailbox::pop(state.id) {
+                Some(message) => message,
+                // Cleared when the agent restarted, or paused with only work
+                // waiting, which `wake` goes back to on resume
+                None => return Ok(()),
+            },
+            message => message,
+        };
+        let (message, job) = match message {
+            AgentMessage::Queued { queued_at, job, message } => {
+                latency::record(Stage::Dequeue, queued_at.elapsed());
+                if !jobs::start(job) {
+                    log_info!("[Agent{}] Skipped cancelled job {}", state.id, job);
+                    finish_drain(state.id);
+                    return Ok(());
+                }
+                (*message, Some(job))
+            }
+            message => (message, None),
+        };
+        let kind = message.kind();
+        let preview = message.preview();
+        let attributes = [KeyValue::new("agent.id", state.id as i64), KeyValue::new("message", kind)];
+        let started = std::time::Instant::now();
+        // A panic here stops the agent and reaches the supervisor, so it is not a crash
+        #[cfg(not(target_arch = "wasm32"))]
+        let handling = crate::crash::caught_elsewhere(handle_message(message, state));
+        #[cfg(target_arch = "wasm32")]
+        let handling = handle_message(message, state);
+        let result = in_span("agent.handle", attributes.to_vec(), handling).await;
+        let elapsed = started.elapsed();
+        let error = result.as_ref().err().map(|e| e.to_string());
+        let preview: String = redact(&preview).chars().take(MESSAGE_PREVIEW_CHARS).collect();
+        if !agent_metrics::is_internal(kind) {
+            state.metrics.record(kind, elapsed, error.as_deref());
+            state.log_message(HandledMessage {
+                ts: now_millis(),
+                kind: kind.to_string(),
+                preview: preview.clone(),
+                duration_ms: elapsed.as_millis() as u64,
+                ok: error.is_none(),
+                error: error.clone(),
+                job,
+            });
+            state.dirty = true;
+        }
+        if let Some(job) = job {
+            jobs::finish(job);
+        }
+
+        let instruments = instruments();
+        instruments.agent_messages.add(1, &attributes);
+        instruments.agent_message_duration.record(elapsed.as_secs_f64(), &attributes);
+        publish("agent.handled", json!({
+            "agent_id": state.id,
+            "message": kind,
+            "duration_ms": elapsed.as_millis() as u64,
+            "ok": result.is_ok(),
+        }));
+        agent_events::emit(AgentEvent::new(state.id, kind, json!({
+            "ok": error.is_none(),
+            "error": error,
+            "duration_ms": elapsed.as_millis() as u64,
+            "preview": preview,
+            "job": job,
+            "processed_count": state.processed_count,
+        })));
+        finish_drain(state.id);
+        result
+    }
+}
+
+/// Future of `handle_message`, boxed with an explicit `Send` bound: the
+/// handler recurses into itself for wrapped messages
+type HandleFuture<'a> = std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), ActorProcessingErr>> + Send + 'a>>;
+
+/// Apply one message to the agent's state
+fn handle_message(message: AgentMessage, state: &mut AgentState) -> HandleFuture<'_> {
+    Box::pin(async move {
+        // Routers pass data on instead of processing it
+        if state.role == AgentRole::Router
+            && matches!(message, AgentMessage::ProcessData { .. } | AgentMessage::ProcessPayload { .. })
+        {
+            if let Some(payload) = message.payload() {
                 state.processed_count += 1;
-                state.last_data = Some(data.clone());
-                
-                // Print with agent identifier (1, 2, 3, 4, or 5)
-                println!("[Agent{}] Processing data: '{}' | Total processed: {}", 
-                    state.id, data, state.processed_count);
-                
-                // Simulate async I/O operation
-                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+                roles::route(state.id, payload);
+            }
+            return Ok(());
+        }
+        match message {
+            AgentMessage::ProcessData { data, .. } => {
+                process_data(data, state).await;
+            }
+            AgentMessage::ProcessPayload { payload } => {
+                process_payload(payload, state).await;
+            }
+            AgentMessage::Broadcast { payload } => {
+                log_info!("[Agent{}] Received broadcast: {}", state.id, payload.describe());
+                process_payload(payload, state).await;
             }
             AgentMessage::GetStatus => {
-                println!("[Agent{}] Status - Processed: {} messages, Last data: {:?}", 
+                log_info!("[Agent{}] Status - Processed: {} messages, Last data: {:?}", 
                     state.id, state.processed_count, state.last_data);
             }
+            AgentMessage::GetStatusReply(reply) => {
+                // The caller may have timed out already
+                let _ = reply.send(state.clone());
+            }
             AgentMessage::CustomAction { action, params } => {
-                println!("[Agent{}] Custom action: '{}' with params: {:?}", 
+                log_info!("[Agent{}] Custom action: '{}' with params: {:?}", 
                     state.id, action, params);
                 state.processed_count += 1;
             }
+            AgentMessage::Summarize => {
+                summarize_agent_history(state);
+            }
+            AgentMessage::Summarized { entries, previous, summary } => {
+                apply_summary(state, entries, previous, summary);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            AgentMessage::Classify { text, reply } => {
+                let input = text.clone();
+                let started = std::time::Instant::now();
+                let classified = crate::compute::run("classify", move || crate::classifier::classify_text(&input))
+                    .await
+                    .and_then(|classified| classified);
+                latency::record(Stage::Compute, started.elapsed());
+                match &classified {
+                    Ok(result) => {
+                        state.processed_count += 1;
+                        log_info!("[Agent{}] Classified '{}' as {} ({:.2})",
+                            state.id, text, result.label, result.confidence);
+                    }
+                    Err(e) => log_error!("[Agent{}] Failed to classify '{}': {}", state.id, text, e),
+                }
+                // The caller may have timed out already
+                let _ = reply.send(classified.map_err(|e| e.to_string()));
+            }
+            AgentMessage::Ping(reply) => {
+                // The caller may have timed out already
+                let _ = reply.send(());
+            }
+            AgentMessage::Probe => {
+                publish("agent.probe", json!({ "agent_id": state.id }));
+            }
+            AgentMessage::SaveState => {
+                save_state(state);
+            }
+            AgentMessage::HandOff { to, reason, reply } => {
+                let attributes = vec![
+                    KeyValue::new("agent.id", state.id as i64),
+                    KeyValue::new("handoff.to", to as i64),
+                    KeyValue::new("handoff.reason", reason.clone()),
+                ];
+                let result = in_span("agent.handoff", attributes, async { hand_off_conversation(state, to, reason) }).await;
+                let _ = reply.send(result);
+            }
+            AgentMessage::AcceptHandoff(handoff) => {
+                accept_handoff(*handoff, state);
+            }
+            AgentMessage::RunStep { input, transform, reply } => {
+                process_data(input.clone(), state).await;
+                let extraction = state.history.back().map(|entry| entry.extraction.clone()).unwrap_or_default();
+                let result = match transform.apply(state.id, &input, &extraction).await {
+                    Ok(output) => Ok(StepOutput { output, extraction }),
+                    Err(e) => Err(format!("transform failed: {}", e)),
+                };
+                let _ = reply.send(result);
+            }
+            AgentMessage::Publish { topic, payload } => {
+                let delivered = deliver_to_subscribers(state.id, &topic, &payload);
+                log_info!("[Agent{}] Published {} on '{}' to {} agents",
+                    state.id, payload.describe(), topic, delivered);
+            }
+            AgentMessage::Subscribe { topic } => {
+                if subscriptions().entry(topic.clone()).or_default().insert(state.id) {
+                    log_info!("[Agent{}] Subscribed to '{}'", state.id, topic);
+                }
+            }
+            AgentMessage::Unsubscribe { topic } => {
+                let mut topics = subscriptions();
+                if let Some(subscribers) = topics.get_mut(&topic) {
+                    subscribers.remove(&state.id);
+                    if subscribers.is_empty() {
+                        topics.remove(&topic);
+                    }
+                }
+            }
+            // Unwrapped in `handle`; only reached when wrapped in another message
+            AgentMessage::Queued { job, message, .. } => {
+                if !jobs::start(job) {
+                    return Ok(());
+                }
+                let result = handle_message(*message, state).await;
+                jobs::finish(job);
+                return result;
+            }
+            AgentMessage::WithDeadline { deadline, message } => {
+                if deadline.is_expired() {
+                    // The caller has given up; don't start work nobody will read
+                    log_warn!("[Agent{}] Dropped {} past its deadline", state.id, message.kind());
+                    publish("agent.deadline_exceeded", json!({
+                        "agent_id": state.id,
+                        "message": message.kind(),
+                    }));
+                    return Ok(());
+                }
+                return deadline::scope(deadline, handle_message(*message, state)).await;
+            }
+            AgentMessage::Pause => {
+                apply_lifecycle(state.id, Lifecycle::Paused);
+            }
+            AgentMessage::Resume => {
+                apply_lifecycle(state.id, Lifecycle::Running);
+            }
+            AgentMessage::Drain => {
+                apply_lifecycle(state.id, Lifecycle::Draining);
+            }
+            // Taken from the priority queue in `handle`
+            AgentMessage::Dequeue => {}
+            #[cfg(feature = "chaos")]
+            AgentMessage::ChaosPanic => {
+                panic!("[Agent{}] chaos: injected panic", state.id);
+            }
         }
         Ok(())
+    })
+}
+
+/// Extract, record and remember one text payload, then act on it in the agent's role
+///
+/// Extraction and the role see the data as received (the provider redacts
+/// prompts for remote models); everything kept is redacted.
+async fn process_data(received: String, state: &mut AgentState) {
+    let received = strip_control_chars(&received);
+    state.processed_count += 1;
+    let started = std::time::Instant::now();
+    let extraction = extract_with_mode(&*agent_provider(state.id), &received, ExtractionMode::from_env()).await;
+    latency::record(Stage::Compute, started.elapsed());
+    let data = redact(&received);
+    let extraction = redact_extraction(&extraction);
+    state.last_data = Some(data.clone());
+    if let Err(e) = store_event(state.id, state.processed_count, data.clone(), extraction.clone()).await {
+        log_error!("[Agent{}] Failed to store event: {}", state.id, e);
+    }
+    if state.role == AgentRole::Storage {
+        roles::store(state.id, state.processed_count, &data, &extraction);
+    }
+    state.history.push_back(HistoryEntry {
+        data: data.clone(),
+        extraction,
+    });
+    
+    // Print with agent identifier
+    log_info!("[Agent{}] Processing data: '{}' | Total processed: {}", 
+        state.id, data, state.processed_count);
+
+    match state.role {
+        AgentRole::Llm => roles::answer(state.id, &received).await,
+        AgentRole::Lstm => roles::infer(state.id, &received).await,
+        AgentRole::General | AgentRole::Storage | AgentRole::Router => {}
+    }
+    
+    // Pace the agent (`processing_delay_ms`, per agent in `agents.toml`)
+    let delay = crate::config::config().processing_delay_of(state.id);
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Process a text payload like `ProcessData`; record binary ones by size and content type
+async fn process_payload(payload: Payload, state: &mut AgentState) {
+    if let Payload::Text { text } = payload {
+        process_data(text, state).await;
+        return;
+    }
+    state.processed_count += 1;
+    let description = payload.describe();
+    state.last_data = Some(description.clone());
+    if let Err(e) = store_event(state.id, state.processed_count, description.clone(), Extraction::default()).await {
+        log_error!("[Agent{}] Failed to store event: {}", state.id, e);
+    }
+    state.history.push_back(HistoryEntry {
+        data: description.clone(),
+        extraction: Extraction::default(),
+    });
+    log_info!("[Agent{}] Processing payload: {} | Total processed: {}",
+        state.id, description, state.processed_count);
+    publish_with_attachment("agent.payload", json!({
+        "agent_id": state.id,
+        "content_type": payload.content_type(),
+        "bytes": payload.len(),
+    }), Some(payload));
+}
+
+/// Send the conversation to agent `to` and forget it here once it was delivered
+fn hand_off_conversation(state: &mut AgentState, to: u8, reason: String) -> Result<HandoffReceipt, String> {
+    if to == state.id {
+        return Err(format!("Agent{} cannot hand off to itself", to));
+    }
+    let target = get_agent(to).ok_or_else(|| format!("Agent{} is not available", to))?;
+    let handoff = Handoff {
+        from: state.id,
+        to,
+        reason,
+        history: state.history.iter().cloned().collect(),
+        summary: state.summary.clone(),
+        ts: now_millis(),
+    };
+    let receipt = HandoffReceipt {
+        from: handoff.from,
+        to,
+        reason: handoff.reason.clone(),
+        entries: handoff.history.len(),
+        summary: handoff.summary.is_some(),
+        ts: handoff.ts,
+    };
+    target
+        .send_message(AgentMessage::AcceptHandoff(Box::new(handoff)))
+        .map_err(|e| format!("Agent{} could not take the conversation: {}", to, e))?;
+
+    state.history.clear();
+    state.summary = None;
+    log_info!("[Agent{}] Handed {} history entries to Agent{}: {}", state.id, receipt.entries, to, receipt.reason);
+    publish("agent.handoff", json!({
+        "from": receipt.from,
+        "to": receipt.to,
+        "reason": receipt.reason,
+        "entries": receipt.entries,
+        "summary": receipt.summary,
+    }));
+    Ok(receipt)
+}
+
+/// Continue a handed-off conversation after the agent's own history
+///
+/// A marker entry records where the conversation came from and why, so it
+/// shows up in the status, the prompt context and later summaries.
+fn accept_handoff(handoff: Handoff, state: &mut AgentState) {
+    state.history.push_back(HistoryEntry {
+        data: format!("Handed over from Agent{}: {}", handoff.from, handoff.reason),
+        extraction: Extraction::default(),
+    });
+    if let Some(summary) = handoff.summary {
+        let summary = format!("From Agent{}: {}", handoff.from, summary);
+        state.summary = Some(match state.summary.take() {
+            Some(own) => format!("{}\n\n{}", own, summary),
+            None => summary,
+        });
+    }
+    let entries = handoff.history.len();
+    state.history.extend(handoff.history);
+    log_info!("[Agent{}] Took over {} history entries from Agent{}", state.id, entries, handoff.from);
+}
+
+/// Fold the oldest history entries into the rolling summary once the history grows too long
+///
+/// The LLM calls can take a while, so they run in their own task and the
+/// agent keeps handling messages; the result comes back as `Summarized`.
+fn summarize_agent_history(state: &mut AgentState) {
+    let config = SummarizerConfig::current();
+    if state.summarizing || state.history.len() <= config.max_history {
+        return;
+    }
+    let Some(agent) = get_agent(state.id) else {
+        return;
+    };
+
+    let drain_count = state.history.len() - config.keep_recent;
+    let entries: Vec<String> = state.history.iter()
+        .take(drain_count)
+        .map(|entry| entry.data.clone())
+        .collect();
+    let previous = state.summary.clone();
+    let provider = agent_provider(state.id);
+    state.summarizing = true;
+    tokio::spawn(async move {
+        let summary = summarize_history(&*provider, previous.as_deref(), &entries, config.chunk_size)
+            .await
+            .map_err(|e| e.to_string());
+        // The agent may have stopped in the meantime
+        let _ = agent.send_message(AgentMessage::Summarized { entries, previous, summary });
+    });
+}
+
+/// Replace the summarized entries with their summary, unless the history
+/// or summary changed underneath (e.g. the conversation was handed off)
+fn apply_summary(state: &mut AgentState, entries: Vec<String>, previous: Option<String>, summary: Result<String, String>) {
+    state.summarizing = false;
+    match summary {
+        Ok(summary) => {
+            let unchanged = state.summary == previous
+                && state.history.len() >= entries.len()
+                && state.history.iter().zip(&entries).all(|(entry, data)| entry.data == *data);
+            if !unchanged {
+                log_info!("[Agent{}] Dropped a summary of history that changed meanwhile", state.id);
+                return;
+            }
+            state.history.drain(..entries.len());
+            state.summary = Some(summary);
+            log_info!("[Agent{}] Summarized {} history entries", state.id, entries.len());
+        }
+        Err(e) => {
+            log_error!("[Agent{}] Summarization failed: {}", state.id, e);
+            // Keep memory bounded even when the LLM is unavailable
+            let max_history = SummarizerConfig::current().max_history;
+            while state.history.len() > max_history * 2 {
+                state.history.pop_front();
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Agent State Persistence
+// ============================================================================
+//
+// Each agent's state (counters, last data, history and summary) is saved in
+// the `agent_state` collection of the configured storage, every
+// `STATE_SAVE_INTERVAL` when it changed and when the agent stops. An agent
+// starting with an id that has saved state resumes from it, so the state
+// survives process restarts, supervisor restarts after a crash (losing only
+// what happened since the last save) and stop/spawn through the registry.
+
+/// Collection holding agent states, keyed by agent id
+pub const AGENT_STATE_COLLECTION: &str = "agent_state";
+
+/// How often a changed agent state is saved
+pub const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The saved state of agent `agent_id`, if it has one
+fn load_state(agent_id: u8) -> Option<AgentState> {
+    match get_typed::<AgentState>(AGENT_STATE_COLLECTION, &agent_id.to_string()) {
+        Ok(Some(state)) if state.id == agent_id => {
+            log_info!("[Agent{}] Resuming from saved state ({} processed, {} history entries)",
+                agent_id, state.processed_count, state.history.len());
+            Some(state)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            log_warn!("[Agent{}] Failed to load saved state, starting fresh: {}", agent_id, e);
+            None
+        }
+    }
+}
+
+/// Save the state if it changed since the last save
+fn save_state(state: &mut AgentState) {
+    if !state.dirty {
+        return;
+    }
+    match put_typed(AGENT_STATE_COLLECTION, &state.id.to_string(), state) {
+        Ok(()) => state.dirty = false,
+        Err(e) => log_error!("[Agent{}] Failed to save state: {}", state.id, e),
     }
 }
 
@@ -94,81 +878,100 @@ impl Actor for Agent {
 // Actor Registry
 // ============================================================================
 
-/// Registry to store references to all 5 agents
-/// Using individual OnceLock for type safety
-pub static AGENT_1: OnceLock<ActorRef<AgentMessage>> = OnceLock::new();
-pub static AGENT_2: OnceLock<ActorRef<AgentMessage>> = OnceLock::new();
-pub static AGENT_3: OnceLock<ActorRef<AgentMessage>> = OnceLock::new();
-pub static AGENT_4: OnceLock<ActorRef<AgentMessage>> = OnceLock::new();
-pub static AGENT_5: OnceLock<ActorRef<AgentMessage>> = OnceLock::new();
+/// Number of agents started by `ensure_agents_initialized` unless `PATTERN_CLOCK_AGENTS`
+/// or `agents.toml` sets another
+pub const DEFAULT_AGENT_COUNT: u8 = 5;
+
+/// Registry of all running agents by id
+static AGENTS: RwLock<BTreeMap<u8, ActorRef<AgentMessage>>> = RwLock::new(BTreeMap::new());
 
-/// Initialize all 5 agents using the provided Tokio runtime
+/// Set once `initialize_agents` has run, even if agents were stopped since
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Initialize `count` agents (ids `1..=count`) using the current Tokio runtime
 /// This should be called once at application startup
 /// Uses a static flag to ensure it only runs once
 static INIT_FLAG: std::sync::OnceLock<tokio::sync::Mutex<bool>> = std::sync::OnceLock::new();
 
-pub async fn initialize_agents() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn initialize_agents(count: u8, monitor: Option<MonitorConfig>) -> Result<(), Box<dyn std::error::Error>> {
     let init_mutex = INIT_FLAG.get_or_init(|| tokio::sync::Mutex::new(false));
     let mut initialized = init_mutex.lock().await;
     
     if *initialized {
         return Ok(()); // Already initialized
     }
-    println!("[AgentRegistry] Initializing all 5 agents...");
-    
-    // Spawn all agents concurrently
-    let (actor1_ref, handle1) = Actor::spawn(None, Agent { id: 1 }, 1)
-        .await
-        .map_err(|e| format!("Failed to spawn Agent1: {:?}", e))?;
-    
-    let (actor2_ref, handle2) = Actor::spawn(None, Agent { id: 2 }, 2)
-        .await
-        .map_err(|e| format!("Failed to spawn Agent2: {:?}", e))?;
-    
-    let (actor3_ref, handle3) = Actor::spawn(None, Agent { id: 3 }, 3)
-        .await
-        .map_err(|e| format!("Failed to spawn Agent3: {:?}", e))?;
-    
-    let (actor4_ref, handle4) = Actor::spawn(None, Agent { id: 4 }, 4)
-        .await
-        .map_err(|e| format!("Failed to spawn Agent4: {:?}", e))?;
-    
-    let (actor5_ref, handle5) = Actor::spawn(None, Agent { id: 5 }, 5)
-        .await
-        .map_err(|e| format!("Failed to spawn Agent5: {:?}", e))?;
+    log_info!("[AgentRegistry] Initializing {} agents...", count);
+    if let Some(path) = &crate::config::config().agents_file {
+        log_info!("[AgentRegistry] Agent settings from {}", path);
+    }
+
+    // Agents are children of the supervisor, which restarts them after a crash
+    if SUPERVISOR.get().is_none() {
+        let (supervisor, _handle) = Actor::spawn(None, AgentSupervisor, ()).await?;
+        let _ = SUPERVISOR.set(supervisor);
+    }
     
-    // Store references in the registry
-    AGENT_1.set(actor1_ref.clone())
-        .map_err(|_| "Failed to store Agent1 reference")?;
-    AGENT_2.set(actor2_ref.clone())
-        .map_err(|_| "Failed to store Agent2 reference")?;
-    AGENT_3.set(actor3_ref.clone())
-        .map_err(|_| "Failed to store Agent3 reference")?;
-    AGENT_4.set(actor4_ref.clone())
-        .map_err(|_| "Failed to store Agent4 reference")?;
-    AGENT_5.set(actor5_ref.clone())
-        .map_err(|_| "Failed to store Agent5 reference")?;
+    for id in 1..=count {
+        spawn_agent(id).await?;
+    }
     
-    println!("[AgentRegistry] All 5 agents initialized successfully!");
+    log_info!("[AgentRegistry] All {} agents initialized successfully!", count);
     
     // Mark as initialized
     *initialized = true;
-    
-    // Spawn tasks to keep actors alive (they run in the background)
-    tokio::spawn(async move {
-        let _ = handle1.await;
-    });
-    tokio::spawn(async move {
-        let _ = handle2.await;
-    });
-    tokio::spawn(async move {
-        let _ = handle3.await;
-    });
-    tokio::spawn(async move {
-        let _ = handle4.await;
-    });
+    INITIALIZED.store(true, Ordering::SeqCst);
+
+    // Rolling throughput and latency for `/api/stats/live`
+    crate::live_stats::ensure_started();
+
+    // Runtime and compute pool load as metrics
+    #[cfg(not(target_arch = "wasm32"))]
+    crate::threads::ensure_started();
+
+    // Watch the agents (and the rest of the app) for lag and error spikes
+    if let Some(config) = monitor {
+        start_monitor(config).await?;
+    }
+
+    // Apply config-file rules and reload the configuration on SIGHUP
+    crate::config::ensure_reload_started();
+
+    // Campaign for leadership of shared background jobs when sharing a database
+    crate::leader::ensure_started();
+
+    // Join the other nodes when a cluster port is configured
+    crate::cluster::ensure_started();
+
+    // Share events with other instances when Redis is configured
+    #[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+    crate::redis_bus::ensure_started();
+
+    // Source connectors declared in the config file
+    #[cfg(not(target_arch = "wasm32"))]
+    crate::connectors::ensure_started();
+
+    // Resume shadow trials of draft rules that were still observing
+    crate::sandbox::ensure_started();
+
+    // Decide approvals whose timeout has passed
+    crate::approvals::ensure_started();
+
+    // Send scheduled data to agents when it is due
+    crate::scheduler::ensure_started();
+
+    // Delete data past its retention policy
+    crate::retention::ensure_started();
+
+    // Periodically ask every agent to condense its history (interval re-read each round, so reloads apply)
     tokio::spawn(async move {
-        let _ = handle5.await;
+        loop {
+            tokio::time::sleep(crate::config::config().summary_interval).await;
+            for agent_id in agent_ids() {
+                if let Some(actor_ref) = get_agent(agent_id) {
+                    let _ = actor_ref.send_message(AgentMessage::Summarize);
+                }
+            }
+        }
     });
     
     Ok(())
@@ -177,20 +980,550 @@ pub async fn initialize_agents() -> Result<(), Box<dyn std::error::Error>> {
 /// Ensure agents are initialized (lazy initialization)
 /// Call this from server functions to ensure agents are ready
 pub async fn ensure_agents_initialized() -> Result<(), Box<dyn std::error::Error>> {
-    if AGENT_1.get().is_none() {
-        initialize_agents().await?;
+    if !is_initialized() {
+        let config = crate::config::config();
+        let monitor = config.monitor.then(MonitorConfig::default);
+        initialize_agents(config.agents, monitor).await?;
     }
     Ok(())
 }
 
-/// Get actor reference by ID (1-5)
-pub fn get_agent(agent_id: u8) -> Option<ActorRef<AgentMessage>> {
-    match agent_id {
-        1 => AGENT_1.get().cloned(),
-        2 => AGENT_2.get().cloned(),
-        3 => AGENT_3.get().cloned(),
-        4 => AGENT_4.get().cloned(),
-        5 => AGENT_5.get().cloned(),
-        _ => None,
+/// How long `agent_status` waits for a busy agent to answer
+pub const STATUS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Ask an agent for a snapshot of its state and wait for the answer
+///
+/// The request overtakes messages queued for the agent but waits for the one
+/// being handled, so an agent busy with a slow message may not answer within
+/// `STATUS_TIMEOUT`.
+pub async fn agent_status(agent_id: u8) -> anyhow::Result<AgentState> {
+    let actor_ref = get_agent(agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+    match actor_ref.call(AgentMessage::GetStatusReply, Some(STATUS_TIMEOUT)).await {
+        Ok(CallResult::Success(state)) => Ok(state),
+        Ok(CallResult::Timeout) => anyhow::bail!("Agent{} did not answer within {:?}", agent_id, STATUS_TIMEOUT),
+        Ok(CallResult::SenderError) => anyhow::bail!("Agent{} stopped before answering", agent_id),
+        Err(e) => anyhow::bail!("failed to reach Agent{}: {}", agent_id, e),
+    }
+}
+
+/// History entries of agent `agent_id` tagged with keyword or entity `term`, oldest first
+///
+/// Read from the agent's state like `agent_status`.
+pub async fn search_history(agent_id: u8, term: &str) -> anyhow::Result<Vec<HistoryEntry>> {
+    let state = agent_status(agent_id).await?;
+    Ok(state.search(term).into_iter().cloned().collect())
+}
+
+/// How long `classify` waits for the agent's answer
+#[cfg(not(target_arch = "wasm32"))]
+pub const CLASSIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Classify `text` on agent `agent_id` and wait for the result
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn classify(agent_id: u8, text: &str) -> anyhow::Result<Classification> {
+    let actor_ref = get_agent(agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+    let text = text.to_string();
+    match actor_ref.call(|reply| AgentMessage::Classify { text, reply }, Some(CLASSIFY_TIMEOUT)).await {
+        Ok(CallResult::Success(result)) => result.map_err(anyhow::Error::msg),
+        Ok(CallResult::Timeout) => anyhow::bail!("Agent{} did not answer within {:?}", agent_id, CLASSIFY_TIMEOUT),
+        Ok(CallResult::SenderError) => anyhow::bail!("Agent{} stopped before answering", agent_id),
+        Err(e) => anyhow::bail!("failed to reach Agent{}: {}", agent_id, e),
+    }
+}
+
+/// How long `hand_off` waits for the handing agent to answer
+pub const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Move agent `from`'s conversation (history and summary) to agent `to`
+///
+/// The handoff runs in `from`'s mailbox order, so messages queued before it
+/// are part of the conversation handed over. `to` continues it with its own
+/// persona; `from` starts over with an empty history.
+pub async fn hand_off(from: u8, to: u8, reason: &str) -> anyhow::Result<HandoffReceipt> {
+    let actor_ref = get_agent(from).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", from))?;
+    let reason = reason.to_string();
+    match actor_ref.call(|reply| AgentMessage::HandOff { to, reason, reply }, Some(HANDOFF_TIMEOUT)).await {
+        Ok(CallResult::Success(result)) => result.map_err(anyhow::Error::msg),
+        Ok(CallResult::Timeout) => anyhow::bail!("Agent{} did not answer within {:?}", from, HANDOFF_TIMEOUT),
+        Ok(CallResult::SenderError) => anyhow::bail!("Agent{} stopped before answering", from),
+        Err(e) => anyhow::bail!("failed to reach Agent{}: {}", from, e),
+    }
+}
+
+/// Whether the agent pool has been started in this process
+pub fn is_initialized() -> bool {
+    INITIALIZED.load(Ordering::SeqCst)
+}
+
+/// Get actor reference by ID (1-based); messages sent through it go through
+/// the agent's priority queue
+pub fn get_agent(agent_id: u8) -> Option<AgentRef> {
+    let agents = AGENTS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    agents.get(&agent_id).map(|actor_ref| AgentRef::new(agent_id, actor_ref.clone()))
+}
+
+/// Queue work (data to process) for a running agent
+///
+/// The message is listed in the job queue, so it can be cancelled, requeued
+/// or moved; every producer of agent work sends through here.
+pub fn send_work(actor_ref: &AgentRef, message: AgentMessage) -> Result<(), mailbox::SendError> {
+    actor_ref.send_message(message.queued(actor_ref.id()))
+}
+
+/// Ids of all running agents, ascending
+pub fn agent_ids() -> Vec<u8> {
+    AGENTS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).keys().copied().collect()
+}
+
+/// Lowest id not used by a running agent
+pub fn next_free_id() -> Option<u8> {
+    let agents = AGENTS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    (1..=u8::MAX).find(|id| !agents.contains_key(id))
+}
+
+/// Start an agent with `id` under the supervisor and add it to the registry
+pub async fn spawn_agent(id: u8) -> Result<ActorRef<AgentMessage>, String> {
+    if id == 0 {
+        return Err("agent ids start at 1".to_string());
+    }
+    request_spawn(id).await
+}
+
+/// Spawn an agent in `role`, whatever its configured role; crash restarts keep it
+pub async fn spawn_agent_as(id: u8, role: AgentRole) -> Result<ActorRef<AgentMessage>, String> {
+    if id == 0 {
+        return Err("agent ids start at 1".to_string());
+    }
+    if get_agent(id).is_some() {
+        return Err(format!("Agent{} is already running", id));
+    }
+    roles::assign(id, role);
+    let result = request_spawn(id).await;
+    if result.is_err() {
+        roles::unassign(id);
+    }
+    result
+}
+
+async fn request_spawn(id: u8) -> Result<ActorRef<AgentMessage>, String> {
+    let supervisor = SUPERVISOR.get().ok_or_else(|| "the agent pool is not initialized".to_string())?;
+    let actor_ref = match supervisor.call(|reply| SupervisorMessage::Spawn { id, reply }, None).await {
+        Ok(CallResult::Success(result)) => result?,
+        Ok(_) => return Err(format!("the supervisor did not answer while spawning Agent{}", id)),
+        Err(e) => return Err(format!("failed to reach the supervisor: {}", e)),
+    };
+    publish("agent.spawned", json!({ "agent_id": id, "role": roles::role_of(id) }));
+    Ok(actor_ref)
+}
+
+/// Stop an agent and remove it from the registry; returns whether it was running
+///
+/// Messages already queued for the agent are dropped, and a restart pending
+/// after a crash is cancelled.
+pub fn stop_agent(id: u8) -> bool {
+    if let Some(supervisor) = SUPERVISOR.get() {
+        let _ = supervisor.cast(SupervisorMessage::Stop { id });
+    }
+    let removed = AGENTS.write().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(&id);
+    let Some(actor_ref) = removed else {
+        return false;
+    };
+    unsubscribe_all(id);
+    roles::unassign(id);
+    jobs::mark_lost(id);
+    mailbox::clear(id);
+    actor_ref.stop(Some("stopped via registry".to_string()));
+    log_info!("[AgentRegistry] Stopped Agent{}", id);
+    publish("agent.stopped", json!({ "agent_id": id }));
+    true
+}
+
+/// Drop `id` from the registry if it still points at the actor `actor_id`
+fn unregister(id: u8, actor_id: ActorId) {
+    let mut agents = AGENTS.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if agents.get(&id).is_some_and(|current| current.get_id() == actor_id) {
+        agents.remove(&id);
+    }
+}
+
+// ============================================================================
+// Topics
+// ============================================================================
+//
+// Agents talk to each other through named topics. An agent subscribes with
+// `AgentMessage::Subscribe`; an agent handling `AgentMessage::Publish` sends
+// the payload to every other subscriber as a `ProcessPayload` message, so one
+// agent's output starts processing in the others. Delivery is fire and
+// forget: subscribers that aren't running are skipped.
+//
+// Subscriptions belong to agent ids, so an agent restarted after a crash
+// keeps receiving its topics. Stopping an agent through the registry drops
+// its subscriptions. An agent never receives its own publications, and
+// delivered payloads are not published again, so topics can't loop.
+
+/// Subscribed agent ids by topic
+static TOPICS: RwLock<BTreeMap<String, BTreeSet<u8>>> = RwLock::new(BTreeMap::new());
+
+fn subscriptions() -> std::sync::RwLockWriteGuard<'static, BTreeMap<String, BTreeSet<u8>>> {
+    TOPICS.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Topics with their subscribed agent ids
+pub fn topics() -> BTreeMap<String, BTreeSet<u8>> {
+    TOPICS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Drop every subscription of agent `id`
+fn unsubscribe_all(id: u8) {
+    let mut topics = subscriptions();
+    for subscribers in topics.values_mut() {
+        subscribers.remove(&id);
+    }
+    topics.retain(|_, subscribers| !subscribers.is_empty());
+}
+
+/// Queue `payload` for every running subscriber of `topic` except `from`; returns how many got it
+fn deliver_to_subscribers(from: u8, topic: &str, payload: &Payload) -> usize {
+    let subscribers: Vec<u8> = TOPICS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(topic)
+        .map(|subscribers| subscribers.iter().copied().filter(|id| *id != from).collect())
+        .unwrap_or_default();
+    let mut delivered = 0;
+    for id in subscribers {
+        let Some(actor_ref) = get_agent(id) else {
+            continue;
+        };
+        let message = AgentMessage::ProcessPayload { payload: payload.clone() }.queued(id);
+        if actor_ref.send_message(message).is_ok() {
+            delivered += 1;
+        }
+    }
+    publish("agent.published", json!({
+        "agent_id": from,
+        "topic": topic,
+        "delivered": delivered,
+    }));
+    delivered
+}
+
+// ============================================================================
+// Broadcast
+// ============================================================================
+
+/// Whether one agent got a broadcast
+#[derive(Debug, Clone, Serialize)]
+pub struct BroadcastDelivery {
+    pub agent_id: u8,
+    pub delivered: bool,
+    /// Why the agent didn't get it (e.g. its mailbox is full)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Job tracking the delivery, while the agent hasn't handled it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job: Option<u64>,
+}
+
+/// Queue `payload` for every running agent, e.g. a cache-invalidation command
+///
+/// Each agent gets it as `AgentMessage::Broadcast`, ahead of its normal data.
+/// Returns one delivery result per agent, in id order.
+pub fn broadcast(payload: &Payload) -> Vec<BroadcastDelivery> {
+    let deliveries: Vec<BroadcastDelivery> = agent_ids()
+        .into_iter()
+        .map(|id| {
+            let Some(actor_ref) = get_agent(id) else {
+                return BroadcastDelivery { agent_id: id, delivered: false, error: Some("stopped".to_string()), job: None };
+            };
+            let message = AgentMessage::Broadcast { payload: payload.clone() }.queued(id);
+            let AgentMessage::Queued { job, .. } = &message else {
+                unreachable!("queued() wraps the message");
+            };
+            let job = *job;
+            match actor_ref.send_message(message) {
+                Ok(()) => BroadcastDelivery { agent_id: id, delivered: true, error: None, job: Some(job) },
+                Err(e) => BroadcastDelivery { agent_id: id, delivered: false, error: Some(e.to_string()), job: None },
+            }
+        })
+        .collect();
+    let delivered = deliveries.iter().filter(|delivery| delivery.delivered).count();
+    log_info!("[AgentRegistry] Broadcast {} to {} of {} agents", payload.describe(), delivered, deliveries.len());
+    publish("agent.broadcast", json!({
+        "payload": payload.describe(),
+        "delivered": delivered,
+        "failed": deliveries.iter().filter(|delivery| !delivery.delivered).map(|delivery| delivery.agent_id).collect::<Vec<_>>(),
+    }));
+    deliveries
+}
+
+// ============================================================================
+// Lifecycle
+// ============================================================================
+//
+// Operators can pause, drain and resume an agent without stopping it (see
+// `mailbox` for what each state does with queued and new work). The change
+// is made by the agent itself, in the order of its control messages, and
+// published as `agent.lifecycle`.
+
+/// An agent's lifecycle state and how much work is waiting for it
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleStatus {
+    pub agent_id: u8,
+    pub state: Lifecycle,
+    /// Messages waiting in the agent's queue
+    pub queued: usize,
+}
+
+pub fn lifecycle_status(agent_id: u8) -> LifecycleStatus {
+    LifecycleStatus { agent_id, state: mailbox::lifecycle(agent_id), queued: mailbox::depth(agent_id) }
+}
+
+/// Send `Pause`, `Resume` or `Drain` to agent `agent_id` and wait until it took effect
+pub async fn change_lifecycle(agent_id: u8, message: AgentMessage) -> anyhow::Result<LifecycleStatus> {
+    let actor_ref = get_agent(agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+    actor_ref.send_message(message).map_err(|e| anyhow::anyhow!("{}", e))?;
+    // Control messages keep their order, so the agent answers after the change
+    match actor_ref.call(AgentMessage::Ping, Some(STATUS_TIMEOUT)).await {
+        Ok(CallResult::Success(())) => {}
+        Ok(CallResult::Timeout) => anyhow::bail!("Agent{} did not answer within {:?}", agent_id, STATUS_TIMEOUT),
+        Ok(CallResult::SenderError) => anyhow::bail!("Agent{} stopped before answering", agent_id),
+        Err(e) => anyhow::bail!("failed to reach Agent{}: {}", agent_id, e),
+    }
+    Ok(lifecycle_status(agent_id))
+}
+
+/// Switch the agent to `lifecycle`, from its own handler
+fn apply_lifecycle(agent_id: u8, lifecycle: Lifecycle) {
+    let previous = mailbox::lifecycle(agent_id);
+    mailbox::set_lifecycle(agent_id, lifecycle);
+    if lifecycle != Lifecycle::Paused {
+        // Work that waited while paused had its turn skipped
+        if let Some(actor_ref) = get_agent(agent_id) {
+            actor_ref.wake();
+        }
+    }
+    let status = lifecycle_status(agent_id);
+    if previous != lifecycle {
+        log_info!("[Agent{}] {} -> {} ({} queued)", agent_id, previous.name(), lifecycle.name(), status.queued);
+        publish("agent.lifecycle", json!(status));
+    }
+}
+
+/// Mark a draining agent drained once its queue is empty
+fn finish_drain(agent_id: u8) {
+    if mailbox::lifecycle(agent_id) == Lifecycle::Draining && mailbox::depth(agent_id) == 0 {
+        mailbox::set_lifecycle(agent_id, Lifecycle::Drained);
+        log_info!("[Agent{}] Drained", agent_id);
+        publish("agent.drained", json!({ "agent_id": agent_id }));
     }
 }
+
+// ============================================================================
+// Supervision
+// ============================================================================
+//
+// Every agent runs as a linked child of one supervisor actor. When an agent's
+// handler fails or panics, ractor stops that agent and reports it to the
+// supervisor, which removes it from the registry and, under the `one_for_one`
+// policy, starts a fresh agent with the same id after a backoff, then points
+// the registry at it. Other agents keep running untouched.
+//
+// The backoff starts at `agent_restart_backoff` and doubles with each crash
+// in a row, up to `MAX_RESTART_BACKOFF`; an agent that stayed up for
+// `STABLE_PERIOD` starts over at the initial delay. After
+// `MAX_CONSECUTIVE_RESTARTS` crashes in a row the agent is left stopped.
+//
+// A restarted agent resumes from its last saved state (see Agent State
+// Persistence), so it loses what happened since that save. Until it is back, messages for its id fail with
+// "not available" like for any agent that isn't running.
+
+/// What the supervisor does when an agent crashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    /// Restart only the crashed agent, after a backoff
+    OneForOne,
+    /// Leave crashed agents stopped
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "one_for_one" => Some(RestartPolicy::OneForOne),
+            "never" => Some(RestartPolicy::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Longest wait before restarting a crashed agent
+pub const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Uptime after which an agent's crash count starts over
+pub const STABLE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Crashes in a row after which an agent is no longer restarted
+pub const MAX_CONSECUTIVE_RESTARTS: u32 = 10;
+
+/// The supervisor of all agents, started by `initialize_agents`
+static SUPERVISOR: OnceLock<ActorRef<SupervisorMessage>> = OnceLock::new();
+
+pub struct AgentSupervisor;
+
+pub enum SupervisorMessage {
+    /// Start an agent (if `id` isn't running) and answer with its reference
+    Spawn {
+        id: u8,
+        reply: RpcReplyPort<Result<ActorRef<AgentMessage>, String>>,
+    },
+    /// The agent was stopped on request; drop any pending restart
+    Stop { id: u8 },
+    /// Restart a crashed agent once its backoff has passed
+    Restart { id: u8 },
+}
+
+#[cfg(feature = "cluster")]
+impl ractor::Message for SupervisorMessage {}
+
+/// A running child agent
+struct Child {
+    id: u8,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct SupervisorState {
+    children: HashMap<ActorId, Child>,
+    /// Crashes in a row per agent id
+    crashes: HashMap<u8, u32>,
+    /// Agents stopped on request since they last crashed
+    stopped: HashSet<u8>,
+}
+
+impl Actor for AgentSupervisor {
+    type Msg = SupervisorMessage;
+    type State = SupervisorState;
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _arguments: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        log_info!("[AgentSupervisor] Started");
+        Ok(SupervisorState::default())
+    }
+
+    async fn handle(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match message {
+            SupervisorMessage::Spawn { id, reply } => {
+                state.stopped.remove(&id);
+                state.crashes.remove(&id);
+                let _ = reply.send(start_child(&myself, id, state).await);
+            }
+            SupervisorMessage::Stop { id } => {
+                state.stopped.insert(id);
+            }
+            SupervisorMessage::Restart { id } => {
+                // Stopped while waiting, or spawned again through the registry
+                if state.stopped.contains(&id) || get_agent(id).is_some() {
+                    return Ok(());
+                }
+                match start_child(&myself, id, state).await {
+                    Ok(_) => {
+                        let crashes = state.crashes.get(&id).copied().unwrap_or(0);
+                        log_info!("[AgentSupervisor] Restarted Agent{} (crash {} in a row)", id, crashes);
+                        publish("agent.restarted", json!({ "agent_id": id, "crashes": crashes }));
+                    }
+                    Err(e) => log_error!("[AgentSupervisor] Failed to restart Agent{}: {}", id, e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_supervisor_evt(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        event: SupervisionEvent,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match event {
+            SupervisionEvent::ActorFailed(cell, error) => {
+                let Some(child) = state.children.remove(&cell.get_id()) else {
+                    return Ok(());
+                };
+                let id = child.id;
+                unregister(id, cell.get_id());
+                jobs::mark_lost(id);
+
+                let crashes = if child.started.elapsed() >= STABLE_PERIOD {
+                    1
+                } else {
+                    state.crashes.get(&id).copied().unwrap_or(0) + 1
+                };
+                state.crashes.insert(id, crashes);
+                log_error!("[AgentSupervisor] Agent{} crashed ({} in a row): {}", id, crashes, error);
+                publish("agent.crashed", json!({
+                    "agent_id": id,
+                    "error": error.to_string(),
+                    "crashes": crashes,
+                }));
+
+                let config = crate::config::config();
+                if config.agent_restart == RestartPolicy::Never || state.stopped.contains(&id) {
+                    // Nothing will take what is queued, nor report its depth correctly
+                    mailbox::clear(id);
+                    return Ok(());
+                }
+                if crashes > MAX_CONSECUTIVE_RESTARTS {
+                    log_error!("[AgentSupervisor] Giving up on Agent{} after {} crashes in a row", id, crashes);
+                    publish("agent.abandoned", json!({ "agent_id": id, "crashes": crashes }));
+                    mailbox::clear(id);
+                    return Ok(());
+                }
+                let delay = restart_delay(config.agent_restart_backoff, crashes);
+                log_warn!("[AgentSupervisor] Restarting Agent{} in {:?}", id, delay);
+                myself.send_after(delay, move || SupervisorMessage::Restart { id });
+            }
+            SupervisionEvent::ActorTerminated(cell, _, _) => {
+                if let Some(child) = state.children.remove(&cell.get_id()) {
+                    unregister(child.id, cell.get_id());
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Spawn agent `id` linked to the supervisor and bind it in the registry
+async fn start_child(
+    myself: &ActorRef<SupervisorMessage>,
+    id: u8,
+    state: &mut SupervisorState,
+) -> Result<ActorRef<AgentMessage>, String> {
+    if get_agent(id).is_some() {
+        return Err(format!("Agent{} is already running", id));
+    }
+    let (actor_ref, _handle) = Actor::spawn_linked(None, Agent { id }, id, myself.get_cell())
+        .await
+        .map_err(|e| format!("Failed to spawn Agent{}: {:?}", id, e))?;
+    state.children.insert(actor_ref.get_id(), Child { id, started: Instant::now() });
+    AGENTS.write().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(id, actor_ref.clone());
+    Ok(actor_ref)
+}
+
+/// Backoff before the restart following the `crashes`-th crash in a row
+fn restart_delay(initial: Duration, crashes: u32) -> Duration {
+    initial
+        .saturating_mul(1u32 << crashes.saturating_sub(1).min(16))
+        .min(MAX_RESTART_BACKOFF)
+}
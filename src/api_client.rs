@@ -0,0 +1,85 @@
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::compression::{compress_if_large, Codec};
+
+// ============================================================================
+// HTTP API Client
+// ============================================================================
+//
+// Talks to a running instance through the same endpoints the web app uses
+// (`src/shared/api.rs`). Server functions take their arguments as a JSON
+// object keyed by parameter name and most return a JSON document wrapped
+// in a JSON string; `get` / `post` unwrap that so callers see the document.
+// Responses are decompressed transparently (gzip / zstd).
+
+/// Base URL of the local server (`IP` / `PORT`, as the server binds them)
+pub fn default_base_url() -> String {
+    let ip = std::env::var("IP").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
+    format!("http://{}:{}", ip, port)
+}
+
+/// Client for the HTTP API of one instance
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            // Above the 60 s long-polls of the event and MCP streams
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(75))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// GET `path` (including any query string)
+    pub async fn get(&self, path: &str) -> anyhow::Result<Value> {
+        let response = self.http.get(format!("{}{}", self.base_url, path)).send().await?;
+        Self::decode(path, response).await
+    }
+
+    /// POST `args` (an object keyed by the server function's parameter names) to `path`
+    ///
+    /// Bodies above the compression threshold are sent zstd-compressed.
+    pub async fn post(&self, path: &str, args: Value) -> anyhow::Result<Value> {
+        let body = serde_json::to_vec(&args)?;
+        let request = self.http.post(format!("{}{}", self.base_url, path)).header("Content-Type", "application/json");
+        let request = match compress_if_large("http.request", Codec::Zstd, &body) {
+            Some(compressed) => request.header("Content-Encoding", Codec::Zstd.name()).body(compressed),
+            None => request.body(body),
+        };
+        Self::decode(path, request.send().await?).await
+    }
+
+    async fn decode(path: &str, response: reqwest::Response) -> anyhow::Result<Value> {
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            anyhow::bail!("{} returned {}: {}", path, status, text.trim());
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        let value: Value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        // Endpoints returning a serialized document send it as a JSON string
+        Ok(match value {
+            Value::String(inner) => match serde_json::from_str::<Value>(&inner) {
+                Ok(document @ (Value::Object(_) | Value::Array(_))) => document,
+                _ if inner.is_empty() => Value::Null,
+                _ => Value::String(inner),
+            },
+            other => other,
+        })
+    }
+}
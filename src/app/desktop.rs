@@ -3,21 +3,12 @@
 #[cfg(feature = "desktop")]
 use dioxus::prelude::*;
 #[cfg(feature = "desktop")]
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-#[cfg(feature = "desktop")]
 use std::time::Duration;
 #[cfg(feature = "desktop")]
 use burn::backend::{Autodiff, wgpu::Wgpu};
 
 #[cfg(feature = "desktop")]
-use crate::shared::{SystemInfo, echo_server};
-
-// Global cognitive cycle state
-#[cfg(feature = "desktop")]
-static COGNITIVE_CYCLE_STATE: AtomicBool = AtomicBool::new(false);
-// Global cognitive cycle counter
-#[cfg(feature = "desktop")]
-static COGNITIVE_CYCLE_COUNTER: AtomicU64 = AtomicU64::new(0);
+use pattern_clock::shared::{SystemInfo, DiagnosticsView, ExperimentsView, LabelingView, LatencyView, GalleryView, PersonasView, ApprovalsView, QueueView, FeedbackView, echo_server};
 
 #[cfg(feature = "desktop")]
 const FAVICON: Asset = asset!("/assets/favicon.ico");
@@ -28,19 +19,12 @@ const MAIN_CSS: Asset = asset!("/assets/main.css");
 #[cfg(feature = "desktop")]
 #[component]
 pub fn DesktopApp() -> Element {
-    let mut cycle_state = use_signal(|| COGNITIVE_CYCLE_STATE.load(Ordering::SeqCst));
+    let mut cycle_state = use_signal(pattern_clock::cycle::is_running);
+    // Show the self-test report until it has been dismissed once
+    let mut show_diagnostics = use_signal(pattern_clock::doctor::is_first_launch);
     
-    use_effect(move || {
-        spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_millis(50));
-            loop {
-                interval.tick().await;
-                if COGNITIVE_CYCLE_STATE.load(Ordering::SeqCst) {
-                    COGNITIVE_CYCLE_COUNTER.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-        });
-    });
+    // Tick interval follows event volume (see `pattern_clock::cycle`)
+    use_effect(pattern_clock::cycle::ensure_started);
     
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
@@ -49,13 +33,24 @@ pub fn DesktopApp() -> Element {
         br {}
         SystemInfo {}
         br {}
+        CrashReportBanner {}
+        UpdateBanner {}
+        if show_diagnostics() {
+            DiagnosticsView {
+                on_dismiss: move |_| {
+                    pattern_clock::doctor::mark_launched();
+                    show_diagnostics.set(false);
+                },
+            }
+            br {}
+        }
         div {
             id: "app-header",
             width: "40%",
             button {
                 onclick: move |_| {
-                    let new_state = !COGNITIVE_CYCLE_STATE.load(Ordering::SeqCst);
-                    COGNITIVE_CYCLE_STATE.store(new_state, Ordering::SeqCst);
+                    let new_state = !pattern_clock::cycle::is_running();
+                    pattern_clock::cycle::set_running(new_state);
                     cycle_state.set(new_state);
                     println!("cognitive_cycle_state={}", new_state);
                 },
@@ -68,6 +63,7 @@ pub fn DesktopApp() -> Element {
                 margin_left: "10px",
             }
         }
+        CycleClock {}
         br {}
         div {
             id: "app-header",
@@ -75,18 +71,30 @@ pub fn DesktopApp() -> Element {
             button {
                 onclick: move |_| {
                     println!("Building LSTM model");
-                    type Backend = Autodiff<Wgpu>;
-                    let device = Default::default();
-                    let config = crate::lstm::LstmConfig::default();
-                    let lstm = crate::lstm::Lstm::<Backend>::new(config, &device);
-                    println!("{:#?}", lstm);
+                    spawn(async move {
+                        let built = pattern_clock::compute::run("desktop.build_lstm", || {
+                            type Backend = Autodiff<Wgpu>;
+                            let device = Default::default();
+                            let config = pattern_clock::lstm::LstmConfig::default();
+                            let lstm = pattern_clock::lstm::Lstm::<Backend>::new(config, &device);
+                            format!("{:#?}", lstm)
+                        }).await;
+                        match built {
+                            Ok(lstm) => println!("{}", lstm),
+                            Err(e) => eprintln!("Failed to build LSTM: {}", e),
+                        }
+                    });
                 },
                 "Build LSTM"
             }
             button {
                 onclick: move |_| {
                     println!("Computing tensor gradients");
-                    crate::burn_tensor_example();
+                    spawn(async move {
+                        if let Err(e) = pattern_clock::compute::run("desktop.tensor_gradients", crate::burn_tensor_example).await {
+                            eprintln!("Failed to compute tensor gradients: {}", e);
+                        }
+                    });
                 },
                 "Compute Tensor Gradients"
             }
@@ -102,6 +110,162 @@ pub fn DesktopApp() -> Element {
             }
         }
         DesktopMCP {}
+        br {}
+        LabelingView {}
+        br {}
+        ExperimentsView {}
+        br {}
+        LatencyView {}
+        br {}
+        GalleryView {}
+        br {}
+        PersonasView {}
+        br {}
+        ApprovalsView {}
+        br {}
+        QueueView {}
+        br {}
+        FeedbackView {}
+    }
+}
+
+/// Shows a newer release and its changelog when an update URL is configured
+#[cfg(feature = "desktop")]
+#[component]
+fn UpdateBanner() -> Element {
+    let mut dismissed = use_signal(|| false);
+    let mut show_notes = use_signal(|| false);
+    let update = use_resource(move || async move {
+        let response = pattern_clock::shared::check_for_update().await.ok()?;
+        let response: serde_json::Value = serde_json::from_str(&response).ok()?;
+        response.get("update").filter(|update| !update.is_null()).cloned()
+    });
+
+    let Some(Some(update)) = update() else {
+        return rsx! {};
+    };
+    if dismissed() {
+        return rsx! {};
+    }
+    let version = update.get("version").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let url = update.get("url").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let notes = update.get("notes").and_then(|v| v.as_str()).unwrap_or_default();
+    let notes_html = pattern_clock::shared::markdown_to_html(notes);
+
+    rsx! {
+        div {
+            id: "update-banner",
+            font_size: "12px",
+            span { "pattern-clock {version} is available " }
+            if !url.is_empty() {
+                a { href: "{url}", target: "_blank", "Download" }
+            }
+            button {
+                onclick: move |_| show_notes.set(!show_notes()),
+                if show_notes() { "Hide Release Notes" } else { "Release Notes" }
+            }
+            button {
+                onclick: move |_| dismissed.set(true),
+                "Dismiss"
+            }
+            if show_notes() {
+                div { dangerous_inner_html: "{notes_html}" }
+            }
+        }
+        br {}
+    }
+}
+
+/// Offers the crash reports written since the last launch
+#[cfg(feature = "desktop")]
+#[component]
+fn CrashReportBanner() -> Element {
+    let mut reports = use_signal(pattern_clock::crash::pending_reports);
+    let mut expanded = use_signal(|| false);
+
+    let Some(latest) = reports().first().cloned() else {
+        return rsx! {};
+    };
+    let count = reports().len();
+    let report_json = serde_json::to_string_pretty(&latest.report).unwrap_or_default();
+
+    rsx! {
+        div {
+            id: "crash-report",
+            p {
+                font_size: "12px",
+                "pattern-clock crashed during the last session: {latest.report.message}"
+                if count > 1 { " ({count} reports)" }
+            }
+            button {
+                onclick: move |_| expanded.set(!expanded()),
+                if expanded() { "Hide Report" } else { "Open Report" }
+            }
+            button {
+                onclick: move |_| {
+                    for stored in reports() {
+                        if let Err(e) = pattern_clock::crash::acknowledge(&stored.path) {
+                            eprintln!("Failed to acknowledge crash report {}: {}", stored.path, e);
+                        }
+                    }
+                    reports.set(Vec::new());
+                },
+                "Dismiss"
+            }
+            if expanded() {
+                p { font_size: "10px", "{latest.path}" }
+                pre { font_size: "10px", "{report_json}" }
+            }
+        }
+        br {}
+    }
+}
+
+/// Clock face whose hand advances one step per cycle tick, with the effective rate
+#[cfg(feature = "desktop")]
+#[component]
+fn CycleClock() -> Element {
+    let mut status = use_signal(pattern_clock::cycle::status);
+
+    use_effect(move || {
+        spawn(async move {
+            let mut refresh = tokio::time::interval(Duration::from_millis(100));
+            loop {
+                refresh.tick().await;
+                status.set(pattern_clock::cycle::status());
+            }
+        });
+    });
+
+    let current = status();
+    // One revolution per 60 ticks
+    let angle = (current.ticks % 60) as f64 * 6.0;
+    let (x, y) = (50.0 + 38.0 * angle.to_radians().sin(), 50.0 - 38.0 * angle.to_radians().cos());
+    let hand = if current.running { "#006400" } else { "#8B0000" };
+
+    rsx! {
+        div {
+            id: "cycle-clock",
+            display: "flex",
+            align_items: "center",
+            gap: "10px",
+            svg {
+                width: "80",
+                height: "80",
+                view_box: "0 0 100 100",
+                circle { cx: "50", cy: "50", r: "45", fill: "none", stroke: "#888", stroke_width: "3" }
+                line { x1: "50", y1: "50", x2: "{x:.1}", y2: "{y:.1}", stroke: hand, stroke_width: "4", stroke_linecap: "round" }
+                circle { cx: "50", cy: "50", r: "4", fill: hand }
+            }
+            div {
+                font_size: "12px",
+                div { "{current.ticks_per_sec:.1} ticks/s ({current.interval_ms} ms)" }
+                div { "{current.events_per_sec:.1} events/s" }
+                if let Some(next) = &current.next_milestone {
+                    div { "next milestone {next}" }
+                }
+            }
+        }
     }
 }
 
@@ -152,6 +316,7 @@ fn DesktopEcho() -> Element {
 fn DesktopMCP() -> Element {
     let mut mcp_response = use_signal(|| String::new());
     let mut is_loading = use_signal(|| false);
+    let mut classify_input = use_signal(|| String::new());
 
     rsx! {
         div {
@@ -162,7 +327,7 @@ fn DesktopMCP() -> Element {
                     mcp_response.set(String::new());
                     spawn(async move {
                         // Call MCP tool directly via server function
-                        match crate::shared::mcp_example_tool().await {
+                        match pattern_clock::shared::mcp_example_tool().await {
                             Ok(result) => {
                                 mcp_response.set(result);
                                 is_loading.set(false);
@@ -185,7 +350,7 @@ fn DesktopMCP() -> Element {
                     mcp_response.set(String::new());
                     spawn(async move {
                         // Call MCP tool directly via server function
-                        match crate::shared::mcp_random_number().await {
+                        match pattern_clock::shared::mcp_random_number().await {
                             Ok(result) => {
                                 mcp_response.set(result);
                                 is_loading.set(false);
@@ -200,6 +365,34 @@ fn DesktopMCP() -> Element {
                 if is_loading() { "Loading..." } else { "Get Random Number" }
             }
         }
+        div {
+            input {
+                placeholder: "Text to classify",
+                value: "{classify_input}",
+                oninput: move |event| classify_input.set(event.value()),
+            }
+            button {
+                disabled: is_loading() || classify_input().is_empty(),
+                onclick: move |_| {
+                    is_loading.set(true);
+                    mcp_response.set(String::new());
+                    spawn(async move {
+                        // Call MCP tool directly via server function
+                        match pattern_clock::shared::mcp_classify_text(classify_input()).await {
+                            Ok(result) => {
+                                mcp_response.set(result);
+                                is_loading.set(false);
+                            }
+                            Err(e) => {
+                                mcp_response.set(format!("Error: {}", e));
+                                is_loading.set(false);
+                            }
+                        }
+                    });
+                },
+                if is_loading() { "Loading..." } else { "Classify Text" }
+            }
+        }
         if !mcp_response().is_empty() {
             div {
                 p {
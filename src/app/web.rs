@@ -6,20 +6,36 @@ use dioxus::prelude::*;
 #[cfg(any(feature = "web", feature = "server"))]
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 
+/// Agent events kept in the activity list
+#[cfg(any(feature = "web", feature = "server"))]
+const ACTIVITY_LEN: usize = 20;
+
 /// Web application root component
 /// Web app acts as MCP client - subscribes to MCP stream for real-time results
+/// and agent activity (agent events arrive on the same stream, as JSON)
 #[cfg(any(feature = "web", feature = "server"))]
 #[component]
 pub fn WebApp() -> Element {
     let mut mcp_results = use_signal(|| Vec::<String>::new());
+    let mut activity = use_signal(|| Vec::<pattern_clock::agent_events::AgentEvent>::new());
     
     // Subscribe to MCP channel when component mounts (long-polling)
     use_effect(move || {
         spawn(async move {
+            let mut subscriber: Option<String> = None;
             loop {
-                match crate::shared::mcp_receive().await {
+                // Keep one subscription across polls so results between polls are not lost
+                if subscriber.is_none() {
+                    subscriber = pattern_clock::shared::mcp_subscribe(None).await.ok();
+                }
+                match pattern_clock::shared::mcp_receive(subscriber.clone()).await {
                     Ok(result) => {
-                        if !result.is_empty() {
+                        if let Ok(event) = serde_json::from_str::<pattern_clock::agent_events::AgentEvent>(&result) {
+                            activity.with_mut(|events| {
+                                events.insert(0, event);
+                                events.truncate(ACTIVITY_LEN);
+                            });
+                        } else if !result.is_empty() {
                             eprintln!("[Web] Received MCP result: {}", result);
                             mcp_results.with_mut(|results| {
                                 results.push(result);
@@ -33,6 +49,8 @@ pub fn WebApp() -> Element {
                     }
                     Err(e) => {
                         eprintln!("[Web] MCP receive error: {}, retrying...", e);
+                        // Disconnected or expired: subscribe again on the next round
+                        subscriber = None;
                         tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
                     }
                 }
@@ -95,6 +113,37 @@ pub fn WebApp() -> Element {
                     }
                 }
             }
+            div {
+                margin_top: "20px",
+                h3 { "Agent Activity" }
+                if activity().is_empty() {
+                    p { color: "#999", "No agent activity yet." }
+                } else {
+                    table {
+                        border_collapse: "collapse",
+                        for event in activity().iter() {
+                            tr {
+                                key: "{event.agent_id}-{event.ts}-{event.kind}",
+                                color: if event.payload["ok"].as_bool() == Some(false) { "#c62828" } else { "inherit" },
+                                td { padding: "2px 8px", "Agent{event.agent_id}" }
+                                td { padding: "2px 8px", "{event.kind}" }
+                                td { padding: "2px 8px", {format!("{} ms", event.payload["duration_ms"])} }
+                                td {
+                                    padding: "2px 8px",
+                                    {event.payload["error"].as_str().or(event.payload["output"].as_str()).or(event.payload["preview"].as_str()).unwrap_or_default().to_string()}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            pattern_clock::shared::LatencyView {}
+            pattern_clock::shared::RulesView {}
+            pattern_clock::shared::GalleryView {}
+            pattern_clock::shared::PersonasView {}
+            pattern_clock::shared::ApprovalsView {}
+            pattern_clock::shared::QueueView {}
+            pattern_clock::shared::FeedbackView {}
         }
     }
 }
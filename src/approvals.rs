@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::events::publish;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed};
+
+// ============================================================================
+// Approvals
+// ============================================================================
+//
+// A step that needs an operator's go-ahead asks for an approval and pauses.
+// The pending approval describes what is about to happen; it is listed under
+// `/api/approvals` and in the Approvals panel, `approval.requested` is
+// published, and notification channels with `approvals: true` receive it.
+// The operator approves or rejects it through the API, and the paused step
+// resumes or aborts.
+//
+// An approval may carry a timeout. Once it expires, its timeout policy
+// decides it (reject unless the policy says approve), recorded as decided by
+// `timeout`. Expired approvals are swept by the leader, so instances sharing
+// a database don't resume the same step twice.
+//
+// The rule sandbox is the only step that waits for approvals so far: a trial
+// started with `approval` pauses when its window ends and its draft is
+// promoted once the approval is granted. There is no workflow engine yet.
+
+/// Collection holding approvals, keyed by approval id
+pub const APPROVALS_COLLECTION: &str = "approvals";
+
+/// How often pending approvals are checked for expiry
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Recorded as the decider of approvals decided by their timeout policy
+pub const TIMEOUT_AUTHOR: &str = "timeout";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Decision taken when an approval times out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnTimeout {
+    #[default]
+    Reject,
+    Approve,
+}
+
+/// How a step asks for approval
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalPolicy {
+    /// Seconds to wait for a decision; without one the step waits indefinitely
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub on_timeout: OnTimeout,
+}
+
+/// The paused step, resumed once the approval is decided
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ApprovalStep {
+    /// Promote the draft rule of a finished trial
+    PromoteTrial { trial: String },
+}
+
+impl ApprovalStep {
+    fn target(&self) -> &str {
+        match self {
+            ApprovalStep::PromoteTrial { trial } => trial,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approval {
+    pub id: String,
+    pub step: ApprovalStep,
+    /// What the operator is asked to approve
+    pub summary: String,
+    pub requested_by: String,
+    /// Milliseconds since the Unix epoch
+    pub requested_at: u64,
+    pub expires_at: Option<u64>,
+    pub on_timeout: OnTimeout,
+    pub status: ApprovalStatus,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<u64>,
+    pub comment: Option<String>,
+}
+
+/// Ask for approval of `step`; the step resumes once it is decided
+pub fn request(step: ApprovalStep, summary: &str, policy: &ApprovalPolicy, author: &str) -> anyhow::Result<Approval> {
+    anyhow::ensure!(policy.timeout_secs != Some(0), "approval timeout must be at least 1 s");
+    let now = now_millis();
+    let approval = Approval {
+        id: format!("{:013}-{}", now, step.target()),
+        step,
+        summary: summary.to_string(),
+        requested_by: author.to_string(),
+        requested_at: now,
+        expires_at: policy.timeout_secs.map(|secs| now + secs * 1000),
+        on_timeout: policy.on_timeout,
+        status: ApprovalStatus::Pending,
+        decided_by: None,
+        decided_at: None,
+        comment: None,
+    };
+    put_typed(APPROVALS_COLLECTION, &approval.id, &approval)?;
+    log_info!("[Approvals] Waiting for approval {}: {}", approval.id, approval.summary);
+    publish("approval.requested", json!(approval));
+    Ok(approval)
+}
+
+/// All approvals, oldest first
+pub fn list_approvals() -> Vec<Approval> {
+    list_typed(APPROVALS_COLLECTION).unwrap_or_else(|e| {
+        log_warn!("[Approvals] Failed to load approvals: {}", e);
+        Vec::new()
+    })
+}
+
+/// Approvals still waiting for a decision, oldest first
+pub fn pending() -> Vec<Approval> {
+    list_approvals().into_iter().filter(|approval| approval.status == ApprovalStatus::Pending).collect()
+}
+
+pub fn get_approval(id: &str) -> anyhow::Result<Option<Approval>> {
+    get_typed(APPROVALS_COLLECTION, id)
+}
+
+/// Approve or reject a pending approval and resume or abort its step
+pub fn decide(id: &str, approve: bool, author: &str, comment: Option<String>) -> anyhow::Result<Approval> {
+    let approval = get_approval(id)?.ok_or_else(|| anyhow::anyhow!("unknown approval {}", id))?;
+    anyhow::ensure!(approval.status == ApprovalStatus::Pending, "approval {} was already {:?}", id, approval.status);
+    settle(approval, approve, author, comment)
+}
+
+/// Record the decision, then resume or abort the paused step
+fn settle(mut approval: Approval, approve: bool, author: &str, comment: Option<String>) -> anyhow::Result<Approval> {
+    approval.status = if approve { ApprovalStatus::Approved } else { ApprovalStatus::Rejected };
+    approval.decided_by = Some(author.to_string());
+    approval.decided_at = Some(now_millis());
+    approval.comment = comment;
+    put_typed(APPROVALS_COLLECTION, &approval.id, &approval)?;
+    log_info!("[Approvals] {} {:?} by {}", approval.id, approval.status, author);
+
+    let resumed = match &approval.step {
+        ApprovalStep::PromoteTrial { trial } => crate::sandbox::resume_after_approval(trial, approve, author),
+    };
+    if let Err(e) = &resumed {
+        log_error!("[Approvals] Failed to resume {}: {}", approval.id, e);
+    }
+    publish("approval.decided", json!({
+        "approval": approval,
+        "error": resumed.err().map(|e| e.to_string()),
+    }));
+    Ok(approval)
+}
+
+/// Decide expired approvals by their timeout policy (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                ticks.tick().await;
+                if !crate::leader::is_leader() {
+                    continue;
+                }
+                let now = now_millis();
+                for approval in pending().into_iter().filter(|approval| approval.expires_at.is_some_and(|at| at <= now)) {
+                    let approve = approval.on_timeout == OnTimeout::Approve;
+                    let id = approval.id.clone();
+                    if let Err(e) = settle(approval, approve, TIMEOUT_AUTHOR, None) {
+                        log_error!("[Approvals] Failed to expire {}: {}", id, e);
+                    }
+                }
+            }
+        });
+    });
+}
@@ -2,38 +2,30 @@
 // This runs as a separate binary that AI assistants can connect to via stdio
 // Run with: cargo run --bin mcp_server
 
-// Binaries need to include the modules they use
-mod mcp_server {
-    include!("../mcp_server.rs");
-}
-mod agents {
-    include!("../agents.rs");
-}
-mod shared {
-    pub mod api {
-        include!("../shared/api.rs");
-    }
-}
-
-use mcp_server::PatternClockMCP;
+use pattern_clock::mcp_server;
+use pattern_clock::telemetry;
 
 // Note: rmcp stdio server implementation may vary
 // This is a placeholder - adjust based on actual rmcp API
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let mcp_server = PatternClockMCP::new();
+    telemetry::init();
+    pattern_clock::crash::install();
+    // The same instance (and tool state) the server functions use
+    let mcp_server = mcp_server::shared();
     
     // For now, just print that MCP server is ready
     // The actual stdio server setup depends on rmcp API
     println!("MCP Server initialized. Tools available:");
-    println!("  - example_tool: Returns a greeting");
-    println!("  - process_agent: Process data through agents 1-5");
-    println!("  - get_agent_status: Get status of agents 1-5");
+    for tool in mcp_server.tools() {
+        let state = if tool.config.enabled { "enabled" } else { "disabled" };
+        println!("  - {} ({})", tool.name, state);
+    }
     println!("\nMCP Server ready (stdio mode)");
     println!("Press Ctrl+C to stop");
     
     // TODO: Implement actual stdio server when rmcp API is confirmed
-    // let server = StdioServer::new(mcp_server);
+    // let server = StdioServer::new(mcp_server.clone());
     // server.run().await?;
     
     // Keep running - wait for interrupt
@@ -0,0 +1,351 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::config;
+use crate::payload::Payload;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage, update_typed};
+
+// ============================================================================
+// Blob Store
+// ============================================================================
+//
+// Documents, uploads and training artifacts (exports and checkpoints) are
+// stored once per content, under their SHA-256. Storing the same bytes again
+// only returns the existing hash. Contents go to a `BlobBackend` chosen by
+// `PATTERN_CLOCK_BLOB_STORE`: `<data_dir>/blobs/<first 2 hex>/<hash>` by
+// default, or an S3-compatible bucket (`s3://bucket/prefix`, feature "s3") so
+// artifacts outlive the box. Metadata and references stay in storage.
+//
+// Blobs are kept alive by references: a row in `blob_refs` naming the blob
+// and its owner (`upload:<id>`, `run:<id>`, ...). The owners add and remove
+// their references alongside their own rows. `gc` deletes blobs nobody
+// references, but spares recently stored ones, because a blob is written
+// before its first reference exists; storing existing bytes again restarts
+// the blob's grace period, as a new reference is about to follow:
+//
+//     pattern-clock blobs gc                   # or POST /api/admin/blobs/gc
+
+/// Collection holding blob metadata, keyed by hash
+pub const BLOBS_COLLECTION: &str = "blobs";
+/// Collection holding references, keyed by `<hash>--<owner>`
+pub const BLOB_REFS_COLLECTION: &str = "blob_refs";
+/// Collection holding uploaded files, keyed by upload id
+pub const UPLOADS_COLLECTION: &str = "uploads";
+
+const BLOB_DIR: &str = "blobs";
+/// Unreferenced blobs younger than this survive `gc`
+pub const DEFAULT_GC_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Metadata of a stored blob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobInfo {
+    /// SHA-256 of the content, lowercase hex
+    pub hash: String,
+    pub size: u64,
+    pub content_type: String,
+    pub created_at: u64,
+    /// Last time `put` stored these bytes, possibly deduplicated; the grace period of `gc` counts from here
+    #[serde(default)]
+    pub stored_at: u64,
+}
+
+impl BlobInfo {
+    fn last_stored(&self) -> u64 {
+        self.created_at.max(self.stored_at)
+    }
+}
+
+/// One owner keeping a blob alive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobRef {
+    pub hash: String,
+    /// `<kind>:<id>` of the referencing row
+    pub owner: String,
+    pub created_at: u64,
+}
+
+/// A file uploaded through the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Upload {
+    pub id: String,
+    pub name: String,
+    pub hash: String,
+    pub size: u64,
+    pub content_type: String,
+    pub uploaded_at: u64,
+}
+
+/// What `gc` did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcReport {
+    pub removed: usize,
+    pub freed_bytes: u64,
+    pub kept: usize,
+    /// Unreferenced, but younger than the grace period
+    pub spared: usize,
+}
+
+/// Where blob contents are kept, addressed by hash
+pub trait BlobBackend: Send + Sync {
+    /// Store the content of `hash`, replacing any previous copy
+    fn write(&self, hash: &str, data: &[u8]) -> anyhow::Result<()>;
+    /// Content of `hash`, if stored
+    fn read(&self, hash: &str) -> anyhow::Result<Option<Vec<u8>>>;
+    fn exists(&self, hash: &str) -> anyhow::Result<bool>;
+    /// Remove the content of `hash`; not an error if it is missing
+    fn remove(&self, hash: &str) -> anyhow::Result<()>;
+}
+
+/// Blob contents as files under a local directory
+#[derive(Debug, Clone)]
+pub struct LocalBlobs {
+    root: PathBuf,
+}
+
+impl LocalBlobs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+}
+
+impl BlobBackend for LocalBlobs {
+    fn write(&self, hash: &str, data: &[u8]) -> anyhow::Result<()> {
+        let path = self.path(hash);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write to a temp file first so readers never see partial blobs; the
+        // name is unique so concurrent writes of the same blob don't mix
+        static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let tmp_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = std::fs::write(&tmp_path, data).and_then(|()| std::fs::rename(&tmp_path, &path)) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn read(&self, hash: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(hash)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn exists(&self, hash: &str) -> anyhow::Result<bool> {
+        Ok(self.path(hash).exists())
+    }
+
+    fn remove(&self, hash: &str) -> anyhow::Result<()> {
+        match std::fs::remove_file(self.path(hash)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+static BACKEND: OnceLock<Box<dyn BlobBackend>> = OnceLock::new();
+
+/// The process-wide blob backend (`PATTERN_CLOCK_BLOB_STORE`, default `local`),
+/// sealing contents when encryption is on (see `encryption`)
+pub fn backend() -> &'static dyn BlobBackend {
+    BACKEND.get_or_init(|| crate::encryption::wrap_blobs(open_backend())).as_ref()
+}
+
+fn open_backend() -> Box<dyn BlobBackend> {
+    let config = config();
+    #[cfg(feature = "s3")]
+    if config.blob_store.starts_with("s3://") {
+        match crate::s3::S3Blobs::from_config(&config) {
+            Ok(backend) => return Box::new(backend),
+            Err(e) => log_error!("[Blobs] Failed to open {}, using local blobs: {}", config.blob_store, e),
+        }
+    }
+    Box::new(LocalBlobs::new(Path::new(&config.data_dir).join(BLOB_DIR)))
+}
+
+/// Replace the process-wide blob backend; fails if it was already used or set
+pub fn set_backend(backend: Box<dyn BlobBackend>) -> Result<(), Box<dyn BlobBackend>> {
+    BACKEND.set(backend)
+}
+
+fn is_hash(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+}
+
+fn ref_key(hash: &str, owner: &str) -> String {
+    format!("{}--{}", hash, owner)
+}
+
+/// SHA-256 of `data` as lowercase hex
+pub fn hash_bytes(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Store `data` (deduplicated by content) and return its metadata
+pub fn put(data: &[u8], content_type: &str) -> anyhow::Result<BlobInfo> {
+    let hash = hash_bytes(data);
+    if let Some(mut info) = get_typed::<BlobInfo>(BLOBS_COLLECTION, &hash)? {
+        if backend().exists(&hash)? {
+            // Keep `gc` off it until the caller has added its reference
+            info.stored_at = now_millis();
+            put_typed(BLOBS_COLLECTION, &hash, &info)?;
+            return Ok(info);
+        }
+    }
+
+    backend().write(&hash, data)?;
+    let now = now_millis();
+    let info = BlobInfo {
+        hash,
+        size: data.len() as u64,
+        content_type: content_type.to_string(),
+        created_at: now,
+        stored_at: now,
+    };
+    put_typed(BLOBS_COLLECTION, &info.hash, &info)?;
+    Ok(info)
+}
+
+/// Store the file at `path`
+pub fn put_file(path: &Path, content_type: &str) -> anyhow::Result<BlobInfo> {
+    put(&std::fs::read(path)?, content_type)
+}
+
+/// Metadata of a blob
+pub fn info(hash: &str) -> anyhow::Result<Option<BlobInfo>> {
+    if !is_hash(hash) {
+        return Ok(None);
+    }
+    get_typed(BLOBS_COLLECTION, hash)
+}
+
+/// Content of a blob
+pub fn read(hash: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    if !is_hash(hash) {
+        return Ok(None);
+    }
+    backend().read(hash)
+}
+
+/// Record that `owner` uses the blob (idempotent)
+pub fn add_ref(hash: &str, owner: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(info(hash)?.is_some(), "unknown blob {}", hash);
+    let reference = BlobRef { hash: hash.to_string(), owner: owner.to_string(), created_at: now_millis() };
+    put_typed(BLOB_REFS_COLLECTION, &ref_key(hash, owner), &reference)
+}
+
+/// Drop `owner`'s reference, returning whether it existed
+pub fn remove_ref(hash: &str, owner: &str) -> anyhow::Result<bool> {
+    storage().delete(BLOB_REFS_COLLECTION, &ref_key(hash, owner))
+}
+
+/// Owners referencing a blob
+pub fn refs(hash: &str) -> anyhow::Result<Vec<BlobRef>> {
+    Ok(list_typed::<BlobRef>(BLOB_REFS_COLLECTION)?
+        .into_iter()
+        .filter(|reference| reference.hash == hash)
+        .collect())
+}
+
+/// Delete blobs without references that were last stored more than `grace` ago
+pub fn gc(grace: Duration) -> anyhow::Result<GcReport> {
+    let referenced: HashSet<String> = list_typed::<BlobRef>(BLOB_REFS_COLLECTION)?
+        .into_iter()
+        .map(|reference| reference.hash)
+        .collect();
+    let cutoff = now_millis().saturating_sub(grace.as_millis() as u64);
+
+    let mut report = GcReport::default();
+    for blob in list_typed::<BlobInfo>(BLOBS_COLLECTION)? {
+        if referenced.contains(&blob.hash) {
+            report.kept += 1;
+            continue;
+        }
+        if blob.last_stored() > cutoff {
+            report.spared += 1;
+            continue;
+        }
+        // Checked again in one update: `put` may have stored it again since
+        // the list was read, and key rotation rewrites contents under it
+        let (mut removed, mut spared) = (false, false);
+        update_typed(BLOBS_COLLECTION, &blob.hash, |current: Option<BlobInfo>| {
+            (removed, spared) = (false, false);
+            let Some(current) = current else {
+                return Ok(None);
+            };
+            if current.last_stored() > cutoff {
+                spared = true;
+                return Ok(Some(current));
+            }
+            if let Err(e) = backend().remove(&current.hash) {
+                log_warn!("[Blobs] Failed to delete blob {}: {}", current.hash, e);
+                return Ok(Some(current));
+            }
+            removed = true;
+            Ok(None)
+        })?;
+        if removed {
+            report.removed += 1;
+            report.freed_bytes += blob.size;
+        } else if spared {
+            report.spared += 1;
+        }
+    }
+    log_info!(
+        "[Blobs] GC removed {} blobs ({} bytes), kept {}, spared {} recent",
+        report.removed, report.freed_bytes, report.kept, report.spared
+    );
+    Ok(report)
+}
+
+/// Store an uploaded file and keep it referenced until the upload is deleted
+pub fn create_upload(name: &str, payload: &Payload) -> anyhow::Result<Upload> {
+    let data = match payload {
+        Payload::Text { text } => text.as_bytes(),
+        Payload::Binary { data, .. } => data.as_slice(),
+    };
+    let blob = put(data, payload.content_type())?;
+    let uploaded_at = now_millis();
+    let upload = Upload {
+        id: format!("{:013}-{}", uploaded_at, &blob.hash[..12]),
+        name: name.to_string(),
+        hash: blob.hash,
+        size: blob.size,
+        content_type: blob.content_type,
+        uploaded_at,
+    };
+    put_typed(UPLOADS_COLLECTION, &upload.id, &upload)?;
+    add_ref(&upload.hash, &format!("upload:{}", upload.id))?;
+    Ok(upload)
+}
+
+/// All uploads, newest first
+pub fn list_uploads() -> anyhow::Result<Vec<Upload>> {
+    let mut uploads: Vec<Upload> = list_typed(UPLOADS_COLLECTION)?;
+    uploads.reverse();
+    Ok(uploads)
+}
+
+/// Delete an upload and release its blob, returning whether it existed
+pub fn delete_upload(id: &str) -> anyhow::Result<bool> {
+    let Some(upload) = get_typed::<Upload>(UPLOADS_COLLECTION, id)? else {
+        return Ok(false);
+    };
+    remove_ref(&upload.hash, &format!("upload:{}", upload.id))?;
+    storage().delete(UPLOADS_COLLECTION, id)
+}
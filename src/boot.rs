@@ -0,0 +1,156 @@
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::config;
+use crate::events::publish;
+
+// ============================================================================
+// Boot Sequence
+// ============================================================================
+//
+// Everything the server path needs is otherwise started on first use, so the
+// first request pays for opening storage and spawning the agents. The server
+// runs `run()` before it binds its port instead, one step at a time:
+//
+//   storage    open the data directory or database
+//   agents     start the agent pool and its background jobs
+//   schedules  load the stored schedules and start firing them
+//   llm        load the default model into the LLM server
+//              (only with `PATTERN_CLOCK_WARM_LLM=true`)
+//
+// Each step publishes `boot.step` when it starts and when it ends, and the
+// sequence ends with `boot.ready`. A failing storage or agents step stops the
+// boot, so the server never accepts traffic it can't serve; a model that
+// doesn't load is only reported, as generation works (slowly) without it.
+// `GET /api/boot` reports the progress, and `status()` in Rust.
+
+/// How long the `llm` step waits for the model to load
+const LLM_WARM_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepState {
+    Running,
+    Done,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BootStep {
+    pub name: &'static str,
+    pub state: StepState,
+    pub duration_ms: u64,
+    /// What the step found, or why it failed or was skipped
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BootStatus {
+    /// Whether the sequence has run through
+    pub ready: bool,
+    pub steps: Vec<BootStep>,
+    pub duration_ms: u64,
+}
+
+static STATUS: Mutex<BootStatus> = Mutex::new(BootStatus { ready: false, steps: Vec::new(), duration_ms: 0 });
+
+/// Progress of the boot sequence; empty if it never ran (lazy start)
+pub fn status() -> BootStatus {
+    STATUS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+fn record(step: BootStep) {
+    publish("boot.step", json!(step));
+    let mut status = STATUS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match status.steps.iter_mut().find(|existing| existing.name == step.name) {
+        Some(existing) => *existing = step,
+        None => status.steps.push(step),
+    }
+}
+
+/// Run step `name`; `Ok` carries what it found
+async fn step<F>(name: &'static str, run: F) -> anyhow::Result<()>
+where
+    F: std::future::Future<Output = anyhow::Result<String>>,
+{
+    log_info!("[Boot] {}...", name);
+    record(BootStep { name, state: StepState::Running, duration_ms: 0, detail: None });
+    let started = Instant::now();
+    let result = run.await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(detail) => {
+            log_info!("[Boot] {} done in {} ms: {}", name, duration_ms, detail);
+            record(BootStep { name, state: StepState::Done, duration_ms, detail: Some(detail) });
+            Ok(())
+        }
+        Err(e) => {
+            log_error!("[Boot] {} failed after {} ms: {}", name, duration_ms, e);
+            record(BootStep { name, state: StepState::Failed, duration_ms, detail: Some(e.to_string()) });
+            Err(e.context(format!("boot step {} failed", name)))
+        }
+    }
+}
+
+fn skip(name: &'static str, reason: &str) {
+    record(BootStep { name, state: StepState::Skipped, duration_ms: 0, detail: Some(reason.to_string()) });
+}
+
+/// Start storage, agents and schedules, and warm the LLM if configured
+/// (once; later calls return at once)
+pub async fn run() -> anyhow::Result<()> {
+    static BOOTED: tokio::sync::Mutex<bool> = tokio::sync::Mutex::const_new(false);
+    let mut booted = BOOTED.lock().await;
+    if *booted {
+        return Ok(());
+    }
+    let started = Instant::now();
+
+    step("storage", async {
+        let storage = crate::storage::storage();
+        // Reading a collection proves the directory or database answers
+        let agents = storage.list(crate::agents::AGENT_STATE_COLLECTION)?.len();
+        Ok::<_, anyhow::Error>(format!("{} saved agent states", agents))
+    }).await?;
+
+    step("agents", async {
+        crate::agents::ensure_agents_initialized().await.map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok::<_, anyhow::Error>(format!("{} agents running", crate::agents::agent_ids().len()))
+    }).await?;
+
+    step("schedules", async {
+        crate::scheduler::ensure_started();
+        Ok::<_, anyhow::Error>(format!("{} schedules", crate::scheduler::list_schedules().len()))
+    }).await?;
+
+    if config().warm_llm {
+        let warmed = step("llm", async {
+            let provider = crate::connections::default_provider();
+            // An empty prompt makes Ollama load the model without generating anything
+            tokio::time::timeout(LLM_WARM_TIMEOUT, provider.generate(""))
+                .await
+                .map_err(|_| anyhow::anyhow!("{} did not load within {:?}", provider.model(), LLM_WARM_TIMEOUT))??;
+            Ok::<_, anyhow::Error>(format!("{} loaded", provider.model()))
+        }).await;
+        if let Err(e) = warmed {
+            log_warn!("[Boot] Continuing without a warm model: {:#}", e);
+        }
+    } else {
+        skip("llm", "PATTERN_CLOCK_WARM_LLM is off");
+    }
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let steps = {
+        let mut status = STATUS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        status.ready = true;
+        status.duration_ms = duration_ms;
+        status.steps.clone()
+    };
+    *booted = true;
+    log_info!("[Boot] Ready in {} ms", duration_ms);
+    publish("boot.ready", json!({ "duration_ms": duration_ms, "steps": steps }));
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rule_store::{get_rule, list_rules, save_rule};
+use crate::rules::PatternRule;
+use crate::schemas::{get_schema, list_schemas, set_schema, AgentSchema};
+use crate::storage::now_millis;
+
+// ============================================================================
+// Pattern Packs
+// ============================================================================
+//
+// A bundle carries pattern rules and agent payload schemas from one instance
+// to another as a single JSON or YAML document:
+//
+//     format: 1
+//     name: web-latency
+//     rules: [...]
+//     schemas: [...]
+//
+// Importing compares every item with what the target already has. Identical
+// items are left alone; for differing ones the caller picks a conflict
+// policy: skip them, overwrite them, or (rules only) import them under a new
+// id. A dry run reports what would happen without saving anything. Imported
+// rules are saved like any other edit, so they show up in the rule history.
+//
+// A few starter packs ship with the binary (`packs/*.yaml`) and are listed
+// in the gallery; installing one goes through the same import.
+
+/// Bundle format written by this version
+pub const BUNDLE_FORMAT: u32 = 1;
+
+/// Rules and schemas exported from one instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub exported_at: u64,
+    /// Version of the exporting instance
+    #[serde(default)]
+    pub source_version: String,
+    #[serde(default)]
+    pub rules: Vec<PatternRule>,
+    #[serde(default)]
+    pub schemas: Vec<AgentSchema>,
+}
+
+/// What to export; `None` means everything of that kind
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub rules: Option<Vec<String>>,
+    pub schemas: Option<Vec<u8>>,
+}
+
+/// Bundle the selected rules and schemas; unknown ids are an error
+pub fn export(name: &str, description: &str, selection: &Selection) -> anyhow::Result<Bundle> {
+    let rules = match &selection.rules {
+        None => list_rules()?,
+        Some(ids) => ids
+            .iter()
+            .map(|id| get_rule(id)?.ok_or_else(|| anyhow::anyhow!("unknown rule {}", id)))
+            .collect::<anyhow::Result<_>>()?,
+    };
+    let schemas = match &selection.schemas {
+        None => list_schemas()?,
+        Some(ids) => ids
+            .iter()
+            .map(|id| get_schema(*id)?.ok_or_else(|| anyhow::anyhow!("agent {} has no schema", id)))
+            .collect::<anyhow::Result<_>>()?,
+    };
+    Ok(Bundle {
+        format: BUNDLE_FORMAT,
+        name: name.to_string(),
+        description: description.to_string(),
+        exported_at: now_millis(),
+        source_version: env!("CARGO_PKG_VERSION").to_string(),
+        rules,
+        schemas,
+    })
+}
+
+/// Read a bundle from JSON or YAML
+pub fn parse(text: &str) -> anyhow::Result<Bundle> {
+    let bundle: Bundle = match serde_json::from_str(text) {
+        Ok(bundle) => bundle,
+        Err(_) => serde_yaml::from_str(text).map_err(|e| anyhow::anyhow!("not a JSON or YAML bundle: {}", e))?,
+    };
+    anyhow::ensure!(
+        bundle.format <= BUNDLE_FORMAT,
+        "bundle format {} is newer than this version supports ({})", bundle.format, BUNDLE_FORMAT
+    );
+    Ok(bundle)
+}
+
+/// How to treat items that already exist with different content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conflict {
+    /// Keep the existing item
+    #[default]
+    Skip,
+    /// Replace the existing item
+    Overwrite,
+    /// Import rules under a free id (`<id>-2`, `<id>-3`, ...); schemas are skipped
+    Rename,
+}
+
+impl Conflict {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "skip" => Some(Conflict::Skip),
+            "overwrite" => Some(Conflict::Overwrite),
+            "rename" => Some(Conflict::Rename),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ImportAction {
+    Created,
+    Unchanged,
+    Overwritten,
+    Skipped,
+    Renamed { to: String },
+    Failed { error: String },
+}
+
+/// Outcome for one item of the bundle
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportItem {
+    /// `rule` or `schema`
+    pub kind: &'static str,
+    pub id: String,
+    #[serde(flatten)]
+    pub action: ImportAction,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub bundle: String,
+    pub dry_run: bool,
+    pub items: Vec<ImportItem>,
+}
+
+/// Import a bundle, resolving conflicts with `conflict`; `author` is recorded in the rule history
+pub fn import(bundle: &Bundle, conflict: Conflict, dry_run: bool, author: &str) -> anyhow::Result<ImportReport> {
+    let mut items = Vec::new();
+
+    for rule in &bundle.rules {
+        let existing = get_rule(&rule.id)?;
+        let (action, target) = match existing {
+            None => (ImportAction::Created, Some(rule.clone())),
+            Some(existing) if existing == *rule => (ImportAction::Unchanged, None),
+            Some(_) => match conflict {
+                Conflict::Skip => (ImportAction::Skipped, None),
+                Conflict::Overwrite => (ImportAction::Overwritten, Some(rule.clone())),
+                Conflict::Rename => {
+                    let id = free_rule_id(&rule.id)?;
+                    (ImportAction::Renamed { to: id.clone() }, Some(PatternRule { id, ..rule.clone() }))
+                }
+            },
+        };
+        let action = match (target, dry_run) {
+            (Some(target), false) => match save_rule(&target, author) {
+                Ok(()) => action,
+                Err(e) => ImportAction::Failed { error: e.to_string() },
+            },
+            (Some(target), true) => match target.validate() {
+                Ok(()) => action,
+                Err(error) => ImportAction::Failed { error },
+            },
+            (None, _) => action,
+        };
+        items.push(ImportItem { kind: "rule", id: rule.id.clone(), action });
+    }
+
+    for schema in &bundle.schemas {
+        let action = match get_schema(schema.agent_id)? {
+            None => ImportAction::Created,
+            Some(existing) if existing.schema == schema.schema => ImportAction::Unchanged,
+            Some(_) if conflict == Conflict::Overwrite => ImportAction::Overwritten,
+            Some(_) => ImportAction::Skipped,
+        };
+        let action = match action {
+            ImportAction::Created | ImportAction::Overwritten if !dry_run => {
+                match set_schema(schema.agent_id, schema.schema.clone()) {
+                    Ok(_) => action,
+                    Err(e) => ImportAction::Failed { error: e.to_string() },
+                }
+            }
+            action => action,
+        };
+        items.push(ImportItem { kind: "schema", id: schema.agent_id.to_string(), action });
+    }
+
+    if !dry_run {
+        log_info!("[Bundles] Imported bundle {} ({} items) for {}", bundle.name, items.len(), author);
+    }
+    Ok(ImportReport { bundle: bundle.name.clone(), dry_run, items })
+}
+
+/// First of `<id>-2`, `<id>-3`, ... not taken by a saved rule
+fn free_rule_id(id: &str) -> anyhow::Result<String> {
+    for n in 2.. {
+        let candidate = format!("{}-{}", id, n);
+        if get_rule(&candidate)?.is_none() {
+            return Ok(candidate);
+        }
+    }
+    unreachable!()
+}
+
+// ----------------------------------------------------------------------------
+// Gallery
+// ----------------------------------------------------------------------------
+
+/// Built-in packs: gallery id and bundle source
+const GALLERY: &[(&str, &str)] = &[
+    ("server-monitoring", include_str!("../packs/server-monitoring.yaml")),
+    ("llm-watchdog", include_str!("../packs/llm-watchdog.yaml")),
+    ("forecasting-starter", include_str!("../packs/forecasting-starter.yaml")),
+];
+
+/// A built-in pack as listed in the gallery
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryPack {
+    pub id: &'static str,
+    pub name: String,
+    pub description: String,
+    /// Ids of the pack's rules
+    pub rules: Vec<String>,
+    /// Agent ids the pack has schemas for
+    pub schemas: Vec<u8>,
+}
+
+/// The built-in pack with gallery id `id`
+pub fn gallery_bundle(id: &str) -> anyhow::Result<Bundle> {
+    let (_, source) = GALLERY
+        .iter()
+        .find(|(pack, _)| *pack == id)
+        .ok_or_else(|| anyhow::anyhow!("unknown pack {}", id))?;
+    parse(source)
+}
+
+/// All built-in packs
+pub fn gallery() -> Vec<GalleryPack> {
+    GALLERY
+        .iter()
+        .filter_map(|(id, _)| match gallery_bundle(id) {
+            Ok(bundle) => Some(GalleryPack {
+                id,
+                name: bundle.name,
+                description: bundle.description,
+                rules: bundle.rules.into_iter().map(|rule| rule.id).collect(),
+                schemas: bundle.schemas.into_iter().map(|schema| schema.agent_id).collect(),
+            }),
+            Err(e) => {
+                log_error!("[Bundles] Built-in pack {} is invalid: {}", id, e);
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use crate::agents::{get_agent, AgentMessage};
+use crate::events::publish;
+
+// ============================================================================
+// Chaos Mode (feature = "chaos")
+// ============================================================================
+//
+// Test-only fault injection for exercising supervision, retries and the
+// self-monitor end-to-end. Never enable the feature in production builds.
+
+/// A fault to inject
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+pub enum ChaosFault {
+    /// Send an agent a `ChaosPanic` message, which it panics handling once
+    /// the work queued ahead of it is done
+    AgentPanic { agent_id: u8 },
+    /// Make the next `count` LLM requests hang for `delay_ms` and then fail
+    LlmTimeout { count: u32, delay_ms: u64 },
+    /// Flood the internal event bus so subscribers observe `Lagged`
+    /// (at most `MAX_BROADCAST_LAG_EVENTS`)
+    BroadcastLag { events: usize },
+}
+
+/// Filler events one `BroadcastLag` may publish, far past the bus capacity
+pub const MAX_BROADCAST_LAG_EVENTS: usize = 100_000;
+
+static LLM_TIMEOUTS: AtomicU32 = AtomicU32::new(0);
+static LLM_TIMEOUT_DELAY_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Inject a fault; returns a short description of what was done
+pub fn inject(fault: ChaosFault) -> anyhow::Result<String> {
+    if let ChaosFault::BroadcastLag { events } = fault {
+        anyhow::ensure!(
+            events <= MAX_BROADCAST_LAG_EVENTS,
+            "broadcast lag is limited to {} events, got {}", MAX_BROADCAST_LAG_EVENTS, events
+        );
+    }
+    publish("chaos.injected", json!(fault));
+    match fault {
+        ChaosFault::AgentPanic { agent_id } => {
+            let actor_ref = get_agent(agent_id)
+                .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+            actor_ref.send_message(AgentMessage::ChaosPanic)
+                .map_err(|e| anyhow::anyhow!("failed to reach Agent{}: {}", agent_id, e))?;
+            Ok(format!("Agent{} will panic when it handles the fault", agent_id))
+        }
+        ChaosFault::LlmTimeout { count, delay_ms } => {
+            LLM_TIMEOUT_DELAY_MS.store(delay_ms, Ordering::SeqCst);
+            LLM_TIMEOUTS.store(count, Ordering::SeqCst);
+            Ok(format!("Next {} LLM requests will time out after {} ms", count, delay_ms))
+        }
+        ChaosFault::BroadcastLag { events } => {
+            for i in 0..events {
+                publish("chaos.filler", json!({ "seq": i }));
+            }
+            Ok(format!("Published {} filler events", events))
+        }
+    }
+}
+
+/// Consume one pending LLM timeout, returning the delay to wait before failing
+pub fn take_llm_timeout() -> Option<std::time::Duration> {
+    LLM_TIMEOUTS
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .ok()
+        .map(|_| std::time::Duration::from_millis(LLM_TIMEOUT_DELAY_MS.load(Ordering::SeqCst)))
+}
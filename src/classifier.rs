@@ -0,0 +1,237 @@
+use burn::backend::{wgpu::Wgpu, NdArray};
+use burn::module::Module;
+use burn::nn::{Embedding, EmbeddingConfig, Linear, LinearConfig};
+use burn::record::CompactRecorder;
+use burn::tensor::activation::softmax;
+use burn::tensor::backend::Backend;
+use burn::tensor::{Int, Tensor, TensorData};
+use serde::Serialize;
+use std::sync::{Mutex, PoisonError};
+
+use crate::lstm::{Lstm, LstmConfig};
+
+// ============================================================================
+// Text Classifier (Embedding -> LSTM -> Linear head)
+// ============================================================================
+
+/// Default location of trained classifier weights (see `registry`)
+pub const CLASSIFIER_WEIGHTS_PATH: &str = "models/classifier";
+
+/// Configuration for the text classifier
+#[derive(Debug, Clone)]
+pub struct TextClassifierConfig {
+    /// Number of hash buckets used by the tokenizer
+    pub vocab_size: usize,
+    /// Token embedding dimension
+    pub embedding_size: usize,
+    /// LSTM hidden state dimension
+    pub hidden_size: usize,
+    /// Number of stacked LSTM layers
+    pub num_layers: usize,
+    /// Output class names, in logit order
+    pub labels: Vec<String>,
+}
+
+impl Default for TextClassifierConfig {
+    fn default() -> Self {
+        Self {
+            vocab_size: 4096,
+            embedding_size: 64,
+            hidden_size: 128,
+            num_layers: 1,
+            // The classes the labeling view offers, so labeled events train it
+            labels: crate::dataset::DEFAULT_LABELS.iter().map(|label| label.to_string()).collect(),
+        }
+    }
+}
+
+impl TextClassifierConfig {
+    /// Check the settings before building a model or tokenizing with them
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.vocab_size >= 2, "vocab_size must be at least 2 (bucket 0 is padding), got {}", self.vocab_size);
+        anyhow::ensure!(!self.labels.is_empty(), "labels must not be empty");
+        Ok(())
+    }
+}
+
+/// Predicted label with its softmax probability
+#[derive(Debug, Clone, Serialize)]
+pub struct Classification {
+    pub label: String,
+    pub confidence: f32,
+    /// False when no trained weights exist and an untrained model answered
+    pub trained: bool,
+}
+
+/// Split text into lowercase words and hash each into a vocabulary bucket
+///
+/// Bucket 0 is reserved for padding/empty input. Words are hashed with
+/// FNV-1a, which stays the same across Rust releases, so trained weights and
+/// calibration data keep matching their tokens.
+///
+/// # Panics
+/// If `vocab_size` is below 2 (see `TextClassifierConfig::validate`).
+pub fn tokenize(text: &str, vocab_size: usize) -> Vec<usize> {
+    assert!(vocab_size >= 2, "vocab_size must be at least 2, got {}", vocab_size);
+    let tokens: Vec<usize> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| 1 + (fnv1a(word.to_lowercase().as_bytes()) % (vocab_size as u64 - 1)) as usize)
+        .collect();
+
+    if tokens.is_empty() { vec![0] } else { tokens }
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Sequence classifier over hashed tokens
+#[derive(Module, Debug)]
+pub struct TextClassifier<B: Backend> {
+    embedding: Embedding<B>,
+    lstm: Lstm<B>,
+    head: Linear<B>,
+}
+
+impl<B: Backend> TextClassifier<B> {
+    /// Create a new (untrained) classifier
+    ///
+    /// # Panics
+    /// If `config` is invalid (see `TextClassifierConfig::validate`).
+    pub fn new(config: &TextClassifierConfig, device: &B::Device) -> Self {
+        if let Err(e) = config.validate() {
+            panic!("invalid classifier config: {}", e);
+        }
+        let lstm_config = LstmConfig {
+            input_size: config.embedding_size,
+            hidden_size: config.hidden_size,
+            num_layers: config.num_layers,
+            ..LstmConfig::default()
+        };
+
+        Self {
+            embedding: EmbeddingConfig::new(config.vocab_size, config.embedding_size).init(device),
+            lstm: Lstm::new(lstm_config, device),
+            head: LinearConfig::new(config.hidden_size, config.labels.len()).init(device),
+        }
+    }
+
+    /// Forward pass
+    ///
+    /// # Arguments
+    /// * `tokens` - Token ids of shape [batch_size, seq_length]
+    ///
+    /// # Returns
+    /// * Logits of shape [batch_size, num_labels]
+    pub fn forward(&self, tokens: Tensor<B, 2, Int>) -> Tensor<B, 2> {
+        let embedded = self.embedding.forward(tokens);
+        let (_, (hidden, _)) = self.lstm.forward(embedded, None);
+        self.head.forward(hidden)
+    }
+}
+
+/// Classifier instance plus the config it was built with
+struct LoadedClassifier<B: Backend> {
+    config: TextClassifierConfig,
+    model: TextClassifier<B>,
+    /// False when no weights were saved yet and the model is untrained
+    trained: bool,
+}
+
+/// A pooled classifier, or why its saved weights could not be loaded
+type PooledClassifier<B> = Mutex<Result<LoadedClassifier<B>, String>>;
+
+/// Model pool keys of the GPU classifier and of the CPU copy (used while the
+/// GPU watchdog has fallen back)
+const POOL_KEY: &str = "classifier";
+const CPU_POOL_KEY: &str = "classifier@cpu";
+
+/// Load the saved weights; an untrained model only when none were saved yet
+///
+/// Weights that exist but don't load (a corrupt file, or one saved with other
+/// settings) are an error, not a silent fallback.
+fn load_classifier<B: Backend>() -> PooledClassifier<B> {
+    let device = Default::default();
+    let config = TextClassifierConfig::default();
+    let model = TextClassifier::<B>::new(&config, &device);
+
+    // `CompactRecorder` adds the extension
+    if !std::path::Path::new(&format!("{}.mpk", CLASSIFIER_WEIGHTS_PATH)).exists() {
+        log_warn!("[Classifier] No trained weights at {}, using untrained model", CLASSIFIER_WEIGHTS_PATH);
+        return Mutex::new(Ok(LoadedClassifier { config, model, trained: false }));
+    }
+    let loaded = match model.load_file(CLASSIFIER_WEIGHTS_PATH, &CompactRecorder::new(), &device) {
+        Ok(model) => {
+            log_info!("[Classifier] Loaded weights from {}", CLASSIFIER_WEIGHTS_PATH);
+            Ok(LoadedClassifier { config, model, trained: true })
+        }
+        Err(e) => {
+            log_error!("[Classifier] Failed to load weights from {}: {:?}", CLASSIFIER_WEIGHTS_PATH, e);
+            Err(format!("failed to load classifier weights from {}: {:?}", CLASSIFIER_WEIGHTS_PATH, e))
+        }
+    };
+    Mutex::new(loaded)
+}
+
+/// The CPU copy, from the int8 weights written by `quantization` when there are any
+fn load_cpu_classifier() -> PooledClassifier<NdArray> {
+    let device = Default::default();
+    let config = TextClassifierConfig::default();
+    let model = TextClassifier::<NdArray>::new(&config, &device);
+    match crate::quantization::load_quantized(model, CLASSIFIER_WEIGHTS_PATH, &device) {
+        Some(model) => Mutex::new(Ok(LoadedClassifier { config, model, trained: true })),
+        None => load_classifier::<NdArray>(),
+    }
+}
+
+/// Classify a text with the shared on-device classifier
+///
+/// Runs on the GPU unless the GPU watchdog has fallen back to the CPU. Fails
+/// when saved weights exist but could not be loaded; without saved weights an
+/// untrained model answers and the result says so (`trained: false`).
+pub fn classify_text(text: &str) -> anyhow::Result<Classification> {
+    if crate::gpu::cpu_fallback() {
+        let classifier = crate::model_pool::get_or_load(CPU_POOL_KEY, load_cpu_classifier);
+        // A panic in an earlier classification leaves the model usable
+        let classifier = classifier.lock().unwrap_or_else(PoisonError::into_inner);
+        return classify_loaded(&classifier, text);
+    }
+    let classifier = crate::model_pool::get_or_load(POOL_KEY, load_classifier::<Wgpu>);
+    let classifier = classifier.lock().unwrap_or_else(PoisonError::into_inner);
+    classify_loaded(&classifier, text)
+}
+
+fn classify_loaded<B: Backend>(classifier: &Result<LoadedClassifier<B>, String>, text: &str) -> anyhow::Result<Classification> {
+    match classifier {
+        Ok(classifier) => Ok(classify_with(classifier, text)),
+        Err(e) => Err(anyhow::anyhow!("{}", e)),
+    }
+}
+
+fn classify_with<B: Backend>(classifier: &LoadedClassifier<B>, text: &str) -> Classification {
+    let device = Default::default();
+
+    let tokens: Vec<i32> = tokenize(text, classifier.config.vocab_size)
+        .into_iter()
+        .map(|token| token as i32)
+        .collect();
+    let seq_len = tokens.len();
+    let input = Tensor::<B, 2, Int>::from_data(TensorData::new(tokens, [1, seq_len]), &device);
+
+    let probabilities = softmax(classifier.model.forward(input), 1);
+    let probabilities: Vec<f32> = probabilities.into_data().to_vec().unwrap_or_default();
+
+    let (best, confidence) = probabilities
+        .iter()
+        .copied()
+        .enumerate()
+        .fold((0, 0.0), |best, (i, p)| if p > best.1 { (i, p) } else { best });
+
+    Classification {
+        label: classifier.config.labels[best].clone(),
+        confidence,
+        trained: classifier.trained,
+    }
+}
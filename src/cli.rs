@@ -0,0 +1,222 @@
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use serde_json::Value;
+
+use crate::api_client::{default_base_url, ApiClient};
+use crate::client::ClientCommand;
+
+// ============================================================================
+// Command Line
+// ============================================================================
+//
+// Subcommands that run instead of the app:
+//
+//     pattern-clock doctor                     startup diagnostics
+//     pattern-clock console [URL]              admin REPL
+//     pattern-clock top [URL]                  terminal dashboard (feature "tui")
+//     pattern-clock client <command>           scriptable API client
+//     pattern-clock blobs gc                   delete unreferenced blobs
+//     pattern-clock encryption rotate          re-wrap stored data under the current key
+//     pattern-clock encryption generate-key    print a new encryption key
+//     pattern-clock completions <shell>        bash / zsh / fish / ... completions
+//
+// All of them accept `--url` and `--output table|json|yaml` (the
+// interactive console and dashboard ignore the latter).
+// Without a subcommand the app launches as before (`--demo` is handled
+// in `main`).
+
+/// Longest cell printed in table output
+const MAX_CELL: usize = 60;
+
+/// First arguments handled here rather than by the app launcher
+const SUBCOMMANDS: &[&str] = &["doctor", "console", "top", "client", "blobs", "encryption", "completions", "help", "--help", "-h", "--version", "-V"];
+
+#[derive(Debug, Parser)]
+#[command(name = "pattern-clock", version, about = "Pattern detection over agent activity and time series")]
+pub struct Cli {
+    /// Base URL of a running instance (defaults to http://$IP:$PORT)
+    #[arg(long, global = true, env = "PATTERN_CLOCK_URL")]
+    pub url: Option<String>,
+    /// Output format
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputMode::Table)]
+    pub output: OutputMode,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputMode {
+    Table,
+    Json,
+    Yaml,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Check configuration, GPU, Ollama, storage and the server port; exits 1 on failures
+    Doctor,
+    /// Interactive admin console against a running instance
+    Console {
+        /// Same as `--url`
+        #[arg(value_name = "URL")]
+        address: Option<String>,
+    },
+    /// Live terminal dashboard of a running instance
+    Top {
+        /// Same as `--url`
+        #[arg(value_name = "URL")]
+        address: Option<String>,
+    },
+    /// Scriptable client for a running instance
+    #[command(subcommand)]
+    Client(ClientCommand),
+    /// Maintain the local blob store (`<data_dir>/blobs`)
+    #[command(subcommand)]
+    Blobs(BlobsCommand),
+    /// Manage encryption at rest (`PATTERN_CLOCK_ENCRYPTION_KEY`)
+    #[command(subcommand)]
+    Encryption(EncryptionCommand),
+    /// Print a shell completion script
+    Completions {
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BlobsCommand {
+    /// Delete blobs that nothing references
+    Gc {
+        /// Spare unreferenced blobs younger than this many seconds
+        #[arg(long, default_value_t = crate::blobs::DEFAULT_GC_GRACE.as_secs())]
+        grace_secs: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EncryptionCommand {
+    /// Re-wrap every data key under the current key and seal plain documents
+    Rotate,
+    /// Print a new random key for `PATTERN_CLOCK_ENCRYPTION_KEY`
+    GenerateKey,
+}
+
+/// Run the subcommand in the process arguments; `None` when the app should launch instead
+pub fn run_subcommand() -> Option<i32> {
+    let first = std::env::args().nth(1)?;
+    if !SUBCOMMANDS.contains(&first.as_str()) {
+        return None;
+    }
+    Some(match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    })
+}
+
+/// Run a parsed command line; returns the exit code
+pub fn run(cli: Cli) -> anyhow::Result<i32> {
+    let runtime = crate::runtime::background_runtime();
+    match cli.command {
+        Command::Doctor => {
+            let report = runtime.block_on(crate::doctor::run_diagnostics());
+            match cli.output {
+                OutputMode::Table => println!("{}", report),
+                output => print(output, &serde_json::to_value(&report)?, &[]),
+            }
+            Ok(if report.is_healthy() { 0 } else { 1 })
+        }
+        Command::Console { address } => {
+            crate::console::run(&address.or(cli.url).unwrap_or_else(default_base_url))?;
+            Ok(0)
+        }
+        #[cfg(feature = "tui")]
+        Command::Top { address } => {
+            crate::top::run(&address.or(cli.url).unwrap_or_else(default_base_url))?;
+            Ok(0)
+        }
+        #[cfg(not(feature = "tui"))]
+        Command::Top { .. } => anyhow::bail!("`top` needs a build with `--features tui`"),
+        Command::Client(command) => {
+            let client = ApiClient::new(cli.url.unwrap_or_else(default_base_url));
+            runtime.block_on(crate::client::run(&client, cli.output, command))?;
+            Ok(0)
+        }
+        Command::Blobs(BlobsCommand::Gc { grace_secs }) => {
+            let report = crate::blobs::gc(std::time::Duration::from_secs(grace_secs))?;
+            print(cli.output, &serde_json::to_value(&report)?, &["removed", "freed_bytes", "kept", "spared"]);
+            Ok(0)
+        }
+        Command::Encryption(EncryptionCommand::Rotate) => {
+            let report = crate::encryption::rotate()?;
+            match cli.output {
+                OutputMode::Table => {
+                    println!("key {}", report.key_id);
+                    print(cli.output, &serde_json::to_value(&report.collections)?, &["collection", "rewrapped", "encrypted", "current", "failed"]);
+                }
+                output => print(output, &serde_json::to_value(&report)?, &[]),
+            }
+            Ok(if report.collections.iter().any(|collection| collection.failed > 0) { 1 } else { 0 })
+        }
+        Command::Encryption(EncryptionCommand::GenerateKey) => {
+            println!("{}", crate::encryption::generate_key());
+            Ok(0)
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "pattern-clock", &mut std::io::stdout());
+            Ok(0)
+        }
+    }
+}
+
+/// Print `value` in the chosen format; tables show `columns` of each object in an array
+pub fn print(output: OutputMode, value: &Value, columns: &[&str]) {
+    match output {
+        OutputMode::Json => println!("{}", serde_json::to_string_pretty(value).unwrap_or_default()),
+        OutputMode::Yaml => print!("{}", serde_yaml::to_string(value).unwrap_or_default()),
+        OutputMode::Table => match value {
+            Value::Array(rows) => print_table(rows, columns, true),
+            Value::Object(_) => print_table(std::slice::from_ref(value), columns, true),
+            Value::Null => println!("ok"),
+            Value::String(text) => println!("{}", text),
+            other => println!("{}", other),
+        },
+    }
+}
+
+fn cell(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    };
+    let text = text.replace('\n', " ");
+    if text.chars().count() > MAX_CELL {
+        format!("{}...", text.chars().take(MAX_CELL - 3).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Left-aligned columns sized to their widest cell
+pub(crate) fn print_table(rows: &[Value], columns: &[&str], header: bool) {
+    let cells: Vec<Vec<String>> = rows.iter().map(|row| columns.iter().map(|c| cell(&row[*c])).collect()).collect();
+    let widths: Vec<usize> = columns.iter().enumerate()
+        .map(|(i, c)| cells.iter().map(|row| row[i].chars().count()).chain([c.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |values: Vec<String>| {
+        values.iter().zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    if header {
+        println!("{}", line(columns.iter().map(|c| c.to_uppercase()).collect()));
+    }
+    for row in cells {
+        println!("{}", line(row));
+    }
+}
@@ -0,0 +1,171 @@
+use clap::{Args, Subcommand};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::api_client::ApiClient;
+use crate::cli::{print, print_table, OutputMode};
+
+// ============================================================================
+// CLI Client (`pattern-clock client ...`)
+// ============================================================================
+//
+// Non-interactive counterpart of the console for shell scripts:
+//
+//     pattern-clock client agent send 2 "disk full on /var"
+//     pattern-clock client events tail --follow --output json | jq .data
+//     pattern-clock client models list --loaded
+//     pattern-clock client forecast run cpu.usage --horizon 30
+//
+// `--output` applies as for every subcommand (see `cli.rs`); `events tail`
+// prints one JSON object per line, or one YAML document per event.
+
+/// Interval between polls of `events tail --follow`
+const FOLLOW_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Subcommand)]
+pub enum ClientCommand {
+    /// List agents or send them messages
+    #[command(subcommand)]
+    Agent(AgentCommand),
+    /// Read stored agent events
+    #[command(subcommand)]
+    Events(EventsCommand),
+    /// Inspect registered and loaded models
+    #[command(subcommand)]
+    Models(ModelsCommand),
+    /// Forecast time series
+    #[command(subcommand)]
+    Forecast(ForecastCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AgentCommand {
+    /// Ids of the running agents
+    List,
+    /// Queue a message for an agent
+    Send {
+        agent: u8,
+        message: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EventsCommand {
+    /// Most recent events, oldest first
+    Tail(TailArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TailArgs {
+    /// Number of events to print first
+    #[arg(long, short = 'n', default_value_t = 20)]
+    pub limit: usize,
+    /// Keep printing new events until interrupted
+    #[arg(long, short = 'f')]
+    pub follow: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ModelsCommand {
+    /// Registered models, or with `--loaded` the ones warm in the model pool
+    List {
+        #[arg(long)]
+        loaded: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ForecastCommand {
+    /// Forecast the next values of a stored series
+    Run {
+        series: String,
+        #[arg(long, default_value_t = 10)]
+        horizon: usize,
+    },
+}
+
+/// Run one client command against `client`
+pub async fn run(client: &ApiClient, output: OutputMode, command: ClientCommand) -> anyhow::Result<()> {
+    match command {
+        ClientCommand::Agent(AgentCommand::List) => {
+            let agents = client.get("/api/agents").await?;
+            let rows: Vec<Value> = agents.as_array().into_iter().flatten()
+                .map(|id| json!({ "agent": id, "name": format!("Agent{}", id) }))
+                .collect();
+            print(output, &Value::Array(rows), &["agent", "name"]);
+        }
+        ClientCommand::Agent(AgentCommand::Send { agent, message }) => {
+            let reply = client.post(&format!("/api/agents/{}/process", agent), json!({ "data": message })).await?;
+            print(output, &json!({ "agent": agent, "reply": reply }), &["agent", "reply"]);
+        }
+        ClientCommand::Events(EventsCommand::Tail(tail)) => tail_events(client, output, tail).await?,
+        ClientCommand::Models(ModelsCommand::List { loaded: false }) => {
+            let models = client.get("/api/models").await?;
+            print(output, &models, &["name", "kind", "description", "weights_path"]);
+        }
+        ClientCommand::Models(ModelsCommand::List { loaded: true }) => {
+            let models = client.get("/api/models/pool").await?;
+            print(output, &models, &["key", "loaded_at_ms", "last_used_ms", "load_ms"]);
+        }
+        ClientCommand::Forecast(ForecastCommand::Run { series, horizon }) => {
+            let values = client.get(&format!("/api/timeseries/{}/forecast?horizon={}", series, horizon)).await?;
+            match output {
+                OutputMode::Json | OutputMode::Yaml => {
+                    print(output, &json!({ "series": series, "horizon": horizon, "values": values }), &[]);
+                }
+                OutputMode::Table => {
+                    let rows: Vec<Value> = values.as_array().into_iter().flatten().enumerate()
+                        .map(|(step, value)| json!({ "step": step + 1, "value": value }))
+                        .collect();
+                    print(output, &Value::Array(rows), &["step", "value"]);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the last `limit` stored events, then (with `--follow`) newer ones as they are stored
+async fn tail_events(client: &ApiClient, output: OutputMode, tail: TailArgs) -> anyhow::Result<()> {
+    const COLUMNS: &[&str] = &["id", "agent_id", "data", "keywords"];
+    let mut last_id = String::new();
+    let mut header = output == OutputMode::Table;
+    let mut limit = tail.limit;
+    loop {
+        let events = client.get(&format!("/api/events?limit={}", limit)).await?;
+        // Newest first on the wire; print oldest first, skipping what was already shown
+        let mut fresh: Vec<&Value> = events.as_array().into_iter().flatten()
+            .filter(|event| event["id"].as_str().is_some_and(|id| id > last_id.as_str()))
+            .collect();
+        fresh.reverse();
+        if let Some(id) = fresh.last().and_then(|event| event["id"].as_str()) {
+            last_id = id.to_string();
+        }
+        match output {
+            OutputMode::Json => {
+                for event in &fresh {
+                    println!("{}", event);
+                }
+            }
+            OutputMode::Yaml => {
+                for event in &fresh {
+                    print!("---\n{}", serde_yaml::to_string(event).unwrap_or_default());
+                }
+            }
+            OutputMode::Table => {
+                let rows: Vec<Value> = fresh.into_iter().cloned().collect();
+                if header || !rows.is_empty() {
+                    print_table(&rows, COLUMNS, header);
+                    header = false;
+                }
+            }
+        }
+
+        if !tail.follow {
+            return Ok(());
+        }
+        // Enough to catch up with a busy second without re-reading the backlog
+        limit = tail.limit.max(100);
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+    }
+}
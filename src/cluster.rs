@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use std::sync::OnceLock;
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use std::time::Duration;
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use ractor::rpc::CallResult;
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use ractor_cluster::{NodeServer, NodeServerMessage, RactorClusterMessage};
+
+use crate::config::config;
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use crate::deadline::Deadline;
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+use crate::mailbox::{Priority, SendError};
+
+// ============================================================================
+// Agent Cluster
+// ============================================================================
+//
+// With `PATTERN_CLOCK_CLUSTER_PORT` set (feature "cluster"), this process is
+// a `ractor_cluster` node: it accepts connections from other nodes on that
+// port and connects to each of `PATTERN_CLOCK_CLUSTER_PEERS` at startup,
+// retrying until the peer answers. Nodes authenticate each other with
+// `PATTERN_CLOCK_CLUSTER_COOKIE`.
+//
+// Agent messages carry reply ports, instants and payloads that cannot cross
+// the network, so remote nodes don't talk to agents directly. Each node
+// starts a gateway actor that joins the `GATEWAY_GROUP` process group; ractor
+// shares the group's members with every connected node, so the group is the
+// registry of remote actor refs. A gateway takes serialized work for one of
+// its node's agents, queues it locally and answers with the outcome.
+//
+// `cluster_routing` decides where `/api/agents/:id/process` sends data:
+//
+//   local        always the agent on this node (the default)
+//   round_robin  this node and every peer with a gateway in turn; the peer
+//                queues the data on its own agent with the same id
+//
+// A peer that goes away drops out of the rotation as soon as its session
+// closes. Nodes only reconnect to a restarted peer by being in its
+// `cluster_peers`, so list every node on every node.
+
+/// Process group of the gateways of all nodes
+pub const GATEWAY_GROUP: &str = "pattern_clock.gateways";
+
+/// How long a peer has to answer a forwarded message
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest wait between attempts to reach a peer
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Which node handles data sent through `/api/agents/:id/process`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Routing {
+    /// The agent on this node
+    #[default]
+    Local,
+    /// This node and every connected peer in turn
+    RoundRobin,
+}
+
+impl Routing {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(Routing::Local),
+            "round_robin" => Some(Routing::RoundRobin),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Routing::Local => "local",
+            Routing::RoundRobin => "round_robin",
+        }
+    }
+}
+
+/// A connected node
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterNode {
+    pub node_id: u64,
+    pub name: String,
+    pub address: String,
+    /// Whether the peer connected to this node rather than the other way round
+    pub inbound: bool,
+    /// Whether the peer's gateway is known, so data can be routed to it
+    pub routable: bool,
+}
+
+/// This node and its peers, as reported by `GET /api/cluster/nodes`
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    /// Whether this process is a cluster node
+    pub enabled: bool,
+    pub name: String,
+    pub port: Option<u16>,
+    pub routing: Routing,
+    pub peers: Vec<ClusterNode>,
+}
+
+/// Why a node did not queue forwarded data; mirrors the HTTP error of a local send
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refusal {
+    pub code: u16,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+impl Refusal {
+    fn new(message: impl Into<String>) -> Self {
+        Refusal { code: 500, message: message.into(), details: None }
+    }
+}
+
+/// Name of this node in the cluster and in `/api/cluster/nodes`
+pub fn node_name() -> String {
+    crate::leader::instance_id()
+}
+
+/// This node and the peers it is connected to
+pub async fn status() -> ClusterStatus {
+    let config = config();
+    ClusterStatus {
+        enabled: server().is_some(),
+        name: node_name(),
+        port: config.cluster_port,
+        routing: config.cluster_routing,
+        peers: peers().await,
+    }
+}
+
+/// Join the cluster (once; requires a Tokio runtime; no-op without a cluster port)
+pub fn ensure_started() {
+    #[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+    {
+        static STARTED: OnceLock<()> = OnceLock::new();
+        STARTED.get_or_init(|| {
+            let config = config();
+            let Some(port) = config.cluster_port else {
+                return;
+            };
+            let cookie = config.cluster_cookie.as_ref().map(|cookie| cookie.expose().to_string()).unwrap_or_default();
+            let peers = config.cluster_peers.clone();
+            tokio::spawn(async move {
+                if let Err(e) = start(port, cookie, peers).await {
+                    log_error!("[Cluster] Failed to start node on port {}: {}", port, e);
+                }
+            });
+        });
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Node server and gateway
+// ----------------------------------------------------------------------------
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+static SERVER: OnceLock<ActorRef<NodeServerMessage>> = OnceLock::new();
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+fn server() -> Option<&'static ActorRef<NodeServerMessage>> {
+    SERVER.get()
+}
+
+#[cfg(not(all(feature = "cluster", not(target_arch = "wasm32"))))]
+fn server() -> Option<()> {
+    None
+}
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+async fn start(port: u16, cookie: String, peers: Vec<String>) -> anyhow::Result<()> {
+    let node = NodeServer::new(port, cookie, node_name(), node_name(), None, None);
+    let (server, _handle) = Actor::spawn(None, node, ())
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    Actor::spawn(None, Gateway, ())
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let _ = SERVER.set(server.clone());
+    log_info!("[Cluster] Node {} listening on port {}", node_name(), port);
+
+    for peer in peers {
+        tokio::spawn(connect(server.clone(), peer));
+    }
+    Ok(())
+}
+
+/// Connect to `peer`, retrying with backoff until it answers
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+async fn connect(server: ActorRef<NodeServerMessage>, peer: String) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match ractor_cluster::client_connect(&server, peer.as_str()).await {
+            Ok(()) => {
+                log_info!("[Cluster] Connected to {}", peer);
+                crate::events::publish("cluster.connected", serde_json::json!({ "peer": peer }));
+                return;
+            }
+            Err(e) => {
+                log_warn!("[Cluster] Failed to connect to {}, retrying in {:?}: {}", peer, backoff, e);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+async fn peers() -> Vec<ClusterNode> {
+    let Some(server) = server() else {
+        return Vec::new();
+    };
+    let sessions = match server.call(NodeServerMessage::GetSessions, Some(FORWARD_TIMEOUT)).await {
+        Ok(CallResult::Success(sessions)) => sessions,
+        Ok(_) => {
+            log_warn!("[Cluster] Node server did not list its sessions");
+            return Vec::new();
+        }
+        Err(e) => {
+            log_warn!("[Cluster] Failed to reach the node server: {}", e);
+            return Vec::new();
+        }
+    };
+    let gateways = gateways();
+    let mut nodes: Vec<ClusterNode> = sessions
+        .into_iter()
+        .map(|(node_id, session)| ClusterNode {
+            node_id,
+            name: session.peer_name.as_ref().map(|peer| peer.name.clone()).unwrap_or_default(),
+            address: session.peer_addr.to_string(),
+            inbound: session.is_server,
+            routable: gateways.iter().any(|gateway| gateway.node_id == node_id),
+        })
+        .collect();
+    nodes.sort_by_key(|node| node.node_id);
+    nodes
+}
+
+#[cfg(not(all(feature = "cluster", not(target_arch = "wasm32"))))]
+async fn peers() -> Vec<ClusterNode> {
+    Vec::new()
+}
+
+/// Messages between nodes; the only one a node sends to a peer's gateway
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+#[derive(RactorClusterMessage)]
+pub enum GatewayMessage {
+    /// A JSON `RemoteWork`, answered with a JSON `Result<String, Refusal>`
+    #[rpc]
+    Process(String, RpcReplyPort<String>),
+}
+
+/// Data for one of the receiving node's agents
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+#[derive(Serialize, Deserialize)]
+struct RemoteWork {
+    agent_id: u8,
+    data: String,
+    priority: Priority,
+    deadline: Option<Deadline>,
+}
+
+/// Queues work from other nodes on this node's agents
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+struct Gateway;
+
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+impl Actor for Gateway {
+    type Msg = GatewayMessage;
+    type State = ();
+    type Arguments = ();
+
+    async fn pre_start(&self, myself: ActorRef<Self::Msg>, _: ()) -> Result<(), ActorProcessingErr> {
+        ractor::pg::join(GATEWAY_GROUP.to_string(), vec![myself.get_cell()]);
+        Ok(())
+    }
+
+    async fn handle(&self, _myself: ActorRef<Self::Msg>, message: Self::Msg, _state: &mut ()) -> Result<(), ActorProcessingErr> {
+        match message {
+            GatewayMessage::Process(work, reply) => {
+                let outcome = match serde_json::from_str::<RemoteWork>(&work) {
+                    Ok(work) => accept(work),
+                    Err(e) => Err(Refusal::new(format!("Malformed work from another node: {}", e))),
+                };
+                let _ = reply.send(serde_json::to_string(&outcome).unwrap_or_default());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Queue forwarded work on the local agent, like a local `/api/agents/:id/process`
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+fn accept(work: RemoteWork) -> Result<String, Refusal> {
+    use crate::agents::AgentMessage;
+
+    let RemoteWork { agent_id, data, priority, deadline } = work;
+    let agent = crate::agents::get_agent(agent_id)
+        .ok_or_else(|| Refusal::new(format!("Agent{} is not available on {}", agent_id, node_name())))?;
+    crate::schemas::validate_payload(agent_id, &crate::payload::Payload::text(data.clone()))
+        .map_err(|e| Refusal::new(e.to_string()))?;
+    agent.send_message(AgentMessage::ProcessData { data: data.clone(), priority }.with_deadline(deadline).queued(agent_id))
+        .map_err(|e| refusal(agent_id, e))?;
+    Ok(format!("Message queued for Agent{} on {}: {}", agent_id, node_name(), data))
+}
+
+/// The same status codes the process endpoints answer a local send with
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+fn refusal(agent_id: u8, e: SendError) -> Refusal {
+    let message = format!("{} (on {})", e, node_name());
+    match &e {
+        SendError::Full { depth, capacity, .. } => Refusal {
+            code: 429,
+            message,
+            details: Some(serde_json::json!({ "agent_id": agent_id, "depth": depth, "capacity": capacity })),
+        },
+        SendError::Draining { .. } => Refusal {
+            code: 503,
+            message,
+            details: Some(serde_json::json!({ "agent_id": agent_id, "state": crate::mailbox::lifecycle(agent_id) })),
+        },
+        SendError::Closed(_) => Refusal::new(message),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Routing
+// ----------------------------------------------------------------------------
+
+/// The gateway of another node
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+#[derive(Clone)]
+pub struct Peer {
+    pub node_id: u64,
+    gateway: ActorRef<GatewayMessage>,
+}
+
+/// Gateways of connected peers, by node id
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+fn gateways() -> Vec<Peer> {
+    let mut peers: Vec<Peer> = ractor::pg::get_members(&GATEWAY_GROUP.to_string())
+        .into_iter()
+        .filter(|cell| !cell.get_id().is_local())
+        .map(|cell| Peer { node_id: cell.get_id().node(), gateway: ActorRef::from(cell) })
+        .collect();
+    peers.sort_by_key(|peer| peer.node_id);
+    peers
+}
+
+/// The peer that should handle the next message, or `None` for this node
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+pub fn route() -> Option<Peer> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    if config().cluster_routing != Routing::RoundRobin || server().is_none() {
+        return None;
+    }
+    let mut peers = gateways();
+    if peers.is_empty() {
+        return None;
+    }
+    // Slot 0 is this node
+    let slot = NEXT.fetch_add(1, Ordering::Relaxed) % (peers.len() + 1);
+    slot.checked_sub(1).map(|index| peers.swap_remove(index))
+}
+
+/// Queue `data` on agent `agent_id` of `peer` and wait for the peer's answer
+#[cfg(all(feature = "cluster", not(target_arch = "wasm32")))]
+pub async fn forward(
+    peer: &Peer,
+    agent_id: u8,
+    data: String,
+    priority: Priority,
+    deadline: Option<Deadline>,
+) -> Result<String, Refusal> {
+    let work = serde_json::to_string(&RemoteWork { agent_id, data, priority, deadline })
+        .map_err(|e| Refusal::new(format!("Failed to serialize work: {}", e)))?;
+    let wait = deadline.map_or(FORWARD_TIMEOUT, |deadline| deadline.remaining().min(FORWARD_TIMEOUT));
+    match peer.gateway.call(|reply| GatewayMessage::Process(work, reply), Some(wait)).await {
+        Ok(CallResult::Success(reply)) => serde_json::from_str(&reply)
+            .unwrap_or_else(|e| Err(Refusal::new(format!("Malformed answer from node {}: {}", peer.node_id, e)))),
+        Ok(CallResult::Timeout) => Err(Refusal {
+            code: 504,
+            message: format!("Node {} did not answer within {:?}", peer.node_id, wait),
+            details: Some(serde_json::json!({ "node_id": peer.node_id })),
+        }),
+        Ok(CallResult::SenderError) | Err(_) => Err(Refusal {
+            code: 503,
+            message: format!("Node {} is no longer reachable", peer.node_id),
+            details: Some(serde_json::json!({ "node_id": peer.node_id })),
+        }),
+    }
+}
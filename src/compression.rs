@@ -0,0 +1,141 @@
+use base64::Engine;
+use opentelemetry::KeyValue;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+
+use crate::config::config;
+use crate::telemetry::instruments;
+
+// ============================================================================
+// Compression
+// ============================================================================
+//
+// Large bodies are compressed where they travel or are stored:
+//
+//   - HTTP: the server compresses responses for clients that accept gzip or
+//     zstd and decompresses `Content-Encoding: gzip|zstd` request bodies
+//     (`http_layers`); `ApiClient` sends large request bodies as zstd.
+//   - Storage: stored event payloads are kept as
+//     `{"codec": "zstd", "data": "<base64>"}`; `deserialize_text` reads
+//     both that and a plain string, so older records keep working.
+//
+// Anything smaller than `compression_threshold` bytes (config) is left as is,
+// as is anything that would not shrink. Compression done here (stored
+// payloads, client request bodies) records its ratio (compressed / original)
+// in the `compression.ratio` metric.
+
+/// Supported encodings, named as in `Content-Encoding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        }
+    }
+}
+
+/// zstd level: fast, and already well ahead of gzip on text
+const ZSTD_LEVEL: i32 = 3;
+
+pub fn compress(codec: Codec, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    match codec {
+        Codec::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        Codec::Zstd => zstd::encode_all(data, ZSTD_LEVEL),
+    }
+}
+
+pub fn decompress(codec: Codec, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    match codec {
+        Codec::Gzip => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(data).read_to_end(&mut decoded)?;
+            Ok(decoded)
+        }
+        Codec::Zstd => zstd::decode_all(data),
+    }
+}
+
+/// Whether `len` bytes are worth compressing under the current configuration
+pub fn above_threshold(len: usize) -> bool {
+    let threshold = config().compression_threshold;
+    threshold > 0 && len >= threshold
+}
+
+/// Compress `data` if it is above the threshold and shrinks; records the ratio as `kind`
+pub fn compress_if_large(kind: &'static str, codec: Codec, data: &[u8]) -> Option<Vec<u8>> {
+    if !above_threshold(data.len()) {
+        return None;
+    }
+    let compressed = match compress(codec, data) {
+        Ok(compressed) => compressed,
+        Err(e) => {
+            log_warn!("[Compression] Failed to compress {} ({} bytes): {}", kind, data.len(), e);
+            return None;
+        }
+    };
+    let ratio = compressed.len() as f64 / data.len() as f64;
+    instruments().compression_ratio.record(ratio, &[
+        KeyValue::new("kind", kind),
+        KeyValue::new("codec", codec.name()),
+    ]);
+    (compressed.len() < data.len()).then_some(compressed)
+}
+
+/// Stored form of `text`: the string itself, or a compressed envelope when large
+pub fn encode_text(kind: &'static str, text: &str) -> Value {
+    match compress_if_large(kind, Codec::Zstd, text.as_bytes()) {
+        Some(compressed) => json!({
+            "codec": Codec::Zstd,
+            "data": base64::engine::general_purpose::STANDARD.encode(compressed),
+        }),
+        None => Value::String(text.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredText {
+    Plain(String),
+    Compressed { codec: Codec, data: String },
+}
+
+/// `deserialize_with` for text written by `encode_text`
+pub fn deserialize_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    use serde::de::Error;
+    match StoredText::deserialize(deserializer)? {
+        StoredText::Plain(text) => Ok(text),
+        StoredText::Compressed { codec, data } => {
+            let compressed = base64::engine::general_purpose::STANDARD.decode(data).map_err(D::Error::custom)?;
+            let decoded = decompress(codec, &compressed).map_err(D::Error::custom)?;
+            String::from_utf8(decoded).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Response compression and request decompression for the HTTP server
+#[cfg(feature = "server")]
+pub fn http_layers(router: axum::Router) -> axum::Router {
+    use tower_http::compression::predicate::{DefaultPredicate, Predicate, SizeAbove};
+    use tower_http::compression::CompressionLayer;
+    use tower_http::decompression::RequestDecompressionLayer;
+
+    let threshold = config().compression_threshold;
+    let router = router.layer(RequestDecompressionLayer::new().gzip(true).zstd(true));
+    if threshold == 0 {
+        return router;
+    }
+    let predicate = DefaultPredicate::new().and(SizeAbove::new(threshold.min(u16::MAX as usize) as u16));
+    router.layer(CompressionLayer::new().gzip(true).zstd(true).compress_when(predicate))
+}
@@ -0,0 +1,155 @@
+use opentelemetry::KeyValue;
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+use crate::crash;
+use crate::telemetry::instruments;
+
+// ============================================================================
+// Compute Pool
+// ============================================================================
+//
+// Burn work (model construction, inference, training, quantization) blocks
+// for as long as the device needs. Running it on an async executor thread
+// would stall every other request scheduled there, so all of it goes
+// through `run`, which queues the job on a small set of dedicated compute
+// threads and returns a future for its result:
+//
+//     let inference = compute::run("lstm.infer", move || lstm::run_lstm(&input)).await?;
+//
+// Long jobs (training, tuning trials, quantization) go through `run_bulk`
+// instead, which lets them take all compute threads but one: however many
+// trials are running, inference still finds a thread within one job's time.
+// With a single compute thread, bulk jobs get it too.
+//
+// A panicking job is reported as an error to its caller and does not take
+// its worker down. Time spent queued goes to the `compute.queue.wait` metric.
+// `PATTERN_CLOCK_COMPUTE_THREADS` sizes the pool; `stats()` reports how many
+// jobs wait and how many threads are busy (see `threads`).
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    jobs: mpsc::Sender<Job>,
+}
+
+/// Jobs sent to the pool that no thread has picked up yet
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
+/// Compute threads running a job
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of compute threads: `compute_threads`, else one per core between 2 and 4
+pub fn worker_count() -> usize {
+    match crate::config::config().compute_threads {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(2).clamp(2, 4),
+        threads => threads,
+    }
+}
+
+/// `(queued, busy)`: jobs waiting for a compute thread and threads running one
+pub fn stats() -> (usize, usize) {
+    (QUEUED.load(Ordering::Relaxed), BUSY.load(Ordering::Relaxed))
+}
+
+fn pool() -> &'static Pool {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        let workers = worker_count();
+        for index in 0..workers {
+            let queue = queue.clone();
+            std::thread::Builder::new()
+                .name(format!("compute-{}", index))
+                .spawn(move || loop {
+                    let job = queue.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => {
+                            QUEUED.fetch_sub(1, Ordering::Relaxed);
+                            BUSY.fetch_add(1, Ordering::Relaxed);
+                            job();
+                            BUSY.fetch_sub(1, Ordering::Relaxed);
+                        }
+                        Err(_) => break,
+                    }
+                })
+                .expect("failed to spawn compute thread");
+        }
+        log_info!("[Compute] Started {} compute threads", workers);
+        Pool { jobs }
+    })
+}
+
+/// Compute threads bulk jobs may hold at once
+pub fn bulk_limit() -> usize {
+    worker_count().saturating_sub(1).max(1)
+}
+
+fn bulk_slots() -> &'static Arc<Semaphore> {
+    static SLOTS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    SLOTS.get_or_init(|| Arc::new(Semaphore::new(bulk_limit())))
+}
+
+/// Run a long `job` (training, tuning, quantization) like `run`, once fewer
+/// than `bulk_limit()` other bulk jobs are queued or running
+pub async fn run_bulk<T, F>(name: &'static str, job: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let slot = bulk_slots()
+        .clone()
+        .acquire_owned()
+        .await
+        .map_err(|_| anyhow::anyhow!("compute pool is not running"))?;
+    // Released when the job ends, even if the caller stopped waiting for it
+    run(name, move || {
+        let _slot = slot;
+        job()
+    }).await
+}
+
+/// Run `job` on a compute thread and wait for its result without blocking the executor
+pub async fn run<T, F>(name: &'static str, job: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+    let queued_at = Instant::now();
+    let job: Job = Box::new(move || {
+        instruments().compute_queue_wait.record(queued_at.elapsed().as_secs_f64(), &[KeyValue::new("job", name)]);
+        let result = crash::catching(job);
+        // The caller may have stopped waiting; the result is dropped then
+        let _ = result_tx.send(result);
+    });
+    let pool = pool();
+    QUEUED.fetch_add(1, Ordering::Relaxed);
+    if pool.jobs.send(job).is_err() {
+        QUEUED.fetch_sub(1, Ordering::Relaxed);
+        anyhow::bail!("compute pool is not running");
+    }
+
+    match result_rx.await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(payload)) => {
+            let message = panic_message(payload.as_ref());
+            log_error!("[Compute] {} panicked: {}", name, message);
+            Err(anyhow::anyhow!("{} panicked: {}", name, message))
+        }
+        Err(_) => Err(anyhow::anyhow!("{} was dropped by the compute pool", name)),
+    }
+}
+
+/// Text of a panic payload (`panic!` with a literal or a formatted message)
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
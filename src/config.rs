@@ -0,0 +1,1219 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::agents::RestartPolicy;
+use crate::cluster::Routing;
+use crate::connections::Persona;
+use crate::connectors::{MailSource, PollSource, WatchedDir};
+use crate::cycle::Alignment;
+use crate::extraction::ExtractionMode;
+use crate::logging::LogFormat;
+use crate::notifications::NotificationChannel;
+use crate::redaction::RedactionPattern;
+use crate::registry::LstmImpl;
+use crate::request_limits::{default_body_limits, is_path_pattern, parse_body_limits, parse_size, DEFAULT_MAX_BODY_SIZE};
+use crate::retention::DataClass;
+use crate::roles::AgentRole;
+use crate::rules::PatternRule;
+use crate::secrets::{self, Secret};
+
+// ============================================================================
+// Service Configuration
+// ============================================================================
+//
+// Every setting of the headless server comes from the environment, read at
+// first use. Unset variables fall back to the defaults below; invalid values
+// are reported on stderr and also fall back, so a typo never keeps a
+// container from starting.
+//
+// | Variable                              | Default                   |
+// |---------------------------------------|---------------------------|
+// | `PATTERN_CLOCK_CONFIG`                | unset (JSON file path)    |
+// | `PATTERN_CLOCK_DATA_DIR`              | `data`                    |
+// | `PATTERN_CLOCK_AGENTS`                | `5`                       |
+// | `PATTERN_CLOCK_AGENTS_FILE`           | `agents.toml` if present  |
+// | `PATTERN_CLOCK_MONITOR`               | `true`                    |
+// | `PATTERN_CLOCK_AGENT_RESTART`         | `one_for_one` / `never`   |
+// | `PATTERN_CLOCK_AGENT_ROLES`           | unset (all `general`)     |
+// | `PATTERN_CLOCK_RESTART_BACKOFF_MS`    | `100` (doubles per crash) |
+// | `PATTERN_CLOCK_MAILBOX_CAPACITY`      | `1000` (0 = unbounded)    |
+// | `PATTERN_CLOCK_PROCESSING_DELAY_MS`   | `10`                      |
+// | `PATTERN_CLOCK_SUMMARY_INTERVAL`      | `60` (seconds)            |
+// | `PATTERN_CLOCK_CYCLE_MIN_INTERVAL_MS` | `20`                      |
+// | `PATTERN_CLOCK_CYCLE_MAX_INTERVAL_MS` | `500`                     |
+// | `PATTERN_CLOCK_CYCLE_ALIGN`           | `none` (minute/hour/day)  |
+// | `PATTERN_CLOCK_TIMEZONE`              | `UTC` (IANA name)         |
+// | `PATTERN_CLOCK_LOG_FORMAT`            | `text` (`text` / `json`)  |
+// | `PATTERN_CLOCK_TRUST_PROXY`           | `false`                   |
+// | `PATTERN_CLOCK_PROXY_HOPS`            | `1`                       |
+// | `PATTERN_CLOCK_WORKER_THREADS`        | unset (one per core)      |
+// | `PATTERN_CLOCK_COMPUTE_THREADS`       | `0` (per core, 2 to 4)    |
+// | `PATTERN_CLOCK_PIN_BACKGROUND`        | `false`                   |
+// | `PATTERN_CLOCK_MODEL_POOL_SIZE`       | `2`                       |
+// | `PATTERN_CLOCK_LSTM_IMPL`             | `custom` (or burn/auto)   |
+// | `PATTERN_CLOCK_UPDATE_URL`            | unset (no update check)   |
+// | `PATTERN_CLOCK_COMPRESSION_THRESHOLD` | `4096` (bytes, 0 = off)   |
+// | `PATTERN_CLOCK_MAX_BODY_SIZE`         | `1m`                      |
+// | `PATTERN_CLOCK_BODY_LIMITS`           | uploads 64m, ...          |
+// | `PATTERN_CLOCK_DATABASE_URL`          | unset (secret reference)  |
+// | `PATTERN_CLOCK_DB_POOL_SIZE`          | `8`                       |
+// | `PATTERN_CLOCK_REDIS_URL`             | unset (secret reference)  |
+// | `PATTERN_CLOCK_CLUSTER_PORT`          | unset (single node)       |
+// | `PATTERN_CLOCK_CLUSTER_PEERS`         | unset (`host:port`, ...)  |
+// | `PATTERN_CLOCK_CLUSTER_COOKIE`        | unset (secret reference)  |
+// | `PATTERN_CLOCK_CLUSTER_ROUTING`       | `local` (or round_robin)  |
+// | `PATTERN_CLOCK_ENCRYPTION_KEY`        | unset (secret reference)  |
+// | `PATTERN_CLOCK_ENCRYPTION_OLD_KEYS`   | unset (secret reference)  |
+// | `PATTERN_CLOCK_ENCRYPTED_COLLECTIONS` | events, generations, ...  |
+// | `PATTERN_CLOCK_BLOB_STORE`            | `local` (or `s3://...`)   |
+// | `PATTERN_CLOCK_S3_ENDPOINT`           | unset (AWS)               |
+// | `PATTERN_CLOCK_S3_REGION`             | `us-east-1`               |
+// | `OLLAMA_URL`                          | `http://127.0.0.1:11434`  |
+// | `OLLAMA_MODEL`                        | `llama3.2`                |
+// | `OLLAMA_EMBED_MODEL`                  | `nomic-embed-text`        |
+// | `OLLAMA_AGENT_MODEL`                  | unset (`OLLAMA_MODEL`)    |
+// | `OLLAMA_API_KEY`                      | unset (secret reference)  |
+// | `PATTERN_CLOCK_LLM_POOL_MAX_IDLE`     | `16` (per host)           |
+// | `PATTERN_CLOCK_LLM_HTTP2`             | `false` (h2 without TLS)  |
+// | `PATTERN_CLOCK_WARM_LLM`              | `false` (load at boot)    |
+// | `EXTRACTION_MODE`                     | `rules` (`rules` / `llm`) |
+// | `PATTERN_CLOCK_REDACTION`             | `true`                    |
+// | `PATTERN_CLOCK_REDACT_LOCAL_MODELS`   | unset (comma-separated)   |
+// | `PATTERN_CLOCK_RETENTION`             | unset (keep everything)   |
+//
+// Bind address and port are taken by the Dioxus server from `IP` / `PORT`,
+// and telemetry export from the standard `OTEL_*` variables. Secrets accept
+// `env:` / `file:` / `keyring:` references or a `_FILE` variable (see
+// `secrets`), so keys never have to be written into the config file.
+//
+// The optional `PATTERN_CLOCK_CONFIG` file overrides the reloadable settings
+// and declares pattern rules, agent personas, notification channels, source
+// connectors and redaction patterns. The agent pool (count, roles, mailbox
+// capacities, processing delays) can also be tuned in `agents.toml`
+// (`AgentsFile`), which overrides both. `reload()` (SIGHUP or
+// `POST /api/admin/config/reload`) re-reads environment and files, validates
+// everything, then swaps the new configuration in at once; on any error the
+// running configuration is kept.
+
+/// Settings of the running service
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// Root directory of file storage (restart required)
+    pub data_dir: String,
+    /// Postgres URL; when set, storage lives there instead of `data_dir` (restart required)
+    pub database_url: Option<Secret>,
+    /// Postgres connections kept open (restart required)
+    pub db_pool_size: usize,
+    /// Redis URL; when set, events are shared with other instances over Redis (restart required)
+    pub redis_url: Option<Secret>,
+    /// Port this node accepts cluster connections on; unset runs a single node (restart required)
+    pub cluster_port: Option<u16>,
+    /// `host:port` of other nodes to connect to at startup (restart required)
+    pub cluster_peers: Vec<String>,
+    /// Shared secret nodes authenticate each other with (restart required)
+    pub cluster_cookie: Option<Secret>,
+    /// Which node handles data sent to `/api/agents/:id/process`
+    pub cluster_routing: Routing,
+    /// Master key (base64, 32 bytes); when set, sensitive collections are encrypted at rest (restart required)
+    pub encryption_key: Option<Secret>,
+    /// Comma-separated keys replaced by `encryption_key`, still accepted for reading (restart required)
+    pub encryption_old_keys: Option<Secret>,
+    /// Collections encrypted when a key is set; `blobs` also covers blob contents (restart required)
+    pub encrypted_collections: Vec<String>,
+    /// Number of agents started on first use (restart required)
+    pub agents: u8,
+    /// Whether the self-monitor runs alongside the agents (restart required)
+    pub monitor: bool,
+    /// Roles by agent id; agents take theirs when they start
+    pub agent_roles: BTreeMap<u8, AgentRole>,
+    /// What the supervisor does when an agent crashes
+    pub agent_restart: RestartPolicy,
+    /// Delay before the first restart of a crashed agent, doubled for each further crash
+    pub agent_restart_backoff: Duration,
+    /// Messages each agent's queue holds before sends are refused (0: no limit)
+    pub mailbox_capacity: usize,
+    /// Pause after each message an agent processes
+    pub processing_delay: Duration,
+    /// Mailbox capacity and processing delay of single agents (`agents.toml`)
+    pub agent_settings: BTreeMap<u8, AgentSettings>,
+    /// The `agents.toml` the pool settings were read from, if any
+    pub agents_file: Option<String>,
+    /// How often agents condense their history
+    pub summary_interval: Duration,
+    /// History length that triggers a summarization pass
+    pub summary_max_history: usize,
+    /// Most recent history entries kept verbatim after a pass
+    pub summary_keep_recent: usize,
+    /// History entries sent to the LLM per map step
+    pub summary_chunk_size: usize,
+    /// Shortest cognitive cycle interval, used under heavy event volume
+    pub cycle_min_interval: Duration,
+    /// Longest cognitive cycle interval, used when idle
+    pub cycle_max_interval: Duration,
+    /// Wall-clock boundary the cycle lands a tick on (a milestone)
+    pub cycle_align: Alignment,
+    /// Timezone of wall-clock boundaries
+    pub timezone: chrono_tz::Tz,
+    pub log_format: LogFormat,
+    /// Honor `X-Forwarded-For` / `X-Real-IP` / `X-Forwarded-Proto` from a reverse proxy
+    pub trust_proxy: bool,
+    /// Trusted proxies in front of the server: the client is that many
+    /// `X-Forwarded-For` entries from the right
+    pub proxy_hops: usize,
+    /// Tokio worker threads serving requests and agents; unset is one per core (restart required)
+    pub worker_threads: Option<usize>,
+    /// Threads running Burn work; 0 is one per core, between 2 and 4 (restart required)
+    pub compute_threads: usize,
+    /// Run the scheduler, compaction and retention loops on their own thread (restart required)
+    pub pin_background: bool,
+    /// Number of models kept loaded by the model pool
+    pub model_pool_size: usize,
+    /// LSTM implementation serving inference; a change reloads the LSTM
+    pub lstm_impl: LstmImpl,
+    /// Releases feed checked for new versions (GitHub releases JSON)
+    pub update_url: Option<String>,
+    /// Bodies and stored payloads at least this large are compressed (0 disables;
+    /// restart required: the server's compression layer is built at startup)
+    pub compression_threshold: usize,
+    /// Largest request body accepted, in bytes, where no `body_limits` pattern matches
+    pub max_body_size: usize,
+    /// Request body limits by path pattern (`*` matches one segment); the most
+    /// specific match wins. Configured patterns add to or replace the defaults.
+    pub body_limits: BTreeMap<String, usize>,
+    /// `local` or `s3://bucket/prefix` (restart required)
+    pub blob_store: String,
+    /// S3-compatible endpoint other than AWS, e.g. MinIO (restart required)
+    pub s3_endpoint: Option<String>,
+    pub s3_region: String,
+    pub ollama_url: String,
+    pub ollama_model: String,
+    /// Model that embeds document chunks and search queries
+    pub ollama_embed_model: String,
+    /// Model of `llm` agents whose persona names none; unset uses `ollama_model`
+    pub ollama_agent_model: Option<String>,
+    /// Sent as a bearer token, for Ollama behind an authenticating proxy
+    pub ollama_api_key: Option<Secret>,
+    /// Idle connections to one LLM server kept open for reuse (restart required)
+    pub llm_pool_max_idle: usize,
+    /// Speak HTTP/2 to the LLM server without TLS negotiation, for `http://`
+    /// servers behind an h2c proxy (restart required)
+    pub llm_http2: bool,
+    /// Load the default model into the LLM server during the boot sequence (restart required)
+    pub warm_llm: bool,
+    pub extraction_mode: ExtractionMode,
+    /// Replace personal data and credentials before storing payloads or sending them to an LLM
+    pub redaction: bool,
+    /// Models that run on this host and may see unredacted prompts
+    pub redaction_local_models: Vec<String>,
+    /// Patterns redacted in addition to the built-in ones
+    pub redaction_patterns: Vec<RedactionPattern>,
+    /// Maximum age per data class; classes without one are kept forever
+    pub retention: BTreeMap<DataClass, Duration>,
+    /// Where `monitor.alert` events are delivered
+    pub notifications: Vec<NotificationChannel>,
+    /// Rules declared in the config file, saved to the rules collection on load
+    pub rules: Vec<PatternRule>,
+    /// Personas by agent id declared in the config file, saved to the personas collection on load
+    pub personas: BTreeMap<u8, Persona>,
+    /// Directories ingested by the filesystem connector (restart required)
+    pub watch_dirs: Vec<WatchedDir>,
+    /// URLs fetched by the poller connector (restart required)
+    pub poll_sources: Vec<PollSource>,
+    /// Mailboxes read by the email connector (restart required)
+    pub mail_sources: Vec<MailSource>,
+}
+
+/// Settings of one agent that override the pool-wide ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentSettings {
+    pub mailbox_capacity: Option<usize>,
+    pub processing_delay: Option<Duration>,
+}
+
+/// Contents of the `PATTERN_CLOCK_CONFIG` file; every field is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    /// Role names by agent id, e.g. `{"1": "llm"}`
+    agent_roles: Option<BTreeMap<u8, String>>,
+    agent_restart: Option<String>,
+    agent_restart_backoff_ms: Option<u64>,
+    mailbox_capacity: Option<usize>,
+    summary_interval: Option<u64>,
+    summary_max_history: Option<usize>,
+    summary_keep_recent: Option<usize>,
+    summary_chunk_size: Option<usize>,
+    cycle_min_interval_ms: Option<u64>,
+    cycle_max_interval_ms: Option<u64>,
+    cycle_align: Option<String>,
+    timezone: Option<String>,
+    log_format: Option<String>,
+    trust_proxy: Option<bool>,
+    proxy_hops: Option<usize>,
+    /// `local` or `round_robin`
+    cluster_routing: Option<String>,
+    model_pool_size: Option<usize>,
+    lstm_impl: Option<String>,
+    update_url: Option<String>,
+    compression_threshold: Option<usize>,
+    /// `65536`, `512k`, `16m`, ...
+    max_body_size: Option<String>,
+    /// Path pattern to size, e.g. `{"/api/uploads": "128m"}`
+    body_limits: Option<BTreeMap<String, String>>,
+    ollama_url: Option<String>,
+    ollama_model: Option<String>,
+    ollama_embed_model: Option<String>,
+    ollama_agent_model: Option<String>,
+    /// Secret reference, e.g. `file:/run/secrets/ollama`
+    ollama_api_key: Option<String>,
+    extraction_mode: Option<String>,
+    redaction: Option<bool>,
+    redaction_local_models: Option<Vec<String>>,
+    #[serde(default)]
+    redaction_patterns: Vec<RedactionPattern>,
+    /// Maximum age per class, e.g. `{"events": "30d"}`
+    retention: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    notifications: Vec<NotificationChannel>,
+    #[serde(default)]
+    rules: Vec<PatternRule>,
+    #[serde(default)]
+    personas: BTreeMap<u8, Persona>,
+    #[serde(default)]
+    watch_dirs: Vec<WatchedDir>,
+    #[serde(default)]
+    poll_sources: Vec<PollSource>,
+    #[serde(default)]
+    mail_sources: Vec<MailSource>,
+}
+
+/// Default agent pool file, read when present
+pub const DEFAULT_AGENTS_FILE: &str = "agents.toml";
+
+/// Contents of `agents.toml` (`PATTERN_CLOCK_AGENTS_FILE`); every field is optional
+///
+/// ```toml
+/// count = 6
+/// mailbox_capacity = 2000
+/// processing_delay_ms = 10
+///
+/// [agents.1]
+/// role = "router"
+/// mailbox_capacity = 200
+///
+/// [agents.3]
+/// role = "lstm"
+/// processing_delay_ms = 0
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AgentsFile {
+    count: Option<u8>,
+    mailbox_capacity: Option<usize>,
+    processing_delay_ms: Option<u64>,
+    /// By agent id; TOML table keys are strings
+    #[serde(default)]
+    agents: BTreeMap<String, AgentsFileEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AgentsFileEntry {
+    role: Option<String>,
+    mailbox_capacity: Option<usize>,
+    processing_delay_ms: Option<u64>,
+}
+
+/// Collects every invalid value instead of stopping at the first
+#[derive(Default)]
+struct Loader {
+    errors: Vec<String>,
+}
+
+impl Loader {
+    fn string(&mut self, name: &str, default: &str) -> String {
+        match std::env::var(name) {
+            Ok(value) if !value.trim().is_empty() => value,
+            _ => default.to_string(),
+        }
+    }
+
+    /// Parse a variable with `parse`, falling back to `default` when unset or invalid
+    fn with<T>(&mut self, name: &str, default: T, parse: impl Fn(&str) -> Option<T>) -> T {
+        let Ok(value) = std::env::var(name) else {
+            return default;
+        };
+        match parse(value.trim()) {
+            Some(parsed) => parsed,
+            None => {
+                self.errors.push(format!("invalid {}={:?}", name, value));
+                default
+            }
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, default: T) -> T {
+        self.with(name, default, |value| value.parse().ok())
+    }
+
+    fn flag(&mut self, name: &str, default: bool) -> bool {
+        self.with(name, default, parse_flag)
+    }
+
+    fn secret(&mut self, name: &str) -> Option<Secret> {
+        secrets::from_env(name).unwrap_or_else(|e| {
+            self.errors.push(format!("secret {}: {}", name, e));
+            None
+        })
+    }
+
+    /// Apply a file value parsed with `parse`, recording it as invalid otherwise
+    fn file_value<T>(&mut self, field: &str, value: Option<String>, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+        let value = value?;
+        let parsed = parse(value.trim());
+        if parsed.is_none() {
+            self.errors.push(format!("invalid config file value {}={:?}", field, value));
+        }
+        parsed
+    }
+
+    /// `agents.toml` and its path; a missing default file is not an error
+    fn agents_file(&mut self) -> Option<(String, AgentsFile)> {
+        let (path, explicit) = match std::env::var("PATTERN_CLOCK_AGENTS_FILE") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_AGENTS_FILE.to_string(), false),
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                self.errors.push(format!("failed to read agents file {}: {}", path, e));
+                return None;
+            }
+        };
+        match toml::from_str(&text) {
+            Ok(file) => Some((path, file)),
+            Err(e) => {
+                self.errors.push(format!("failed to parse agents file {}: {}", path, e));
+                None
+            }
+        }
+    }
+
+    fn file(&mut self) -> ConfigFile {
+        let Ok(path) = std::env::var("PATTERN_CLOCK_CONFIG") else {
+            return ConfigFile::default();
+        };
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+        parsed.unwrap_or_else(|e| {
+            self.errors.push(format!("failed to read config file {}: {}", path, e));
+            ConfigFile::default()
+        })
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Comma-separated values, trimmed, without empty ones
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+fn parse_extraction_mode(value: &str) -> Option<ExtractionMode> {
+    match value {
+        "rules" => Some(ExtractionMode::Rules),
+        "llm" => Some(ExtractionMode::Llm),
+        _ => None,
+    }
+}
+
+impl ServiceConfig {
+    /// Messages agent `id`'s queue holds before sends are refused (0: no limit)
+    pub fn mailbox_capacity_of(&self, id: u8) -> usize {
+        self.agent_settings.get(&id).and_then(|settings| settings.mailbox_capacity).unwrap_or(self.mailbox_capacity)
+    }
+
+    /// Pause after each message agent `id` processes
+    pub fn processing_delay_of(&self, id: u8) -> Duration {
+        self.agent_settings.get(&id).and_then(|settings| settings.processing_delay).unwrap_or(self.processing_delay)
+    }
+
+    /// Read environment and config file, returning the configuration and every problem found
+    fn load() -> (Self, Vec<String>) {
+        use crate::agents::DEFAULT_AGENT_COUNT;
+        use crate::connections::{DEFAULT_OLLAMA_EMBED_MODEL, DEFAULT_OLLAMA_MODEL, DEFAULT_OLLAMA_URL};
+        use crate::storage::{DEFAULT_DATA_DIR, DEFAULT_ENCRYPTED_COLLECTIONS};
+
+        let mut loader = Loader::default();
+        let mut config = Self {
+            data_dir: loader.string("PATTERN_CLOCK_DATA_DIR", DEFAULT_DATA_DIR),
+            database_url: loader.secret("PATTERN_CLOCK_DATABASE_URL"),
+            db_pool_size: loader.parse("PATTERN_CLOCK_DB_POOL_SIZE", 8usize),
+            redis_url: loader.secret("PATTERN_CLOCK_REDIS_URL"),
+            cluster_port: loader.with("PATTERN_CLOCK_CLUSTER_PORT", None, |value| value.parse().ok().map(Some)),
+            cluster_peers: parse_list(&loader.string("PATTERN_CLOCK_CLUSTER_PEERS", "")),
+            cluster_cookie: loader.secret("PATTERN_CLOCK_CLUSTER_COOKIE"),
+            cluster_routing: loader.with("PATTERN_CLOCK_CLUSTER_ROUTING", Routing::Local, Routing::parse),
+            encryption_key: loader.secret("PATTERN_CLOCK_ENCRYPTION_KEY"),
+            encryption_old_keys: loader.secret("PATTERN_CLOCK_ENCRYPTION_OLD_KEYS"),
+            encrypted_collections: parse_list(&loader.string(
+                "PATTERN_CLOCK_ENCRYPTED_COLLECTIONS",
+                &DEFAULT_ENCRYPTED_COLLECTIONS.join(","),
+            )),
+            agents: loader.parse("PATTERN_CLOCK_AGENTS", DEFAULT_AGENT_COUNT),
+            monitor: loader.flag("PATTERN_CLOCK_MONITOR", true),
+            agent_roles: loader.with("PATTERN_CLOCK_AGENT_ROLES", BTreeMap::new(), crate::roles::parse_roles),
+            agent_restart: loader.with("PATTERN_CLOCK_AGENT_RESTART", RestartPolicy::OneForOne, RestartPolicy::parse),
+            agent_restart_backoff: Duration::from_millis(loader.parse("PATTERN_CLOCK_RESTART_BACKOFF_MS", 100u64)),
+            mailbox_capacity: loader.parse("PATTERN_CLOCK_MAILBOX_CAPACITY", 1000usize),
+            processing_delay: Duration::from_millis(loader.parse("PATTERN_CLOCK_PROCESSING_DELAY_MS", 10u64)),
+            agent_settings: BTreeMap::new(),
+            agents_file: None,
+            summary_interval: Duration::from_secs(loader.parse("PATTERN_CLOCK_SUMMARY_INTERVAL", 60u64)),
+            summary_max_history: loader.parse("PATTERN_CLOCK_SUMMARY_MAX_HISTORY", 200usize),
+            summary_keep_recent: loader.parse("PATTERN_CLOCK_SUMMARY_KEEP_RECENT", 20usize),
+            summary_chunk_size: loader.parse("PATTERN_CLOCK_SUMMARY_CHUNK_SIZE", 25usize),
+            cycle_min_interval: Duration::from_millis(loader.parse("PATTERN_CLOCK_CYCLE_MIN_INTERVAL_MS", 20u64)),
+            cycle_max_interval: Duration::from_millis(loader.parse("PATTERN_CLOCK_CYCLE_MAX_INTERVAL_MS", 500u64)),
+            cycle_align: loader.with("PATTERN_CLOCK_CYCLE_ALIGN", Alignment::None, Alignment::parse),
+            timezone: loader.with("PATTERN_CLOCK_TIMEZONE", chrono_tz::UTC, |value| value.parse().ok()),
+            log_format: loader.with("PATTERN_CLOCK_LOG_FORMAT", LogFormat::Text, LogFormat::parse),
+            trust_proxy: loader.flag("PATTERN_CLOCK_TRUST_PROXY", false),
+            proxy_hops: loader.parse("PATTERN_CLOCK_PROXY_HOPS", 1usize),
+            worker_threads: loader.with("PATTERN_CLOCK_WORKER_THREADS", None, |value| value.parse().ok().map(Some)),
+            compute_threads: loader.parse("PATTERN_CLOCK_COMPUTE_THREADS", 0usize),
+            pin_background: loader.flag("PATTERN_CLOCK_PIN_BACKGROUND", false),
+            model_pool_size: loader.parse("PATTERN_CLOCK_MODEL_POOL_SIZE", 2usize),
+            lstm_impl: loader.with("PATTERN_CLOCK_LSTM_IMPL", LstmImpl::Custom, LstmImpl::parse),
+            update_url: std::env::var("PATTERN_CLOCK_UPDATE_URL").ok().filter(|url| !url.trim().is_empty()),
+            compression_threshold: loader.parse("PATTERN_CLOCK_COMPRESSION_THRESHOLD", 4096usize),
+            max_body_size: loader.with("PATTERN_CLOCK_MAX_BODY_SIZE", DEFAULT_MAX_BODY_SIZE, parse_size),
+            body_limits: loader.with("PATTERN_CLOCK_BODY_LIMITS", default_body_limits(), |value| {
+                let mut limits = default_body_limits();
+                limits.extend(parse_body_limits(value)?);
+                Some(limits)
+            }),
+            blob_store: loader.string("PATTERN_CLOCK_BLOB_STORE", "local"),
+            s3_endpoint: std::env::var("PATTERN_CLOCK_S3_ENDPOINT").ok().filter(|url| !url.trim().is_empty()),
+            s3_region: loader.string("PATTERN_CLOCK_S3_REGION", "us-east-1"),
+            ollama_url: loader.string("OLLAMA_URL", DEFAULT_OLLAMA_URL),
+            ollama_model: loader.string("OLLAMA_MODEL", DEFAULT_OLLAMA_MODEL),
+            ollama_embed_model: loader.string("OLLAMA_EMBED_MODEL", DEFAULT_OLLAMA_EMBED_MODEL),
+            ollama_agent_model: std::env::var("OLLAMA_AGENT_MODEL").ok().filter(|model| !model.trim().is_empty()),
+            ollama_api_key: loader.secret("OLLAMA_API_KEY"),
+            llm_pool_max_idle: loader.parse("PATTERN_CLOCK_LLM_POOL_MAX_IDLE", 16usize),
+            llm_http2: loader.flag("PATTERN_CLOCK_LLM_HTTP2", false),
+            warm_llm: loader.flag("PATTERN_CLOCK_WARM_LLM", false),
+            extraction_mode: loader.with("EXTRACTION_MODE", ExtractionMode::Rules, parse_extraction_mode),
+            redaction: loader.flag("PATTERN_CLOCK_REDACTION", true),
+            redaction_local_models: parse_list(&loader.string("PATTERN_CLOCK_REDACT_LOCAL_MODELS", "")),
+            redaction_patterns: Vec::new(),
+            retention: loader.with("PATTERN_CLOCK_RETENTION", BTreeMap::new(), crate::retention::parse_policies),
+            notifications: Vec::new(),
+            rules: Vec::new(),
+            personas: BTreeMap::new(),
+            watch_dirs: Vec::new(),
+            poll_sources: Vec::new(),
+            mail_sources: Vec::new(),
+        };
+
+        let file = loader.file();
+        if let Some(roles) = file.agent_roles {
+            config.agent_roles = BTreeMap::new();
+            for (id, role) in roles {
+                match AgentRole::parse(&role) {
+                    Some(role) => {
+                        config.agent_roles.insert(id, role);
+                    }
+                    None => loader.errors.push(format!("invalid config file value agent_roles.{}={:?}", id, role)),
+                }
+            }
+        }
+        if let Some(policy) = loader.file_value("agent_restart", file.agent_restart, RestartPolicy::parse) {
+            config.agent_restart = policy;
+        }
+        if let Some(ms) = file.agent_restart_backoff_ms {
+            config.agent_restart_backoff = Duration::from_millis(ms);
+        }
+        if let Some(capacity) = file.mailbox_capacity {
+            config.mailbox_capacity = capacity;
+        }
+        if let Some(secs) = file.summary_interval {
+            config.summary_interval = Duration::from_secs(secs);
+        }
+        if let Some(entries) = file.summary_max_history {
+            config.summary_max_history = entries;
+        }
+        if let Some(entries) = file.summary_keep_recent {
+            config.summary_keep_recent = entries;
+        }
+        if let Some(entries) = file.summary_chunk_size {
+            config.summary_chunk_size = entries;
+        }
+        if let Some(ms) = file.cycle_min_interval_ms {
+            config.cycle_min_interval = Duration::from_millis(ms);
+        }
+        if let Some(ms) = file.cycle_max_interval_ms {
+            config.cycle_max_interval = Duration::from_millis(ms);
+        }
+        if let Some(alignment) = loader.file_value("cycle_align", file.cycle_align, Alignment::parse) {
+            config.cycle_align = alignment;
+        }
+        if let Some(timezone) = loader.file_value("timezone", file.timezone, |value| value.parse().ok()) {
+            config.timezone = timezone;
+        }
+        if let Some(format) = loader.file_value("log_format", file.log_format, LogFormat::parse) {
+            config.log_format = format;
+        }
+        if let Some(trust_proxy) = file.trust_proxy {
+            config.trust_proxy = trust_proxy;
+        }
+        if let Some(hops) = file.proxy_hops {
+            config.proxy_hops = hops;
+        }
+        if let Some(routing) = loader.file_value("cluster_routing", file.cluster_routing, Routing::parse) {
+            config.cluster_routing = routing;
+        }
+        if let Some(size) = file.model_pool_size {
+            config.model_pool_size = size;
+        }
+        if let Some(implementation) = loader.file_value("lstm_impl", file.lstm_impl, LstmImpl::parse) {
+            config.lstm_impl = implementation;
+        }
+        if let Some(url) = file.update_url {
+            config.update_url = Some(url).filter(|url| !url.trim().is_empty());
+        }
+        if let Some(threshold) = file.compression_threshold {
+            config.compression_threshold = threshold;
+        }
+        if let Some(size) = loader.file_value("max_body_size", file.max_body_size, parse_size) {
+            config.max_body_size = size;
+        }
+        for (pattern, size) in file.body_limits.unwrap_or_default() {
+            match parse_size(&size).filter(|_| is_path_pattern(&pattern)) {
+                Some(size) => {
+                    config.body_limits.insert(pattern, size);
+                }
+                None => loader.errors.push(format!("invalid config file value body_limits.{}={:?}", pattern, size)),
+            }
+        }
+        if let Some(url) = file.ollama_url {
+            config.ollama_url = url;
+        }
+        if let Some(model) = file.ollama_model {
+            config.ollama_model = model;
+        }
+        if let Some(model) = file.ollama_embed_model {
+            config.ollama_embed_model = model;
+        }
+        if let Some(model) = file.ollama_agent_model {
+            config.ollama_agent_model = Some(model).filter(|model| !model.trim().is_empty());
+        }
+        if let Some(reference) = file.ollama_api_key {
+            match secrets::resolve(&reference) {
+                Ok(key) => config.ollama_api_key = Some(key),
+                Err(e) => loader.errors.push(format!("ollama_api_key: {}", e)),
+            }
+        }
+        if let Some(mode) = loader.file_value("extraction_mode", file.extraction_mode, parse_extraction_mode) {
+            config.extraction_mode = mode;
+        }
+        if let Some(redaction) = file.redaction {
+            config.redaction = redaction;
+        }
+        if let Some(models) = file.redaction_local_models {
+            config.redaction_local_models = models;
+        }
+        config.redaction_patterns = file.redaction_patterns;
+        if let Some(retention) = file.retention {
+            config.retention = BTreeMap::new();
+            for (class, age) in retention {
+                match (DataClass::parse(&class), crate::retention::parse_age(&age)) {
+                    (Some(class), Some(age)) => {
+                        config.retention.insert(class, age);
+                    }
+                    _ => loader.errors.push(format!("invalid config file value retention.{}={:?}", class, age)),
+                }
+            }
+        }
+        config.notifications = file.notifications;
+        config.rules = file.rules;
+        config.personas = file.personas;
+        config.watch_dirs = file.watch_dirs;
+        config.poll_sources = file.poll_sources;
+        config.mail_sources = file.mail_sources;
+
+        // The agent pool file is the most specific, so it goes last
+        if let Some((path, agents_file)) = loader.agents_file() {
+            config.agents_file = Some(path);
+            if let Some(count) = agents_file.count {
+                config.agents = count;
+            }
+            if let Some(capacity) = agents_file.mailbox_capacity {
+                config.mailbox_capacity = capacity;
+            }
+            if let Some(ms) = agents_file.processing_delay_ms {
+                config.processing_delay = Duration::from_millis(ms);
+            }
+            for (key, entry) in agents_file.agents {
+                let Some(id) = key.parse::<u8>().ok().filter(|id| *id > 0) else {
+                    loader.errors.push(format!("invalid agents file id {:?}: expected 1-255", key));
+                    continue;
+                };
+                if let Some(role) = entry.role {
+                    match AgentRole::parse(&role) {
+                        Some(role) => {
+                            config.agent_roles.insert(id, role);
+                        }
+                        None => loader.errors.push(format!("invalid agents file value agents.{}.role={:?}", id, role)),
+                    }
+                }
+                let settings = AgentSettings {
+                    mailbox_capacity: entry.mailbox_capacity,
+                    processing_delay: entry.processing_delay_ms.map(Duration::from_millis),
+                };
+                if settings != AgentSettings::default() {
+                    config.agent_settings.insert(id, settings);
+                }
+            }
+        }
+
+        let mut errors = loader.errors;
+        errors.extend(config.validate());
+        (config, errors)
+    }
+
+    /// Problems with values that parsed but are unusable
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.agents == 0 {
+            errors.push("at least one agent is required".to_string());
+        }
+        if self.agent_restart_backoff.is_zero() {
+            errors.push("agent restart backoff must be positive".to_string());
+        }
+        if self.summary_interval.is_zero() {
+            errors.push("summary interval must be at least one second".to_string());
+        }
+        if self.summary_keep_recent >= self.summary_max_history {
+            errors.push("summary keep-recent count must be below the history limit".to_string());
+        }
+        if self.summary_chunk_size == 0 {
+            errors.push("summary chunk size must be at least 1".to_string());
+        }
+        if self.cycle_min_interval.is_zero() {
+            errors.push("cycle minimum interval must be at least 1 ms".to_string());
+        }
+        if self.cycle_min_interval > self.cycle_max_interval {
+            errors.push("cycle minimum interval must not exceed the maximum interval".to_string());
+        }
+        if self.worker_threads == Some(0) {
+            errors.push("worker threads must be at least 1".to_string());
+        }
+        if self.model_pool_size == 0 {
+            errors.push("model pool size must be at least 1".to_string());
+        }
+        if self.proxy_hops == 0 {
+            errors.push("proxy hops must be at least 1".to_string());
+        }
+        if let Some(url) = &self.database_url {
+            if !cfg!(feature = "postgres") {
+                errors.push("a database URL needs a build with `--features postgres`".to_string());
+            } else if !(url.expose().starts_with("postgres://") || url.expose().starts_with("postgresql://")) {
+                errors.push("database URL must start with postgres:// or postgresql://".to_string());
+            }
+        }
+        if self.db_pool_size == 0 {
+            errors.push("database pool size must be at least 1".to_string());
+        }
+        if let Some(url) = &self.redis_url {
+            if !cfg!(feature = "redis") {
+                errors.push("a Redis URL needs a build with `--features redis`".to_string());
+            } else if !(url.expose().starts_with("redis://") || url.expose().starts_with("rediss://")) {
+                errors.push("Redis URL must start with redis:// or rediss://".to_string());
+            }
+        }
+        if self.cluster_port.is_some() && !cfg!(feature = "cluster") {
+            errors.push("a cluster port needs a build with `--features cluster`".to_string());
+        }
+        if self.cluster_port == Some(0) {
+            errors.push("cluster port must not be 0".to_string());
+        }
+        if !self.cluster_peers.is_empty() && self.cluster_port.is_none() {
+            errors.push("cluster peers need a cluster port".to_string());
+        }
+        if self.encryption_key.is_some() && cfg!(target_arch = "wasm32") {
+            errors.push("encryption at rest is only available in native builds".to_string());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = crate::encryption::Keyring::from_config(self) {
+            errors.push(format!("invalid {}", e));
+        }
+        if self.encryption_old_keys.is_some() && self.encryption_key.is_none() {
+            errors.push("previous encryption keys need an encryption key".to_string());
+        }
+        if let Some(url) = self.update_url.as_deref().filter(|url| !is_http_url(url)) {
+            errors.push(format!("update URL {:?} must start with http:// or https://", url));
+        }
+        if self.blob_store != "local" {
+            match self.blob_store.strip_prefix("s3://") {
+                Some(location) if !location.split('/').next().unwrap_or_default().is_empty() => {
+                    if !cfg!(feature = "s3") {
+                        errors.push("an s3:// blob store needs a build with `--features s3`".to_string());
+                    }
+                }
+                _ => errors.push(format!("blob store {:?} must be `local` or s3://bucket[/prefix]", self.blob_store)),
+            }
+        }
+        if let Some(url) = self.s3_endpoint.as_deref().filter(|url| !is_http_url(url)) {
+            errors.push(format!("S3 endpoint {:?} must start with http:// or https://", url));
+        }
+        if !is_http_url(&self.ollama_url) {
+            errors.push(format!("Ollama URL {:?} must start with http:// or https://", self.ollama_url));
+        }
+        if self.ollama_model.trim().is_empty() {
+            errors.push("Ollama model must not be empty".to_string());
+        }
+        if self.ollama_embed_model.trim().is_empty() {
+            errors.push("Ollama embedding model must not be empty".to_string());
+        }
+        for (index, pattern) in self.redaction_patterns.iter().enumerate() {
+            if let Err(e) = pattern.validate() {
+                errors.push(format!("redaction pattern {:?}: {}", pattern.name, e));
+            }
+            if self.redaction_patterns[..index].iter().any(|other| other.name == pattern.name) {
+                errors.push(format!("duplicate redaction pattern {:?}", pattern.name));
+            }
+        }
+        for (index, channel) in self.notifications.iter().enumerate() {
+            if let Err(e) = channel.validate() {
+                errors.push(format!("notification channel {:?}: {}", channel.name, e));
+            }
+            if self.notifications[..index].iter().any(|other| other.name == channel.name) {
+                errors.push(format!("duplicate notification channel {:?}", channel.name));
+            }
+        }
+        for rule in &self.rules {
+            if let Err(e) = rule.validate() {
+                errors.push(format!("rule {:?}: {}", rule.id, e));
+            }
+        }
+        for (agent_id, persona) in &self.personas {
+            if let Err(e) = persona.validate() {
+                errors.push(format!("persona of Agent{}: {}", agent_id, e));
+            }
+        }
+        for dir in &self.watch_dirs {
+            if let Err(e) = dir.validate() {
+                errors.push(format!("watched directory {:?}: {}", dir.path, e));
+            }
+        }
+        for (index, source) in self.poll_sources.iter().enumerate() {
+            if let Err(e) = source.validate() {
+                errors.push(format!("poll source {:?}: {}", source.name, e));
+            }
+            if self.poll_sources[..index].iter().any(|other| other.name == source.name) {
+                errors.push(format!("duplicate poll source {:?}", source.name));
+            }
+        }
+        for (index, source) in self.mail_sources.iter().enumerate() {
+            if let Err(e) = source.validate() {
+                errors.push(format!("mail source {:?}: {}", source.name, e));
+            }
+            if self.mail_sources[..index].iter().any(|other| other.name == source.name) {
+                errors.push(format!("duplicate mail source {:?}", source.name));
+            }
+        }
+        errors
+    }
+
+    /// Read the configuration at startup, reporting problems and using defaults where possible
+    pub fn from_env() -> Self {
+        let (mut config, errors) = Self::load();
+        for error in errors {
+            // Logging reads this configuration, so report directly
+            eprintln!("[Config] Configuration problem: {}", error);
+        }
+        if config.agents == 0 {
+            config.agents = crate::agents::DEFAULT_AGENT_COUNT;
+        }
+        if config.agent_restart_backoff.is_zero() {
+            config.agent_restart_backoff = Duration::from_millis(100);
+        }
+        if config.summary_interval.is_zero() {
+            config.summary_interval = Duration::from_secs(60);
+        }
+        if config.summary_keep_recent >= config.summary_max_history {
+            config.summary_max_history = 200;
+            config.summary_keep_recent = 20;
+        }
+        if config.summary_chunk_size == 0 {
+            config.summary_chunk_size = 25;
+        }
+        if config.cycle_min_interval.is_zero() || config.cycle_min_interval > config.cycle_max_interval {
+            config.cycle_min_interval = Duration::from_millis(20);
+            config.cycle_max_interval = Duration::from_millis(500);
+        }
+        if config.worker_threads == Some(0) {
+            config.worker_threads = None;
+        }
+        if config.model_pool_size == 0 {
+            config.model_pool_size = 2;
+        }
+        if config.proxy_hops == 0 {
+            config.proxy_hops = 1;
+        }
+        config
+    }
+
+    /// Keep `current`'s value of the settings that only apply after a restart,
+    /// returning the changed settings that take effect now and those that
+    /// need a restart
+    ///
+    /// `current` is destructured without `..`, so a new field doesn't compile
+    /// until it is listed here as live or restart-only.
+    fn merge_reload(&mut self, current: Self) -> (Vec<&'static str>, Vec<&'static str>) {
+        let next = self;
+        let mut changed = Vec::new();
+        let mut restart_required = Vec::new();
+        let ServiceConfig {
+            data_dir, database_url, db_pool_size, redis_url, cluster_port, cluster_peers,
+            cluster_cookie, cluster_routing, encryption_key, encryption_old_keys,
+            encrypted_collections, agents, monitor, agent_roles, agent_restart,
+            agent_restart_backoff, mailbox_capacity, processing_delay, agent_settings, agents_file,
+            summary_interval, summary_max_history, summary_keep_recent, summary_chunk_size,
+            cycle_min_interval, cycle_max_interval, cycle_align, timezone, log_format, trust_proxy,
+            proxy_hops, worker_threads, compute_threads, pin_background, model_pool_size, lstm_impl, update_url, compression_threshold, max_body_size,
+            body_limits, blob_store, s3_endpoint, s3_region, ollama_url, ollama_model,
+            ollama_embed_model, ollama_agent_model, ollama_api_key, llm_pool_max_idle, llm_http2,
+            warm_llm, extraction_mode, redaction, redaction_local_models, redaction_patterns,
+            retention, notifications, rules, personas, watch_dirs, poll_sources, mail_sources,
+        } = current;
+        macro_rules! live {
+            ($($field:ident),* $(,)?) => {$(
+                if next.$field != $field {
+                    changed.push(stringify!($field));
+                }
+            )*};
+        }
+        macro_rules! restart_only {
+            ($($field:ident),* $(,)?) => {$(
+                if next.$field != $field {
+                    restart_required.push(stringify!($field));
+                    next.$field = $field;
+                }
+            )*};
+        }
+        live!(
+            cluster_routing, summary_interval, summary_max_history, summary_keep_recent,
+            summary_chunk_size, cycle_min_interval, cycle_max_interval, cycle_align, timezone,
+            log_format, trust_proxy, proxy_hops, model_pool_size, lstm_impl, update_url, max_body_size, body_limits, ollama_url, ollama_model,
+            ollama_embed_model, ollama_agent_model, ollama_api_key, extraction_mode, redaction,
+            redaction_local_models, redaction_patterns, retention, notifications, rules, personas,
+        );
+        // Apply to agents started afterwards
+        live!(
+            agent_roles, agent_restart, agent_restart_backoff, mailbox_capacity, processing_delay,
+            agent_settings, agents_file,
+        );
+        restart_only!(
+            data_dir, database_url, db_pool_size, redis_url, cluster_port, cluster_peers,
+            cluster_cookie, encryption_key, encryption_old_keys, encrypted_collections, agents,
+            monitor, worker_threads, compute_threads, pin_background, blob_store, s3_endpoint,
+            s3_region, llm_pool_max_idle, llm_http2, warm_llm, watch_dirs, poll_sources,
+            mail_sources, compression_threshold,
+        );
+        (changed, restart_required)
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+static CONFIG: OnceLock<RwLock<Arc<ServiceConfig>>> = OnceLock::new();
+
+fn current() -> &'static RwLock<Arc<ServiceConfig>> {
+    CONFIG.get_or_init(|| RwLock::new(Arc::new(ServiceConfig::from_env())))
+}
+
+/// The process-wide configuration (a snapshot; hold it only briefly)
+pub fn config() -> Arc<ServiceConfig> {
+    current().read().unwrap().clone()
+}
+
+/// The running configuration with secrets and webhook URLs left out, for crash reports
+///
+/// Does not wait for the configuration lock, so it is safe to call from a
+/// panic hook; returns `null` if the lock is busy.
+pub fn summary() -> serde_json::Value {
+    let Ok(config) = current().try_read() else {
+        return serde_json::Value::Null;
+    };
+    // Built key by key: one `json!` literal this size exceeds the macro recursion limit
+    let mut summary = serde_json::Map::new();
+    summary.insert("data_dir".into(), serde_json::json!(config.data_dir));
+    summary.insert("database_url".into(), serde_json::json!(config.database_url.as_ref().map(|_| "[redacted]")));
+    summary.insert("db_pool_size".into(), serde_json::json!(config.db_pool_size));
+    summary.insert("redis_url".into(), serde_json::json!(config.redis_url.as_ref().map(|_| "[redacted]")));
+    summary.insert("cluster_port".into(), serde_json::json!(config.cluster_port));
+    summary.insert("cluster_peers".into(), serde_json::json!(config.cluster_peers));
+    summary.insert("cluster_cookie".into(), serde_json::json!(config.cluster_cookie.as_ref().map(|_| "[redacted]")));
+    summary.insert("cluster_routing".into(), serde_json::json!(config.cluster_routing.name()));
+    summary.insert("encryption_key".into(), serde_json::json!(config.encryption_key.as_ref().map(|_| "[redacted]")));
+    summary.insert("encryption_old_keys".into(), serde_json::json!(config.encryption_old_keys.as_ref().map(|_| "[redacted]")));
+    summary.insert("encrypted_collections".into(), serde_json::json!(config.encrypted_collections));
+    summary.insert("agents".into(), serde_json::json!(config.agents));
+    summary.insert("monitor".into(), serde_json::json!(config.monitor));
+    summary.insert("agent_roles".into(), serde_json::json!(config.agent_roles.iter().map(|(id, role)| (id.to_string(), role.name())).collect::<BTreeMap<_, _>>()));
+    summary.insert("agent_restart".into(), serde_json::json!(config.agent_restart));
+    summary.insert("agent_restart_backoff_ms".into(), serde_json::json!(config.agent_restart_backoff.as_millis() as u64));
+    summary.insert("mailbox_capacity".into(), serde_json::json!(config.mailbox_capacity));
+    summary.insert("processing_delay_ms".into(), serde_json::json!(config.processing_delay.as_millis() as u64));
+    summary.insert("agent_settings".into(), serde_json::json!(config.agent_settings.iter().map(|(id, settings)| (id.to_string(), serde_json::json!({
+            "mailbox_capacity": settings.mailbox_capacity,
+            "processing_delay_ms": settings.processing_delay.map(|delay| delay.as_millis() as u64),
+        }))).collect::<BTreeMap<_, _>>()));
+    summary.insert("agents_file".into(), serde_json::json!(config.agents_file));
+    summary.insert("summary_interval_secs".into(), serde_json::json!(config.summary_interval.as_secs()));
+    summary.insert("summary_max_history".into(), serde_json::json!(config.summary_max_history));
+    summary.insert("summary_keep_recent".into(), serde_json::json!(config.summary_keep_recent));
+    summary.insert("summary_chunk_size".into(), serde_json::json!(config.summary_chunk_size));
+    summary.insert("cycle_min_interval_ms".into(), serde_json::json!(config.cycle_min_interval.as_millis() as u64));
+    summary.insert("cycle_max_interval_ms".into(), serde_json::json!(config.cycle_max_interval.as_millis() as u64));
+    summary.insert("cycle_align".into(), serde_json::json!(config.cycle_align));
+    summary.insert("timezone".into(), serde_json::json!(config.timezone.name()));
+    summary.insert("log_format".into(), serde_json::json!(format!("{:?}", config.log_format)));
+    summary.insert("trust_proxy".into(), serde_json::json!(config.trust_proxy));
+    summary.insert("proxy_hops".into(), serde_json::json!(config.proxy_hops));
+    summary.insert("worker_threads".into(), serde_json::json!(config.worker_threads));
+    summary.insert("compute_threads".into(), serde_json::json!(config.compute_threads));
+    summary.insert("pin_background".into(), serde_json::json!(config.pin_background));
+    summary.insert("model_pool_size".into(), serde_json::json!(config.model_pool_size));
+    summary.insert("lstm_impl".into(), serde_json::json!(config.lstm_impl.name()));
+    summary.insert("update_url".into(), serde_json::json!(config.update_url));
+    summary.insert("compression_threshold".into(), serde_json::json!(config.compression_threshold));
+    summary.insert("max_body_size".into(), serde_json::json!(config.max_body_size));
+    summary.insert("body_limits".into(), serde_json::json!(config.body_limits));
+    summary.insert("blob_store".into(), serde_json::json!(config.blob_store));
+    summary.insert("s3_endpoint".into(), serde_json::json!(config.s3_endpoint));
+    summary.insert("s3_region".into(), serde_json::json!(config.s3_region));
+    summary.insert("ollama_url".into(), serde_json::json!(config.ollama_url));
+    summary.insert("ollama_model".into(), serde_json::json!(config.ollama_model));
+    summary.insert("ollama_embed_model".into(), serde_json::json!(config.ollama_embed_model));
+    summary.insert("ollama_agent_model".into(), serde_json::json!(config.ollama_agent_model));
+    summary.insert("ollama_api_key".into(), serde_json::json!(config.ollama_api_key.as_ref().map(|_| "[redacted]")));
+    summary.insert("llm_pool_max_idle".into(), serde_json::json!(config.llm_pool_max_idle));
+    summary.insert("llm_http2".into(), serde_json::json!(config.llm_http2));
+    summary.insert("warm_llm".into(), serde_json::json!(config.warm_llm));
+    summary.insert("extraction_mode".into(), serde_json::json!(format!("{:?}", config.extraction_mode)));
+    summary.insert("redaction".into(), serde_json::json!(config.redaction));
+    summary.insert("redaction_local_models".into(), serde_json::json!(config.redaction_local_models));
+    summary.insert("redaction_patterns".into(), serde_json::json!(config.redaction_patterns.iter().map(|pattern| pattern.name.as_str()).collect::<Vec<_>>()));
+    summary.insert("retention".into(), serde_json::json!(config.retention.iter().map(|(class, age)| (class.name(), age.as_secs())).collect::<BTreeMap<_, _>>()));
+    summary.insert("notifications".into(), serde_json::json!(config.notifications.iter().map(|channel| channel.name.as_str()).collect::<Vec<_>>()));
+    summary.insert("rules".into(), serde_json::json!(config.rules.len()));
+    summary.insert("personas".into(), serde_json::json!(config.personas.keys().collect::<Vec<_>>()));
+    summary.insert("watch_dirs".into(), serde_json::json!(config.watch_dirs.iter().map(|dir| dir.path.as_str()).collect::<Vec<_>>()));
+    summary.insert("poll_sources".into(), serde_json::json!(config.poll_sources.iter().map(|source| source.name.as_str()).collect::<Vec<_>>()));
+    summary.insert("mail_sources".into(), serde_json::json!(config.mail_sources.iter().map(|source| source.name.as_str()).collect::<Vec<_>>()));
+    serde_json::Value::Object(summary)
+}
+
+/// Problems with the current environment and config file, without applying them
+pub fn problems() -> Vec<String> {
+    ServiceConfig::load().1
+}
+
+/// Outcome of a successful reload
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadReport {
+    /// Settings now in effect
+    pub changed: Vec<&'static str>,
+    /// Settings that changed but keep their old value until restart
+    pub restart_required: Vec<&'static str>,
+    /// Rules from the config file saved to the rules collection
+    pub rules_saved: usize,
+    /// Personas from the config file saved to the personas collection
+    pub personas_saved: usize,
+}
+
+/// Re-read environment and config file and swap in the new configuration
+///
+/// Nothing changes unless every value is valid. Settings that only apply at
+/// startup keep their running value. Publishes `config.reloaded`.
+pub fn reload() -> Result<ReloadReport, Vec<String>> {
+    let (mut next, errors) = ServiceConfig::load();
+    if !errors.is_empty() {
+        log_error!("[Config] Reload rejected: {}", errors.join("; "));
+        return Err(errors);
+    }
+
+    let mut slot = current().write().unwrap();
+    let (changed, restart_required) = next.merge_reload((**slot).clone());
+    *slot = Arc::new(next);
+    let next = slot.clone();
+    drop(slot);
+
+    if ["ollama_url", "ollama_model", "ollama_api_key"].iter().any(|name| changed.contains(name)) {
+        crate::connections::reload_default_provider(&next);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if changed.contains(&"lstm_impl") {
+        crate::lstm::unload();
+    }
+    let rules_saved = save_config_rules(&next);
+    let personas_saved = save_config_personas(&next);
+    let report = ReloadReport { changed, restart_required, rules_saved, personas_saved };
+    log_info!(
+        "[Config] Reloaded: changed {:?}, restart required for {:?}",
+        report.changed, report.restart_required
+    );
+    crate::events::publish("config.reloaded", serde_json::json!(report));
+    Ok(report)
+}
+
+/// Save the rules declared in the config file, returning how many were saved
+#[cfg(not(target_arch = "wasm32"))]
+fn save_config_rules(config: &ServiceConfig) -> usize {
+    config.rules.iter()
+        .filter(|rule| match crate::rule_store::save_rule(rule, "config") {
+            Ok(()) => true,
+            Err(e) => {
+                log_error!("[Config] Failed to save rule {}: {}", rule.id, e);
+                false
+            }
+        })
+        .count()
+}
+
+/// Rules are saved on the server
+#[cfg(target_arch = "wasm32")]
+fn save_config_rules(_config: &ServiceConfig) -> usize {
+    0
+}
+
+/// Save the personas declared in the config file, returning how many were saved
+fn save_config_personas(config: &ServiceConfig) -> usize {
+    config.personas.iter()
+        .filter(|(agent_id, persona)| match crate::personas::set_persona(**agent_id, (*persona).clone()) {
+            Ok(_) => true,
+            Err(e) => {
+                log_error!("[Config] Failed to save persona of Agent{}: {}", agent_id, e);
+                false
+            }
+        })
+        .count()
+}
+
+/// Save config-file rules and personas and reload on SIGHUP (once per process)
+pub fn ensure_reload_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        save_config_rules(&config());
+        save_config_personas(&config());
+
+        #[cfg(unix)]
+        tokio::spawn(async {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    log_error!("[Config] Failed to install SIGHUP handler: {}", e);
+                    return;
+                }
+            };
+            while hangups.recv().await.is_some() {
+                log_info!("[Config] SIGHUP received, reloading configuration");
+                let _ = reload();
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The configuration of the test environment, which is expected to be valid
+    fn valid() -> ServiceConfig {
+        let (config, _) = ServiceConfig::load();
+        assert_eq!(config.validate(), Vec::<String>::new());
+        config
+    }
+
+    fn errors_after(change: impl FnOnce(&mut ServiceConfig)) -> Vec<String> {
+        let mut config = valid();
+        change(&mut config);
+        config.validate()
+    }
+
+    fn rejected(change: impl FnOnce(&mut ServiceConfig), expected: &str) {
+        let errors = errors_after(change);
+        assert!(errors.iter().any(|e| e.contains(expected)), "expected {:?} in {:?}", expected, errors);
+    }
+
+    #[test]
+    fn unusable_values_are_rejected() {
+        rejected(|c| c.agents = 0, "at least one agent");
+        rejected(|c| c.agent_restart_backoff = Duration::ZERO, "restart backoff");
+        rejected(|c| c.summary_keep_recent = c.summary_max_history, "keep-recent");
+        rejected(|c| c.summary_chunk_size = 0, "chunk size");
+        rejected(|c| c.cycle_min_interval = c.cycle_max_interval + Duration::from_millis(1), "must not exceed");
+        rejected(|c| c.proxy_hops = 0, "proxy hops");
+        rejected(|c| c.redis_url = Some(Secret::new("http://localhost:6379")), "Redis URL");
+        rejected(|c| c.cluster_peers = vec!["10.0.0.2:4697".to_string()], "cluster peers need a cluster port");
+        rejected(|c| c.encryption_old_keys = Some(Secret::new("old")), "previous encryption keys");
+        rejected(|c| c.update_url = Some("ftp://updates".to_string()), "update URL");
+        rejected(|c| c.blob_store = "s3://".to_string(), "blob store");
+        rejected(|c| c.ollama_url = "localhost:11434".to_string(), "Ollama URL");
+        rejected(|c| c.ollama_model = " ".to_string(), "Ollama model");
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let errors = errors_after(|c| {
+            c.agents = 0;
+            c.proxy_hops = 0;
+            c.model_pool_size = 0;
+        });
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+    }
+
+    #[test]
+    fn reloading_applies_live_settings_and_keeps_restart_only_ones() {
+        let current = valid();
+        let mut next = current.clone();
+        next.summary_interval += Duration::from_secs(1);
+        next.proxy_hops += 1;
+        next.mailbox_capacity += 1;
+        next.data_dir = format!("{}-elsewhere", current.data_dir);
+        next.agents = current.agents.wrapping_add(1).max(1);
+
+        let (changed, restart_required) = next.merge_reload(current.clone());
+        assert_eq!(changed, ["summary_interval", "proxy_hops", "mailbox_capacity"]);
+        assert_eq!(restart_required, ["data_dir", "agents"]);
+        assert_eq!(next.summary_interval, current.summary_interval + Duration::from_secs(1));
+        assert_eq!(next.mailbox_capacity, current.mailbox_capacity + 1);
+        assert_eq!(next.data_dir, current.data_dir, "restart-only settings keep their running value");
+        assert_eq!(next.agents, current.agents);
+    }
+
+    #[test]
+    fn reloading_an_unchanged_configuration_changes_nothing() {
+        let current = valid();
+        let (changed, restart_required) = current.clone().merge_reload(current);
+        assert!(changed.is_empty() && restart_required.is_empty());
+    }
+}
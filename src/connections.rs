@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::config::ServiceConfig;
+use crate::deadline;
+use crate::events::publish;
+use crate::secrets::Secret;
+use crate::telemetry::{in_span, instruments};
+use opentelemetry::KeyValue;
+
+// ============================================================================
+// LLM Provider Connections
+// ============================================================================
+
+/// Default address of a local Ollama server
+pub const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
+/// Default model used when none is configured
+pub const DEFAULT_OLLAMA_MODEL: &str = "llama3.2";
+/// Default model for `OllamaProvider::embed`
+pub const DEFAULT_OLLAMA_EMBED_MODEL: &str = "nomic-embed-text";
+
+/// Boxed future returned by `LlmProvider` methods
+pub type LlmFuture<'a, T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'a>>;
+
+/// A text-generation backend (Ollama in production, scripted mocks in tests)
+pub trait LlmProvider: Send + Sync {
+    /// Model name, used in logs and telemetry
+    fn model(&self) -> &str;
+    /// Send a single prompt and return the generated text
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String>;
+    /// Send a prompt in JSON mode and parse the structured output
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value>;
+
+    /// `generate` as `persona`; backends without system prompts or options prepend the system prompt
+    fn generate_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(async move { self.generate(&persona.prefixed(prompt)).await })
+    }
+
+    /// `generate_json` as `persona`
+    fn generate_json_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(async move { self.generate_json(&persona.prefixed(prompt)).await })
+    }
+}
+
+/// How one agent talks to the LLM: system prompt, model and sampling temperature
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    #[serde(default)]
+    pub system_prompt: String,
+    /// Model other than the provider's
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+impl Persona {
+    /// Check the persona before saving it
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model.as_deref().is_some_and(|model| model.trim().is_empty()) {
+            return Err("model must not be empty when set".to_string());
+        }
+        if self.temperature.is_some_and(|temperature| !(0.0..=2.0).contains(&temperature)) {
+            return Err("temperature must be between 0 and 2".to_string());
+        }
+        Ok(())
+    }
+
+    /// `prompt` preceded by the system prompt, for backends without a separate one
+    pub fn prefixed(&self, prompt: &str) -> String {
+        if self.system_prompt.trim().is_empty() {
+            prompt.to_string()
+        } else {
+            format!("{}\n\n{}", self.system_prompt.trim(), prompt)
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Shared HTTP client
+// ----------------------------------------------------------------------------
+//
+// Every `OllamaProvider` sends through one `reqwest::Client`, so providers
+// built per call (`from_config`, documents, Python bindings) reuse the same
+// pool of keep-alive connections instead of opening a new one per request.
+// HTTPS servers that offer HTTP/2 get it through ALPN, with keep-alive pings
+// on idle connections; `llm_http2` forces it for plain `http://` servers
+// behind an h2c proxy (Ollama itself only speaks HTTP/1.1).
+
+/// How long an idle pooled connection is kept
+const POOL_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
+/// Interval of TCP and HTTP/2 keep-alive probes
+const KEEP_ALIVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The client shared by all LLM providers, built from the configuration at first use
+pub fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| build_client(&crate::config::config()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn build_client(config: &ServiceConfig) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .pool_max_idle_per_host(config.llm_pool_max_idle)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(KEEP_ALIVE_INTERVAL)
+        .http2_keep_alive_interval(KEEP_ALIVE_INTERVAL)
+        .http2_keep_alive_while_idle(true)
+        .http2_adaptive_window(true);
+    if config.llm_http2 {
+        builder = builder.http2_prior_knowledge();
+    }
+    builder.build().unwrap_or_else(|e| {
+        log_warn!("[LLM] Failed to build the pooled HTTP client, using defaults: {}", e);
+        reqwest::Client::new()
+    })
+}
+
+/// The browser pools connections itself
+#[cfg(target_arch = "wasm32")]
+fn build_client(_config: &ServiceConfig) -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+static IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+static PEAK_IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+static RESPONSES: AtomicU64 = AtomicU64::new(0);
+static HTTP2_RESPONSES: AtomicU64 = AtomicU64::new(0);
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Traffic through the shared client since startup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Requests waiting for a response
+    pub in_flight: u64,
+    /// Most requests waiting at once
+    pub peak_in_flight: u64,
+    /// Responses received, whatever their status
+    pub responses: u64,
+    /// Responses that came over HTTP/2
+    pub http2_responses: u64,
+    /// Requests that got no response (connection refused, reset, timed out)
+    pub failures: u64,
+    /// Idle connections kept per host
+    pub max_idle_per_host: usize,
+    /// Whether HTTP/2 is used without negotiation
+    pub http2_prior_knowledge: bool,
+}
+
+/// Current counters of the shared client
+pub fn pool_stats() -> PoolStats {
+    let config = crate::config::config();
+    PoolStats {
+        in_flight: IN_FLIGHT.load(Ordering::Relaxed),
+        peak_in_flight: PEAK_IN_FLIGHT.load(Ordering::Relaxed),
+        responses: RESPONSES.load(Ordering::Relaxed),
+        http2_responses: HTTP2_RESPONSES.load(Ordering::Relaxed),
+        failures: FAILURES.load(Ordering::Relaxed),
+        max_idle_per_host: config.llm_pool_max_idle,
+        http2_prior_knowledge: config.llm_http2,
+    }
+}
+
+/// Counts a request as in flight until dropped, also when its deadline cancels it
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        let in_flight = IN_FLIGHT.fetch_add(1, Ordering::Relaxed) + 1;
+        PEAK_IN_FLIGHT.fetch_max(in_flight, Ordering::Relaxed);
+        instruments().llm_http_in_flight.add(1, &[]);
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+        instruments().llm_http_in_flight.add(-1, &[]);
+    }
+}
+
+/// Send `builder` and record the response's HTTP version and status
+async fn send_counted(builder: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let _in_flight = InFlight::start();
+    let result = builder.send().await;
+    let attributes = match &result {
+        Ok(response) => {
+            RESPONSES.fetch_add(1, Ordering::Relaxed);
+            if response.version() == reqwest::Version::HTTP_2 {
+                HTTP2_RESPONSES.fetch_add(1, Ordering::Relaxed);
+            }
+            [
+                KeyValue::new("version", format!("{:?}", response.version())),
+                KeyValue::new("status", i64::from(response.status().as_u16())),
+            ]
+        }
+        Err(_) => {
+            FAILURES.fetch_add(1, Ordering::Relaxed);
+            [KeyValue::new("version", "none"), KeyValue::new("status", "error")]
+        }
+    };
+    instruments().llm_http_responses.add(1, &attributes);
+    result
+}
+
+/// Client for the Ollama `/api/generate` endpoint
+#[derive(Debug, Clone)]
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    /// Base URL of the Ollama server (without trailing slash)
+    pub base_url: String,
+    /// Model name passed to every request
+    pub model: String,
+    /// Bearer token sent with every request, if set
+    pub api_key: Option<Secret>,
+}
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<GenerateOptions>,
+}
+
+#[derive(Serialize)]
+struct GenerateOptions {
+    temperature: f32,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+impl OllamaProvider {
+    /// Create a provider for the given server and model, on the shared connection pool
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            client: http_client().clone(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+            api_key: None,
+        }
+    }
+
+    /// Authenticate requests with a bearer token
+    pub fn with_api_key(mut self, api_key: Secret) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    /// Create a provider from the configured Ollama URL and model (`OLLAMA_URL` / `OLLAMA_MODEL`)
+    pub fn from_env() -> Self {
+        Self::from_config(&crate::config::config())
+    }
+
+    /// Create a provider from the given configuration
+    pub fn from_config(config: &ServiceConfig) -> Self {
+        let provider = Self::new(config.ollama_url.as_str(), config.ollama_model.as_str());
+        match &config.ollama_api_key {
+            Some(api_key) => provider.with_api_key(api_key.clone()),
+            None => provider,
+        }
+    }
+
+    /// Send a single prompt and return the generated text
+    pub async fn generate(&self, prompt: &str) -> anyhow::Result<String> {
+        self.request(prompt, None, None).await
+    }
+
+    /// Send a prompt in JSON mode and parse the structured output
+    pub async fn generate_json(&self, prompt: &str) -> anyhow::Result<serde_json::Value> {
+        let text = self.request(prompt, Some("json"), None).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Embed `inputs` with `model` (`/api/embed`), one vector per input
+    pub async fn embed(&self, model: &str, inputs: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut builder = self.client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&EmbedRequest { model, input: inputs });
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key.expose());
+        }
+        let response = deadline::enforce(async {
+            Ok::<_, anyhow::Error>(send_counted(builder).await?.error_for_status()?.json::<EmbedResponse>().await?)
+        }).await?;
+        anyhow::ensure!(
+            response.embeddings.len() == inputs.len(),
+            "{} returned {} embeddings for {} inputs", model, response.embeddings.len(), inputs.len()
+        );
+        Ok(response.embeddings)
+    }
+
+    async fn request(&self, prompt: &str, format: Option<&str>, persona: Option<&Persona>) -> anyhow::Result<String> {
+        let model = persona.and_then(|persona| persona.model.as_deref()).unwrap_or(&self.model);
+        let started = std::time::Instant::now();
+        let attributes = vec![
+            KeyValue::new("llm.model", model.to_string()),
+            KeyValue::new("llm.format", format.unwrap_or("text").to_string()),
+        ];
+        // Cancelled when the deadline of the calling request passes
+        let result = in_span("llm.generate", attributes, deadline::enforce(self.send(model, prompt, format, persona))).await;
+
+        let elapsed = started.elapsed();
+        let status = if result.is_ok() { "ok" } else { "error" };
+        let attributes = [KeyValue::new("model", model.to_string()), KeyValue::new("status", status)];
+        let instruments = instruments();
+        instruments.llm_requests.add(1, &attributes);
+        instruments.llm_duration.record(elapsed.as_secs_f64(), &attributes);
+        publish("llm.request", serde_json::json!({
+            "model": model,
+            "ok": result.is_ok(),
+            "duration_ms": elapsed.as_millis() as u64,
+        }));
+        result
+    }
+
+    async fn send(&self, model: &str, prompt: &str, format: Option<&str>, persona: Option<&Persona>) -> anyhow::Result<String> {
+        #[cfg(feature = "chaos")]
+        if let Some(delay) = crate::chaos::take_llm_timeout() {
+            tokio::time::sleep(delay).await;
+            anyhow::bail!("chaos: injected LLM timeout after {:?}", delay);
+        }
+
+        let request = GenerateRequest {
+            model,
+            prompt,
+            stream: false,
+            format,
+            system: persona.map(|persona| persona.system_prompt.trim()).filter(|system| !system.is_empty()),
+            options: persona.and_then(|persona| persona.temperature).map(|temperature| GenerateOptions { temperature }),
+        };
+
+        let mut builder = self.client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&request);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key.expose());
+        }
+        let response = send_counted(builder)
+            .await?
+            .error_for_status()?
+            .json::<GenerateResponse>()
+            .await?;
+
+        Ok(response.response.trim().to_string())
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(OllamaProvider::generate(self, prompt))
+    }
+
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(OllamaProvider::generate_json(self, prompt))
+    }
+
+    fn generate_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(self.request(prompt, None, Some(persona)))
+    }
+
+    fn generate_json_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(async move {
+            let text = self.request(prompt, Some("json"), Some(persona)).await?;
+            Ok(serde_json::from_str(&text)?)
+        })
+    }
+}
+
+/// A provider that sends every request as one persona
+pub struct PersonaProvider {
+    inner: Arc<dyn LlmProvider>,
+    persona: Persona,
+}
+
+impl PersonaProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, persona: Persona) -> Self {
+        Self { inner, persona }
+    }
+}
+
+impl LlmProvider for PersonaProvider {
+    fn model(&self) -> &str {
+        self.persona.model.as_deref().unwrap_or_else(|| self.inner.model())
+    }
+
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String> {
+        self.inner.generate_as(&self.persona, prompt)
+    }
+
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        self.inner.generate_json_as(&self.persona, prompt)
+    }
+}
+
+/// Shared provider; `from_config` providers follow configuration reloads
+struct DefaultProvider {
+    provider: Arc<dyn LlmProvider>,
+    from_config: bool,
+}
+
+static DEFAULT_PROVIDER: OnceLock<RwLock<DefaultProvider>> = OnceLock::new();
+
+fn provider_slot() -> &'static RwLock<DefaultProvider> {
+    DEFAULT_PROVIDER.get_or_init(|| RwLock::new(DefaultProvider {
+        provider: Arc::new(OllamaProvider::from_env()),
+        from_config: true,
+    }))
+}
+
+/// Get the process-wide LLM provider (Ollama from the configuration by default)
+pub fn default_provider() -> Arc<dyn LlmProvider> {
+    provider_slot().read().unwrap().provider.clone()
+}
+
+/// Replace the process-wide provider; fails if it was already used or set
+pub fn set_default_provider(provider: Box<dyn LlmProvider>) -> Result<(), Box<dyn LlmProvider>> {
+    let mut provider = Some(provider);
+    DEFAULT_PROVIDER.get_or_init(|| RwLock::new(DefaultProvider {
+        provider: Arc::from(provider.take().unwrap()),
+        from_config: false,
+    }));
+    provider.map_or(Ok(()), Err)
+}
+
+/// Point the configured Ollama provider at reloaded settings; explicitly set providers are kept
+pub(crate) fn reload_default_provider(config: &ServiceConfig) {
+    let Some(slot) = DEFAULT_PROVIDER.get() else {
+        return;
+    };
+    let mut current = slot.write().unwrap();
+    if current.from_config {
+        current.provider = Arc::new(OllamaProvider::from_config(config));
+    }
+}
+
+/// The provider for `agent_id`: the default one, speaking as the agent's persona if it has one
+///
+/// Its calls are recorded as generations that users can give feedback on,
+/// and its prompts are redacted unless the model runs locally (see `redaction`).
+/// `llm` agents use `ollama_agent_model` unless their persona names a model.
+pub fn agent_provider(agent_id: u8) -> Arc<dyn LlmProvider> {
+    let provider: Arc<dyn LlmProvider> = Arc::new(crate::redaction::RedactingProvider::new(default_provider()));
+    let agent_model = match crate::roles::role_of(agent_id) {
+        crate::roles::AgentRole::Llm => crate::config::config().ollama_agent_model.clone(),
+        _ => None,
+    };
+    let persona = match crate::personas::get_persona(agent_id) {
+        Ok(entry) => entry.map(|entry| entry.persona),
+        Err(e) => {
+            log_warn!("[Personas] Failed to load persona of Agent{}: {}", agent_id, e);
+            None
+        }
+    };
+    let persona = match (persona, agent_model) {
+        (Some(mut persona), Some(model)) => {
+            if persona.model.is_none() {
+                persona.model = Some(model);
+            }
+            Some(persona)
+        }
+        (None, Some(model)) => Some(Persona { model: Some(model), ..Persona::default() }),
+        (persona, None) => persona,
+    };
+    let (provider, system_prompt): (Arc<dyn LlmProvider>, _) = match persona {
+        Some(persona) => {
+            let system_prompt = Some(persona.system_prompt.clone()).filter(|prompt| !prompt.is_empty());
+            (Arc::new(PersonaProvider::new(provider, persona)), system_prompt)
+        }
+        None => (provider, None),
+    };
+    Arc::new(crate::feedback::RecordingProvider::new(provider, agent_id, system_prompt))
+}
@@ -0,0 +1,229 @@
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::MailSource;
+use crate::agents::{get_agent, send_work, AgentMessage};
+use crate::blobs;
+use crate::config::config;
+use crate::events::publish;
+use crate::payload::Payload;
+use crate::secrets::resolve;
+use crate::storage::{get_typed, put_typed};
+
+// ============================================================================
+// Email Connector
+// ============================================================================
+//
+// Polls the configured `mail_sources` over IMAP (TLS) and sends each new
+// message matching the source's filters to its agent as a JSON payload:
+//
+//     {"source": "alerts", "uid": 812, "message_id": "<...>", "from": "...",
+//      "to": "...", "subject": "...", "date": "...", "body": "...",
+//      "attachments": [{"name": "report.pdf", "content_type": "application/pdf",
+//                       "size": 48213, "blob": "<sha-256>"}]}
+//
+// Attachments are stored in the blob store, referenced by
+// `mail:<source>:<uid>`, and the agent gets their hashes
+// (`GET /api/blobs/:hash` returns the content). The body is the first
+// `text/plain` part, or the first `text/html` part if there is none.
+//
+// Messages are read with `BODY.PEEK[]`, so the mailbox is left untouched
+// (nothing is marked read). The last UID handled is kept in `mail_state`;
+// a source's first poll only records where the mailbox stands, so existing
+// mail is not replayed. If the server resets its UIDs (`UIDVALIDITY`
+// changes) the source starts over the same way.
+
+/// Collection holding the UID cursor per source, keyed by source name
+pub const MAIL_STATE_COLLECTION: &str = "mail_state";
+
+/// Messages fetched per poll; the rest wait for the next one
+const MAX_MESSAGES_PER_POLL: usize = 50;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct MailState {
+    uid_validity: u32,
+    last_uid: u32,
+}
+
+/// A message as fetched from the server
+struct RawMessage {
+    uid: u32,
+    data: Vec<u8>,
+}
+
+/// Start polling the configured mailboxes (once; restart to pick up changes)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        for source in config().mail_sources.clone() {
+            log_info!(
+                "[Connectors] Polling {}@{}/{} every {}s for Agent{}",
+                source.username, source.host, source.mailbox, source.interval_secs, source.agent
+            );
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(source.interval_secs));
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    if !crate::leader::is_leader() {
+                        continue;
+                    }
+                    match poll(&source).await {
+                        Ok((sent, skipped)) => {
+                            if sent > 0 {
+                                log_info!("[Connectors] {}: sent {} new messages to Agent{}", source.name, sent, source.agent);
+                            }
+                            publish("connector.ingested", json!({
+                                "connector": "email",
+                                "source": source.name,
+                                "agent": source.agent,
+                                "sent": sent,
+                                "skipped": skipped,
+                            }));
+                        }
+                        Err(e) => {
+                            log_warn!("[Connectors] Polling mailbox {} failed: {}", source.name, e);
+                            publish("connector.failed", json!({
+                                "connector": "email",
+                                "source": source.name,
+                                "error": e.to_string(),
+                            }));
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Fetch and send the new messages of one source; returns (sent, filtered out)
+async fn poll(source: &MailSource) -> anyhow::Result<(usize, usize)> {
+    let state = get_typed::<MailState>(MAIL_STATE_COLLECTION, &source.name)?;
+    let fetch_source = source.clone();
+    let (messages, next) = tokio::task::spawn_blocking(move || fetch(&fetch_source, state)).await??;
+
+    let mut sent = 0;
+    let mut skipped = 0;
+    for message in &messages {
+        match to_payload(source, message)? {
+            Some(payload) => {
+                crate::schemas::validate_payload(source.agent, &payload)?;
+                let actor_ref = get_agent(source.agent)
+                    .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", source.agent))?;
+                send_work(&actor_ref, AgentMessage::ProcessPayload { payload })
+                    .map_err(|e| anyhow::anyhow!("failed to queue payload for Agent{}: {}", source.agent, e))?;
+                sent += 1;
+            }
+            None => skipped += 1,
+        }
+    }
+    put_typed(MAIL_STATE_COLLECTION, &source.name, &next)?;
+    Ok((sent, skipped))
+}
+
+/// Fetch messages after the cursor; returns them with the cursor to save
+fn fetch(source: &MailSource, state: Option<MailState>) -> anyhow::Result<(Vec<RawMessage>, MailState)> {
+    let password = resolve(&source.password)?;
+    let tls = native_tls::TlsConnector::builder().build()?;
+    let client = imap::connect((source.host.as_str(), source.port), &source.host, &tls)?;
+    let mut session = client.login(&source.username, password.expose()).map_err(|(e, _)| e)?;
+
+    let mailbox = session.select(&source.mailbox)?;
+    let uid_validity = mailbox.uid_validity.unwrap_or(0);
+    let state = match state {
+        Some(state) if state.uid_validity == uid_validity => state,
+        // First poll (or reset UIDs): start from the newest message
+        _ => {
+            let last_uid = session.uid_search("ALL")?.into_iter().max().unwrap_or(0);
+            session.logout()?;
+            return Ok((Vec::new(), MailState { uid_validity, last_uid }));
+        }
+    };
+
+    // `N:*` always matches the newest message, even when it is below N
+    let mut uids: Vec<u32> = session.uid_search(format!("UID {}:*", state.last_uid + 1))?
+        .into_iter()
+        .filter(|uid| *uid > state.last_uid)
+        .collect();
+    uids.sort_unstable();
+    uids.truncate(MAX_MESSAGES_PER_POLL);
+
+    let mut messages = Vec::new();
+    if !uids.is_empty() {
+        let set = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+        for fetched in session.uid_fetch(set, "(UID BODY.PEEK[])")?.iter() {
+            if let (Some(uid), Some(data)) = (fetched.uid, fetched.body()) {
+                messages.push(RawMessage { uid, data: data.to_vec() });
+            }
+        }
+        messages.sort_by_key(|message| message.uid);
+    }
+    session.logout()?;
+
+    let last_uid = uids.last().copied().unwrap_or(state.last_uid);
+    Ok((messages, MailState { uid_validity, last_uid }))
+}
+
+fn contains_ignore_case(haystack: &str, needle: Option<&str>) -> bool {
+    needle.is_none_or(|needle| haystack.to_lowercase().contains(&needle.to_lowercase()))
+}
+
+/// JSON payload for a message, or `None` when the source's filters exclude it
+fn to_payload(source: &MailSource, message: &RawMessage) -> anyhow::Result<Option<Payload>> {
+    let parsed = mailparse::parse_mail(&message.data)?;
+    let header = |name: &str| parsed.headers.get_first_value(name).unwrap_or_default();
+    let (subject, from) = (header("Subject"), header("From"));
+    let matches = contains_ignore_case(&subject, source.subject_contains.as_deref())
+        && contains_ignore_case(&from, source.from_contains.as_deref());
+    if !matches {
+        return Ok(None);
+    }
+
+    let owner = format!("mail:{}:{}", source.name, message.uid);
+    let mut body: Option<(bool, String)> = None;
+    let mut attachments = Vec::new();
+    for part in parsed.parts() {
+        if !part.subparts.is_empty() {
+            continue;
+        }
+        let disposition = part.get_content_disposition();
+        let name = disposition.params.get("filename").or_else(|| part.ctype.params.get("name"));
+        let mimetype = part.ctype.mimetype.to_lowercase();
+        if disposition.disposition == DispositionType::Attachment || name.is_some() {
+            attachments.push(store_attachment(part, name.cloned().unwrap_or_default(), &owner)?);
+        } else if mimetype == "text/plain" && !body.as_ref().is_some_and(|(plain, _)| *plain) {
+            body = Some((true, part.get_body()?));
+        } else if mimetype == "text/html" && body.is_none() {
+            body = Some((false, part.get_body()?));
+        }
+    }
+
+    let document = json!({
+        "source": source.name,
+        "uid": message.uid,
+        "message_id": header("Message-ID"),
+        "from": from,
+        "to": header("To"),
+        "subject": subject,
+        "date": header("Date"),
+        "body": body.map(|(_, text)| text).unwrap_or_default(),
+        "attachments": attachments,
+    });
+    Ok(Some(Payload::binary("application/json", serde_json::to_vec(&document)?)))
+}
+
+/// Put an attachment in the blob store and describe it for the payload
+fn store_attachment(part: &ParsedMail, name: String, owner: &str) -> anyhow::Result<Value> {
+    let data = part.get_body_raw()?;
+    let blob = blobs::put(&data, &part.ctype.mimetype)?;
+    blobs::add_ref(&blob.hash, owner)?;
+    Ok(json!({
+        "name": name,
+        "content_type": blob.content_type,
+        "size": blob.size,
+        "blob": blob.hash,
+    }))
+}
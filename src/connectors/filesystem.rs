@@ -0,0 +1,248 @@
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::{WatchTarget, WatchedDir};
+use crate::blobs::hash_bytes;
+use crate::config::config;
+use crate::events::publish;
+use crate::storage::{get_typed, put_typed};
+use crate::timeseries::{self, AppendReport, Point, MAX_BATCH_POINTS};
+
+// ============================================================================
+// Filesystem Watcher
+// ============================================================================
+//
+// Watches the `watch_dirs` of the configuration. Files present at startup
+// and every file created or changed afterwards are ingested by target:
+//
+//   - documents: `<dir>/notes/runbook.md` becomes the document
+//     `notes/runbook.md` (re-ingesting only re-embeds changed chunks)
+//   - timeseries: `<dir>/cpu.usage.csv` appends its `ts,value` rows
+//     (milliseconds since the epoch; a header line is skipped) to the series
+//     `cpu.usage`. Files are expected to grow by appending, so only rows past
+//     the ones already ingested are sent; a file that shrank starts over.
+//
+// What was ingested from each file is kept in `watch_state`, so a restart
+// does not ingest unchanged files again. Editors fire several events per
+// save; events are collected for `DEBOUNCE` before files are read.
+
+/// Collection holding the ingestion state of each watched file, keyed by path hash
+pub const WATCH_STATE_COLLECTION: &str = "watch_state";
+
+/// Quiet period after the last filesystem event before files are ingested
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// What has been ingested from one file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FileState {
+    path: String,
+    /// Content hash at the last ingestion
+    hash: String,
+    /// CSV rows already appended
+    #[serde(default)]
+    rows: usize,
+}
+
+/// A watched directory with its path resolved
+struct Watched {
+    root: PathBuf,
+    dir: WatchedDir,
+}
+
+impl Watched {
+    /// Whether this directory is responsible for `path`
+    fn covers(&self, path: &Path) -> bool {
+        let in_dir = if self.dir.recursive {
+            path.starts_with(&self.root)
+        } else {
+            path.parent() == Some(self.root.as_path())
+        };
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        in_dir && self.dir.extensions().contains(&extension.as_str())
+    }
+}
+
+/// Start watching the configured directories (once; restart to pick up changes)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let dirs = config().watch_dirs.clone();
+        if dirs.is_empty() {
+            return;
+        }
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log_warn!("[Connectors] Filesystem watch error: {}", e),
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log_error!("[Connectors] Failed to start the filesystem watcher: {}", e);
+                return;
+            }
+        };
+
+        let mut watched = Vec::new();
+        for dir in dirs {
+            let root = match std::fs::canonicalize(&dir.path) {
+                Ok(root) => root,
+                Err(e) => {
+                    log_warn!("[Connectors] Not watching {}: {}", dir.path, e);
+                    continue;
+                }
+            };
+            let mode = if dir.recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+            if let Err(e) = watcher.watch(&root, mode) {
+                log_warn!("[Connectors] Not watching {}: {}", dir.path, e);
+                continue;
+            }
+            log_info!("[Connectors] Watching {} for {:?}", root.display(), dir.target);
+            watched.push(Watched { root, dir });
+        }
+
+        tokio::spawn(async move {
+            // Dropping the watcher would stop the events
+            let _watcher = watcher;
+            for entry in &watched {
+                for path in files_in(&entry.root, entry.dir.recursive) {
+                    if entry.covers(&path) {
+                        ingest_file(entry, &path).await;
+                    }
+                }
+            }
+
+            while let Some(path) = receiver.recv().await {
+                let mut pending = HashSet::from([path]);
+                while let Ok(Some(path)) = tokio::time::timeout(DEBOUNCE, receiver.recv()).await {
+                    pending.insert(path);
+                }
+                for path in pending {
+                    let Ok(path) = std::fs::canonicalize(&path) else {
+                        continue;
+                    };
+                    if let Some(entry) = watched.iter().find(|entry| entry.covers(&path)) {
+                        ingest_file(entry, &path).await;
+                    }
+                }
+            }
+        });
+    });
+}
+
+/// Files in `dir` (and its subdirectories when `recursive`), in name order
+fn files_in(dir: &Path, recursive: bool) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            if recursive {
+                files.extend(files_in(&path, true));
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Ingest one file and publish the outcome
+async fn ingest_file(entry: &Watched, path: &Path) {
+    let display = path.display().to_string();
+    match try_ingest(entry, path).await {
+        Ok(Some(report)) => {
+            log_info!("[Connectors] Ingested {} ({:?})", display, entry.dir.target);
+            publish("connector.ingested", json!({
+                "connector": "filesystem",
+                "path": display,
+                "target": entry.dir.target,
+                "report": report,
+            }));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            log_warn!("[Connectors] Failed to ingest {}: {}", display, e);
+            publish("connector.failed", json!({
+                "connector": "filesystem",
+                "path": display,
+                "error": e.to_string(),
+            }));
+        }
+    }
+}
+
+/// Ingest `path` unless it is unchanged; returns the target's report
+async fn try_ingest(entry: &Watched, path: &Path) -> anyhow::Result<Option<serde_json::Value>> {
+    let text = String::from_utf8(std::fs::read(path)?)?;
+    let hash = hash_bytes(text.as_bytes());
+    let key = hash_bytes(path.to_string_lossy().as_bytes())[..16].to_string();
+    let previous = get_typed::<FileState>(WATCH_STATE_COLLECTION, &key)?.unwrap_or_default();
+    if previous.hash == hash {
+        return Ok(None);
+    }
+
+    let mut state = FileState { path: path.display().to_string(), hash, rows: 0 };
+    let report = match entry.dir.target {
+        WatchTarget::Documents => {
+            let name = path.strip_prefix(&entry.root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+            serde_json::to_value(crate::documents::ingest(&name, &text).await?)?
+        }
+        WatchTarget::Timeseries => {
+            let series = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            let (points, skipped) = parse_csv(&text);
+            // A file that shrank was rewritten, not appended to
+            let from = if points.len() >= previous.rows { previous.rows } else { 0 };
+            let mut total = AppendReport::default();
+            for batch in points[from..].chunks(MAX_BATCH_POINTS) {
+                let report = timeseries::append(series, batch.to_vec()).map_err(|e| anyhow::anyhow!("{}", e))?;
+                total.accepted += report.accepted;
+                total.out_of_order += report.out_of_order;
+                total.replaced += report.replaced;
+                total.too_late += report.too_late;
+            }
+            state.rows = points.len();
+            json!({ "series": series, "rows": points.len() - from, "skipped": skipped, "append": total })
+        }
+    };
+    put_typed(WATCH_STATE_COLLECTION, &key, &state)?;
+    Ok(Some(report))
+}
+
+/// Parse `ts,value` rows; returns the points and the number of unparsable lines
+///
+/// A first line that doesn't parse is taken as a header and not counted.
+fn parse_csv(text: &str) -> (Vec<Point>, usize) {
+    let mut points = Vec::new();
+    let mut skipped = 0;
+    for (index, line) in text.lines().map(str::trim).enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split(',').map(str::trim);
+        let parsed = match (fields.next(), fields.next()) {
+            (Some(ts), Some(value)) => ts.parse::<u64>().ok().zip(value.parse::<f64>().ok()),
+            _ => None,
+        };
+        match parsed {
+            Some((ts, value)) => points.push(Point { ts, value, tags: Default::default() }),
+            None if index == 0 => {}
+            None => skipped += 1,
+        }
+    }
+    (points, skipped)
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================================================================
+// Source Connectors
+// ============================================================================
+//
+// Connectors pull data from outside the service and feed it in without a
+// client pushing it through the API. They are declared in the
+// `PATTERN_CLOCK_CONFIG` file, start with the agents and publish a
+// `connector.ingested` event for everything they take in (and
+// `connector.failed` when that goes wrong):
+//
+//   - `watch_dirs`: directories whose markdown files become documents and
+//     whose CSV files become time series (`filesystem`)
+//   - `poll_sources`: JSON APIs and RSS / Atom feeds fetched on a schedule,
+//     each new item sent to an agent as a JSON payload (`poller`)
+//   - `mail_sources`: IMAP mailboxes whose new messages are sent to an agent,
+//     attachments going to the blob store (`email`)
+//
+// The poller and email connectors run on the leader only (see `leader`);
+// watched directories are local to each instance.
+//
+// The declarations below are plain data shared with the configuration; the
+// connectors themselves are native only.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod email;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod filesystem;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod poller;
+
+/// Where files from a watched directory go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchTarget {
+    /// Markdown / text files are ingested as searchable documents
+    Documents,
+    /// CSV files of `ts,value` rows are appended to the series named after the file
+    Timeseries,
+}
+
+/// A directory watched for new and changed files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchedDir {
+    pub path: String,
+    pub target: WatchTarget,
+    /// Also watch subdirectories
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+impl WatchedDir {
+    /// Check the declaration before it is swapped into the configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.path.trim().is_empty() {
+            return Err("watched directory path must not be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// File extensions picked up for the target
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self.target {
+            WatchTarget::Documents => &["md", "markdown", "txt"],
+            WatchTarget::Timeseries => &["csv"],
+        }
+    }
+}
+
+/// How a polled response is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    /// JSON, addressed with JSONPath-lite: `$.data.items[*]`, `$.title`, `$.tags[0]`
+    Json,
+    /// XML (RSS, Atom), addressed with XPath-lite: `//item`, `title`, `enclosure/@url`
+    Xml,
+}
+
+/// A URL fetched on a schedule, its items fed to an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollSource {
+    pub name: String,
+    pub url: String,
+    pub format: FeedFormat,
+    #[serde(default = "default_poll_interval")]
+    pub interval_secs: u64,
+    /// Path selecting the items in the response
+    pub items: String,
+    /// Payload field -> path of its value, relative to an item
+    pub fields: BTreeMap<String, String>,
+    /// Payload field identifying an item; items seen before are not sent again
+    /// (without one, the whole payload identifies the item)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_field: Option<String>,
+    /// Agent receiving the payloads
+    pub agent: u8,
+}
+
+fn default_poll_interval() -> u64 {
+    300
+}
+
+impl PollSource {
+    /// Check the declaration before it is swapped into the configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("source name must not be empty".to_string());
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(format!("URL {:?} must start with http:// or https://", self.url));
+        }
+        if self.interval_secs == 0 {
+            return Err("interval must be at least one second".to_string());
+        }
+        if self.agent == 0 {
+            return Err("agent ids start at 1".to_string());
+        }
+        if self.fields.is_empty() {
+            return Err("at least one field is required".to_string());
+        }
+        if let Some(id_field) = self.id_field.as_ref().filter(|id| !self.fields.contains_key(id.as_str())) {
+            return Err(format!("id field {:?} is not one of the fields", id_field));
+        }
+        for path in std::iter::once(&self.items).chain(self.fields.values()) {
+            let valid = match self.format {
+                FeedFormat::Json => path.starts_with('$'),
+                FeedFormat::Xml => !path.trim().is_empty(),
+            };
+            if !valid {
+                return Err(format!("invalid {:?} path {:?}", self.format, path));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An IMAP mailbox whose new messages are fed to an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MailSource {
+    pub name: String,
+    /// IMAP server, reached over TLS
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    /// Secret reference, e.g. `file:/run/secrets/imap`
+    pub password: String,
+    #[serde(default = "default_mailbox")]
+    pub mailbox: String,
+    #[serde(default = "default_mail_interval")]
+    pub interval_secs: u64,
+    /// Only messages whose subject contains this (case-insensitive) are sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_contains: Option<String>,
+    /// Only messages whose sender address contains this (case-insensitive) are sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_contains: Option<String>,
+    /// Agent receiving the payloads
+    pub agent: u8,
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+fn default_mail_interval() -> u64 {
+    60
+}
+
+impl MailSource {
+    /// Check the declaration before it is swapped into the configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("source name must not be empty".to_string());
+        }
+        if self.host.trim().is_empty() || self.username.trim().is_empty() {
+            return Err("host and username are required".to_string());
+        }
+        if self.interval_secs == 0 {
+            return Err("interval must be at least one second".to_string());
+        }
+        if self.agent == 0 {
+            return Err("agent ids start at 1".to_string());
+        }
+        crate::secrets::resolve(&self.password).map_err(|e| format!("password: {}", e))?;
+        Ok(())
+    }
+}
+
+/// Start every connector declared in the configuration (once; requires a Tokio runtime)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn ensure_started() {
+    filesystem::ensure_started();
+    poller::ensure_started();
+    email::ensure_started();
+}
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::{FeedFormat, PollSource};
+use crate::agents::{get_agent, send_work, AgentMessage};
+use crate::blobs::hash_bytes;
+use crate::config::config;
+use crate::events::publish;
+use crate::payload::Payload;
+use crate::storage::{get_typed, put_typed};
+
+// ============================================================================
+// HTTP Poller
+// ============================================================================
+//
+// Fetches each of the configured `poll_sources` every `interval_secs`,
+// selects its items and maps each to a flat JSON object:
+//
+//     { "name": "status", "url": "https://status.example.com/feed.rss",
+//       "format": "xml", "items": "//item", "agent": 3, "id_field": "link",
+//       "fields": { "title": "title", "link": "link", "published": "pubDate" } }
+//
+// sends Agent3 `{"source": "status", "title": "...", "link": "...", ...}` as
+// an `application/json` payload for every item not seen before. Payloads go
+// through the agent's schema, if it has one, like payloads from the API.
+//
+// Paths are deliberately small subsets:
+//
+//   - JSONPath-lite: `$`, `.key`, `['key']`, `[0]`, `[*]` and `.*`
+//   - XPath-lite: `/` (child), `//` (descendant), element names (namespace
+//     prefixes ignored), `*`, `.`, and a final `@attr`; an element's value
+//     is its text content
+//
+// Ids of the items already sent are kept in `poll_state`, capped at
+// `MAX_SEEN`, so restarts don't resend a feed.
+
+/// Collection holding the ids seen per source, keyed by source name
+pub const POLL_STATE_COLLECTION: &str = "poll_state";
+
+/// Item ids remembered per source
+const MAX_SEEN: usize = 1000;
+/// Timeout of one fetch
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PollState {
+    /// Ids of items already sent, oldest first
+    seen: Vec<String>,
+}
+
+/// Start polling the configured sources (once; restart to pick up changes)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let http = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build().unwrap_or_default();
+        for source in config().poll_sources.clone() {
+            log_info!("[Connectors] Polling {} every {}s for Agent{}", source.url, source.interval_secs, source.agent);
+            let http = http.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(source.interval_secs));
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    if !crate::leader::is_leader() {
+                        continue;
+                    }
+                    match poll(&http, &source).await {
+                        Ok((sent, seen)) => {
+                            if sent > 0 {
+                                log_info!("[Connectors] {}: sent {} new items to Agent{}", source.name, sent, source.agent);
+                            }
+                            publish("connector.ingested", json!({
+                                "connector": "poller",
+                                "source": source.name,
+                                "agent": source.agent,
+                                "sent": sent,
+                                "seen": seen,
+                            }));
+                        }
+                        Err(e) => {
+                            log_warn!("[Connectors] Polling {} failed: {}", source.name, e);
+                            publish("connector.failed", json!({
+                                "connector": "poller",
+                                "source": source.name,
+                                "error": e.to_string(),
+                            }));
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Fetch one source and send its new items; returns (sent, already seen)
+async fn poll(http: &reqwest::Client, source: &PollSource) -> anyhow::Result<(usize, usize)> {
+    let body = http.get(&source.url).send().await?.error_for_status()?.text().await?;
+    let items = extract(source, &body)?;
+    let actor_ref = get_agent(source.agent)
+        .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", source.agent))?;
+
+    let mut state = get_typed::<PollState>(POLL_STATE_COLLECTION, &source.name)?.unwrap_or_default();
+    let (mut sent, mut seen) = (0, 0);
+    for mut item in items {
+        let id = match source.id_field.as_ref().and_then(|field| item.get(field)) {
+            Some(Value::String(id)) => id.clone(),
+            Some(other) => other.to_string(),
+            None => hash_bytes(Value::Object(item.clone()).to_string().as_bytes()),
+        };
+        if state.seen.contains(&id) {
+            seen += 1;
+            continue;
+        }
+
+        item.insert("source".to_string(), Value::String(source.name.clone()));
+        let payload = Payload::binary("application/json", serde_json::to_vec(&item)?);
+        crate::schemas::validate_payload(source.agent, &payload)?;
+        send_work(&actor_ref, AgentMessage::ProcessPayload { payload })
+            .map_err(|e| anyhow::anyhow!("failed to queue payload for Agent{}: {}", source.agent, e))?;
+        state.seen.push(id);
+        sent += 1;
+    }
+
+    if sent > 0 {
+        let excess = state.seen.len().saturating_sub(MAX_SEEN);
+        state.seen.drain(..excess);
+        put_typed(POLL_STATE_COLLECTION, &source.name, &state)?;
+    }
+    Ok((sent, seen))
+}
+
+/// Map the items of a response to payload objects, in document order
+pub fn extract(source: &PollSource, body: &str) -> anyhow::Result<Vec<Map<String, Value>>> {
+    match source.format {
+        FeedFormat::Json => {
+            let document: Value = serde_json::from_str(body)?;
+            json_path(&document, &source.items)?
+                .into_iter()
+                .map(|item| {
+                    source.fields.iter()
+                        .map(|(field, path)| {
+                            let value = json_path(item, path)?.into_iter().next().cloned().unwrap_or(Value::Null);
+                            Ok::<_, anyhow::Error>((field.clone(), value))
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        FeedFormat::Xml => {
+            let document = roxmltree::Document::parse(body)?;
+            Ok(xml_path(document.root(), &source.items)
+                .into_iter()
+                .map(|item| {
+                    source.fields.iter()
+                        .map(|(field, path)| (field.clone(), xml_value(item, path).map_or(Value::Null, Value::String)))
+                        .collect()
+                })
+                .collect())
+        }
+    }
+}
+
+/// Values selected by a JSONPath-lite `path` (see the module comment)
+pub fn json_path<'a>(root: &'a Value, path: &str) -> anyhow::Result<Vec<&'a Value>> {
+    let rest = path.trim().strip_prefix('$').ok_or_else(|| anyhow::anyhow!("JSON path {:?} must start with $", path))?;
+    let mut current = vec![root];
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        let step: Step = match c {
+            '.' => {
+                let mut key = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next == '.' || next == '[' {
+                        break;
+                    }
+                    key.push(next);
+                    chars.next();
+                }
+                if key == "*" { Step::All } else if key.is_empty() { anyhow::bail!("empty key in JSON path {:?}", path) } else { Step::Key(key) }
+            }
+            '[' => {
+                let mut inner = String::new();
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        break;
+                    }
+                    inner.push(next);
+                }
+                let inner = inner.trim();
+                if inner == "*" {
+                    Step::All
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    Step::Index(index)
+                } else if let Some(key) = inner.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')) {
+                    Step::Key(key.to_string())
+                } else {
+                    anyhow::bail!("unsupported selector [{}] in JSON path {:?}", inner, path);
+                }
+            }
+            other => anyhow::bail!("unexpected {:?} in JSON path {:?}", other, path),
+        };
+        current = current.into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (&step, value) {
+                    (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Step::Index(index), Value::Array(items)) => items.get(*index).into_iter().collect(),
+                    (Step::All, Value::Array(items)) => items.iter().collect(),
+                    (Step::All, Value::Object(map)) => map.values().collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    Ok(current)
+}
+
+enum Step {
+    Key(String),
+    Index(usize),
+    All,
+}
+
+/// Elements selected by an XPath-lite `path` (see the module comment)
+pub fn xml_path<'a, 'input>(start: roxmltree::Node<'a, 'input>, path: &str) -> Vec<roxmltree::Node<'a, 'input>> {
+    let path = path.trim();
+    let mut current = vec![if path.starts_with('/') { start.document().root() } else { start }];
+    let mut descendant = false;
+    for (index, segment) in path.split('/').enumerate() {
+        if segment.is_empty() {
+            // The leading `/` of an absolute path, or the second `/` of `//`
+            descendant = index > 0 || path.starts_with("//");
+            continue;
+        }
+        let matches = |node: &roxmltree::Node| node.is_element() && (segment == "*" || node.tag_name().name() == segment);
+        current = match (segment, descendant) {
+            (".", _) => current,
+            (_, true) => current.iter().flat_map(|&node| node.descendants().filter(move |n| *n != node && matches(n))).collect(),
+            (_, false) => current.iter().flat_map(|node| node.children().filter(matches)).collect(),
+        };
+        descendant = false;
+    }
+    current
+}
+
+/// Text of the first match of `path` below `item`; a final `@attr` selects an attribute
+pub fn xml_value(item: roxmltree::Node, path: &str) -> Option<String> {
+    let (elements, attribute) = match path.trim().rsplit_once('@') {
+        Some((elements, attribute)) => (elements.trim_end_matches('/'), Some(attribute)),
+        None => (path.trim(), None),
+    };
+    let nodes = if elements.is_empty() { vec![item] } else { xml_path(item, elements) };
+    let node = nodes.into_iter().next()?;
+    match attribute {
+        Some(attribute) => node.attributes().find(|a| a.name() == attribute).map(|a| a.value().to_string()),
+        None => Some(node.descendants().filter_map(|n| n.is_text().then(|| n.text()).flatten()).collect::<String>().trim().to_string()),
+    }
+}
@@ -0,0 +1,172 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use crate::api_client::ApiClient;
+
+// ============================================================================
+// Admin Console (`pattern-clock console [URL]`)
+// ============================================================================
+//
+// Line-based REPL against a running instance over its HTTP API, for
+// operators on machines without the GUI. Defaults to the local server
+// (`IP` / `PORT`). `help` lists the commands.
+
+const HELP: &str = "\
+Commands:
+  agents                       list running agents
+  send <agent> <message>       queue a message for an agent
+  status <agent>               ask an agent to report its status
+  events [limit]               most recent stored events (default 20)
+  tail                         follow live events until Ctrl-C
+  alerts [limit]               recent monitor alerts (default 20)
+  series                       list time series
+  forecast <series> [horizon]  forecast the next values of a series (default 10)
+  tool <name> [json args]      call an MCP tool, e.g. tool classify_text {\"text\": \"disk full\"}
+  version                      server build information
+  help                         show this help
+  quit                         leave the console";
+
+/// Run the console until `quit` or end of input
+pub fn run(base_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::new(base_url);
+    let runtime = crate::runtime::background_runtime();
+
+    match runtime.block_on(client.get("/api/version")) {
+        Ok(version) => println!(
+            "Connected to {} (pattern-clock {})",
+            client.base_url(),
+            version.get("version").and_then(|v| v.as_str()).unwrap_or("unknown")
+        ),
+        Err(e) => anyhow::bail!("cannot reach {}: {}", client.base_url(), e),
+    }
+    println!("Type `help` for commands.");
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("pattern-clock> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if matches!(line, "quit" | "exit") {
+            return Ok(());
+        }
+        if let Err(e) = runtime.block_on(execute(&client, line)) {
+            eprintln!("error: {}", e);
+        }
+    }
+}
+
+/// Run one console command
+async fn execute(client: &ApiClient, line: &str) -> anyhow::Result<()> {
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    match command {
+        "help" => println!("{}", HELP),
+        "agents" => {
+            let agents = client.get("/api/agents").await?;
+            let ids: Vec<String> = agents.as_array().into_iter().flatten().map(|id| format!("Agent{}", id)).collect();
+            println!("{} running: {}", ids.len(), ids.join(", "));
+        }
+        "send" => {
+            let (agent, message) = rest.split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow::anyhow!("usage: send <agent> <message>"))?;
+            let agent = parse_agent(agent)?;
+            let reply = client.post(&format!("/api/agents/{}/process", agent), json!({ "data": message.trim() })).await?;
+            print_value(&reply);
+        }
+        "status" => {
+            let agent = parse_agent(rest)?;
+            print_value(&client.get(&format!("/api/agents/{}/status", agent)).await?);
+        }
+        "events" => {
+            let limit = parse_optional(rest, 20)?;
+            let events = client.get(&format!("/api/events?limit={}", limit)).await?;
+            for event in events.as_array().into_iter().flatten() {
+                println!("{}", event);
+            }
+        }
+        "tail" => tail(client).await?,
+        "alerts" => {
+            let limit = parse_optional(rest, 20)?;
+            let alerts = client.get(&format!("/api/monitor/alerts?limit={}", limit)).await?;
+            for alert in alerts.as_array().into_iter().flatten() {
+                println!("{}", alert);
+            }
+        }
+        "series" => {
+            let names = client.get("/api/timeseries").await?;
+            for name in names.as_array().into_iter().flatten() {
+                println!("{}", name.as_str().unwrap_or_default());
+            }
+        }
+        "forecast" => {
+            let mut args = rest.split_whitespace();
+            let series = args.next().ok_or_else(|| anyhow::anyhow!("usage: forecast <series> [horizon]"))?;
+            let horizon = parse_optional(args.next().unwrap_or(""), 10)?;
+            let values = client.get(&format!("/api/timeseries/{}/forecast?horizon={}", series, horizon)).await?;
+            for (step, value) in values.as_array().into_iter().flatten().enumerate() {
+                println!("+{:<4} {}", step + 1, value);
+            }
+        }
+        "tool" => {
+            let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if name.is_empty() {
+                anyhow::bail!("usage: tool <name> [json args]");
+            }
+            let args: Value = if args.trim().is_empty() {
+                json!({})
+            } else {
+                serde_json::from_str(args).map_err(|e| anyhow::anyhow!("tool arguments must be a JSON object: {}", e))?
+            };
+            print_value(&client.post(&format!("/api/mcp/{}", name), args).await?);
+        }
+        "version" => print_value(&client.get("/api/version").await?),
+        other => anyhow::bail!("unknown command `{}` (try `help`)", other),
+    }
+    Ok(())
+}
+
+/// Print live events until Ctrl-C
+async fn tail(client: &ApiClient) -> anyhow::Result<()> {
+    println!("Following events, Ctrl-C to stop");
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    loop {
+        tokio::select! {
+            _ = &mut interrupted => return Ok(()),
+            // Empty responses are long-poll timeouts
+            event = client.get("/api/events/stream") => match event? {
+                Value::Null => {}
+                event => println!("{}", event),
+            },
+        }
+    }
+}
+
+fn parse_agent(value: &str) -> anyhow::Result<u8> {
+    value.trim().trim_start_matches("Agent").parse()
+        .map_err(|_| anyhow::anyhow!("`{}` is not an agent id", value.trim()))
+}
+
+fn parse_optional(value: &str, default: usize) -> anyhow::Result<usize> {
+    if value.trim().is_empty() {
+        return Ok(default);
+    }
+    value.trim().parse().map_err(|_| anyhow::anyhow!("`{}` is not a number", value.trim()))
+}
+
+fn print_value(value: &Value) {
+    match value {
+        Value::String(text) => println!("{}", text),
+        Value::Null => println!("ok"),
+        other => println!("{}", serde_json::to_string_pretty(other).unwrap_or_default()),
+    }
+}
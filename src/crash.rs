@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+
+use crate::compute::panic_message;
+use crate::events::Event;
+use crate::storage::now_millis;
+
+// ============================================================================
+// Crash Reports
+// ============================================================================
+//
+// `install()` adds a panic hook that writes `crash-<ts>-<n>.json` to
+// `<data_dir>/crashes` with the panic message and location, a backtrace,
+// the last published events and the configuration (secrets and webhook URLs
+// left out). Panics that are caught and handled (`catching`, used by the
+// compute pool and the GPU watchdog, and `caught_elsewhere`, used for agent
+// handlers that ractor turns into supervision events) don't produce a report.
+//
+// Reports stay pending until acknowledged; the desktop app offers the
+// pending ones on its next launch.
+
+const CRASH_DIR: &str = "crashes";
+/// Suffix of reports that have been looked at
+const SEEN_SUFFIX: &str = ".seen.json";
+
+/// Everything recorded about one panic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub ts: u64,
+    pub version: String,
+    pub thread: String,
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub recent_events: Vec<Event>,
+    pub config: serde_json::Value,
+}
+
+/// A report on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCrashReport {
+    pub path: String,
+    pub report: CrashReport,
+}
+
+thread_local! {
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Marks the current thread as catching its panics until dropped (also when unwinding)
+struct CatchingGuard;
+
+impl CatchingGuard {
+    fn enter() -> Self {
+        CATCHING.with(|depth| depth.set(depth.get() + 1));
+        CatchingGuard
+    }
+}
+
+impl Drop for CatchingGuard {
+    fn drop(&mut self) {
+        CATCHING.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// `catch_unwind` for panics the caller handles itself (no crash report is written)
+pub(crate) fn catching<T>(work: impl FnOnce() -> T) -> std::thread::Result<T> {
+    let _guard = CatchingGuard::enter();
+    panic::catch_unwind(AssertUnwindSafe(work))
+}
+
+/// Run `future` without crash reports for its panics, which something up the
+/// stack catches and handles (e.g. ractor, for panics in actor handlers)
+///
+/// The thread is marked only while the future is polled, since it may move
+/// between worker threads at each await.
+pub(crate) async fn caught_elsewhere<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        let _guard = CatchingGuard::enter();
+        future.as_mut().poll(cx)
+    })
+    .await
+}
+
+fn crash_dir(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join(CRASH_DIR)
+}
+
+/// Install the crash-report panic hook (once per process; keeps the previous hook)
+pub fn install() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if CATCHING.with(|depth| depth.get()) > 0 {
+                return;
+            }
+
+            let config = crate::config::summary();
+            let data_dir = config.get("data_dir").and_then(|dir| dir.as_str())
+                .unwrap_or(crate::storage::DEFAULT_DATA_DIR)
+                .to_string();
+            let report = CrashReport {
+                ts: now_millis(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                thread: std::thread::current().name().unwrap_or("unnamed").to_string(),
+                message: panic_message(info.payload()),
+                location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+                backtrace: Backtrace::force_capture().to_string(),
+                recent_events: crate::events::recent(),
+                config,
+            };
+            match write_report(&data_dir, &report) {
+                // Logging may be what panicked, so report directly
+                Ok(path) => eprintln!("[Crash] Report written to {}", path.display()),
+                Err(e) => eprintln!("[Crash] Failed to write crash report: {}", e),
+            }
+        }));
+    });
+}
+
+fn write_report(data_dir: &str, report: &CrashReport) -> std::io::Result<PathBuf> {
+    let dir = crash_dir(data_dir);
+    std::fs::create_dir_all(&dir)?;
+    // Several threads can panic within the same millisecond
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    let n = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("crash-{}-{}.json", report.ts, n));
+    let json = serde_json::to_string_pretty(report).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// Reports not acknowledged yet, newest first
+pub fn pending_reports() -> Vec<StoredCrashReport> {
+    let Ok(entries) = std::fs::read_dir(crash_dir(&crate::config::config().data_dir)) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<StoredCrashReport> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            name.starts_with("crash-") && name.ends_with(".json") && !name.ends_with(SEEN_SUFFIX)
+        })
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            let report = serde_json::from_str(&text).ok()?;
+            Some(StoredCrashReport { path: path.display().to_string(), report })
+        })
+        .collect();
+    reports.sort_by(|a, b| b.report.ts.cmp(&a.report.ts));
+    reports
+}
+
+/// Mark a report as seen so it is no longer offered
+pub fn acknowledge(path: &str) -> std::io::Result<()> {
+    let seen = path.strip_suffix(".json").map(|stem| format!("{}{}", stem, SEEN_SUFFIX));
+    match seen {
+        Some(seen) => std::fs::rename(path, seen),
+        None => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a crash report path")),
+    }
+}
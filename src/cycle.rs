@@ -0,0 +1,368 @@
+use chrono::{DateTime, NaiveDateTime, TimeDelta, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::config::config;
+use crate::events::{publish, subscribe};
+use crate::storage::now_millis;
+use crate::timeseries::{self, Point};
+
+// ============================================================================
+// Cognitive Cycle
+// ============================================================================
+//
+// The cycle ticks faster when the service is busy and slower when it is
+// idle. Event volume is the rate of local events on the bus, smoothed over
+// a few seconds; the tick interval is
+//
+//     cycle_max_interval / (1 + events per second)
+//
+// clamped to [`cycle_min_interval`, `cycle_max_interval`]: an idle service
+// ticks at the maximum interval, one event a second halves it, and so on.
+// Both bounds are reloadable.
+//
+// While running, the effective rate (ticks per second) is appended to the
+// `cycle.rate` series every `SAMPLE_INTERVAL`, so it can be charted next to
+// the event volume that drove it (`GET /api/timeseries/cycle.rate`).
+//
+// Subsystems that should run in step with the cycle subscribe to a derived
+// clock instead of keeping their own timer: the time-series forecaster
+// follows `Schedule::every(100)` (`timeseries::ensure_forecasts_started`),
+// and the anomaly checks of the `rules` phase run on `Schedule::EVERY_TICK`
+// (phases declare their schedule, see `phases`). Others can shift a clock,
+// e.g. `cycle::subscribe_clock(Schedule::every(100).with_phase(50))`.
+//
+// A schedule fires on the ticks where `tick % every == phase`, so all clocks
+// stay phase-locked to the one tick counter: they speed up and slow down
+// with it and stop while the cycle is stopped. Subscribers sharing a
+// schedule share its channel. One that falls more than `CLOCK_CAPACITY`
+// ticks behind misses ticks rather than holding the cycle back.
+//
+// With `cycle_align` set to `minute`, `hour` or `day`, the cycle also lands a
+// tick exactly on every such wall-clock boundary in the configured
+// `timezone` (the wait before it is shortened), and that tick is a
+// milestone: it is published as `cycle.milestone` and delivered to
+// `subscribe_milestones()`, so reports run at 14:00 local time rather than
+// at some offset from process start. Boundaries skipped by a DST change
+// move to the next one that exists.
+//
+// Every tick also runs the cycle's phases and is kept in their journal for
+// replays (see `phases`).
+
+/// Series receiving the effective tick rate
+pub const RATE_SERIES: &str = "cycle.rate";
+
+/// How often event volume is measured and the rate is recorded
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// Weight of the newest measurement in the smoothed event rate
+const SMOOTHING: f64 = 0.5;
+/// Ticks buffered per derived clock for slow subscribers
+const CLOCK_CAPACITY: usize = 16;
+
+/// Wall-clock boundary the cycle aligns milestones to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Alignment {
+    #[default]
+    None,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Alignment {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Alignment::None),
+            "minute" => Some(Alignment::Minute),
+            "hour" => Some(Alignment::Hour),
+            "day" => Some(Alignment::Day),
+            _ => None,
+        }
+    }
+
+    /// Start of the boundary period containing `local`
+    fn truncate(self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Alignment::None => None,
+            Alignment::Minute => local.with_second(0)?.with_nanosecond(0),
+            Alignment::Hour => local.with_minute(0)?.with_second(0)?.with_nanosecond(0),
+            Alignment::Day => local.date().and_hms_opt(0, 0, 0),
+        }
+    }
+
+    fn period(self) -> TimeDelta {
+        match self {
+            Alignment::None | Alignment::Day => TimeDelta::days(1),
+            Alignment::Minute => TimeDelta::minutes(1),
+            Alignment::Hour => TimeDelta::hours(1),
+        }
+    }
+}
+
+/// First `alignment` boundary in `timezone` strictly after `now_ms` (ms since the epoch)
+pub fn next_boundary(alignment: Alignment, timezone: chrono_tz::Tz, now_ms: u64) -> Option<u64> {
+    let now = Utc.timestamp_millis_opt(now_ms as i64).single()?;
+    let mut local = alignment.truncate(now.with_timezone(&timezone).naive_local())?;
+    // A boundary inside a DST gap does not exist; try the following ones
+    for _ in 0..4 {
+        local += alignment.period();
+        if let Some(boundary) = timezone.from_local_datetime(&local).earliest() {
+            let boundary_ms = boundary.timestamp_millis() as u64;
+            if boundary_ms > now_ms {
+                return Some(boundary_ms);
+            }
+        }
+    }
+    None
+}
+
+/// A tick that landed on a wall-clock boundary
+#[derive(Debug, Clone, Serialize)]
+pub struct Milestone {
+    pub tick: u64,
+    pub alignment: Alignment,
+    pub ts: u64,
+    /// The boundary in the configured timezone (RFC 3339)
+    pub local: String,
+}
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static TICKS: AtomicU64 = AtomicU64::new(0);
+/// Local events since the last sample
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+/// Smoothed events per second, as `f64` bits
+static EVENT_RATE: AtomicU64 = AtomicU64::new(0);
+
+/// A derived clock: fires on the ticks where `tick % every == phase`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub every: u64,
+    pub phase: u64,
+}
+
+impl Schedule {
+    pub const EVERY_TICK: Schedule = Schedule { every: 1, phase: 0 };
+
+    /// Every `n`th tick (at least every tick)
+    pub fn every(n: u64) -> Self {
+        Self { every: n.max(1), phase: 0 }
+    }
+
+    /// The same clock shifted by `phase` ticks
+    pub fn with_phase(self, phase: u64) -> Self {
+        Self { phase: phase % self.every, ..self }
+    }
+
+    pub fn fires_on(self, tick: u64) -> bool {
+        tick % self.every == self.phase
+    }
+}
+
+/// One tick delivered to a derived clock's subscribers
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CycleTick {
+    /// Tick number on the central cycle
+    pub tick: u64,
+    pub ts: u64,
+}
+
+/// A derived clock with its current subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockStatus {
+    pub schedule: Schedule,
+    pub subscribers: usize,
+}
+
+/// Current state of the cycle, for the clock
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleStatus {
+    pub running: bool,
+    pub ticks: u64,
+    pub interval_ms: u64,
+    /// Effective rate; 0 while stopped
+    pub ticks_per_sec: f64,
+    pub events_per_sec: f64,
+    pub clocks: Vec<ClockStatus>,
+    /// Next milestone in the configured timezone (RFC 3339), when aligned
+    pub next_milestone: Option<String>,
+}
+
+fn clocks() -> &'static Mutex<Vec<(Schedule, broadcast::Sender<CycleTick>)>> {
+    static CLOCKS: OnceLock<Mutex<Vec<(Schedule, broadcast::Sender<CycleTick>)>>> = OnceLock::new();
+    CLOCKS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Follow a derived clock of the cycle (see the module comment)
+pub fn subscribe_clock(schedule: Schedule) -> broadcast::Receiver<CycleTick> {
+    let mut clocks = clocks().lock().unwrap();
+    if let Some((_, sender)) = clocks.iter().find(|(existing, _)| *existing == schedule) {
+        return sender.subscribe();
+    }
+    let (sender, receiver) = broadcast::channel(CLOCK_CAPACITY);
+    clocks.push((schedule, sender));
+    receiver
+}
+
+fn milestones() -> &'static broadcast::Sender<Milestone> {
+    static MILESTONES: OnceLock<broadcast::Sender<Milestone>> = OnceLock::new();
+    MILESTONES.get_or_init(|| broadcast::channel(CLOCK_CAPACITY).0)
+}
+
+/// Follow the ticks that land on wall-clock boundaries (see the module comment)
+pub fn subscribe_milestones() -> broadcast::Receiver<Milestone> {
+    milestones().subscribe()
+}
+
+/// `ts` in `timezone`, RFC 3339
+fn local_time(ts: u64, timezone: chrono_tz::Tz) -> Option<String> {
+    DateTime::from_timestamp_millis(ts as i64).map(|time| time.with_timezone(&timezone).to_rfc3339())
+}
+
+/// Advance the cycle by one tick and fire the derived clocks due on it; returns the tick number
+fn tick() -> u64 {
+    let tick = TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+    let ts = now_millis();
+    let mut clocks = clocks().lock().unwrap();
+    // Clocks nobody follows any more are dropped
+    clocks.retain(|(_, sender)| sender.receiver_count() > 0);
+    for (schedule, sender) in clocks.iter() {
+        if schedule.fires_on(tick) {
+            let _ = sender.send(CycleTick { tick, ts });
+        }
+    }
+    drop(clocks);
+    crate::phases::run_tick(&CycleTick { tick, ts });
+    tick
+}
+
+/// Tick on the boundary `ts` and announce it as a milestone
+fn milestone(alignment: Alignment, timezone: chrono_tz::Tz, ts: u64) {
+    let milestone = Milestone {
+        tick: tick(),
+        alignment,
+        ts,
+        local: local_time(ts, timezone).unwrap_or_default(),
+    };
+    publish("cycle.milestone", serde_json::to_value(&milestone).unwrap_or_default());
+    let _ = milestones().send(milestone);
+}
+
+/// Start or stop ticking
+pub fn set_running(running: bool) {
+    RUNNING.store(running, Ordering::SeqCst);
+}
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+/// Ticks since the process started
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+fn event_rate() -> f64 {
+    f64::from_bits(EVENT_RATE.load(Ordering::Relaxed))
+}
+
+/// Tick interval for the current event volume, within the configured bounds
+pub fn interval() -> Duration {
+    let config = config();
+    let (min, max) = (config.cycle_min_interval, config.cycle_max_interval);
+    max.div_f64(1.0 + event_rate()).clamp(min, max.max(min))
+}
+
+/// Where the cycle stands now
+pub fn status() -> CycleStatus {
+    let running = is_running();
+    let interval = interval();
+    CycleStatus {
+        running,
+        ticks: ticks(),
+        interval_ms: interval.as_millis() as u64,
+        ticks_per_sec: if running { 1.0 / interval.as_secs_f64() } else { 0.0 },
+        events_per_sec: event_rate(),
+        clocks: clocks()
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(schedule, sender)| ClockStatus { schedule: *schedule, subscribers: sender.receiver_count() })
+            .collect(),
+        next_milestone: {
+            let config = config();
+            next_boundary(config.cycle_align, config.timezone, now_millis())
+                .and_then(|boundary| local_time(boundary, config.timezone))
+        },
+    }
+}
+
+/// Start the tick loop and the event volume sampler (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        crate::phases::ensure_started();
+        crate::timeseries::ensure_forecasts_started();
+        let mut events = subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) if event.is_local() => {
+                        EVENTS.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut samples = tokio::time::interval(SAMPLE_INTERVAL);
+            samples.tick().await;
+            loop {
+                samples.tick().await;
+                let measured = EVENTS.swap(0, Ordering::Relaxed) as f64 / SAMPLE_INTERVAL.as_secs_f64();
+                let smoothed = SMOOTHING * measured + (1.0 - SMOOTHING) * event_rate();
+                EVENT_RATE.store(smoothed.to_bits(), Ordering::Relaxed);
+                if is_running() {
+                    let point = Point { ts: now_millis(), value: status().ticks_per_sec, tags: Default::default() };
+                    if let Err(e) = timeseries::append(RATE_SERIES, vec![point]) {
+                        log_warn!("[Cycle] Failed to record tick rate: {}", e);
+                    }
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut last_milestone = 0;
+            loop {
+                // Re-read every tick, so volume changes and reloads apply at once
+                let wait = interval();
+                let (alignment, timezone) = {
+                    let config = config();
+                    (config.cycle_align, config.timezone)
+                };
+                let now = now_millis();
+                match next_boundary(alignment, timezone, now) {
+                    // Shorten the wait so this tick lands on the boundary
+                    Some(boundary) if boundary > last_milestone && boundary - now <= wait.as_millis() as u64 => {
+                        tokio::time::sleep(Duration::from_millis(boundary - now)).await;
+                        last_milestone = boundary;
+                        if is_running() {
+                            milestone(alignment, timezone, boundary);
+                        }
+                    }
+                    _ => {
+                        tokio::time::sleep(wait).await;
+                        if is_running() {
+                            tick();
+                        }
+                    }
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::experiments::hash_dataset;
+use crate::extraction::Extraction;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage, update_typed};
+
+// ============================================================================
+// Stored Events and Labeled Datasets
+// ============================================================================
+
+/// Collection holding processed agent payloads
+pub const EVENTS_COLLECTION: &str = "events";
+/// Collection holding user-assigned labels, keyed by event id
+pub const LABELS_COLLECTION: &str = "labels";
+/// Collection holding dataset snapshots, keyed by version id
+pub const DATASETS_COLLECTION: &str = "datasets";
+/// Collection holding the index of recent events (one `RECENT_KEY` document)
+pub const EVENT_INDEX_COLLECTION: &str = "event_index";
+const RECENT_KEY: &str = "recent";
+/// Event ids kept in the recent index; `recent_events` asking for more lists
+/// the whole collection
+pub const RECENT_INDEX_LEN: usize = 500;
+
+/// Label values offered by the labeling view: the text classifier's classes,
+/// in logit order (free-form class names are also accepted, but not trained on)
+pub const DEFAULT_LABELS: &[&str] = &["negative", "neutral", "positive"];
+
+/// A payload processed by an agent, persisted for labeling and training
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvent {
+    /// Sortable id: `<millis>-<agent>-<sequence>`
+    pub id: String,
+    pub agent_id: u8,
+    /// Stored compressed when large (see `compression::encode_text`)
+    #[cfg_attr(not(target_arch = "wasm32"), serde(deserialize_with = "crate::compression::deserialize_text"))]
+    pub data: String,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+/// A label assigned to a stored event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLabel {
+    pub event_id: String,
+    pub label: String,
+    pub ts: u64,
+}
+
+/// One training example as exported for the trainer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabeledExample {
+    pub text: String,
+    pub label: String,
+}
+
+/// Ids of the latest events, oldest first, so `recent_events` need not list
+/// the whole collection
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecentIndex {
+    ids: VecDeque<String>,
+}
+
+/// Persist a processed payload
+pub fn record_event(agent_id: u8, sequence: u64, data: &str, extraction: &Extraction) -> anyhow::Result<StoredEvent> {
+    let ts = now_millis();
+    let event = StoredEvent {
+        id: format!("{:013}-{}-{}", ts, agent_id, sequence),
+        agent_id,
+        data: data.to_string(),
+        keywords: extraction.keywords.clone(),
+        ts,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let document = {
+        let mut document = serde_json::to_value(&event)?;
+        document["data"] = crate::compression::encode_text("event", &event.data);
+        document
+    };
+    #[cfg(target_arch = "wasm32")]
+    let document = serde_json::to_value(&event)?;
+    storage().put(EVENTS_COLLECTION, &event.id, &document)?;
+    // Left missing until `recent_events` builds it from the events stored so far
+    update_typed(EVENT_INDEX_COLLECTION, RECENT_KEY, |index: Option<RecentIndex>| {
+        Ok(index.map(|mut index| {
+            index.ids.push_back(event.id.clone());
+            while index.ids.len() > RECENT_INDEX_LEN {
+                index.ids.pop_front();
+            }
+            index
+        }))
+    })?;
+    Ok(event)
+}
+
+/// `record_event` on a blocking thread, so storage I/O doesn't hold up the
+/// agent's runtime worker; the event is stored once this returns
+pub async fn store_event(agent_id: u8, sequence: u64, data: String, extraction: Extraction) -> anyhow::Result<StoredEvent> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let event = tokio::task::spawn_blocking(move || record_event(agent_id, sequence, &data, &extraction)).await??;
+    #[cfg(target_arch = "wasm32")]
+    let event = record_event(agent_id, sequence, &data, &extraction)?;
+    Ok(event)
+}
+
+/// Most recent stored events, newest first
+///
+/// Up to `RECENT_INDEX_LEN` events come from the recent index; events
+/// deleted since (e.g. by retention) are skipped.
+pub fn recent_events(limit: usize) -> anyhow::Result<Vec<StoredEvent>> {
+    if limit <= RECENT_INDEX_LEN {
+        if let Some(index) = get_typed::<RecentIndex>(EVENT_INDEX_COLLECTION, RECENT_KEY)? {
+            let mut events = Vec::new();
+            for id in index.ids.iter().rev().take(limit) {
+                if let Some(event) = get_typed(EVENTS_COLLECTION, id)? {
+                    events.push(event);
+                }
+            }
+            return Ok(events);
+        }
+    }
+
+    let mut events: Vec<StoredEvent> = list_typed(EVENTS_COLLECTION)?;
+    let ids: VecDeque<String> =
+        events.iter().skip(events.len().saturating_sub(RECENT_INDEX_LEN)).map(|event| event.id.clone()).collect();
+    update_typed(EVENT_INDEX_COLLECTION, RECENT_KEY, |index: Option<RecentIndex>| {
+        Ok(Some(index.unwrap_or_else(|| RecentIndex { ids: ids.clone() })))
+    })?;
+    events.reverse();
+    events.truncate(limit);
+    Ok(events)
+}
+
+/// Assign (or replace) the label of a stored event
+pub fn save_label(event_id: &str, label: &str) -> anyhow::Result<EventLabel> {
+    let label = EventLabel {
+        event_id: event_id.to_string(),
+        label: label.trim().to_string(),
+        ts: now_millis(),
+    };
+    anyhow::ensure!(!label.label.is_empty(), "label must not be empty");
+    put_typed(LABELS_COLLECTION, event_id, &label)?;
+    Ok(label)
+}
+
+/// All labels, keyed by event id
+pub fn all_labels() -> anyhow::Result<Vec<EventLabel>> {
+    list_typed(LABELS_COLLECTION)
+}
+
+/// Join labels with their events into training examples
+pub fn labeled_examples() -> anyhow::Result<Vec<LabeledExample>> {
+    let events: Vec<StoredEvent> = list_typed(EVENTS_COLLECTION)?;
+    let labels = all_labels()?;
+    Ok(labels
+        .into_iter()
+        .filter_map(|label| {
+            events.iter()
+                .find(|event| event.id == label.event_id)
+                .map(|event| LabeledExample { text: event.data.clone(), label: label.label })
+        })
+        .collect())
+}
+
+/// Export labeled examples as JSON Lines (one `{"text", "label"}` object per line)
+pub fn export_jsonl(examples: &[LabeledExample]) -> String {
+    examples
+        .iter()
+        .filter_map(|example| serde_json::to_string(example).ok())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse a JSON Lines dataset produced by `export_jsonl`
+pub fn parse_jsonl(jsonl: &str) -> anyhow::Result<Vec<LabeledExample>> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(Into::into))
+        .collect()
+}
+
+// ============================================================================
+// Dataset Versioning
+// ============================================================================
+
+/// Snapshot metadata of a dataset used for training
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetVersion {
+    /// `ds-<hash>`; identical content maps to the same version
+    pub id: String,
+    /// Content hash (see `experiments::hash_dataset`)
+    pub hash: String,
+    pub rows: usize,
+    /// Where the rows came from (e.g. `labels`, `inline`)
+    pub source: String,
+    /// Parameters of the transform that produced the rows (split, fold, filters, ...)
+    pub transform: serde_json::Value,
+    pub created_at: u64,
+}
+
+/// Register a dataset snapshot, reusing the existing version if the content is unchanged
+pub fn register_dataset_version<T: Serialize>(
+    data: &[T],
+    source: &str,
+    transform: serde_json::Value,
+) -> anyhow::Result<DatasetVersion> {
+    let hash = hash_dataset(data);
+    let id = format!("ds-{}", hash);
+    if let Some(existing) = get_typed::<DatasetVersion>(DATASETS_COLLECTION, &id)? {
+        return Ok(existing);
+    }
+
+    let version = DatasetVersion {
+        id,
+        hash,
+        rows: data.len(),
+        source: source.to_string(),
+        transform,
+        created_at: now_millis(),
+    };
+    put_typed(DATASETS_COLLECTION, &version.id, &version)?;
+    Ok(version)
+}
+
+/// All dataset versions, newest first
+pub fn list_dataset_versions() -> anyhow::Result<Vec<DatasetVersion>> {
+    let mut versions: Vec<DatasetVersion> = list_typed(DATASETS_COLLECTION)?;
+    versions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(versions)
+}
+
+/// Look up one dataset version
+pub fn get_dataset_version(id: &str) -> anyhow::Result<Option<DatasetVersion>> {
+    get_typed(DATASETS_COLLECTION, id)
+}
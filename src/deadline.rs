@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::storage::now_millis;
+
+// ============================================================================
+// Request Deadlines
+// ============================================================================
+//
+// A caller's time budget travels with its work: the HTTP layer turns the
+// `X-Request-Timeout-Ms` header into a `Deadline`, agents receive it wrapped
+// around the message (`AgentMessage::WithDeadline`) and run the handler in a
+// deadline scope, and LLM requests inside that scope are cancelled once the
+// budget is spent instead of completing for a caller that already gave up.
+
+/// Header carrying the caller's budget in milliseconds
+pub const TIMEOUT_HEADER: &str = "x-request-timeout-ms";
+
+/// Point in time (milliseconds since the Unix epoch) after which work is abandoned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Deadline {
+    pub at_ms: u64,
+}
+
+impl Deadline {
+    /// Deadline `timeout` from now
+    pub fn after(timeout: Duration) -> Self {
+        Self { at_ms: now_millis().saturating_add(timeout.as_millis() as u64) }
+    }
+
+    /// Time left before the deadline (zero once it has passed)
+    pub fn remaining(&self) -> Duration {
+        Duration::from_millis(self.at_ms.saturating_sub(now_millis()))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        now_millis() >= self.at_ms
+    }
+
+    /// The earlier of two deadlines
+    pub fn min(self, other: Option<Deadline>) -> Deadline {
+        other.map_or(self, |other| Ord::min(self, other))
+    }
+}
+
+/// Work abandoned because its deadline passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineExceeded(pub Deadline);
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline exceeded ({} ms ago)", now_millis().saturating_sub(self.0.at_ms))
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+tokio::task_local! {
+    static CURRENT: Deadline;
+}
+
+/// Deadline of the surrounding `scope`, if any
+pub fn current() -> Option<Deadline> {
+    CURRENT.try_with(|deadline| *deadline).ok()
+}
+
+/// Run `future` with `deadline` as the current deadline (nested scopes keep the earlier one)
+pub async fn scope<F: Future>(deadline: Deadline, future: F) -> F::Output {
+    CURRENT.scope(deadline.min(current()), future).await
+}
+
+/// Run `future`, cancelling it with `DeadlineExceeded` when the current deadline passes
+pub async fn enforce<T, F>(future: F) -> anyhow::Result<T>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    let Some(deadline) = current() else {
+        return future.await;
+    };
+    if deadline.is_expired() {
+        return Err(DeadlineExceeded(deadline).into());
+    }
+    tokio::time::timeout(deadline.remaining(), future)
+        .await
+        .map_err(|_| anyhow::Error::from(DeadlineExceeded(deadline)))?
+}
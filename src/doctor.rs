@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::config;
+
+// ============================================================================
+// Startup Diagnostics (`pattern-clock doctor`)
+// ============================================================================
+//
+// Checks everything the service depends on and reports all problems at
+// once instead of failing on the first:
+//
+//   config    environment and config file parse and validate
+//   gpu       a WGPU adapter is available to Burn
+//   ollama    the configured Ollama server answers
+//   storage   the data directory is writable (or Postgres is reachable)
+//   port      the server address (`IP` / `PORT`) can be bound
+//
+// `pattern-clock doctor` prints the report and exits non-zero on failures;
+// the desktop app shows the same report on first launch.
+
+/// Marker written to the data directory once the first-launch report was dismissed
+const FIRST_LAUNCH_MARKER: &str = ".diagnostics-seen";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but something may need attention
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status, detail: detail.into() }
+    }
+}
+
+/// All checks, in the order they ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    pub checks: Vec<Check>,
+}
+
+impl DiagnosticsReport {
+    /// True when no check failed (warnings allowed)
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for DiagnosticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pattern-clock diagnostics")?;
+        for check in &self.checks {
+            let mark = match check.status {
+                CheckStatus::Ok => "ok  ",
+                CheckStatus::Warn => "warn",
+                CheckStatus::Fail => "FAIL",
+            };
+            writeln!(f, "  [{}] {:<8} {}", mark, check.name, check.detail)?;
+        }
+        let failures = self.checks.iter().filter(|check| check.status == CheckStatus::Fail).count();
+        if failures == 0 {
+            write!(f, "All checks passed")
+        } else {
+            write!(f, "{} check(s) failed", failures)
+        }
+    }
+}
+
+/// Run every check
+pub async fn run_diagnostics() -> DiagnosticsReport {
+    let checks = vec![
+        check_config(),
+        check_gpu().await,
+        check_ollama().await,
+        check_storage(),
+        check_port(),
+    ];
+    DiagnosticsReport { checks }
+}
+
+fn check_config() -> Check {
+    let problems = crate::config::problems();
+    if problems.is_empty() {
+        let source = std::env::var("PATTERN_CLOCK_CONFIG")
+            .map(|path| format!("environment and {}", path))
+            .unwrap_or_else(|_| "environment".to_string());
+        Check::new("config", CheckStatus::Ok, format!("valid ({})", source))
+    } else {
+        Check::new("config", CheckStatus::Fail, problems.join("; "))
+    }
+}
+
+async fn check_gpu() -> Check {
+    let probe = crate::compute::run("doctor.gpu", || {
+        use burn::backend::wgpu::Wgpu;
+        use burn::tensor::Tensor;
+        let device = Default::default();
+        let tensor = Tensor::<Wgpu, 1>::from_floats([1.0, 2.0, 3.0], &device);
+        tensor.sum().into_data().to_vec::<f32>().map(|sum| sum.first().copied()).ok().flatten()
+    });
+    match tokio::time::timeout(CHECK_TIMEOUT * 3, probe).await {
+        Ok(Ok(Some(sum))) if (sum - 6.0).abs() < 1e-3 => {
+            let status = crate::gpu::status();
+            if status.cpu_fallback {
+                Check::new("gpu", CheckStatus::Warn, "WGPU adapter available, but running on the CPU after out-of-memory failures")
+            } else {
+                Check::new("gpu", CheckStatus::Ok, "WGPU adapter available")
+            }
+        }
+        Ok(Ok(result)) => Check::new("gpu", CheckStatus::Fail, format!("WGPU computed a wrong result ({:?})", result)),
+        Ok(Err(e)) => Check::new("gpu", CheckStatus::Fail, format!("no usable WGPU adapter: {}", e)),
+        Err(_) => Check::new("gpu", CheckStatus::Fail, "timed out initializing the WGPU adapter"),
+    }
+}
+
+async fn check_ollama() -> Check {
+    let config = config();
+    let url = format!("{}/api/tags", config.ollama_url.trim_end_matches('/'));
+    let mut request = reqwest::Client::new().get(&url).timeout(CHECK_TIMEOUT);
+    if let Some(key) = &config.ollama_api_key {
+        request = request.bearer_auth(key.expose());
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => {
+            let models: Vec<String> = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|body| body.get("models").and_then(|m| m.as_array()).cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|model| model.get("name").and_then(|n| n.as_str()).map(str::to_string))
+                .collect();
+            let has_model = models.iter().any(|name| {
+                name == &config.ollama_model || name.split(':').next() == Some(config.ollama_model.as_str())
+            });
+            if has_model {
+                Check::new("ollama", CheckStatus::Ok, format!("{} reachable, model {} available", config.ollama_url, config.ollama_model))
+            } else {
+                Check::new("ollama", CheckStatus::Warn, format!(
+                    "{} reachable, but model {} is not pulled (run `ollama pull {}`)",
+                    config.ollama_url, config.ollama_model, config.ollama_model
+                ))
+            }
+        }
+        Ok(response) => Check::new("ollama", CheckStatus::Fail, format!("{} answered {}", url, response.status())),
+        Err(e) => Check::new("ollama", CheckStatus::Fail, format!("{} unreachable: {}", config.ollama_url, e)),
+    }
+}
+
+fn check_storage() -> Check {
+    #[cfg(feature = "postgres")]
+    if let Some(url) = config().database_url.clone() {
+        use crate::storage::Storage;
+        let result = crate::postgres::PostgresStorage::connect(url.expose(), 1)
+            .and_then(|storage| storage.list("doctor").map(|_| ()));
+        return match result {
+            Ok(()) => Check::new("storage", CheckStatus::Ok, "Postgres reachable, schema up to date"),
+            Err(e) => Check::new("storage", CheckStatus::Fail, format!("Postgres unreachable: {}", e)),
+        };
+    }
+    let dir = config().data_dir.clone();
+    let probe = Path::new(&dir).join(".doctor-probe");
+    let result = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check::new("storage", CheckStatus::Ok, format!("{} is writable", dir)),
+        Err(e) => Check::new("storage", CheckStatus::Fail, format!("{} is not writable: {}", dir, e)),
+    }
+}
+
+fn check_port() -> Check {
+    let ip = std::env::var("IP").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
+    let address = format!("{}:{}", ip, port);
+    match std::net::TcpListener::bind(&address) {
+        Ok(_) => Check::new("port", CheckStatus::Ok, format!("{} is free", address)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => Check::new(
+            "port",
+            CheckStatus::Warn,
+            format!("{} is in use (fine if the server is already running)", address),
+        ),
+        Err(e) => Check::new("port", CheckStatus::Fail, format!("cannot bind {}: {}", address, e)),
+    }
+}
+
+/// Whether the first-launch diagnostics have not been dismissed yet
+pub fn is_first_launch() -> bool {
+    !Path::new(&config().data_dir).join(FIRST_LAUNCH_MARKER).exists()
+}
+
+/// Remember that the first-launch diagnostics were shown
+pub fn mark_launched() {
+    let dir = config().data_dir.clone();
+    let result = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(Path::new(&dir).join(FIRST_LAUNCH_MARKER), b""));
+    if let Err(e) = result {
+        log_warn!("[Doctor] Failed to record first launch in {}: {}", dir, e);
+    }
+}
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::blobs;
+use crate::config::config;
+use crate::connections::OllamaProvider;
+use crate::events::publish;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Documents
+// ============================================================================
+//
+// Text documents are split into chunks, embedded with the configured Ollama
+// embedding model (`OLLAMA_EMBED_MODEL`) and searched by cosine similarity.
+// The document text is kept in the blob store; chunks and their vectors in
+// the `document_chunks` collection, keyed `<document id>--<index>`.
+//
+// A document is identified by its name, so uploading it again replaces it.
+// Chunks are cut at paragraph boundaries and each carries the hash of its
+// text: on re-upload only chunks whose hash is new are embedded, the rest
+// reuse their stored vectors, and only rows that changed are rewritten.
+// Editing one paragraph of a long document costs one embedding, not all.
+
+/// Collection holding document metadata, keyed by document id
+pub const DOCUMENTS_COLLECTION: &str = "documents";
+/// Collection holding chunks and their embeddings
+pub const CHUNKS_COLLECTION: &str = "document_chunks";
+
+/// Chunks are packed from paragraphs up to this many characters
+const CHUNK_CHARS: usize = 1000;
+
+/// An ingested document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub name: String,
+    /// Blob holding the full text
+    pub hash: String,
+    /// Hash of each chunk's text, in order
+    pub chunks: Vec<String>,
+    /// Model the chunk embeddings came from
+    pub embed_model: String,
+    pub updated_at: u64,
+}
+
+/// One chunk of a document with its embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub document_id: String,
+    pub index: usize,
+    pub hash: String,
+    pub text: String,
+    pub embedding: Vec<f32>,
+}
+
+/// What an ingestion did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestReport {
+    pub document_id: String,
+    pub chunks: usize,
+    /// Chunks sent to the embedding model
+    pub embedded: usize,
+    /// Chunks whose stored embedding was reused
+    pub reused: usize,
+    /// Stored chunks dropped because the document got shorter
+    pub removed: usize,
+}
+
+/// A chunk matching a search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub document_id: String,
+    pub name: String,
+    pub index: usize,
+    pub text: String,
+    /// Cosine similarity to the query
+    pub score: f32,
+}
+
+/// Stable id for the document called `name`
+pub fn document_id(name: &str) -> String {
+    blobs::hash_bytes(name.as_bytes())[..16].to_string()
+}
+
+fn chunk_key(document_id: &str, index: usize) -> String {
+    format!("{}--{:05}", document_id, index)
+}
+
+/// Split `text` into chunks of whole paragraphs (long paragraphs are cut at `CHUNK_CHARS`)
+pub fn chunk_text(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() + 2 > CHUNK_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        let chars: Vec<char> = paragraph.chars().collect();
+        for piece in chars.chunks(CHUNK_CHARS) {
+            if !current.is_empty() {
+                if current.chars().count() + piece.len() + 2 > CHUNK_CHARS {
+                    chunks.push(std::mem::take(&mut current));
+                } else {
+                    current.push_str("\n\n");
+                }
+            }
+            current.extend(piece);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Stored chunks of a document, by index
+fn load_chunks(document_id: &str) -> anyhow::Result<Vec<Chunk>> {
+    let mut chunks: Vec<Chunk> = list_typed::<Chunk>(CHUNKS_COLLECTION)?
+        .into_iter()
+        .filter(|chunk| chunk.document_id == document_id)
+        .collect();
+    chunks.sort_by_key(|chunk| chunk.index);
+    Ok(chunks)
+}
+
+/// Ingest (or re-ingest) the document called `name`, embedding only chunks not seen before
+pub async fn ingest(name: &str, text: &str) -> anyhow::Result<IngestReport> {
+    let config = config();
+    let embed_model = config.ollama_embed_model.clone();
+    let id = document_id(name);
+    let blob = blobs::put(text.as_bytes(), "text/plain; charset=utf-8")?;
+    let previous = get_typed::<Document>(DOCUMENTS_COLLECTION, &id)?;
+    let stored = load_chunks(&id)?;
+
+    // Vectors from another model are not comparable, so a model change re-embeds everything
+    let same_model = previous.as_ref().is_some_and(|doc| doc.embed_model == embed_model);
+    let known: HashMap<&str, &[f32]> = if same_model {
+        stored.iter().map(|chunk| (chunk.hash.as_str(), chunk.embedding.as_slice())).collect()
+    } else {
+        HashMap::new()
+    };
+
+    let texts = chunk_text(text);
+    let hashes: Vec<String> = texts.iter().map(|text| blobs::hash_bytes(text.as_bytes())).collect();
+    let mut missing: Vec<(&str, String)> = Vec::new();
+    for (text, hash) in texts.iter().zip(&hashes) {
+        if !known.contains_key(hash.as_str()) && !missing.iter().any(|(seen, _)| *seen == hash.as_str()) {
+            missing.push((hash, text.clone()));
+        }
+    }
+    let inputs: Vec<String> = missing.iter().map(|(_, text)| text.clone()).collect();
+    let embeddings = OllamaProvider::from_config(&config).embed(&embed_model, &inputs).await?;
+    let fresh: HashMap<&str, Vec<f32>> = missing.iter().map(|(hash, _)| *hash).zip(embeddings).collect();
+
+    let mut report = IngestReport { document_id: id.clone(), chunks: texts.len(), embedded: missing.len(), ..Default::default() };
+    for (index, (text, hash)) in texts.into_iter().zip(&hashes).enumerate() {
+        let embedding = match known.get(hash.as_str()) {
+            Some(embedding) => {
+                report.reused += 1;
+                embedding.to_vec()
+            }
+            None => fresh[hash.as_str()].clone(),
+        };
+        // Rows already holding this chunk are left alone
+        if same_model && stored.get(index).is_some_and(|chunk| chunk.index == index && chunk.hash == *hash) {
+            continue;
+        }
+        let chunk = Chunk { document_id: id.clone(), index, hash: hash.clone(), text, embedding };
+        put_typed(CHUNKS_COLLECTION, &chunk_key(&id, index), &chunk)?;
+    }
+    for chunk in stored.iter().filter(|chunk| chunk.index >= hashes.len()) {
+        storage().delete(CHUNKS_COLLECTION, &chunk_key(&id, chunk.index))?;
+        report.removed += 1;
+    }
+
+    let document = Document {
+        id: id.clone(),
+        name: name.to_string(),
+        hash: blob.hash,
+        chunks: hashes,
+        embed_model,
+        updated_at: now_millis(),
+    };
+    put_typed(DOCUMENTS_COLLECTION, &id, &document)?;
+    let owner = format!("document:{}", id);
+    blobs::add_ref(&document.hash, &owner)?;
+    if let Some(previous) = previous.filter(|previous| previous.hash != document.hash) {
+        blobs::remove_ref(&previous.hash, &owner)?;
+    }
+
+    log_info!(
+        "[Documents] Ingested {}: {} chunks, {} embedded, {} reused, {} removed",
+        name, report.chunks, report.embedded, report.reused, report.removed
+    );
+    publish("document.ingested", serde_json::json!({ "name": name, "report": report }));
+    Ok(report)
+}
+
+/// All documents, by id
+pub fn list_documents() -> anyhow::Result<Vec<Document>> {
+    list_typed(DOCUMENTS_COLLECTION)
+}
+
+/// Delete a document and its chunks, returning whether it existed
+pub fn delete_document(id: &str) -> anyhow::Result<bool> {
+    let Some(document) = get_typed::<Document>(DOCUMENTS_COLLECTION, id)? else {
+        return Ok(false);
+    };
+    for chunk in load_chunks(id)? {
+        storage().delete(CHUNKS_COLLECTION, &chunk_key(id, chunk.index))?;
+    }
+    blobs::remove_ref(&document.hash, &format!("document:{}", id))?;
+    storage().delete(DOCUMENTS_COLLECTION, id)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 { 0.0 } else { dot / denominator }
+}
+
+/// The `limit` chunks most similar to `query`, best first
+pub async fn search(query: &str, limit: usize) -> anyhow::Result<Vec<SearchHit>> {
+    let config = config();
+    let embed_model = config.ollama_embed_model.clone();
+    let query_embedding = OllamaProvider::from_config(&config)
+        .embed(&embed_model, &[query.to_string()])
+        .await?
+        .remove(0);
+
+    let names: HashMap<String, String> = list_documents()?
+        .into_iter()
+        .filter(|doc| doc.embed_model == embed_model)
+        .map(|doc| (doc.id, doc.name))
+        .collect();
+    let mut hits: Vec<SearchHit> = list_typed::<Chunk>(CHUNKS_COLLECTION)?
+        .into_iter()
+        .filter_map(|chunk| {
+            let name = names.get(&chunk.document_id)?.clone();
+            Some(SearchHit {
+                score: cosine(&query_embedding, &chunk.embedding),
+                document_id: chunk.document_id,
+                name,
+                index: chunk.index,
+                text: chunk.text,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
@@ -0,0 +1,617 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+
+use crate::blobs::{BlobBackend, BlobInfo, BLOBS_COLLECTION};
+use crate::config::{config, ServiceConfig};
+use crate::storage::{list_typed, Storage, UpdateFn};
+
+// ============================================================================
+// Encryption at Rest
+// ============================================================================
+//
+// With `PATTERN_CLOCK_ENCRYPTION_KEY` set (a secret reference to 32 random
+// bytes in base64, see `pattern-clock encryption generate-key`), documents of
+// the collections in `PATTERN_CLOCK_ENCRYPTED_COLLECTIONS` are sealed before
+// they reach storage, and blob contents too when `blobs` is listed.
+//
+// Sealing is envelope encryption: every document gets its own random data
+// key (ChaCha20-Poly1305), and the data key is stored wrapped by the master
+// key next to the ciphertext:
+//
+//     {"$encrypted": {"v": 1, "kid": "<master key id>", "key": "<wrapped data key>",
+//                     "nonce": "...", "data": "<ciphertext>"}}
+//
+// Reads decrypt transparently, whatever the collection, and pass plain
+// documents through, so data written before encryption was turned on keeps
+// working. The collection and key are bound to the ciphertext, so a sealed
+// document can't be moved to another key.
+//
+// To rotate, make the new key `PATTERN_CLOCK_ENCRYPTION_KEY`, move the old
+// one to `PATTERN_CLOCK_ENCRYPTION_OLD_KEYS` (comma-separated), restart,
+// and run `pattern-clock encryption rotate`: it re-wraps every data key
+// under the new key (the data itself isn't re-encrypted) and seals plain
+// documents left in encrypted collections. Afterwards the old key can go.
+//
+// Storage set with `storage::set_storage` and blob backends set with
+// `blobs::set_backend` are used as given.
+
+/// Field of a sealed document
+const ENVELOPE_FIELD: &str = "$encrypted";
+
+/// First bytes of sealed blob contents
+const BLOB_MAGIC: &[u8] = b"PCENC1";
+
+const NONCE_LEN: usize = 12;
+/// Hex characters of a key id
+const KEY_ID_LEN: usize = 8;
+/// Nonce, data key and tag
+const WRAPPED_KEY_LEN: usize = NONCE_LEN + 32 + 16;
+
+/// A 32-byte key from its base64 form
+pub fn parse_key(value: &str) -> Result<[u8; 32], String> {
+    let bytes = STANDARD.decode(value.trim()).map_err(|e| format!("is not base64: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("must be 32 bytes, not {}", bytes.len()))
+}
+
+/// A new random key, base64-encoded
+pub fn generate_key() -> String {
+    STANDARD.encode(ChaCha20Poly1305::generate_key(&mut OsRng))
+}
+
+struct MasterKey {
+    /// Start of the key's SHA-256, stored with the data keys it wraps
+    id: String,
+    cipher: ChaCha20Poly1305,
+}
+
+impl MasterKey {
+    fn new(key: &[u8; 32]) -> Self {
+        let id = Sha256::digest(key)[..KEY_ID_LEN / 2].iter().map(|byte| format!("{:02x}", byte)).collect();
+        MasterKey { id, cipher: ChaCha20Poly1305::new(Key::from_slice(key)) }
+    }
+
+    fn wrap_key(&self, data_key: &Key) -> anyhow::Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: data_key.as_slice(), aad: self.id.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("failed to wrap data key"))?;
+        Ok([nonce.as_slice(), &wrapped].concat())
+    }
+
+    fn unwrap_key(&self, wrapped: &[u8]) -> anyhow::Result<Key> {
+        anyhow::ensure!(wrapped.len() == WRAPPED_KEY_LEN, "malformed wrapped key");
+        let (nonce, wrapped) = wrapped.split_at(NONCE_LEN);
+        let data_key = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: wrapped, aad: self.id.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("failed to unwrap data key with key {}", self.id))?;
+        Ok(Key::clone_from_slice(&data_key))
+    }
+}
+
+/// Data sealed under a data key, and that key wrapped by a master key
+struct Sealed {
+    key_id: String,
+    wrapped_key: Vec<u8>,
+    nonce: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Sealed {
+    fn to_value(&self) -> Value {
+        let mut envelope = serde_json::Map::new();
+        envelope.insert(ENVELOPE_FIELD.to_string(), json!({
+            "v": 1,
+            "kid": self.key_id,
+            "key": STANDARD.encode(&self.wrapped_key),
+            "nonce": STANDARD.encode(&self.nonce),
+            "data": STANDARD.encode(&self.data),
+        }));
+        Value::Object(envelope)
+    }
+
+    /// `None` for a plain document
+    fn from_value(value: &Value) -> Option<anyhow::Result<Self>> {
+        let object = value.as_object().filter(|object| object.len() == 1)?;
+        Some(Self::from_envelope(object.get(ENVELOPE_FIELD)?))
+    }
+
+    fn from_envelope(envelope: &Value) -> anyhow::Result<Self> {
+        let field = |name: &str| {
+            envelope[name].as_str().ok_or_else(|| anyhow::anyhow!("sealed document without {}", name))
+        };
+        Ok(Sealed {
+            key_id: field("kid")?.to_string(),
+            wrapped_key: STANDARD.decode(field("key")?)?,
+            nonce: STANDARD.decode(field("nonce")?)?,
+            data: STANDARD.decode(field("data")?)?,
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [BLOB_MAGIC, self.key_id.as_bytes(), &self.wrapped_key, &self.nonce, &self.data].concat()
+    }
+
+    /// `None` for plain contents
+    fn from_bytes(bytes: &[u8]) -> Option<anyhow::Result<Self>> {
+        let rest = bytes.strip_prefix(BLOB_MAGIC)?;
+        if rest.len() < KEY_ID_LEN + WRAPPED_KEY_LEN + NONCE_LEN {
+            return Some(Err(anyhow::anyhow!("truncated sealed blob")));
+        }
+        let (key_id, rest) = rest.split_at(KEY_ID_LEN);
+        let (wrapped_key, rest) = rest.split_at(WRAPPED_KEY_LEN);
+        let (nonce, data) = rest.split_at(NONCE_LEN);
+        Some(Ok(Sealed {
+            key_id: String::from_utf8_lossy(key_id).into_owned(),
+            wrapped_key: wrapped_key.to_vec(),
+            nonce: nonce.to_vec(),
+            data: data.to_vec(),
+        }))
+    }
+}
+
+/// The current master key and the old ones still accepted for reading
+pub struct Keyring {
+    current: MasterKey,
+    old: Vec<MasterKey>,
+}
+
+impl Keyring {
+    /// Keys of `config`; `None` when encryption is off
+    pub fn from_config(config: &ServiceConfig) -> Result<Option<Self>, String> {
+        let Some(key) = &config.encryption_key else {
+            return Ok(None);
+        };
+        let current = MasterKey::new(&parse_key(key.expose()).map_err(|e| format!("encryption key {}", e))?);
+        let old = match &config.encryption_old_keys {
+            Some(keys) => keys
+                .expose()
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(|key| parse_key(key).map(|key| MasterKey::new(&key)))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("old encryption key {}", e))?,
+            None => Vec::new(),
+        };
+        Ok(Some(Keyring { current, old }))
+    }
+
+    /// Id of the key new data keys are wrapped with
+    pub fn key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    fn find(&self, key_id: &str) -> anyhow::Result<&MasterKey> {
+        std::iter::once(&self.current)
+            .chain(&self.old)
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| anyhow::anyhow!("data sealed with unknown key {}", key_id))
+    }
+
+    fn seal(&self, aad: &[u8], plaintext: &[u8]) -> anyhow::Result<Sealed> {
+        let data_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let data = ChaCha20Poly1305::new(&data_key)
+            .encrypt(&nonce, Payload { msg: plaintext, aad })
+            .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+        Ok(Sealed {
+            key_id: self.current.id.clone(),
+            wrapped_key: self.current.wrap_key(&data_key)?,
+            nonce: nonce.to_vec(),
+            data,
+        })
+    }
+
+    fn open(&self, aad: &[u8], sealed: &Sealed) -> anyhow::Result<Vec<u8>> {
+        let data_key = self.find(&sealed.key_id)?.unwrap_key(&sealed.wrapped_key)?;
+        anyhow::ensure!(sealed.nonce.len() == NONCE_LEN, "malformed nonce");
+        ChaCha20Poly1305::new(&data_key)
+            .decrypt(Nonce::from_slice(&sealed.nonce), Payload { msg: &sealed.data, aad })
+            .map_err(|_| anyhow::anyhow!("decryption failed: wrong key or modified data"))
+    }
+
+    /// Wrap the data key with the current key; `false` if it already is
+    fn rewrap(&self, sealed: &mut Sealed) -> anyhow::Result<bool> {
+        if sealed.key_id == self.current.id {
+            return Ok(false);
+        }
+        let data_key = self.find(&sealed.key_id)?.unwrap_key(&sealed.wrapped_key)?;
+        sealed.wrapped_key = self.current.wrap_key(&data_key)?;
+        sealed.key_id = self.current.id.clone();
+        Ok(true)
+    }
+}
+
+/// The configured keys, read once; `None` when encryption is off
+///
+/// Panics on an invalid key: storing in the clear what was meant to be
+/// encrypted is worse than not starting.
+pub fn keyring() -> Option<&'static Keyring> {
+    static KEYRING: OnceLock<Option<Keyring>> = OnceLock::new();
+    KEYRING
+        .get_or_init(|| Keyring::from_config(&config()).unwrap_or_else(|e| panic!("[Encryption] Invalid {}", e)))
+        .as_ref()
+}
+
+/// Whether new documents of `collection` are sealed
+pub fn encrypts(collection: &str) -> bool {
+    config().encrypted_collections.iter().any(|name| name == collection)
+}
+
+fn document_aad(collection: &str, key: &str) -> Vec<u8> {
+    format!("{}/{}", collection, key).into_bytes()
+}
+
+fn open_document(keys: &Keyring, collection: &str, key: &str, value: Value) -> anyhow::Result<Value> {
+    match Sealed::from_value(&value) {
+        Some(sealed) => Ok(serde_json::from_slice(&keys.open(&document_aad(collection, key), &sealed?)?)?),
+        None => Ok(value),
+    }
+}
+
+/// Storage that seals documents of encrypted collections
+pub struct EncryptedStorage {
+    inner: Arc<dyn Storage>,
+    keys: &'static Keyring,
+}
+
+impl Storage for EncryptedStorage {
+    fn put(&self, collection: &str, key: &str, value: &Value) -> anyhow::Result<()> {
+        if !encrypts(collection) {
+            return self.inner.put(collection, key, value);
+        }
+        let sealed = self.keys.seal(&document_aad(collection, key), &serde_json::to_vec(value)?)?;
+        self.inner.put(collection, key, &sealed.to_value())
+    }
+
+    fn get(&self, collection: &str, key: &str) -> anyhow::Result<Option<Value>> {
+        self.inner
+            .get(collection, key)?
+            .map(|value| open_document(self.keys, collection, key, value))
+            .transpose()
+    }
+
+    fn list(&self, collection: &str) -> anyhow::Result<Vec<(String, Value)>> {
+        self.inner
+            .list(collection)?
+            .into_iter()
+            .map(|(key, value)| open_document(self.keys, collection, &key, value).map(|value| (key, value)))
+            .collect()
+    }
+
+    fn delete(&self, collection: &str, key: &str) -> anyhow::Result<bool> {
+        self.inner.delete(collection, key)
+    }
+
+    fn update(&self, collection: &str, key: &str, f: &mut UpdateFn<'_>) -> anyhow::Result<Option<Value>> {
+        let keys = self.keys;
+        // The caller gets the plain document back, the inner storage the sealed one
+        let mut plain = None;
+        self.inner.update(collection, key, &mut |current| {
+            let current = current.map(|value| open_document(keys, collection, key, value)).transpose()?;
+            plain = f(current)?;
+            match &plain {
+                Some(value) if encrypts(collection) => {
+                    Ok(Some(keys.seal(&document_aad(collection, key), &serde_json::to_vec(value)?)?.to_value()))
+                }
+                other => Ok(other.clone()),
+            }
+        })?;
+        Ok(plain)
+    }
+}
+
+/// Blob backend that seals contents when `blobs` is an encrypted collection
+pub struct EncryptedBlobs {
+    inner: Arc<dyn BlobBackend>,
+    keys: &'static Keyring,
+}
+
+impl BlobBackend for EncryptedBlobs {
+    fn write(&self, hash: &str, data: &[u8]) -> anyhow::Result<()> {
+        if !encrypts(BLOBS_COLLECTION) {
+            return self.inner.write(hash, data);
+        }
+        self.inner.write(hash, &self.keys.seal(hash.as_bytes(), data)?.to_bytes())
+    }
+
+    fn read(&self, hash: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(data) = self.inner.read(hash)? else {
+            return Ok(None);
+        };
+        match Sealed::from_bytes(&data) {
+            Some(sealed) => Ok(Some(self.keys.open(hash.as_bytes(), &sealed?)?)),
+            None => Ok(Some(data)),
+        }
+    }
+
+    fn exists(&self, hash: &str) -> anyhow::Result<bool> {
+        self.inner.exists(hash)
+    }
+
+    fn remove(&self, hash: &str) -> anyhow::Result<()> {
+        self.inner.remove(hash)
+    }
+}
+
+/// The storage under the encryption, kept for key rotation
+static RAW_STORAGE: OnceLock<Arc<dyn Storage>> = OnceLock::new();
+static RAW_BLOBS: OnceLock<Arc<dyn BlobBackend>> = OnceLock::new();
+
+/// `inner` behind `EncryptedStorage` when a key is configured
+pub fn wrap_storage(inner: Box<dyn Storage>) -> Box<dyn Storage> {
+    let Some(keys) = keyring() else {
+        return inner;
+    };
+    let inner: Arc<dyn Storage> = Arc::from(inner);
+    let _ = RAW_STORAGE.set(inner.clone());
+    log_info!("[Encryption] Sealing {} with key {}", config().encrypted_collections.join(", "), keys.key_id());
+    Box::new(EncryptedStorage { inner, keys })
+}
+
+/// `inner` behind `EncryptedBlobs` when a key is configured
+pub fn wrap_blobs(inner: Box<dyn BlobBackend>) -> Box<dyn BlobBackend> {
+    let Some(keys) = keyring() else {
+        return inner;
+    };
+    let inner: Arc<dyn BlobBackend> = Arc::from(inner);
+    let _ = RAW_BLOBS.set(inner.clone());
+    Box::new(EncryptedBlobs { inner, keys })
+}
+
+/// What a rotation did to one collection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionRotation {
+    /// Collection name, or `blob contents`
+    pub collection: String,
+    /// Data keys now wrapped with the current key
+    pub rewrapped: usize,
+    /// Plain documents sealed
+    pub encrypted: usize,
+    /// Already under the current key
+    pub current: usize,
+    /// Left as they were: sealed with an unknown key, or failed to write
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationReport {
+    /// Id of the current key
+    pub key_id: String,
+    pub collections: Vec<CollectionRotation>,
+}
+
+enum Outcome {
+    Rewrapped,
+    Encrypted,
+    Current,
+    /// Deleted since the collection was listed
+    Gone,
+}
+
+impl CollectionRotation {
+    fn new(collection: &str) -> Self {
+        CollectionRotation { collection: collection.to_string(), ..Default::default() }
+    }
+
+    fn count(&mut self, item: &str, outcome: anyhow::Result<Outcome>) {
+        match outcome {
+            Ok(Outcome::Rewrapped) => self.rewrapped += 1,
+            Ok(Outcome::Encrypted) => self.encrypted += 1,
+            Ok(Outcome::Current) => self.current += 1,
+            Ok(Outcome::Gone) => {}
+            Err(e) => {
+                log_warn!("[Encryption] Failed to rotate {} in {}: {}", item, self.collection, e);
+                self.failed += 1;
+            }
+        }
+    }
+}
+
+/// Re-seal a document from its current value, so writes made by a running
+/// server since the collection was listed are kept
+fn rotate_document(keys: &Keyring, raw: &dyn Storage, collection: &str, key: &str) -> anyhow::Result<Outcome> {
+    let mut outcome = Outcome::Gone;
+    raw.update(collection, key, &mut |current| {
+        let Some(value) = current else {
+            outcome = Outcome::Gone;
+            return Ok(None);
+        };
+        let sealed = match Sealed::from_value(&value) {
+            Some(sealed) => {
+                let mut sealed = sealed?;
+                if !keys.rewrap(&mut sealed)? {
+                    outcome = Outcome::Current;
+                    return Ok(Some(value));
+                }
+                outcome = Outcome::Rewrapped;
+                sealed
+            }
+            None => {
+                outcome = Outcome::Encrypted;
+                keys.seal(&document_aad(collection, key), &serde_json::to_vec(&value)?)?
+            }
+        };
+        Ok(Some(sealed.to_value()))
+    })?;
+    Ok(outcome)
+}
+
+/// Re-seal a blob's content while holding its info document, so `gc` can't
+/// delete it in between and have it written back
+fn rotate_blob(keys: &Keyring, raw: &dyn BlobBackend, raw_storage: &dyn Storage, hash: &str) -> anyhow::Result<Outcome> {
+    let mut outcome = Outcome::Gone;
+    raw_storage.update(BLOBS_COLLECTION, hash, &mut |info| {
+        outcome = Outcome::Gone;
+        if info.is_none() {
+            return Ok(None);
+        }
+        let Some(data) = raw.read(hash)? else {
+            return Ok(info);
+        };
+        let sealed = match Sealed::from_bytes(&data) {
+            Some(sealed) => {
+                let mut sealed = sealed?;
+                if !keys.rewrap(&mut sealed)? {
+                    outcome = Outcome::Current;
+                    return Ok(info);
+                }
+                outcome = Outcome::Rewrapped;
+                sealed
+            }
+            None => {
+                outcome = Outcome::Encrypted;
+                keys.seal(hash.as_bytes(), &data)?
+            }
+        };
+        raw.write(hash, &sealed.to_bytes())?;
+        Ok(info)
+    })?;
+    Ok(outcome)
+}
+
+/// Re-wrap every data key of the encrypted collections with the current key,
+/// sealing plain documents on the way
+pub fn rotate() -> anyhow::Result<RotationReport> {
+    let keys = keyring().ok_or_else(|| anyhow::anyhow!("encryption is off: PATTERN_CLOCK_ENCRYPTION_KEY is not set"))?;
+    crate::storage::storage();
+    let raw = RAW_STORAGE.get().ok_or_else(|| anyhow::anyhow!("storage was set up without encryption"))?;
+
+    let mut collections = Vec::new();
+    for collection in &config().encrypted_collections {
+        let mut rotation = CollectionRotation::new(collection);
+        for (key, _) in raw.list(collection)? {
+            rotation.count(&key, rotate_document(keys, raw.as_ref(), collection, &key));
+        }
+        collections.push(rotation);
+    }
+
+    if encrypts(BLOBS_COLLECTION) {
+        crate::blobs::backend();
+        let raw_blobs = RAW_BLOBS.get().ok_or_else(|| anyhow::anyhow!("blob backend was set up without encryption"))?;
+        let mut rotation = CollectionRotation::new("blob contents");
+        for blob in list_typed::<BlobInfo>(BLOBS_COLLECTION)? {
+            rotation.count(&blob.hash, rotate_blob(keys, raw_blobs.as_ref(), raw.as_ref(), &blob.hash));
+        }
+        collections.push(rotation);
+    }
+
+    let rewrapped: usize = collections.iter().map(|rotation| rotation.rewrapped + rotation.encrypted).sum();
+    log_info!("[Encryption] Rotated to key {}: {} items rewritten", keys.key_id(), rewrapped);
+    Ok(RotationReport { key_id: keys.key_id().to_string(), collections })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MemoryStorage;
+
+    fn keyring(current: u8, old: &[u8]) -> Keyring {
+        Keyring {
+            current: MasterKey::new(&[current; 32]),
+            old: old.iter().map(|key| MasterKey::new(&[*key; 32])).collect(),
+        }
+    }
+
+    fn sealed_document(keys: &Keyring, value: &Value) -> Value {
+        keys.seal(&document_aad("notes", "a"), &serde_json::to_vec(value).unwrap()).unwrap().to_value()
+    }
+
+    #[test]
+    fn sealed_documents_and_blobs_open_to_the_original() {
+        let keys = keyring(1, &[]);
+        let value = json!({"title": "hello", "count": 3});
+        let sealed = sealed_document(&keys, &value);
+        assert!(sealed.get("title").is_none());
+        assert_eq!(open_document(&keys, "notes", "a", sealed).unwrap(), value);
+
+        let blob = keys.seal(b"blob", b"raw bytes").unwrap().to_bytes();
+        let sealed = Sealed::from_bytes(&blob).unwrap().unwrap();
+        assert_eq!(keys.open(b"blob", &sealed).unwrap(), b"raw bytes");
+    }
+
+    #[test]
+    fn a_wrong_key_does_not_open() {
+        let sealed = sealed_document(&keyring(1, &[]), &json!({"title": "hello"}));
+        let error = open_document(&keyring(2, &[]), "notes", "a", sealed.clone()).unwrap_err();
+        assert!(error.to_string().contains("unknown key"));
+
+        // Same id, different key: the wrapped data key fails to authenticate
+        let mut impostor = keyring(2, &[]);
+        impostor.current.id = keyring(1, &[]).key_id().to_string();
+        assert!(open_document(&impostor, "notes", "a", sealed.clone()).is_err());
+
+        // Sealed for another document
+        assert!(open_document(&keyring(1, &[]), "notes", "b", sealed).is_err());
+    }
+
+    #[test]
+    fn tampered_data_or_nonce_does_not_open() {
+        let keys = keyring(1, &[]);
+        let aad = document_aad("notes", "a");
+
+        let mut sealed = keys.seal(&aad, b"secret").unwrap();
+        sealed.data[0] ^= 1;
+        assert!(keys.open(&aad, &sealed).is_err());
+
+        let mut sealed = keys.seal(&aad, b"secret").unwrap();
+        sealed.nonce[0] ^= 1;
+        assert!(keys.open(&aad, &sealed).is_err());
+
+        let blob = keys.seal(b"blob", b"raw bytes").unwrap().to_bytes();
+        assert!(Sealed::from_bytes(&blob[..BLOB_MAGIC.len() + KEY_ID_LEN]).unwrap().is_err());
+    }
+
+    #[test]
+    fn plaintext_written_before_encryption_reads_unchanged() {
+        let keys = keyring(1, &[]);
+        let value = json!({"title": "written in the clear"});
+        assert_eq!(open_document(&keys, "notes", "a", value.clone()).unwrap(), value);
+        assert!(Sealed::from_bytes(b"plain blob contents").is_none());
+    }
+
+    #[test]
+    fn rotation_keeps_old_data_readable_and_rewraps_it() {
+        let value = json!({"title": "hello"});
+        let sealed = sealed_document(&keyring(1, &[]), &value);
+
+        let rotated = keyring(2, &[1]);
+        assert_eq!(open_document(&rotated, "notes", "a", sealed.clone()).unwrap(), value);
+
+        let mut envelope = Sealed::from_value(&sealed).unwrap().unwrap();
+        assert!(rotated.rewrap(&mut envelope).unwrap());
+        assert_eq!(envelope.key_id, rotated.key_id());
+        assert!(!rotated.rewrap(&mut envelope).unwrap());
+
+        // The old key can be dropped once everything is rewrapped
+        let value_after = open_document(&keyring(2, &[]), "notes", "a", envelope.to_value()).unwrap();
+        assert_eq!(value_after, value);
+    }
+
+    #[test]
+    fn rotate_document_encrypts_plain_and_rewraps_old_documents() {
+        let storage = MemoryStorage::default();
+        storage.put("notes", "a", &sealed_document(&keyring(1, &[]), &json!({"n": 1}))).unwrap();
+        storage.put("notes", "plain", &json!({"n": 2})).unwrap();
+
+        let keys = keyring(2, &[1]);
+        assert!(matches!(rotate_document(&keys, &storage, "notes", "a"), Ok(Outcome::Rewrapped)));
+        assert!(matches!(rotate_document(&keys, &storage, "notes", "plain"), Ok(Outcome::Encrypted)));
+        assert!(matches!(rotate_document(&keys, &storage, "notes", "a"), Ok(Outcome::Current)));
+        assert!(matches!(rotate_document(&keys, &storage, "notes", "missing"), Ok(Outcome::Gone)));
+
+        let current = keyring(2, &[]);
+        let plain = storage.get("notes", "plain").unwrap().unwrap();
+        assert_eq!(open_document(&current, "notes", "plain", plain).unwrap(), json!({"n": 2}));
+    }
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::payload::Payload;
+use crate::storage::now_millis;
+
+// ============================================================================
+// Internal Event Bus
+// ============================================================================
+//
+// Events are broadcast to subscribers in this process. A forwarder (the
+// Redis transport, `redis_bus`) can additionally carry them to other
+// instances, which deliver them with `deliver_remote`; such events have
+// their `origin` set, so subscribers acting on events (monitor, stats,
+// notifications) can leave other instances' events to those instances.
+//
+// Subscribers share one `Arc<BroadcastEvent>` per event instead of each
+// getting a clone of its payload and attachment. A subscriber that sends
+// events on as JSON (the events endpoint, FFI, Python, Redis) takes
+// `BroadcastEvent::json`, which serializes the event once for all of them.
+
+/// A structured event published by a subsystem (training, agents, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Dotted event name, e.g. `tuning.trial_finished`
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+    /// Binary (or large text) data travelling with the event; base64 in JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<Payload>,
+    /// Instance that published the event, when it came from another instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+impl Event {
+    /// Whether the event was published in this process
+    pub fn is_local(&self) -> bool {
+        self.origin.is_none()
+    }
+}
+
+/// An event as delivered to subscribers, shared by all of them
+///
+/// Dereferences to the `Event`.
+#[derive(Debug)]
+pub struct BroadcastEvent {
+    event: Event,
+    json: OnceLock<String>,
+}
+
+impl BroadcastEvent {
+    pub fn new(event: Event) -> Arc<Self> {
+        Arc::new(BroadcastEvent { event, json: OnceLock::new() })
+    }
+
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    /// The event as JSON, serialized by the first subscriber asking for it
+    pub fn json(&self) -> &str {
+        self.json.get_or_init(|| serde_json::to_string(&self.event).unwrap_or_default())
+    }
+}
+
+impl Deref for BroadcastEvent {
+    type Target = Event;
+
+    fn deref(&self) -> &Event {
+        &self.event
+    }
+}
+
+impl Serialize for BroadcastEvent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.event.serialize(serializer)
+    }
+}
+
+/// What subscribers receive
+pub type SharedEvent = Arc<BroadcastEvent>;
+
+/// Receives every locally published event, e.g. to send it to other instances
+pub type Forwarder = Box<dyn Fn(&BroadcastEvent) + Send + Sync>;
+
+static FORWARDER: OnceLock<Forwarder> = OnceLock::new();
+
+static EVENT_BUS: OnceLock<broadcast::Sender<SharedEvent>> = OnceLock::new();
+
+/// Number of events kept for crash reports
+const RECENT_CAPACITY: usize = 100;
+
+/// Most recent events (without attachments), oldest first
+static RECENT: Mutex<VecDeque<SharedEvent>> = Mutex::new(VecDeque::new());
+
+fn event_bus() -> &'static broadcast::Sender<SharedEvent> {
+    EVENT_BUS.get_or_init(|| {
+        let (tx, _) = broadcast::channel(256);
+        tx
+    })
+}
+
+/// Publish an event to all current subscribers (dropped if nobody listens)
+pub fn publish(kind: &str, payload: serde_json::Value) {
+    publish_with_attachment(kind, payload, None);
+}
+
+/// Publish an event carrying a text or binary attachment
+pub fn publish_with_attachment(kind: &str, payload: serde_json::Value, attachment: Option<Payload>) {
+    let event = BroadcastEvent::new(Event {
+        kind: kind.to_string(),
+        payload,
+        ts: now_millis(),
+        attachment: None,
+        origin: None,
+    });
+    remember(event.clone());
+    // Only events with an attachment differ from what is remembered
+    let event = match attachment {
+        Some(attachment) => BroadcastEvent::new(Event { attachment: Some(attachment), ..event.event().clone() }),
+        None => event,
+    };
+    if let Some(forward) = FORWARDER.get() {
+        forward(&event);
+    }
+    let _ = event_bus().send(event);
+}
+
+/// Deliver an event published by another instance to local subscribers (not forwarded again)
+pub fn deliver_remote(event: Event) {
+    let _ = event_bus().send(BroadcastEvent::new(event));
+}
+
+/// Install the forwarder; fails if one is already set
+pub fn set_forwarder(forwarder: Forwarder) -> Result<(), Forwarder> {
+    FORWARDER.set(forwarder)
+}
+
+/// Keep `event` (published without its attachment) in the recent buffer
+fn remember(event: SharedEvent) {
+    let mut recent = RECENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if recent.len() == RECENT_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(event);
+}
+
+/// The last published events, oldest first
+///
+/// Empty if the buffer is locked, so a panic inside `publish` can still
+/// produce a crash report.
+pub fn recent() -> Vec<Event> {
+    RECENT.try_lock().map(|recent| recent.iter().map(|event| event.event().clone()).collect()).unwrap_or_default()
+}
+
+/// Subscribe to events published from now on
+pub fn subscribe() -> broadcast::Receiver<SharedEvent> {
+    event_bus().subscribe()
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, update_typed};
+
+// ============================================================================
+// Experiment Tracking
+// ============================================================================
+
+/// Collection holding one document per training run
+pub const EXPERIMENTS_COLLECTION: &str = "experiments";
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Metrics recorded at the end of an epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochMetrics {
+    pub epoch: usize,
+    /// Mean training loss over the epoch's batches
+    pub loss: f32,
+}
+
+/// A recorded training run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentRun {
+    /// Sortable id: `run-<millis>-<model>-<sequence>`
+    pub id: String,
+    /// Registry name of the trained model
+    pub model: String,
+    /// Training/model configuration as JSON
+    pub config: serde_json::Value,
+    /// Per-epoch training metrics
+    pub epochs: Vec<EpochMetrics>,
+    /// Final named metrics (train_loss, val_loss, val_accuracy, ...)
+    pub metrics: BTreeMap<String, f32>,
+    /// Paths of saved checkpoints/exports
+    pub artifacts: Vec<String>,
+    /// Blob store hashes of the artifacts' content at the time they were attached
+    #[serde(default)]
+    pub artifact_blobs: Vec<String>,
+    /// Content hash of the training data
+    pub data_hash: String,
+    /// Dataset version (see `dataset::DatasetVersion`) the run trained on
+    #[serde(default)]
+    pub dataset_version: Option<String>,
+    /// RNG seed used for init/shuffling/dropout
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+/// Stable 64-bit FNV-1a hash of the serialized dataset, as hex
+pub fn hash_dataset<T: Serialize>(data: &[T]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for item in data {
+        let bytes = serde_json::to_vec(item).unwrap_or_default();
+        for byte in bytes.iter().chain(b"\n") {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Store a finished run and return its id
+pub fn record_run(
+    model: &str,
+    config: serde_json::Value,
+    epochs: Vec<EpochMetrics>,
+    data_hash: String,
+    dataset_version: Option<String>,
+    seed: u64,
+    started_at: u64,
+) -> anyhow::Result<String> {
+    let finished_at = now_millis();
+    let mut metrics = BTreeMap::new();
+    if let Some(last) = epochs.last() {
+        metrics.insert("train_loss".to_string(), last.loss);
+    }
+
+    let mut run = ExperimentRun {
+        id: String::new(),
+        model: model.to_string(),
+        config,
+        epochs,
+        metrics,
+        artifacts: Vec::new(),
+        artifact_blobs: Vec::new(),
+        data_hash,
+        dataset_version,
+        seed: Some(seed),
+        started_at,
+        finished_at,
+    };
+    // Trials and folds finish within the same millisecond, and other
+    // instances share the collection: never overwrite an existing run
+    loop {
+        run.id = format!("run-{:013}-{}-{}", finished_at, model, SEQUENCE.fetch_add(1, Ordering::Relaxed));
+        let mut taken = false;
+        update_typed(EXPERIMENTS_COLLECTION, &run.id, |current: Option<ExperimentRun>| {
+            taken = current.is_some();
+            Ok(current.or_else(|| Some(run.clone())))
+        })?;
+        if !taken {
+            return Ok(run.id);
+        }
+    }
+}
+
+/// Load a run, apply `update`, and store it again
+fn update_run(run_id: &str, update: impl FnOnce(&mut ExperimentRun)) -> anyhow::Result<()> {
+    let mut run: ExperimentRun = get_typed(EXPERIMENTS_COLLECTION, run_id)?
+        .ok_or_else(|| anyhow::anyhow!("unknown experiment run {}", run_id))?;
+    update(&mut run);
+    put_typed(EXPERIMENTS_COLLECTION, run_id, &run)
+}
+
+/// Set a final metric on a recorded run
+pub fn set_metric(run_id: &str, name: &str, value: f32) -> anyhow::Result<()> {
+    update_run(run_id, |run| {
+        run.metrics.insert(name.to_string(), value);
+    })
+}
+
+/// Attach an artifact path to a recorded run
+pub fn attach_artifact(run_id: &str, path: &str) -> anyhow::Result<()> {
+    update_run(run_id, |run| run.artifacts.push(path.to_string()))
+}
+
+/// Snapshot an artifact file into the blob store, referenced by the run (`run:<id>`)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn attach_artifact_blob(run_id: &str, file: &std::path::Path) -> anyhow::Result<String> {
+    let blob = crate::blobs::put_file(file, "application/octet-stream")?;
+    crate::blobs::add_ref(&blob.hash, &format!("run:{}", run_id))?;
+    let hash = blob.hash.clone();
+    update_run(run_id, |run| {
+        if !run.artifact_blobs.contains(&blob.hash) {
+            run.artifact_blobs.push(blob.hash);
+        }
+    })?;
+    Ok(hash)
+}
+
+/// All recorded runs, newest first
+pub fn list_runs() -> anyhow::Result<Vec<ExperimentRun>> {
+    let mut runs: Vec<ExperimentRun> = list_typed(EXPERIMENTS_COLLECTION)?;
+    runs.reverse();
+    Ok(runs)
+}
+
+/// Runs that trained on the given dataset version, newest first
+pub fn runs_for_dataset(version_id: &str) -> anyhow::Result<Vec<ExperimentRun>> {
+    Ok(list_runs()?
+        .into_iter()
+        .filter(|run| run.dataset_version.as_deref() == Some(version_id))
+        .collect())
+}
+
+/// Load the given runs (unknown ids are skipped)
+pub fn compare_runs(ids: &[String]) -> anyhow::Result<Vec<ExperimentRun>> {
+    let mut runs = Vec::new();
+    for id in ids {
+        if let Some(run) = get_typed::<ExperimentRun>(EXPERIMENTS_COLLECTION, id)? {
+            runs.push(run);
+        }
+    }
+    Ok(runs)
+}
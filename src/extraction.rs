@@ -0,0 +1,158 @@
+use crate::connections::LlmProvider;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// ============================================================================
+// Entity and Keyword Extraction
+// ============================================================================
+
+/// Kind of entity recognized in a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Email,
+    Url,
+    Number,
+    /// Capitalized word sequence (people, places, products)
+    Name,
+    /// Anything an LLM reports that doesn't fit the kinds above
+    #[serde(other)]
+    Other,
+}
+
+/// A single entity found in a text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub text: String,
+}
+
+/// Entities and keywords extracted from one event/document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extraction {
+    pub entities: Vec<Entity>,
+    pub keywords: Vec<String>,
+}
+
+impl Extraction {
+    /// True if any keyword or entity text matches `term` (case-insensitive)
+    pub fn matches(&self, term: &str) -> bool {
+        let term = term.to_lowercase();
+        self.keywords.iter().any(|k| *k == term)
+            || self.entities.iter().any(|e| e.text.to_lowercase() == term)
+    }
+}
+
+/// How extraction is performed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtractionMode {
+    /// Regex rules only (fast, always available)
+    #[default]
+    Rules,
+    /// Ask the LLM for structured JSON, falling back to rules on failure
+    Llm,
+}
+
+impl ExtractionMode {
+    /// Read the mode from `EXTRACTION_MODE` (`rules` or `llm`)
+    pub fn from_env() -> Self {
+        crate::config::config().extraction_mode
+    }
+}
+
+/// Maximum number of keywords kept per text
+const MAX_KEYWORDS: usize = 8;
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have",
+    "in", "is", "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were",
+    "will", "with",
+];
+
+struct Patterns {
+    email: Regex,
+    url: Regex,
+    number: Regex,
+    name: Regex,
+    word: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        email: Regex::new(r"[\w.+-]+@[\w-]+\.[\w.-]+").unwrap(),
+        url: Regex::new(r"https?://[^\s]+").unwrap(),
+        number: Regex::new(r"\b\d+(?:\.\d+)?\b").unwrap(),
+        name: Regex::new(r"\b[A-Z][a-z]+(?:\s+[A-Z][a-z]+)*\b").unwrap(),
+        word: Regex::new(r"[A-Za-z][A-Za-z0-9_-]{2,}").unwrap(),
+    })
+}
+
+/// Rule-based extraction of entities and keywords
+pub fn extract(text: &str) -> Extraction {
+    let patterns = patterns();
+    let mut entities = Vec::new();
+    let mut push = |kind: EntityKind, value: &str| {
+        if !entities.iter().any(|e: &Entity| e.text == value) {
+            entities.push(Entity { kind, text: value.to_string() });
+        }
+    };
+
+    for m in patterns.email.find_iter(text) {
+        push(EntityKind::Email, m.as_str());
+    }
+    for m in patterns.url.find_iter(text) {
+        push(EntityKind::Url, m.as_str());
+    }
+    for m in patterns.number.find_iter(text) {
+        push(EntityKind::Number, m.as_str());
+    }
+    for m in patterns.name.find_iter(text) {
+        push(EntityKind::Name, m.as_str());
+    }
+
+    // Keywords: most frequent non-stopword terms, ties broken by first occurrence
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (position, m) in patterns.word.find_iter(text).enumerate() {
+        let word = m.as_str().to_lowercase();
+        if STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        counts.entry(word).or_insert((0, position)).0 += 1;
+    }
+    let mut ranked: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    let keywords = ranked.into_iter().take(MAX_KEYWORDS).map(|(word, _)| word).collect();
+
+    Extraction { entities, keywords }
+}
+
+/// LLM structured-output extraction
+pub async fn extract_with_llm(provider: &dyn LlmProvider, text: &str) -> anyhow::Result<Extraction> {
+    let prompt = format!(
+        "Extract named entities and keywords from the text below. Respond with JSON of the form \
+         {{\"entities\": [{{\"kind\": \"email|url|number|name|other\", \"text\": \"...\"}}], \
+         \"keywords\": [\"...\"]}}.\n\nText:\n{}",
+        text
+    );
+    let value = provider.generate_json(&prompt).await?;
+    let mut extraction: Extraction = serde_json::from_value(value)?;
+    extraction.keywords = extraction.keywords.into_iter().map(|k| k.to_lowercase()).collect();
+    Ok(extraction)
+}
+
+/// Run extraction in the given mode
+pub async fn extract_with_mode(provider: &dyn LlmProvider, text: &str, mode: ExtractionMode) -> Extraction {
+    match mode {
+        ExtractionMode::Rules => extract(text),
+        ExtractionMode::Llm => match extract_with_llm(provider, text).await {
+            Ok(extraction) => extraction,
+            Err(e) => {
+                log_error!("[Extraction] LLM extraction failed, using rules: {}", e);
+                extract(text)
+            }
+        },
+    }
+}
@@ -0,0 +1,253 @@
+use opentelemetry::KeyValue;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::storage::now_millis;
+use crate::telemetry::instruments;
+
+// ============================================================================
+// Bounded Fan-out with Per-Subscriber Lag Policies
+// ============================================================================
+//
+// One shared history ring of sequence-numbered messages; each named
+// subscriber keeps its own cursor into it, so a subscriber that polls
+// between messages (long-polling web clients) still receives everything
+// published in between. What happens when a subscriber falls behind is
+// chosen per subscriber:
+//
+//   drop_oldest       keep the newest `capacity` messages, skip the rest
+//   disconnect        drop the subscriber; its next poll reports the lag
+//   spill_to_history  keep reading from the whole history ring
+//
+// Skipped messages are counted per subscriber and in the `fanout.lagged`
+// metric.
+
+/// How a subscriber that falls behind is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LagPolicy {
+    DropOldest,
+    Disconnect,
+    #[default]
+    SpillToHistory,
+}
+
+impl LagPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "drop_oldest" => Some(LagPolicy::DropOldest),
+            "disconnect" => Some(LagPolicy::Disconnect),
+            "spill_to_history" => Some(LagPolicy::SpillToHistory),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LagPolicy::DropOldest => "drop_oldest",
+            LagPolicy::Disconnect => "disconnect",
+            LagPolicy::SpillToHistory => "spill_to_history",
+        }
+    }
+}
+
+/// Why a subscriber could not receive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FanoutError {
+    /// The subscriber was dropped after falling `lagged` messages behind
+    Disconnected { lagged: u64 },
+    /// No subscriber with this id (never subscribed, or expired)
+    UnknownSubscriber,
+}
+
+impl std::fmt::Display for FanoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FanoutError::Disconnected { lagged } => {
+                write!(f, "disconnected after falling {} messages behind; subscribe again", lagged)
+            }
+            FanoutError::UnknownSubscriber => write!(f, "unknown subscriber; subscribe first"),
+        }
+    }
+}
+
+impl std::error::Error for FanoutError {}
+
+/// Lag statistics of one subscriber
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriberStats {
+    pub id: String,
+    pub policy: LagPolicy,
+    /// Messages published but not yet received
+    pub pending: u64,
+    pub delivered: u64,
+    /// Messages skipped because the subscriber fell behind
+    pub lagged: u64,
+    pub last_poll_ms: u64,
+}
+
+struct Subscriber {
+    policy: LagPolicy,
+    /// Sequence number of the next message to deliver
+    cursor: u64,
+    delivered: u64,
+    lagged: u64,
+    last_poll_ms: u64,
+}
+
+struct Ring<T> {
+    messages: VecDeque<T>,
+    /// Sequence number of `messages[0]`
+    first_seq: u64,
+    subscribers: HashMap<String, Subscriber>,
+}
+
+impl<T> Ring<T> {
+    fn next_seq(&self) -> u64 {
+        self.first_seq + self.messages.len() as u64
+    }
+}
+
+/// Broadcast channel with a bounded history and per-subscriber cursors
+pub struct Fanout<T> {
+    name: &'static str,
+    history: usize,
+    capacity: usize,
+    idle_timeout: Duration,
+    ring: Mutex<Ring<T>>,
+    notify: Notify,
+    next_id: AtomicU64,
+}
+
+impl<T: Clone> Fanout<T> {
+    /// `history` messages are retained for `spill_to_history` subscribers,
+    /// `capacity` for `drop_oldest` / `disconnect` subscribers
+    pub fn new(name: &'static str, history: usize, capacity: usize) -> Self {
+        Self {
+            name,
+            history: history.max(1),
+            capacity: capacity.clamp(1, history.max(1)),
+            idle_timeout: Duration::from_secs(5 * 60),
+            ring: Mutex::new(Ring { messages: VecDeque::new(), first_seq: 0, subscribers: HashMap::new() }),
+            notify: Notify::new(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Append a message for all subscribers and wake waiting receivers
+    pub fn publish(&self, message: T) {
+        let mut ring = self.ring.lock().unwrap();
+        ring.messages.push_back(message);
+        if ring.messages.len() > self.history {
+            ring.messages.pop_front();
+            ring.first_seq += 1;
+        }
+        // Forget subscribers that stopped polling
+        let idle_before = now_millis().saturating_sub(self.idle_timeout.as_millis() as u64);
+        ring.subscribers.retain(|_, subscriber| subscriber.last_poll_ms >= idle_before);
+        drop(ring);
+        self.notify.notify_waiters();
+    }
+
+    /// Register a subscriber starting at the next published message; returns its id
+    pub fn subscribe(&self, policy: LagPolicy) -> String {
+        let id = format!("{}-{:x}-{}", self.name, now_millis(), self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut ring = self.ring.lock().unwrap();
+        let cursor = ring.next_seq();
+        ring.subscribers.insert(id.clone(), Subscriber {
+            policy,
+            cursor,
+            delivered: 0,
+            lagged: 0,
+            last_poll_ms: now_millis(),
+        });
+        id
+    }
+
+    /// Remove a subscriber
+    pub fn unsubscribe(&self, id: &str) {
+        self.ring.lock().unwrap().subscribers.remove(id);
+    }
+
+    /// Next message for `id` without waiting, applying its lag policy first
+    pub fn try_recv(&self, id: &str) -> Result<Option<T>, FanoutError> {
+        let mut ring = self.ring.lock().unwrap();
+        let first_seq = ring.first_seq;
+        let next_seq = ring.next_seq();
+        let subscriber = ring.subscribers.get_mut(id).ok_or(FanoutError::UnknownSubscriber)?;
+        subscriber.last_poll_ms = now_millis();
+
+        // Oldest message this subscriber may still receive
+        let oldest = match subscriber.policy {
+            LagPolicy::SpillToHistory => first_seq,
+            LagPolicy::DropOldest | LagPolicy::Disconnect => first_seq.max(next_seq.saturating_sub(self.capacity as u64)),
+        };
+        if subscriber.cursor < oldest {
+            let skipped = oldest - subscriber.cursor;
+            subscriber.lagged += skipped;
+            instruments().fanout_lagged.add(skipped, &[
+                KeyValue::new("channel", self.name),
+                KeyValue::new("policy", subscriber.policy.as_str()),
+            ]);
+            log_warn!("[Fanout] {} subscriber {} fell {} messages behind ({})",
+                self.name, id, skipped, subscriber.policy.as_str());
+            if subscriber.policy == LagPolicy::Disconnect {
+                let lagged = subscriber.lagged;
+                ring.subscribers.remove(id);
+                return Err(FanoutError::Disconnected { lagged });
+            }
+            subscriber.cursor = oldest;
+        }
+
+        if subscriber.cursor >= next_seq {
+            return Ok(None);
+        }
+        let index = (subscriber.cursor - first_seq) as usize;
+        subscriber.cursor += 1;
+        subscriber.delivered += 1;
+        Ok(ring.messages.get(index).cloned())
+    }
+
+    /// Wait up to `timeout` for the next message for `id`
+    pub async fn recv(&self, id: &str, timeout: Duration) -> Result<Option<T>, FanoutError> {
+        let wait = async {
+            loop {
+                // Register for the wake-up before checking, so a publish in between is not missed
+                let notified = self.notify.notified();
+                tokio::pin!(notified);
+                notified.as_mut().enable();
+                if let Some(message) = self.try_recv(id)? {
+                    return Ok(Some(message));
+                }
+                notified.await;
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(result) => result,
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Lag statistics of all subscribers
+    pub fn stats(&self) -> Vec<SubscriberStats> {
+        let ring = self.ring.lock().unwrap();
+        let next_seq = ring.next_seq();
+        let mut stats: Vec<SubscriberStats> = ring.subscribers
+            .iter()
+            .map(|(id, subscriber)| SubscriberStats {
+                id: id.clone(),
+                policy: subscriber.policy,
+                pending: next_seq.saturating_sub(subscriber.cursor),
+                delivered: subscriber.delivered,
+                lagged: subscriber.lagged,
+                last_poll_ms: subscriber.last_poll_ms,
+            })
+            .collect();
+        stats.sort_by(|a, b| a.id.cmp(&b.id));
+        stats
+    }
+}
@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::connections::{LlmFuture, LlmProvider};
+use crate::dataset::{StoredEvent, EVENTS_COLLECTION};
+use crate::events::publish;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Output Feedback
+// ============================================================================
+//
+// Users rate and annotate what agents produce: a thumbs up or down, labels
+// and a free-text comment. Feedback targets either an LLM generation or a
+// stored agent event, and keeps a copy of what it rated (prompt, system
+// prompt, model and output) so it stays meaningful after the generation is
+// pruned or the persona changes. A target has one feedback entry; rating it
+// again replaces it.
+//
+// Every LLM call an agent makes (extraction in `llm` mode, summaries,
+// pipeline steps) is recorded as a generation through `RecordingProvider`.
+// Only the newest `MAX_GENERATIONS` are kept.
+//
+// Feedback is aggregated per model and per agent, and rated generations can
+// be exported as JSON Lines fine-tuning datasets (chat, instruction or raw),
+// optionally saved as a blob and registered as a dataset version.
+
+/// Collection holding recorded LLM generations, keyed by generation id
+pub const GENERATIONS_COLLECTION: &str = "generations";
+/// Collection holding feedback, keyed by target (`generation.<id>` or `event.<id>`)
+pub const FEEDBACK_COLLECTION: &str = "feedback";
+
+/// Generations kept; older ones are pruned
+pub const MAX_GENERATIONS: usize = 1000;
+/// Generations recorded between two prunings
+const PRUNE_EVERY: u64 = 100;
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// One LLM call made for an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Generation {
+    /// Sortable id: `<millis>-<agent>-<sequence>`
+    pub id: String,
+    pub agent_id: u8,
+    pub model: String,
+    /// System prompt of the agent's persona, if it has one
+    pub system_prompt: Option<String>,
+    pub prompt: String,
+    pub output: String,
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+}
+
+/// Record a generation, pruning old ones now and then
+pub fn record_generation(agent_id: u8, model: &str, system_prompt: Option<&str>, prompt: &str, output: &str) {
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let ts = now_millis();
+    let generation = Generation {
+        id: format!("{:013}-{}-{}", ts, agent_id, sequence),
+        agent_id,
+        model: model.to_string(),
+        system_prompt: system_prompt.map(str::to_string),
+        prompt: crate::redaction::redact(prompt),
+        output: crate::redaction::redact(output),
+        ts,
+    };
+    if let Err(e) = put_typed(GENERATIONS_COLLECTION, &generation.id, &generation) {
+        log_warn!("[Feedback] Failed to record generation of Agent{}: {}", agent_id, e);
+    }
+    if sequence % PRUNE_EVERY == PRUNE_EVERY - 1 {
+        prune_generations();
+    }
+}
+
+fn prune_generations() {
+    let Ok(ids) = storage().list(GENERATIONS_COLLECTION) else {
+        return;
+    };
+    let excess = ids.len().saturating_sub(MAX_GENERATIONS);
+    for (id, _) in ids.into_iter().take(excess) {
+        let _ = storage().delete(GENERATIONS_COLLECTION, &id);
+    }
+}
+
+/// Most recent generations, newest first
+pub fn recent_generations(limit: usize) -> anyhow::Result<Vec<Generation>> {
+    let mut generations: Vec<Generation> = list_typed(GENERATIONS_COLLECTION)?;
+    generations.reverse();
+    generations.truncate(limit);
+    Ok(generations)
+}
+
+/// Provider wrapper recording every successful call as a generation of `agent_id`
+pub struct RecordingProvider {
+    inner: Arc<dyn LlmProvider>,
+    agent_id: u8,
+    system_prompt: Option<String>,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, agent_id: u8, system_prompt: Option<String>) -> Self {
+        Self { inner, agent_id, system_prompt }
+    }
+
+    fn record(&self, prompt: &str, output: &str) {
+        record_generation(self.agent_id, self.inner.model(), self.system_prompt.as_deref(), prompt, output);
+    }
+}
+
+impl LlmProvider for RecordingProvider {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(async move {
+            let output = self.inner.generate(prompt).await?;
+            self.record(prompt, &output);
+            Ok(output)
+        })
+    }
+
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(async move {
+            let output = self.inner.generate_json(prompt).await?;
+            self.record(prompt, &output.to_string());
+            Ok(output)
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Feedback
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+impl Rating {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Rating::Up),
+            "down" => Some(Rating::Down),
+            _ => None,
+        }
+    }
+}
+
+/// What the feedback is about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FeedbackTarget {
+    /// A recorded LLM generation
+    Generation { id: String },
+    /// A payload processed by an agent (see `/api/events`)
+    Event { id: String },
+}
+
+impl FeedbackTarget {
+    fn key(&self) -> String {
+        match self {
+            FeedbackTarget::Generation { id } => format!("generation.{}", id),
+            FeedbackTarget::Event { id } => format!("event.{}", id),
+        }
+    }
+}
+
+/// Feedback as submitted by a user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedbackInput {
+    pub target: FeedbackTarget,
+    #[serde(default)]
+    pub rating: Option<Rating>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Stored feedback with a copy of the rated output and how it was produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feedback {
+    pub target: FeedbackTarget,
+    pub rating: Option<Rating>,
+    pub labels: Vec<String>,
+    pub comment: Option<String>,
+    pub author: String,
+    pub ts: u64,
+    pub agent_id: u8,
+    /// Model of a generation; `None` for agent events
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub prompt: Option<String>,
+    pub output: String,
+}
+
+/// Save (or replace) the feedback on a generation or event
+pub fn submit(input: FeedbackInput, author: &str) -> anyhow::Result<Feedback> {
+    let labels: Vec<String> = input
+        .labels
+        .iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect();
+    let comment = input.comment.map(|comment| comment.trim().to_string()).filter(|comment| !comment.is_empty());
+    anyhow::ensure!(
+        input.rating.is_some() || !labels.is_empty() || comment.is_some(),
+        "feedback needs a rating, a label or a comment"
+    );
+
+    let mut feedback = Feedback {
+        target: input.target.clone(),
+        rating: input.rating,
+        labels,
+        comment,
+        author: author.to_string(),
+        ts: now_millis(),
+        agent_id: 0,
+        model: None,
+        system_prompt: None,
+        prompt: None,
+        output: String::new(),
+    };
+    match &input.target {
+        FeedbackTarget::Generation { id } => {
+            let generation = get_typed::<Generation>(GENERATIONS_COLLECTION, id)?
+                .ok_or_else(|| anyhow::anyhow!("unknown generation {}", id))?;
+            feedback.agent_id = generation.agent_id;
+            feedback.model = Some(generation.model);
+            feedback.system_prompt = generation.system_prompt;
+            feedback.prompt = Some(generation.prompt);
+            feedback.output = generation.output;
+        }
+        FeedbackTarget::Event { id } => {
+            let event = get_typed::<StoredEvent>(EVENTS_COLLECTION, id)?
+                .ok_or_else(|| anyhow::anyhow!("unknown event {}", id))?;
+            feedback.agent_id = event.agent_id;
+            feedback.output = event.data;
+        }
+    }
+    put_typed(FEEDBACK_COLLECTION, &input.target.key(), &feedback)?;
+    publish("feedback.submitted", json!({
+        "target": feedback.target,
+        "agent_id": feedback.agent_id,
+        "rating": feedback.rating,
+        "labels": feedback.labels,
+    }));
+    Ok(feedback)
+}
+
+/// All feedback, ordered by target
+pub fn list_feedback() -> anyhow::Result<Vec<Feedback>> {
+    list_typed(FEEDBACK_COLLECTION)
+}
+
+/// Ratings of one model or agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Quality {
+    pub up: usize,
+    pub down: usize,
+    /// Share of thumbs up among rated outputs; `None` until something is rated
+    pub approval: Option<f64>,
+}
+
+impl Quality {
+    fn add(&mut self, rating: Option<Rating>) {
+        match rating {
+            Some(Rating::Up) => self.up += 1,
+            Some(Rating::Down) => self.down += 1,
+            None => return,
+        }
+        self.approval = Some(self.up as f64 / (self.up + self.down) as f64);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedbackStats {
+    pub total: usize,
+    pub overall: Quality,
+    /// By model; agent events count under `agent`
+    pub by_model: BTreeMap<String, Quality>,
+    pub by_agent: BTreeMap<u8, Quality>,
+    /// How often each label was given
+    pub labels: BTreeMap<String, usize>,
+}
+
+/// Aggregate quality of all feedback
+pub fn stats() -> anyhow::Result<FeedbackStats> {
+    let mut stats = FeedbackStats::default();
+    for feedback in list_feedback()? {
+        stats.total += 1;
+        stats.overall.add(feedback.rating);
+        let model = feedback.model.clone().unwrap_or_else(|| "agent".to_string());
+        stats.by_model.entry(model).or_default().add(feedback.rating);
+        stats.by_agent.entry(feedback.agent_id).or_default().add(feedback.rating);
+        for label in feedback.labels {
+            *stats.labels.entry(label).or_default() += 1;
+        }
+    }
+    Ok(stats)
+}
+
+// ----------------------------------------------------------------------------
+// Fine-tuning Datasets
+// ----------------------------------------------------------------------------
+//
+// Rated generations become fine-tuning examples in one of three JSON Lines
+// formats, filtered by rating and by when the feedback was given. An export
+// can also be saved as an artifact: the JSONL goes to the blob store (kept
+// from garbage collection by a `dataset:<id>` reference) and is registered as
+// a dataset version, so a training run can name exactly what it used.
+// Event feedback has no prompt and is never exported.
+
+/// JSON Lines layout of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuningFormat {
+    /// OpenAI chat format: `{"messages": [system, user, assistant]}`
+    #[default]
+    Chat,
+    /// Plain instruction pairs: `{"instruction", "output"}`
+    Instruction,
+    /// Everything kept with the feedback: prompt, completion, model, rating, labels, comment
+    Raw,
+}
+
+impl TuningFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "chat" | "openai" => Some(TuningFormat::Chat),
+            "instruction" => Some(TuningFormat::Instruction),
+            "raw" => Some(TuningFormat::Raw),
+            _ => None,
+        }
+    }
+}
+
+/// Which feedback goes into an export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TuningFilter {
+    /// Only feedback with this rating; unrated feedback is always left out
+    #[serde(default)]
+    pub rating: Option<Rating>,
+    /// Feedback given at or after this time (milliseconds since the Unix epoch)
+    #[serde(default)]
+    pub from: Option<u64>,
+    /// Feedback given before this time
+    #[serde(default)]
+    pub to: Option<u64>,
+}
+
+impl TuningFilter {
+    fn accepts(&self, feedback: &Feedback) -> bool {
+        feedback.prompt.is_some()
+            && feedback.rating.is_some()
+            && self.rating.is_none_or(|rating| feedback.rating == Some(rating))
+            && self.from.is_none_or(|from| feedback.ts >= from)
+            && self.to.is_none_or(|to| feedback.ts < to)
+    }
+}
+
+/// One exported example in `format`
+fn tuning_example(feedback: &Feedback, format: TuningFormat) -> Option<serde_json::Value> {
+    let prompt = feedback.prompt.as_deref()?;
+    Some(match format {
+        TuningFormat::Chat => {
+            let mut messages = Vec::new();
+            if let Some(system) = &feedback.system_prompt {
+                messages.push(json!({ "role": "system", "content": system }));
+            }
+            messages.push(json!({ "role": "user", "content": prompt }));
+            messages.push(json!({ "role": "assistant", "content": feedback.output }));
+            json!({ "messages": messages })
+        }
+        TuningFormat::Instruction => json!({ "instruction": prompt, "output": feedback.output }),
+        TuningFormat::Raw => json!({
+            "system": feedback.system_prompt,
+            "prompt": prompt,
+            "completion": feedback.output,
+            "model": feedback.model,
+            "rating": feedback.rating,
+            "labels": feedback.labels,
+            "comment": feedback.comment,
+            "ts": feedback.ts,
+        }),
+    })
+}
+
+/// Examples built from the feedback `filter` accepts, oldest feedback first
+pub fn tuning_examples(feedback: &[Feedback], filter: &TuningFilter, format: TuningFormat) -> Vec<serde_json::Value> {
+    let mut accepted: Vec<&Feedback> = feedback.iter().filter(|feedback| filter.accepts(feedback)).collect();
+    accepted.sort_by_key(|feedback| feedback.ts);
+    accepted.into_iter().filter_map(|feedback| tuning_example(feedback, format)).collect()
+}
+
+/// Examples as JSON Lines
+pub fn export_jsonl(examples: &[serde_json::Value]) -> String {
+    examples.iter().map(|example| example.to_string()).collect::<Vec<_>>().join("\n")
+}
+
+/// A saved export
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningArtifact {
+    pub dataset: crate::dataset::DatasetVersion,
+    /// The JSONL in the blob store; download it from `/api/blobs/<hash>`
+    pub blob: crate::blobs::BlobInfo,
+    pub format: TuningFormat,
+}
+
+/// Build an export and save it as a blob and dataset version
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_artifact(filter: &TuningFilter, format: TuningFormat) -> anyhow::Result<TuningArtifact> {
+    let examples = tuning_examples(&list_feedback()?, filter, format);
+    anyhow::ensure!(!examples.is_empty(), "no rated generations match the filter");
+    let blob = crate::blobs::put(export_jsonl(&examples).as_bytes(), "application/jsonl")?;
+    let dataset = crate::dataset::register_dataset_version(
+        &examples,
+        "feedback",
+        json!({ "format": format, "filter": filter, "blob": blob.hash }),
+    )?;
+    crate::blobs::add_ref(&blob.hash, &format!("dataset:{}", dataset.id))?;
+    log_info!("[Feedback] Saved {} {:?} examples as {}", examples.len(), format, dataset.id);
+    Ok(TuningArtifact { dataset, blob, format })
+}
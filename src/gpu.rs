@@ -0,0 +1,196 @@
+use opentelemetry::KeyValue;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+
+use crate::compute::panic_message;
+use crate::crash;
+use crate::events::publish;
+use crate::storage::now_millis;
+use crate::telemetry::instruments;
+
+// ============================================================================
+// GPU Memory Watchdog
+// ============================================================================
+//
+// WGPU reports allocation failures by panicking inside Burn. GPU work runs
+// through `guarded`, which turns those panics into errors and degrades in
+// steps when they are out-of-memory failures:
+//
+//   1. halve the batch size used for training (down to 1/16)
+//   2. run inference and training on the CPU backend (NdArray)
+//
+// Each quiet period of `RECOVERY_MS` without another failure undoes one
+// step. The current state is reported by `status()` (shown in `SystemInfo`)
+// and failures are counted in the `gpu.oom` metric.
+
+/// Largest factor the batch size is divided by before falling back to the CPU
+const MAX_BATCH_DIVISOR: usize = 16;
+/// Time without out-of-memory failures after which one degradation step is undone
+const RECOVERY_MS: u64 = 15 * 60 * 1000;
+
+/// How close the GPU is to running out of memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryPressure {
+    /// No recent out-of-memory failures
+    Normal,
+    /// Training runs with reduced batch sizes
+    Elevated,
+    /// GPU work has fallen back to the CPU backend
+    Critical,
+}
+
+/// GPU degradation state, as reported by `/api/system/info`
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuStatus {
+    pub pressure: MemoryPressure,
+    /// Out-of-memory failures since startup
+    pub oom_failures: u64,
+    pub last_oom_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// Factor training batch sizes are currently divided by
+    pub batch_divisor: usize,
+    pub cpu_fallback: bool,
+}
+
+/// GPU work that panicked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuError {
+    /// The device ran out of memory (already recorded; degradation applied)
+    OutOfMemory { operation: &'static str, message: String },
+    /// Any other panic inside the backend
+    Panicked { operation: &'static str, message: String },
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::OutOfMemory { operation, message } => write!(f, "GPU out of memory during {}: {}", operation, message),
+            GpuError::Panicked { operation, message } => write!(f, "GPU backend panicked during {}: {}", operation, message),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+struct State {
+    oom_failures: u64,
+    last_oom_ms: Option<u64>,
+    last_error: Option<String>,
+    batch_divisor: usize,
+    cpu_fallback: bool,
+    /// Last time a degradation step was applied or undone
+    changed_ms: u64,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    oom_failures: 0,
+    last_oom_ms: None,
+    last_error: None,
+    batch_divisor: 1,
+    cpu_fallback: false,
+    changed_ms: 0,
+});
+
+/// Lock the state, first undoing degradation steps whose quiet period has passed
+fn state() -> std::sync::MutexGuard<'static, State> {
+    let mut state = STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let now = now_millis();
+    while (state.cpu_fallback || state.batch_divisor > 1) && now.saturating_sub(state.changed_ms) >= RECOVERY_MS {
+        if state.cpu_fallback {
+            state.cpu_fallback = false;
+        } else {
+            state.batch_divisor /= 2;
+        }
+        state.changed_ms += RECOVERY_MS;
+        log_info!("[Gpu] No out-of-memory failures for {} min, recovering (batch divisor {}, cpu fallback {})",
+            RECOVERY_MS / 60_000, state.batch_divisor, state.cpu_fallback);
+    }
+    state
+}
+
+/// Current degradation state
+pub fn status() -> GpuStatus {
+    let state = state();
+    let pressure = if state.cpu_fallback {
+        MemoryPressure::Critical
+    } else if state.batch_divisor > 1 {
+        MemoryPressure::Elevated
+    } else {
+        MemoryPressure::Normal
+    };
+    GpuStatus {
+        pressure,
+        oom_failures: state.oom_failures,
+        last_oom_ms: state.last_oom_ms,
+        last_error: state.last_error.clone(),
+        batch_divisor: state.batch_divisor,
+        cpu_fallback: state.cpu_fallback,
+    }
+}
+
+/// Whether GPU work should currently run on the CPU backend instead
+pub fn cpu_fallback() -> bool {
+    state().cpu_fallback
+}
+
+/// `configured` batch size reduced for the current memory pressure (at least 1)
+pub fn effective_batch_size(configured: usize) -> usize {
+    (configured / state().batch_divisor).max(1)
+}
+
+/// Whether a backend panic message describes an allocation failure
+pub fn is_out_of_memory(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["out of memory", "outofmemory", "failed to allocate", "allocation failed", "not enough memory"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Run GPU work, turning backend panics into `GpuError` and recording out-of-memory failures
+pub fn guarded<T>(operation: &'static str, work: impl FnOnce() -> T) -> Result<T, GpuError> {
+    crash::catching(work).map_err(|payload| {
+        let message = panic_message(payload.as_ref());
+        if is_out_of_memory(&message) {
+            record_out_of_memory(operation, &message);
+            GpuError::OutOfMemory { operation, message }
+        } else {
+            log_error!("[Gpu] {} panicked: {}", operation, message);
+            GpuError::Panicked { operation, message }
+        }
+    })
+}
+
+/// Record an allocation failure and apply the next degradation step
+pub fn record_out_of_memory(operation: &'static str, message: &str) {
+    let mut state = state();
+    let now = now_millis();
+    state.oom_failures += 1;
+    state.last_oom_ms = Some(now);
+    state.last_error = Some(message.to_string());
+    state.changed_ms = now;
+
+    let action = if state.cpu_fallback {
+        "none"
+    } else if state.batch_divisor < MAX_BATCH_DIVISOR {
+        state.batch_divisor *= 2;
+        "reduce_batch"
+    } else {
+        state.cpu_fallback = true;
+        "cpu_fallback"
+    };
+    let (batch_divisor, cpu_fallback) = (state.batch_divisor, state.cpu_fallback);
+    drop(state);
+
+    instruments().gpu_oom.add(1, &[KeyValue::new("operation", operation), KeyValue::new("action", action)]);
+    log_warn!("[Gpu] Out of memory during {} ({}), batch divisor {}, cpu fallback {}",
+        operation, action, batch_divisor, cpu_fallback);
+    publish("gpu.out_of_memory", json!({
+        "operation": operation,
+        "message": message,
+        "action": action,
+        "batch_divisor": batch_divisor,
+        "cpu_fallback": cpu_fallback,
+    }));
+}
@@ -0,0 +1,219 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::agents::{get_agent, AgentMessage};
+use crate::events::publish;
+use crate::payload::Payload;
+use crate::storage::now_millis;
+use serde_json::json;
+
+// ============================================================================
+// Agent Job Queue
+// ============================================================================
+//
+// Ractor mailboxes can't be inspected, so messages queued with
+// `AgentMessage::queued` are tracked here as jobs: pending until the agent
+// dequeues them, running while it handles them, and forgotten once handled.
+// Messages sent without `queued` (timers, probes, the demo) are not jobs.
+//
+// Operators can act on jobs:
+// - cancel a pending job; the agent skips it when it reaches it
+// - requeue a job at the back of its agent's queue, or move it to another
+//   agent; a pending original is cancelled, a running one finishes
+//
+// Only data jobs (`process_data`, `process_payload`) carry a payload that can
+// be sent again. When an agent crashes or is stopped, its pending and
+// running jobs are kept as `lost` so they can be requeued once it is back,
+// or moved to another agent.
+
+/// Characters of the payload shown in previews
+const PREVIEW_CHARS: usize = 120;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+static JOBS: Mutex<BTreeMap<u64, Job>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    /// Cancelled while pending; dropped when the agent reaches it
+    Cancelled,
+    /// Its agent crashed before finishing it
+    Lost,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub agent_id: u8,
+    /// Message variant, as in telemetry
+    pub kind: &'static str,
+    pub preview: String,
+    pub status: JobStatus,
+    /// Milliseconds since the Unix epoch
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    /// Whether the job can be requeued or moved
+    pub replayable: bool,
+    #[serde(skip)]
+    payload: Option<Payload>,
+}
+
+fn jobs() -> std::sync::MutexGuard<'static, BTreeMap<u64, Job>> {
+    JOBS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Record `message` as pending for `agent_id` and return its job id
+pub fn track(agent_id: u8, message: &AgentMessage) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let payload = message.payload();
+    let job = Job {
+        id,
+        agent_id,
+        kind: message.kind(),
+        preview: crate::redaction::redact(&message.preview()).chars().take(PREVIEW_CHARS).collect(),
+        status: JobStatus::Pending,
+        queued_at: now_millis(),
+        started_at: None,
+        replayable: payload.is_some(),
+        payload,
+    };
+    jobs().insert(id, job);
+    id
+}
+
+/// Mark a job running as its agent dequeues it; false if it was cancelled
+pub fn start(id: u64) -> bool {
+    let mut jobs = jobs();
+    match jobs.get_mut(&id) {
+        Some(job) if job.status == JobStatus::Cancelled => {
+            jobs.remove(&id);
+            false
+        }
+        Some(job) => {
+            job.status = JobStatus::Running;
+            job.started_at = Some(now_millis());
+            true
+        }
+        None => true,
+    }
+}
+
+/// Forget a handled job
+pub fn finish(id: u64) {
+    jobs().remove(&id);
+}
+
+/// Mark the unfinished jobs of a crashed agent as lost
+pub fn mark_lost(agent_id: u8) {
+    for job in jobs().values_mut().filter(|job| job.agent_id == agent_id) {
+        if matches!(job.status, JobStatus::Pending | JobStatus::Running) {
+            job.status = JobStatus::Lost;
+        }
+    }
+}
+
+/// Jobs, oldest first, of one agent or all
+pub fn list(agent_id: Option<u8>) -> Vec<Job> {
+    jobs()
+        .values()
+        .filter(|job| agent_id.is_none_or(|agent_id| job.agent_id == agent_id))
+        .cloned()
+        .collect()
+}
+
+/// Forget lost and cancelled jobs queued before `cutoff` (ms); returns how
+/// many were (or, with `dry_run`, would be) forgotten
+///
+/// Cancelled jobs are dropped when their agent reaches them; only those
+/// whose agent went away first are forgotten here, as forgetting a job still
+/// in a mailbox would let it run.
+pub fn purge_before(cutoff: u64, dry_run: bool) -> usize {
+    let mut jobs = jobs();
+    let stale: Vec<u64> = jobs
+        .values()
+        .filter(|job| job.queued_at < cutoff)
+        .filter(|job| match job.status {
+            JobStatus::Lost => true,
+            JobStatus::Cancelled => get_agent(job.agent_id).is_none(),
+            _ => false,
+        })
+        .map(|job| job.id)
+        .collect();
+    if !dry_run {
+        for id in &stale {
+            jobs.remove(id);
+        }
+    }
+    stale.len()
+}
+
+/// Cancel a pending job, or drop a lost one
+pub fn cancel(id: u64) -> anyhow::Result<Job> {
+    let job = {
+        let mut jobs = jobs();
+        let job = jobs.get_mut(&id).ok_or_else(|| anyhow::anyhow!("unknown job {}", id))?;
+        let lost = match job.status {
+            JobStatus::Pending => false,
+            JobStatus::Lost => true,
+            status => anyhow::bail!("job {} is {:?}, only pending or lost jobs can be cancelled", id, status),
+        };
+        job.status = JobStatus::Cancelled;
+        let job = job.clone();
+        // Nothing will dequeue a lost job
+        if lost {
+            jobs.remove(&id);
+        }
+        job
+    };
+    log_info!("[Jobs] Cancelled job {} of Agent{}", id, job.agent_id);
+    publish("job.cancelled", json!({ "job": id, "agent_id": job.agent_id }));
+    Ok(job)
+}
+
+/// Queue a job again for `to` (default: its agent) and return the new job
+pub fn requeue(id: u64, to: Option<u8>) -> anyhow::Result<Job> {
+    let job = jobs().get(&id).cloned().ok_or_else(|| anyhow::anyhow!("unknown job {}", id))?;
+    anyhow::ensure!(job.status != JobStatus::Cancelled, "job {} was cancelled", id);
+    let payload = job.payload.clone().ok_or_else(|| anyhow::anyhow!("{} jobs can't be requeued", job.kind))?;
+    let agent_id = to.unwrap_or(job.agent_id);
+    let actor_ref = get_agent(agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+
+    let message = AgentMessage::ProcessPayload { payload }.queued(agent_id);
+    let AgentMessage::Queued { job: new_id, .. } = &message else {
+        unreachable!("queued() wraps the message");
+    };
+    let new_id = *new_id;
+    let new_job = jobs().get(&new_id).cloned().ok_or_else(|| anyhow::anyhow!("job {} disappeared", new_id))?;
+    if let Err(e) = actor_ref.send_message(message) {
+        finish(new_id);
+        anyhow::bail!("failed to reach Agent{}: {}", agent_id, e);
+    }
+    // The original doesn't run anymore, unless it already is
+    {
+        let mut jobs = jobs();
+        match jobs.get(&id).map(|job| job.status) {
+            Some(JobStatus::Pending) => {
+                if let Some(job) = jobs.get_mut(&id) {
+                    job.status = JobStatus::Cancelled;
+                }
+            }
+            Some(JobStatus::Lost) => {
+                jobs.remove(&id);
+            }
+            _ => {}
+        }
+    }
+    log_info!("[Jobs] Requeued job {} of Agent{} as job {} of Agent{}", id, job.agent_id, new_id, agent_id);
+    publish("job.requeued", json!({
+        "job": id,
+        "agent_id": job.agent_id,
+        "new_job": new_id,
+        "to": agent_id,
+    }));
+    Ok(new_job)
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::storage::now_millis;
+
+// ============================================================================
+// Latency Budget
+// ============================================================================
+//
+// Where the time goes between a message arriving over HTTP and its results
+// reaching a client, split into the stages of the pipeline:
+//
+//   receive   handler entry until the message is queued on the agent
+//             (`/api/agents/:id/process` and `/api/agents/:id/payload`)
+//   dequeue   waiting in the agent's mailbox
+//   compute   LLM extraction and on-device classification while handling it
+//   delivery  event published until a client of `/api/events/stream` gets it
+//
+// Samples of the last `WINDOW_MS` are kept per stage (at most `MAX_SAMPLES`)
+// and reported as percentiles by `GET /api/stats/latency`; the stage with
+// the highest p95 is flagged as the slowest.
+
+/// Length of the rolling window
+const WINDOW_MS: u64 = 5 * 60 * 1000;
+/// Samples kept per stage; the oldest go first
+const MAX_SAMPLES: usize = 10_000;
+
+/// A stage of the message pipeline, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Receive,
+    Dequeue,
+    Compute,
+    Delivery,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Receive, Stage::Dequeue, Stage::Compute, Stage::Delivery];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Receive => "receive",
+            Stage::Dequeue => "dequeue",
+            Stage::Compute => "compute",
+            Stage::Delivery => "delivery",
+        }
+    }
+}
+
+/// `(ts, duration in µs)` per stage, oldest first
+fn samples() -> &'static Mutex<[VecDeque<(u64, u64)>; 4]> {
+    static SAMPLES: OnceLock<Mutex<[VecDeque<(u64, u64)>; 4]>> = OnceLock::new();
+    SAMPLES.get_or_init(|| Mutex::new(Default::default()))
+}
+
+/// Record that one message spent `elapsed` in `stage`
+pub fn record(stage: Stage, elapsed: Duration) {
+    let now = now_millis();
+    let mut samples = samples().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let stage_samples = &mut samples[stage as usize];
+    if stage_samples.len() == MAX_SAMPLES {
+        stage_samples.pop_front();
+    }
+    stage_samples.push_back((now, elapsed.as_micros() as u64));
+}
+
+/// Percentiles of one stage over the window, in milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageLatency {
+    pub stage: Stage,
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Per-stage latency of the pipeline over the window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBudget {
+    pub ts: u64,
+    pub window_secs: u64,
+    /// One entry per stage, in pipeline order
+    pub stages: Vec<StageLatency>,
+    /// Stage with the highest p95 (none before any sample)
+    pub slowest: Option<Stage>,
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted[(sorted.len() * percent).div_ceil(100).clamp(1, sorted.len()) - 1]
+}
+
+fn millis(micros: u64) -> f64 {
+    micros as f64 / 1000.0
+}
+
+/// Stage latencies for the last five minutes
+pub fn snapshot() -> LatencyBudget {
+    let now = now_millis();
+    let start = now.saturating_sub(WINDOW_MS);
+    let mut samples = samples().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let stages: Vec<StageLatency> = Stage::ALL
+        .into_iter()
+        .map(|stage| {
+            let stage_samples = &mut samples[stage as usize];
+            while stage_samples.front().is_some_and(|(ts, _)| *ts < start) {
+                stage_samples.pop_front();
+            }
+            let mut durations: Vec<u64> = stage_samples.iter().map(|(_, micros)| *micros).collect();
+            durations.sort_unstable();
+            StageLatency {
+                stage,
+                samples: durations.len(),
+                p50_ms: millis(percentile(&durations, 50)),
+                p95_ms: millis(percentile(&durations, 95)),
+                p99_ms: millis(percentile(&durations, 99)),
+                max_ms: millis(durations.last().copied().unwrap_or(0)),
+            }
+        })
+        .collect();
+    drop(samples);
+
+    let slowest = stages
+        .iter()
+        .filter(|latency| latency.samples > 0)
+        .max_by(|a, b| a.p95_ms.total_cmp(&b.p95_ms))
+        .map(|latency| latency.stage);
+
+    LatencyBudget { ts: now, window_secs: WINDOW_MS / 1000, stages, slowest }
+}
@@ -0,0 +1,146 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+use std::time::Duration;
+
+use crate::config::config;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+use crate::events::publish;
+
+// ============================================================================
+// Leader Election
+// ============================================================================
+//
+// Instances sharing a Postgres database (`PATTERN_CLOCK_DATABASE_URL`) all
+// serve reads and event streams, but background jobs that write shared state
+// must run on exactly one of them: time-series compaction, Prometheus
+// scraping and the poller and email connectors. Those jobs check
+// `is_leader()` before each round.
+//
+// The leader is the instance holding a Postgres session-level advisory lock
+// on its own connection. Followers try to take the lock every
+// `CHECK_INTERVAL`; the leader checks its connection just as often, and
+// steps down if the check fails or gets no answer within an interval. When
+// the leader stops or loses its connection, Postgres releases the lock and a
+// follower takes over within one interval. Changes publish `leader.acquired`
+// and `leader.lost`.
+//
+// Without a database there is one instance, and it is always the leader.
+
+/// Advisory lock id identifying the leader
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+const LEADER_LOCK: i64 = 0x7061_7474_6572_6e01;
+/// How often followers campaign and the leader checks its connection
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Leadership of this instance, as reported by `GET /api/cluster/leader`
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderStatus {
+    pub instance: String,
+    pub leader: bool,
+    /// `postgres` when elected, `single` for a standalone instance
+    pub election: &'static str,
+}
+
+fn leader_flag() -> &'static AtomicBool {
+    static LEADER: OnceLock<AtomicBool> = OnceLock::new();
+    LEADER.get_or_init(|| AtomicBool::new(!elected()))
+}
+
+/// Whether leadership is decided by election (a database is configured)
+fn elected() -> bool {
+    cfg!(all(feature = "postgres", not(target_arch = "wasm32"))) && config().database_url.is_some()
+}
+
+/// Whether this instance should run the shared background jobs
+pub fn is_leader() -> bool {
+    leader_flag().load(Ordering::Relaxed)
+}
+
+/// Name of this instance in logs and events (`HOSTNAME`, else the process id)
+pub fn instance_id() -> String {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| std::env::var("HOSTNAME").unwrap_or_else(|_| format!("pid-{}", std::process::id())))
+        .clone()
+}
+
+/// Leadership of this instance
+pub fn status() -> LeaderStatus {
+    LeaderStatus {
+        instance: instance_id(),
+        leader: is_leader(),
+        election: if elected() { "postgres" } else { "single" },
+    }
+}
+
+/// Start campaigning for leadership (once; requires a Tokio runtime; no-op without a database)
+pub fn ensure_started() {
+    #[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+    {
+        static STARTED: OnceLock<()> = OnceLock::new();
+        STARTED.get_or_init(|| {
+            let Some(url) = config().database_url.clone() else {
+                return;
+            };
+            tokio::spawn(async move {
+                loop {
+                    if let Err(e) = campaign(url.expose()).await {
+                        log_warn!("[Leader] Election connection failed: {}", e);
+                    }
+                    set_leader(false);
+                    tokio::time::sleep(CHECK_INTERVAL).await;
+                }
+            });
+        });
+    }
+}
+
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+fn set_leader(leader: bool) {
+    if leader_flag().swap(leader, Ordering::Relaxed) == leader {
+        return;
+    }
+    let instance = instance_id();
+    if leader {
+        log_info!("[Leader] {} is now the leader", instance);
+        publish("leader.acquired", serde_json::json!({ "instance": instance }));
+    } else {
+        log_warn!("[Leader] {} is no longer the leader", instance);
+        publish("leader.lost", serde_json::json!({ "instance": instance }));
+    }
+}
+
+/// Hold one connection, taking the lock when free; returns when the connection fails
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+async fn campaign(url: &str) -> anyhow::Result<()> {
+    let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls).await?;
+    // The lock lives as long as this connection
+    let connection = tokio::spawn(connection);
+    let result = hold(&client).await;
+    connection.abort();
+    result
+}
+
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+async fn hold(client: &tokio_postgres::Client) -> anyhow::Result<()> {
+    loop {
+        if is_leader() {
+            // A connection that stopped answering may already have lost the
+            // lock to another instance: step down before waiting any longer
+            let checked = tokio::time::timeout(CHECK_INTERVAL, client.simple_query("SELECT 1")).await;
+            if !matches!(checked, Ok(Ok(_))) {
+                set_leader(false);
+                checked.map_err(|_| anyhow::anyhow!("connection check timed out after {:?}", CHECK_INTERVAL))??;
+            }
+        } else {
+            let acquired: bool = tokio::time::timeout(CHECK_INTERVAL, client.query_one("SELECT pg_try_advisory_lock($1)", &[&LEADER_LOCK]))
+                .await
+                .map_err(|_| anyhow::anyhow!("lock attempt timed out after {:?}", CHECK_INTERVAL))??
+                .get(0);
+            set_leader(acquired);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
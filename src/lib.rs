@@ -0,0 +1,133 @@
+// pattern-clock core library
+//
+// Agents, LLM connections, models, storage and the server API, shared by the
+// Dioxus app (`src/main.rs`) and the MCP server (`src/bin/mcp_server.rs`) and
+// usable by other Rust projects that embed the engine.
+
+// Proving the Wgpu-backed models `Sync` (for the shared model pool) overflows the default limit
+#![recursion_limit = "256"]
+
+// Service configuration and logging (first, so the log macros are in scope below)
+#[macro_use]
+pub mod logging;
+pub mod config;
+pub mod secrets;
+
+// Agents and runtime
+pub mod agent_events;
+pub mod agent_metrics;
+pub mod agents;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod api_client;
+pub mod approvals;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod boot;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod client;
+pub mod cluster;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod console;
+pub mod cycle;
+pub mod deadline;
+#[cfg(all(feature = "demo", not(target_arch = "wasm32")))]
+pub mod demo;
+pub mod events;
+pub mod fanout;
+pub mod jobs;
+pub mod latency;
+pub mod leader;
+pub mod live_stats;
+pub mod mailbox;
+pub mod monitor;
+pub mod notifications;
+pub mod payload;
+pub mod personas;
+pub mod phases;
+pub mod pipeline;
+pub mod roles;
+pub mod runtime;
+pub mod sandbox;
+pub mod scheduler;
+pub mod summarizer;
+pub mod telemetry;
+pub mod threads;
+#[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+pub mod top;
+pub mod updates;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+// LLM and data connections
+pub mod connections;
+pub mod connectors;
+pub mod extraction;
+pub mod line_protocol;
+pub mod prometheus;
+pub mod redaction;
+pub mod request_limits;
+pub mod rules;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rule_store;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod schemas;
+
+// Storage and datasets
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bundles;
+pub mod dataset;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod documents;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod encryption;
+pub mod experiments;
+pub mod feedback;
+pub mod retention;
+pub mod storage;
+pub mod timeseries;
+
+// Models (native only)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blobs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod classifier;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compression;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compute;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod crash;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod doctor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gpu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lstm;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod model_pool;
+#[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+pub mod postgres;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod quantization;
+#[cfg(all(feature = "redis", not(target_arch = "wasm32")))]
+pub mod redis_bus;
+pub mod registry;
+#[cfg(all(feature = "s3", not(target_arch = "wasm32")))]
+pub mod s3;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod seq2seq;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tensor_io;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod training;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tuning;
+
+// MCP tools and server API (plus the views built on it)
+pub mod mcp_server;
+pub mod shared;
+
+pub use runtime::{PatternClock, PatternClockBuilder, RuntimeHandle};
@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+
+use crate::timeseries::{append_all, AppendError, AppendReport, Point};
+
+// ============================================================================
+// InfluxDB Line Protocol Ingestion
+// ============================================================================
+//
+// Accepts the text format written by Telegraf and other Influx clients:
+//
+//   measurement[,tag=value...] field=value[,field=value...] [timestamp]
+//
+// Every numeric or boolean field becomes its own series named
+// `<measurement>.<field>`; string fields are skipped.
+
+/// Timestamp unit of incoming lines (the `precision` query parameter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
+impl Precision {
+    /// Parse Influx precision names (`ns`, `us`, `ms`, `s`; v1 also uses `n`, `u`)
+    pub fn parse(precision: Option<&str>) -> Result<Self, AppendError> {
+        match precision.unwrap_or("ns") {
+            "ns" | "n" => Ok(Precision::Nanoseconds),
+            "us" | "u" => Ok(Precision::Microseconds),
+            "ms" => Ok(Precision::Milliseconds),
+            "s" => Ok(Precision::Seconds),
+            other => Err(AppendError::Invalid(format!("unsupported precision '{}'", other))),
+        }
+    }
+
+    /// Milliseconds since the epoch (negative timestamps clamp to zero);
+    /// `None` past what fits in milliseconds
+    fn to_millis(self, ts: i64) -> Option<u64> {
+        let ts = ts.max(0) as u64;
+        match self {
+            Precision::Nanoseconds => Some(ts / 1_000_000),
+            Precision::Microseconds => Some(ts / 1_000),
+            Precision::Milliseconds => Some(ts),
+            Precision::Seconds => ts.checked_mul(1_000),
+        }
+    }
+}
+
+/// Split on `separator` outside of backslash escapes and double quotes
+fn split_unescaped(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    let mut quoted = false;
+    for (i, c) in input.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+fn unescape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                output.push(next);
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+/// Parse a field value; `None` for string fields, which are not stored
+fn parse_field_value(raw: &str) -> Result<Option<f64>, String> {
+    if raw.starts_with('"') {
+        return Ok(None);
+    }
+    match raw {
+        "t" | "T" | "true" | "True" | "TRUE" => return Ok(Some(1.0)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Ok(Some(0.0)),
+        _ => {}
+    }
+    let number = raw.strip_suffix('i').or_else(|| raw.strip_suffix('u')).unwrap_or(raw);
+    number.parse::<f64>()
+        .map(Some)
+        .map_err(|_| format!("invalid field value '{}'", raw))
+}
+
+/// Parse one line into `(series name, point)` pairs, one per numeric field
+fn parse_line(line: &str, precision: Precision, now_ms: u64) -> Result<Vec<(String, Point)>, String> {
+    let sections = split_unescaped(line, ' ');
+    let sections: Vec<&str> = sections.into_iter().filter(|s| !s.is_empty()).collect();
+    let (series_key, fields, timestamp) = match sections.as_slice() {
+        [key, fields] => (*key, *fields, None),
+        [key, fields, ts] => (*key, *fields, Some(*ts)),
+        _ => return Err("expected '<measurement>[,tags] <fields> [timestamp]'".to_string()),
+    };
+
+    let mut key_parts = split_unescaped(series_key, ',').into_iter();
+    let measurement = unescape(key_parts.next().unwrap_or_default());
+    if measurement.is_empty() {
+        return Err("missing measurement".to_string());
+    }
+    let mut tags = BTreeMap::new();
+    for tag in key_parts {
+        let (key, value) = tag.split_once('=').ok_or_else(|| format!("invalid tag '{}'", tag))?;
+        tags.insert(unescape(key), unescape(value));
+    }
+
+    let ts = match timestamp {
+        Some(raw) => {
+            let ts = raw.parse::<i64>().map_err(|_| format!("invalid timestamp '{}'", raw))?;
+            precision.to_millis(ts).ok_or_else(|| format!("timestamp '{}' is out of range", raw))?
+        }
+        None => now_ms,
+    };
+
+    let mut points = Vec::new();
+    for field in split_unescaped(fields, ',') {
+        let (key, value) = field.split_once('=').ok_or_else(|| format!("invalid field '{}'", field))?;
+        if let Some(value) = parse_field_value(value)? {
+            points.push((
+                format!("{}.{}", measurement, unescape(key)),
+                Point { ts, value, tags: tags.clone() },
+            ));
+        }
+    }
+    Ok(points)
+}
+
+/// Parse a line protocol body, grouping points by series name
+///
+/// Blank lines and `#` comments are ignored. Fails on the first malformed line.
+pub fn parse(body: &str, precision: Precision, now_ms: u64) -> Result<BTreeMap<String, Vec<Point>>, AppendError> {
+    let mut series: BTreeMap<String, Vec<Point>> = BTreeMap::new();
+    for (number, line) in body.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let points = parse_line(line, precision, now_ms)
+            .map_err(|reason| AppendError::Invalid(format!("line {}: {}", number + 1, reason)))?;
+        for (name, point) in points {
+            series.entry(name).or_default().push(point);
+        }
+    }
+    Ok(series)
+}
+
+/// Parse a line protocol body and append every series to the store
+///
+/// Returns per-series append reports. The whole body is validated and every
+/// series' rate limit checked before anything is written, so a refused body
+/// leaves all series unchanged.
+pub fn write(body: &str, precision: Precision) -> Result<BTreeMap<String, AppendReport>, AppendError> {
+    append_all(parse(body, precision, crate::storage::now_millis())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(series, value)` of every point parsed from `line`
+    fn values(line: &str) -> Vec<(String, f64)> {
+        parse_line(line, Precision::Milliseconds, 7)
+            .unwrap()
+            .into_iter()
+            .map(|(name, point)| (name, point.value))
+            .collect()
+    }
+
+    #[test]
+    fn split_skips_escaped_and_quoted_separators() {
+        assert_eq!(split_unescaped(r#"a\,b,"c,d",e"#, ','), [r"a\,b", r#""c,d""#, "e"]);
+        assert_eq!(split_unescaped("a", ','), ["a"]);
+    }
+
+    #[test]
+    fn escaped_measurement_and_tags_are_unescaped() {
+        let points = parse_line(r"cpu\ load,host=server\ 1,region=us\,west value=1 1000", Precision::Milliseconds, 7).unwrap();
+        assert_eq!(points.len(), 1);
+        let (name, point) = &points[0];
+        assert_eq!(name, "cpu load.value");
+        assert_eq!(point.ts, 1000);
+        assert_eq!(point.tags["host"], "server 1");
+        assert_eq!(point.tags["region"], "us,west");
+    }
+
+    #[test]
+    fn string_fields_with_commas_and_spaces_are_skipped() {
+        assert_eq!(
+            values(r#"weather,city=x temp=21.5,note="warm, dry",ok=true 5"#),
+            [("weather.temp".to_string(), 21.5), ("weather.ok".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn integer_suffixes_and_booleans_are_numbers() {
+        assert_eq!(
+            values("m count=42i,unsigned=7u,negative=-3i"),
+            [("m.count".to_string(), 42.0), ("m.unsigned".to_string(), 7.0), ("m.negative".to_string(), -3.0)]
+        );
+        assert_eq!(
+            values("m a=t,b=F,c=true,d=FALSE"),
+            [("m.a".to_string(), 1.0), ("m.b".to_string(), 0.0), ("m.c".to_string(), 1.0), ("m.d".to_string(), 0.0)]
+        );
+        assert!(parse_line("m a=yes", Precision::Milliseconds, 7).is_err());
+    }
+
+    #[test]
+    fn timestamps_follow_the_precision() {
+        let ts = |line: &str, precision| parse_line(line, precision, 7).map(|points| points[0].1.ts);
+        assert_eq!(ts("m v=1 1500000000", Precision::Nanoseconds), Ok(1500));
+        assert_eq!(ts("m v=1 1500000", Precision::Microseconds), Ok(1500));
+        assert_eq!(ts("m v=1 1500", Precision::Milliseconds), Ok(1500));
+        assert_eq!(ts("m v=1 2", Precision::Seconds), Ok(2000));
+        assert_eq!(ts("m v=1", Precision::Seconds), Ok(7), "no timestamp is now");
+        assert!(ts(&format!("m v=1 {}", i64::MAX), Precision::Seconds).is_err());
+
+        assert_eq!(Precision::parse(None).unwrap(), Precision::Nanoseconds);
+        assert_eq!(Precision::parse(Some("u")).unwrap(), Precision::Microseconds);
+        assert!(Precision::parse(Some("h")).is_err());
+    }
+
+    #[test]
+    fn parse_groups_by_series_and_names_the_bad_line() {
+        let series = parse("# comment\n\ncpu value=1 1\ncpu value=2,idle=3 2\n", Precision::Milliseconds, 7).unwrap();
+        assert_eq!(series.keys().collect::<Vec<_>>(), ["cpu.idle", "cpu.value"]);
+        assert_eq!(series["cpu.value"].len(), 2);
+
+        match parse("cpu value=1 1\ncpu", Precision::Milliseconds, 7) {
+            Err(AppendError::Invalid(reason)) => assert!(reason.starts_with("line 2:"), "{}", reason),
+            other => panic!("expected an invalid line, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use crate::events::{recent, subscribe, Event};
+use crate::storage::now_millis;
+
+// ============================================================================
+// Live Statistics
+// ============================================================================
+//
+// Rolling one-minute view of what the service is doing, for dashboards that
+// poll (`GET /api/stats/live`, `pattern-clock top`): messages handled per
+// agent, LLM request latency and errors, how regularly the monitor's tick
+// loop fires, and how loaded the runtimes are. Fed from the event bus plus
+// `record_tick`.
+
+/// Length of the rolling window
+const WINDOW_MS: u64 = 60 * 1000;
+/// Events included in a snapshot's feed
+const FEED_LEN: usize = 20;
+
+#[derive(Default)]
+struct Window {
+    /// `(ts, agent_id, duration_ms, ok)` of handled messages
+    handled: VecDeque<(u64, u8, u64, bool)>,
+    /// `(ts, duration_ms, ok)` of LLM requests
+    llm: VecDeque<(u64, u64, bool)>,
+    /// `(ts, delay_ms)` of monitor ticks
+    ticks: VecDeque<(u64, u64)>,
+}
+
+impl Window {
+    fn expire(&mut self, now: u64) {
+        let start = now.saturating_sub(WINDOW_MS);
+        while self.handled.front().is_some_and(|entry| entry.0 < start) {
+            self.handled.pop_front();
+        }
+        while self.llm.front().is_some_and(|entry| entry.0 < start) {
+            self.llm.pop_front();
+        }
+        while self.ticks.front().is_some_and(|entry| entry.0 < start) {
+            self.ticks.pop_front();
+        }
+    }
+
+    fn observe(&mut self, event: &Event) {
+        let payload = &event.payload;
+        let duration_ms = payload["duration_ms"].as_u64().unwrap_or(0);
+        let ok = payload["ok"].as_bool().unwrap_or(false);
+        match event.kind.as_str() {
+            "agent.handled" => {
+                let agent_id = payload["agent_id"].as_u64().unwrap_or(0) as u8;
+                self.handled.push_back((event.ts, agent_id, duration_ms, ok));
+            }
+            "llm.request" => self.llm.push_back((event.ts, duration_ms, ok)),
+            _ => {}
+        }
+    }
+}
+
+fn window() -> &'static Mutex<Window> {
+    static WINDOW: OnceLock<Mutex<Window>> = OnceLock::new();
+    WINDOW.get_or_init(|| Mutex::new(Window::default()))
+}
+
+/// Messages one agent handled in the window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStats {
+    pub agent_id: u8,
+    pub handled: usize,
+    pub errors: usize,
+    pub per_sec: f64,
+    pub avg_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmStats {
+    pub requests: usize,
+    pub errors: usize,
+    pub avg_ms: f64,
+    pub p95_ms: u64,
+    /// Connections of the shared LLM client, since startup
+    #[serde(default)]
+    pub pool: crate::connections::PoolStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickStats {
+    pub ticks: usize,
+    pub per_sec: f64,
+    /// Largest lateness of a tick in the window
+    pub max_delay_ms: u64,
+}
+
+/// Everything in the current window, plus the latest events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveStats {
+    pub ts: u64,
+    pub window_secs: u64,
+    pub agents: Vec<AgentStats>,
+    pub llm: LlmStats,
+    pub ticks: TickStats,
+    /// Worker runtime, compute pool and background thread load, at the time of the snapshot
+    #[serde(default)]
+    pub runtime: crate::threads::RuntimeStats,
+    /// Most recent events, newest first
+    pub events: Vec<Event>,
+}
+
+/// Start following the event bus (once per process; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let mut events = subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    // Other instances count their own traffic
+                    Ok(event) if !event.is_local() => {}
+                    Ok(event) => {
+                        let mut window = window().lock().unwrap();
+                        window.expire(now_millis());
+                        window.observe(&event);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log_warn!("[LiveStats] Event stream lagged, skipped {} events", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    });
+}
+
+/// Count one monitor tick that fired `delay_ms` late
+pub fn record_tick(delay_ms: u64) {
+    let now = now_millis();
+    let mut window = window().lock().unwrap();
+    window.expire(now);
+    window.ticks.push_back((now, delay_ms));
+}
+
+fn average(values: impl Iterator<Item = u64>) -> f64 {
+    let (sum, count) = values.fold((0u64, 0usize), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 { 0.0 } else { sum as f64 / count as f64 }
+}
+
+/// Statistics for the last minute
+pub fn snapshot() -> LiveStats {
+    let now = now_millis();
+    let mut window = window().lock().unwrap();
+    window.expire(now);
+    let window_secs = WINDOW_MS / 1000;
+
+    let mut per_agent: BTreeMap<u8, Vec<(u64, bool)>> = crate::agents::agent_ids().into_iter().map(|id| (id, Vec::new())).collect();
+    for &(_, agent_id, duration_ms, ok) in &window.handled {
+        per_agent.entry(agent_id).or_default().push((duration_ms, ok));
+    }
+    let agents = per_agent
+        .into_iter()
+        .map(|(agent_id, handled)| AgentStats {
+            agent_id,
+            handled: handled.len(),
+            errors: handled.iter().filter(|(_, ok)| !ok).count(),
+            per_sec: handled.len() as f64 / window_secs as f64,
+            avg_ms: average(handled.iter().map(|(duration_ms, _)| *duration_ms)),
+        })
+        .collect();
+
+    let mut durations: Vec<u64> = window.llm.iter().map(|(_, duration_ms, _)| *duration_ms).collect();
+    durations.sort_unstable();
+    let llm = LlmStats {
+        requests: window.llm.len(),
+        errors: window.llm.iter().filter(|(_, _, ok)| !ok).count(),
+        avg_ms: average(durations.iter().copied()),
+        p95_ms: durations.get((durations.len() * 95 / 100).min(durations.len().saturating_sub(1))).copied().unwrap_or(0),
+        pool: crate::connections::pool_stats(),
+    };
+
+    let ticks = TickStats {
+        ticks: window.ticks.len(),
+        per_sec: window.ticks.len() as f64 / window_secs as f64,
+        max_delay_ms: window.ticks.iter().map(|(_, delay_ms)| *delay_ms).max().unwrap_or(0),
+    };
+    drop(window);
+
+    let mut events = recent();
+    events.reverse();
+    events.truncate(FEED_LEN);
+
+    LiveStats { ts: now, window_secs, agents, llm, ticks, runtime: crate::threads::stats(), events }
+}
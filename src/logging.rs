@@ -0,0 +1,105 @@
+use serde_json::json;
+use std::io::Write;
+
+use crate::storage::now_millis;
+
+// ============================================================================
+// Service Logging
+// ============================================================================
+//
+// Log lines keep the existing `[Target] message` shape. With
+// `PATTERN_CLOCK_LOG_FORMAT=json` every line is written to stdout as one JSON
+// object instead (`ts`, `level`, `target`, `msg`), ready for container log
+// collectors:
+//
+//     log_info!("[Agent{}] Processing data", id);
+//     {"ts":1700000000000,"level":"info","target":"Agent1","msg":"Processing data"}
+
+/// Output format of service logs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[Target] message`; info to stdout, warnings and errors to stderr
+    #[default]
+    Text,
+    /// One JSON object per line, everything to stdout
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// Split a `[Target] message` line into its target and message
+fn split_target(line: &str) -> (&str, &str) {
+    line.strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(target, message)| (target, message.trim_start()))
+        .unwrap_or(("pattern-clock", line))
+}
+
+/// Write one log line in the configured format
+pub fn log(level: Level, line: &str) {
+    match crate::config::config().log_format {
+        LogFormat::Text if level == Level::Info => println!("{}", line),
+        LogFormat::Text => eprintln!("{}", line),
+        LogFormat::Json => {
+            let (target, message) = split_target(line);
+            let record = json!({
+                "ts": now_millis(),
+                "level": level.as_str(),
+                "target": target,
+                "msg": message,
+            });
+            // One write per record so concurrent lines never interleave
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{}", record);
+        }
+    }
+}
+
+/// Log an informational `[Target] message` line
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, &format!($($arg)*))
+    };
+}
+
+/// Log a `[Target] message` line about a recoverable problem
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Warn, &format!($($arg)*))
+    };
+}
+
+/// Log a `[Target] message` line about a failed operation
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Error, &format!($($arg)*))
+    };
+}
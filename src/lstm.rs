@@ -1,8 +1,24 @@
-use burn::module::Module;
+use burn::backend::wgpu::Wgpu;
+use burn::backend::NdArray;
+use burn::module::{Module, Param};
 use burn::nn::Linear;
 use burn::nn::LinearConfig;
+use burn::nn::LstmState;
+use burn::record::CompactRecorder;
+use burn::tensor::activation::{sigmoid, tanh};
 use burn::tensor::backend::Backend;
-use burn::tensor::Tensor;
+use burn::tensor::{Bool, Distribution, ElementConversion, Int, Tensor, TensorData};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::gpu::{self, GpuError};
+use crate::model_pool;
+use crate::registry::LstmImpl;
+use crate::storage::now_millis;
+use crate::tensor_io::{JsonTensor, TensorError};
 
 /// Configuration for LSTM model
 #[derive(Debug, Clone)]
@@ -77,36 +93,35 @@ impl<B: Backend> LstmCell<B> {
         hidden: Tensor<B, 2>,
         cell: Tensor<B, 2>,
     ) -> (Tensor<B, 2>, Tensor<B, 2>) {
-        // Compute gate activations
-        let gates_ih = self.gate_ih.forward(input);
-        let gates_hh = self.gate_hh.forward(hidden);
-        let gates = gates_ih + gates_hh;
+        self.forward_projected(self.gate_ih.forward(input), hidden, cell)
+    }
 
-        // Split gates: [batch, 4*hidden] -> 4 x [batch, hidden]
-        let gates_chunks = gates.chunk(4, 1);
-        
-        // Sigmoid: 1 / (1 + exp(-x))
-        let sigmoid = |x: Tensor<B, 2>| {
-            let device = x.device();
-            let dims = x.dims();
-            let one = Tensor::zeros(dims, &device) + 1.0;
-            one.clone() / (one + (-x).exp())
-        };
-        
-        // Tanh: (exp(2x) - 1) / (exp(2x) + 1)
-        let tanh = |x: Tensor<B, 2>| {
-            let device = x.device();
-            let dims = x.dims();
-            let two_x = x.clone() * 2.0;
-            let exp_2x = two_x.exp();
-            let one = Tensor::zeros(dims, &device) + 1.0;
-            (exp_2x.clone() - one.clone()) / (exp_2x + one)
-        };
-        
-        let input_gate = sigmoid(gates_chunks[0].clone());
-        let forget_gate = sigmoid(gates_chunks[1].clone());
-        let cell_gate = tanh(gates_chunks[2].clone());
-        let output_gate = sigmoid(gates_chunks[3].clone());
+    /// Input-to-hidden projection of a whole `[.., input_size]` sequence in one matmul
+    pub fn project_input<const D: usize>(&self, input: Tensor<B, D>) -> Tensor<B, D> {
+        self.gate_ih.forward(input)
+    }
+
+    /// Forward pass for a single timestep whose input is already projected (see `project_input`)
+    ///
+    /// Only the hidden-to-hidden matmul depends on the previous step, so
+    /// this is all that has to run sequentially: one matmul, one add, one
+    /// sigmoid over all four gates and the elementwise state update.
+    pub fn forward_projected(
+        &self,
+        projected_input: Tensor<B, 2>,
+        hidden: Tensor<B, 2>,
+        cell: Tensor<B, 2>,
+    ) -> (Tensor<B, 2>, Tensor<B, 2>) {
+        let gates = projected_input + self.gate_hh.forward(hidden);
+
+        // Gates are laid out [input, forget, cell, output]; the sigmoid runs over
+        // all four at once and the cell gate's slice is replaced by its tanh
+        let [batch_size, _] = gates.dims();
+        let hidden_size = self.hidden_size;
+        let cell_gate = tanh(gates.clone().slice([0..batch_size, 2 * hidden_size..3 * hidden_size]));
+        let sigmoids = sigmoid(gates).chunk(4, 1);
+        let [input_gate, forget_gate, _, output_gate] = <[Tensor<B, 2>; 4]>::try_from(sigmoids)
+            .expect("four gates");
 
         // Update cell state: c_t = f_t * c_{t-1} + i_t * g_t
         let new_cell = forget_gate * cell + input_gate * cell_gate;
@@ -116,6 +131,124 @@ impl<B: Backend> LstmCell<B> {
 
         (new_hidden, new_cell)
     }
+
+    /// The same cell as a `burn::nn::Lstm`, sharing its weights
+    pub fn to_builtin(&self) -> burn::nn::Lstm<B> {
+        let weight = self.gate_ih.weight.val();
+        let [input_size, _] = weight.dims();
+        let bias = self.gate_ih.bias.is_some();
+        let mut lstm = burn::nn::LstmConfig::new(input_size, self.hidden_size, bias).init(&weight.device());
+
+        // Gates are laid out [input, forget, cell, output] along the output dimension
+        let gates = [&mut lstm.input_gate, &mut lstm.forget_gate, &mut lstm.cell_gate, &mut lstm.output_gate];
+        for (index, gate) in gates.into_iter().enumerate() {
+            gate.input_transform = gate_slice(&self.gate_ih, index, self.hidden_size);
+            gate.hidden_transform = gate_slice(&self.gate_hh, index, self.hidden_size);
+        }
+        lstm
+    }
+}
+
+/// The columns of gate `index` of a fused 4-gate linear layer
+fn gate_slice<B: Backend>(linear: &Linear<B>, index: usize, hidden_size: usize) -> Linear<B> {
+    let columns = index * hidden_size..(index + 1) * hidden_size;
+    let weight = linear.weight.val();
+    let [rows, _] = weight.dims();
+    let mut gate = linear.clone();
+    gate.weight = Param::from_tensor(weight.slice([0..rows, columns.clone()]));
+    gate.bias = linear.bias.as_ref().map(|bias| Param::from_tensor(bias.val().slice([columns])));
+    gate
+}
+
+/// (hidden, cell) state of every layer, first layer first, each `[batch_size, hidden_size]`
+pub type LayerStates<B> = Vec<(Tensor<B, 2>, Tensor<B, 2>)>;
+
+/// An LSTM implementation `SequenceSession` and inference can run
+pub trait Recurrent<B: Backend> {
+    /// If true, input shape is [batch, seq, features], else [seq, batch, features]
+    fn batch_first(&self) -> bool;
+
+    /// Forward pass carrying the (hidden, cell) state of every layer (see `Lstm::forward_stateful`)
+    fn forward_stateful(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+    ) -> (Tensor<B, 3>, LayerStates<B>);
+
+    /// Forward pass over independent sequences padded to a common length
+    ///
+    /// `lengths[row]` is the number of real timesteps of each batch row (at
+    /// least 1). Every row's final states are taken at its own last step and
+    /// its outputs past that step are zero, so the results match running the
+    /// rows one by one. By default rows of equal length run together.
+    fn forward_packed(&self, input: Tensor<B, 3>, lengths: &[usize]) -> (Tensor<B, 3>, LayerStates<B>) {
+        packed_by_length(self, input, lengths)
+    }
+}
+
+/// `forward_packed` for implementations that can't mask padded steps
+///
+/// Sorts the rows by length, runs each group of equal length over its
+/// real steps only and restores the original row order.
+fn packed_by_length<B: Backend>(
+    model: &(impl Recurrent<B> + ?Sized),
+    input: Tensor<B, 3>,
+    lengths: &[usize],
+) -> (Tensor<B, 3>, LayerStates<B>) {
+    let input = if model.batch_first() { input } else { input.swap_dims(0, 1) };
+    let [batch_size, seq_len, input_size] = input.dims();
+    assert_eq!(lengths.len(), batch_size, "one length per batch row");
+    assert!(lengths.iter().all(|length| (1..=seq_len).contains(length)), "lengths between 1 and the padded length");
+    let device = input.device();
+    let indices = |rows: Vec<usize>| {
+        let rows: Vec<i64> = rows.into_iter().map(|row| row as i64).collect();
+        let count = rows.len();
+        Tensor::<B, 1, Int>::from_data(TensorData::new(rows, [count]), &device)
+    };
+
+    let mut order: Vec<usize> = (0..batch_size).collect();
+    order.sort_by_key(|row| lengths[*row]);
+    let mut restore = vec![0; batch_size];
+    for (position, row) in order.iter().enumerate() {
+        restore[*row] = position;
+    }
+    let sorted = input.select(0, indices(order.clone()));
+
+    let mut outputs = Vec::new();
+    let mut layer_states: Vec<(Vec<Tensor<B, 2>>, Vec<Tensor<B, 2>>)> = Vec::new();
+    let mut start = 0;
+    while start < batch_size {
+        let length = lengths[order[start]];
+        let end = start + order[start..].iter().take_while(|row| lengths[**row] == length).count();
+        let group = sorted.clone().slice([start..end, 0..length, 0..input_size]);
+        let group = if model.batch_first() { group } else { group.swap_dims(0, 1) };
+        let (output, states) = model.forward_stateful(group, None);
+        let output = if model.batch_first() { output } else { output.swap_dims(0, 1) };
+
+        let [rows, _, hidden_size] = output.dims();
+        outputs.push(if length < seq_len {
+            Tensor::cat(vec![output, Tensor::zeros([rows, seq_len - length, hidden_size], &device)], 1)
+        } else {
+            output
+        });
+        layer_states.resize_with(states.len(), Default::default);
+        for ((hiddens, cells), (hidden, cell)) in layer_states.iter_mut().zip(states) {
+            hiddens.push(hidden);
+            cells.push(cell);
+        }
+        start = end;
+    }
+
+    let restore = indices(restore);
+    let output = Tensor::cat(outputs, 0).select(0, restore.clone());
+    let output = if model.batch_first() { output } else { output.swap_dims(0, 1) };
+    let states = layer_states
+        .into_iter()
+        .map(|(hiddens, cells)| {
+            (Tensor::cat(hiddens, 0).select(0, restore.clone()), Tensor::cat(cells, 0).select(0, restore.clone()))
+        })
+        .collect();
+    (output, states)
 }
 
 /// Multi-layer LSTM model
@@ -167,13 +300,56 @@ impl<B: Backend> Lstm<B> {
         input: Tensor<B, 3>,
         initial_state: Option<(Tensor<B, 2>, Tensor<B, 2>)>,
     ) -> (Tensor<B, 3>, (Tensor<B, 2>, Tensor<B, 2>)) {
+        // Only the first layer starts from the given state; the others start from zero
+        let (output, mut states) = self.forward_stateful(input, initial_state.map(|state| vec![state]));
+        (output, states.pop().unwrap())
+    }
+
+    /// Forward pass carrying the (hidden, cell) state of every layer
+    ///
+    /// Layers without an entry in `states` start from zero. Feeding the
+    /// returned states into the next call continues the sequence exactly
+    /// where this one stopped, which is how `SequenceSession` processes
+    /// long sequences chunk by chunk.
+    ///
+    /// Each layer projects its whole input sequence in one matmul before the
+    /// recurrence, so a timestep issues only the hidden-to-hidden work (see
+    /// `LstmCell::forward_projected`) instead of a dozen small kernels.
+    pub fn forward_stateful(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+    ) -> (Tensor<B, 3>, LayerStates<B>) {
+        self.run_layers(input, states, None, true)
+    }
+
+    /// `Recurrent::forward_packed` in one pass: padded steps leave a row's state unchanged
+    ///
+    /// All rows step through every layer together, so a batch of sequences
+    /// of different lengths costs one sequence of the longest length.
+    pub fn forward_packed(&self, input: Tensor<B, 3>, lengths: &[usize]) -> (Tensor<B, 3>, LayerStates<B>) {
+        self.run_layers(input, None, Some(lengths), true)
+    }
+
+    /// `forward_stateful` computing each timestep's input projection separately
+    ///
+    /// Same results; kept as the baseline of the fusion benchmark.
+    pub fn forward_stepwise(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+    ) -> (Tensor<B, 3>, LayerStates<B>) {
+        self.run_layers(input, states, None, false)
+    }
+
+    fn run_layers(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+        lengths: Option<&[usize]>,
+        project_sequence: bool,
+    ) -> (Tensor<B, 3>, LayerStates<B>) {
         let device = input.device();
-        let [seq_len, batch_size, input_size] = if self.batch_first {
-            let dims = input.dims();
-            [dims[1], dims[0], dims[2]]
-        } else {
-            input.dims()
-        };
 
         // Transpose if batch_first to work with [seq, batch, features]
         let mut input_seq = if self.batch_first {
@@ -181,54 +357,683 @@ impl<B: Backend> Lstm<B> {
         } else {
             input
         };
+        let [seq_len, batch_size, _] = input_seq.dims();
 
-        // Initialize states
-        let (mut hidden, mut cell) = initial_state.unwrap_or_else(|| {
-            (
-                Tensor::zeros([batch_size, self.hidden_size], &device),
-                Tensor::zeros([batch_size, self.hidden_size], &device),
-            )
+        // `[seq, batch, 1]`: whether each row is still within its sequence at a step
+        let lengths = lengths.filter(|lengths| lengths.iter().any(|length| *length != seq_len));
+        let running = lengths.map(|lengths| {
+            assert_eq!(lengths.len(), batch_size, "one length per batch row");
+            let running: Vec<bool> = (0..seq_len)
+                .flat_map(|t| lengths.iter().map(move |length| t < *length))
+                .collect();
+            Tensor::<B, 3, Bool>::from_data(TensorData::new(running, [seq_len, batch_size, 1]), &device)
         });
-
-        // Process through each layer
-        let mut layer_outputs: Option<Tensor<B, 3>> = None;
-        
-        for layer_idx in 0..self.cells.len() {
-            let mut layer_output = Vec::with_capacity(seq_len);
-            
-            // Reset states for each layer (except first)
-            if layer_idx > 0 {
-                hidden = Tensor::zeros([batch_size, self.hidden_size], &device);
-                cell = Tensor::zeros([batch_size, self.hidden_size], &device);
+        let step_masks: Vec<Tensor<B, 2, Bool>> = running
+            .iter()
+            .flat_map(|running| running.clone().chunk(seq_len, 0))
+            .map(|running_t| running_t.squeeze_dim::<2>(0).expand([batch_size, self.hidden_size]))
+            .collect();
+        // Rows past their length keep the state of their last real step
+        let advance = |t: usize, state: (Tensor<B, 2>, Tensor<B, 2>), next: (Tensor<B, 2>, Tensor<B, 2>)| {
+            match step_masks.get(t) {
+                Some(running_t) => (
+                    state.0.mask_where(running_t.clone(), next.0),
+                    state.1.mask_where(running_t.clone(), next.1),
+                ),
+                None => next,
             }
+        };
 
-            // Process sequence
-            for t in 0..seq_len {
-                let input_t = input_seq.clone().slice([t..t+1, 0..batch_size, 0..input_size]).squeeze_dim(0);
-                (hidden, cell) = self.cells[layer_idx].forward(input_t, hidden, cell);
-                layer_output.push(hidden.clone());
-            }
+        let mut states = states.unwrap_or_default().into_iter();
+        let mut final_states = Vec::with_capacity(self.cells.len());
 
-            // Stack outputs: [seq, batch, hidden]
-            let stacked = Tensor::stack(layer_output, 0);
-            
-            if layer_idx < self.cells.len() - 1 {
-                // Use output as input for next layer
-                input_seq = stacked;
+        // Process through each layer, feeding its outputs to the next
+        for layer in &self.cells {
+            let (mut hidden, mut cell) = states.next().unwrap_or_else(|| {
+                (
+                    Tensor::zeros([batch_size, self.hidden_size], &device),
+                    Tensor::zeros([batch_size, self.hidden_size], &device),
+                )
+            });
+
+            let mut layer_output = Vec::with_capacity(seq_len);
+            if project_sequence {
+                for (t, projected_t) in layer.project_input(input_seq).chunk(seq_len, 0).into_iter().enumerate() {
+                    let next = layer.forward_projected(projected_t.squeeze_dim(0), hidden.clone(), cell.clone());
+                    (hidden, cell) = advance(t, (hidden, cell), next);
+                    layer_output.push(hidden.clone());
+                }
             } else {
-                layer_outputs = Some(stacked);
+                let input_size = input_seq.dims()[2];
+                for t in 0..seq_len {
+                    let input_t = input_seq.clone().slice([t..t+1, 0..batch_size, 0..input_size]).squeeze_dim(0);
+                    let next = layer.forward(input_t, hidden.clone(), cell.clone());
+                    (hidden, cell) = advance(t, (hidden, cell), next);
+                    layer_output.push(hidden.clone());
+                }
             }
+
+            // Stack outputs: [seq, batch, hidden]
+            input_seq = Tensor::stack(layer_output, 0);
+            final_states.push((hidden, cell));
         }
 
-        let final_output = layer_outputs.unwrap();
+        // Zero the outputs of padded steps
+        if let Some(running) = running {
+            input_seq = input_seq.mask_fill(running.bool_not().expand([seq_len, batch_size, self.hidden_size]), 0.0);
+        }
 
         // Transpose back if batch_first
         let output = if self.batch_first {
-            final_output.swap_dims(0, 1)
+            input_seq.swap_dims(0, 1)
         } else {
-            final_output
+            input_seq
         };
 
-        (output, (hidden, cell))
+        (output, final_states)
+    }
+}
+
+impl<B: Backend> Recurrent<B> for Lstm<B> {
+    fn batch_first(&self) -> bool {
+        self.batch_first
+    }
+
+    fn forward_stateful(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+    ) -> (Tensor<B, 3>, LayerStates<B>) {
+        Lstm::forward_stateful(self, input, states)
+    }
+
+    fn forward_packed(&self, input: Tensor<B, 3>, lengths: &[usize]) -> (Tensor<B, 3>, LayerStates<B>) {
+        Lstm::forward_packed(self, input, lengths)
+    }
+}
+
+// ============================================================================
+// Built-in Implementation
+// ============================================================================
+//
+// Burn ships its own LSTM (`burn::nn::Lstm`, one layer per module).
+// `BuiltinLstm` runs the weights of an `Lstm` through it, so both
+// implementations can be compared on the same model (`benches/lstm.rs`) and
+// either can serve inference (`PATTERN_CLOCK_LSTM_IMPL`). Weights are still
+// trained and saved as an `Lstm`; the built-in copy is made at load.
+
+/// Largest output difference at which the two implementations count as equivalent
+pub const PARITY_TOLERANCE: f32 = 1e-4;
+
+/// `burn::nn::Lstm` layers holding the weights of an `Lstm`
+#[derive(Debug, Clone)]
+pub struct BuiltinLstm<B: Backend> {
+    layers: Vec<burn::nn::Lstm<B>>,
+    batch_first: bool,
+}
+
+impl<B: Backend> BuiltinLstm<B> {
+    pub fn from_lstm(model: &Lstm<B>) -> Self {
+        Self {
+            layers: model.cells.iter().map(LstmCell::to_builtin).collect(),
+            batch_first: model.batch_first,
+        }
+    }
+}
+
+impl<B: Backend> Recurrent<B> for BuiltinLstm<B> {
+    fn batch_first(&self) -> bool {
+        self.batch_first
+    }
+
+    fn forward_stateful(
+        &self,
+        input: Tensor<B, 3>,
+        states: Option<LayerStates<B>>,
+    ) -> (Tensor<B, 3>, LayerStates<B>) {
+        // burn::nn::Lstm takes [batch, seq, features]
+        let mut input_seq = if self.batch_first { input } else { input.swap_dims(0, 1) };
+        let mut states = states.unwrap_or_default().into_iter();
+        let mut final_states = Vec::with_capacity(self.layers.len());
+
+        for layer in &self.layers {
+            let state = states.next().map(|(hidden, cell)| LstmState::new(cell, hidden));
+            let (output, state) = layer.forward(input_seq, state);
+            input_seq = output;
+            final_states.push((state.hidden, state.cell));
+        }
+
+        let output = if self.batch_first { input_seq } else { input_seq.swap_dims(0, 1) };
+        (output, final_states)
+    }
+}
+
+/// Largest absolute difference between two implementations' outputs and final states on `input`
+pub fn parity_error<B: Backend>(a: &dyn Recurrent<B>, b: &dyn Recurrent<B>, input: Tensor<B, 3>) -> f32 {
+    let max_diff = |x: Tensor<B, 3>, y: Tensor<B, 3>| (x - y).abs().max().into_scalar().elem::<f32>();
+    let (output_a, states_a) = a.forward_stateful(input.clone(), None);
+    let (output_b, states_b) = b.forward_stateful(input, None);
+    states_a
+        .into_iter()
+        .zip(states_b)
+        .map(|((hidden_a, cell_a), (hidden_b, cell_b))| {
+            max_diff(hidden_a.unsqueeze(), hidden_b.unsqueeze()).max(max_diff(cell_a.unsqueeze(), cell_b.unsqueeze()))
+        })
+        .fold(max_diff(output_a, output_b), f32::max)
+}
+
+/// Stateful LSTM run over a sequence that arrives in chunks
+///
+/// Keeps every layer's (hidden, cell) state between `step` calls, so
+/// feeding a sequence in chunks gives the same outputs as one `forward`
+/// over the whole sequence.
+#[derive(Debug)]
+pub struct SequenceSession<B: Backend> {
+    states: Option<LayerStates<B>>,
+    steps: usize,
+}
+
+impl<B: Backend> Default for SequenceSession<B> {
+    fn default() -> Self {
+        Self { states: None, steps: 0 }
+    }
+}
+
+impl<B: Backend> SequenceSession<B> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timesteps processed so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Run the next chunk (same layout as `Lstm::forward` input) and return its outputs
+    pub fn step(&mut self, model: &(impl Recurrent<B> + ?Sized), chunk: Tensor<B, 3>) -> Tensor<B, 3> {
+        let seq_dim = if model.batch_first() { 1 } else { 0 };
+        self.steps += chunk.dims()[seq_dim];
+        let (output, states) = model.forward_stateful(chunk, self.states.take());
+        self.states = Some(states);
+        output
+    }
+
+    /// Forget the carried state and start a new sequence
+    pub fn reset(&mut self) {
+        self.states = None;
+        self.steps = 0;
+    }
+}
+
+// ============================================================================
+// Shared Inference Model
+// ============================================================================
+
+/// Default location of trained LSTM weights (see `registry`)
+pub const LSTM_WEIGHTS_PATH: &str = "models/lstm";
+
+struct LoadedLstm<B: Backend> {
+    config: LstmConfig,
+    model: Lstm<B>,
+    /// Set when the built-in implementation serves inference
+    builtin: Option<BuiltinLstm<B>>,
+}
+
+impl<B: Backend> LoadedLstm<B> {
+    /// The implementation serving inference
+    fn runner(&self) -> &dyn Recurrent<B> {
+        match &self.builtin {
+            Some(builtin) => builtin,
+            None => &self.model,
+        }
+    }
+}
+
+/// Model pool keys of the GPU model and of the same weights on the CPU backend
+/// (used while the GPU watchdog has fallen back)
+const POOL_KEY: &str = "lstm";
+const CPU_POOL_KEY: &str = "lstm@cpu";
+
+fn load_lstm<B: Backend>() -> Mutex<LoadedLstm<B>> {
+    let device = Default::default();
+    let config = LstmConfig::default();
+    let model = Lstm::<B>::new(config.clone(), &device);
+
+    let model = match model.clone().load_file(LSTM_WEIGHTS_PATH, &CompactRecorder::new(), &device) {
+        Ok(trained) => {
+            log_info!("[Lstm] Loaded weights from {}", LSTM_WEIGHTS_PATH);
+            trained
+        }
+        Err(_) => {
+            log_warn!("[Lstm] No trained weights at {}, using untrained model", LSTM_WEIGHTS_PATH);
+            model
+        }
+    };
+
+    let builtin = choose_builtin(&config, &model, &device);
+    Mutex::new(LoadedLstm { config, model, builtin })
+}
+
+/// The CPU copy, from the int8 weights written by `quantization` when there are any
+///
+/// The quantized model always runs the custom implementation: the built-in
+/// copy would be rebuilt from dequantized weights.
+fn load_cpu_lstm() -> Mutex<LoadedLstm<NdArray>> {
+    let device = Default::default();
+    let config = LstmConfig::default();
+    let model = Lstm::<NdArray>::new(config.clone(), &device);
+    match crate::quantization::load_quantized(model, LSTM_WEIGHTS_PATH, &device) {
+        Some(model) => Mutex::new(LoadedLstm { config, model, builtin: None }),
+        None => load_lstm::<NdArray>(),
+    }
+}
+
+/// Timesteps and timed runs of the `auto` comparison
+const AUTO_PROBE_STEPS: usize = 32;
+const AUTO_ROUNDS: u32 = 5;
+
+fn time_forward<B: Backend>(model: &dyn Recurrent<B>, input: &Tensor<B, 3>) -> Duration {
+    // One untimed run first, so kernel compilation and allocation don't count
+    let _ = model.forward_stateful(input.clone(), None).0.into_data();
+    let started = Instant::now();
+    for _ in 0..AUTO_ROUNDS {
+        let _ = model.forward_stateful(input.clone(), None).0.into_data();
+    }
+    started.elapsed() / AUTO_ROUNDS
+}
+
+/// The built-in copy of `model` if `PATTERN_CLOCK_LSTM_IMPL` selects it
+///
+/// With `auto` both run a probe sequence; the built-in one is picked when it
+/// is faster and its outputs agree within `PARITY_TOLERANCE`.
+fn choose_builtin<B: Backend>(config: &LstmConfig, model: &Lstm<B>, device: &B::Device) -> Option<BuiltinLstm<B>> {
+    let choice = crate::config::config().lstm_impl;
+    if choice == LstmImpl::Custom {
+        return None;
+    }
+    let builtin = BuiltinLstm::from_lstm(model);
+    if choice == LstmImpl::Burn {
+        log_info!("[Lstm] Using the built-in burn implementation");
+        return Some(builtin);
+    }
+
+    let shape = if config.batch_first {
+        [1, AUTO_PROBE_STEPS, config.input_size]
+    } else {
+        [AUTO_PROBE_STEPS, 1, config.input_size]
+    };
+    let probe = Tensor::<B, 3>::random(shape, Distribution::Default, device);
+    let error = parity_error(model, &builtin, probe.clone());
+    if error > PARITY_TOLERANCE {
+        log_warn!("[Lstm] Built-in implementation differs by {:.2e}, keeping the custom one", error);
+        return None;
+    }
+    let custom = time_forward(model, &probe);
+    let burn = time_forward(&builtin, &probe);
+    let use_builtin = burn < custom;
+    log_info!(
+        "[Lstm] Probe run: custom {:?}, burn {:?} (max difference {:.2e}); using {}",
+        custom, burn, error, if use_builtin { "burn" } else { "custom" }
+    );
+    use_builtin.then_some(builtin)
+}
+
+/// Drop the loaded LSTM so the next request loads it again (e.g. after `lstm_impl` changed)
+pub fn unload() {
+    model_pool::evict(POOL_KEY);
+    model_pool::evict(CPU_POOL_KEY);
+}
+
+/// Output sequence and final state of one LSTM run
+#[derive(Debug, Clone, Serialize)]
+pub struct LstmInference {
+    /// `[batch, seq, hidden_size]`
+    pub output: JsonTensor,
+    /// `[batch, hidden_size]`
+    pub hidden: JsonTensor,
+    /// `[batch, hidden_size]`
+    pub cell: JsonTensor,
+}
+
+/// Validate a `[batch, seq, features]` or `[seq, features]` input and add the batch dimension if missing
+fn batched_input(input: &JsonTensor, input_size: usize) -> Result<JsonTensor, TensorError> {
+    if input.rank() == 2 {
+        input.expect_shape(&[("seq", None), ("features", Some(input_size))])?;
+        Ok(input.clone().unsqueeze())
+    } else {
+        input.expect_shape(&[("batch", None), ("seq", None), ("features", Some(input_size))])?;
+        Ok(input.clone())
+    }
+}
+
+/// Validate one queued input as a `[batch, seq, features]` tensor on `device`
+fn prepare_input<B: Backend>(lstm: &LoadedLstm<B>, input: &JsonTensor, device: &B::Device) -> anyhow::Result<Tensor<B, 3>> {
+    let input = batched_input(input, lstm.config.input_size)?;
+    anyhow::ensure!(input.shape[1] > 0, "the input sequence is empty");
+    Ok(input.to_tensor::<B, 3>(device)?)
+}
+
+/// Run several inputs as one padded batch; invalid inputs fail on their own
+fn infer_batch<B: Backend>(lstm: &LoadedLstm<B>, inputs: &[JsonTensor]) -> Vec<anyhow::Result<LstmInference>> {
+    let device = Default::default();
+    let tensors: Vec<anyhow::Result<Tensor<B, 3>>> = inputs.iter().map(|input| prepare_input(lstm, input, &device)).collect();
+    let max_len = tensors.iter().flatten().map(|tensor| tensor.dims()[1]).max().unwrap_or(0);
+
+    // Each input's (first row, rows, length) in the combined batch
+    let mut rows = Vec::new();
+    let mut lengths = Vec::new();
+    let placements: Vec<anyhow::Result<(usize, usize, usize)>> = tensors
+        .into_iter()
+        .map(|tensor| {
+            let tensor = tensor?;
+            let [batch, seq_len, features] = tensor.dims();
+            let start = lengths.len();
+            lengths.resize(start + batch, seq_len);
+            rows.push(if seq_len < max_len {
+                Tensor::cat(vec![tensor, Tensor::zeros([batch, max_len - seq_len, features], &device)], 1)
+            } else {
+                tensor
+            });
+            Ok((start, batch, seq_len))
+        })
+        .collect();
+
+    let forward = (!rows.is_empty()).then(|| lstm.runner().forward_packed(Tensor::cat(rows, 0), &lengths));
+    let hidden_size = lstm.config.hidden_size;
+    placements
+        .into_iter()
+        .map(|placement| {
+            let (start, batch, seq_len) = placement?;
+            let (output, states) = forward.as_ref().expect("valid inputs were batched");
+            let (hidden, cell) = states.last().expect("the LSTM has at least one layer");
+            let rows = start..start + batch;
+            Ok(LstmInference {
+                output: JsonTensor::from_tensor(output.clone().slice([rows.clone(), 0..seq_len, 0..hidden_size])),
+                hidden: JsonTensor::from_tensor(hidden.clone().slice([rows.clone(), 0..hidden_size])),
+                cell: JsonTensor::from_tensor(cell.clone().slice([rows, 0..hidden_size])),
+            })
+        })
+        .collect()
+}
+
+// Concurrent `run_lstm` calls (the inference endpoint, MCP, `lstm` agents)
+// are batched: every call queues its input, and the call that gets to run
+// next takes everything queued so far (up to `MAX_BATCH_ROWS` sequences)
+// and runs it as one padded batch (`Recurrent::forward_packed`). A burst of
+// small requests then costs a few wide passes instead of one narrow pass
+// each.
+
+/// Most sequences (batch rows) run together; a larger request still runs, alone
+const MAX_BATCH_ROWS: usize = 64;
+
+struct QueuedInference {
+    input: JsonTensor,
+    reply: mpsc::Sender<anyhow::Result<LstmInference>>,
+}
+
+static QUEUED: Mutex<VecDeque<QueuedInference>> = Mutex::new(VecDeque::new());
+/// Held while a batch runs, so queued inputs collect meanwhile
+static BATCH_RUNNER: Mutex<()> = Mutex::new(());
+
+/// Batch rows of a queued input (inputs that won't validate count as one)
+fn batch_rows(input: &JsonTensor) -> usize {
+    if input.rank() == 3 { input.shape[0].max(1) } else { 1 }
+}
+
+/// Run the oldest queued inputs as one batch, if any are left
+fn run_queued() {
+    // Guards no data, so a batch that panicked doesn't stop the ones after it
+    let _runner = BATCH_RUNNER.lock().unwrap_or_else(PoisonError::into_inner);
+    let batch: Vec<QueuedInference> = {
+        let mut queued = QUEUED.lock().unwrap();
+        let mut rows = 0;
+        let mut batch = Vec::new();
+        while let Some(next) = queued.front() {
+            let next_rows = batch_rows(&next.input);
+            if !batch.is_empty() && rows + next_rows > MAX_BATCH_ROWS {
+                break;
+            }
+            rows += next_rows;
+            batch.extend(queued.pop_front());
+        }
+        batch
+    };
+    if batch.is_empty() {
+        return;
+    }
+
+    let inputs: Vec<JsonTensor> = batch.iter().map(|queued| queued.input.clone()).collect();
+    for (queued, result) in batch.into_iter().zip(infer_with_fallback(&inputs)) {
+        let _ = queued.reply.send(result);
+    }
+}
+
+/// Runs on the GPU unless the GPU watchdog has fallen back to the CPU; a
+/// GPU out-of-memory failure retries the batch on the CPU backend.
+fn infer_with_fallback(inputs: &[JsonTensor]) -> Vec<anyhow::Result<LstmInference>> {
+    if !gpu::cpu_fallback() {
+        let lstm = model_pool::get_or_load(POOL_KEY, load_lstm::<Wgpu>);
+        let lstm = lstm.lock().unwrap();
+        match gpu::guarded("lstm.infer", || infer_batch(&lstm, inputs)) {
+            Ok(results) => return results,
+            Err(GpuError::OutOfMemory { .. }) => log_warn!("[Lstm] Retrying inference on the CPU backend"),
+            Err(e) => return inputs.iter().map(|_| Err(anyhow::anyhow!("{}", e))).collect(),
+        }
+    }
+    let lstm = model_pool::get_or_load(CPU_POOL_KEY, load_cpu_lstm);
+    let lstm = lstm.lock().unwrap();
+    infer_batch(&lstm, inputs)
+}
+
+/// Run the shared LSTM over a `[batch, seq, features]` (or unbatched `[seq, features]`) tensor
+///
+/// Batched with concurrent calls (see above); the result is the same as
+/// running the input alone.
+pub fn run_lstm(input: &JsonTensor) -> anyhow::Result<LstmInference> {
+    let (reply, result) = mpsc::channel();
+    QUEUED.lock().unwrap().push_back(QueuedInference { input: input.clone(), reply });
+    loop {
+        match result.try_recv() {
+            Ok(result) => return result,
+            Err(mpsc::TryRecvError::Empty) => run_queued(),
+            Err(mpsc::TryRecvError::Disconnected) => anyhow::bail!("the inference batch failed"),
+        }
+    }
+}
+
+// ============================================================================
+// Streaming Inference Sessions
+// ============================================================================
+//
+// Long or live sequences are sent in chunks to `/api/infer/stream`; each
+// chunk runs through a `SequenceSession` that carries the LSTM state to the
+// next chunk and returns only that chunk's outputs. Sessions that receive no
+// chunk for `STREAM_IDLE_TIMEOUT_MS` are dropped, and at most `MAX_STREAMS`
+// are open at once.
+//
+// A session is taken out of its slot while a chunk runs, so the sessions map
+// is not locked during the GPU step; another chunk for the same session is
+// refused until it is back.
+
+const STREAM_IDLE_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+const MAX_STREAMS: usize = 64;
+
+struct StreamSession {
+    /// `None` while a chunk runs
+    session: Option<SequenceSession<Wgpu>>,
+    batch: usize,
+    last_used_ms: u64,
+}
+
+static STREAMS: OnceLock<Mutex<HashMap<String, StreamSession>>> = OnceLock::new();
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A new stream session was refused because `MAX_STREAMS` are open
+#[derive(Debug)]
+pub struct TooManyStreams {
+    pub limit: usize,
+}
+
+impl std::fmt::Display for TooManyStreams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} stream sessions are open; close one or wait for one to expire", self.limit)
+    }
+}
+
+impl std::error::Error for TooManyStreams {}
+
+/// Outputs for one chunk of a streamed sequence
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamChunkOutput {
+    /// Session id to send with the next chunk
+    pub session: String,
+    /// Timesteps processed by the session so far, including this chunk
+    pub steps: usize,
+    /// `[batch, chunk_len, hidden_size]`
+    pub output: JsonTensor,
+    /// True once the session has been closed
+    pub closed: bool,
+}
+
+/// Feed one `[batch, chunk_len, features]` (or `[chunk_len, features]`) chunk into a stream
+///
+/// `session: None` opens a new session, failing with `TooManyStreams` when
+/// `MAX_STREAMS` are open. With `close` the session is dropped after this chunk.
+pub fn stream_lstm(session: Option<&str>, chunk: &JsonTensor, close: bool) -> anyhow::Result<StreamChunkOutput> {
+    let lstm = model_pool::get_or_load(POOL_KEY, load_lstm::<Wgpu>);
+    let lstm = lstm.lock().unwrap();
+    let device = Default::default();
+
+    let chunk = batched_input(chunk, lstm.config.input_size)?;
+    let batch = chunk.shape[0];
+    let input = if chunk.shape[1] > 0 { Some(chunk.to_tensor::<Wgpu, 3>(&device)?) } else { None };
+
+    let streams = STREAMS.get_or_init(Default::default);
+    let now = now_millis();
+    let (id, mut sequence) = {
+        let mut streams = streams.lock().unwrap();
+        streams.retain(|_, stream| {
+            stream.session.is_none() || now.saturating_sub(stream.last_used_ms) < STREAM_IDLE_TIMEOUT_MS
+        });
+        match session {
+            Some(id) => {
+                let stream = streams.get_mut(id)
+                    .ok_or_else(|| anyhow::anyhow!("unknown or expired stream session {}", id))?;
+                anyhow::ensure!(
+                    stream.batch == batch,
+                    "chunk has batch size {} but session {} was opened with batch size {}",
+                    batch, id, stream.batch
+                );
+                let sequence = stream.session.take()
+                    .ok_or_else(|| anyhow::anyhow!("stream session {} is still processing a chunk", id))?;
+                stream.last_used_ms = now;
+                (id.to_string(), sequence)
+            }
+            None => {
+                if streams.len() >= MAX_STREAMS {
+                    return Err(TooManyStreams { limit: MAX_STREAMS }.into());
+                }
+                let id = format!("lstm-{:x}-{}", now, NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed));
+                streams.insert(id.clone(), StreamSession { session: None, batch, last_used_ms: now });
+                log_info!("[Lstm] Opened stream session {} (batch {})", id, batch);
+                (id, SequenceSession::new())
+            }
+        }
+    };
+
+    let output = if let Some(input) = input {
+        match gpu::guarded("lstm.stream", || JsonTensor::from_tensor(sequence.step(lstm.runner(), input))) {
+            Ok(output) => output,
+            Err(e) => {
+                // The carried state is lost with the failed step
+                streams.lock().unwrap().remove(&id);
+                return Err(anyhow::anyhow!("stream session {} closed: {}", id, e));
+            }
+        }
+    } else {
+        JsonTensor { shape: vec![batch, 0, lstm.config.hidden_size], dtype: Default::default(), data: Vec::new() }
+    };
+    let steps = sequence.steps();
+
+    let mut streams = streams.lock().unwrap();
+    if close {
+        streams.remove(&id);
+        log_info!("[Lstm] Closed stream session {} after {} steps", id, steps);
+    } else if let Some(stream) = streams.get_mut(&id) {
+        stream.session = Some(sequence);
+        stream.last_used_ms = now_millis();
+    }
+    Ok(StreamChunkOutput { session: id, steps, output, closed: close })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type B = NdArray;
+
+    fn random_input(batch: usize, seq_len: usize, input_size: usize) -> Tensor<B, 3> {
+        Tensor::random([batch, seq_len, input_size], Distribution::Default, &Default::default())
+    }
+
+    #[test]
+    fn custom_lstm_matches_burn_lstm() {
+        for (input_size, hidden_size, num_layers) in [(8, 16, 1), (16, 32, 2)] {
+            let config = LstmConfig { input_size, hidden_size, num_layers, ..Default::default() };
+            let custom = Lstm::<B>::new(config, &Default::default());
+            let builtin = BuiltinLstm::from_lstm(&custom);
+            let error = parity_error(&custom, &builtin, random_input(4, 12, input_size));
+            assert!(
+                error <= PARITY_TOLERANCE,
+                "{}x{}x{}: implementations differ by {:.2e}",
+                input_size, hidden_size, num_layers, error
+            );
+        }
+    }
+
+    #[test]
+    fn fused_projection_matches_stepwise() {
+        let config = LstmConfig { input_size: 8, hidden_size: 16, ..Default::default() };
+        let model = Lstm::<B>::new(config, &Default::default());
+        let input = random_input(4, 24, 8);
+        let (fused, _) = model.forward_stateful(input.clone(), None);
+        let (stepwise, _) = model.forward_stepwise(input, None);
+        let error = (fused - stepwise).abs().max().into_scalar().elem::<f32>();
+        assert!(error <= PARITY_TOLERANCE, "fused and stepwise differ by {:.2e}", error);
+    }
+
+    #[test]
+    fn packed_batch_matches_single_runs() {
+        let device = Default::default();
+        let config = LstmConfig { input_size: 8, hidden_size: 16, ..Default::default() };
+        let custom = Lstm::<B>::new(config.clone(), &device);
+        let builtin = BuiltinLstm::from_lstm(&custom);
+
+        let lengths = [3, 9, 1, 6];
+        let max_len = 9;
+        let sequences: Vec<Tensor<B, 3>> = lengths.iter().map(|length| random_input(1, *length, 8)).collect();
+        let padded = Tensor::cat(
+            sequences
+                .iter()
+                .map(|sequence| match max_len - sequence.dims()[1] {
+                    0 => sequence.clone(),
+                    padding => Tensor::cat(vec![sequence.clone(), Tensor::zeros([1, padding, 8], &device)], 1),
+                })
+                .collect(),
+            0,
+        );
+
+        let implementations: [(&str, &dyn Recurrent<B>); 2] = [("custom", &custom), ("burn", &builtin)];
+        for (name, model) in implementations {
+            let (_, packed_states) = model.forward_packed(padded.clone(), &lengths);
+            let (packed_hidden, _) = packed_states.last().expect("one layer").clone();
+            for (row, sequence) in sequences.iter().enumerate() {
+                let (_, states) = model.forward_stateful(sequence.clone(), None);
+                let (hidden, _) = states.last().expect("one layer").clone();
+                let row_hidden = packed_hidden.clone().slice([row..row + 1, 0..config.hidden_size]);
+                let error = (hidden - row_hidden).abs().max().into_scalar().elem::<f32>();
+                assert!(error <= PARITY_TOLERANCE, "{} row {}: packed and single runs differ by {:.2e}", name, row, error);
+            }
+        }
     }
 }
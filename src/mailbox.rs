@@ -0,0 +1,486 @@
+use ractor::concurrency::{oneshot, timeout};
+use ractor::rpc::CallResult;
+use ractor::{ActorRef, MessagingErr, RpcReplyPort};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::agents::AgentMessage;
+use crate::config::config;
+
+// ============================================================================
+// Priority Mailbox
+// ============================================================================
+//
+// Ractor mailboxes are first in, first out, so during a burst a status
+// request waits behind every data message already queued. Messages sent
+// through `AgentRef` (what `get_agent` returns) go into a per-agent priority
+// queue instead, and the actor's mailbox only gets an `AgentMessage::Dequeue`
+// for each; on every `Dequeue` the agent handles the most urgent message
+// waiting. Messages of the same priority keep their order.
+//
+// Priorities, most urgent first:
+//
+//   control  status requests, probes, state saves, subscriptions, finished
+//            summaries (see `AgentMessage::Summarized`)
+//   high     `ProcessData` sent with `priority=high`
+//   normal   everything else, and `ProcessData` by default
+//   low      `ProcessData` sent with `priority=low`; waits as long as more
+//            urgent work keeps arriving
+//
+// Each agent's queue holds at most `mailbox_capacity` messages (default
+// 1000, 0 for no limit, per agent in `agents.toml`). Sending to a full queue fails with
+// `SendError::Full`, which the process endpoints return as 429 with the
+// current depth, so producers back off instead of growing memory without
+// bound. Control messages are always accepted.
+//
+// An agent can be paused, resumed or drained without stopping it
+// (`AgentMessage::Pause`, `Resume`, `Drain`; control messages themselves):
+//
+//   paused    new work is still queued (up to the capacity), but the agent
+//             only takes control messages off its queue
+//   draining  the agent works through its queue and refuses new work with
+//             `SendError::Draining`; once the queue is empty it is `drained`
+//             and keeps refusing work
+//   running   resuming picks up everything that waited meanwhile
+//
+// Stopping an agent uses ractor's stop signal, which overtakes any message.
+// Hand-offs stay `normal` so they still follow the data queued before them.
+// Timers the agent sets on itself bypass the queue and are handled in
+// mailbox order. A restarted agent starts running, with an empty queue;
+// what was waiting for it is lost along with its old mailbox (queued jobs
+// are kept as `lost`, see `jobs`). The queue of an agent left stopped after
+// a crash is dropped right away.
+
+/// How urgently a message is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// Reserved for control messages
+    Control,
+}
+
+impl Priority {
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Control => "control",
+        }
+    }
+
+    /// A priority data can be sent with (`low`, `normal` or `high`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an agent takes work off its queue and accepts new work
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lifecycle {
+    #[default]
+    Running,
+    /// Only control messages are handled; other work waits in the queue
+    Paused,
+    /// New work is refused while the queue empties
+    Draining,
+    /// The queue is empty and new work is refused until resumed
+    Drained,
+}
+
+impl Lifecycle {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lifecycle::Running => "running",
+            Lifecycle::Paused => "paused",
+            Lifecycle::Draining => "draining",
+            Lifecycle::Drained => "drained",
+        }
+    }
+
+    /// Whether work other than control messages is refused
+    pub fn refuses_work(self) -> bool {
+        matches!(self, Lifecycle::Draining | Lifecycle::Drained)
+    }
+}
+
+struct Entry {
+    priority: Priority,
+    seq: u64,
+    message: AgentMessage,
+}
+
+impl Ord for Entry {
+    /// Higher priority first, then earlier sequence numbers
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+
+static QUEUES: Mutex<BTreeMap<u8, BinaryHeap<Entry>>> = Mutex::new(BTreeMap::new());
+
+fn queues() -> MutexGuard<'static, BTreeMap<u8, BinaryHeap<Entry>>> {
+    QUEUES.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+static LIFECYCLES: Mutex<BTreeMap<u8, Lifecycle>> = Mutex::new(BTreeMap::new());
+
+fn lifecycles() -> MutexGuard<'static, BTreeMap<u8, Lifecycle>> {
+    LIFECYCLES.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Whether agent `agent_id` is running, paused or draining
+pub fn lifecycle(agent_id: u8) -> Lifecycle {
+    lifecycles().get(&agent_id).copied().unwrap_or_default()
+}
+
+/// Set by the agent when it handles `Pause`, `Resume` or `Drain`
+pub fn set_lifecycle(agent_id: u8, lifecycle: Lifecycle) {
+    lifecycles().insert(agent_id, lifecycle);
+}
+
+/// The most urgent message waiting for agent `agent_id`; only control
+/// messages while it is paused
+pub fn pop(agent_id: u8) -> Option<AgentMessage> {
+    let paused = lifecycle(agent_id) == Lifecycle::Paused;
+    let mut queues = queues();
+    let queue = queues.get_mut(&agent_id)?;
+    if paused && queue.peek()?.priority != Priority::Control {
+        return None;
+    }
+    queue.pop().map(|entry| entry.message)
+}
+
+/// Messages waiting for agent `agent_id`
+pub fn depth(agent_id: u8) -> usize {
+    queues().get(&agent_id).map_or(0, |queue| queue.len())
+}
+
+/// Drop everything waiting for agent `agent_id` and mark it running again;
+/// returns how many messages went
+pub fn clear(agent_id: u8) -> usize {
+    lifecycles().remove(&agent_id);
+    queues().remove(&agent_id).map_or(0, |queue| queue.len())
+}
+
+/// Why a message was not queued
+#[derive(Debug)]
+pub enum SendError {
+    /// The agent's queue already holds `capacity` messages
+    Full {
+        agent_id: u8,
+        depth: usize,
+        capacity: usize,
+        message: AgentMessage,
+    },
+    /// The agent is draining (or drained) and takes no new work until resumed
+    Draining {
+        agent_id: u8,
+        message: AgentMessage,
+    },
+    /// The agent is not running
+    Closed(MessagingErr<AgentMessage>),
+}
+
+impl SendError {
+    /// The message that was not queued
+    pub fn into_message(self) -> Option<AgentMessage> {
+        match self {
+            SendError::Full { message, .. } | SendError::Draining { message, .. } => Some(message),
+            SendError::Closed(MessagingErr::SendErr(message)) => Some(message),
+            SendError::Closed(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Full { agent_id, depth, capacity, .. } => write!(
+                f,
+                "Agent{} mailbox is full ({} of {} messages queued), retry later",
+                agent_id, depth, capacity
+            ),
+            SendError::Draining { agent_id, .. } => {
+                write!(f, "Agent{} is draining and takes no new work until it is resumed", agent_id)
+            }
+            SendError::Closed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/// Forget the job of a message that won't reach its agent
+fn forget_job(message: &AgentMessage) {
+    if let AgentMessage::Queued { job, .. } = message {
+        crate::jobs::finish(*job);
+    }
+}
+
+/// A running agent; sends go through its priority queue
+///
+/// Dereferences to the actor, for everything but sending.
+#[derive(Debug, Clone)]
+pub struct AgentRef {
+    id: u8,
+    actor: ActorRef<AgentMessage>,
+}
+
+impl AgentRef {
+    pub fn new(id: u8, actor: ActorRef<AgentMessage>) -> Self {
+        AgentRef { id, actor }
+    }
+
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Queue `message` at its priority, unless the queue is full or the agent is draining
+    ///
+    /// The job of a `queued` message that is turned away is forgotten.
+    pub fn send_message(&self, message: AgentMessage) -> Result<(), SendError> {
+        let priority = message.priority();
+        if priority != Priority::Control && lifecycle(self.id).refuses_work() {
+            forget_job(&message);
+            return Err(SendError::Draining { agent_id: self.id, message });
+        }
+        let seq = NEXT_SEQ.fetch_add(1, AtomicOrdering::Relaxed);
+        let capacity = config().mailbox_capacity_of(self.id);
+        {
+            let mut queues = queues();
+            let queue = queues.entry(self.id).or_default();
+            if priority != Priority::Control && capacity > 0 && queue.len() >= capacity {
+                let depth = queue.len();
+                drop(queues);
+                forget_job(&message);
+                return Err(SendError::Full { agent_id: self.id, depth, capacity, message });
+            }
+            queue.push(Entry { priority, seq, message });
+        }
+        if self.actor.send_message(AgentMessage::Dequeue).is_ok() {
+            return Ok(());
+        }
+        // The agent is gone: take the message back out
+        let mut queues = queues();
+        let queue = queues.entry(self.id).or_default();
+        let mut entries = std::mem::take(queue).into_vec();
+        let message = entries.iter().position(|entry| entry.seq == seq).map(|index| entries.swap_remove(index).message);
+        *queue = BinaryHeap::from(entries);
+        drop(queues);
+        let message = message.unwrap_or(AgentMessage::Dequeue);
+        forget_job(&message);
+        Err(SendError::Closed(MessagingErr::SendErr(message)))
+    }
+
+    /// Have the agent look at its queue once per waiting message, e.g. for
+    /// the work that waited while it was paused
+    pub fn wake(&self) {
+        for _ in 0..depth(self.id) {
+            if self.actor.send_message(AgentMessage::Dequeue).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Queue the message built by `build` and wait up to `timeout_after` for its reply
+    pub async fn call<T>(
+        &self,
+        build: impl FnOnce(RpcReplyPort<T>) -> AgentMessage,
+        timeout_after: Option<Duration>,
+    ) -> Result<CallResult<T>, SendError> {
+        let (sender, receiver) = oneshot();
+        self.send_message(build(sender.into()))?;
+        Ok(match timeout_after {
+            Some(duration) => match timeout(duration, receiver).await {
+                Ok(Ok(reply)) => CallResult::Success(reply),
+                Ok(Err(_)) => CallResult::SenderError,
+                Err(_) => CallResult::Timeout,
+            },
+            None => match receiver.await {
+                Ok(reply) => CallResult::Success(reply),
+                Err(_) => CallResult::SenderError,
+            },
+        })
+    }
+}
+
+impl std::ops::Deref for AgentRef {
+    type Target = ActorRef<AgentMessage>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.actor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ractor::{Actor, ActorProcessingErr};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    /// Stands in for an agent: counts its `Dequeue`s and leaves the queue to the test
+    struct Counter;
+
+    impl Actor for Counter {
+        type Msg = AgentMessage;
+        type State = Arc<AtomicUsize>;
+        type Arguments = Arc<AtomicUsize>;
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            dequeues: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(dequeues)
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            message: Self::Msg,
+            dequeues: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            if matches!(message, AgentMessage::Dequeue) {
+                dequeues.fetch_add(1, AtomicOrdering::SeqCst);
+            }
+            Ok(())
+        }
+    }
+
+    /// An `AgentRef` for `id` (well above the ids agents run with) and its `Dequeue` count
+    async fn counter(id: u8) -> (AgentRef, Arc<AtomicUsize>) {
+        clear(id);
+        let dequeues = Arc::new(AtomicUsize::new(0));
+        let (actor, _handle) = Actor::spawn(None, Counter, dequeues.clone()).await.unwrap();
+        (AgentRef::new(id, actor), dequeues)
+    }
+
+    async fn wait_for(dequeues: &AtomicUsize, expected: usize) {
+        for _ in 0..500 {
+            if dequeues.load(AtomicOrdering::SeqCst) >= expected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(dequeues.load(AtomicOrdering::SeqCst), expected);
+    }
+
+    fn data(text: &str, priority: Priority) -> AgentMessage {
+        AgentMessage::ProcessData { data: text.to_string(), priority }
+    }
+
+    fn popped(id: u8) -> Option<String> {
+        pop(id).map(|message| match message {
+            AgentMessage::ProcessData { data, .. } => data,
+            message => message.kind().to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn most_urgent_first_and_in_order_within_a_priority() {
+        let (agent, dequeues) = counter(240).await;
+        agent.send_message(data("low", Priority::Low)).unwrap();
+        agent.send_message(data("normal 1", Priority::Normal)).unwrap();
+        agent.send_message(data("high", Priority::High)).unwrap();
+        agent.send_message(data("normal 2", Priority::Normal)).unwrap();
+        agent.send_message(AgentMessage::Probe).unwrap();
+
+        // One `Dequeue` per queued message
+        wait_for(&dequeues, 5).await;
+        assert_eq!(depth(240), 5);
+        let order: Vec<String> = std::iter::from_fn(|| popped(240)).collect();
+        assert_eq!(order, [AgentMessage::Probe.kind(), "high", "normal 1", "normal 2", "low"]);
+        assert_eq!(depth(240), 0);
+        agent.stop(None);
+    }
+
+    #[tokio::test]
+    async fn a_full_queue_refuses_work_but_not_control_messages() {
+        let (agent, dequeues) = counter(241).await;
+        let capacity = config().mailbox_capacity_of(241);
+        for i in 0..capacity {
+            agent.send_message(data(&i.to_string(), Priority::Normal)).unwrap();
+        }
+        match agent.send_message(data("one too many", Priority::High)) {
+            Err(SendError::Full { depth, capacity: limit, message, .. }) => {
+                assert_eq!((depth, limit), (capacity, capacity));
+                assert!(matches!(message, AgentMessage::ProcessData { data, .. } if data == "one too many"));
+            }
+            other => panic!("expected a full mailbox, got {:?}", other),
+        }
+        agent.send_message(AgentMessage::Probe).unwrap();
+
+        // Refused messages send no `Dequeue`
+        wait_for(&dequeues, capacity + 1).await;
+        assert_eq!(clear(241), capacity + 1);
+        assert_eq!(depth(241), 0);
+        agent.stop(None);
+    }
+
+    #[tokio::test]
+    async fn paused_agents_only_take_control_messages_and_draining_ones_refuse_work() {
+        let (agent, _dequeues) = counter(242).await;
+        agent.send_message(data("waits", Priority::High)).unwrap();
+        set_lifecycle(242, Lifecycle::Paused);
+        assert_eq!(popped(242), None);
+        agent.send_message(AgentMessage::Probe).unwrap();
+        assert_eq!(popped(242).as_deref(), Some(AgentMessage::Probe.kind()));
+        assert_eq!(popped(242), None);
+        assert_eq!(depth(242), 1);
+
+        set_lifecycle(242, Lifecycle::Draining);
+        assert!(matches!(agent.send_message(data("refused", Priority::Normal)), Err(SendError::Draining { .. })));
+        assert_eq!(popped(242).as_deref(), Some("waits"));
+
+        // Clearing forgets the lifecycle too
+        clear(242);
+        assert_eq!(lifecycle(242), Lifecycle::Running);
+        agent.stop(None);
+    }
+
+    #[tokio::test]
+    async fn sending_to_a_stopped_agent_takes_the_message_back() {
+        clear(243);
+        let (actor, handle) = Actor::spawn(None, Counter, Arc::new(AtomicUsize::new(0))).await.unwrap();
+        let agent = AgentRef::new(243, actor);
+        agent.stop(None);
+        handle.await.unwrap();
+        match agent.send_message(data("lost", Priority::Normal)) {
+            Err(error) => assert!(matches!(error.into_message(), Some(AgentMessage::ProcessData { .. }))),
+            Ok(()) => panic!("a stopped agent accepted a message"),
+        }
+        assert_eq!(depth(243), 0);
+    }
+}
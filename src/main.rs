@@ -18,17 +18,42 @@ use burn::backend::{Autodiff, wgpu::Wgpu}; // Backend types: Autodiff wrapper an
 #[cfg(feature = "desktop")]
 use burn::tensor::Distribution;           // Distribution for random tensor generation
 
-// Modules
-mod mcp_server;
-mod agents;
-#[cfg(feature = "desktop")]
-mod lstm;
+// Core engine (agents, connections, models, storage, API) lives in the library crate
+use pattern_clock::telemetry;
 
 // Platform-specific app modules
 mod app;
-mod shared;
 
 fn main() {
+    // Install OTLP exporters (no-op unless OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    telemetry::init();
+    // Write a crash report to <data_dir>/crashes on panic
+    #[cfg(not(target_arch = "wasm32"))]
+    pattern_clock::crash::install();
+    // Size the server's Tokio runtime (`PATTERN_CLOCK_WORKER_THREADS`, see `threads.rs`)
+    #[cfg(not(target_arch = "wasm32"))]
+    pattern_clock::threads::configure_workers();
+
+    // `pattern-clock doctor|console|top|client|completions` run instead of the app (see `cli.rs`)
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(code) = pattern_clock::cli::run_subcommand() {
+        std::process::exit(code);
+    }
+
+    // `--demo`: seeded data and fake traffic instead of the data directory and Ollama
+    if std::env::args().any(|arg| arg == "--demo") {
+        #[cfg(all(feature = "demo", not(target_arch = "wasm32")))]
+        if let Err(e) = pattern_clock::demo::start() {
+            eprintln!("Failed to start demo mode: {}", e);
+            std::process::exit(1);
+        }
+        #[cfg(not(all(feature = "demo", not(target_arch = "wasm32"))))]
+        {
+            eprintln!("--demo needs a build with `--features demo`");
+            std::process::exit(1);
+        }
+    }
+
     #[cfg(feature = "desktop")]
     {
         // Window configuration for desktop
@@ -46,7 +71,12 @@ fn main() {
         // Web mode or fullstack server with web client
         // When dx serve runs, it builds server binary with 'web' feature
         // This launches WebApp which will be served to the browser
+        #[cfg(not(feature = "server"))]
         dioxus::launch(app::web::WebApp);
+        // The server build adds response compression / request decompression
+        // and request limits
+        #[cfg(feature = "server")]
+        serve_with_compression();
     }
     
     #[cfg(all(not(feature = "desktop"), not(feature = "web"), feature = "server"))]
@@ -55,10 +85,24 @@ fn main() {
         // to serve the web client. This branch should not normally be hit,
         // but if it is, we still launch WebApp to serve the client.
         // The wasm client is built separately and served as static files.
-        dioxus::launch(app::web::WebApp);
+        serve_with_compression();
     }
 }
 
+/// Serve `WebApp` and the server functions with the HTTP compression layers (see `compression.rs`)
+/// and request limits (see `request_limits.rs`)
+///
+/// Limits sit inside decompression, so they see the decompressed body. Storage, agents and
+/// schedules are started before the port opens (see `boot.rs`), not by the first request.
+#[cfg(all(not(feature = "desktop"), feature = "server"))]
+fn serve_with_compression() {
+    dioxus::serve(|| async move {
+        pattern_clock::boot::run().await?;
+        let router = pattern_clock::request_limits::http_layer(dioxus::server::router(app::web::WebApp));
+        Ok(pattern_clock::compression::http_layers(router))
+    });
+}
+
 
 // ============================================================================
 // Burn Tensor Example
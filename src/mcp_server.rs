@@ -1,47 +1,234 @@
 use rmcp::{
     ServerHandler,
-    handler::server::router::tool::ToolRouter,
+    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
     model::*,
+    schemars,
     tool, tool_handler, tool_router,
 };
-use crate::agents::{get_agent, ensure_agents_initialized, AgentMessage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use crate::agents::{get_agent, ensure_agents_initialized, send_work, AgentMessage};
+use crate::deadline::Deadline;
+use crate::storage::now_millis;
 
+// ============================================================================
+// MCP Server
+// ============================================================================
+//
+// One `PatternClockMCP` serves the whole process (`shared()`): the server
+// functions behind the desktop app's MCP buttons and the stdio binary use
+// the same instance, so its tool router is built once and its tool state
+// (call counters and per-tool settings) is seen by every caller. Clones
+// share that state, for transports that take the handler by value.
+
+/// Tools whose calls are counted and can be configured, `process_agent` included
+pub const TOOL_NAMES: &[&str] =
+    &["example_tool", "get_random_number", "classify_text", "run_lstm", "search_agent_history", "process_agent"];
+
+/// Arguments for the classify_text tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ClassifyTextRequest {
+    /// Text to classify
+    pub text: String,
+    /// Classify on this agent instead of directly
+    #[serde(default)]
+    pub agent_id: Option<u8>,
+}
+
+/// Arguments for the search_agent_history tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchAgentHistoryRequest {
+    /// Agent whose history is searched
+    pub agent_id: u8,
+    /// Keyword or entity to look for (case-insensitive)
+    pub term: String,
+}
+
+/// Arguments for the run_lstm tool
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RunLstmRequest {
+    /// Input sequence: a nested `[batch][seq][features]` (or `[seq][features]`) array,
+    /// or `{"shape": [...], "data": [...]}` with row-major data
+    pub input: serde_json::Value,
+}
+
+/// Settings of one tool, changed at runtime through `POST /api/mcp/tools/:name`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolConfig {
+    /// Disabled tools answer every call with an error
+    pub enabled: bool,
+    /// Calls running longer are abandoned with an error (native builds)
+    pub timeout_ms: Option<u64>,
+}
+
+impl Default for ToolConfig {
+    fn default() -> Self {
+        ToolConfig { enabled: true, timeout_ms: None }
+    }
+}
+
+/// Calls of one tool since startup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ToolStats {
+    pub calls: u64,
+    /// Calls answered with an error, including refused and timed-out ones
+    pub errors: u64,
+    /// Calls refused because the tool was disabled
+    pub refused: u64,
+    pub total_ms: u64,
+    pub last_called_ms: Option<u64>,
+}
+
+/// One tool with its settings and counters, as listed by `GET /api/mcp/tools`
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolStatus {
+    pub name: &'static str,
+    pub config: ToolConfig,
+    pub stats: ToolStats,
+}
+
+#[derive(Default)]
+struct ToolState {
+    config: RwLock<BTreeMap<&'static str, ToolConfig>>,
+    stats: Mutex<BTreeMap<&'static str, ToolStats>>,
+}
+
+#[derive(Clone)]
 pub struct PatternClockMCP {
     tool_router: ToolRouter<PatternClockMCP>,
+    state: Arc<ToolState>,
+}
+
+static SHARED: OnceLock<PatternClockMCP> = OnceLock::new();
+
+/// The process-wide MCP server, created on first use
+pub fn shared() -> &'static PatternClockMCP {
+    SHARED.get_or_init(PatternClockMCP::new)
+}
+
+impl Default for PatternClockMCP {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[tool_router]
 impl PatternClockMCP {
+    /// A server with its own tool state; most callers want `shared()`
     pub fn new() -> Self {
         Self {
             tool_router: Self::tool_router(),
+            state: Arc::default(),
         }
     }
 
     /// Example tool that returns a greeting
     #[tool(description = "A simple example tool that returns a greeting message")]
     pub async fn example_tool(&self) -> String {
-        "Hello from pattern-clock MCP server!".to_string()
+        self.run_tool("example_tool", async { "Hello from pattern-clock MCP server!".to_string() }).await
     }
 
     /// Get random numbers
     #[tool(description = "Returns a random number between 0 and 1000")]
     pub async fn get_random_number(&self) -> String {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        
-        let mut hasher = DefaultHasher::new();
-        timestamp.hash(&mut hasher);
-        let hash = hasher.finish();
-        
-        let random = (hash % 1000) as u64;
-        format!("Random number: {}", random)
+        self.run_tool("get_random_number", async { random_number() }).await
+    }
+
+    /// Classify text with the on-device LSTM classifier
+    #[tool(description = "Classifies text with the on-device LSTM classifier, directly or on agent `agent_id`, and returns label and confidence as JSON")]
+    pub async fn classify_text(&self, Parameters(request): Parameters<ClassifyTextRequest>) -> String {
+        self.run_tool("classify_text", classify(request)).await
+    }
+
+    /// Run the on-device LSTM over a feature sequence
+    #[tool(description = "Runs the on-device LSTM over a [batch, seq, features] tensor (nested array or {shape, data} row-major) and returns output, hidden and cell tensors as JSON")]
+    pub async fn run_lstm(&self, Parameters(request): Parameters<RunLstmRequest>) -> String {
+        self.run_tool("run_lstm", infer(request)).await
+    }
+
+    /// Find an agent's history entries by extracted keyword or entity
+    #[tool(description = "Lists the history entries of agent `agent_id` whose extracted keywords or entities match `term` (case-insensitive), with their extractions, as JSON")]
+    pub async fn search_agent_history(&self, Parameters(request): Parameters<SearchAgentHistoryRequest>) -> String {
+        self.run_tool("search_agent_history", search_history(request)).await
+    }
+}
+
+/// A number between 0 and 1000 from the clock's nanoseconds
+fn random_number() -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    
+    let mut hasher = DefaultHasher::new();
+    timestamp.hash(&mut hasher);
+    let hash = hasher.finish();
+    
+    let random = (hash % 1000) as u64;
+    format!("Random number: {}", random)
+}
+
+/// Body of the `classify_text` tool
+async fn classify(request: ClassifyTextRequest) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let classified = match request.agent_id {
+            Some(agent_id) => crate::agents::classify(agent_id, &request.text).await,
+            None => {
+                let text = request.text;
+                crate::compute::run("classify", move || crate::classifier::classify_text(&text))
+                    .await
+                    .and_then(|classified| classified)
+            }
+        };
+        match classified {
+            Ok(result) => serde_json::to_string(&result).unwrap_or_default(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = request;
+        "Error: classification is not available on this platform".to_string()
+    }
+}
+
+/// Body of the `search_agent_history` tool
+async fn search_history(request: SearchAgentHistoryRequest) -> String {
+    if let Err(e) = ensure_agents_initialized().await {
+        return format!("Error: Failed to initialize agents: {}", e);
+    }
+    match crate::agents::search_history(request.agent_id, &request.term).await {
+        Ok(entries) => serde_json::to_string(&entries).unwrap_or_default(),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+/// Body of the `run_lstm` tool
+async fn infer(request: RunLstmRequest) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let result = match crate::tensor_io::JsonTensor::parse(&request.input) {
+            Ok(input) => crate::compute::run("lstm.infer", move || crate::lstm::run_lstm(&input))
+                .await
+                .and_then(|result| result),
+            Err(e) => Err(e.into()),
+        };
+        match result {
+            Ok(inference) => serde_json::to_string(&inference).unwrap_or_default(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = request;
+        "Error: LSTM inference is not available on this platform".to_string()
     }
 }
 
@@ -67,23 +254,107 @@ impl PatternClockMCP {
         self.get_random_number().await
     }
 
-    /// Process agent directly (for use by desktop app)
-    pub async fn call_process_agent(&self, agent_id: u8, data: String) -> String {
-        if agent_id < 1 || agent_id > 5 {
-            return format!("Error: agent_id must be between 1 and 5, got {}", agent_id);
+    /// Classify text directly (for use by desktop app)
+    pub async fn call_classify_text(&self, text: String) -> String {
+        self.classify_text(Parameters(ClassifyTextRequest { text, agent_id: None })).await
+    }
+
+    /// Run the LSTM directly (for use by desktop app)
+    pub async fn call_run_lstm(&self, input: serde_json::Value) -> String {
+        self.run_lstm(Parameters(RunLstmRequest { input })).await
+    }
+
+    /// Process agent directly (for use by desktop app), honoring the caller's deadline if any
+    pub async fn call_process_agent(&self, agent_id: u8, data: String, deadline: Option<Deadline>) -> String {
+        self.run_tool("process_agent", process_agent(agent_id, data, deadline)).await
+    }
+}
+
+/// Body of the `process_agent` call
+async fn process_agent(agent_id: u8, data: String, deadline: Option<Deadline>) -> String {
+    // Any running agent, including ones spawned at runtime (checked against the registry below)
+    if agent_id == 0 {
+        return "Error: agent_id must be at least 1".to_string();
+    }
+
+    if let Err(e) = ensure_agents_initialized().await {
+        return format!("Error: Failed to initialize agents: {}", e);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(e) = crate::schemas::validate_text(agent_id, &data) {
+        return format!("Error: {}", e);
+    }
+
+    if let Some(actor_ref) = get_agent(agent_id) {
+        match send_work(&actor_ref, AgentMessage::ProcessData {
+            data: data.clone(),
+            priority: crate::mailbox::Priority::Normal,
+        }.with_deadline(deadline)) {
+            Ok(()) => format!("Message queued for Agent{}: {}", agent_id, data),
+            Err(e) => format!("Error: {}", e),
         }
+    } else {
+        format!("Error: Agent{} is not available", agent_id)
+    }
+}
 
-        if let Err(e) = ensure_agents_initialized().await {
-            return format!("Error: Failed to initialize agents: {}", e);
+// Tool state shared by all clones of a server
+impl PatternClockMCP {
+    /// Settings of tool `name` (defaults unless changed)
+    pub fn tool_config(&self, name: &str) -> ToolConfig {
+        let config = self.state.config.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        config.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Change the settings of tool `name`; fails for tools not in `TOOL_NAMES`
+    pub fn configure_tool(&self, name: &str, config: ToolConfig) -> Result<ToolConfig, String> {
+        let name = TOOL_NAMES.iter().copied().find(|tool| *tool == name)
+            .ok_or_else(|| format!("unknown tool {:?}; expected one of {}", name, TOOL_NAMES.join(", ")))?;
+        if config.timeout_ms == Some(0) {
+            return Err("timeout_ms must be positive".to_string());
         }
+        self.state.config.write().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(name, config.clone());
+        log_info!("[MCP] Tool {} configured: {:?}", name, config);
+        Ok(config)
+    }
 
-        if let Some(actor_ref) = get_agent(agent_id) {
-            actor_ref.send_message(AgentMessage::ProcessData {
-                data: data.clone(),
-            });
-            format!("Message queued for Agent{}: {}", agent_id, data)
+    /// Every tool with its settings and counters
+    pub fn tools(&self) -> Vec<ToolStatus> {
+        let stats = self.state.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        TOOL_NAMES.iter().map(|&name| ToolStatus {
+            name,
+            config: self.tool_config(name),
+            stats: stats.get(name).cloned().unwrap_or_default(),
+        }).collect()
+    }
+
+    /// Run `call` as tool `name` unless it is disabled, within its timeout, and count it
+    async fn run_tool(&self, name: &'static str, call: impl Future<Output = String>) -> String {
+        let config = self.tool_config(name);
+        let started = std::time::Instant::now();
+        let result = if !config.enabled {
+            format!("Error: tool {} is disabled", name)
         } else {
-            format!("Error: Agent{} is not available", agent_id)
+            match config.timeout_ms {
+                #[cfg(not(target_arch = "wasm32"))]
+                Some(ms) => tokio::time::timeout(std::time::Duration::from_millis(ms), call).await
+                    .unwrap_or_else(|_| format!("Error: tool {} timed out after {} ms", name, ms)),
+                _ => call.await,
+            }
+        };
+
+        let mut stats = self.state.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let entry = stats.entry(name).or_default();
+        entry.calls += 1;
+        entry.total_ms += started.elapsed().as_millis() as u64;
+        entry.last_called_ms = Some(now_millis());
+        if !config.enabled {
+            entry.refused += 1;
+        }
+        if result.starts_with("Error:") {
+            entry.errors += 1;
         }
+        result
     }
 }
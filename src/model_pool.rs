@@ -0,0 +1,123 @@
+use opentelemetry::KeyValue;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::config::config;
+use crate::storage::now_millis;
+use crate::telemetry::instruments;
+
+// ============================================================================
+// Warm Model Pool (LRU)
+// ============================================================================
+//
+// Loaded models stay on their device between requests, but only the
+// `model_pool_size` most recently used ones (`PATTERN_CLOCK_MODEL_POOL_SIZE`,
+// reloadable). Asking for a model that is not loaded loads it; loading past
+// the pool size evicts the least recently used model. Callers hold an `Arc`,
+// so an evicted model is freed once its last in-flight request finishes.
+//
+// Entries are keyed by registry name, plus `@backend` for the same weights
+// loaded on another backend (e.g. `lstm@cpu`). Load times go to the
+// `model.load.duration` metric and evictions to `model.pool.evictions`.
+
+/// A loaded model, as reported by `/api/models/pool`
+#[derive(Debug, Clone, Serialize)]
+pub struct PooledModel {
+    pub key: String,
+    pub loaded_at_ms: u64,
+    pub last_used_ms: u64,
+    /// Time spent loading the model, in milliseconds
+    pub load_ms: u64,
+}
+
+struct Entry {
+    model: Arc<dyn Any + Send + Sync>,
+    loaded_at_ms: u64,
+    last_used_ms: u64,
+    load_ms: u64,
+}
+
+fn pool() -> &'static Mutex<HashMap<String, Entry>> {
+    static POOL: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    POOL.get_or_init(Default::default)
+}
+
+/// The model stored under `key`, loading it with `load` if it is not in the pool
+///
+/// The pool is not locked while `load` runs; if two callers load the same
+/// model at once, the first one stored wins and the other copy is dropped.
+pub fn get_or_load<T: Send + Sync + 'static>(key: &str, load: impl FnOnce() -> T) -> Arc<T> {
+    if let Some(model) = lookup::<T>(key) {
+        return model;
+    }
+
+    let started = Instant::now();
+    let loaded = Arc::new(load());
+    let elapsed = started.elapsed();
+    instruments().model_load_duration.record(elapsed.as_secs_f64(), &[KeyValue::new("model", key.to_string())]);
+    log_info!("[ModelPool] Loaded {} in {} ms", key, elapsed.as_millis());
+
+    let mut pool = pool().lock().unwrap();
+    let now = now_millis();
+    let entry = pool.entry(key.to_string()).or_insert_with(|| Entry {
+        model: loaded,
+        loaded_at_ms: now,
+        last_used_ms: now,
+        load_ms: elapsed.as_millis() as u64,
+    });
+    entry.last_used_ms = now;
+    let model = entry.model.clone();
+    evict_least_recently_used(&mut pool, key);
+    drop(pool);
+
+    model.downcast::<T>().unwrap_or_else(|_| panic!("model pool key {} holds a different type", key))
+}
+
+fn lookup<T: Send + Sync + 'static>(key: &str) -> Option<Arc<T>> {
+    let mut pool = pool().lock().unwrap();
+    let entry = pool.get_mut(key)?;
+    entry.last_used_ms = now_millis();
+    entry.model.clone().downcast::<T>().ok()
+}
+
+/// Drop least recently used entries (never `keep`) until the pool fits its configured size
+fn evict_least_recently_used(pool: &mut HashMap<String, Entry>, keep: &str) {
+    let capacity = config().model_pool_size.max(1);
+    while pool.len() > capacity {
+        let Some(oldest) = pool
+            .iter()
+            .filter(|(key, _)| key.as_str() != keep)
+            .min_by_key(|(_, entry)| entry.last_used_ms)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        pool.remove(&oldest);
+        instruments().model_pool_evictions.add(1, &[KeyValue::new("model", oldest.clone())]);
+        log_info!("[ModelPool] Evicted {} (pool size {})", oldest, capacity);
+    }
+}
+
+/// Remove a model from the pool; returns whether it was loaded
+pub fn evict(key: &str) -> bool {
+    pool().lock().unwrap().remove(key).is_some()
+}
+
+/// Loaded models, most recently used first
+pub fn loaded_models() -> Vec<PooledModel> {
+    let pool = pool().lock().unwrap();
+    let mut models: Vec<PooledModel> = pool
+        .iter()
+        .map(|(key, entry)| PooledModel {
+            key: key.clone(),
+            loaded_at_ms: entry.loaded_at_ms,
+            last_used_ms: entry.last_used_ms,
+            load_ms: entry.load_ms,
+        })
+        .collect();
+    models.sort_by(|a, b| b.last_used_ms.cmp(&a.last_used_ms));
+    models
+}
@@ -0,0 +1,320 @@
+use ractor::{Actor, ActorProcessingErr, ActorRef};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::agents::{agent_ids, get_agent, AgentMessage};
+use crate::events::{publish, subscribe, Event, SharedEvent};
+use crate::storage::{list_typed, now_millis, put_typed};
+
+// ============================================================================
+// Self-Monitoring Meta-Agent
+// ============================================================================
+//
+// Watches pattern-clock's own event stream and raises alerts for agent lag,
+// LLM error spikes and slow ticks. Alerts are published as `monitor.alert`
+// events and stored in the `alerts` collection.
+
+/// Collection holding raised alerts, keyed by alert id
+pub const ALERTS_COLLECTION: &str = "alerts";
+
+/// Thresholds of the self-monitor
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    /// How often agents are probed and the tick loop is checked
+    pub probe_interval: Duration,
+    /// Probe round-trip above which an agent is considered lagging
+    pub agent_lag_threshold: Duration,
+    /// Agent message handling time above which a message is considered slow
+    pub slow_message_threshold: Duration,
+    /// Delay of the probe tick itself above which the runtime is considered overloaded
+    pub slow_tick_threshold: Duration,
+    /// Window over which LLM error rates are computed
+    pub llm_error_window: Duration,
+    /// Error fraction within the window that raises an alert
+    pub llm_error_rate: f64,
+    /// Minimum requests in the window before the error rate is evaluated
+    pub llm_min_requests: usize,
+    /// Minimum time between two alerts of the same kind and subject
+    pub alert_cooldown: Duration,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval: Duration::from_secs(10),
+            agent_lag_threshold: Duration::from_secs(2),
+            slow_message_threshold: Duration::from_secs(5),
+            slow_tick_threshold: Duration::from_secs(1),
+            llm_error_window: Duration::from_secs(60),
+            llm_error_rate: 0.5,
+            llm_min_requests: 5,
+            alert_cooldown: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+/// A problem detected by the monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    /// Sortable id: `<millis>-<kind>`
+    pub id: String,
+    /// `agent_lag`, `slow_message`, `llm_errors` or `slow_tick`
+    pub kind: String,
+    /// What the alert is about, e.g. `agent-3` or the LLM model name
+    pub subject: String,
+    pub severity: Severity,
+    pub message: String,
+    pub ts: u64,
+}
+
+/// Messages handled by the monitor actor
+#[derive(Debug, Clone)]
+pub enum MonitorMessage {
+    /// An event from the internal bus
+    Observe(SharedEvent),
+    /// Periodic check; `delay_ms` is how late the tick fired
+    Tick { delay_ms: u64 },
+}
+
+#[cfg(feature = "cluster")]
+impl ractor::Message for MonitorMessage {}
+
+pub struct Monitor;
+
+pub struct MonitorState {
+    config: MonitorConfig,
+    /// Probe send times per agent, cleared when the agent answers
+    pending_probes: HashMap<u8, u64>,
+    /// `(ts, ok)` of recent LLM requests
+    llm_requests: VecDeque<(u64, bool)>,
+    /// Last alert time per `(kind, subject)`
+    last_alert: HashMap<(String, String), u64>,
+}
+
+impl MonitorState {
+    fn raise(&mut self, kind: &str, subject: &str, severity: Severity, message: String) {
+        let now = now_millis();
+        let key = (kind.to_string(), subject.to_string());
+        let cooldown = self.config.alert_cooldown.as_millis() as u64;
+        if self.last_alert.get(&key).is_some_and(|last| now.saturating_sub(*last) < cooldown) {
+            return;
+        }
+        self.last_alert.insert(key, now);
+
+        let alert = Alert {
+            id: format!("{:013}-{}", now, kind),
+            kind: kind.to_string(),
+            subject: subject.to_string(),
+            severity,
+            message,
+            ts: now,
+        };
+        log_warn!("[Monitor] {:?} {}: {}", alert.severity, alert.subject, alert.message);
+        if let Err(e) = put_typed(ALERTS_COLLECTION, &alert.id, &alert) {
+            log_error!("[Monitor] Failed to store alert: {}", e);
+        }
+        publish("monitor.alert", json!(alert));
+    }
+
+    fn observe(&mut self, event: &Event) {
+        let payload = &event.payload;
+        match event.kind.as_str() {
+            "agent.probe" => {
+                let Some(agent_id) = payload["agent_id"].as_u64() else { return };
+                if let Some(sent_at) = self.pending_probes.remove(&(agent_id as u8)) {
+                    let lag = event.ts.saturating_sub(sent_at);
+                    if lag > self.config.agent_lag_threshold.as_millis() as u64 {
+                        self.raise(
+                            "agent_lag",
+                            &format!("agent-{}", agent_id),
+                            Severity::Warning,
+                            format!("Agent{} answered a probe after {} ms", agent_id, lag),
+                        );
+                    }
+                }
+            }
+            "agent.handled" => {
+                let duration_ms = payload["duration_ms"].as_u64().unwrap_or(0);
+                if duration_ms > self.config.slow_message_threshold.as_millis() as u64 {
+                    let agent_id = payload["agent_id"].as_u64().unwrap_or(0);
+                    self.raise(
+                        "slow_message",
+                        &format!("agent-{}", agent_id),
+                        Severity::Warning,
+                        format!("Agent{} took {} ms to handle {}", agent_id, duration_ms, payload["message"]),
+                    );
+                }
+            }
+            "llm.request" => {
+                self.llm_requests.push_back((event.ts, payload["ok"].as_bool().unwrap_or(false)));
+                self.check_llm_errors(payload["model"].as_str().unwrap_or("llm"));
+            }
+            _ => {}
+        }
+    }
+
+    fn check_llm_errors(&mut self, model: &str) {
+        let window_start = now_millis().saturating_sub(self.config.llm_error_window.as_millis() as u64);
+        while self.llm_requests.front().is_some_and(|(ts, _)| *ts < window_start) {
+            self.llm_requests.pop_front();
+        }
+        let total = self.llm_requests.len();
+        if total < self.config.llm_min_requests {
+            return;
+        }
+        let errors = self.llm_requests.iter().filter(|(_, ok)| !ok).count();
+        let rate = errors as f64 / total as f64;
+        if rate >= self.config.llm_error_rate {
+            let severity = if errors == total { Severity::Critical } else { Severity::Warning };
+            self.raise(
+                "llm_errors",
+                model,
+                severity,
+                format!("{} of {} LLM requests failed in the last {}s", errors, total, self.config.llm_error_window.as_secs()),
+            );
+        }
+    }
+
+    fn tick(&mut self, delay_ms: u64) {
+        if delay_ms > self.config.slow_tick_threshold.as_millis() as u64 {
+            self.raise(
+                "slow_tick",
+                "runtime",
+                Severity::Warning,
+                format!("Monitor tick fired {} ms late; the runtime may be overloaded", delay_ms),
+            );
+        }
+
+        // Probes still unanswered from the previous round count as lag too
+        let now = now_millis();
+        let threshold = self.config.agent_lag_threshold.as_millis() as u64;
+        let overdue: Vec<(u8, u64)> = self.pending_probes
+            .iter()
+            .filter(|(_, sent_at)| now.saturating_sub(**sent_at) > threshold)
+            .map(|(id, sent_at)| (*id, now.saturating_sub(*sent_at)))
+            .collect();
+        for (agent_id, waited) in overdue {
+            self.raise(
+                "agent_lag",
+                &format!("agent-{}", agent_id),
+                Severity::Critical,
+                format!("Agent{} has not answered a probe for {} ms", agent_id, waited),
+            );
+        }
+
+        for agent_id in agent_ids() {
+            if self.pending_probes.contains_key(&agent_id) {
+                continue;
+            }
+            if let Some(actor_ref) = get_agent(agent_id) {
+                if actor_ref.send_message(AgentMessage::Probe).is_ok() {
+                    self.pending_probes.insert(agent_id, now);
+                }
+            }
+        }
+    }
+}
+
+impl Actor for Monitor {
+    type Msg = MonitorMessage;
+    type State = MonitorState;
+    type Arguments = MonitorConfig;
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        config: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        log_info!("[Monitor] Starting self-monitor");
+        Ok(MonitorState {
+            config,
+            pending_probes: HashMap::new(),
+            llm_requests: VecDeque::new(),
+            last_alert: HashMap::new(),
+        })
+    }
+
+    async fn handle(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        match message {
+            MonitorMessage::Observe(event) => state.observe(&event),
+            MonitorMessage::Tick { delay_ms } => state.tick(delay_ms),
+        }
+        Ok(())
+    }
+}
+
+static MONITOR: OnceLock<ActorRef<MonitorMessage>> = OnceLock::new();
+
+/// Spawn the monitor actor, its event forwarder and its tick loop (once)
+pub async fn start_monitor(config: MonitorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if MONITOR.get().is_some() {
+        return Ok(());
+    }
+    let probe_interval = config.probe_interval;
+    let (monitor_ref, handle) = Actor::spawn(None, Monitor, config)
+        .await
+        .map_err(|e| format!("Failed to spawn Monitor: {:?}", e))?;
+    MONITOR.set(monitor_ref.clone())
+        .map_err(|_| "Failed to store Monitor reference")?;
+
+    tokio::spawn(async move {
+        let _ = handle.await;
+    });
+
+    // Deliver alerts to the configured notification channels
+    crate::notifications::ensure_notifier_started();
+
+    // Forward the internal event bus (skipping our own alerts and other instances' events)
+    let forward_ref = monitor_ref.clone();
+    let mut events = subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if event.kind != "monitor.alert" && event.is_local() => {
+                    let _ = forward_ref.send_message(MonitorMessage::Observe(event));
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    log_warn!("[Monitor] Event stream lagged, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    // Tick loop; lateness of the tick itself is the slow-tick signal
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(probe_interval);
+        loop {
+            let scheduled = interval.tick().await;
+            let delay_ms = scheduled.elapsed().as_millis() as u64;
+            crate::live_stats::record_tick(delay_ms);
+            let _ = monitor_ref.send_message(MonitorMessage::Tick { delay_ms });
+        }
+    });
+
+    Ok(())
+}
+
+/// Stored alerts, newest first
+pub fn recent_alerts(limit: usize) -> anyhow::Result<Vec<Alert>> {
+    let mut alerts: Vec<Alert> = list_typed(ALERTS_COLLECTION)?;
+    alerts.reverse();
+    alerts.truncate(limit);
+    Ok(alerts)
+}
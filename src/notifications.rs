@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::config;
+use crate::events::subscribe;
+use crate::monitor::{Alert, Severity};
+use crate::secrets::resolve;
+
+// ============================================================================
+// Alert Notification Channels
+// ============================================================================
+//
+// Webhook channels declared in the `PATTERN_CLOCK_CONFIG` file. Every
+// `monitor.alert` event at or above a channel's minimum severity is POSTed to
+// its URL as JSON. Channels with `approvals: true` also receive every
+// `approval.requested` event, i.e. the pending approval. Channels are looked
+// up in the current configuration for each delivery, so a config reload
+// takes effect with the next alert.
+
+/// Timeout of one webhook delivery
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A webhook receiving monitor alerts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub name: String,
+    /// Receives each alert as a JSON body
+    pub url: String,
+    /// Alerts below this severity are not delivered
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+    /// Bearer token as a secret reference (e.g. `file:/run/secrets/webhook`), resolved per delivery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    /// Also deliver approvals waiting for a decision
+    #[serde(default)]
+    pub approvals: bool,
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Warning
+}
+
+impl NotificationChannel {
+    /// Check that the channel is usable before it is swapped into the configuration
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("channel name must not be empty".to_string());
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(format!("webhook URL {:?} must start with http:// or https://", self.url));
+        }
+        if let Some(reference) = &self.auth {
+            resolve(reference).map_err(|e| format!("auth: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(NOTIFY_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+async fn deliver(channel: &NotificationChannel, body: &impl Serialize) -> anyhow::Result<()> {
+    let mut request = client().post(&channel.url).json(body);
+    if let Some(reference) = &channel.auth {
+        request = request.bearer_auth(resolve(reference)?.expose());
+    }
+    request
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Forward `monitor.alert` and `approval.requested` events to the configured channels (once per process)
+pub fn ensure_notifier_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let mut events = subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    // Events from other instances are delivered by those instances
+                    Ok(event) if event.is_local() => event,
+                    Ok(_) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log_warn!("[Notifications] Event stream lagged, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if event.kind == "approval.requested" {
+                    for channel in config().notifications.iter().filter(|c| c.approvals) {
+                        if let Err(e) = deliver(channel, &event.payload).await {
+                            log_error!("[Notifications] Failed to notify {} of an approval: {}", channel.name, e);
+                        }
+                    }
+                    continue;
+                }
+                if event.kind != "monitor.alert" {
+                    continue;
+                }
+                let Ok(alert) = serde_json::from_value::<Alert>(event.payload.clone()) else {
+                    continue;
+                };
+                for channel in config().notifications.iter().filter(|c| alert.severity >= c.min_severity) {
+                    if let Err(e) = deliver(channel, &alert).await {
+                        log_error!("[Notifications] Failed to notify {}: {}", channel.name, e);
+                    }
+                }
+            }
+        });
+    });
+}
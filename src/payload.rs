@@ -0,0 +1,94 @@
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// ============================================================================
+// Text and Binary Payloads
+// ============================================================================
+//
+// Payloads stay raw bytes inside the process (agent messages, the event bus)
+// and are base64-encoded only where they cross a JSON boundary:
+//
+//     {"type": "text", "text": "cpu at 97%"}
+//     {"type": "binary", "content_type": "image/png", "data": "iVBORw0KGgo..."}
+
+/// Data handed to agents and attached to events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Payload {
+    Text {
+        text: String,
+    },
+    Binary {
+        /// MIME type, e.g. `image/png` or `application/octet-stream`
+        content_type: String,
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+    },
+}
+
+impl Payload {
+    pub fn text(text: impl Into<String>) -> Self {
+        Payload::Text { text: text.into() }
+    }
+
+    pub fn binary(content_type: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        Payload::Binary { content_type: content_type.into(), data: data.into() }
+    }
+
+    pub fn content_type(&self) -> &str {
+        match self {
+            Payload::Text { .. } => "text/plain",
+            Payload::Binary { content_type, .. } => content_type,
+        }
+    }
+
+    /// Size of the payload in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            Payload::Text { text } => text.len(),
+            Payload::Binary { data, .. } => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The text, or `None` for binary payloads
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Payload::Text { text } => Some(text),
+            Payload::Binary { .. } => None,
+        }
+    }
+
+    /// Short human-readable description, used where only text fits (history, logs)
+    pub fn describe(&self) -> String {
+        match self {
+            Payload::Text { text } => text.clone(),
+            Payload::Binary { content_type, data } => format!("[{} payload, {} bytes]", content_type, data.len()),
+        }
+    }
+}
+
+impl From<String> for Payload {
+    fn from(text: String) -> Self {
+        Payload::Text { text }
+    }
+}
+
+/// Serde adapter encoding `Vec<u8>` as a standard base64 string
+pub mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| serde::de::Error::custom(format!("invalid base64 payload: {}", e)))
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::connections::Persona;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Agent Personas
+// ============================================================================
+//
+// An agent can be given a persona: a system prompt, a model and a sampling
+// temperature used for all of its LLM calls (extraction and summaries), so
+// Agent2 can be a terse classifier on a small model while Agent4 explains at
+// length. Agents without a persona use the default provider as before.
+//
+// Personas are set through `/api/agents/:id/persona` or the Personas panel,
+// or declared under `personas` in the config file (saved on load and on
+// every reload, replacing edits made through the API).
+
+/// Collection holding personas, keyed by agent id
+pub const PERSONAS_COLLECTION: &str = "personas";
+
+/// A persona assigned to an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentPersona {
+    pub agent_id: u8,
+    pub persona: Persona,
+    /// Milliseconds since the Unix epoch
+    pub updated_at: u64,
+}
+
+/// All saved personas
+pub fn list_personas() -> anyhow::Result<Vec<AgentPersona>> {
+    list_typed(PERSONAS_COLLECTION)
+}
+
+/// Persona of one agent, if it has one
+pub fn get_persona(agent_id: u8) -> anyhow::Result<Option<AgentPersona>> {
+    get_typed(PERSONAS_COLLECTION, &agent_id.to_string())
+}
+
+/// Validate and save the persona of `agent_id` (replacing any previous one)
+pub fn set_persona(agent_id: u8, persona: Persona) -> anyhow::Result<AgentPersona> {
+    persona.validate().map_err(|e| anyhow::anyhow!(e))?;
+    // Config reloads save the same personas again; keep their timestamps
+    if let Some(entry) = get_persona(agent_id)?.filter(|entry| entry.persona == persona) {
+        return Ok(entry);
+    }
+    let entry = AgentPersona { agent_id, persona, updated_at: now_millis() };
+    put_typed(PERSONAS_COLLECTION, &agent_id.to_string(), &entry)?;
+    log_info!("[Personas] Persona set for Agent{}", agent_id);
+    Ok(entry)
+}
+
+/// Remove an agent's persona, returning whether it had one
+pub fn delete_persona(agent_id: u8) -> anyhow::Result<bool> {
+    storage().delete(PERSONAS_COLLECTION, &agent_id.to_string())
+}
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use crate::cycle::{CycleTick, Schedule};
+use crate::events::{publish, subscribe, Event};
+use crate::rules::{PatternRule, RuleMatch};
+use crate::timeseries::Point;
+
+// ============================================================================
+// Cycle Phases
+// ============================================================================
+//
+// Phases are the handlers the cognitive cycle runs on its ticks. Each phase
+// has a schedule (see `cycle::Schedule`) and gets the local events
+// published since it last ran. A phase only reports what it found through
+// its `PhaseOutput`; on the live cycle its firings are published as
+// `phase.fired` events, so the same phase code can be replayed without
+// touching anything outside.
+//
+// Every tick of a running cycle is kept in a journal (the last
+// `JOURNAL_CAPACITY` ticks, in memory) with the events that arrived before
+// it. `replay(from, to)` runs fresh instances of the phases over a recorded
+// span as fast as it can and returns their firings, which answers "why did
+// this pattern fire at tick 48,233": replay the ticks before it and read
+// the firing's detail.
+//
+// The built-in `rules` phase evaluates the saved pattern rules against
+// event series: every numeric payload field of an event is a point of the
+// series `<kind>.<field>` (e.g. `llm.request.duration_ms`), tagged with the
+// event's string fields.
+
+/// Ticks kept in the journal
+pub const JOURNAL_CAPACITY: usize = 100_000;
+/// Ticks a replay may cover
+pub const MAX_REPLAY_TICKS: u64 = JOURNAL_CAPACITY as u64;
+/// Points kept per rule by the rules phase
+const RULE_HISTORY: usize = 256;
+/// How often the live rules phase re-reads the saved rules
+const RULES_REFRESH_MS: u64 = 5_000;
+
+/// A handler run on cycle ticks
+pub trait Phase: Send {
+    fn name(&self) -> &'static str;
+
+    fn schedule(&self) -> Schedule {
+        Schedule::EVERY_TICK
+    }
+
+    /// Handle one scheduled tick and the events since the previous one;
+    /// report through `output` only, so the phase can be replayed
+    fn on_tick(&mut self, tick: &CycleTick, events: &[Event], output: &mut PhaseOutput);
+}
+
+/// Whether a phase instance runs on the live cycle or in a replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Live,
+    Replay,
+}
+
+/// Creates a fresh instance of a phase (one for the live cycle, one per replay)
+pub type PhaseFactory = fn(Mode) -> Box<dyn Phase>;
+
+/// Something a phase found on a tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Firing {
+    pub tick: u64,
+    pub ts: u64,
+    pub phase: String,
+    /// What fired, e.g. a rule id
+    pub subject: String,
+    pub detail: serde_json::Value,
+}
+
+/// Collects the firings of one phase run
+pub struct PhaseOutput {
+    phase: &'static str,
+    tick: CycleTick,
+    firings: Vec<Firing>,
+}
+
+impl PhaseOutput {
+    pub fn fire(&mut self, subject: &str, detail: serde_json::Value) {
+        self.firings.push(Firing {
+            tick: self.tick.tick,
+            ts: self.tick.ts,
+            phase: self.phase.to_string(),
+            subject: subject.to_string(),
+            detail,
+        });
+    }
+}
+
+/// One tick of the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickRecord {
+    pub tick: u64,
+    pub ts: u64,
+    /// Local events published since the previous tick
+    pub events: Vec<Event>,
+}
+
+/// Result of replaying a span of the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub from: u64,
+    pub to: u64,
+    /// Ticks of the span found in the journal
+    pub ticks: usize,
+    pub events: usize,
+    pub phases: Vec<String>,
+    pub firings: Vec<Firing>,
+}
+
+/// A phase instance with the events waiting for its next scheduled tick
+struct Running {
+    phase: Box<dyn Phase>,
+    pending: Vec<Event>,
+}
+
+impl Running {
+    fn new(factory: PhaseFactory, mode: Mode) -> Self {
+        Self { phase: factory(mode), pending: Vec::new() }
+    }
+
+    fn on_tick(&mut self, tick: &CycleTick, events: &[Event]) -> Vec<Firing> {
+        self.pending.extend_from_slice(events);
+        if !self.phase.schedule().fires_on(tick.tick) {
+            return Vec::new();
+        }
+        let mut output = PhaseOutput { phase: self.phase.name(), tick: *tick, firings: Vec::new() };
+        let events = std::mem::take(&mut self.pending);
+        self.phase.on_tick(tick, &events, &mut output);
+        output.firings
+    }
+}
+
+struct Engine {
+    factories: Vec<PhaseFactory>,
+    live: Vec<Running>,
+    journal: VecDeque<TickRecord>,
+    /// Events since the last tick
+    arrived: Vec<Event>,
+}
+
+fn engine() -> &'static Mutex<Engine> {
+    static ENGINE: OnceLock<Mutex<Engine>> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let factories: Vec<PhaseFactory> = vec![RulesPhase::create];
+        Mutex::new(Engine {
+            live: factories.iter().map(|factory| Running::new(*factory, Mode::Live)).collect(),
+            factories,
+            journal: VecDeque::new(),
+            arrived: Vec::new(),
+        })
+    })
+}
+
+/// Add a phase to the live cycle and to future replays
+pub fn register(factory: PhaseFactory) {
+    let mut engine = engine().lock().unwrap();
+    engine.factories.push(factory);
+    engine.live.push(Running::new(factory, Mode::Live));
+}
+
+/// Names of the registered phases, in run order
+pub fn phase_names() -> Vec<String> {
+    engine().lock().unwrap().live.iter().map(|running| running.phase.name().to_string()).collect()
+}
+
+/// Whether an event is an input of the phases (their own output is not)
+fn is_input(event: &Event) -> bool {
+    event.is_local() && event.kind != "phase.fired" && !event.kind.starts_with("cycle.")
+}
+
+/// Start collecting events for the journal (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let mut events = subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) if is_input(&event) => {
+                        let mut engine = engine().lock().unwrap();
+                        // Nothing consumes events while the cycle is stopped
+                        if crate::cycle::is_running() {
+                            engine.arrived.push(event.event().clone());
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log_warn!("[Phases] Event stream lagged, skipped {} events", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    });
+}
+
+/// Record `tick` in the journal and run the live phases on it (called by the cycle)
+pub(crate) fn run_tick(tick: &CycleTick) {
+    let firings = {
+        let mut engine = engine().lock().unwrap();
+        let events = std::mem::take(&mut engine.arrived);
+        let firings: Vec<Firing> = engine.live.iter_mut().flat_map(|running| running.on_tick(tick, &events)).collect();
+        if engine.journal.len() == JOURNAL_CAPACITY {
+            engine.journal.pop_front();
+        }
+        engine.journal.push_back(TickRecord { tick: tick.tick, ts: tick.ts, events });
+        firings
+    };
+    for firing in firings {
+        publish("phase.fired", serde_json::to_value(&firing).unwrap_or_default());
+    }
+}
+
+/// Journal entries for ticks `from..=to`
+pub fn journal(from: u64, to: u64) -> Vec<TickRecord> {
+    let engine = engine().lock().unwrap();
+    engine.journal.iter().filter(|record| (from..=to).contains(&record.tick)).cloned().collect()
+}
+
+/// Replay ticks `from..=to` through fresh phases (all, or only `phase`), without side effects
+pub fn replay(from: u64, to: u64, phase: Option<&str>) -> anyhow::Result<ReplayReport> {
+    anyhow::ensure!(from <= to, "replay start {} is after its end {}", from, to);
+    anyhow::ensure!(to - from < MAX_REPLAY_TICKS, "replay spans more than {} ticks", MAX_REPLAY_TICKS);
+    let factories = engine().lock().unwrap().factories.clone();
+    let mut phases: Vec<Running> = factories.into_iter().map(|factory| Running::new(factory, Mode::Replay)).collect();
+    if let Some(name) = phase {
+        phases.retain(|running| running.phase.name() == name);
+        anyhow::ensure!(!phases.is_empty(), "no phase named {:?}", name);
+    }
+
+    let records = journal(from, to);
+    let mut firings = Vec::new();
+    for record in &records {
+        let tick = CycleTick { tick: record.tick, ts: record.ts };
+        for running in &mut phases {
+            firings.extend(running.on_tick(&tick, &record.events));
+        }
+    }
+    Ok(ReplayReport {
+        from,
+        to,
+        ticks: records.len(),
+        events: records.iter().map(|record| record.events.len()).sum(),
+        phases: phases.iter().map(|running| running.phase.name().to_string()).collect(),
+        firings,
+    })
+}
+
+// ----------------------------------------------------------------------------
+// Rules phase
+// ----------------------------------------------------------------------------
+
+/// Points of the event series carried by `event` (`<kind>.<field>`)
+pub fn event_points(event: &Event) -> Vec<(String, Point)> {
+    let Some(fields) = event.payload.as_object() else {
+        return Vec::new();
+    };
+    let tags: BTreeMap<String, String> = fields
+        .iter()
+        .filter_map(|(field, value)| value.as_str().map(|text| (field.clone(), text.to_string())))
+        .collect();
+    fields
+        .iter()
+        .filter_map(|(field, value)| {
+            let value = value.as_f64().or_else(|| value.as_bool().map(|b| if b { 1.0 } else { 0.0 }))?;
+            Some((format!("{}.{}", event.kind, field), Point { ts: event.ts, value, tags: tags.clone() }))
+        })
+        .collect()
+}
+
+/// Recent points of one rule's series, evaluated as new points arrive
+#[derive(Default)]
+pub struct RuleWindow {
+    history: VecDeque<Point>,
+}
+
+impl RuleWindow {
+    /// Add the points of `events` on the rule's series; returns the matches among them
+    pub fn observe(&mut self, rule: &PatternRule, events: &[Event]) -> Vec<RuleMatch> {
+        let mut added = 0;
+        for (series, point) in events.iter().flat_map(event_points) {
+            if series != rule.series {
+                continue;
+            }
+            if self.history.len() == RULE_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(point);
+            added += 1;
+        }
+        if added == 0 {
+            return Vec::new();
+        }
+        let points: Vec<Point> = self.history.iter().cloned().collect();
+        // Only matches among the new points; earlier ones were reported before
+        let first_new_ts = points[points.len().saturating_sub(added)].ts;
+        rule.evaluate(&points).into_iter().filter(|found| found.ts >= first_new_ts).collect()
+    }
+}
+
+/// Evaluates the saved pattern rules against event series on every tick
+pub struct RulesPhase {
+    rules: Vec<PatternRule>,
+    /// Whether to re-read the saved rules every `RULES_REFRESH_MS` (replays use the rules as they are now)
+    refresh: bool,
+    loaded_at: u64,
+    windows: HashMap<String, RuleWindow>,
+}
+
+impl RulesPhase {
+    fn create(mode: Mode) -> Box<dyn Phase> {
+        Box::new(Self {
+            rules: load_rules(),
+            refresh: mode == Mode::Live,
+            loaded_at: crate::storage::now_millis(),
+            windows: HashMap::new(),
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_rules() -> Vec<PatternRule> {
+    crate::rule_store::list_rules()
+        .unwrap_or_else(|e| {
+            log_warn!("[Phases] Failed to load rules: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .collect()
+}
+
+/// Rules are saved on the server
+#[cfg(target_arch = "wasm32")]
+fn load_rules() -> Vec<PatternRule> {
+    Vec::new()
+}
+
+impl Phase for RulesPhase {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    /// Anomaly checks run on every tick of the cycle
+    fn schedule(&self) -> Schedule {
+        Schedule::EVERY_TICK
+    }
+
+    fn on_tick(&mut self, tick: &CycleTick, events: &[Event], output: &mut PhaseOutput) {
+        if self.refresh && tick.ts.saturating_sub(self.loaded_at) >= RULES_REFRESH_MS {
+            self.rules = load_rules();
+            self.loaded_at = tick.ts;
+        }
+        if events.is_empty() {
+            return;
+        }
+        for rule in &self.rules {
+            let window = self.windows.entry(rule.id.clone()).or_default();
+            for found in window.observe(rule, events) {
+                output.fire(&rule.id, serde_json::json!({
+                    "rule": rule.name,
+                    "series": rule.series,
+                    "match": found,
+                }));
+            }
+        }
+    }
+}
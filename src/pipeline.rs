@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+use crate::agents::{get_agent, AgentMessage};
+use crate::connections::agent_provider;
+use crate::events::publish;
+use crate::extraction::Extraction;
+use ractor::rpc::CallResult;
+
+// ============================================================================
+// Agent Pipelines
+// ============================================================================
+//
+// A pipeline chains agents: the input goes to the first agent, and each
+// agent's output is the next one's input. Every step is handled in the
+// agent's mailbox like other work, so the agent extracts and remembers the
+// input as if it had been sent to `/api/agents/:id/process`. The step's
+// transformation then produces the output handed on: the input unchanged,
+// re-cased, the extracted keywords, or an LLM rewrite using the agent's
+// persona.
+//
+// A run waits for each step in turn and stops at the first failing one.
+// The result carries the final output and every hop with its input, output,
+// extraction and duration.
+
+/// Longest wait for one step, including the agent's queue and LLM calls
+pub const STEP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Most steps in one pipeline
+pub const MAX_STEPS: usize = 32;
+
+/// How a step turns its input into the output for the next step
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Pass the input on unchanged
+    #[default]
+    None,
+    Lowercase,
+    Uppercase,
+    /// The extracted keywords, comma separated
+    Keywords,
+    /// Ask the agent's LLM (with its persona); `{input}` in the prompt is replaced by the input
+    Llm { prompt: String },
+}
+
+impl Transform {
+    /// Apply the transformation for `agent_id`
+    pub async fn apply(&self, agent_id: u8, input: &str, extraction: &Extraction) -> anyhow::Result<String> {
+        Ok(match self {
+            Transform::None => input.to_string(),
+            Transform::Lowercase => input.to_lowercase(),
+            Transform::Uppercase => input.to_uppercase(),
+            Transform::Keywords => extraction.keywords.join(", "),
+            Transform::Llm { prompt } => {
+                let prompt = if prompt.contains("{input}") {
+                    prompt.replace("{input}", input)
+                } else {
+                    format!("{}\n\n{}", prompt, input)
+                };
+                agent_provider(agent_id).generate(&prompt).await?.trim().to_string()
+            }
+        })
+    }
+}
+
+/// One agent of a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub agent_id: u8,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+/// Agents applied in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub steps: Vec<PipelineStep>,
+}
+
+/// What an agent answers for one step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepOutput {
+    pub output: String,
+    pub extraction: Extraction,
+}
+
+/// One step of a run
+#[derive(Debug, Clone, Serialize)]
+pub struct Hop {
+    pub agent_id: u8,
+    pub input: String,
+    pub output: String,
+    pub extraction: Extraction,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineResult {
+    /// Output of the last step
+    pub output: String,
+    pub hops: Vec<Hop>,
+}
+
+impl Pipeline {
+    /// Agents in order, each passing its input on unchanged
+    pub fn through(agent_ids: &[u8]) -> Self {
+        Pipeline {
+            steps: agent_ids
+                .iter()
+                .map(|agent_id| PipelineStep { agent_id: *agent_id, transform: Transform::None })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("a pipeline needs at least one step".to_string());
+        }
+        if self.steps.len() > MAX_STEPS {
+            return Err(format!("a pipeline has at most {} steps", MAX_STEPS));
+        }
+        for step in &self.steps {
+            if get_agent(step.agent_id).is_none() {
+                return Err(format!("Agent{} is not available", step.agent_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Feed `input` through every step and collect the hops
+    pub async fn run(&self, input: &str) -> anyhow::Result<PipelineResult> {
+        self.validate().map_err(|e| anyhow::anyhow!(e))?;
+        let mut hops = Vec::with_capacity(self.steps.len());
+        let mut current = input.to_string();
+        for (index, step) in self.steps.iter().enumerate() {
+            let started = Instant::now();
+            let output = run_step(step, current.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!("step {} (Agent{}): {}", index + 1, step.agent_id, e))?;
+            let input = std::mem::replace(&mut current, output.output);
+            hops.push(Hop {
+                agent_id: step.agent_id,
+                input,
+                output: current.clone(),
+                extraction: output.extraction,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        publish("pipeline.finished", json!({
+            "agents": self.steps.iter().map(|step| step.agent_id).collect::<Vec<_>>(),
+            "duration_ms": hops.iter().map(|hop| hop.duration_ms).sum::<u64>(),
+        }));
+        Ok(PipelineResult { output: current, hops })
+    }
+}
+
+async fn run_step(step: &PipelineStep, input: String) -> anyhow::Result<StepOutput> {
+    let actor_ref = get_agent(step.agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", step.agent_id))?;
+    let transform = step.transform.clone();
+    let message = |reply| AgentMessage::RunStep { input, transform, reply }.queued(step.agent_id);
+    match actor_ref.call(message, Some(STEP_TIMEOUT)).await {
+        Ok(CallResult::Success(result)) => result.map_err(anyhow::Error::msg),
+        Ok(CallResult::Timeout) => anyhow::bail!("no answer within {:?}", STEP_TIMEOUT),
+        Ok(CallResult::SenderError) => anyhow::bail!("the agent stopped before answering"),
+        Err(e) => anyhow::bail!("failed to reach the agent: {}", e),
+    }
+}
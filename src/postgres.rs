@@ -0,0 +1,196 @@
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use std::future::Future;
+use tokio_postgres::NoTls;
+
+use crate::storage::{Storage, UpdateFn};
+
+// ============================================================================
+// Postgres Storage
+// ============================================================================
+//
+// `Storage` on Postgres, so several instances can share one state. Selected
+// by setting `PATTERN_CLOCK_DATABASE_URL` (feature "postgres"):
+//
+//     PATTERN_CLOCK_DATABASE_URL=postgres://pattern:secret@db:5432/pattern_clock
+//
+// Documents live in one table keyed by (collection, key) with a JSONB value.
+// The schema is created and upgraded by `MIGRATIONS` at connect time; each
+// applied version is recorded in `schema_migrations`, and an advisory lock
+// keeps instances starting together from migrating twice. Connections are
+// not encrypted (run the database on a private network).
+//
+// `Storage` is synchronous and is called from async code, so queries run on
+// a small runtime of their own. A caller on a Tokio worker waits inside
+// `block_in_place`, which hands the worker's other tasks to another thread
+// first; callers on a current-thread runtime wait on a helper thread.
+// `update` runs its read-modify-write in one transaction, holding an
+// advisory lock on the document so it also covers documents not yet stored.
+
+/// Schema versions, applied in order; never edit an entry, append a new one
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE storage_documents (
+        collection TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value JSONB NOT NULL,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        PRIMARY KEY (collection, key)
+    )",
+];
+
+/// Advisory lock id held while migrating
+const MIGRATION_LOCK: i64 = 0x7061_7474_6572_6e00;
+
+/// Storage backed by a Postgres connection pool
+pub struct PostgresStorage {
+    pool: Pool,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl PostgresStorage {
+    /// Connect with up to `pool_size` connections and bring the schema up to date
+    pub fn connect(url: &str, pool_size: usize) -> anyhow::Result<Self> {
+        let manager = Manager::from_config(
+            url.parse::<tokio_postgres::Config>()?,
+            NoTls,
+            ManagerConfig { recycling_method: RecyclingMethod::Fast },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(pool_size)
+            .runtime(deadpool_postgres::Runtime::Tokio1)
+            .build()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .thread_name("pattern-clock-db")
+            .build()?;
+        let storage = Self { pool, runtime };
+
+        let pool = storage.pool.clone();
+        let applied = storage.run(async move { migrate(&pool).await })?;
+        if applied > 0 {
+            log_info!("[Storage] Applied {} Postgres migrations", applied);
+        }
+        Ok(storage)
+    }
+
+    /// Run `task` on the storage runtime and wait for it without stalling the caller's runtime
+    fn run<T: Send>(&self, task: impl Future<Output = anyhow::Result<T>> + Send) -> anyhow::Result<T> {
+        use tokio::runtime::{Handle, RuntimeFlavor};
+
+        match Handle::try_current().map(|handle| handle.runtime_flavor()) {
+            Ok(RuntimeFlavor::MultiThread) => tokio::task::block_in_place(|| self.runtime.block_on(task)),
+            // `block_in_place` needs a multi-thread runtime, and `block_on` can't run inside one
+            Ok(_) => std::thread::scope(|scope| {
+                scope
+                    .spawn(|| self.runtime.block_on(task))
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("database task panicked"))?
+            }),
+            Err(_) => self.runtime.block_on(task),
+        }
+    }
+}
+
+/// Apply pending migrations; returns how many were applied
+async fn migrate(pool: &Pool) -> anyhow::Result<usize> {
+    let mut client = pool.get().await?;
+    let transaction = client.transaction().await?;
+    transaction.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK]).await?;
+    transaction.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+    ).await?;
+    let current: i32 = transaction
+        .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", &[])
+        .await?
+        .get(0);
+
+    let mut applied = 0;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as i32 + 1;
+        transaction.batch_execute(migration).await?;
+        transaction.execute("INSERT INTO schema_migrations (version) VALUES ($1)", &[&version]).await?;
+        applied += 1;
+    }
+    transaction.commit().await?;
+    Ok(applied)
+}
+
+impl Storage for PostgresStorage {
+    fn put(&self, collection: &str, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        let (pool, collection, key, value) = (self.pool.clone(), collection.to_string(), key.to_string(), value.clone());
+        self.run(async move {
+            pool.get().await?.execute(
+                "INSERT INTO storage_documents (collection, key, value) VALUES ($1, $2, $3)
+                 ON CONFLICT (collection, key) DO UPDATE SET value = EXCLUDED.value, updated_at = now()",
+                &[&collection, &key, &value],
+            ).await?;
+            Ok(())
+        })
+    }
+
+    fn get(&self, collection: &str, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let (pool, collection, key) = (self.pool.clone(), collection.to_string(), key.to_string());
+        self.run(async move {
+            let row = pool.get().await?.query_opt(
+                "SELECT value FROM storage_documents WHERE collection = $1 AND key = $2",
+                &[&collection, &key],
+            ).await?;
+            Ok(row.map(|row| row.get(0)))
+        })
+    }
+
+    fn list(&self, collection: &str) -> anyhow::Result<Vec<(String, serde_json::Value)>> {
+        let (pool, collection) = (self.pool.clone(), collection.to_string());
+        self.run(async move {
+            // Byte order, matching the file storage's key sort
+            let rows = pool.get().await?.query(
+                "SELECT key, value FROM storage_documents WHERE collection = $1 ORDER BY key COLLATE \"C\"",
+                &[&collection],
+            ).await?;
+            Ok(rows.into_iter().map(|row| (row.get(0), row.get(1))).collect())
+        })
+    }
+
+    fn delete(&self, collection: &str, key: &str) -> anyhow::Result<bool> {
+        let (pool, collection, key) = (self.pool.clone(), collection.to_string(), key.to_string());
+        self.run(async move {
+            let deleted = pool.get().await?.execute(
+                "DELETE FROM storage_documents WHERE collection = $1 AND key = $2",
+                &[&collection, &key],
+            ).await?;
+            Ok(deleted > 0)
+        })
+    }
+
+    fn update(&self, collection: &str, key: &str, f: &mut UpdateFn<'_>) -> anyhow::Result<Option<serde_json::Value>> {
+        self.run(async {
+            let mut client = self.pool.get().await?;
+            let transaction = client.transaction().await?;
+            // Row locks can't cover a document that doesn't exist yet
+            transaction
+                .execute("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", &[&format!("{}/{}", collection, key)])
+                .await?;
+            let current = transaction.query_opt(
+                "SELECT value FROM storage_documents WHERE collection = $1 AND key = $2",
+                &[&collection, &key],
+            ).await?.map(|row| row.get(0));
+            let next = f(current)?;
+            match &next {
+                Some(value) => transaction.execute(
+                    "INSERT INTO storage_documents (collection, key, value) VALUES ($1, $2, $3)
+                     ON CONFLICT (collection, key) DO UPDATE SET value = EXCLUDED.value, updated_at = now()",
+                    &[&collection, &key, value],
+                ).await?,
+                None => transaction.execute(
+                    "DELETE FROM storage_documents WHERE collection = $1 AND key = $2",
+                    &[&collection, &key],
+                ).await?,
+            };
+            transaction.commit().await?;
+            Ok(next)
+        })
+    }
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::storage::{list_typed, now_millis, put_typed, storage};
+use crate::timeseries::{append, Point, MAX_TAGS};
+
+// ============================================================================
+// Prometheus Scrape Source
+// ============================================================================
+
+/// Collection holding configured scrape targets, keyed by target name
+pub const SCRAPE_TARGETS_COLLECTION: &str = "scrape_targets";
+
+/// How often the scraper checks which targets are due
+pub const SCRAPE_TICK: Duration = Duration::from_secs(5);
+/// Default scrape interval of a target
+pub const DEFAULT_SCRAPE_INTERVAL_SECS: u64 = 30;
+/// Timeout of a single scrape request
+pub const SCRAPE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maps one Prometheus metric (optionally filtered by labels) to a named series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricMapping {
+    /// Prometheus metric name, e.g. `node_load1`
+    pub metric: String,
+    /// Target series in the timeseries store
+    pub series: String,
+    /// Only samples whose labels equal all of these are kept
+    #[serde(default)]
+    pub matchers: BTreeMap<String, String>,
+}
+
+/// A Prometheus endpoint to scrape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScrapeTarget {
+    pub name: String,
+    /// Full URL of the exposition endpoint, e.g. `http://node-exporter:9100/metrics`
+    pub url: String,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    pub metrics: Vec<MetricMapping>,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_SCRAPE_INTERVAL_SECS
+}
+
+/// One sample parsed from the text exposition format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub metric: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+    /// Milliseconds since the Unix epoch, when the exporter provides one
+    pub ts: Option<u64>,
+}
+
+/// Parse `name{label="value",...}` label sets, handling `\"`, `\\` and `\n` escapes
+fn parse_labels(input: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut labels = BTreeMap::new();
+    let mut rest = input.trim();
+    while !rest.is_empty() {
+        let (key, after_key) = rest.split_once('=').ok_or_else(|| format!("invalid labels '{}'", input))?;
+        let after_key = after_key.trim_start().strip_prefix('"')
+            .ok_or_else(|| format!("unquoted label value in '{}'", input))?;
+
+        let mut value = String::new();
+        let mut chars = after_key.char_indices();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                '"' => {
+                    end = Some(i);
+                    break;
+                }
+                c => value.push(c),
+            }
+        }
+        let end = end.ok_or_else(|| format!("unterminated label value in '{}'", input))?;
+        labels.insert(key.trim().trim_start_matches(',').trim().to_string(), value);
+        rest = after_key[end + 1..].trim_start().trim_start_matches(',').trim_start();
+    }
+    Ok(labels)
+}
+
+fn parse_value(raw: &str) -> Result<f64, String> {
+    match raw {
+        "+Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => raw.parse().map_err(|_| format!("invalid sample value '{}'", raw)),
+    }
+}
+
+/// Parse the Prometheus text exposition format (comments and `# TYPE`/`# HELP` lines are skipped)
+pub fn parse_exposition(body: &str) -> Result<Vec<Sample>, String> {
+    let mut samples = Vec::new();
+    for line in body.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (metric, labels, rest) = match line.find('{') {
+            Some(open) => {
+                let close = line.rfind('}').ok_or_else(|| format!("unterminated labels in '{}'", line))?;
+                (&line[..open], parse_labels(&line[open + 1..close])?, &line[close + 1..])
+            }
+            None => {
+                let (metric, rest) = line.split_once(char::is_whitespace)
+                    .ok_or_else(|| format!("missing value in '{}'", line))?;
+                (metric, BTreeMap::new(), rest)
+            }
+        };
+
+        let mut fields = rest.split_whitespace();
+        let value = parse_value(fields.next().ok_or_else(|| format!("missing value in '{}'", line))?)?;
+        let ts = fields.next()
+            .map(|ts| ts.parse::<i64>().map(|ts| ts.max(0) as u64))
+            .transpose()
+            .map_err(|_| format!("invalid timestamp in '{}'", line))?;
+
+        samples.push(Sample { metric: metric.trim().to_string(), labels, value, ts });
+    }
+    Ok(samples)
+}
+
+/// Group the samples selected by `mappings` into points per series
+///
+/// Non-finite values (`NaN`, `±Inf`) are dropped since the store rejects them.
+pub fn map_samples(samples: &[Sample], mappings: &[MetricMapping], now_ms: u64) -> HashMap<String, Vec<Point>> {
+    let mut series: HashMap<String, Vec<Point>> = HashMap::new();
+    for mapping in mappings {
+        let selected = samples.iter().filter(|sample| {
+            sample.metric == mapping.metric
+                && sample.value.is_finite()
+                && mapping.matchers.iter().all(|(k, v)| sample.labels.get(k) == Some(v))
+        });
+        for sample in selected {
+            series.entry(mapping.series.clone()).or_default().push(Point {
+                ts: sample.ts.unwrap_or(now_ms),
+                value: sample.value,
+                tags: sample.labels.iter().take(MAX_TAGS).map(|(k, v)| (k.clone(), v.clone())).collect(),
+            });
+        }
+    }
+    series
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(SCRAPE_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Scrape one target and append the mapped samples; returns the number of points written
+pub async fn scrape(target: &ScrapeTarget) -> anyhow::Result<usize> {
+    let body = client()
+        .get(&target.url)
+        .header("Accept", "text/plain;version=0.0.4")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let samples = parse_exposition(&body).map_err(|e| anyhow::anyhow!(e))?;
+
+    let mut written = 0;
+    for (name, points) in map_samples(&samples, &target.metrics, now_millis()) {
+        written += append(&name, points).map_err(|e| anyhow::anyhow!("{}: {}", name, e))?.accepted;
+    }
+    Ok(written)
+}
+
+/// All configured scrape targets
+pub fn list_targets() -> anyhow::Result<Vec<ScrapeTarget>> {
+    list_typed(SCRAPE_TARGETS_COLLECTION)
+}
+
+/// Add or replace a scrape target
+pub fn save_target(target: &ScrapeTarget) -> anyhow::Result<()> {
+    crate::timeseries::validate_name(&target.name).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    for mapping in &target.metrics {
+        crate::timeseries::validate_name(&mapping.series).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+    anyhow::ensure!(target.interval_secs > 0, "scrape interval must be positive");
+    put_typed(SCRAPE_TARGETS_COLLECTION, &target.name, target)
+}
+
+/// Remove a scrape target, returning whether it existed
+pub fn delete_target(name: &str) -> anyhow::Result<bool> {
+    storage().delete(SCRAPE_TARGETS_COLLECTION, name)
+}
+
+static SCRAPER: OnceLock<()> = OnceLock::new();
+
+/// Start the background scraper (once; requires a Tokio runtime)
+///
+/// Targets are re-read on every tick, so added or removed targets take
+/// effect without a restart.
+pub fn ensure_scraper_started() {
+    SCRAPER.get_or_init(|| {
+        tokio::spawn(async {
+            let mut last_scrape: HashMap<String, u64> = HashMap::new();
+            let mut interval = tokio::time::interval(SCRAPE_TICK);
+            loop {
+                interval.tick().await;
+                if !crate::leader::is_leader() {
+                    continue;
+                }
+                let targets = list_targets().unwrap_or_else(|e| {
+                    log_error!("[Prometheus] Failed to load scrape targets: {}", e);
+                    Vec::new()
+                });
+                let now = now_millis();
+                for target in targets {
+                    let due = last_scrape
+                        .get(&target.name)
+                        .is_none_or(|last| now.saturating_sub(*last) >= target.interval_secs * 1000);
+                    if !due {
+                        continue;
+                    }
+                    last_scrape.insert(target.name.clone(), now);
+                    if let Err(e) = scrape(&target).await {
+                        log_error!("[Prometheus] Scrape of {} ({}) failed: {}", target.name, target.url, e);
+                    }
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,200 @@
+use burn::backend::NdArray;
+use burn::module::{Module, Quantizer};
+use burn::record::CompactRecorder;
+use burn::tensor::backend::Backend;
+use burn::tensor::quantization::{Calibration, QuantScheme};
+use burn::tensor::{Distribution, ElementConversion, Tensor};
+use serde::Serialize;
+
+use crate::classifier::{tokenize, TextClassifier, TextClassifierConfig};
+use crate::dataset::LabeledExample;
+use crate::lstm::{Lstm, LstmConfig};
+use crate::registry::{find_model, ModelKind};
+use crate::seq2seq::tokens_to_tensor;
+
+// ============================================================================
+// Post-Training Quantization (int8 weights)
+// ============================================================================
+
+/// CPU backend used for quantized deployments
+pub type CpuBackend = NdArray;
+
+/// Size and accuracy impact of quantizing one model
+#[derive(Debug, Clone, Serialize)]
+pub struct QuantizationReport {
+    pub model: String,
+    pub params: usize,
+    /// On-disk size of the float weights record
+    pub float_bytes: u64,
+    /// On-disk size of the int8 weights record, scales included
+    pub int8_bytes: u64,
+    /// Accuracy before quantization, when labeled examples are available
+    pub baseline_accuracy: Option<f32>,
+    /// Accuracy after quantization, when labeled examples are available
+    pub quantized_accuracy: Option<f32>,
+    /// `quantized_accuracy - baseline_accuracy`
+    pub accuracy_delta: Option<f32>,
+    /// Mean absolute difference between fp32 and int8 outputs
+    pub mean_abs_output_diff: f32,
+    /// Where the quantized weights were written
+    pub output_path: String,
+}
+
+/// Where the int8 copy of the weights at `weights_path` is written (without the recorder extension)
+pub fn quantized_path(weights_path: &str) -> String {
+    format!("{}-int8", weights_path)
+}
+
+/// Size of a record written by `CompactRecorder` for `path`
+fn record_size(path: &str) -> anyhow::Result<u64> {
+    let file = format!("{}.mpk", path);
+    std::fs::metadata(&file)
+        .map(|metadata| metadata.len())
+        .map_err(|e| anyhow::anyhow!("failed to read {}: {}", file, e))
+}
+
+/// Load the int8 copy of the weights at `weights_path` into `model`, if one was written
+///
+/// Used by the model pool to build the `@cpu` entries, so CPU fallback serves
+/// the quantized weights.
+pub fn load_quantized<M: Module<CpuBackend>>(
+    model: M,
+    weights_path: &str,
+    device: &<CpuBackend as Backend>::Device,
+) -> Option<M> {
+    let path = quantized_path(weights_path);
+    // Quantize first so the module's tensors match the int8 record
+    match quantize_weights(model).load_file(path.clone(), &CompactRecorder::new(), device) {
+        Ok(model) => {
+            log_info!("[Quantization] Loaded int8 weights from {}", path);
+            Some(model)
+        }
+        Err(_) => None,
+    }
+}
+
+/// Quantize all float weights of a module to symmetric per-tensor int8
+pub fn quantize_weights<B: Backend, M: Module<B>>(model: M) -> M {
+    let mut quantizer = Quantizer {
+        calibration: Calibration::MinMax,
+        scheme: QuantScheme::default(),
+    };
+    model.quantize_weights(&mut quantizer)
+}
+
+fn mean_abs_diff<B: Backend, const D: usize>(a: Tensor<B, D>, b: Tensor<B, D>) -> f32 {
+    (a - b).abs().mean().into_scalar().elem::<f32>()
+}
+
+/// Fraction of examples whose predicted label matches
+fn classifier_accuracy<B: Backend>(
+    model: &TextClassifier<B>,
+    examples: &[(Vec<usize>, i64)],
+    device: &B::Device,
+) -> f32 {
+    if examples.is_empty() {
+        return 0.0;
+    }
+    let tokens: Vec<Vec<usize>> = examples.iter().map(|(tokens, _)| tokens.clone()).collect();
+    let predictions: Vec<i64> = model.forward(tokens_to_tensor(&tokens, device))
+        .argmax(1)
+        .into_data()
+        .convert::<i64>()
+        .to_vec()
+        .unwrap_or_default();
+    let correct = predictions.iter().zip(examples).filter(|(p, (_, c))| *p == c).count();
+    correct as f32 / examples.len() as f32
+}
+
+/// Quantize the registered text classifier and report the accuracy delta on `examples`
+pub fn quantize_classifier(examples: &[LabeledExample]) -> anyhow::Result<QuantizationReport> {
+    let entry = find_model("classifier").ok_or_else(|| anyhow::anyhow!("classifier is not registered"))?;
+    let device = Default::default();
+    let config = TextClassifierConfig::default();
+    let model = TextClassifier::<CpuBackend>::new(&config, &device)
+        .load_file(entry.weights_path, &CompactRecorder::new(), &device)
+        .map_err(|e| anyhow::anyhow!("failed to load {}: {:?}", entry.weights_path, e))?;
+    let quantized = quantize_weights(model.clone());
+
+    let encoded: Vec<(Vec<usize>, i64)> = examples
+        .iter()
+        .filter_map(|example| {
+            let class = config.labels.iter().position(|l| *l == example.label)?;
+            Some((tokenize(&example.text, config.vocab_size), class as i64))
+        })
+        .collect();
+
+    let (baseline_accuracy, quantized_accuracy, mean_abs_output_diff) = if encoded.is_empty() {
+        (None, None, 0.0)
+    } else {
+        let tokens: Vec<Vec<usize>> = encoded.iter().map(|(tokens, _)| tokens.clone()).collect();
+        let diff = mean_abs_diff(
+            model.forward(tokens_to_tensor(&tokens, &device)),
+            quantized.forward(tokens_to_tensor(&tokens, &device)),
+        );
+        (
+            Some(classifier_accuracy(&model, &encoded, &device)),
+            Some(classifier_accuracy(&quantized, &encoded, &device)),
+            diff,
+        )
+    };
+
+    finish_report(entry.name, quantized, entry.weights_path, baseline_accuracy, quantized_accuracy, mean_abs_output_diff)
+}
+
+/// Quantize the registered LSTM (forecaster) and report output drift on random inputs
+pub fn quantize_lstm() -> anyhow::Result<QuantizationReport> {
+    let entry = find_model("lstm").ok_or_else(|| anyhow::anyhow!("lstm is not registered"))?;
+    let device = Default::default();
+    let config = LstmConfig::default();
+    let model = Lstm::<CpuBackend>::new(config.clone(), &device)
+        .load_file(entry.weights_path, &CompactRecorder::new(), &device)
+        .map_err(|e| anyhow::anyhow!("failed to load {}: {:?}", entry.weights_path, e))?;
+    let quantized = quantize_weights(model.clone());
+
+    let input = Tensor::<CpuBackend, 3>::random([4, 16, config.input_size], Distribution::Default, &device);
+    let (expected, _) = model.forward(input.clone(), None);
+    let (actual, _) = quantized.forward(input, None);
+
+    finish_report(entry.name, quantized, entry.weights_path, None, None, mean_abs_diff(expected, actual))
+}
+
+/// Quantize a registered model by name
+pub fn quantize_registered(name: &str, examples: &[LabeledExample]) -> anyhow::Result<QuantizationReport> {
+    match find_model(name).map(|entry| entry.kind) {
+        Some(ModelKind::TextClassifier) => quantize_classifier(examples),
+        Some(ModelKind::Lstm) => quantize_lstm(),
+        Some(kind) => anyhow::bail!("quantization is not supported for {:?} models", kind),
+        None => anyhow::bail!("unknown model {}", name),
+    }
+}
+
+fn finish_report<M: Module<CpuBackend>>(
+    name: &str,
+    quantized: M,
+    weights_path: &str,
+    baseline_accuracy: Option<f32>,
+    quantized_accuracy: Option<f32>,
+    mean_abs_output_diff: f32,
+) -> anyhow::Result<QuantizationReport> {
+    let params = quantized.num_params();
+    let output_path = quantized_path(weights_path);
+    quantized.save_file(output_path.clone(), &CompactRecorder::new())
+        .map_err(|e| anyhow::anyhow!("failed to save {}: {:?}", output_path, e))?;
+    // The CPU copy is reloaded from the new int8 record on next use
+    crate::model_pool::evict(&format!("{}@cpu", name));
+
+    let report = QuantizationReport {
+        model: name.to_string(),
+        params,
+        float_bytes: record_size(weights_path)?,
+        int8_bytes: record_size(&output_path)?,
+        baseline_accuracy,
+        quantized_accuracy,
+        accuracy_delta: baseline_accuracy.zip(quantized_accuracy).map(|(before, after)| after - before),
+        mean_abs_output_diff,
+        output_path,
+    };
+    log_info!("[Quantization] {:?}", report);
+    Ok(report)
+}
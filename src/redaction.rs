@@ -0,0 +1,351 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::connections::{LlmFuture, LlmProvider, Persona};
+use crate::extraction::{Entity, Extraction};
+
+// ============================================================================
+// PII Redaction
+// ============================================================================
+//
+// Personal data and credentials are replaced by placeholders such as
+// `[EMAIL]` before payloads are stored or sent to an LLM:
+//
+// - agents store redacted data in their state, events and message log, and
+//   redacted entities and keywords
+// - job previews and recorded generations (prompt and output) are redacted
+// - prompts leave through `RedactingProvider`, which `agent_provider` puts in
+//   front of the shared provider, unless the model is listed in
+//   `PATTERN_CLOCK_REDACT_LOCAL_MODELS` (models known to run on the host, so
+//   the data never leaves it)
+//
+// Built-in patterns cover email addresses, card numbers (Luhn-checked),
+// phone numbers (not dotted numbers such as IPv4 addresses), API keys and
+// tokens, bearer tokens and `password=...`-style assignments. The config file
+// adds patterns of its own (`redaction_patterns`), applied after the
+// built-in ones. `PATTERN_CLOCK_REDACTION=false` turns redaction off.
+//
+// Redaction is pattern matching, not detection: it catches the common
+// shapes and misses anything that doesn't look like them.
+
+/// A named pattern and what replaces its matches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionPattern {
+    pub name: String,
+    /// Regular expression (`regex` crate syntax)
+    pub pattern: String,
+    /// Replacement, may refer to groups as `$1`; default `[NAME]`
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+impl RedactionPattern {
+    fn new(name: &str, pattern: &str, replacement: Option<&str>) -> Self {
+        RedactionPattern {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            replacement: replacement.map(str::to_string),
+        }
+    }
+
+    /// Check the pattern before using it
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name must not be empty".to_string());
+        }
+        let regex = Regex::new(&self.pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+        if regex.is_match("") {
+            return Err("pattern must not match empty text".to_string());
+        }
+        Ok(())
+    }
+
+    fn replacement(&self) -> String {
+        self.replacement.clone().unwrap_or_else(|| format!("[{}]", self.name.to_uppercase()))
+    }
+}
+
+/// Patterns applied before the configured ones
+pub fn builtin_patterns() -> Vec<RedactionPattern> {
+    vec![
+        RedactionPattern::new("email", r"[\w.+-]+@[\w-]+\.[\w.-]*\w", None),
+        // Keys with a recognizable prefix: OpenAI/Anthropic, AWS, GitHub, Slack, Google
+        RedactionPattern::new(
+            "key",
+            r"\b(?:sk-[A-Za-z0-9_-]{16,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})",
+            None,
+        ),
+        RedactionPattern::new("token", r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{8,}=*", Some("Bearer [TOKEN]")),
+        // `api_key=...`, `password: ...`; the name stays readable
+        RedactionPattern::new(
+            "secret",
+            r#"(?i)\b((?:api[_-]?key|access[_-]?key|secret|token|passw(?:or)?d|pwd)\s*[:=]\s*)["']?[^\s"',;]{4,}["']?"#,
+            Some("${1}[SECRET]"),
+        ),
+        // `4111 1111 1111 1111`, `4111-1111-1111-1111` or 15-16 bare digits;
+        // only numbers passing the Luhn check (see `keep_match`)
+        RedactionPattern::new("card", r"\b(?:\d{4}[ -]){3}\d{3,4}\b|\b\d{15,16}\b", None),
+        // `+44 20 7946 0958`, `(555) 123-4567`, `555-123-4567`, `555.123.4567`;
+        // bare digit runs (ids, timestamps) and dotted numbers such as IPv4
+        // addresses are left alone (see `keep_match`)
+        RedactionPattern::new(
+            "phone",
+            r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)[\s.-]?|\b\d{2,4}[\s.-])\d{3,4}[\s.-]\d{3,4}\b|\+\d{8,15}\b",
+            None,
+        ),
+    ]
+}
+
+/// Whether `digits` (separators ignored) pass the Luhn checksum of card numbers
+fn luhn_valid(digits: &str) -> bool {
+    let digits: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    !digits.is_empty() && sum % 10 == 0
+}
+
+/// Whether the match at `range` is part of a longer dotted number, such as an IPv4 address
+fn in_dotted_number(text: &str, range: Range<usize>) -> bool {
+    let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+    let mut before = text[..range.start].chars().rev();
+    let mut after = text[range.end..].chars();
+    let joined_before = match before.next() {
+        Some('.') => is_digit(before.next()),
+        c => is_digit(c),
+    };
+    let joined_after = match after.next() {
+        Some('.') => is_digit(after.next()),
+        c => is_digit(c),
+    };
+    joined_before || joined_after
+}
+
+/// Whether a built-in pattern's match only looks like personal data
+fn keep_match(name: &str, text: &str, range: Range<usize>) -> bool {
+    match name {
+        "card" => !luhn_valid(&text[range]),
+        "phone" => in_dotted_number(text, range),
+        _ => false,
+    }
+}
+
+struct Rule {
+    name: String,
+    regex: Regex,
+    replacement: String,
+    /// Built-in patterns may leave some matches alone (`keep_match`)
+    builtin: bool,
+}
+
+struct Compiled {
+    /// The configured patterns this was compiled from
+    configured: Vec<RedactionPattern>,
+    rules: Vec<Rule>,
+}
+
+fn compile(configured: &[RedactionPattern]) -> Compiled {
+    let builtin = builtin_patterns();
+    let rules = builtin
+        .iter()
+        .map(|pattern| (pattern, true))
+        .chain(configured.iter().map(|pattern| (pattern, false)))
+        .filter_map(|(pattern, builtin)| match Regex::new(&pattern.pattern) {
+            Ok(regex) => Some(Rule { name: pattern.name.clone(), regex, replacement: pattern.replacement(), builtin }),
+            // Rejected by config validation; only reached for patterns set in code
+            Err(e) => {
+                log_warn!("[Redaction] Skipping pattern {:?}: {}", pattern.name, e);
+                None
+            }
+        })
+        .collect();
+    Compiled { configured: configured.to_vec(), rules }
+}
+
+/// Compiled patterns, rebuilt when the configured ones change
+fn compiled() -> Arc<Compiled> {
+    static COMPILED: OnceLock<RwLock<Arc<Compiled>>> = OnceLock::new();
+    let config = crate::config::config();
+    let slot = COMPILED.get_or_init(|| RwLock::new(Arc::new(compile(&config.redaction_patterns))));
+    let current = slot.read().unwrap().clone();
+    if current.configured == config.redaction_patterns {
+        return current;
+    }
+    let fresh = Arc::new(compile(&config.redaction_patterns));
+    *slot.write().unwrap() = fresh.clone();
+    fresh
+}
+
+/// Whether redaction is on
+pub fn enabled() -> bool {
+    crate::config::config().redaction
+}
+
+/// `text` with every match replaced, and how many matches each pattern had
+pub fn redact_counted(text: &str) -> (String, BTreeMap<String, usize>) {
+    let mut counts = BTreeMap::new();
+    if !enabled() {
+        return (text.to_string(), counts);
+    }
+    let mut text = text.to_string();
+    for rule in &compiled().rules {
+        let mut matches = 0;
+        let replaced = rule.regex.replace_all(&text, |captures: &regex::Captures| {
+            let found = captures.get(0).expect("group 0 is the whole match");
+            if rule.builtin && keep_match(&rule.name, &text, found.range()) {
+                return found.as_str().to_string();
+            }
+            matches += 1;
+            let mut replacement = String::new();
+            captures.expand(&rule.replacement, &mut replacement);
+            replacement
+        });
+        if matches > 0 {
+            text = replaced.into_owned();
+            *counts.entry(rule.name.clone()).or_default() += matches;
+        }
+    }
+    (text, counts)
+}
+
+/// `text` with personal data and credentials replaced
+pub fn redact(text: &str) -> String {
+    redact_counted(text).0
+}
+
+/// `extraction` with redacted entities and keywords; entities that were
+/// nothing but personal data keep their placeholder
+pub fn redact_extraction(extraction: &Extraction) -> Extraction {
+    if !enabled() {
+        return extraction.clone();
+    }
+    Extraction {
+        entities: extraction
+            .entities
+            .iter()
+            .map(|entity| Entity { kind: entity.kind.clone(), text: redact(&entity.text) })
+            .collect(),
+        keywords: extraction.keywords.iter().map(|keyword| redact(keyword)).collect(),
+    }
+}
+
+/// Whether prompts for `model` may contain personal data
+pub fn is_local_model(model: &str) -> bool {
+    crate::config::config().redaction_local_models.iter().any(|local| local == model)
+}
+
+/// A provider that redacts prompts for models not known to run locally
+pub struct RedactingProvider {
+    inner: Arc<dyn LlmProvider>,
+}
+
+impl RedactingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>) -> Self {
+        Self { inner }
+    }
+
+    fn prompt_for(&self, model: &str, prompt: &str) -> String {
+        if is_local_model(model) {
+            prompt.to_string()
+        } else {
+            redact(prompt)
+        }
+    }
+
+    /// The persona and prompt to send; the system prompt is sent too, so it is redacted like the prompt
+    fn persona_for(&self, persona: &Persona, prompt: &str) -> (Persona, String) {
+        let model = persona.model.as_deref().unwrap_or_else(|| self.inner.model());
+        let persona = Persona { system_prompt: self.prompt_for(model, &persona.system_prompt), ..persona.clone() };
+        (persona, self.prompt_for(model, prompt))
+    }
+}
+
+impl LlmProvider for RedactingProvider {
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(async move {
+            let prompt = self.prompt_for(self.inner.model(), prompt);
+            self.inner.generate(&prompt).await
+        })
+    }
+
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(async move {
+            let prompt = self.prompt_for(self.inner.model(), prompt);
+            self.inner.generate_json(&prompt).await
+        })
+    }
+
+    fn generate_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, String> {
+        Box::pin(async move {
+            let (persona, prompt) = self.persona_for(persona, prompt);
+            self.inner.generate_as(&persona, &prompt).await
+        })
+    }
+
+    fn generate_json_as<'a>(&'a self, persona: &'a Persona, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        Box::pin(async move {
+            let (persona, prompt) = self.persona_for(persona, prompt);
+            self.inner.generate_json_as(&persona, &prompt).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_emails_cards_and_tokens() {
+        assert_eq!(redact("mail jane.doe+ops@example.co.uk now"), "mail [EMAIL] now");
+        assert_eq!(redact("paid with 4111 1111 1111 1111."), "paid with [CARD].");
+        assert_eq!(redact("card 4111111111111111"), "card [CARD]");
+        assert_eq!(redact("Authorization: Bearer abc.def-123456"), "Authorization: Bearer [TOKEN]");
+        assert_eq!(redact("key sk-abcdefghijklmnop1234"), "key [KEY]");
+        assert_eq!(redact("password=hunter22"), "password=[SECRET]");
+    }
+
+    #[test]
+    fn card_numbers_must_pass_the_luhn_check() {
+        assert!(luhn_valid("4111 1111 1111 1111"));
+        assert!(!luhn_valid("4111111111111112"));
+        assert_eq!(redact("order 4111111111111112"), "order 4111111111111112");
+    }
+
+    #[test]
+    fn phone_numbers_are_redacted_but_ip_addresses_are_not() {
+        assert_eq!(redact("call 555-123-4567 or (555) 123-4567"), "call [PHONE] or [PHONE]");
+        assert_eq!(redact("call 555.123.4567."), "call [PHONE].");
+        assert_eq!(redact("from 192.168.100.200 via 10.0.0.1"), "from 192.168.100.200 via 10.0.0.1");
+        assert_eq!(redact("host 1234.567.8901.2"), "host 1234.567.8901.2");
+    }
+
+    #[test]
+    fn text_without_personal_data_is_unchanged() {
+        for text in ["cpu at 93% on web-1 since 1760000000000", "order 12345 shipped", "v1.2.3 released", ""] {
+            let (redacted, counts) = redact_counted(text);
+            assert_eq!(redacted, text);
+            assert!(counts.is_empty(), "{:?} matched {:?}", text, counts);
+        }
+    }
+
+    #[test]
+    fn counts_matches_per_pattern() {
+        let (_, counts) = redact_counted("a@example.com b@example.com 555-123-4567 from 192.168.100.200");
+        assert_eq!(counts.get("email"), Some(&2));
+        assert_eq!(counts.get("phone"), Some(&1));
+    }
+}
@@ -0,0 +1,133 @@
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+use crate::config::config;
+use crate::events::{deliver_remote, set_forwarder, BroadcastEvent, Event};
+
+// ============================================================================
+// Redis Event Transport
+// ============================================================================
+//
+// With `PATTERN_CLOCK_REDIS_URL` set (feature "redis"), every event published
+// here is also published on the Redis channel `CHANNEL`, and events other
+// instances publish there are delivered to local subscribers. A web client
+// following `/api/events/stream` on one instance then sees the events of
+// all of them. Each message names its origin, so an instance skips its own.
+//
+// Delivery is best effort, like the local bus: at most `OUTGOING_CAPACITY`
+// events wait to be published, events published while Redis is unreachable
+// or the queue is full are dropped (and counted in the log), and both
+// directions reconnect after `RECONNECT_DELAY`.
+
+/// Redis pub/sub channel carrying events
+pub const CHANNEL: &str = "pattern-clock:events";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Events waiting to be published to Redis
+const OUTGOING_CAPACITY: usize = 1024;
+
+/// Events dropped since the last report
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// A message on the channel (written by the forwarder as JSON directly)
+#[derive(Deserialize)]
+struct Envelope {
+    origin: String,
+    event: Event,
+}
+
+/// Identifies this process on the channel (`instance_id`, which may repeat across restarts, plus start time)
+fn origin() -> &'static str {
+    static ORIGIN: OnceLock<String> = OnceLock::new();
+    ORIGIN.get_or_init(|| format!("{}@{}", crate::leader::instance_id(), crate::storage::now_millis()))
+}
+
+/// Connect the event bus to Redis (once; requires a Tokio runtime; no-op without a Redis URL)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        let Some(url) = config().redis_url.clone() else {
+            return;
+        };
+        let client = match redis::Client::open(url.expose()) {
+            Ok(client) => client,
+            Err(e) => {
+                log_error!("[Events] Invalid Redis URL: {}", e);
+                return;
+            }
+        };
+
+        let (sender, mut outgoing) = tokio::sync::mpsc::channel::<String>(OUTGOING_CAPACITY);
+        // An `Envelope` around the event's shared JSON, so it isn't serialized again
+        let origin_json = serde_json::to_string(origin()).unwrap_or_default();
+        let forwarder = move |event: &BroadcastEvent| {
+            if sender.try_send(format!(r#"{{"origin":{},"event":{}}}"#, origin_json, event.json())).is_err() {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        };
+        if set_forwarder(Box::new(forwarder)).is_err() {
+            log_warn!("[Events] An event forwarder is already installed, not using Redis");
+            return;
+        }
+
+        let publisher = client.clone();
+        tokio::spawn(async move {
+            loop {
+                match publisher.get_multiplexed_async_connection().await {
+                    Ok(mut connection) => {
+                        while let Some(message) = outgoing.recv().await {
+                            let published: redis::RedisResult<()> = redis::cmd("PUBLISH")
+                                .arg(CHANNEL)
+                                .arg(message)
+                                .query_async(&mut connection)
+                                .await;
+                            if let Err(e) = published {
+                                log_warn!("[Events] Publishing to Redis failed: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => log_warn!("[Events] Redis connection failed: {}", e),
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                // Drop what piled up while disconnected
+                while outgoing.try_recv().is_ok() {
+                    DROPPED.fetch_add(1, Ordering::Relaxed);
+                }
+                let dropped = DROPPED.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    log_warn!("[Events] Dropped {} events while Redis was unreachable", dropped);
+                }
+            }
+        });
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = receive(&client).await {
+                    log_warn!("[Events] Redis subscription failed: {}", e);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+        log_info!("[Events] Sharing events over Redis channel {}", CHANNEL);
+    });
+}
+
+/// Deliver events from other instances until the subscription ends
+async fn receive(client: &redis::Client) -> redis::RedisResult<()> {
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(CHANNEL).await?;
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let text: String = message.get_payload()?;
+        match serde_json::from_str::<Envelope>(&text) {
+            Ok(envelope) if envelope.origin == origin() => {}
+            Ok(Envelope { origin, event }) => deliver_remote(Event { origin: Some(origin), ..event }),
+            Err(e) => log_warn!("[Events] Ignoring malformed Redis message: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+
+// ============================================================================
+// Model Registry
+// ============================================================================
+
+/// Kinds of models available in the ML subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Stacked LSTM over feature sequences
+    Lstm,
+    /// Embedding + LSTM + linear head text classifier
+    TextClassifier,
+    /// LSTM encoder-decoder over event token sequences
+    Seq2Seq,
+}
+
+/// Registry entry describing one model
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEntry {
+    /// Unique model name
+    pub name: &'static str,
+    pub kind: ModelKind,
+    pub description: &'static str,
+    /// Where trained weights are saved/loaded (without recorder extension)
+    pub weights_path: &'static str,
+}
+
+/// All models known to the application
+pub fn registered_models() -> Vec<ModelEntry> {
+    vec![
+        ModelEntry {
+            name: "lstm",
+            kind: ModelKind::Lstm,
+            description: "Multi-layer LSTM over feature sequences",
+            weights_path: "models/lstm",
+        },
+        ModelEntry {
+            name: "classifier",
+            kind: ModelKind::TextClassifier,
+            description: "Text classifier returning label and confidence",
+            weights_path: "models/classifier",
+        },
+        ModelEntry {
+            name: "seq2seq",
+            kind: ModelKind::Seq2Seq,
+            description: "Encoder-decoder predicting the next sequence of agent actions",
+            weights_path: "models/seq2seq",
+        },
+    ]
+}
+
+/// Look up a registry entry by name
+pub fn find_model(name: &str) -> Option<ModelEntry> {
+    registered_models().into_iter().find(|entry| entry.name == name)
+}
+
+/// Which LSTM implementation serves inference (`PATTERN_CLOCK_LSTM_IMPL`)
+///
+/// Weights are always stored in the format of the hand-rolled `lstm::Lstm`;
+/// `Burn` runs them through `burn::nn::Lstm` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LstmImpl {
+    /// The hand-rolled `LstmCell`
+    Custom,
+    /// `burn::nn::Lstm`, one module per layer
+    Burn,
+    /// Whichever is faster on a probe sequence at load, if their outputs agree
+    Auto,
+}
+
+impl LstmImpl {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "custom" => Some(LstmImpl::Custom),
+            "burn" => Some(LstmImpl::Burn),
+            "auto" => Some(LstmImpl::Auto),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LstmImpl::Custom => "custom",
+            LstmImpl::Burn => "burn",
+            LstmImpl::Auto => "auto",
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::config::config;
+
+// ============================================================================
+// Request Limits and Input Sanitation
+// ============================================================================
+//
+// Request bodies are checked before any handler sees them (`http_layer`):
+//
+//   - size: at most `max_body_size` (default 1 MiB), or the limit of the most
+//     specific `body_limits` pattern matching the path (uploads, documents,
+//     binary payloads and line-protocol writes get more by default). Counted
+//     after request decompression, so a small gzip body can't expand past
+//     the limit. Too large: 413.
+//   - encoding: text bodies (no content type, `text/*`, JSON, form data)
+//     must be UTF-8. Otherwise: 422.
+//   - control characters: stripped from text bodies and from every string
+//     (and key) of JSON bodies; newline, carriage return and tab are kept.
+//     Query strings with percent-encoded control characters are rejected
+//     (422).
+//
+// Rejections are JSON, e.g.
+// `{"error": "payload_too_large", "message": "...", "limit": 1048576}`.
+//
+// Agents strip control characters from the data they process as well, which
+// covers input that doesn't come over HTTP (MCP, connectors, the Rust API).
+
+/// Body limit of paths without a more specific one
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Endpoints that take files or batches, and their default limits
+pub fn default_body_limits() -> BTreeMap<String, usize> {
+    const MIB: usize = 1024 * 1024;
+    [
+        ("/api/uploads", 64 * MIB),
+        ("/api/documents", 32 * MIB),
+        ("/api/agents/*/payload", 16 * MIB),
+        ("/api/bundles/import", 16 * MIB),
+        ("/api/v2/write", 16 * MIB),
+        ("/write", 16 * MIB),
+    ]
+    .into_iter()
+    .map(|(pattern, limit)| (pattern.to_string(), limit))
+    .collect()
+}
+
+/// A size in bytes: `65536`, `512k`, `16m` or `1g` (binary multiples)
+pub fn parse_size(value: &str) -> Option<usize> {
+    let value = value.trim().to_ascii_lowercase();
+    let (digits, multiplier) = match value.chars().last()? {
+        'k' => (&value[..value.len() - 1], 1024),
+        'm' => (&value[..value.len() - 1], 1024 * 1024),
+        'g' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value.as_str(), 1),
+    };
+    let size = digits.parse::<usize>().ok()?.checked_mul(multiplier)?;
+    (size > 0).then_some(size)
+}
+
+/// Whether `pattern` can be a body limit pattern: a path, `*` for any one segment
+pub fn is_path_pattern(pattern: &str) -> bool {
+    pattern.starts_with('/') && !pattern.contains(char::is_whitespace)
+}
+
+/// Limits by path pattern, as in `/api/uploads=128m,/api/agents/*/payload=32m`
+pub fn parse_body_limits(value: &str) -> Option<BTreeMap<String, usize>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (pattern, size) = item.split_once('=')?;
+            let pattern = pattern.trim();
+            is_path_pattern(pattern).then_some(())?;
+            Some((pattern.to_string(), parse_size(size)?))
+        })
+        .collect()
+}
+
+/// Segments of `pattern` if it matches the start of `path`
+fn matched_segments(pattern: &str, path: &str) -> Option<usize> {
+    let pattern: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    if pattern.len() > path.len() {
+        return None;
+    }
+    pattern
+        .iter()
+        .zip(&path)
+        .all(|(expected, actual)| *expected == "*" || expected == actual)
+        .then_some(pattern.len())
+}
+
+/// Largest body accepted for `path`
+pub fn body_limit(path: &str) -> usize {
+    let config = config();
+    limit_for(&config.body_limits, config.max_body_size, path)
+}
+
+/// Limit of the most specific pattern in `limits` matching `path`, else `default`
+fn limit_for(limits: &BTreeMap<String, usize>, default: usize, path: &str) -> usize {
+    limits
+        .iter()
+        .filter_map(|(pattern, limit)| Some((matched_segments(pattern, path)?, *limit)))
+        .max_by_key(|(segments, _)| *segments)
+        .map_or(default, |(_, limit)| limit)
+}
+
+/// Whether a body of `length` bytes is over `limit`; one of exactly `limit` is accepted
+fn exceeds(length: u64, limit: usize) -> bool {
+    length > limit as u64
+}
+
+fn is_stripped(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\r' | '\t')
+}
+
+/// `text` without control characters other than newline, carriage return and tab
+pub fn strip_control_chars(text: &str) -> String {
+    text.chars().filter(|c| !is_stripped(*c)).collect()
+}
+
+/// Strip control characters from every string and key; returns whether anything changed
+pub fn sanitize_json(value: &mut Value) -> bool {
+    match value {
+        Value::String(text) if text.chars().any(is_stripped) => {
+            *text = strip_control_chars(text);
+            true
+        }
+        Value::Array(items) => items.iter_mut().fold(false, |changed, item| sanitize_json(item) | changed),
+        Value::Object(object) if object.keys().any(|key| key.chars().any(is_stripped)) => {
+            *object = std::mem::take(object)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    sanitize_json(&mut value);
+                    (strip_control_chars(&key), value)
+                })
+                .collect();
+            true
+        }
+        Value::Object(object) => object.values_mut().fold(false, |changed, value| sanitize_json(value) | changed),
+        _ => false,
+    }
+}
+
+/// Why a request was turned away, sent as its JSON body
+#[derive(Debug, Clone, Serialize)]
+pub struct Rejection {
+    #[serde(skip)]
+    pub status: u16,
+    /// `payload_too_large`, `invalid_utf8`, `invalid_query` or `unreadable_body`
+    pub error: &'static str,
+    pub message: String,
+    /// Body limit of the endpoint, in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Byte offset of the first invalid byte
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+}
+
+impl Rejection {
+    pub fn too_large(limit: usize) -> Self {
+        Rejection {
+            status: 413,
+            error: "payload_too_large",
+            message: format!("request body exceeds the limit of {} bytes", limit),
+            limit: Some(limit),
+            offset: None,
+        }
+    }
+
+    fn unprocessable(error: &'static str, message: String, offset: Option<usize>) -> Self {
+        Rejection { status: 422, error, message, limit: None, offset }
+    }
+}
+
+fn mime(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+fn is_json(mime: &str) -> bool {
+    mime == "application/json" || mime.ends_with("+json")
+}
+
+fn is_text(mime: &str) -> bool {
+    mime.is_empty()
+        || mime.starts_with("text/")
+        || is_json(mime)
+        || mime == "application/x-ndjson"
+        || mime == "application/x-www-form-urlencoded"
+}
+
+/// Validate and sanitize a body of `content_type`; binary bodies pass unchanged
+pub fn check_body(content_type: &str, body: Vec<u8>) -> Result<Vec<u8>, Rejection> {
+    let mime = mime(content_type);
+    if !is_text(&mime) {
+        return Ok(body);
+    }
+    let text = std::str::from_utf8(&body).map_err(|e| {
+        Rejection::unprocessable(
+            "invalid_utf8",
+            format!("request body is not valid UTF-8 (byte {})", e.valid_up_to()),
+            Some(e.valid_up_to()),
+        )
+    })?;
+    if is_json(&mime) {
+        // Malformed JSON is left for the handler to reject
+        return Ok(match serde_json::from_str::<Value>(text) {
+            Ok(mut value) => {
+                if sanitize_json(&mut value) {
+                    serde_json::to_vec(&value).unwrap_or(body)
+                } else {
+                    body
+                }
+            }
+            Err(_) => body,
+        });
+    }
+    if text.chars().any(is_stripped) {
+        return Ok(strip_control_chars(text).into_bytes());
+    }
+    Ok(body)
+}
+
+/// Reject query strings with percent-encoded control characters
+pub fn check_query(query: &str) -> Result<(), Rejection> {
+    let bytes = query.as_bytes();
+    for (offset, window) in bytes.windows(3).enumerate() {
+        if window[0] != b'%' {
+            continue;
+        }
+        let Some(byte) = std::str::from_utf8(&window[1..]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) else {
+            continue;
+        };
+        if byte < 0x20 || byte == 0x7f {
+            return Err(Rejection::unprocessable(
+                "invalid_query",
+                format!("query string contains the control character %{:02X}", byte),
+                Some(offset),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+impl axum::response::IntoResponse for Rejection {
+    fn into_response(self) -> axum::response::Response {
+        let status = axum::http::StatusCode::from_u16(self.status).unwrap_or(axum::http::StatusCode::BAD_REQUEST);
+        (status, axum::Json(self)).into_response()
+    }
+}
+
+/// Body limits, UTF-8 validation and control-character stripping for the HTTP server
+#[cfg(feature = "server")]
+pub fn http_layer(router: axum::Router) -> axum::Router {
+    router.layer(axum::middleware::from_fn(guard))
+}
+
+#[cfg(feature = "server")]
+async fn guard(request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+    use axum::http::{header, Method};
+    use axum::response::IntoResponse;
+    use tokio_stream::StreamExt;
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let reject = |rejection: Rejection| {
+        log_warn!("[Limits] Rejected {} {}: {}", method, path, rejection.message);
+        rejection.into_response()
+    };
+    if let Err(rejection) = request.uri().query().map_or(Ok(()), check_query) {
+        return reject(rejection);
+    }
+    if matches!(method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let limit = body_limit(&path);
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| exceeds(length, limit)) {
+        return reject(Rejection::too_large(limit));
+    }
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let (mut parts, body) = request.into_parts();
+    let mut chunks = body.into_data_stream();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                return reject(Rejection {
+                    status: 400,
+                    error: "unreadable_body",
+                    message: format!("failed to read the request body: {}", e),
+                    limit: None,
+                    offset: None,
+                })
+            }
+        };
+        if exceeds((bytes.len() + chunk.len()) as u64, limit) {
+            return reject(Rejection::too_large(limit));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let bytes = match check_body(&content_type, bytes) {
+        Ok(bytes) => bytes,
+        Err(rejection) => return reject(rejection),
+    };
+    parts.headers.insert(header::CONTENT_LENGTH, bytes.len().into());
+    next.run(axum::extract::Request::from_parts(parts, axum::body::Body::from(bytes))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_most_specific_pattern_sets_the_limit() {
+        let mut limits = default_body_limits();
+        limits.insert("/api/agents/*/payload/large".to_string(), 100);
+        let limit = |path| limit_for(&limits, DEFAULT_MAX_BODY_SIZE, path);
+
+        assert_eq!(limit("/api/uploads"), 64 * 1024 * 1024);
+        assert_eq!(limit("/api/uploads/photo.png"), 64 * 1024 * 1024);
+        assert_eq!(limit("/api/agents/7/payload"), 16 * 1024 * 1024);
+        assert_eq!(limit("/api/agents/7/payload/large"), 100);
+        assert_eq!(limit("/api/agents/7/status"), DEFAULT_MAX_BODY_SIZE);
+        assert_eq!(limit("/api/uploadsx"), DEFAULT_MAX_BODY_SIZE);
+        assert_eq!(limit("/write"), 16 * 1024 * 1024);
+    }
+
+    #[test]
+    fn bodies_at_the_limit_pass_and_over_it_are_rejected() {
+        assert!(!exceeds(1024, 1024));
+        assert!(exceeds(1025, 1024));
+
+        let rejection = Rejection::too_large(1024);
+        assert_eq!(rejection.status, 413);
+        assert_eq!(rejection.limit, Some(1024));
+        assert_eq!(
+            serde_json::to_value(&rejection).unwrap()["error"],
+            serde_json::json!("payload_too_large")
+        );
+    }
+
+    #[test]
+    fn sizes_and_limit_lists_parse() {
+        assert_eq!(parse_size("65536"), Some(65536));
+        assert_eq!(parse_size("512K"), Some(512 * 1024));
+        assert_eq!(parse_size("16m"), Some(16 * 1024 * 1024));
+        assert_eq!(parse_size("0"), None);
+        assert_eq!(parse_size("lots"), None);
+
+        let limits = parse_body_limits("/api/uploads=128m, /api/agents/*/payload=32m").unwrap();
+        assert_eq!(limits["/api/uploads"], 128 * 1024 * 1024);
+        assert_eq!(limits["/api/agents/*/payload"], 32 * 1024 * 1024);
+        assert!(parse_body_limits("api/uploads=1m").is_none());
+        assert!(parse_body_limits("/api/uploads").is_none());
+    }
+
+    #[test]
+    fn control_characters_are_stripped_but_whitespace_is_kept() {
+        assert_eq!(strip_control_chars("a\u{0}b\u{7}c\u{1b}[0m\u{7f}"), "abc[0m");
+        assert_eq!(strip_control_chars("line\r\n\tindented"), "line\r\n\tindented");
+        assert_eq!(strip_control_chars("caf\u{e9} \u{1F600}"), "caf\u{e9} \u{1F600}");
+
+        let mut value = serde_json::json!({"na\u{0}me": ["ok", "b\u{8}ad"], "n": 1});
+        assert!(sanitize_json(&mut value));
+        assert_eq!(value, serde_json::json!({"name": ["ok", "bad"], "n": 1}));
+        assert!(!sanitize_json(&mut value));
+    }
+
+    #[test]
+    fn text_bodies_are_checked_and_binary_ones_pass() {
+        assert_eq!(check_body("text/plain", b"a\x00b\n".to_vec()).unwrap(), b"ab\n");
+        assert_eq!(check_body("application/json; charset=utf-8", b"{\"a\":\"x\\u0000\"}".to_vec()).unwrap(), br#"{"a":"x"}"#);
+        assert_eq!(check_body("application/json", b"{not json\x01".to_vec()).unwrap(), b"{not json\x01");
+
+        let rejection = check_body("text/plain", b"ok\xffno".to_vec()).unwrap_err();
+        assert_eq!((rejection.status, rejection.error, rejection.offset), (422, "invalid_utf8", Some(2)));
+
+        let binary = vec![0, 0xff, 1];
+        assert_eq!(check_body("application/octet-stream", binary.clone()).unwrap(), binary);
+    }
+
+    #[test]
+    fn queries_with_encoded_control_characters_are_rejected() {
+        assert!(check_query("q=hello%20world&x=%7E").is_ok());
+        assert!(check_query("q=100%").is_ok());
+        assert_eq!(check_query("q=a%0Ab").unwrap_err().offset, Some(3));
+        assert!(check_query("q=%7f").is_err());
+    }
+}
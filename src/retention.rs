@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::dataset::{EVENTS_COLLECTION, LABELS_COLLECTION};
+use crate::events::publish;
+use crate::feedback::GENERATIONS_COLLECTION;
+use crate::storage::{now_millis, storage};
+
+// ============================================================================
+// Data Retention
+// ============================================================================
+//
+// How long each class of data is kept:
+//
+//   events         stored agent events (`/api/events`) and their labels
+//   conversations  recorded LLM generations, prompt and output
+//   jobs           lost jobs, and cancelled jobs whose agent is gone
+//   series         time-series points and rollups, on top of each series'
+//                  own retention policy
+//
+// Policies come from `PATTERN_CLOCK_RETENTION` (`events=30d,jobs=12h`) or the
+// config file's `retention` object; a class without one is kept forever.
+// Every `RETENTION_INTERVAL` the leader deletes stored data past its policy,
+// and every instance forgets its own stale jobs. `purged` counts are
+// published as `retention.purged`.
+//
+// `POST /api/admin/purge` runs the same purge on demand, for some classes or
+// with another age, and only reports what it would delete unless called with
+// `dry_run=false`.
+
+/// How often the background task applies the retention policies
+pub const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// A class of data with its own retention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataClass {
+    Events,
+    Conversations,
+    Jobs,
+    Series,
+}
+
+impl DataClass {
+    pub const ALL: [DataClass; 4] = [DataClass::Events, DataClass::Conversations, DataClass::Jobs, DataClass::Series];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DataClass::Events => "events",
+            DataClass::Conversations => "conversations",
+            DataClass::Jobs => "jobs",
+            DataClass::Series => "series",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        DataClass::ALL.into_iter().find(|class| class.name() == name)
+    }
+}
+
+/// `30d`, `12h`, `90m` or `45s`
+pub fn parse_age(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let amount: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        'd' => amount.checked_mul(24 * 60 * 60)?,
+        'h' => amount.checked_mul(60 * 60)?,
+        'm' => amount.checked_mul(60)?,
+        's' => amount,
+        _ => return None,
+    };
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Maximum age per class, as in `events=30d,conversations=90d`
+pub fn parse_policies(value: &str) -> Option<BTreeMap<DataClass, Duration>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (class, age) = item.split_once('=')?;
+            Some((DataClass::parse(class.trim())?, parse_age(age)?))
+        })
+        .collect()
+}
+
+/// What a purge did (or would do) to one class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassPurge {
+    pub class: DataClass,
+    /// Data older than this was purged (milliseconds since the Unix epoch)
+    pub cutoff: u64,
+    pub max_age_secs: u64,
+    /// Items older than the cutoff: events, generations, jobs, or series points and rollups
+    pub matched: usize,
+    /// Items deleted; 0 in a dry run
+    pub deleted: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub dry_run: bool,
+    pub ts: u64,
+    /// Classes purged; classes without a policy or age are left out
+    pub classes: Vec<ClassPurge>,
+}
+
+/// Delete the documents of `collection` whose key starts with a timestamp
+/// before `cutoff` (`<millis>-...` keys), returning the keys
+fn purge_timestamped(collection: &str, cutoff: u64, dry_run: bool) -> anyhow::Result<Vec<String>> {
+    let stale: Vec<String> = storage()
+        .list(collection)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| key.split('-').next().and_then(|ts| ts.parse::<u64>().ok()).is_some_and(|ts| ts < cutoff))
+        .collect();
+    if !dry_run {
+        for key in &stale {
+            storage().delete(collection, key)?;
+        }
+    }
+    Ok(stale)
+}
+
+fn purge_class(class: DataClass, cutoff: u64, dry_run: bool) -> anyhow::Result<usize> {
+    Ok(match class {
+        DataClass::Events => {
+            let events = purge_timestamped(EVENTS_COLLECTION, cutoff, dry_run)?;
+            if !dry_run {
+                for id in &events {
+                    storage().delete(LABELS_COLLECTION, id)?;
+                }
+            }
+            events.len()
+        }
+        DataClass::Conversations => purge_timestamped(GENERATIONS_COLLECTION, cutoff, dry_run)?.len(),
+        DataClass::Jobs => crate::jobs::purge_before(cutoff, dry_run),
+        DataClass::Series => crate::timeseries::purge_before(cutoff, dry_run)?,
+    })
+}
+
+/// Purge `classes` past `max_age`, or past their configured policy when no
+/// age is given
+pub fn purge(classes: &[DataClass], max_age: Option<Duration>, dry_run: bool) -> PurgeReport {
+    let policies = crate::config::config().retention.clone();
+    let now = now_millis();
+    let classes = classes
+        .iter()
+        .filter_map(|&class| {
+            let age = max_age.or_else(|| policies.get(&class).copied())?;
+            let cutoff = now.saturating_sub(age.as_millis() as u64);
+            let (matched, error) = match purge_class(class, cutoff, dry_run) {
+                Ok(matched) => (matched, None),
+                Err(e) => {
+                    log_error!("[Retention] Failed to purge {}: {}", class.name(), e);
+                    (0, Some(e.to_string()))
+                }
+            };
+            Some(ClassPurge {
+                class,
+                cutoff,
+                max_age_secs: age.as_secs(),
+                matched,
+                deleted: if dry_run { 0 } else { matched },
+                error,
+            })
+        })
+        .collect();
+    let report = PurgeReport { dry_run, ts: now, classes };
+    let deleted: usize = report.classes.iter().map(|class| class.deleted).sum();
+    if deleted > 0 {
+        log_info!("[Retention] Purged {} items", deleted);
+        publish("retention.purged", json!({
+            "purged": report.classes.iter().map(|class| (class.class.name(), class.deleted)).collect::<BTreeMap<_, _>>(),
+        }));
+    }
+    report
+}
+
+/// Apply the configured policies every `RETENTION_INTERVAL` (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        crate::threads::spawn_background(async move {
+            let mut ticks = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                ticks.tick().await;
+                // Jobs live in each instance; stored data is shared and left to the leader
+                let classes: Vec<DataClass> = DataClass::ALL
+                    .into_iter()
+                    .filter(|class| *class == DataClass::Jobs || crate::leader::is_leader())
+                    .collect();
+                let _ = tokio::task::spawn_blocking(move || purge(&classes, None, false)).await;
+            }
+        });
+    });
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{OnceLock, RwLock};
+
+use crate::agents::{agent_ids, get_agent, AgentMessage};
+use crate::connections::agent_provider;
+use crate::events::publish;
+use crate::extraction::Extraction;
+use crate::jobs::{self, JobStatus};
+use crate::payload::Payload;
+use crate::storage::now_millis;
+
+// ============================================================================
+// Agent Roles
+// ============================================================================
+//
+// Every agent processes data the same way (extraction, history, stored
+// events); its role decides what it does with the data afterwards:
+//
+//   general  nothing more (the default)
+//   llm      sends the data to its model (`OLLAMA_AGENT_MODEL`, unless its
+//            persona names one), as its persona, publishes the answer as
+//            `agent.output` and emits it as an `llm_output` agent event
+//   lstm     runs data that parses as a tensor through the shared LSTM and
+//            publishes the final hidden state as `agent.output`
+//   storage  appends the (redacted) data to `<data_dir>/records/agent<id>.jsonl`
+//   router   doesn't process data itself but queues it on the least busy
+//            agent that isn't a router
+//
+// Roles come from `PATTERN_CLOCK_AGENT_ROLES` (`1=llm,2=lstm,3=storage`) or
+// the config file's `agent_roles`, or are given when spawning an agent
+// (`/api/agents/spawn?role=`). An agent takes its role when it starts, so a
+// changed configuration applies to agents spawned or restarted afterwards.
+
+/// Directory under the data directory where storage agents write their records
+pub const RECORDS_DIR: &str = "records";
+
+/// Kind of the agent event carrying an `llm` agent's answer
+pub const LLM_OUTPUT_KIND: &str = "llm_output";
+
+/// What an agent does with the data it processes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentRole {
+    #[default]
+    General,
+    Llm,
+    Lstm,
+    Storage,
+    Router,
+}
+
+impl AgentRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            AgentRole::General => "general",
+            AgentRole::Llm => "llm",
+            AgentRole::Lstm => "lstm",
+            AgentRole::Storage => "storage",
+            AgentRole::Router => "router",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "general" => Some(AgentRole::General),
+            "llm" => Some(AgentRole::Llm),
+            "lstm" => Some(AgentRole::Lstm),
+            "storage" => Some(AgentRole::Storage),
+            "router" => Some(AgentRole::Router),
+            _ => None,
+        }
+    }
+}
+
+/// Roles per agent id, as in `1=llm,2=lstm`
+pub fn parse_roles(value: &str) -> Option<std::collections::BTreeMap<u8, AgentRole>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (id, role) = item.split_once('=')?;
+            Some((id.trim().parse().ok()?, AgentRole::parse(role.trim())?))
+        })
+        .collect()
+}
+
+/// Roles given at spawn time, which win over the configured ones
+fn assigned() -> &'static RwLock<HashMap<u8, AgentRole>> {
+    static ASSIGNED: OnceLock<RwLock<HashMap<u8, AgentRole>>> = OnceLock::new();
+    ASSIGNED.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Give agent `id` a role for its next start (and restarts after crashes)
+pub fn assign(id: u8, role: AgentRole) {
+    assigned().write().unwrap().insert(id, role);
+}
+
+/// Forget the role given to a stopped agent
+pub fn unassign(id: u8) {
+    assigned().write().unwrap().remove(&id);
+}
+
+/// The role agent `id` starts with
+pub fn role_of(id: u8) -> AgentRole {
+    if let Some(role) = assigned().read().unwrap().get(&id) {
+        return *role;
+    }
+    crate::config::config().agent_roles.get(&id).copied().unwrap_or_default()
+}
+
+/// Ask the agent's model about `data`, publish the answer and emit it as an `llm_output` agent event
+pub async fn answer(agent_id: u8, data: &str) {
+    let provider = agent_provider(agent_id);
+    let started = std::time::Instant::now();
+    let result = provider.generate(data).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let (output, error) = match result {
+        Ok(output) => {
+            log_info!("[Agent{}] LLM answered with {} characters", agent_id, output.len());
+            let output = crate::redaction::redact(&output);
+            publish("agent.output", json!({
+                "agent_id": agent_id,
+                "role": AgentRole::Llm,
+                "model": provider.model(),
+                "output": output,
+            }));
+            (Some(output), None)
+        }
+        Err(e) => {
+            log_warn!("[Agent{}] LLM request failed: {}", agent_id, e);
+            (None, Some(e.to_string()))
+        }
+    };
+    crate::agent_events::emit(crate::agent_events::AgentEvent::new(agent_id, LLM_OUTPUT_KIND, json!({
+        "ok": error.is_none(),
+        "error": error,
+        "duration_ms": duration_ms,
+        "model": provider.model(),
+        "output": output,
+    })));
+}
+
+/// Run `data` through the shared LSTM if it is a tensor and publish the final hidden state
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn infer(agent_id: u8, data: &str) {
+    let tensor = serde_json::from_str::<serde_json::Value>(data)
+        .ok()
+        .and_then(|value| crate::tensor_io::JsonTensor::parse(&value).ok());
+    let Some(tensor) = tensor else {
+        log_warn!("[Agent{}] Skipped LSTM inference: the data is not a tensor", agent_id);
+        return;
+    };
+    match crate::compute::run("lstm.infer", move || crate::lstm::run_lstm(&tensor)).await {
+        Ok(Ok(inference)) => {
+            publish("agent.output", json!({
+                "agent_id": agent_id,
+                "role": AgentRole::Lstm,
+                "output_shape": inference.output.shape,
+                "hidden": inference.hidden,
+            }));
+        }
+        Ok(Err(e)) => log_warn!("[Agent{}] LSTM inference failed: {}", agent_id, e),
+        Err(e) => log_warn!("[Agent{}] LSTM inference task failed: {}", agent_id, e),
+    }
+}
+
+/// The LSTM isn't available in the browser
+#[cfg(target_arch = "wasm32")]
+pub async fn infer(agent_id: u8, _data: &str) {
+    log_warn!("[Agent{}] Skipped LSTM inference: not available in this build", agent_id);
+}
+
+/// Append a processed (already redacted) record to the agent's records file
+pub fn store(agent_id: u8, sequence: u64, data: &str, extraction: &Extraction) {
+    let dir = std::path::Path::new(&crate::config::config().data_dir).join(RECORDS_DIR);
+    let record = json!({
+        "agent_id": agent_id,
+        "sequence": sequence,
+        "data": data,
+        "keywords": extraction.keywords,
+        "entities": extraction.entities,
+        "ts": now_millis(),
+    });
+    let result = std::fs::create_dir_all(&dir).and_then(|_| {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(format!("agent{}.jsonl", agent_id)))?;
+        writeln!(file, "{}", record)
+    });
+    if let Err(e) = result {
+        log_error!("[Agent{}] Failed to write record: {}", agent_id, e);
+    }
+}
+
+/// Queue `payload` on the least busy agent that isn't a router; returns its id
+pub fn route(agent_id: u8, payload: Payload) -> Option<u8> {
+    let mut busy: HashMap<u8, usize> = HashMap::new();
+    for job in jobs::list(None) {
+        if matches!(job.status, JobStatus::Pending | JobStatus::Running) {
+            *busy.entry(job.agent_id).or_default() += 1;
+        }
+    }
+    let target = agent_ids()
+        .into_iter()
+        .filter(|id| *id != agent_id && role_of(*id) != AgentRole::Router)
+        .min_by_key(|id| (busy.get(id).copied().unwrap_or(0), *id));
+    let Some(target) = target else {
+        log_warn!("[Agent{}] Nowhere to route {}: no other agents", agent_id, payload.describe());
+        return None;
+    };
+    let actor_ref = get_agent(target)?;
+    let message = AgentMessage::ProcessPayload { payload }.queued(target);
+    let AgentMessage::Queued { job, .. } = &message else {
+        unreachable!("queued() wraps the message");
+    };
+    let job = *job;
+    if let Err(e) = actor_ref.send_message(message) {
+        jobs::finish(job);
+        log_warn!("[Agent{}] Failed to route to Agent{}: {}", agent_id, target, e);
+        return None;
+    }
+    publish("agent.routed", json!({ "agent_id": agent_id, "to": target }));
+    Some(target)
+}
@@ -0,0 +1,92 @@
+use crate::rules::{diff_rules, PatternRule, RuleChange, RuleVersion};
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Saved Pattern Rules
+// ============================================================================
+//
+// Rules are saved one document per rule. Every change is also kept as a
+// numbered `RuleVersion`; saving an identical rule (as config reloads do)
+// records nothing.
+
+/// Collection holding saved rules, keyed by rule id
+pub const RULES_COLLECTION: &str = "rules";
+
+/// Collection holding rule versions, keyed by `<rule id>.<version>`
+pub const RULE_VERSIONS_COLLECTION: &str = "rule_versions";
+
+/// All saved rules
+pub fn list_rules() -> anyhow::Result<Vec<PatternRule>> {
+    list_typed(RULES_COLLECTION)
+}
+
+/// Look up one rule
+pub fn get_rule(id: &str) -> anyhow::Result<Option<PatternRule>> {
+    get_typed(RULES_COLLECTION, id)
+}
+
+/// Validate and save (or replace) a rule, recording a version when it changed
+pub fn save_rule(rule: &PatternRule, author: &str) -> anyhow::Result<()> {
+    rule.validate().map_err(|e| anyhow::anyhow!(e))?;
+    let before = get_rule(&rule.id)?;
+    put_typed(RULES_COLLECTION, &rule.id, rule)?;
+    if before.as_ref() != Some(rule) {
+        record_version(&rule.id, before.as_ref(), Some(rule), author, RuleChange::Saved)?;
+    }
+    Ok(())
+}
+
+/// Delete a rule, returning whether it existed (its history is kept)
+pub fn delete_rule(id: &str, author: &str) -> anyhow::Result<bool> {
+    let before = get_rule(id)?;
+    let existed = storage().delete(RULES_COLLECTION, id)?;
+    if let Some(before) = before.filter(|_| existed) {
+        record_version(id, Some(&before), None, author, RuleChange::Deleted)?;
+    }
+    Ok(existed)
+}
+
+/// Versions of one rule, oldest first
+pub fn rule_history(id: &str) -> anyhow::Result<Vec<RuleVersion>> {
+    let mut versions: Vec<RuleVersion> = list_typed::<RuleVersion>(RULE_VERSIONS_COLLECTION)?
+        .into_iter()
+        .filter(|version| version.rule_id == id)
+        .collect();
+    versions.sort_by_key(|version| version.version);
+    Ok(versions)
+}
+
+/// Restore a rule as it was in `version`, recorded as a new version
+pub fn rollback_rule(id: &str, version: u32, author: &str) -> anyhow::Result<RuleVersion> {
+    let target = get_typed::<RuleVersion>(RULE_VERSIONS_COLLECTION, &version_key(id, version))?
+        .ok_or_else(|| anyhow::anyhow!("rule {} has no version {}", id, version))?;
+    let rule = target.rule
+        .ok_or_else(|| anyhow::anyhow!("version {} of rule {} is a deletion", version, id))?;
+    let before = get_rule(id)?;
+    put_typed(RULES_COLLECTION, id, &rule)?;
+    record_version(id, before.as_ref(), Some(&rule), author, RuleChange::RolledBack { version })
+}
+
+fn version_key(id: &str, version: u32) -> String {
+    format!("{}.{:06}", id, version)
+}
+
+fn record_version(
+    id: &str,
+    before: Option<&PatternRule>,
+    after: Option<&PatternRule>,
+    author: &str,
+    change: RuleChange,
+) -> anyhow::Result<RuleVersion> {
+    let version = RuleVersion {
+        rule_id: id.to_string(),
+        version: rule_history(id)?.last().map_or(1, |last| last.version + 1),
+        ts: now_millis(),
+        author: author.to_string(),
+        change,
+        rule: after.cloned(),
+        diff: diff_rules(before, after),
+    };
+    put_typed(RULE_VERSIONS_COLLECTION, &version_key(id, version.version), &version)?;
+    Ok(version)
+}
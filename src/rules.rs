@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::timeseries::Point;
+
+// ============================================================================
+// Pattern Rules and Anomaly Detectors
+// ============================================================================
+//
+// Pure evaluation code with no runtime dependencies, so the same rules run on
+// the server and in the wasm32 web client (which previews matches against
+// fetched history before saving a rule). Saving rules and their history is
+// native-only, in `rule_store`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Gt => value > threshold,
+            Comparison::Ge => value >= threshold,
+            Comparison::Lt => value < threshold,
+            Comparison::Le => value <= threshold,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+        }
+    }
+}
+
+/// How a rule decides that a point matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Detector {
+    /// Value compared against a fixed threshold
+    Threshold { op: Comparison, value: f64 },
+    /// Value more than `threshold` standard deviations from the mean of the previous `window` points
+    ZScore { window: usize, threshold: f64 },
+    /// Absolute change per second between consecutive points above `max_per_sec`
+    RateOfChange { max_per_sec: f64 },
+    /// Value deviating from an exponentially weighted mean by more than `tolerance` weighted deviations
+    Ewma { alpha: f64, tolerance: f64 },
+}
+
+/// A saved pattern rule over one series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternRule {
+    pub id: String,
+    pub name: String,
+    /// Series the rule evaluates
+    pub series: String,
+    /// Only points carrying all of these tags are evaluated
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    pub detector: Detector,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A point matched by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub ts: u64,
+    pub value: f64,
+    /// Detector-specific magnitude (z-score, rate, deviation, ...)
+    pub score: f64,
+    pub reason: String,
+}
+
+impl PatternRule {
+    /// Check that the rule is well-formed before saving or evaluating it
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+            return Err("rule id must be non-empty and contain only letters, digits, '_' and '-'".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("rule name must not be empty".to_string());
+        }
+        if self.series.is_empty() {
+            return Err("rule series must not be empty".to_string());
+        }
+        match &self.detector {
+            Detector::Threshold { value, .. } if !value.is_finite() => {
+                Err("threshold must be a finite number".to_string())
+            }
+            Detector::ZScore { window, threshold } if *window < 2 || *threshold <= 0.0 => {
+                Err("z-score window must be at least 2 and threshold positive".to_string())
+            }
+            Detector::RateOfChange { max_per_sec } if *max_per_sec <= 0.0 => {
+                Err("max rate of change must be positive".to_string())
+            }
+            Detector::Ewma { alpha, tolerance } if !(*alpha > 0.0 && *alpha <= 1.0) || *tolerance <= 0.0 => {
+                Err("EWMA alpha must be in (0, 1] and tolerance positive".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Points of `points` (sorted by timestamp) matched by the rule
+    pub fn evaluate(&self, points: &[Point]) -> Vec<RuleMatch> {
+        let points: Vec<&Point> = points
+            .iter()
+            .filter(|p| self.tags.iter().all(|(k, v)| p.tags.get(k) == Some(v)))
+            .collect();
+        match &self.detector {
+            Detector::Threshold { op, value } => points
+                .iter()
+                .filter(|p| op.holds(p.value, *value))
+                .map(|p| RuleMatch {
+                    ts: p.ts,
+                    value: p.value,
+                    score: p.value - value,
+                    reason: format!("{} {} {}", p.value, op.symbol(), value),
+                })
+                .collect(),
+            Detector::ZScore { window, threshold } => zscore_matches(&points, *window, *threshold),
+            Detector::RateOfChange { max_per_sec } => points
+                .windows(2)
+                .filter_map(|pair| {
+                    let seconds = pair[1].ts.saturating_sub(pair[0].ts).max(1) as f64 / 1000.0;
+                    let rate = (pair[1].value - pair[0].value) / seconds;
+                    (rate.abs() > *max_per_sec).then(|| RuleMatch {
+                        ts: pair[1].ts,
+                        value: pair[1].value,
+                        score: rate,
+                        reason: format!("changed {:.3}/s (max {})", rate, max_per_sec),
+                    })
+                })
+                .collect(),
+            Detector::Ewma { alpha, tolerance } => ewma_matches(&points, *alpha, *tolerance),
+        }
+    }
+}
+
+fn zscore_matches(points: &[&Point], window: usize, threshold: f64) -> Vec<RuleMatch> {
+    let mut matches = Vec::new();
+    let mut recent: VecDeque<f64> = VecDeque::with_capacity(window);
+    for point in points {
+        if recent.len() == window {
+            let mean = recent.iter().sum::<f64>() / window as f64;
+            let variance = recent.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window as f64;
+            let std = variance.sqrt();
+            if std > f64::EPSILON {
+                let z = (point.value - mean) / std;
+                if z.abs() > threshold {
+                    matches.push(RuleMatch {
+                        ts: point.ts,
+                        value: point.value,
+                        score: z,
+                        reason: format!("z-score {:.2} over last {} points", z, window),
+                    });
+                }
+            }
+            recent.pop_front();
+        }
+        recent.push_back(point.value);
+    }
+    matches
+}
+
+fn ewma_matches(points: &[&Point], alpha: f64, tolerance: f64) -> Vec<RuleMatch> {
+    let mut matches = Vec::new();
+    let Some(first) = points.first() else {
+        return matches;
+    };
+    let mut mean = first.value;
+    let mut variance = 0.0_f64;
+    for point in &points[1..] {
+        let deviation = point.value - mean;
+        let std = variance.sqrt();
+        if std > f64::EPSILON && deviation.abs() > tolerance * std {
+            matches.push(RuleMatch {
+                ts: point.ts,
+                value: point.value,
+                score: deviation / std,
+                reason: format!("{:.2} weighted deviations from EWMA {:.3}", deviation / std, mean),
+            });
+        }
+        mean += alpha * deviation;
+        variance = (1.0 - alpha) * (variance + alpha * deviation * deviation);
+    }
+    matches
+}
+
+// ----------------------------------------------------------------------------
+// Version history
+// ----------------------------------------------------------------------------
+//
+// Every change to a saved rule is kept as a numbered version with the rule
+// as it was afterwards, the fields that changed and who changed it, so edits
+// are auditable and any earlier version can be restored (see `rule_store`).
+
+/// Request header naming who made a change through the API
+pub const AUTHOR_HEADER: &str = "x-author";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleChange {
+    Saved,
+    Deleted,
+    /// Restored the rule as it was in `version`
+    RolledBack { version: u32 },
+}
+
+/// One changed field, as a dotted path into the rule's JSON (`detector.value`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// A rule as of one change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleVersion {
+    pub rule_id: String,
+    /// Numbered from 1 per rule
+    pub version: u32,
+    pub ts: u64,
+    pub author: String,
+    pub change: RuleChange,
+    /// The rule after the change (none once deleted)
+    pub rule: Option<PatternRule>,
+    pub diff: Vec<FieldChange>,
+}
+
+/// Fields that differ between two versions of a rule
+pub fn diff_rules(before: Option<&PatternRule>, after: Option<&PatternRule>) -> Vec<FieldChange> {
+    let to_json = |rule: Option<&PatternRule>| rule.and_then(|rule| serde_json::to_value(rule).ok());
+    let mut changes = Vec::new();
+    diff_values("", to_json(before).as_ref(), to_json(after).as_ref(), &mut changes);
+    changes
+}
+
+fn diff_values(
+    path: &str,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    if before == after {
+        return;
+    }
+    let field = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+    match (before, after) {
+        // Objects of the same kind are compared field by field; a detector of another type is one change
+        (Some(serde_json::Value::Object(a)), Some(serde_json::Value::Object(b))) if a.get("type") == b.get("type") => {
+            let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                diff_values(&field(key), a.get(key), b.get(key), changes);
+            }
+        }
+        (None, Some(serde_json::Value::Object(b))) if path.is_empty() => {
+            for (key, value) in b {
+                diff_values(&field(key), None, Some(value), changes);
+            }
+        }
+        (Some(serde_json::Value::Object(a)), None) if path.is_empty() => {
+            for (key, value) in a {
+                diff_values(&field(key), Some(value), None, changes);
+            }
+        }
+        _ => changes.push(FieldChange { field: path.to_string(), before: before.cloned(), after: after.cloned() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(detector: Detector) -> PatternRule {
+        PatternRule {
+            id: "cpu-high".to_string(),
+            name: "CPU high".to_string(),
+            series: "cpu".to_string(),
+            tags: BTreeMap::new(),
+            detector,
+            enabled: true,
+        }
+    }
+
+    /// One point a second
+    fn points(values: &[f64]) -> Vec<Point> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Point { ts: (i as u64 + 1) * 1000, value, tags: BTreeMap::new() })
+            .collect()
+    }
+
+    fn matched(rule: &PatternRule, points: &[Point]) -> Vec<(u64, f64)> {
+        rule.evaluate(points).iter().map(|m| (m.ts, m.score)).collect()
+    }
+
+    #[test]
+    fn thresholds_match_points_past_the_value() {
+        let rule = rule(Detector::Threshold { op: Comparison::Gt, value: 5.0 });
+        assert_eq!(matched(&rule, &points(&[1.0, 6.0, 5.0, 9.0])), [(2000, 1.0), (4000, 4.0)]);
+
+        let rule = PatternRule { detector: Detector::Threshold { op: Comparison::Ge, value: 5.0 }, ..rule };
+        assert_eq!(rule.evaluate(&points(&[5.0]))[0].reason, "5 >= 5");
+    }
+
+    #[test]
+    fn zscore_flags_outliers_against_the_previous_window() {
+        let rule = rule(Detector::ZScore { window: 3, threshold: 2.0 });
+        let matches = rule.evaluate(&points(&[9.0, 11.0, 10.0, 9.0, 11.0, 10.0, 30.0]));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].ts, 7000);
+        assert!(matches[0].score > 20.0);
+
+        // A flat window has no spread to measure against
+        assert!(rule.evaluate(&points(&[10.0, 10.0, 10.0, 30.0])).is_empty());
+    }
+
+    #[test]
+    fn rate_of_change_is_per_second_in_either_direction() {
+        let rule = rule(Detector::RateOfChange { max_per_sec: 5.0 });
+        let mut series = points(&[0.0, 3.0, 6.0, 0.0]);
+        series[2].ts = 2500;
+        series[3].ts = 3500;
+        assert_eq!(matched(&rule, &series), [(2500, 6.0), (3500, -6.0)]);
+    }
+
+    #[test]
+    fn ewma_flags_values_far_from_the_weighted_mean() {
+        let rule = rule(Detector::Ewma { alpha: 0.5, tolerance: 3.0 });
+        let matches = rule.evaluate(&points(&[10.0, 12.0, 8.0, 11.0, 9.0, 40.0]));
+        assert_eq!(matches.iter().map(|m| m.ts).collect::<Vec<_>>(), [6000]);
+        assert!(rule.evaluate(&[]).is_empty());
+    }
+
+    #[test]
+    fn only_points_with_the_rule_tags_are_evaluated() {
+        let rule = PatternRule {
+            tags: BTreeMap::from([("host".to_string(), "a".to_string())]),
+            ..rule(Detector::Threshold { op: Comparison::Gt, value: 0.0 })
+        };
+        let mut series = points(&[1.0, 2.0]);
+        series[0].tags.insert("host".to_string(), "a".to_string());
+        series[1].tags.insert("host".to_string(), "b".to_string());
+        assert_eq!(matched(&rule, &series), [(1000, 1.0)]);
+    }
+
+    #[test]
+    fn malformed_rules_are_rejected() {
+        assert!(rule(Detector::ZScore { window: 3, threshold: 2.0 }).validate().is_ok());
+        assert!(rule(Detector::ZScore { window: 1, threshold: 2.0 }).validate().is_err());
+        assert!(rule(Detector::Ewma { alpha: 0.0, tolerance: 3.0 }).validate().is_err());
+        assert!(rule(Detector::Threshold { op: Comparison::Lt, value: f64::NAN }).validate().is_err());
+        assert!(rule(Detector::RateOfChange { max_per_sec: 0.0 }).validate().is_err());
+        let unnamed = PatternRule { id: "has space".to_string(), ..rule(Detector::RateOfChange { max_per_sec: 1.0 }) };
+        assert!(unnamed.validate().is_err());
+    }
+
+    #[test]
+    fn diffs_list_changed_fields_by_path() {
+        let before = rule(Detector::Threshold { op: Comparison::Gt, value: 5.0 });
+        assert!(diff_rules(Some(&before), Some(&before)).is_empty());
+
+        let after = PatternRule { detector: Detector::Threshold { op: Comparison::Gt, value: 7.0 }, ..before.clone() };
+        assert_eq!(diff_rules(Some(&before), Some(&after)), [FieldChange {
+            field: "detector.value".to_string(),
+            before: Some(json!(5.0)),
+            after: Some(json!(7.0)),
+        }]);
+
+        let renamed = PatternRule { name: "CPU very high".to_string(), enabled: false, ..before.clone() };
+        let fields: Vec<String> = diff_rules(Some(&before), Some(&renamed)).into_iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["enabled", "name"]);
+
+        // Another kind of detector is replaced as a whole
+        let zscore = PatternRule { detector: Detector::ZScore { window: 3, threshold: 2.0 }, ..before.clone() };
+        let changes = diff_rules(Some(&before), Some(&zscore));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "detector");
+
+        // Created and deleted rules list every top-level field
+        let mut created: Vec<String> = diff_rules(None, Some(&before)).into_iter().map(|c| c.field).collect();
+        created.sort();
+        assert_eq!(created, ["detector", "enabled", "id", "name", "series", "tags"]);
+        assert!(diff_rules(Some(&before), None).iter().all(|c| c.after.is_none()));
+    }
+}
@@ -0,0 +1,232 @@
+use tokio::sync::broadcast;
+
+use crate::agents::{
+    agent_ids, broadcast as broadcast_payload, change_lifecycle, get_agent, initialize_agents, is_initialized, spawn_agent,
+    send_work, spawn_agent_as, stop_agent, AgentMessage, BroadcastDelivery, LifecycleStatus,
+};
+use crate::agent_events::SharedAgentEvent;
+use crate::connections::{set_default_provider, LlmProvider};
+use crate::events::{subscribe, SharedEvent};
+use crate::mailbox::Priority;
+use crate::monitor::MonitorConfig;
+use crate::payload::Payload;
+use crate::storage::{set_storage, Storage};
+
+// ============================================================================
+// Embeddable Runtime
+// ============================================================================
+//
+// Runs the agent runtime inside an existing Tokio application, without the
+// Dioxus server:
+//
+//     let runtime = PatternClock::builder()
+//         .agents(8)
+//         .with_provider(OllamaProvider::from_env())
+//         .with_storage(FileStorage::new("data"))
+//         .build()
+//         .start()
+//         .await?;
+//     runtime.submit(1, "cpu at 97%")?;
+
+/// Entry point of the embedding API
+pub struct PatternClock {
+    agents: u8,
+    provider: Option<Box<dyn LlmProvider>>,
+    storage: Option<Box<dyn Storage>>,
+    monitor: Option<MonitorConfig>,
+}
+
+/// Configures a `PatternClock` runtime
+pub struct PatternClockBuilder {
+    agents: u8,
+    provider: Option<Box<dyn LlmProvider>>,
+    storage: Option<Box<dyn Storage>>,
+    monitor: Option<MonitorConfig>,
+}
+
+impl Default for PatternClockBuilder {
+    fn default() -> Self {
+        Self {
+            agents: crate::config::config().agents,
+            provider: None,
+            storage: None,
+            monitor: Some(MonitorConfig::default()),
+        }
+    }
+}
+
+impl PatternClockBuilder {
+    /// Number of agents to start (ids `1..=count`; default: `PATTERN_CLOCK_AGENTS` or `agents.toml`, else 5)
+    pub fn agents(mut self, count: u8) -> Self {
+        self.agents = count;
+        self
+    }
+
+    /// LLM provider used by agents, extraction and summarization (default: Ollama from the environment)
+    pub fn with_provider(mut self, provider: impl LlmProvider + 'static) -> Self {
+        self.provider = Some(Box::new(provider));
+        self
+    }
+
+    /// Storage backend (default: `FileStorage` under `PATTERN_CLOCK_DATA_DIR`)
+    pub fn with_storage(mut self, storage: impl Storage + 'static) -> Self {
+        self.storage = Some(Box::new(storage));
+        self
+    }
+
+    /// Self-monitor thresholds, or `None` to run without the monitor
+    pub fn monitor(mut self, config: Option<MonitorConfig>) -> Self {
+        self.monitor = config;
+        self
+    }
+
+    pub fn build(self) -> PatternClock {
+        PatternClock {
+            agents: self.agents,
+            provider: self.provider,
+            storage: self.storage,
+            monitor: self.monitor,
+        }
+    }
+}
+
+impl PatternClock {
+    pub fn builder() -> PatternClockBuilder {
+        PatternClockBuilder::default()
+    }
+
+    /// Install the provider and storage and spawn the agents on the current Tokio runtime
+    ///
+    /// Provider, storage and agents are process-wide, so only one runtime can
+    /// be started per process; starting again (or after the Dioxus server has
+    /// started its agents) fails.
+    pub async fn start(self) -> anyhow::Result<RuntimeHandle> {
+        anyhow::ensure!(self.agents > 0, "at least one agent is required");
+        anyhow::ensure!(!is_initialized(), "the agent runtime is already running");
+
+        if let Some(provider) = self.provider {
+            set_default_provider(provider)
+                .map_err(|_| anyhow::anyhow!("an LLM provider is already in use"))?;
+        }
+        if let Some(storage) = self.storage {
+            set_storage(storage)
+                .map_err(|_| anyhow::anyhow!("a storage backend is already in use"))?;
+        }
+
+        initialize_agents(self.agents, self.monitor)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to start agents: {}", e))?;
+        Ok(RuntimeHandle)
+    }
+}
+
+/// Multi-threaded Tokio runtime for hosts without one (Python, C FFI)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn background_runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        if let Some(threads) = crate::config::config().worker_threads {
+            builder.worker_threads(threads);
+        }
+        builder
+            .enable_all()
+            .thread_name("pattern-clock")
+            .build()
+            .expect("failed to build Tokio runtime")
+    })
+}
+
+/// Handle to a started runtime
+#[derive(Debug, Clone)]
+pub struct RuntimeHandle;
+
+impl RuntimeHandle {
+    /// Ids of the running agents
+    pub fn agent_ids(&self) -> Vec<u8> {
+        agent_ids()
+    }
+
+    /// Start another agent with `id`
+    pub async fn spawn_agent(&self, id: u8) -> anyhow::Result<()> {
+        spawn_agent(id).await.map(|_| ()).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Start another agent with `id` in `role`, whatever its configured role
+    pub async fn spawn_agent_as(&self, id: u8, role: crate::roles::AgentRole) -> anyhow::Result<()> {
+        spawn_agent_as(id, role).await.map(|_| ()).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Stop one agent; returns whether it was running
+    pub fn stop_agent(&self, id: u8) -> bool {
+        stop_agent(id)
+    }
+
+    /// Send any message to an agent
+    pub fn send(&self, agent_id: u8, message: AgentMessage) -> anyhow::Result<()> {
+        get_agent(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?
+            .send_message(message)
+            .map_err(|e| anyhow::anyhow!("failed to send to Agent{}: {}", agent_id, e))
+    }
+
+    /// Queue work for an agent, listed in the job queue
+    fn send_work(&self, agent_id: u8, message: AgentMessage) -> anyhow::Result<()> {
+        let actor_ref = get_agent(agent_id).ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?;
+        send_work(&actor_ref, message).map_err(|e| anyhow::anyhow!("failed to send to Agent{}: {}", agent_id, e))
+    }
+
+    /// Queue a payload for processing by an agent
+    pub fn submit(&self, agent_id: u8, data: impl Into<String>) -> anyhow::Result<()> {
+        self.submit_with_priority(agent_id, data, Priority::Normal)
+    }
+
+    /// Queue a payload ahead of (`High`) or behind (`Low`) other data waiting for the agent
+    pub fn submit_with_priority(&self, agent_id: u8, data: impl Into<String>, priority: Priority) -> anyhow::Result<()> {
+        self.send_work(agent_id, AgentMessage::ProcessData { data: data.into(), priority })
+    }
+
+    /// Queue a text or binary payload (e.g. an image) for processing by an agent
+    pub fn submit_payload(&self, agent_id: u8, payload: Payload) -> anyhow::Result<()> {
+        self.send_work(agent_id, AgentMessage::ProcessPayload { payload })
+    }
+
+    /// Queue a payload on every running agent, ahead of their normal data; one result per agent
+    pub fn broadcast(&self, payload: Payload) -> Vec<BroadcastDelivery> {
+        broadcast_payload(&payload)
+    }
+
+    /// Stop an agent taking work off its queue without stopping it; new work is queued meanwhile
+    pub async fn pause(&self, agent_id: u8) -> anyhow::Result<LifecycleStatus> {
+        change_lifecycle(agent_id, AgentMessage::Pause).await
+    }
+
+    /// Let a paused or drained agent take work again
+    pub async fn resume(&self, agent_id: u8) -> anyhow::Result<LifecycleStatus> {
+        change_lifecycle(agent_id, AgentMessage::Resume).await
+    }
+
+    /// Have an agent finish its queued work while refusing new work until resumed
+    pub async fn drain(&self, agent_id: u8) -> anyhow::Result<LifecycleStatus> {
+        change_lifecycle(agent_id, AgentMessage::Drain).await
+    }
+
+    /// Subscribe to internal events (agent activity, alerts, training, ...)
+    pub fn subscribe(&self) -> broadcast::Receiver<SharedEvent> {
+        subscribe()
+    }
+
+    /// Subscribe to the messages agents handle, one `AgentEvent` each
+    pub fn subscribe_agent_events(&self) -> broadcast::Receiver<SharedAgentEvent> {
+        crate::agent_events::subscribe()
+    }
+
+    /// Stop all agents
+    pub fn shutdown(&self) {
+        for agent_id in agent_ids() {
+            if let Some(actor_ref) = get_agent(agent_id) {
+                actor_ref.stop(None);
+            }
+        }
+    }
+}
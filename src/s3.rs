@@ -0,0 +1,90 @@
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+
+use crate::blobs::BlobBackend;
+use crate::config::ServiceConfig;
+
+// ============================================================================
+// S3 Blob Backend
+// ============================================================================
+//
+// Keeps blob contents in an S3-compatible bucket, selected with
+// `PATTERN_CLOCK_BLOB_STORE=s3://bucket/prefix`. Objects are named
+// `<prefix>/<first 2 hex>/<hash>` like the local layout. AWS is used unless
+// `PATTERN_CLOCK_S3_ENDPOINT` points elsewhere (MinIO, R2, ...), in which
+// case requests are path-style. Credentials come from the usual places:
+// `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY`, the AWS profile, or the
+// instance role.
+
+/// Blob contents as objects in a bucket
+pub struct S3Blobs {
+    bucket: Box<Bucket>,
+    prefix: String,
+}
+
+impl S3Blobs {
+    /// Open the bucket named by `blob_store` (`s3://bucket[/prefix]`)
+    pub fn from_config(config: &ServiceConfig) -> anyhow::Result<Self> {
+        let location = config.blob_store.strip_prefix("s3://")
+            .ok_or_else(|| anyhow::anyhow!("blob store {:?} is not an s3:// URL", config.blob_store))?;
+        let (name, prefix) = location.split_once('/').unwrap_or((location, ""));
+        let region = match &config.s3_endpoint {
+            Some(endpoint) => Region::Custom { region: config.s3_region.clone(), endpoint: endpoint.clone() },
+            None => config.s3_region.parse()?,
+        };
+        let mut bucket = Bucket::new(name, region, Credentials::default()?)?;
+        if config.s3_endpoint.is_some() {
+            bucket = bucket.with_path_style();
+        }
+        log_info!("[Blobs] Storing blobs in s3://{}/{}", name, prefix);
+        Ok(Self { bucket, prefix: prefix.trim_matches('/').to_string() })
+    }
+
+    fn key(&self, hash: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("{}/{}", &hash[..2], hash)
+        } else {
+            format!("{}/{}/{}", self.prefix, &hash[..2], hash)
+        }
+    }
+}
+
+fn ensure_success(operation: &str, key: &str, status: u16) -> anyhow::Result<()> {
+    anyhow::ensure!((200..300).contains(&status), "S3 {} of {} returned {}", operation, key, status);
+    Ok(())
+}
+
+impl BlobBackend for S3Blobs {
+    fn write(&self, hash: &str, data: &[u8]) -> anyhow::Result<()> {
+        let key = self.key(hash);
+        let response = self.bucket.put_object(&key, data)?;
+        ensure_success("PUT", &key, response.status_code())
+    }
+
+    fn read(&self, hash: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let key = self.key(hash);
+        let response = self.bucket.get_object(&key)?;
+        if response.status_code() == 404 {
+            return Ok(None);
+        }
+        ensure_success("GET", &key, response.status_code())?;
+        Ok(Some(response.to_vec()))
+    }
+
+    fn exists(&self, hash: &str) -> anyhow::Result<bool> {
+        let key = self.key(hash);
+        let (_, status) = self.bucket.head_object(&key)?;
+        if status == 404 {
+            return Ok(false);
+        }
+        ensure_success("HEAD", &key, status)?;
+        Ok(true)
+    }
+
+    fn remove(&self, hash: &str) -> anyhow::Result<()> {
+        let key = self.key(hash);
+        let response = self.bucket.delete_object(&key)?;
+        // Deleting a missing object is a success in S3
+        ensure_success("DELETE", &key, response.status_code())
+    }
+}
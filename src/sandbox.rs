@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::approvals::{self, ApprovalPolicy, ApprovalStep};
+use crate::events::{publish, subscribe, Event};
+use crate::phases::RuleWindow;
+use crate::rules::{PatternRule, RuleMatch};
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Rule Sandbox
+// ============================================================================
+//
+// A draft pattern rule can be tried on live events before it is activated.
+// A trial runs the draft in shadow mode for its observation window: it
+// evaluates the same event series as the cycle's `rules` phase, but its
+// matches are only recorded on the trial. Nothing is published for them and
+// no notification goes out. The draft is not a saved rule, so the live phase
+// never sees it.
+//
+// When the window ends the trial gets a report (events seen, points on the
+// rule's series, matches and their rate) and `rule.trial_finished` is
+// published. The draft is then promoted to a saved rule or discarded.
+// Trials are kept in `rule_trials`; a trial still observing when the
+// process stops resumes for the rest of its window on the next start.
+//
+// A trial started with an approval policy doesn't wait for someone to
+// promote it: when its window ends it asks for approval and pauses. Once the
+// approval is granted the draft is promoted; a rejection ends the trial.
+
+/// Collection holding trials, keyed by trial id
+pub const TRIALS_COLLECTION: &str = "rule_trials";
+
+/// Observation window used when none is given
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Longest observation window
+pub const MAX_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Matches kept on a trial; later ones are only counted
+const MAX_RECORDED_MATCHES: usize = 500;
+/// How often an observing trial's progress is saved
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrialStatus {
+    Observing,
+    Finished,
+    /// Finished and waiting for its promotion to be approved
+    AwaitingApproval,
+    Promoted,
+    /// Its promotion was rejected
+    Rejected,
+}
+
+/// Summary of a finished observation window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrialReport {
+    pub events_seen: u64,
+    /// Points on the rule's series
+    pub points_seen: u64,
+    pub matches: u64,
+    pub matches_per_hour: f64,
+    pub first_match_ts: Option<u64>,
+    pub last_match_ts: Option<u64>,
+}
+
+/// A draft rule running in shadow mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trial {
+    pub id: String,
+    pub rule: PatternRule,
+    pub status: TrialStatus,
+    pub started_at: u64,
+    pub ends_at: u64,
+    pub events_seen: u64,
+    pub points_seen: u64,
+    pub match_count: u64,
+    /// The first `MAX_RECORDED_MATCHES` matches
+    pub matches: Vec<RuleMatch>,
+    pub report: Option<TrialReport>,
+    /// Ask for approval to promote once the window ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalPolicy>,
+    /// The approval asked for, once the trial is awaiting it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_id: Option<String>,
+}
+
+impl Trial {
+    fn record(&mut self, events: &[Event], window: &mut RuleWindow) {
+        self.events_seen += events.len() as u64;
+        self.points_seen += events
+            .iter()
+            .flat_map(crate::phases::event_points)
+            .filter(|(series, _)| *series == self.rule.series)
+            .count() as u64;
+        for found in window.observe(&self.rule, events) {
+            self.match_count += 1;
+            if self.matches.len() < MAX_RECORDED_MATCHES {
+                self.matches.push(found);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        let hours = self.ends_at.saturating_sub(self.started_at) as f64 / 3_600_000.0;
+        self.status = TrialStatus::Finished;
+        self.report = Some(TrialReport {
+            events_seen: self.events_seen,
+            points_seen: self.points_seen,
+            matches: self.match_count,
+            matches_per_hour: if hours > 0.0 { self.match_count as f64 / hours } else { 0.0 },
+            first_match_ts: self.matches.first().map(|found| found.ts),
+            last_match_ts: self.matches.last().map(|found| found.ts),
+        });
+    }
+}
+
+/// Start a shadow trial of `rule` for `window` (default one hour; requires a Tokio runtime)
+///
+/// With an approval policy the draft is promoted once an operator approves it.
+pub fn start_trial(rule: PatternRule, window: Option<Duration>, approval: Option<ApprovalPolicy>) -> anyhow::Result<Trial> {
+    rule.validate().map_err(|e| anyhow::anyhow!(e))?;
+    let window = window.unwrap_or(DEFAULT_WINDOW);
+    anyhow::ensure!(!window.is_zero() && window <= MAX_WINDOW, "observation window must be between 1 s and 7 days");
+
+    let now = now_millis();
+    let trial = Trial {
+        id: format!("{:013}-{}", now, rule.id),
+        rule,
+        status: TrialStatus::Observing,
+        started_at: now,
+        ends_at: now + window.as_millis() as u64,
+        events_seen: 0,
+        points_seen: 0,
+        match_count: 0,
+        matches: Vec::new(),
+        report: None,
+        approval,
+        approval_id: None,
+    };
+    put_typed(TRIALS_COLLECTION, &trial.id, &trial)?;
+    log_info!("[Sandbox] Trying rule {} in shadow mode for {}s", trial.rule.id, window.as_secs());
+    observe(trial.clone());
+    Ok(trial)
+}
+
+/// Follow the event bus for `trial` until its window ends
+fn observe(mut trial: Trial) {
+    let mut events = subscribe();
+    tokio::spawn(async move {
+        let mut window = RuleWindow::default();
+        let mut saves = tokio::time::interval(SAVE_INTERVAL);
+        let end = tokio::time::sleep(Duration::from_millis(trial.ends_at.saturating_sub(now_millis())));
+        tokio::pin!(end);
+        loop {
+            tokio::select! {
+                _ = &mut end => break,
+                _ = saves.tick() => {
+                    // Stop if the trial was discarded meanwhile
+                    if !matches!(get_typed::<Trial>(TRIALS_COLLECTION, &trial.id), Ok(Some(_))) {
+                        return;
+                    }
+                    if let Err(e) = put_typed(TRIALS_COLLECTION, &trial.id, &trial) {
+                        log_warn!("[Sandbox] Failed to save trial {}: {}", trial.id, e);
+                    }
+                }
+                received = events.recv() => match received {
+                    Ok(event) if event.is_local() => trial.record(std::slice::from_ref(event.event()), &mut window),
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        log_warn!("[Sandbox] Trial {} lagged, skipped {} events", trial.id, skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                },
+            }
+        }
+
+        if !matches!(get_typed::<Trial>(TRIALS_COLLECTION, &trial.id), Ok(Some(_))) {
+            return;
+        }
+        trial.finish();
+        if let Some(policy) = trial.approval.clone() {
+            let summary = format!(
+                "Promote rule {} ({} matches in the trial)", trial.rule.id, trial.match_count
+            );
+            let step = ApprovalStep::PromoteTrial { trial: trial.id.clone() };
+            match approvals::request(step, &summary, &policy, &format!("trial {}", trial.id)) {
+                Ok(approval) => {
+                    trial.status = TrialStatus::AwaitingApproval;
+                    trial.approval_id = Some(approval.id);
+                }
+                Err(e) => log_error!("[Sandbox] Failed to ask for approval of trial {}: {}", trial.id, e),
+            }
+        }
+        if let Err(e) = put_typed(TRIALS_COLLECTION, &trial.id, &trial) {
+            log_error!("[Sandbox] Failed to save trial {}: {}", trial.id, e);
+        }
+        log_info!("[Sandbox] Trial {} finished with {} matches", trial.id, trial.match_count);
+        publish("rule.trial_finished", json!({
+            "trial": trial.id,
+            "rule": trial.rule.id,
+            "report": trial.report,
+        }));
+    });
+}
+
+/// Resume trials that were observing when the process stopped (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        for trial in list_trials().into_iter().filter(|trial| trial.status == TrialStatus::Observing) {
+            observe(trial);
+        }
+    });
+}
+
+/// All trials, oldest first
+pub fn list_trials() -> Vec<Trial> {
+    list_typed(TRIALS_COLLECTION).unwrap_or_else(|e| {
+        log_warn!("[Sandbox] Failed to load trials: {}", e);
+        Vec::new()
+    })
+}
+
+pub fn get_trial(id: &str) -> anyhow::Result<Option<Trial>> {
+    get_typed(TRIALS_COLLECTION, id)
+}
+
+/// Save a finished trial's draft as an active rule
+pub fn promote(id: &str) -> anyhow::Result<Trial> {
+    let mut trial = get_trial(id)?.ok_or_else(|| anyhow::anyhow!("unknown trial {}", id))?;
+    anyhow::ensure!(trial.status == TrialStatus::Finished, "trial {} is {:?}, not finished", id, trial.status);
+    promote_trial(trial, &format!("trial {}", id))
+}
+
+fn promote_trial(mut trial: Trial, author: &str) -> anyhow::Result<Trial> {
+    save_promoted(&PatternRule { enabled: true, ..trial.rule.clone() }, author)?;
+    trial.status = TrialStatus::Promoted;
+    put_typed(TRIALS_COLLECTION, &trial.id, &trial)?;
+    log_info!("[Sandbox] Promoted rule {} from trial {}", trial.rule.id, trial.id);
+    Ok(trial)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_promoted(rule: &PatternRule, author: &str) -> anyhow::Result<()> {
+    crate::rule_store::save_rule(rule, author)
+}
+
+/// Rules are saved on the server
+#[cfg(target_arch = "wasm32")]
+fn save_promoted(_rule: &PatternRule, _author: &str) -> anyhow::Result<()> {
+    anyhow::bail!("rules can only be saved on the server")
+}
+
+/// Promote (if `approved`) or reject a trial that was waiting for approval
+pub fn resume_after_approval(id: &str, approved: bool, author: &str) -> anyhow::Result<Trial> {
+    let mut trial = get_trial(id)?.ok_or_else(|| anyhow::anyhow!("trial {} was discarded", id))?;
+    anyhow::ensure!(
+        trial.status == TrialStatus::AwaitingApproval,
+        "trial {} is {:?}, not awaiting approval", id, trial.status
+    );
+    if approved {
+        return promote_trial(trial, &format!("trial {} approved by {}", id, author));
+    }
+    trial.status = TrialStatus::Rejected;
+    put_typed(TRIALS_COLLECTION, &trial.id, &trial)?;
+    log_info!("[Sandbox] Promotion of rule {} from trial {} rejected by {}", trial.rule.id, trial.id, author);
+    Ok(trial)
+}
+
+/// Discard a trial (stops it if still observing); returns whether it existed
+pub fn discard(id: &str) -> anyhow::Result<bool> {
+    storage().delete(TRIALS_COLLECTION, id)
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::events::publish;
+use crate::mailbox::Priority;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Scheduler
+// ============================================================================
+//
+// A schedule sends the same data to one agent at a fixed interval, as in
+// "send this to Agent3 every 30s", which turns the agent pool into a small
+// job runner. Schedules are registered, listed and cancelled under
+// `/api/schedule` and stored, so they survive restarts; a schedule that was
+// due while the server was down fires once when it comes back and then keeps
+// its interval.
+//
+// Every `TICK` the leader sends the due schedules' data as `ProcessData`
+// through the agent's queue, so the runs show up as jobs like any other
+// request. Each run is published as `schedule.fired`, or `schedule.failed`
+// if the agent is gone or its mailbox is full; a failed run is not retried
+// before the next one is due. Only the leader fires schedules, so instances
+// sharing a database don't send every run twice.
+
+/// Collection holding schedules, keyed by schedule id
+pub const SCHEDULES_COLLECTION: &str = "schedules";
+
+/// How often due schedules are looked for, and so the finest interval
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub agent_id: u8,
+    /// Sent to the agent as `ProcessData` on every run
+    pub data: String,
+    #[serde(default)]
+    pub priority: Priority,
+    pub every_secs: u64,
+    pub created_by: String,
+    /// Milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub next_run: u64,
+    pub last_run: Option<u64>,
+    /// Runs the agent accepted
+    #[serde(default)]
+    pub runs: u64,
+    /// Runs that could not be sent
+    #[serde(default)]
+    pub failures: u64,
+    pub last_error: Option<String>,
+}
+
+/// Send `data` to agent `agent_id` every `every`, starting one interval from now
+pub fn register(agent_id: u8, data: String, priority: Priority, every: Duration, author: &str) -> anyhow::Result<Schedule> {
+    anyhow::ensure!(agent_id != 0, "agent ids start at 1");
+    anyhow::ensure!(every >= TICK, "schedule interval must be at least {} s", TICK.as_secs());
+    anyhow::ensure!(priority != Priority::Control, "control priority is reserved for control messages");
+    let now = now_millis();
+    let every_secs = every.as_secs();
+    let schedule = Schedule {
+        id: format!("{:013}-agent{}", now, agent_id),
+        agent_id,
+        data,
+        priority,
+        every_secs,
+        created_by: author.to_string(),
+        created_at: now,
+        next_run: now + every_secs * 1000,
+        last_run: None,
+        runs: 0,
+        failures: 0,
+        last_error: None,
+    };
+    put_typed(SCHEDULES_COLLECTION, &schedule.id, &schedule)?;
+    log_info!("[Scheduler] {} sends to Agent{} every {} s", schedule.id, agent_id, every_secs);
+    publish("schedule.registered", json!(schedule));
+    Ok(schedule)
+}
+
+/// All active schedules, oldest first
+pub fn list_schedules() -> Vec<Schedule> {
+    list_typed(SCHEDULES_COLLECTION).unwrap_or_else(|e| {
+        log_warn!("[Scheduler] Failed to load schedules: {}", e);
+        Vec::new()
+    })
+}
+
+pub fn get_schedule(id: &str) -> anyhow::Result<Option<Schedule>> {
+    get_typed(SCHEDULES_COLLECTION, id)
+}
+
+/// Stop and delete a schedule; runs already queued are still handled
+pub fn cancel(id: &str, author: &str) -> anyhow::Result<Schedule> {
+    let schedule = get_schedule(id)?.ok_or_else(|| anyhow::anyhow!("unknown schedule {}", id))?;
+    storage().delete(SCHEDULES_COLLECTION, id)?;
+    log_info!("[Scheduler] {} cancelled by {}", id, author);
+    publish("schedule.cancelled", json!({ "schedule": schedule, "by": author }));
+    Ok(schedule)
+}
+
+/// Queue one run of `schedule` and work out when the next one is due
+fn fire(mut schedule: Schedule, now: u64) -> Schedule {
+    let sent = match crate::agents::get_agent(schedule.agent_id) {
+        Some(actor_ref) => {
+            let message = crate::agents::AgentMessage::ProcessData {
+                data: schedule.data.clone(),
+                priority: schedule.priority,
+            };
+            actor_ref.send_message(message.queued(schedule.agent_id)).map_err(|e| e.to_string())
+        }
+        None => Err(format!("Agent{} is not available", schedule.agent_id)),
+    };
+    schedule.last_run = Some(now);
+    match &sent {
+        Ok(()) => {
+            schedule.runs += 1;
+            schedule.last_error = None;
+        }
+        Err(e) => {
+            log_warn!("[Scheduler] {} could not reach Agent{}: {}", schedule.id, schedule.agent_id, e);
+            schedule.failures += 1;
+            schedule.last_error = Some(e.clone());
+        }
+    }
+
+    // Runs missed while the server was down or busy are skipped, not caught up
+    let every = schedule.every_secs.max(1) * 1000;
+    let behind = now.saturating_sub(schedule.next_run) / every + 1;
+    schedule.next_run += behind * every;
+
+    let topic = if sent.is_ok() { "schedule.fired" } else { "schedule.failed" };
+    publish(topic, json!({
+        "id": schedule.id,
+        "agent_id": schedule.agent_id,
+        "runs": schedule.runs,
+        "next_run": schedule.next_run,
+        "error": sent.err(),
+    }));
+    schedule
+}
+
+/// Fire due schedules (once; requires a Tokio runtime)
+pub fn ensure_started() {
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        crate::threads::spawn_background(async move {
+            let mut ticks = tokio::time::interval(TICK);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                if !crate::leader::is_leader() {
+                    continue;
+                }
+                let now = now_millis();
+                for schedule in list_schedules().into_iter().filter(|schedule| schedule.next_run <= now) {
+                    let id = schedule.id.clone();
+                    // Cancelled since the list was read: don't write it back
+                    if !matches!(get_schedule(&id), Ok(Some(_))) {
+                        continue;
+                    }
+                    let schedule = fire(schedule, now);
+                    if let Err(e) = put_typed(SCHEDULES_COLLECTION, &id, &schedule) {
+                        log_error!("[Scheduler] Failed to save {}: {}", id, e);
+                    }
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::payload::Payload;
+use crate::storage::{get_typed, list_typed, now_millis, put_typed, storage};
+
+// ============================================================================
+// Payload Schemas
+// ============================================================================
+//
+// An agent can be given a JSON Schema; every payload sent to it through the
+// API (`/api/agents/:id/process`, `/api/agents/:id/payload`, the legacy
+// per-agent routes and the `process_agent` MCP tool) must then be a JSON
+// document matching it. Rejected payloads never reach the agent, so they
+// can't end up in prompts, stored events or training data. Errors carry the
+// JSON pointer of each offending value:
+//
+//     payload rejected by the schema of Agent2: /temperature: "hot" is not of type "number"
+//
+// Agents without a schema accept anything, as before.
+
+/// Collection holding schemas, keyed by agent id
+pub const SCHEMAS_COLLECTION: &str = "schemas";
+
+/// A JSON Schema attached to an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentSchema {
+    pub agent_id: u8,
+    pub schema: Value,
+    /// Milliseconds since the Unix epoch
+    pub updated_at: u64,
+}
+
+/// One value that does not match the schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer into the payload (empty for the document itself)
+    pub path: String,
+    pub message: String,
+}
+
+/// Why a payload was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub agent_id: u8,
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "payload rejected by the schema of Agent{}: ", self.agent_id)?;
+        let violations: Vec<String> = self.violations.iter()
+            .map(|v| if v.path.is_empty() { v.message.clone() } else { format!("{}: {}", v.path, v.message) })
+            .collect();
+        write!(f, "{}", violations.join("; "))
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Compiled validators by agent id, dropped whenever a schema changes
+fn validators() -> &'static Mutex<HashMap<u8, Arc<jsonschema::Validator>>> {
+    static VALIDATORS: OnceLock<Mutex<HashMap<u8, Arc<jsonschema::Validator>>>> = OnceLock::new();
+    VALIDATORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn compile(schema: &Value) -> anyhow::Result<jsonschema::Validator> {
+    jsonschema::validator_for(schema).map_err(|e| anyhow::anyhow!("invalid JSON Schema: {}", e))
+}
+
+/// All saved schemas
+pub fn list_schemas() -> anyhow::Result<Vec<AgentSchema>> {
+    list_typed(SCHEMAS_COLLECTION)
+}
+
+/// Schema of one agent, if it has one
+pub fn get_schema(agent_id: u8) -> anyhow::Result<Option<AgentSchema>> {
+    get_typed(SCHEMAS_COLLECTION, &agent_id.to_string())
+}
+
+/// Check that `schema` compiles, then save it for `agent_id` (replacing any previous one)
+pub fn set_schema(agent_id: u8, schema: Value) -> anyhow::Result<AgentSchema> {
+    let validator = compile(&schema)?;
+    let entry = AgentSchema { agent_id, schema, updated_at: now_millis() };
+    put_typed(SCHEMAS_COLLECTION, &agent_id.to_string(), &entry)?;
+    validators().lock().unwrap().insert(agent_id, Arc::new(validator));
+    log_info!("[Schemas] Schema set for Agent{}", agent_id);
+    Ok(entry)
+}
+
+/// Remove an agent's schema, returning whether it had one
+pub fn delete_schema(agent_id: u8) -> anyhow::Result<bool> {
+    validators().lock().unwrap().remove(&agent_id);
+    storage().delete(SCHEMAS_COLLECTION, &agent_id.to_string())
+}
+
+fn validator(agent_id: u8) -> anyhow::Result<Option<Arc<jsonschema::Validator>>> {
+    if let Some(validator) = validators().lock().unwrap().get(&agent_id) {
+        return Ok(Some(validator.clone()));
+    }
+    let Some(entry) = get_schema(agent_id)? else {
+        return Ok(None);
+    };
+    let validator = Arc::new(compile(&entry.schema)?);
+    validators().lock().unwrap().insert(agent_id, validator.clone());
+    Ok(Some(validator))
+}
+
+/// Validate a payload for `agent_id`; `Ok` when the agent has no schema
+///
+/// Text payloads must be JSON text; binary payloads must be `application/json`
+/// (or `+json`) UTF-8.
+pub fn validate_payload(agent_id: u8, payload: &Payload) -> anyhow::Result<()> {
+    let Some(validator) = validator(agent_id)? else {
+        return Ok(());
+    };
+    let rejected = |message: String| SchemaError {
+        agent_id,
+        violations: vec![SchemaViolation { path: String::new(), message }],
+    };
+
+    let text = match payload {
+        Payload::Text { text } => text.as_str(),
+        Payload::Binary { content_type, data } => {
+            let essence = content_type.split(';').next().unwrap_or_default().trim();
+            if essence != "application/json" && !essence.ends_with("+json") {
+                return Err(rejected(format!("expected a JSON payload, got {}", content_type)).into());
+            }
+            std::str::from_utf8(data).map_err(|_| rejected("payload is not UTF-8".to_string()))?
+        }
+    };
+    let document: Value = serde_json::from_str(text)
+        .map_err(|e| rejected(format!("payload is not JSON: {}", e)))?;
+
+    let violations: Vec<SchemaViolation> = validator.iter_errors(&document)
+        .map(|error| SchemaViolation { path: error.instance_path.to_string(), message: error.to_string() })
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(SchemaError { agent_id, violations }.into())
+    }
+}
+
+/// `validate_payload` for plain text data
+pub fn validate_text(agent_id: u8, data: &str) -> anyhow::Result<()> {
+    validate_payload(agent_id, &Payload::text(data))
+}
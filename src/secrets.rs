@@ -0,0 +1,92 @@
+use std::fmt;
+
+// ============================================================================
+// Secrets
+// ============================================================================
+//
+// API keys and webhook tokens are given as references instead of plaintext
+// wherever a secret is configured:
+//
+//   env:NAME              value of another environment variable
+//   file:/run/secrets/x   contents of a file (Docker / Kubernetes secrets)
+//   keyring:service/user  OS keyring entry (desktop builds with `keyring`)
+//
+// Anything else is taken literally. For environment variables, `NAME_FILE`
+// is honored as well (`OLLAMA_API_KEY_FILE=/run/secrets/ollama`).
+
+/// A resolved secret; never shown by `Debug` or `Display`
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// The secret value, for the one place that needs it (e.g. an auth header)
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+/// Resolve a secret reference (`env:`, `file:`, `keyring:` or a literal value)
+pub fn resolve(reference: &str) -> anyhow::Result<Secret> {
+    if let Some(name) = reference.strip_prefix("env:") {
+        return std::env::var(name)
+            .map(Secret)
+            .map_err(|_| anyhow::anyhow!("environment variable {} is not set", name));
+    }
+    if let Some(path) = reference.strip_prefix("file:") {
+        return read_secret_file(path);
+    }
+    if let Some(entry) = reference.strip_prefix("keyring:") {
+        return keyring_secret(entry);
+    }
+    Ok(Secret(reference.to_string()))
+}
+
+/// Secret from `NAME` (itself a reference) or from the file named by `NAME_FILE`
+pub fn from_env(name: &str) -> anyhow::Result<Option<Secret>> {
+    if let Ok(reference) = std::env::var(name) {
+        if !reference.is_empty() {
+            return resolve(&reference).map(Some);
+        }
+    }
+    match std::env::var(format!("{}_FILE", name)) {
+        Ok(path) if !path.is_empty() => read_secret_file(&path).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn read_secret_file(path: &str) -> anyhow::Result<Secret> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read secret file {}: {}", path, e))?;
+    // Secret files usually end with a newline that is not part of the value
+    Ok(Secret(contents.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+#[cfg(all(feature = "keyring", not(target_arch = "wasm32")))]
+fn keyring_secret(entry: &str) -> anyhow::Result<Secret> {
+    let (service, user) = entry.split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("keyring reference must be keyring:service/user, got {}", entry))?;
+    let password = keyring::Entry::new(service, user)?.get_password()
+        .map_err(|e| anyhow::anyhow!("failed to read keyring entry {}: {}", entry, e))?;
+    Ok(Secret(password))
+}
+
+#[cfg(not(all(feature = "keyring", not(target_arch = "wasm32"))))]
+fn keyring_secret(entry: &str) -> anyhow::Result<Secret> {
+    anyhow::bail!("keyring entry {} requested, but this build has no keyring support (feature `keyring`)", entry)
+}
@@ -0,0 +1,164 @@
+use burn::backend::wgpu::Wgpu;
+use burn::module::Module;
+use burn::nn::{Embedding, EmbeddingConfig, Linear, LinearConfig};
+use burn::record::CompactRecorder;
+use burn::tensor::backend::Backend;
+use burn::tensor::{Int, Tensor, TensorData};
+use std::sync::Mutex;
+
+use crate::lstm::{Lstm, LstmConfig};
+
+// ============================================================================
+// Sequence-to-Sequence Model (LSTM encoder-decoder)
+// ============================================================================
+
+/// Default location of trained sequence-to-sequence weights (see `registry`)
+pub const SEQ2SEQ_WEIGHTS_PATH: &str = "models/seq2seq";
+
+/// Token used to pad sequences in a batch (ignored by the loss)
+pub const PAD_TOKEN: usize = 0;
+/// Token fed to the decoder as the first input
+pub const START_TOKEN: usize = 1;
+/// Token marking the end of a decoded sequence
+pub const END_TOKEN: usize = 2;
+/// First id available for event/action tokens
+pub const FIRST_EVENT_TOKEN: usize = 3;
+
+/// Configuration for the sequence-to-sequence model
+#[derive(Debug, Clone)]
+pub struct Seq2SeqConfig {
+    /// Number of distinct tokens (including the reserved ones)
+    pub vocab_size: usize,
+    /// Token embedding dimension
+    pub embedding_size: usize,
+    /// Hidden state dimension shared by encoder and decoder
+    pub hidden_size: usize,
+    /// Maximum number of tokens produced by `generate`
+    pub max_output_len: usize,
+}
+
+impl Default for Seq2SeqConfig {
+    fn default() -> Self {
+        Self {
+            vocab_size: 64,
+            embedding_size: 32,
+            hidden_size: 128,
+            max_output_len: 16,
+        }
+    }
+}
+
+/// Encoder-decoder model mapping one event sequence to another
+#[derive(Module, Debug)]
+pub struct Seq2Seq<B: Backend> {
+    embedding: Embedding<B>,
+    encoder: Lstm<B>,
+    decoder: Lstm<B>,
+    output: Linear<B>,
+    max_output_len: usize,
+}
+
+impl<B: Backend> Seq2Seq<B> {
+    /// Create a new (untrained) model
+    pub fn new(config: &Seq2SeqConfig, device: &B::Device) -> Self {
+        let lstm_config = LstmConfig {
+            input_size: config.embedding_size,
+            hidden_size: config.hidden_size,
+            ..LstmConfig::default()
+        };
+
+        Self {
+            embedding: EmbeddingConfig::new(config.vocab_size, config.embedding_size).init(device),
+            encoder: Lstm::new(lstm_config.clone(), device),
+            decoder: Lstm::new(lstm_config, device),
+            output: LinearConfig::new(config.hidden_size, config.vocab_size).init(device),
+            max_output_len: config.max_output_len,
+        }
+    }
+
+    /// Teacher-forced forward pass
+    ///
+    /// # Arguments
+    /// * `source` - Source token ids [batch_size, source_length]
+    /// * `target_input` - Decoder inputs (target shifted right, starting with START_TOKEN) [batch_size, target_length]
+    ///
+    /// # Returns
+    /// * Logits of shape [batch_size, target_length, vocab_size]
+    pub fn forward(&self, source: Tensor<B, 2, Int>, target_input: Tensor<B, 2, Int>) -> Tensor<B, 3> {
+        let (_, encoder_state) = self.encoder.forward(self.embedding.forward(source), None);
+        let (decoded, _) = self.decoder.forward(self.embedding.forward(target_input), Some(encoder_state));
+        self.output.forward(decoded)
+    }
+
+    /// Greedily decode an output sequence for a single source sequence
+    pub fn generate(&self, source: &[usize], device: &B::Device) -> Vec<usize> {
+        let source = tokens_to_tensor::<B>(&[source.to_vec()], device);
+        let (_, mut state) = self.encoder.forward(self.embedding.forward(source), None);
+
+        let mut output = Vec::new();
+        let mut previous = START_TOKEN;
+        for _ in 0..self.max_output_len {
+            let input = tokens_to_tensor::<B>(&[vec![previous]], device);
+            let (decoded, next_state) = self.decoder.forward(self.embedding.forward(input), Some(state));
+            state = next_state;
+
+            let logits = self.output.forward(decoded);
+            let next: Vec<i64> = logits.argmax(2).into_data().convert::<i64>().to_vec().unwrap_or_default();
+            let token = next.first().copied().unwrap_or(END_TOKEN as i64) as usize;
+            if token == END_TOKEN || token == PAD_TOKEN {
+                break;
+            }
+            output.push(token);
+            previous = token;
+        }
+        output
+    }
+}
+
+/// Model instance plus the config it was built with
+struct LoadedSeq2Seq {
+    config: Seq2SeqConfig,
+    model: Seq2Seq<Wgpu>,
+}
+
+fn load_seq2seq() -> Mutex<LoadedSeq2Seq> {
+    let device = Default::default();
+    let config = Seq2SeqConfig::default();
+    let model = Seq2Seq::<Wgpu>::new(&config, &device);
+
+    let model = match model.clone().load_file(SEQ2SEQ_WEIGHTS_PATH, &CompactRecorder::new(), &device) {
+        Ok(trained) => {
+            log_info!("[Seq2Seq] Loaded weights from {}", SEQ2SEQ_WEIGHTS_PATH);
+            trained
+        }
+        Err(_) => {
+            log_warn!("[Seq2Seq] No trained weights at {}, using untrained model", SEQ2SEQ_WEIGHTS_PATH);
+            model
+        }
+    };
+
+    Mutex::new(LoadedSeq2Seq { config, model })
+}
+
+/// Predict the event sequence following `source` with the shared model
+pub fn predict_sequence(source: &[usize]) -> anyhow::Result<Vec<usize>> {
+    let seq2seq = crate::model_pool::get_or_load("seq2seq", load_seq2seq);
+    let seq2seq = seq2seq.lock().unwrap();
+    let vocab_size = seq2seq.config.vocab_size;
+    anyhow::ensure!(!source.is_empty(), "source sequence is empty");
+    if let Some(token) = source.iter().copied().find(|token| !(FIRST_EVENT_TOKEN..vocab_size).contains(token)) {
+        anyhow::bail!("token {} is outside {}..{}", token, FIRST_EVENT_TOKEN, vocab_size);
+    }
+    Ok(seq2seq.model.generate(source, &Default::default()))
+}
+
+/// Build a padded [batch_size, max_length] Int tensor from token sequences
+pub fn tokens_to_tensor<B: Backend>(sequences: &[Vec<usize>], device: &B::Device) -> Tensor<B, 2, Int> {
+    let max_len = sequences.iter().map(|s| s.len()).max().unwrap_or(0).max(1);
+    let mut values = Vec::with_capacity(sequences.len() * max_len);
+    for sequence in sequences {
+        values.extend(sequence.iter().map(|&t| t as i64));
+        values.extend(std::iter::repeat_n(PAD_TOKEN as i64, max_len - sequence.len()));
+    }
+    Tensor::from_data(TensorData::new(values, [sequences.len(), max_len]), device)
+}
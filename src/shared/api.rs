@@ -2,17 +2,45 @@
 
 use dioxus::prelude::*;
 use crate::agents::{get_agent, ensure_agents_initialized};
+use crate::fanout::{Fanout, LagPolicy};
 use std::sync::OnceLock;
 use tokio::sync::broadcast;
 
-// MCP broadcast channel for streaming results to web clients
-static MCP_BROADCASTER: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+/// MCP results kept for web clients that subscribed with `spill_to_history`
+const MCP_HISTORY: usize = 1000;
+/// MCP results kept for `drop_oldest` / `disconnect` subscribers
+const MCP_SUBSCRIBER_CAPACITY: usize = 100;
 
-fn get_mcp_broadcaster() -> broadcast::Sender<String> {
-    MCP_BROADCASTER.get_or_init(|| {
-        let (tx, _) = broadcast::channel(100);
-        tx
-    }).clone()
+// MCP fan-out channel for streaming results to web clients
+static MCP_RESULTS: OnceLock<Fanout<String>> = OnceLock::new();
+
+fn mcp_results() -> &'static Fanout<String> {
+    MCP_RESULTS.get_or_init(|| {
+        #[cfg(feature = "server")]
+        forward_agent_events();
+        Fanout::new("mcp", MCP_HISTORY, MCP_SUBSCRIBER_CAPACITY)
+    })
+}
+
+/// Publish agent events on the MCP channel as JSON, leaving out bookkeeping messages
+#[cfg(feature = "server")]
+fn forward_agent_events() {
+    let mut events = crate::agent_events::subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) if crate::agent_metrics::is_internal(&event.kind) => {}
+                Ok(event) => match serde_json::to_string(&*event) {
+                    Ok(json) => mcp_results().publish(json),
+                    Err(e) => log_warn!("[MCP] Failed to serialize agent event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log_warn!("[MCP] Agent event stream lagged, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
 }
 
 /// Echo the user input on the server.
@@ -24,14 +52,165 @@ pub async fn echo_server(input: String) -> Result<String, ServerFnError> {
     // For backward compatibility, send to Agent1
     if let Some(actor_ref) = get_agent(1) {
         use crate::agents::AgentMessage;
-        let _ = actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: input.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(1, e))?;
     }
     
     Ok(input)
 }
 
+// ============================================================================
+// Service Endpoints
+// ============================================================================
+
+/// Build information, for deployment checks and container probes
+///
+/// `PATTERN_CLOCK_GIT_SHA` and `PATTERN_CLOCK_BUILD_DATE` are read at compile
+/// time, so image builds can stamp them with `--build-arg`. The client address
+/// and scheme as seen through the proxy headers help check a proxy setup.
+#[get("/api/version", headers: dioxus::fullstack::HeaderMap)]
+pub async fn version() -> Result<String, ServerFnError> {
+    Ok(serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_sha": option_env!("PATTERN_CLOCK_GIT_SHA").unwrap_or("unknown"),
+        "build_date": option_env!("PATTERN_CLOCK_BUILD_DATE").unwrap_or("unknown"),
+        "profile": if cfg!(debug_assertions) { "debug" } else { "release" },
+        "features": enabled_features(),
+        "client_ip": client_ip(&headers),
+        "client_scheme": client_scheme(&headers),
+    }).to_string())
+}
+
+#[cfg(feature = "server")]
+fn enabled_features() -> Vec<&'static str> {
+    [
+        ("server", cfg!(feature = "server")),
+        ("chaos", cfg!(feature = "chaos")),
+        ("testing", cfg!(feature = "testing")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+/// Value of a reverse-proxy header, if proxies are trusted
+///
+/// Only honored with `PATTERN_CLOCK_TRUST_PROXY`, since any client can send
+/// these headers when the server is exposed directly.
+#[cfg(feature = "server")]
+fn forwarded(headers: &dioxus::fullstack::HeaderMap, name: &str) -> Option<String> {
+    let config = crate::config::config();
+    if !config.trust_proxy {
+        return None;
+    }
+    forwarded_entry(headers, name, config.proxy_hops)
+}
+
+/// The entry `hops` from the right of a forwarding header
+///
+/// Each proxy appends to the comma-separated list, either on the same line or
+/// on a line of its own, so all lines are read in order. Entries left of the
+/// ones our `hops` proxies added come from the client and are skipped.
+#[cfg(feature = "server")]
+fn forwarded_entry(headers: &dioxus::fullstack::HeaderMap, name: &str, hops: usize) -> Option<String> {
+    let mut entries = Vec::new();
+    for value in headers.get_all(name) {
+        // An unreadable line makes the positions of the ones after it unknowable
+        entries.extend(value.to_str().ok()?.split(',').map(str::trim));
+    }
+    // Fewer entries than proxies: the first proxy was bypassed or dropped the header
+    let entry = entries.len().checked_sub(hops.max(1)).map(|index| entries[index])?;
+    Some(entry.to_string()).filter(|entry| !entry.is_empty())
+}
+
+/// Original client address from reverse-proxy headers
+#[cfg(feature = "server")]
+fn client_ip(headers: &dioxus::fullstack::HeaderMap) -> Option<String> {
+    forwarded(headers, "x-forwarded-for").or_else(|| forwarded(headers, "x-real-ip"))
+}
+
+/// Scheme the client used (`https` when TLS ends at the proxy), from `X-Forwarded-Proto`
+#[cfg(feature = "server")]
+fn client_scheme(headers: &dioxus::fullstack::HeaderMap) -> Option<String> {
+    forwarded(headers, "x-forwarded-proto")
+}
+
+/// Deadline from the caller's `X-Request-Timeout-Ms` header
+#[cfg(feature = "server")]
+fn request_deadline(headers: &dioxus::fullstack::HeaderMap) -> Option<crate::deadline::Deadline> {
+    let timeout_ms = headers.get(crate::deadline::TIMEOUT_HEADER)?
+        .to_str().ok()?
+        .trim()
+        .parse::<u64>().ok()?;
+    Some(crate::deadline::Deadline::after(std::time::Duration::from_millis(timeout_ms)))
+}
+
+/// Who made a change: the `X-Author` header, else the client address, else "api"
+#[cfg(feature = "server")]
+fn request_author(headers: &dioxus::fullstack::HeaderMap) -> String {
+    headers.get(crate::rules::AUTHOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|author| !author.is_empty())
+        .map(str::to_string)
+        .or_else(|| client_ip(headers))
+        .unwrap_or_else(|| "api".to_string())
+}
+
+/// Reject data that doesn't match the agent's JSON Schema, if it has one (see `schemas`)
+#[cfg(feature = "server")]
+fn check_schema(agent_id: u8, payload: &crate::payload::Payload) -> Result<(), ServerFnError> {
+    crate::schemas::validate_payload(agent_id, payload).map_err(|e| ServerFnError::new(e.to_string()))
+}
+
+/// A message agent `agent_id` didn't take; a full mailbox is a 429 with the queue depth
+#[cfg(feature = "server")]
+fn queue_error(agent_id: u8, e: crate::mailbox::SendError) -> ServerFnError {
+    match &e {
+        crate::mailbox::SendError::Full { depth, capacity, .. } => ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 429,
+            details: Some(serde_json::json!({ "agent_id": agent_id, "depth": depth, "capacity": capacity })),
+        },
+        crate::mailbox::SendError::Draining { .. } => ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 503,
+            details: Some(serde_json::json!({ "agent_id": agent_id, "state": crate::mailbox::lifecycle(agent_id) })),
+        },
+        crate::mailbox::SendError::Closed(_) => {
+            ServerFnError::new(format!("Failed to queue message for Agent{}: {}", agent_id, e))
+        }
+    }
+}
+
+/// A refused time-series write: 429 with the retry delay when rate limited,
+/// 400 for bad input, 500 when storage failed
+#[cfg(feature = "server")]
+fn append_error(context: String, e: crate::timeseries::AppendError) -> ServerFnError {
+    use crate::timeseries::AppendError;
+
+    let message = format!("{}: {}", context, e);
+    match e {
+        AppendError::RateLimited { retry_after_ms } => ServerFnError::ServerError {
+            message,
+            code: 429,
+            details: Some(serde_json::json!({ "retry_after_ms": retry_after_ms })),
+        },
+        AppendError::Invalid(_) => ServerFnError::ServerError { message, code: 400, details: None },
+        AppendError::Storage(_) => ServerFnError::new(message),
+    }
+}
+
+/// A message another node didn't take, with the status code it would have answered
+#[cfg(all(feature = "server", feature = "cluster"))]
+fn refusal_error(refusal: crate::cluster::Refusal) -> ServerFnError {
+    ServerFnError::ServerError { message: refusal.message, code: refusal.code, details: refusal.details }
+}
+
 // ============================================================================
 // HTTP/REST API Endpoints for Multi-Agent System
 // ============================================================================
@@ -41,12 +220,14 @@ pub async fn echo_server(input: String) -> Result<String, ServerFnError> {
 pub async fn process_agent1(data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    check_schema(1, &crate::payload::Payload::text(data.clone()))?;
     
     if let Some(actor_ref) = get_agent(1) {
         use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: data.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(1, e))?;
         Ok(format!("Message queued for Agent1: {}", data))
     } else {
         Err(ServerFnError::new("Agent1 is not available"))
@@ -58,12 +239,14 @@ pub async fn process_agent1(data: String) -> Result<String, ServerFnError> {
 pub async fn process_agent2(data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    check_schema(2, &crate::payload::Payload::text(data.clone()))?;
     
     if let Some(actor_ref) = get_agent(2) {
         use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: data.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(2, e))?;
         Ok(format!("Message queued for Agent2: {}", data))
     } else {
         Err(ServerFnError::new("Agent2 is not available"))
@@ -75,12 +258,14 @@ pub async fn process_agent2(data: String) -> Result<String, ServerFnError> {
 pub async fn process_agent3(data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    check_schema(3, &crate::payload::Payload::text(data.clone()))?;
     
     if let Some(actor_ref) = get_agent(3) {
         use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: data.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(3, e))?;
         Ok(format!("Message queued for Agent3: {}", data))
     } else {
         Err(ServerFnError::new("Agent3 is not available"))
@@ -92,12 +277,14 @@ pub async fn process_agent3(data: String) -> Result<String, ServerFnError> {
 pub async fn process_agent4(data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    check_schema(4, &crate::payload::Payload::text(data.clone()))?;
     
     if let Some(actor_ref) = get_agent(4) {
         use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: data.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(4, e))?;
         Ok(format!("Message queued for Agent4: {}", data))
     } else {
         Err(ServerFnError::new("Agent4 is not available"))
@@ -109,48 +296,1429 @@ pub async fn process_agent4(data: String) -> Result<String, ServerFnError> {
 pub async fn process_agent5(data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    check_schema(5, &crate::payload::Payload::text(data.clone()))?;
     
     if let Some(actor_ref) = get_agent(5) {
         use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
+        crate::agents::send_work(&actor_ref, AgentMessage::ProcessData {
             data: data.clone(),
-        });
+            priority: crate::mailbox::Priority::Normal,
+        }).map_err(|e| queue_error(5, e))?;
         Ok(format!("Message queued for Agent5: {}", data))
     } else {
         Err(ServerFnError::new("Agent5 is not available"))
     }
 }
 
-/// Get status of a specific agent
+/// Queue a text or binary payload on any agent
+///
+/// Body: `{"payload": {"type": "binary", "content_type": "image/png", "data": "<base64>"}}`
+/// or `{"payload": {"type": "text", "text": "..."}}`.
+#[post("/api/agents/:id/payload", headers: dioxus::fullstack::HeaderMap)]
+pub async fn process_agent_payload(id: u8, payload: crate::payload::Payload) -> Result<String, ServerFnError> {
+    let received = std::time::Instant::now();
+    let deadline = request_deadline(&headers);
+    crate::telemetry::traced_request("/api/agents/:id/payload", async move {
+        ensure_agents_initialized().await
+            .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+        let actor_ref = get_agent(id)
+            .ok_or_else(|| ServerFnError::new(format!("Agent{} is not available", id)))?;
+        check_schema(id, &payload)?;
+        let description = payload.describe();
+        crate::agents::send_work(&actor_ref, crate::agents::AgentMessage::ProcessPayload { payload }.with_deadline(deadline))
+            .map_err(|e| queue_error(id, e))?;
+        crate::latency::record(crate::latency::Stage::Receive, received.elapsed());
+        Ok(format!("Payload queued for Agent{}: {}", id, description))
+    }).await
+}
+
+/// Queue one payload on every running agent, e.g. a cache-invalidation command
+///
+/// Body as for `/api/agents/:id/payload`. Returns the delivery result of
+/// each agent: `[{"agent_id": 1, "delivered": true, "job": 812}, {"agent_id":
+/// 2, "delivered": false, "error": "Agent2 mailbox is full ..."}, ...]`.
+#[post("/api/agents/broadcast")]
+pub async fn broadcast_to_agents(payload: crate::payload::Payload) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    let deliveries = crate::agents::broadcast(&payload);
+    serde_json::to_string(&deliveries)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize deliveries: {}", e)))
+}
+
+/// Ids of the running agents
+#[get("/api/agents")]
+pub async fn list_agents() -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    serde_json::to_string(&crate::agents::agent_ids())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize agents: {}", e)))
+}
+
+/// Start another agent with `id` (default: the lowest free id), optionally in `role`; returns its id
+#[post("/api/agents/spawn?id&role")]
+pub async fn spawn_agent(id: Option<u8>, role: Option<String>) -> Result<u8, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    let id = id
+        .or_else(crate::agents::next_free_id)
+        .ok_or_else(|| ServerFnError::new("No free agent id"))?;
+    match role {
+        Some(name) => {
+            let role = crate::roles::AgentRole::parse(&name).ok_or_else(|| ServerFnError::new(format!(
+                "Unknown role {:?} (use general, llm, lstm, storage or router)", name
+            )))?;
+            crate::agents::spawn_agent_as(id, role).await.map_err(ServerFnError::new)?;
+        }
+        None => {
+            crate::agents::spawn_agent(id).await.map_err(ServerFnError::new)?;
+        }
+    }
+    log_info!("[AgentRegistry] Spawned Agent{} via API", id);
+    Ok(id)
+}
+
+/// Stop an agent and drop its queued messages; returns whether it was running
+#[post("/api/agents/:id/stop")]
+pub async fn stop_agent(id: u8) -> Result<bool, ServerFnError> {
+    Ok(crate::agents::stop_agent(id))
+}
+
+/// State of one agent: processed count, last data, recent history and rolling summary
+///
+/// Waits for the agent to answer (up to `agents::STATUS_TIMEOUT`).
 #[get("/api/agents/:id/status")]
 pub async fn get_agent_status(id: u8) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
     
-    if let Some(actor_ref) = get_agent(id) {
-        use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::GetStatus);
-        Ok(format!("Status request sent to Agent{}", id))
-    } else {
-        Err(ServerFnError::new(format!("Agent{} is not available", id)))
+    let state = crate::agents::agent_status(id).await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    serde_json::to_string(&state)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize status of Agent{}: {}", id, e)))
+}
+
+/// Stop agent `id` taking work off its queue; new work is queued until it is resumed
+///
+/// Returns the agent's lifecycle state and queue depth once the pause took
+/// effect: `{"agent_id": 2, "state": "paused", "queued": 0}`.
+#[post("/api/agents/:id/pause")]
+pub async fn pause_agent(id: u8) -> Result<String, ServerFnError> {
+    change_agent_lifecycle(id, crate::agents::AgentMessage::Pause).await
+}
+
+/// Let agent `id` work through its queue again and accept new work after a drain
+#[post("/api/agents/:id/resume")]
+pub async fn resume_agent(id: u8) -> Result<String, ServerFnError> {
+    change_agent_lifecycle(id, crate::agents::AgentMessage::Resume).await
+}
+
+/// Have agent `id` finish its queued work while refusing new work (503) until resumed
+///
+/// Returns right away with the state `draining` (or `drained` if nothing was
+/// queued); poll `/api/agents/:id/lifecycle` or wait for `agent.drained`.
+#[post("/api/agents/:id/drain")]
+pub async fn drain_agent(id: u8) -> Result<String, ServerFnError> {
+    change_agent_lifecycle(id, crate::agents::AgentMessage::Drain).await
+}
+
+/// Whether agent `id` is running, paused, draining or drained, and its queue depth
+#[get("/api/agents/:id/lifecycle")]
+pub async fn get_agent_lifecycle(id: u8) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    if get_agent(id).is_none() {
+        return Err(ServerFnError::new(format!("Agent{} is not available", id)));
     }
+    serde_json::to_string(&crate::agents::lifecycle_status(id))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize lifecycle of Agent{}: {}", id, e)))
+}
+
+#[cfg(feature = "server")]
+async fn change_agent_lifecycle(id: u8, message: crate::agents::AgentMessage) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    let status = crate::agents::change_lifecycle(id, message).await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    serde_json::to_string(&status)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize lifecycle of Agent{}: {}", id, e)))
+}
+
+/// What one agent has handled: the last `limit` (default 50) of its recent messages, newest first
+///
+/// Answered by the agent like `/api/agents/:id/status`.
+#[get("/api/agents/:id/history?limit")]
+pub async fn get_agent_history(id: u8, limit: Option<usize>) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+    let state = crate::agents::agent_status(id).await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    serde_json::to_string(&state.recent_messages(limit.unwrap_or(50)))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize history of Agent{}: {}", id, e)))
+}
+
+/// History entries of one agent tagged with keyword or entity `term` (case-insensitive), oldest first
+///
+/// Answered by the agent like `/api/agents/:id/status`.
+#[get("/api/agents/:id/search?term")]
+pub async fn search_agent_history(id: u8, term: String) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+    let entries = crate::agents::search_history(id, &term).await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    serde_json::to_string(&entries)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize history of Agent{}: {}", id, e)))
+}
+
+/// Throughput, error counts and latency histogram of one agent, overall and per message kind
+///
+/// Answered by the agent like `/api/agents/:id/status`.
+#[get("/api/agents/:id/metrics")]
+pub async fn get_agent_metrics(id: u8) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+    let state = crate::agents::agent_status(id).await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    serde_json::to_string(&state.metrics.report(id))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize metrics of Agent{}: {}", id, e)))
+}
+
+/// Hand agent `id`'s conversation (history and summary) to agent `to`, e.g. from triage to a specialist
+#[post("/api/agents/:id/handoff?to&reason")]
+pub async fn hand_off_agent(id: u8, to: u8, reason: Option<String>) -> Result<String, ServerFnError> {
+    crate::telemetry::traced_request("/api/agents/:id/handoff", async move {
+        ensure_agents_initialized().await
+            .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+        let reason = reason.unwrap_or_default();
+        let receipt = crate::agents::hand_off(id, to, &reason).await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        serde_json::to_string(&receipt)
+            .map_err(|e| ServerFnError::new(format!("Failed to serialize handoff: {}", e)))
+    }).await
+}
+
+/// Subscribe agent `id` to what other agents publish on `topic`
+#[post("/api/agents/:id/subscribe?topic")]
+pub async fn subscribe_agent(id: u8, topic: String) -> Result<(), ServerFnError> {
+    send_topic_message(id, crate::agents::AgentMessage::Subscribe { topic }).await
+}
+
+#[post("/api/agents/:id/unsubscribe?topic")]
+pub async fn unsubscribe_agent(id: u8, topic: String) -> Result<(), ServerFnError> {
+    send_topic_message(id, crate::agents::AgentMessage::Unsubscribe { topic }).await
+}
+
+/// Have agent `id` publish `data` on `topic`; every other subscriber processes it
+#[post("/api/agents/:id/publish?topic")]
+pub async fn publish_from_agent(id: u8, topic: String, data: String) -> Result<(), ServerFnError> {
+    let payload = crate::payload::Payload::text(data);
+    send_topic_message(id, crate::agents::AgentMessage::Publish { topic, payload }.queued(id)).await
+}
+
+/// Topics with their subscribed agent ids
+#[get("/api/topics")]
+pub async fn list_topics() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::agents::topics())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize topics: {}", e)))
+}
+
+#[cfg(feature = "server")]
+async fn send_topic_message(id: u8, message: crate::agents::AgentMessage) -> Result<(), ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    let actor_ref = get_agent(id).ok_or_else(|| ServerFnError::new(format!("Agent{} is not available", id)))?;
+    actor_ref.send_message(message).map_err(|e| queue_error(id, e))
 }
 
 /// Process data through any agent (dynamic routing)
-#[post("/api/agents/:id/process")]
-pub async fn process_agent_dynamic(id: u8, data: String) -> Result<String, ServerFnError> {
+///
+/// With an `X-Request-Timeout-Ms` header the agent drops the message once the
+/// budget is spent and cancels LLM calls still running at that point.
+/// `priority` (`low`, `normal` or `high`, default `normal`) decides which
+/// waiting data the agent handles first.
+#[post("/api/agents/:id/process?priority", headers: dioxus::fullstack::HeaderMap)]
+pub async fn process_agent_dynamic(id: u8, priority: Option<String>, data: String) -> Result<String, ServerFnError> {
+    let received = std::time::Instant::now();
+    let deadline = request_deadline(&headers);
+    let priority = match priority.as_deref() {
+        Some(name) => crate::mailbox::Priority::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown priority {:?}: expected low, normal or high", name)))?,
+        None => crate::mailbox::Priority::Normal,
+    };
+    crate::telemetry::traced_request("/api/agents/:id/process", async move {
+        ensure_agents_initialized().await
+            .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+        // With `cluster_routing=round_robin`, every few messages go to another node
+        #[cfg(feature = "cluster")]
+        if let Some(peer) = crate::cluster::route() {
+            let queued = crate::cluster::forward(&peer, id, data, priority, deadline).await.map_err(refusal_error)?;
+            crate::latency::record(crate::latency::Stage::Receive, received.elapsed());
+            return Ok(queued);
+        }
+
+        if let Some(actor_ref) = get_agent(id) {
+            use crate::agents::AgentMessage;
+            check_schema(id, &crate::payload::Payload::text(data.clone()))?;
+            actor_ref.send_message(AgentMessage::ProcessData {
+                data: data.clone(),
+                priority,
+            }.with_deadline(deadline).queued(id))
+                .map_err(|e| queue_error(id, e))?;
+            crate::latency::record(crate::latency::Stage::Receive, received.elapsed());
+            Ok(format!("Message queued for Agent{}: {}", id, data))
+        } else {
+            Err(ServerFnError::new(format!("Agent{} is not available", id)))
+        }
+    }).await
+}
+
+// ============================================================================
+// Job Queue Endpoints
+// ============================================================================
+
+/// Pending and running jobs, oldest first, of agent `agent` or of all agents
+#[get("/api/queue?agent")]
+pub async fn list_jobs(agent: Option<u8>) -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::jobs::list(agent))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize jobs: {}", e)))
+}
+
+/// Cancel a pending job (or drop a lost one)
+#[post("/api/queue/:id/cancel")]
+pub async fn cancel_job(id: u64) -> Result<String, ServerFnError> {
+    let job = crate::jobs::cancel(id)
+        .map_err(|e| ServerFnError::new(format!("Failed to cancel job {}: {}", id, e)))?;
+    serde_json::to_string(&job)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize job: {}", e)))
+}
+
+/// Queue a job again at the back of agent `to` (default: its own agent); returns the new job
+#[post("/api/queue/:id/requeue?to")]
+pub async fn requeue_job(id: u64, to: Option<u8>) -> Result<String, ServerFnError> {
+    let job = crate::jobs::requeue(id, to)
+        .map_err(|e| ServerFnError::new(format!("Failed to requeue job {}: {}", id, e)))?;
+    serde_json::to_string(&job)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize job: {}", e)))
+}
+
+// ============================================================================
+// Pipeline Endpoints
+// ============================================================================
+
+/// Run `data` through `pipeline`, or through agents `agents` (e.g. `1,2,3,4`) unchanged
+///
+/// Returns the final output and every hop with its input, output and extraction.
+#[post("/api/pipeline/run?agents")]
+pub async fn run_pipeline(
+    agents: Option<String>,
+    data: String,
+    pipeline: Option<crate::pipeline::Pipeline>,
+) -> Result<String, ServerFnError> {
+    crate::telemetry::traced_request("/api/pipeline/run", async move {
+        ensure_agents_initialized().await
+            .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+
+        let pipeline = match (pipeline, agents) {
+            (Some(pipeline), _) => pipeline,
+            (None, Some(agents)) => {
+                let ids = agents
+                    .split(',')
+                    .map(|id| id.trim().parse::<u8>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| ServerFnError::new(format!("Invalid agent list {:?}", agents)))?;
+                crate::pipeline::Pipeline::through(&ids)
+            }
+            (None, None) => return Err(ServerFnError::new("Give a pipeline or a list of agents")),
+        };
+        let result = pipeline.run(&data).await
+            .map_err(|e| ServerFnError::new(format!("Pipeline failed at {}", e)))?;
+        serde_json::to_string(&result)
+            .map_err(|e| ServerFnError::new(format!("Failed to serialize pipeline result: {}", e)))
+    }).await
+}
+
+// ============================================================================
+// Pattern Rule Endpoints
+// ============================================================================
+
+/// List saved pattern rules
+#[get("/api/rules")]
+pub async fn list_rules() -> Result<String, ServerFnError> {
+    let rules = crate::rule_store::list_rules()
+        .map_err(|e| ServerFnError::new(format!("Failed to load rules: {}", e)))?;
+    serde_json::to_string(&rules)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize rules: {}", e)))
+}
+
+/// Validate and save a pattern rule
+#[post("/api/rules", headers: dioxus::fullstack::HeaderMap)]
+pub async fn save_rule(rule: crate::rules::PatternRule) -> Result<(), ServerFnError> {
+    crate::rule_store::save_rule(&rule, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to save rule {}: {}", rule.id, e)))
+}
+
+/// Delete a pattern rule
+#[post("/api/rules/:id/delete", headers: dioxus::fullstack::HeaderMap)]
+pub async fn delete_rule(id: String) -> Result<bool, ServerFnError> {
+    crate::rule_store::delete_rule(&id, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to delete rule {}: {}", id, e)))
+}
+
+/// Every change to a rule with its diff and author, oldest first
+#[get("/api/rules/:id/history")]
+pub async fn rule_history(id: String) -> Result<String, ServerFnError> {
+    let versions = crate::rule_store::rule_history(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load history of rule {}: {}", id, e)))?;
+    serde_json::to_string(&versions)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize history: {}", e)))
+}
+
+/// Restore a rule as it was in `version`; returns the new version
+#[post("/api/rules/:id/rollback?version", headers: dioxus::fullstack::HeaderMap)]
+pub async fn rollback_rule(id: String, version: u32) -> Result<String, ServerFnError> {
+    let restored = crate::rule_store::rollback_rule(&id, version, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to roll back rule {}: {}", id, e)))?;
+    serde_json::to_string(&restored)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize version: {}", e)))
+}
+
+/// Evaluate a saved rule against its series' history within `[from, to]`
+#[get("/api/rules/:id/preview?from&to")]
+pub async fn preview_rule(id: String, from: Option<u64>, to: Option<u64>) -> Result<String, ServerFnError> {
+    let rule = crate::rule_store::get_rule(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load rule {}: {}", id, e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Unknown rule {}", id)))?;
+    let points = crate::timeseries::query(&rule.series, from, to)
+        .map_err(|e| ServerFnError::new(format!("Failed to load series {}: {}", rule.series, e)))?;
+    let matches = rule.evaluate(&points);
+    serde_json::to_string(&matches)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize matches: {}", e)))
+}
+
+/// Try a draft rule on live events in shadow mode for `window_secs` (default one hour)
+///
+/// With `approval` the draft is promoted once an operator approves it after the window.
+#[post("/api/rules/trials")]
+pub async fn start_rule_trial(
+    rule: crate::rules::PatternRule,
+    window_secs: Option<u64>,
+    approval: Option<crate::approvals::ApprovalPolicy>,
+) -> Result<String, ServerFnError> {
+    let trial = crate::sandbox::start_trial(rule, window_secs.map(std::time::Duration::from_secs), approval)
+        .map_err(|e| ServerFnError::new(format!("Failed to start trial: {}", e)))?;
+    serde_json::to_string(&trial)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize trial: {}", e)))
+}
+
+/// All rule trials with their progress or report
+#[get("/api/rules/trials")]
+pub async fn list_rule_trials() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::sandbox::list_trials())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize trials: {}", e)))
+}
+
+/// One rule trial, including its recorded matches
+#[get("/api/rules/trials/:id")]
+pub async fn get_rule_trial(id: String) -> Result<String, ServerFnError> {
+    let trial = crate::sandbox::get_trial(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load trial {}: {}", id, e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Unknown trial {}", id)))?;
+    serde_json::to_string(&trial)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize trial: {}", e)))
+}
+
+/// Save a finished trial's draft as an active rule
+#[post("/api/rules/trials/:id/promote")]
+pub async fn promote_rule_trial(id: String) -> Result<String, ServerFnError> {
+    let trial = crate::sandbox::promote(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to promote trial {}: {}", id, e)))?;
+    serde_json::to_string(&trial)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize trial: {}", e)))
+}
+
+/// Discard a rule trial, stopping it if still observing
+#[post("/api/rules/trials/:id/delete")]
+pub async fn delete_rule_trial(id: String) -> Result<bool, ServerFnError> {
+    crate::sandbox::discard(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete trial {}: {}", id, e)))
+}
+
+// ============================================================================
+// Approval Endpoints
+// ============================================================================
+
+/// Approvals, oldest first; only those waiting for a decision with `pending=true`
+#[get("/api/approvals?pending")]
+pub async fn list_approvals(pending: Option<bool>) -> Result<String, ServerFnError> {
+    let approvals = if pending.unwrap_or(false) {
+        crate::approvals::pending()
+    } else {
+        crate::approvals::list_approvals()
+    };
+    serde_json::to_string(&approvals)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize approvals: {}", e)))
+}
+
+#[get("/api/approvals/:id")]
+pub async fn get_approval(id: String) -> Result<String, ServerFnError> {
+    let approval = crate::approvals::get_approval(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load approval {}: {}", id, e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Unknown approval {}", id)))?;
+    serde_json::to_string(&approval)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize approval: {}", e)))
+}
+
+/// Approve a pending approval; the paused step resumes
+#[post("/api/approvals/:id/approve?comment", headers: dioxus::fullstack::HeaderMap)]
+pub async fn approve(id: String, comment: Option<String>) -> Result<String, ServerFnError> {
+    decide_approval(&id, true, &request_author(&headers), comment)
+}
+
+/// Reject a pending approval; the paused step is aborted
+#[post("/api/approvals/:id/reject?comment", headers: dioxus::fullstack::HeaderMap)]
+pub async fn reject(id: String, comment: Option<String>) -> Result<String, ServerFnError> {
+    decide_approval(&id, false, &request_author(&headers), comment)
+}
+
+#[cfg(feature = "server")]
+fn decide_approval(id: &str, approve: bool, author: &str, comment: Option<String>) -> Result<String, ServerFnError> {
+    let approval = crate::approvals::decide(id, approve, author, comment)
+        .map_err(|e| ServerFnError::new(format!("Failed to decide approval {}: {}", id, e)))?;
+    serde_json::to_string(&approval)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize approval: {}", e)))
+}
+
+// ============================================================================
+// Schedule Endpoints
+// ============================================================================
+
+/// Send the body to agent `agent` every `every` (`30s`, `5m`, `1h`, `1d`) at `priority`
+#[post("/api/schedule?agent&every&priority", headers: dioxus::fullstack::HeaderMap)]
+pub async fn register_schedule(agent: u8, every: String, priority: Option<String>, data: String) -> Result<String, ServerFnError> {
     ensure_agents_initialized().await
         .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
-    
-    if let Some(actor_ref) = get_agent(id) {
-        use crate::agents::AgentMessage;
-        actor_ref.send_message(AgentMessage::ProcessData {
-            data: data.clone(),
+    let interval = crate::retention::parse_age(&every)
+        .ok_or_else(|| ServerFnError::new(format!("Invalid interval {:?}: expected e.g. 30s, 5m, 1h or 1d", every)))?;
+    let priority = match priority.as_deref() {
+        Some(name) => crate::mailbox::Priority::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown priority {:?}: expected low, normal or high", name)))?,
+        None => crate::mailbox::Priority::Normal,
+    };
+    check_schema(agent, &crate::payload::Payload::text(data.clone()))?;
+    let schedule = crate::scheduler::register(agent, data, priority, interval, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to register schedule: {}", e)))?;
+    serde_json::to_string(&schedule)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schedule: {}", e)))
+}
+
+/// Active schedules, oldest first, with their run counts and next run
+#[get("/api/schedule")]
+pub async fn list_schedules() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::scheduler::list_schedules())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schedules: {}", e)))
+}
+
+/// Stop a schedule; returns the cancelled schedule
+#[post("/api/schedule/:id/cancel", headers: dioxus::fullstack::HeaderMap)]
+pub async fn cancel_schedule(id: String) -> Result<String, ServerFnError> {
+    let schedule = crate::scheduler::cancel(&id, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to cancel schedule {}: {}", id, e)))?;
+    serde_json::to_string(&schedule)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schedule: {}", e)))
+}
+
+// ============================================================================
+// Payload Schema Endpoints
+// ============================================================================
+
+/// All agent payload schemas
+#[get("/api/schemas")]
+pub async fn list_schemas() -> Result<String, ServerFnError> {
+    let schemas = crate::schemas::list_schemas()
+        .map_err(|e| ServerFnError::new(format!("Failed to load schemas: {}", e)))?;
+    serde_json::to_string(&schemas)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schemas: {}", e)))
+}
+
+/// The JSON Schema of one agent (`null` if it accepts anything)
+#[get("/api/agents/:id/schema")]
+pub async fn get_agent_schema(id: u8) -> Result<String, ServerFnError> {
+    let schema = crate::schemas::get_schema(id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load schema of Agent{}: {}", id, e)))?;
+    serde_json::to_string(&schema)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schema: {}", e)))
+}
+
+/// Attach a JSON Schema to an agent; payloads sent to it must match from now on
+#[post("/api/agents/:id/schema")]
+pub async fn set_agent_schema(id: u8, schema: serde_json::Value) -> Result<String, ServerFnError> {
+    let entry = crate::schemas::set_schema(id, schema)
+        .map_err(|e| ServerFnError::new(format!("Failed to set schema of Agent{}: {}", id, e)))?;
+    serde_json::to_string(&entry)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize schema: {}", e)))
+}
+
+/// Remove an agent's schema
+#[post("/api/agents/:id/schema/delete")]
+pub async fn delete_agent_schema(id: u8) -> Result<bool, ServerFnError> {
+    crate::schemas::delete_schema(id)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete schema of Agent{}: {}", id, e)))
+}
+
+// ============================================================================
+// Redaction Endpoints
+// ============================================================================
+
+/// Redact the posted text as payloads are before they are stored, with the matches per pattern
+///
+/// For trying configured patterns; with redaction off the text comes back
+/// unchanged and `enabled` is false.
+#[post("/api/redaction/preview")]
+pub async fn preview_redaction(text: String) -> Result<String, ServerFnError> {
+    let (redacted, matches) = crate::redaction::redact_counted(&text);
+    Ok(serde_json::json!({
+        "enabled": crate::redaction::enabled(),
+        "text": redacted,
+        "matches": matches,
+    }).to_string())
+}
+
+// ============================================================================
+// Agent Persona Endpoints
+// ============================================================================
+
+/// All agent personas
+#[get("/api/personas")]
+pub async fn list_personas() -> Result<String, ServerFnError> {
+    let personas = crate::personas::list_personas()
+        .map_err(|e| ServerFnError::new(format!("Failed to load personas: {}", e)))?;
+    serde_json::to_string(&personas)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize personas: {}", e)))
+}
+
+/// The persona of one agent (`null` if it uses the default provider as is)
+#[get("/api/agents/:id/persona")]
+pub async fn get_agent_persona(id: u8) -> Result<String, ServerFnError> {
+    let persona = crate::personas::get_persona(id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load persona of Agent{}: {}", id, e)))?;
+    serde_json::to_string(&persona)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize persona: {}", e)))
+}
+
+/// Give an agent a persona (system prompt, model, temperature) for its LLM calls
+#[post("/api/agents/:id/persona")]
+pub async fn set_agent_persona(id: u8, persona: crate::connections::Persona) -> Result<String, ServerFnError> {
+    let entry = crate::personas::set_persona(id, persona)
+        .map_err(|e| ServerFnError::new(format!("Failed to set persona of Agent{}: {}", id, e)))?;
+    serde_json::to_string(&entry)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize persona: {}", e)))
+}
+
+/// Remove an agent's persona
+#[post("/api/agents/:id/persona/delete")]
+pub async fn delete_agent_persona(id: u8) -> Result<bool, ServerFnError> {
+    crate::personas::delete_persona(id)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete persona of Agent{}: {}", id, e)))
+}
+
+// ============================================================================
+// Bundle Endpoints
+// ============================================================================
+
+/// Export rules and schemas as a bundle (`rules`/`schemas`: comma-separated ids, default all; `format`: json or yaml)
+#[get("/api/bundles/export?name&rules&schemas&format")]
+pub async fn export_bundle(
+    name: Option<String>,
+    rules: Option<String>,
+    schemas: Option<String>,
+    format: Option<String>,
+) -> Result<String, ServerFnError> {
+    let ids = |list: Option<String>| list.map(|list| {
+        list.split(',').map(str::trim).filter(|id| !id.is_empty()).map(str::to_string).collect::<Vec<_>>()
+    });
+    let schemas = ids(schemas)
+        .map(|list| list.iter().map(|id| id.parse::<u8>()).collect::<Result<Vec<_>, _>>())
+        .transpose()
+        .map_err(|_| ServerFnError::new("schemas must be agent ids"))?;
+    let selection = crate::bundles::Selection { rules: ids(rules), schemas };
+    let bundle = crate::bundles::export(name.as_deref().unwrap_or("pattern-pack"), "", &selection)
+        .map_err(|e| ServerFnError::new(format!("Failed to export bundle: {}", e)))?;
+    match format.as_deref().unwrap_or("json") {
+        "json" => serde_json::to_string_pretty(&bundle)
+            .map_err(|e| ServerFnError::new(format!("Failed to serialize bundle: {}", e))),
+        "yaml" => serde_yaml::to_string(&bundle)
+            .map_err(|e| ServerFnError::new(format!("Failed to serialize bundle: {}", e))),
+        other => Err(ServerFnError::new(format!("Unknown bundle format {}", other))),
+    }
+}
+
+/// Import a JSON or YAML bundle (`conflict`: skip, overwrite or rename; `dry_run` saves nothing)
+#[post("/api/bundles/import?conflict&dry_run", headers: dioxus::fullstack::HeaderMap)]
+pub async fn import_bundle(conflict: Option<String>, dry_run: Option<bool>, bundle: String) -> Result<String, ServerFnError> {
+    let conflict = match conflict.as_deref() {
+        None => crate::bundles::Conflict::default(),
+        Some(name) => crate::bundles::Conflict::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown conflict policy {}", name)))?,
+    };
+    let bundle = crate::bundles::parse(&bundle)
+        .map_err(|e| ServerFnError::new(format!("Invalid bundle: {}", e)))?;
+    let report = crate::bundles::import(&bundle, conflict, dry_run.unwrap_or(false), &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to import bundle {}: {}", bundle.name, e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize import report: {}", e)))
+}
+
+/// Built-in starter packs
+#[get("/api/bundles/gallery")]
+pub async fn list_gallery() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::bundles::gallery())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize gallery: {}", e)))
+}
+
+/// Install a built-in pack through the bundle import (same `conflict` and `dry_run` as `/api/bundles/import`)
+#[post("/api/bundles/gallery/:id/install?conflict&dry_run", headers: dioxus::fullstack::HeaderMap)]
+pub async fn install_gallery_pack(id: String, conflict: Option<String>, dry_run: Option<bool>) -> Result<String, ServerFnError> {
+    let conflict = match conflict.as_deref() {
+        None => crate::bundles::Conflict::default(),
+        Some(name) => crate::bundles::Conflict::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown conflict policy {}", name)))?,
+    };
+    let bundle = crate::bundles::gallery_bundle(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load pack {}: {}", id, e)))?;
+    let report = crate::bundles::import(&bundle, conflict, dry_run.unwrap_or(false), &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to install pack {}: {}", id, e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize import report: {}", e)))
+}
+
+// ============================================================================
+// Blob Store Endpoints
+// ============================================================================
+
+/// Store a file in the blob store; it stays until the upload is deleted
+///
+/// Body: `{"name": "report.pdf", "payload": {"type": "binary", "content_type": "application/pdf", "data": "<base64>"}}`
+#[post("/api/uploads")]
+pub async fn create_upload(name: String, payload: crate::payload::Payload) -> Result<String, ServerFnError> {
+    let upload = crate::blobs::create_upload(&name, &payload)
+        .map_err(|e| ServerFnError::new(format!("Failed to store upload {}: {}", name, e)))?;
+    serde_json::to_string(&upload)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize upload: {}", e)))
+}
+
+/// All uploads, newest first
+#[get("/api/uploads")]
+pub async fn list_uploads() -> Result<String, ServerFnError> {
+    let uploads = crate::blobs::list_uploads()
+        .map_err(|e| ServerFnError::new(format!("Failed to load uploads: {}", e)))?;
+    serde_json::to_string(&uploads)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize uploads: {}", e)))
+}
+
+/// Delete an upload; its blob is removed by the next GC unless something else references it
+#[post("/api/uploads/:id/delete")]
+pub async fn delete_upload(id: String) -> Result<bool, ServerFnError> {
+    crate::blobs::delete_upload(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete upload {}: {}", id, e)))
+}
+
+/// Content of a blob as a payload (`{"type": "binary", "content_type": ..., "data": "<base64>"}`)
+#[get("/api/blobs/:hash")]
+pub async fn get_blob(hash: String) -> Result<String, ServerFnError> {
+    let info = crate::blobs::info(&hash)
+        .map_err(|e| ServerFnError::new(format!("Failed to load blob {}: {}", hash, e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Unknown blob {}", hash)))?;
+    let data = crate::blobs::read(&hash)
+        .map_err(|e| ServerFnError::new(format!("Failed to read blob {}: {}", hash, e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Blob {} is missing from disk", hash)))?;
+    serde_json::to_string(&crate::payload::Payload::binary(info.content_type, data))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize blob: {}", e)))
+}
+
+/// Delete unreferenced blobs older than `grace_secs` (default one hour)
+#[post("/api/admin/blobs/gc?grace_secs")]
+pub async fn gc_blobs(grace_secs: Option<u64>) -> Result<String, ServerFnError> {
+    let grace = grace_secs.map_or(crate::blobs::DEFAULT_GC_GRACE, std::time::Duration::from_secs);
+    let report = crate::blobs::gc(grace)
+        .map_err(|e| ServerFnError::new(format!("Blob GC failed: {}", e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize GC report: {}", e)))
+}
+
+// ============================================================================
+// Document Endpoints
+// ============================================================================
+
+/// Ingest a text document; uploading the same name again only re-embeds changed chunks
+#[post("/api/documents")]
+pub async fn ingest_document(name: String, text: String) -> Result<String, ServerFnError> {
+    let report = crate::documents::ingest(&name, &text)
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to ingest {}: {}", name, e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize ingest report: {}", e)))
+}
+
+/// All ingested documents
+#[get("/api/documents")]
+pub async fn list_documents() -> Result<String, ServerFnError> {
+    let documents = crate::documents::list_documents()
+        .map_err(|e| ServerFnError::new(format!("Failed to load documents: {}", e)))?;
+    serde_json::to_string(&documents)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize documents: {}", e)))
+}
+
+/// Chunks most similar to `query` (default 5)
+#[get("/api/documents/search?query&limit")]
+pub async fn search_documents(query: String, limit: Option<usize>) -> Result<String, ServerFnError> {
+    let hits = crate::documents::search(&query, limit.unwrap_or(5))
+        .await
+        .map_err(|e| ServerFnError::new(format!("Document search failed: {}", e)))?;
+    serde_json::to_string(&hits)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize search hits: {}", e)))
+}
+
+/// Delete a document and its chunks
+#[post("/api/documents/:id/delete")]
+pub async fn delete_document(id: String) -> Result<bool, ServerFnError> {
+    crate::documents::delete_document(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete document {}: {}", id, e)))
+}
+
+// ============================================================================
+// Cluster Endpoints
+// ============================================================================
+
+/// Whether this instance is the leader running shared background jobs
+#[get("/api/cluster/leader")]
+pub async fn get_leader_status() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::leader::status())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize leader status: {}", e)))
+}
+
+/// This node, its routing policy and the peers it is connected to
+#[get("/api/cluster/nodes")]
+pub async fn list_cluster_nodes() -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    serde_json::to_string(&crate::cluster::status().await)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize cluster nodes: {}", e)))
+}
+
+// ============================================================================
+// Cognitive Cycle Endpoints
+// ============================================================================
+
+/// Journal entries shown at most per request
+const MAX_JOURNAL_TICKS: u64 = 1000;
+
+/// Rate, tick count, derived clocks and next milestone of the cycle
+#[get("/api/cycle")]
+pub async fn get_cycle_status() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::cycle::status())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize cycle status: {}", e)))
+}
+
+/// Recorded ticks `from..=to` with their events (at most 1000 ticks)
+#[get("/api/cycle/journal?from&to")]
+pub async fn get_cycle_journal(from: u64, to: u64) -> Result<String, ServerFnError> {
+    if to.saturating_sub(from) >= MAX_JOURNAL_TICKS {
+        return Err(ServerFnError::new(format!("at most {} ticks per request", MAX_JOURNAL_TICKS)));
+    }
+    serde_json::to_string(&crate::phases::journal(from, to))
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize journal: {}", e)))
+}
+
+/// Replay recorded ticks `from..=to` through the phases (or only `phase`) without side effects
+#[post("/api/cycle/replay?from&to&phase")]
+pub async fn replay_cycle(from: u64, to: u64, phase: Option<String>) -> Result<String, ServerFnError> {
+    let report = crate::phases::replay(from, to, phase.as_deref())
+        .map_err(|e| ServerFnError::new(format!("Replay failed: {}", e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize replay: {}", e)))
+}
+
+// ============================================================================
+// Self-Monitoring Endpoints
+// ============================================================================
+
+/// Get the most recent alerts raised by the self-monitor (newest first)
+#[get("/api/monitor/alerts?limit")]
+pub async fn list_alerts(limit: Option<usize>) -> Result<String, ServerFnError> {
+    let alerts = crate::monitor::recent_alerts(limit.unwrap_or(50))
+        .map_err(|e| ServerFnError::new(format!("Failed to load alerts: {}", e)))?;
+    serde_json::to_string(&alerts)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize alerts: {}", e)))
+}
+
+/// Inject a failure (agent panic, LLM timeout, broadcast lag); only in `chaos` builds
+#[cfg(feature = "chaos")]
+#[post("/api/admin/chaos")]
+pub async fn inject_chaos(fault: crate::chaos::ChaosFault) -> Result<String, ServerFnError> {
+    ensure_agents_initialized().await
+        .map_err(|e| ServerFnError::new(format!("Failed to initialize agents: {}", e)))?;
+    crate::chaos::inject(fault)
+        .map_err(|e| ServerFnError::new(format!("Failed to inject fault: {}", e)))
+}
+
+/// Re-read environment and config file and swap in the new configuration (same as SIGHUP)
+#[post("/api/admin/config/reload", headers: dioxus::fullstack::HeaderMap)]
+pub async fn reload_config() -> Result<String, ServerFnError> {
+    log_info!(
+        "[Config] Reload requested by {}",
+        client_ip(&headers).as_deref().unwrap_or("API client")
+    );
+    let report = crate::config::reload()
+        .map_err(|errors| ServerFnError::new(format!("Invalid configuration: {}", errors.join("; "))))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize reload report: {}", e)))
+}
+
+/// Check the configured releases feed for a newer version
+///
+/// Returns `{"current_version": "...", "update": null | {version, name, notes, url, published_at}}`.
+#[get("/api/updates/check")]
+pub async fn check_for_update() -> Result<String, ServerFnError> {
+    let update = crate::updates::check_for_update()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Update check failed: {}", e)))?;
+    Ok(serde_json::json!({
+        "current_version": crate::updates::CURRENT_VERSION,
+        "update": update,
+    }).to_string())
+}
+
+/// Progress of the boot sequence: `{"ready": true, "steps": [{"name": "storage", "state": "done", ...}, ...]}`
+#[get("/api/boot")]
+pub async fn get_boot_status() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::boot::status())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize boot status: {}", e)))
+}
+
+/// Run the startup diagnostics (config, GPU, Ollama, storage, port)
+#[get("/api/admin/diagnostics")]
+pub async fn get_diagnostics() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::doctor::run_diagnostics().await)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize diagnostics: {}", e)))
+}
+
+/// Purge data past its retention: `classes` (comma-separated, default all of events,
+/// conversations, jobs, series), `older_than` (`30d`, `12h`, ...; default each class's policy)
+///
+/// Only reports what it would delete unless `dry_run=false`.
+#[post("/api/admin/purge?classes&older_than&dry_run")]
+pub async fn purge_data(
+    classes: Option<String>,
+    older_than: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<String, ServerFnError> {
+    use crate::retention::DataClass;
+    let classes = match classes.as_deref().filter(|classes| !classes.trim().is_empty()) {
+        None => DataClass::ALL.to_vec(),
+        Some(names) => names
+            .split(',')
+            .map(|name| DataClass::parse(name.trim())
+                .ok_or_else(|| ServerFnError::new(format!("Unknown data class {:?} (use events, conversations, jobs or series)", name))))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let max_age = older_than
+        .map(|age| crate::retention::parse_age(&age)
+            .ok_or_else(|| ServerFnError::new(format!("Invalid age {:?} (use e.g. 30d, 12h, 90m)", age))))
+        .transpose()?;
+    let report = crate::retention::purge(&classes, max_age, dry_run.unwrap_or(true));
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize purge report: {}", e)))
+}
+
+/// Throughput, LLM latency and tick rate over the last minute, with the latest events
+#[get("/api/stats/live")]
+pub async fn get_live_stats() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::live_stats::snapshot())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize stats: {}", e)))
+}
+
+/// Latency percentiles per pipeline stage (receive, dequeue, compute, delivery) over the last five minutes
+#[get("/api/stats/latency")]
+pub async fn get_latency_budget() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::latency::snapshot())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize latency: {}", e)))
+}
+
+// ============================================================================
+// Labeling Endpoints
+// ============================================================================
+
+/// Get the most recent stored events (newest first)
+#[get("/api/events?limit")]
+pub async fn list_events(limit: Option<usize>) -> Result<String, ServerFnError> {
+    let events = crate::dataset::recent_events(limit.unwrap_or(50))
+        .map_err(|e| ServerFnError::new(format!("Failed to load events: {}", e)))?;
+    serde_json::to_string(&events)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize events: {}", e)))
+}
+
+/// Get all saved labels
+#[get("/api/labels")]
+pub async fn list_labels() -> Result<String, ServerFnError> {
+    let labels = crate::dataset::all_labels()
+        .map_err(|e| ServerFnError::new(format!("Failed to load labels: {}", e)))?;
+    serde_json::to_string(&labels)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize labels: {}", e)))
+}
+
+/// Assign a label to a stored event
+#[post("/api/labels")]
+pub async fn save_label(event_id: String, label: String) -> Result<String, ServerFnError> {
+    let label = crate::dataset::save_label(&event_id, &label)
+        .map_err(|e| ServerFnError::new(format!("Failed to save label: {}", e)))?;
+    serde_json::to_string(&label)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize label: {}", e)))
+}
+
+/// Export labeled events as a JSON Lines training dataset
+#[get("/api/labels/export")]
+pub async fn export_labels() -> Result<String, ServerFnError> {
+    let examples = crate::dataset::labeled_examples()
+        .map_err(|e| ServerFnError::new(format!("Failed to build dataset: {}", e)))?;
+    Ok(crate::dataset::export_jsonl(&examples))
+}
+
+// ============================================================================
+// Feedback Endpoints
+// ============================================================================
+
+/// Most recent LLM generations of the agents, newest first
+#[get("/api/generations?limit")]
+pub async fn list_generations(limit: Option<usize>) -> Result<String, ServerFnError> {
+    let generations = crate::feedback::recent_generations(limit.unwrap_or(50))
+        .map_err(|e| ServerFnError::new(format!("Failed to load generations: {}", e)))?;
+    serde_json::to_string(&generations)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize generations: {}", e)))
+}
+
+/// All feedback with the rated outputs
+#[get("/api/feedback")]
+pub async fn list_feedback() -> Result<String, ServerFnError> {
+    let feedback = crate::feedback::list_feedback()
+        .map_err(|e| ServerFnError::new(format!("Failed to load feedback: {}", e)))?;
+    serde_json::to_string(&feedback)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize feedback: {}", e)))
+}
+
+/// Rate or annotate a generation or agent event (replacing earlier feedback on it)
+#[post("/api/feedback", headers: dioxus::fullstack::HeaderMap)]
+pub async fn submit_feedback(feedback: crate::feedback::FeedbackInput) -> Result<String, ServerFnError> {
+    let feedback = crate::feedback::submit(feedback, &request_author(&headers))
+        .map_err(|e| ServerFnError::new(format!("Failed to save feedback: {}", e)))?;
+    serde_json::to_string(&feedback)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize feedback: {}", e)))
+}
+
+/// Ratings per model and agent, and label counts
+#[get("/api/feedback/stats")]
+pub async fn feedback_stats() -> Result<String, ServerFnError> {
+    let stats = crate::feedback::stats()
+        .map_err(|e| ServerFnError::new(format!("Failed to aggregate feedback: {}", e)))?;
+    serde_json::to_string(&stats)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize feedback stats: {}", e)))
+}
+
+/// Rated generations as a JSON Lines fine-tuning dataset
+///
+/// `format` is `chat` (OpenAI messages, the default), `instruction` or `raw`;
+/// `rating` (`up`/`down`) and `from`/`to` (milliseconds) filter the feedback.
+#[get("/api/feedback/export?format&rating&from&to")]
+pub async fn export_feedback(
+    format: Option<String>,
+    rating: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<String, ServerFnError> {
+    let (filter, format) = tuning_options(format, rating, from, to)?;
+    let feedback = crate::feedback::list_feedback()
+        .map_err(|e| ServerFnError::new(format!("Failed to load feedback: {}", e)))?;
+    Ok(crate::feedback::export_jsonl(&crate::feedback::tuning_examples(&feedback, &filter, format)))
+}
+
+/// Save a fine-tuning export as a blob and dataset version; download it from `/api/blobs/<hash>`
+#[post("/api/feedback/datasets?format&rating&from&to")]
+pub async fn save_feedback_dataset(
+    format: Option<String>,
+    rating: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<String, ServerFnError> {
+    let (filter, format) = tuning_options(format, rating, from, to)?;
+    let artifact = crate::feedback::save_artifact(&filter, format)
+        .map_err(|e| ServerFnError::new(format!("Failed to save dataset: {}", e)))?;
+    serde_json::to_string(&artifact)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize dataset: {}", e)))
+}
+
+#[cfg(feature = "server")]
+fn tuning_options(
+    format: Option<String>,
+    rating: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<(crate::feedback::TuningFilter, crate::feedback::TuningFormat), ServerFnError> {
+    let format = match format.as_deref() {
+        None => crate::feedback::TuningFormat::default(),
+        Some(name) => crate::feedback::TuningFormat::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown format {:?} (use chat, instruction or raw)", name)))?,
+    };
+    let rating = match rating.as_deref() {
+        None => None,
+        Some(name) => Some(crate::feedback::Rating::parse(name)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown rating {:?} (use up or down)", name)))?),
+    };
+    Ok((crate::feedback::TuningFilter { rating, from, to }, format))
+}
+
+// ============================================================================
+// Time-Series Endpoints
+// ============================================================================
+
+/// Append a batch of points to a named series
+///
+/// Returns counts of accepted, out-of-order, replaced and too-late points.
+/// Rate-limited requests fail with 429 and `retry_after_ms`, invalid points with 400.
+#[post("/api/timeseries/:name/append")]
+pub async fn append_timeseries(name: String, points: Vec<crate::timeseries::Point>) -> Result<String, ServerFnError> {
+    crate::telemetry::traced_request("/api/timeseries/:name/append", async move {
+        crate::timeseries::ensure_compaction_started();
+        let report = crate::timeseries::append(&name, points)
+            .map_err(|e| append_error(format!("Failed to append to {}", name), e))?;
+        serde_json::to_string(&report)
+            .map_err(|e| ServerFnError::new(format!("Failed to serialize report: {}", e)))
+    }).await
+}
+
+/// Get the points of a series, optionally limited to `[from, to]` (milliseconds)
+#[get("/api/timeseries/:name?from&to")]
+pub async fn query_timeseries(name: String, from: Option<u64>, to: Option<u64>) -> Result<String, ServerFnError> {
+    let points = crate::timeseries::query(&name, from, to)
+        .map_err(|e| ServerFnError::new(format!("Failed to load series {}: {}", name, e)))?;
+    serde_json::to_string(&points)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize points: {}", e)))
+}
+
+/// Forecast the next `horizon` values of a series (Holt's linear smoothing, default 10)
+///
+/// A horizon over `MAX_FORECAST_HORIZON` (10000) is refused with 400.
+#[get("/api/timeseries/:name/forecast?horizon")]
+pub async fn forecast_timeseries(name: String, horizon: Option<usize>) -> Result<String, ServerFnError> {
+    let horizon = horizon.unwrap_or(10);
+    if horizon > crate::timeseries::MAX_FORECAST_HORIZON {
+        return Err(ServerFnError::ServerError {
+            message: format!("horizon must be at most {}, got {}", crate::timeseries::MAX_FORECAST_HORIZON, horizon),
+            code: 400,
+            details: None,
         });
-        Ok(format!("Message queued for Agent{}: {}", id, data))
-    } else {
-        Err(ServerFnError::new(format!("Agent{} is not available", id)))
     }
+    let forecast = crate::timeseries::forecast(&name, horizon)
+        .map_err(|e| ServerFnError::new(format!("Failed to load series {}: {}", name, e)))?;
+    serde_json::to_string(&forecast)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize forecast: {}", e)))
+}
+
+/// Get the retention policy of a series
+#[get("/api/timeseries/:name/policy")]
+pub async fn get_retention_policy(name: String) -> Result<String, ServerFnError> {
+    let policy = crate::timeseries::get_policy(&name)
+        .map_err(|e| ServerFnError::new(format!("Failed to load series {}: {}", name, e)))?;
+    serde_json::to_string(&policy)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize policy: {}", e)))
+}
+
+/// Set the retention policy (raw/rollup retention, rollup interval and function) of a series
+#[post("/api/timeseries/:name/policy")]
+pub async fn set_retention_policy(name: String, policy: crate::timeseries::RetentionPolicy) -> Result<(), ServerFnError> {
+    crate::timeseries::ensure_compaction_started();
+    crate::timeseries::set_policy(&name, policy)
+        .map_err(|e| append_error(format!("Failed to set policy of {}", name), e))
+}
+
+/// InfluxDB v1 write endpoint (`/write?db=...&precision=...`) for Telegraf's `influxdb` output
+///
+/// The body is raw line protocol; each numeric field is stored as series
+/// `<measurement>.<field>`. The `db` parameter is accepted and ignored.
+#[post("/write?db&precision")]
+pub async fn influx_write(db: Option<String>, precision: Option<String>, body: String) -> Result<String, ServerFnError> {
+    let _ = db;
+    crate::telemetry::traced_request("/write", async move { write_line_protocol(precision, body) }).await
+}
+
+/// InfluxDB v2 write endpoint (`/api/v2/write?org=...&bucket=...&precision=...`) for Telegraf's `influxdb_v2` output
+#[post("/api/v2/write?org&bucket&precision")]
+pub async fn influx_write_v2(org: Option<String>, bucket: Option<String>, precision: Option<String>, body: String) -> Result<String, ServerFnError> {
+    let _ = (org, bucket);
+    crate::telemetry::traced_request("/api/v2/write", async move { write_line_protocol(precision, body) }).await
+}
+
+#[cfg(feature = "server")]
+fn write_line_protocol(precision: Option<String>, body: String) -> Result<String, ServerFnError> {
+    use crate::line_protocol::{write, Precision};
+
+    crate::timeseries::ensure_compaction_started();
+    // Like Influx: 400 for malformed lines and 429 when rate limited, since
+    // Telegraf retries 5xx answers and a bad line would block its buffer
+    let precision = Precision::parse(precision.as_deref())
+        .map_err(|e| append_error("Invalid precision".to_string(), e))?;
+    let reports = write(&body, precision)
+        .map_err(|e| append_error("Failed to write line protocol".to_string(), e))?;
+    serde_json::to_string(&reports)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize reports: {}", e)))
+}
+
+/// List configured Prometheus scrape targets
+#[get("/api/scrape/targets")]
+pub async fn list_scrape_targets() -> Result<String, ServerFnError> {
+    crate::prometheus::ensure_scraper_started();
+    let targets = crate::prometheus::list_targets()
+        .map_err(|e| ServerFnError::new(format!("Failed to load scrape targets: {}", e)))?;
+    serde_json::to_string(&targets)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize scrape targets: {}", e)))
+}
+
+/// Add or replace a Prometheus scrape target (URL, interval and metric-to-series mappings)
+#[post("/api/scrape/targets")]
+pub async fn save_scrape_target(target: crate::prometheus::ScrapeTarget) -> Result<(), ServerFnError> {
+    crate::prometheus::ensure_scraper_started();
+    crate::prometheus::save_target(&target)
+        .map_err(|e| ServerFnError::new(format!("Failed to save scrape target: {}", e)))
+}
+
+/// Remove a Prometheus scrape target
+#[post("/api/scrape/targets/:name/delete")]
+pub async fn delete_scrape_target(name: String) -> Result<bool, ServerFnError> {
+    crate::prometheus::delete_target(&name)
+        .map_err(|e| ServerFnError::new(format!("Failed to delete scrape target {}: {}", name, e)))
+}
+
+/// List all series names
+#[get("/api/timeseries")]
+pub async fn list_timeseries() -> Result<String, ServerFnError> {
+    let names = crate::timeseries::series_names()
+        .map_err(|e| ServerFnError::new(format!("Failed to load series: {}", e)))?;
+    serde_json::to_string(&names)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize series: {}", e)))
+}
+
+// ============================================================================
+// Model Registry Endpoints
+// ============================================================================
+
+/// List all registered models
+#[get("/api/models")]
+pub async fn list_models() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::registry::registered_models())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize models: {}", e)))
+}
+
+/// Models currently loaded in the warm pool, most recently used first
+#[get("/api/models/pool")]
+pub async fn list_loaded_models() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::model_pool::loaded_models())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize model pool: {}", e)))
+}
+
+// ============================================================================
+// Inference Endpoints
+// ============================================================================
+
+/// Run the LSTM over `input` (nested `[batch][seq][features]` array or `{shape, dtype, data}`)
+#[post("/api/infer/lstm")]
+pub async fn infer_lstm(input: serde_json::Value) -> Result<String, ServerFnError> {
+    let input = crate::tensor_io::JsonTensor::parse(&input)
+        .map_err(|e| ServerFnError::new(format!("Invalid input tensor: {}", e)))?;
+    let inference = crate::compute::run("lstm.infer", move || crate::lstm::run_lstm(&input))
+        .await
+        .map_err(|e| ServerFnError::new(format!("Inference task failed: {}", e)))?
+        .map_err(|e| ServerFnError::new(format!("Inference failed: {}", e)))?;
+    serde_json::to_string(&inference)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize output: {}", e)))
+}
+
+/// Predict the event token sequence following `source` with the seq2seq model
+#[post("/api/infer/seq2seq")]
+pub async fn infer_seq2seq(source: Vec<usize>) -> Result<String, ServerFnError> {
+    let output = crate::compute::run("seq2seq.infer", move || crate::seq2seq::predict_sequence(&source))
+        .await
+        .map_err(|e| ServerFnError::new(format!("Inference task failed: {}", e)))?
+        .map_err(|e| ServerFnError::new(format!("Inference failed: {}", e)))?;
+    serde_json::to_string(&output)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize output: {}", e)))
+}
+
+/// Run the next chunk of a long or live sequence through the LSTM, keeping its state between calls
+///
+/// Omit `session` with the first chunk and send the returned id with every
+/// following chunk; `close: true` ends the session after its chunk. Each
+/// response carries only the outputs for the chunk just sent. Opening a
+/// session while the maximum number are open answers 429.
+#[post("/api/infer/stream")]
+pub async fn infer_stream(session: Option<String>, chunk: serde_json::Value, close: Option<bool>) -> Result<String, ServerFnError> {
+    let chunk = crate::tensor_io::JsonTensor::parse(&chunk)
+        .map_err(|e| ServerFnError::new(format!("Invalid chunk tensor: {}", e)))?;
+    let result = crate::compute::run("lstm.stream", move || {
+        crate::lstm::stream_lstm(session.as_deref(), &chunk, close.unwrap_or(false))
+    })
+        .await
+        .map_err(|e| ServerFnError::new(format!("Inference task failed: {}", e)))?
+        .map_err(|e| match e.downcast_ref::<crate::lstm::TooManyStreams>() {
+            Some(refused) => ServerFnError::ServerError {
+                message: e.to_string(),
+                code: 429,
+                details: Some(serde_json::json!({ "limit": refused.limit })),
+            },
+            None => ServerFnError::new(format!("Inference failed: {}", e)),
+        })?;
+    serde_json::to_string(&result)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize output: {}", e)))
+}
+
+// ============================================================================
+// Experiment Tracking Endpoints
+// ============================================================================
+
+/// List all recorded training runs (newest first)
+#[get("/api/experiments")]
+pub async fn list_experiments() -> Result<String, ServerFnError> {
+    let runs = crate::experiments::list_runs()
+        .map_err(|e| ServerFnError::new(format!("Failed to load experiments: {}", e)))?;
+    serde_json::to_string(&runs)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize experiments: {}", e)))
+}
+
+/// Get the given runs side by side (`ids` is a comma-separated list of run ids)
+#[get("/api/experiments/compare?ids")]
+pub async fn compare_experiments(ids: String) -> Result<String, ServerFnError> {
+    let ids: Vec<String> = ids.split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    let runs = crate::experiments::compare_runs(&ids)
+        .map_err(|e| ServerFnError::new(format!("Failed to load experiments: {}", e)))?;
+    serde_json::to_string(&runs)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize experiments: {}", e)))
+}
+
+// ============================================================================
+// Dataset Version Endpoints
+// ============================================================================
+
+/// List dataset versions with the ids of runs trained on each
+#[get("/api/datasets")]
+pub async fn list_datasets() -> Result<String, ServerFnError> {
+    let versions = crate::dataset::list_dataset_versions()
+        .map_err(|e| ServerFnError::new(format!("Failed to load datasets: {}", e)))?;
+    let runs = crate::experiments::list_runs()
+        .map_err(|e| ServerFnError::new(format!("Failed to load experiments: {}", e)))?;
+
+    let datasets: Vec<serde_json::Value> = versions.into_iter().map(|version| {
+        let run_ids: Vec<&str> = runs.iter()
+            .filter(|run| run.dataset_version.as_deref() == Some(version.id.as_str()))
+            .map(|run| run.id.as_str())
+            .collect();
+        serde_json::json!({ "version": version, "runs": run_ids })
+    }).collect();
+    Ok(serde_json::Value::from(datasets).to_string())
+}
+
+/// Get one dataset version and the runs (with artifacts) that trained on it
+#[get("/api/datasets/:id")]
+pub async fn get_dataset(id: String) -> Result<String, ServerFnError> {
+    let version = crate::dataset::get_dataset_version(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load dataset: {}", e)))?
+        .ok_or_else(|| ServerFnError::new(format!("Dataset {} not found", id)))?;
+    let runs = crate::experiments::runs_for_dataset(&id)
+        .map_err(|e| ServerFnError::new(format!("Failed to load experiments: {}", e)))?;
+    Ok(serde_json::json!({ "version": version, "runs": runs }).to_string())
+}
+
+// ============================================================================
+// Hyperparameter Search Endpoints
+// ============================================================================
+
+/// Start a grid search over the labeled dataset; progress is streamed as `tuning.*` events
+#[post("/api/tuning/run")]
+pub async fn run_tuning() -> Result<String, ServerFnError> {
+    let examples = crate::dataset::labeled_examples()
+        .map_err(|e| ServerFnError::new(format!("Failed to build dataset: {}", e)))?;
+    if examples.is_empty() {
+        return Err(ServerFnError::new("No labeled examples to tune on"));
+    }
+
+    let count = examples.len();
+    let mut config = crate::tuning::TuningConfig::default();
+    config.training.dataset_source = crate::dataset::LABELS_COLLECTION.to_string();
+    crate::tuning::split_examples(&examples, &config)
+        .map_err(|e| ServerFnError::new(format!("Cannot tune on this dataset: {}", e)))?;
+    tokio::spawn(async move {
+        if let Err(e) = crate::tuning::run_search(examples, config).await {
+            log_error!("[Tuning] Search failed: {}", e);
+        }
+    });
+    Ok(format!("Hyperparameter search started on {} examples", count))
+}
+
+/// Train a registered model and save it where inference loads it from
+///
+/// `dataset` is JSON Lines: `{"text", "label"}` objects for the classifier
+/// (without it the labeled events are used), `{"source", "target"}` token
+/// sequences for seq2seq. With `k_folds` of 2 or more the classifier is
+/// cross-validated first, and the per-fold metrics come back with the
+/// training report.
+#[post("/api/models/:name/train?k_folds")]
+pub async fn train_model(name: String, k_folds: Option<usize>, dataset: Option<String>) -> Result<String, ServerFnError> {
+    use crate::registry::ModelKind;
+    use crate::training::TrainingData;
+
+    let entry = crate::registry::find_model(&name)
+        .ok_or_else(|| ServerFnError::new(format!("Model {} not found", name)))?;
+    let mut config = crate::training::TrainingConfig::default();
+    let data = match (entry.kind, dataset) {
+        (ModelKind::Seq2Seq, Some(jsonl)) => {
+            let vocab_size = crate::seq2seq::Seq2SeqConfig::default().vocab_size;
+            TrainingData::Sequences(crate::training::parse_sequence_jsonl(&jsonl, vocab_size)
+                .map_err(|e| ServerFnError::new(format!("Invalid dataset: {}", e)))?)
+        }
+        (ModelKind::Seq2Seq, None) => return Err(ServerFnError::new("seq2seq needs a dataset of source and target sequences")),
+        (_, Some(jsonl)) => TrainingData::Labeled(crate::dataset::parse_jsonl(&jsonl)
+            .map_err(|e| ServerFnError::new(format!("Invalid dataset: {}", e)))?),
+        (_, None) => {
+            config.dataset_source = crate::dataset::LABELS_COLLECTION.to_string();
+            TrainingData::Labeled(crate::dataset::labeled_examples()
+                .map_err(|e| ServerFnError::new(format!("Failed to build dataset: {}", e)))?)
+        }
+    };
+    config.k_folds = k_folds.unwrap_or(config.k_folds);
+    let outcome = crate::compute::run_bulk("train", move || crate::training::train_registered(&name, &data, &config))
+        .await
+        .map_err(|e| ServerFnError::new(format!("Training task failed: {}", e)))?
+        .map_err(|e| ServerFnError::new(format!("Training failed: {}", e)))?;
+    serde_json::to_string(&outcome)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize report: {}", e)))
+}
+
+/// Quantize a registered model to int8 weights and report the accuracy delta
+#[post("/api/models/:name/quantize")]
+pub async fn quantize_model(name: String) -> Result<String, ServerFnError> {
+    let examples = crate::dataset::labeled_examples()
+        .map_err(|e| ServerFnError::new(format!("Failed to build dataset: {}", e)))?;
+    let report = crate::compute::run_bulk("quantize", move || crate::quantization::quantize_registered(&name, &examples))
+        .await
+        .map_err(|e| ServerFnError::new(format!("Quantization task failed: {}", e)))?
+        .map_err(|e| ServerFnError::new(format!("Quantization failed: {}", e)))?;
+    serde_json::to_string(&report)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize report: {}", e)))
 }
 
 // ============================================================================
@@ -160,13 +1728,13 @@ pub async fn process_agent_dynamic(id: u8, data: String) -> Result<String, Serve
 /// Call MCP example tool - Desktop app triggers, broadcasts to web clients via MCP channel
 #[post("/api/mcp/example_tool")]
 pub async fn mcp_example_tool() -> Result<String, ServerFnError> {
-    eprintln!("[MCP] example_tool triggered from desktop app");
-    let mcp_server = crate::mcp_server::PatternClockMCP::new();
+    log_info!("[MCP] example_tool triggered from desktop app");
+    let mcp_server = crate::mcp_server::shared();
     let result = mcp_server.call_example_tool().await;
-    eprintln!("[MCP] example_tool result: {}", result);
+    log_info!("[MCP] example_tool result: {}", result);
     
     // Broadcast result through MCP channel to web clients
-    let _ = get_mcp_broadcaster().send(result.clone());
+    mcp_results().publish(result.clone());
     
     Ok(result)
 }
@@ -174,68 +1742,154 @@ pub async fn mcp_example_tool() -> Result<String, ServerFnError> {
 /// Call MCP random number tool - Desktop app triggers, broadcasts to web clients via MCP channel
 #[post("/api/mcp/random_number")]
 pub async fn mcp_random_number() -> Result<String, ServerFnError> {
-    eprintln!("[MCP] random_number triggered from desktop app");
-    let mcp_server = crate::mcp_server::PatternClockMCP::new();
+    log_info!("[MCP] random_number triggered from desktop app");
+    let mcp_server = crate::mcp_server::shared();
     let result = mcp_server.call_get_random_number().await;
-    eprintln!("[MCP] random_number result: {}", result);
+    log_info!("[MCP] random_number result: {}", result);
     
     // Broadcast result through MCP channel to web clients
-    let _ = get_mcp_broadcaster().send(result.clone());
+    mcp_results().publish(result.clone());
     
     Ok(result)
 }
 
 /// Call MCP process agent tool - Desktop app triggers, broadcasts to web clients via MCP channel
-#[post("/api/mcp/process_agent")]
+#[post("/api/mcp/process_agent", headers: dioxus::fullstack::HeaderMap)]
 pub async fn mcp_process_agent(agent_id: u8, data: String) -> Result<String, ServerFnError> {
-    eprintln!("[MCP] process_agent triggered from desktop app: agent_id={}, data={}", agent_id, data);
-    let mcp_server = crate::mcp_server::PatternClockMCP::new();
-    let result = mcp_server.call_process_agent(agent_id, data).await;
-    eprintln!("[MCP] process_agent result: {}", result);
+    log_info!("[MCP] process_agent triggered from desktop app: agent_id={}, data={}", agent_id, data);
+    let mcp_server = crate::mcp_server::shared();
+    let result = mcp_server.call_process_agent(agent_id, data, request_deadline(&headers)).await;
+    log_info!("[MCP] process_agent result: {}", result);
+    
+    // Broadcast result through MCP channel to web clients
+    mcp_results().publish(result.clone());
+    
+    Ok(result)
+}
+
+/// Call MCP classify text tool - Desktop app triggers, broadcasts to web clients via MCP channel
+#[post("/api/mcp/classify_text")]
+pub async fn mcp_classify_text(text: String) -> Result<String, ServerFnError> {
+    log_info!("[MCP] classify_text triggered from desktop app: text={}", text);
+    let mcp_server = crate::mcp_server::shared();
+    let result = mcp_server.call_classify_text(text).await;
+    log_info!("[MCP] classify_text result: {}", result);
     
     // Broadcast result through MCP channel to web clients
-    let _ = get_mcp_broadcaster().send(result.clone());
+    mcp_results().publish(result.clone());
     
     Ok(result)
 }
 
+/// MCP tools with their settings and call counters
+#[get("/api/mcp/tools")]
+pub async fn list_mcp_tools() -> Result<String, ServerFnError> {
+    serde_json::to_string(&crate::mcp_server::shared().tools())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize tools: {}", e)))
+}
+
+/// Enable or disable MCP tool `name`, or set its timeout (`timeout_ms=0` removes it)
+#[post("/api/mcp/tools/:name?enabled&timeout_ms")]
+pub async fn configure_mcp_tool(name: String, enabled: Option<bool>, timeout_ms: Option<u64>) -> Result<String, ServerFnError> {
+    let mcp_server = crate::mcp_server::shared();
+    let mut config = mcp_server.tool_config(&name);
+    if let Some(enabled) = enabled {
+        config.enabled = enabled;
+    }
+    if let Some(timeout_ms) = timeout_ms {
+        config.timeout_ms = Some(timeout_ms).filter(|&ms| ms > 0);
+    }
+    let config = mcp_server.configure_tool(&name, config)
+        .map_err(|e| ServerFnError::new(format!("Failed to configure tool: {}", e)))?;
+    serde_json::to_string(&config)
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize tool config: {}", e)))
+}
+
 // ============================================================================
 // MCP Stream Endpoint - Web clients subscribe to MCP results
 // ============================================================================
 
+/// Register a web client on the MCP channel and return its subscriber id
+///
+/// `policy` decides what happens when the client falls behind: `drop_oldest`,
+/// `disconnect` or `spill_to_history` (default).
+#[post("/api/mcp/subscribe?policy")]
+pub async fn mcp_subscribe(policy: Option<String>) -> Result<String, ServerFnError> {
+    let policy = match policy.as_deref() {
+        None => LagPolicy::default(),
+        Some(value) => LagPolicy::parse(value)
+            .ok_or_else(|| ServerFnError::new(format!("Unknown lag policy: {}", value)))?,
+    };
+    Ok(mcp_results().subscribe(policy))
+}
+
 /// Get next MCP result (long-polling endpoint for web clients)
 /// This is the MCP communication channel - web app polls this endpoint
-#[get("/api/mcp/receive")]
-pub async fn mcp_receive() -> Result<String, ServerFnError> {
-    eprintln!("[MCP] Web client requesting MCP result");
-    
-    let mut rx = get_mcp_broadcaster().subscribe();
-    
-    // Wait up to 60 seconds for a result
+///
+/// With a `subscriber` id from `/api/mcp/subscribe`, results published
+/// between polls are delivered too; without one, only results published
+/// while this request waits are seen.
+#[get("/api/mcp/receive?subscriber", headers: dioxus::fullstack::HeaderMap)]
+pub async fn mcp_receive(subscriber: Option<String>) -> Result<String, ServerFnError> {
+    log_info!("[MCP] Web client requesting MCP result");
+
+    // Anonymous pollers get a subscriber for the duration of this request
+    let anonymous = subscriber.is_none();
+    let subscriber = subscriber.unwrap_or_else(|| mcp_results().subscribe(LagPolicy::DropOldest));
+
+    // Wait up to 60 seconds for a result, or less if the client's budget is shorter
+    let wait = request_deadline(&headers)
+        .map_or(tokio::time::Duration::from_secs(60), |deadline| {
+            deadline.remaining().min(tokio::time::Duration::from_secs(60))
+        });
+    let received = mcp_results().recv(&subscriber, wait).await;
+    if anonymous {
+        mcp_results().unsubscribe(&subscriber);
+    }
+    match received {
+        Ok(Some(result)) => {
+            log_info!("[MCP] Sending result to web client: {}", result);
+            Ok(result)
+        }
+        // Timeout - return empty string (normal for long-polling)
+        Ok(None) => Ok(String::new()),
+        Err(e) => {
+            log_warn!("[MCP] Web client {}: {}", subscriber, e);
+            Err(ServerFnError::new(e.to_string()))
+        }
+    }
+}
+
+/// Lag statistics of MCP subscribers
+#[get("/api/mcp/subscribers")]
+pub async fn mcp_subscribers() -> Result<String, ServerFnError> {
+    serde_json::to_string(&mcp_results().stats())
+        .map_err(|e| ServerFnError::new(format!("Failed to serialize subscribers: {}", e)))
+}
+
+/// Get next internal event (long-polling endpoint, same semantics as `mcp_receive`)
+#[get("/api/events/stream")]
+pub async fn events_receive() -> Result<String, ServerFnError> {
+    let mut rx = crate::events::subscribe();
+
     match tokio::time::timeout(
         tokio::time::Duration::from_secs(60),
         rx.recv()
     ).await {
-        Ok(Ok(result)) => {
-            eprintln!("[MCP] Sending result to web client: {}", result);
-            Ok(result)
-        }
-        Ok(Err(broadcast::error::RecvError::Closed)) => {
-            eprintln!("[MCP] Broadcast channel closed");
-            Ok(String::new())
-        }
-        Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
-            eprintln!("[MCP] Web client lagged, skipped {} messages", skipped);
-            // Try to get the latest message
-            match rx.try_recv() {
-                Ok(result) => Ok(result),
-                Err(_) => Ok(String::new()),
+        Ok(Ok(event)) => {
+            // Other instances' clocks may differ; only time our own events
+            if event.is_local() {
+                let delay = crate::storage::now_millis().saturating_sub(event.ts);
+                crate::latency::record(crate::latency::Stage::Delivery, std::time::Duration::from_millis(delay));
             }
+            Ok(event.json().to_string())
         }
-        Err(_) => {
-            // Timeout - return empty string (normal for long-polling)
+        Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+            log_warn!("[Events] Client lagged, skipped {} events", skipped);
             Ok(String::new())
         }
+        // Closed channel or timeout - return empty string (normal for long-polling)
+        _ => Ok(String::new()),
     }
 }
 
@@ -251,3 +1905,45 @@ pub async fn receive_signal_removed() -> Result<String, ServerFnError> {
 pub async fn send_signal_removed(_data: String) -> Result<String, ServerFnError> {
     Err(ServerFnError::new("Endpoint removed - use MCP endpoints instead"))
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use dioxus::fullstack::HeaderMap;
+
+    fn headers(lines: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for line in lines {
+            headers.append("x-forwarded-for", HeaderValue::from_static(line));
+        }
+        headers
+    }
+
+    #[test]
+    fn forwarded_entry_counts_hops_from_the_right() {
+        let headers = headers(&["203.0.113.7, 10.0.0.2, 10.0.0.3"]);
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 1).as_deref(), Some("10.0.0.3"));
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 2).as_deref(), Some("10.0.0.2"));
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 3).as_deref(), Some("203.0.113.7"));
+        // Zero hops is treated as one proxy
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 0).as_deref(), Some("10.0.0.3"));
+    }
+
+    #[test]
+    fn forwarded_entry_needs_an_entry_per_proxy() {
+        let headers = headers(&["203.0.113.7"]);
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 2), None);
+        assert_eq!(forwarded_entry(&HeaderMap::new(), "x-forwarded-for", 1), None);
+        assert_eq!(forwarded_entry(&self::headers(&["203.0.113.7, "]), "x-forwarded-for", 1), None);
+    }
+
+    #[test]
+    fn forwarded_entry_reads_every_header_line() {
+        // The client forged the first line; our proxy added its own line
+        let headers = headers(&["198.51.100.66", "203.0.113.7"]);
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 1).as_deref(), Some("203.0.113.7"));
+        let headers = self::headers(&["198.51.100.66, 203.0.113.7", "10.0.0.2"]);
+        assert_eq!(forwarded_entry(&headers, "x-forwarded-for", 2).as_deref(), Some("203.0.113.7"));
+    }
+}
@@ -0,0 +1,82 @@
+// Pending approvals with approve/reject buttons
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::{approve, list_approvals, reject};
+use crate::approvals::Approval;
+
+/// Approvals waiting for a decision, refreshed after each decision
+#[component]
+pub fn ApprovalsView() -> Element {
+    let mut comment = use_signal(|| String::new());
+    let mut status = use_signal(|| String::new());
+
+    let mut approvals = use_resource(move || async move {
+        let approvals = list_approvals(Some(true)).await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<Approval>>(&approvals).unwrap_or_default()
+    });
+
+    let decide = move |id: String, approved: bool| {
+        spawn(async move {
+            let comment = Some(comment()).filter(|comment| !comment.trim().is_empty());
+            let result = if approved {
+                approve(id.clone(), comment).await
+            } else {
+                reject(id.clone(), comment).await
+            };
+            match result {
+                Ok(_) => status.set(format!("{} {}", if approved { "Approved" } else { "Rejected" }, id)),
+                Err(e) => status.set(format!("Error: {}", e)),
+            }
+            approvals.restart();
+        });
+    };
+
+    rsx! {
+        div {
+            id: "approvals",
+            h5 { "Approvals" }
+            input {
+                placeholder: "comment (optional)",
+                value: "{comment}",
+                oninput: move |event| comment.set(event.value()),
+            }
+            button { onclick: move |_| approvals.restart(), "Refresh" }
+            if approvals().unwrap_or_default().is_empty() {
+                p { "Nothing is waiting for approval" }
+            }
+            for approval in approvals().unwrap_or_default() {
+                div {
+                    key: "{approval.id}",
+                    class: "approval",
+                    strong { "{approval.summary}" }
+                    p {
+                        class: "approval-meta",
+                        "Requested by {approval.requested_by}"
+                        if let Some(expires_at) = approval.expires_at {
+                            " · decided by timeout at {expires_at} ({approval.on_timeout:?})"
+                        }
+                    }
+                    button {
+                        onclick: {
+                            let id = approval.id.clone();
+                            move |_| decide(id.clone(), true)
+                        },
+                        "Approve"
+                    }
+                    button {
+                        onclick: {
+                            let id = approval.id.clone();
+                            move |_| decide(id.clone(), false)
+                        },
+                        "Reject"
+                    }
+                }
+            }
+            if !status().is_empty() {
+                div { class: "approval-status", "{status}" }
+            }
+        }
+    }
+}
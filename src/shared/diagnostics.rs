@@ -0,0 +1,63 @@
+// Diagnostics view showing the startup self-test report
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::get_diagnostics;
+
+/// Runs the startup diagnostics and lists each check with its status
+#[component]
+pub fn DiagnosticsView(on_dismiss: EventHandler<()>) -> Element {
+    let mut refresh = use_signal(|| 0u32);
+
+    let report = use_resource(move || async move {
+        refresh();
+        get_diagnostics()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|report| serde_json::from_str::<serde_json::Value>(&report).map_err(|e| e.to_string()))
+    });
+
+    rsx! {
+        div {
+            id: "diagnostics",
+            h5 { "Diagnostics" }
+            match report() {
+                None => rsx! { p { "Running checks..." } },
+                Some(Err(e)) => rsx! { p { "Diagnostics failed: {e}" } },
+                Some(Ok(report)) => rsx! {
+                    for check in report.get("checks").and_then(|v| v.as_array()).cloned().unwrap_or_default() {
+                        {
+                            let name = check.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                            let status = check.get("status").and_then(|v| v.as_str()).unwrap_or("fail").to_string();
+                            let detail = check.get("detail").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                            let color = match status.as_str() {
+                                "ok" => "#006400",
+                                "warn" => "#b8860b",
+                                _ => "#8B0000",
+                            };
+                            rsx! {
+                                div {
+                                    key: "{name}",
+                                    class: "diagnostics-row",
+                                    span { color: "{color}", "[{status}] " }
+                                    span { "{name}: {detail}" }
+                                }
+                            }
+                        }
+                    }
+                },
+            }
+            div {
+                button {
+                    onclick: move |_| refresh += 1,
+                    "Run Again"
+                }
+                button {
+                    onclick: move |_| on_dismiss.call(()),
+                    "Dismiss"
+                }
+            }
+        }
+    }
+}
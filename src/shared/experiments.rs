@@ -0,0 +1,162 @@
+// Experiment run comparison table and loss-curve overlay
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::list_experiments;
+use crate::experiments::ExperimentRun;
+
+const CURVE_WIDTH: f32 = 360.0;
+const CURVE_HEIGHT: f32 = 120.0;
+const CURVE_COLORS: &[&str] = &["#09ff00", "#ffc107", "#4fc3f7", "#ff5252", "#ce93d8"];
+
+/// Metric columns shown in the table (sortable)
+const METRIC_COLUMNS: &[&str] = &["train_loss", "val_loss", "val_accuracy"];
+
+/// SVG polyline points for a run's per-epoch loss, scaled to the shared maximum
+fn curve_points(run: &ExperimentRun, max_epoch: usize, max_loss: f32) -> String {
+    run.epochs
+        .iter()
+        .map(|m| {
+            let x = m.epoch as f32 / max_epoch.max(1) as f32 * CURVE_WIDTH;
+            let y = CURVE_HEIGHT - (m.loss / max_loss.max(f32::EPSILON)) * CURVE_HEIGHT;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Table of recorded training runs with metric sorting and a loss-curve overlay for selected runs
+#[component]
+pub fn ExperimentsView() -> Element {
+    let mut refresh = use_signal(|| 0u32);
+    let mut sort_by = use_signal(|| "val_loss".to_string());
+    let mut ascending = use_signal(|| true);
+    let mut selected = use_signal(|| Vec::<String>::new());
+
+    let runs = use_resource(move || async move {
+        refresh();
+        let runs = list_experiments().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<ExperimentRun>>(&runs).unwrap_or_default()
+    });
+
+    let sorted_runs = use_memo(move || {
+        let mut runs = runs().unwrap_or_default();
+        let key = sort_by();
+        runs.sort_by(|a, b| {
+            let a = a.metrics.get(&key).copied().unwrap_or(f32::NAN);
+            let b = b.metrics.get(&key).copied().unwrap_or(f32::NAN);
+            if ascending() { a.total_cmp(&b) } else { b.total_cmp(&a) }
+        });
+        runs
+    });
+
+    let compared: Vec<ExperimentRun> = sorted_runs.cloned()
+        .into_iter()
+        .filter(|run| selected().contains(&run.id))
+        .collect();
+    let max_epoch = compared.iter().flat_map(|r| r.epochs.iter().map(|m| m.epoch)).max().unwrap_or(1);
+    let max_loss = compared.iter().flat_map(|r| r.epochs.iter().map(|m| m.loss)).fold(0.0, f32::max);
+
+    rsx! {
+        div {
+            id: "experiments",
+            h5 { "Experiments" }
+            button {
+                onclick: move |_| refresh += 1,
+                "Refresh"
+            }
+            table {
+                thead {
+                    tr {
+                        th { "" }
+                        th { "Run" }
+                        th { "Model" }
+                        for column in METRIC_COLUMNS.iter().map(|c| c.to_string()) {
+                            th {
+                                class: "sortable",
+                                onclick: {
+                                    let column = column.clone();
+                                    move |_| {
+                                        if sort_by() == column {
+                                            ascending.set(!ascending());
+                                        } else {
+                                            sort_by.set(column.clone());
+                                            ascending.set(true);
+                                        }
+                                    }
+                                },
+                                if sort_by() == column {
+                                    if ascending() { "{column} ▲" } else { "{column} ▼" }
+                                } else {
+                                    "{column}"
+                                }
+                            }
+                        }
+                        th { "Data" }
+                    }
+                }
+                tbody {
+                    for run in sorted_runs.cloned() {
+                        tr {
+                            key: "{run.id}",
+                            td {
+                                input {
+                                    r#type: "checkbox",
+                                    checked: selected().contains(&run.id),
+                                    onchange: {
+                                        let id = run.id.clone();
+                                        move |_| {
+                                            let id = id.clone();
+                                            selected.with_mut(|selected| {
+                                                if let Some(pos) = selected.iter().position(|s| *s == id) {
+                                                    selected.remove(pos);
+                                                } else {
+                                                    selected.push(id);
+                                                }
+                                            });
+                                        }
+                                    },
+                                }
+                            }
+                            td { "{run.id}" }
+                            td { "{run.model}" }
+                            for column in METRIC_COLUMNS {
+                                td {
+                                    {run.metrics.get(*column).map(|v| format!("{:.4}", v)).unwrap_or_else(|| "-".to_string())}
+                                }
+                            }
+                            td { "{run.data_hash}" }
+                        }
+                    }
+                }
+            }
+            if !compared.is_empty() {
+                svg {
+                    width: "{CURVE_WIDTH}",
+                    height: "{CURVE_HEIGHT}",
+                    view_box: "0 0 {CURVE_WIDTH} {CURVE_HEIGHT}",
+                    for (idx, run) in compared.iter().enumerate() {
+                        polyline {
+                            key: "{run.id}",
+                            fill: "none",
+                            stroke: CURVE_COLORS[idx % CURVE_COLORS.len()],
+                            stroke_width: "2",
+                            points: curve_points(run, max_epoch, max_loss),
+                        }
+                    }
+                }
+                div {
+                    for (idx, run) in compared.iter().enumerate() {
+                        span {
+                            key: "{run.id}",
+                            color: CURVE_COLORS[idx % CURVE_COLORS.len()],
+                            margin_right: "10px",
+                            "{run.id}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+// Feedback on LLM generations and agent events, with quality stats per model
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::{feedback_stats, list_events, list_generations, submit_feedback};
+use crate::dataset::StoredEvent;
+use crate::feedback::{FeedbackInput, FeedbackStats, FeedbackTarget, Generation, Rating};
+
+/// An output to rate: target, who produced it, prompt (for generations) and the output
+#[derive(Debug, Clone, PartialEq)]
+struct Output {
+    target: FeedbackTarget,
+    key: String,
+    source: String,
+    prompt: Option<String>,
+    text: String,
+}
+
+/// Recent outputs with thumbs up/down, labels and a comment
+#[component]
+pub fn FeedbackView() -> Element {
+    let mut show_events = use_signal(|| false);
+    let mut labels = use_signal(|| String::new());
+    let mut comment = use_signal(|| String::new());
+    let mut status = use_signal(|| String::new());
+
+    let outputs = use_resource(move || async move {
+        if show_events() {
+            let events = list_events(Some(20)).await.unwrap_or_else(|_| "[]".to_string());
+            serde_json::from_str::<Vec<StoredEvent>>(&events)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|event| Output {
+                    key: event.id.clone(),
+                    source: format!("Agent{}", event.agent_id),
+                    target: FeedbackTarget::Event { id: event.id },
+                    prompt: None,
+                    text: event.data,
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let generations = list_generations(Some(20)).await.unwrap_or_else(|_| "[]".to_string());
+            serde_json::from_str::<Vec<Generation>>(&generations)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|generation| Output {
+                    key: generation.id.clone(),
+                    source: format!("Agent{} · {}", generation.agent_id, generation.model),
+                    target: FeedbackTarget::Generation { id: generation.id },
+                    prompt: Some(generation.prompt),
+                    text: generation.output,
+                })
+                .collect::<Vec<_>>()
+        }
+    });
+    let mut stats = use_resource(move || async move {
+        let stats = feedback_stats().await.unwrap_or_else(|_| "{}".to_string());
+        serde_json::from_str::<FeedbackStats>(&stats).unwrap_or_default()
+    });
+
+    let rate = move |target: FeedbackTarget, rating: Option<Rating>| {
+        let input = FeedbackInput {
+            target,
+            rating,
+            labels: labels().split(',').map(|label| label.trim().to_string()).filter(|label| !label.is_empty()).collect(),
+            comment: Some(comment()).filter(|comment| !comment.trim().is_empty()),
+        };
+        spawn(async move {
+            match submit_feedback(input).await {
+                Ok(_) => {
+                    status.set("Feedback saved".to_string());
+                    comment.set(String::new());
+                }
+                Err(e) => status.set(format!("Error: {}", e)),
+            }
+            stats.restart();
+        });
+    };
+
+    let summary = stats().unwrap_or_default();
+    let stats_total = summary.total;
+    let model_lines: Vec<(String, String)> = summary
+        .by_model
+        .into_iter()
+        .map(|(model, quality)| {
+            let approval = quality.approval.map(|approval| format!(" ({:.0}%)", approval * 100.0)).unwrap_or_default();
+            (model, format!("👍 {} 👎 {}{}", quality.up, quality.down, approval))
+        })
+        .collect();
+
+    rsx! {
+        div {
+            id: "feedback",
+            h5 { "Output Feedback" }
+            div {
+                select {
+                    onchange: move |event| show_events.set(event.value() == "events"),
+                    option { value: "generations", "LLM outputs" }
+                    option { value: "events", "Agent events" }
+                }
+                input {
+                    placeholder: "labels, comma separated",
+                    value: "{labels}",
+                    oninput: move |event| labels.set(event.value()),
+                }
+                input {
+                    placeholder: "comment",
+                    value: "{comment}",
+                    oninput: move |event| comment.set(event.value()),
+                }
+            }
+            for output in outputs().unwrap_or_default() {
+                div {
+                    key: "{output.key}",
+                    class: "feedback-output",
+                    div { class: "feedback-source", "{output.source}" }
+                    if let Some(prompt) = &output.prompt {
+                        div { class: "feedback-prompt", "{prompt}" }
+                    }
+                    div { "{output.text}" }
+                    button {
+                        onclick: {
+                            let target = output.target.clone();
+                            move |_| rate(target.clone(), Some(Rating::Up))
+                        },
+                        "👍"
+                    }
+                    button {
+                        onclick: {
+                            let target = output.target.clone();
+                            move |_| rate(target.clone(), Some(Rating::Down))
+                        },
+                        "👎"
+                    }
+                    button {
+                        onclick: {
+                            let target = output.target.clone();
+                            move |_| rate(target.clone(), None)
+                        },
+                        "Annotate"
+                    }
+                }
+            }
+            if !status().is_empty() {
+                div { class: "feedback-status", "{status}" }
+            }
+            div {
+                class: "feedback-stats",
+                "{stats_total} rated outputs"
+                for (model, line) in model_lines {
+                    div { key: "{model}", "{model}: {line}" }
+                }
+            }
+        }
+    }
+}
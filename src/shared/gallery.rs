@@ -0,0 +1,114 @@
+// Gallery of built-in pattern packs, installed through the bundle import
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+use serde_json;
+
+use super::api::{install_gallery_pack, list_gallery};
+
+/// A gallery entry as returned by `/api/bundles/gallery`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Pack {
+    id: String,
+    name: String,
+    description: String,
+    rules: Vec<String>,
+}
+
+/// One line of an import report
+#[derive(Debug, Clone, Deserialize)]
+struct ReportItem {
+    kind: String,
+    id: String,
+    action: String,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Report {
+    dry_run: bool,
+    items: Vec<ReportItem>,
+}
+
+/// Starter packs with a dry-run check and an install button each
+#[component]
+pub fn GalleryView() -> Element {
+    let mut conflict = use_signal(|| "skip".to_string());
+    let mut report = use_signal(|| None::<(String, Result<Report, String>)>);
+
+    let packs = use_resource(move || async move {
+        let packs = list_gallery().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<Pack>>(&packs).unwrap_or_default()
+    });
+
+    let run = move |id: String, dry_run: bool| {
+        spawn(async move {
+            let result = install_gallery_pack(id.clone(), Some(conflict()), Some(dry_run))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<Report>(&text).map_err(|e| e.to_string()));
+            report.set(Some((id, result)));
+        });
+    };
+
+    rsx! {
+        div {
+            id: "gallery",
+            h5 { "Pattern Packs" }
+            label {
+                "Existing items that differ: "
+                select {
+                    value: "{conflict}",
+                    onchange: move |event| conflict.set(event.value()),
+                    option { value: "skip", "keep mine" }
+                    option { value: "overwrite", "overwrite" }
+                    option { value: "rename", "import renamed" }
+                }
+            }
+            for pack in packs().unwrap_or_default() {
+                div {
+                    key: "{pack.id}",
+                    class: "gallery-pack",
+                    strong { "{pack.name}" }
+                    p { "{pack.description}" }
+                    p { class: "gallery-rules", "Rules: {pack.rules.join(\", \")}" }
+                    button {
+                        onclick: {
+                            let id = pack.id.clone();
+                            move |_| run(id.clone(), true)
+                        },
+                        "Check"
+                    }
+                    button {
+                        onclick: {
+                            let id = pack.id.clone();
+                            move |_| run(id.clone(), false)
+                        },
+                        "Install"
+                    }
+                }
+            }
+            match report() {
+                None => rsx! {},
+                Some((id, Err(e))) => rsx! { p { "Failed to install {id}: {e}" } },
+                Some((id, Ok(report))) => rsx! {
+                    p {
+                        if report.dry_run { "Installing {id} would do:" } else { "Installed {id}:" }
+                    }
+                    for item in report.items {
+                        div {
+                            key: "{item.kind}-{item.id}",
+                            class: "gallery-item",
+                            "{item.kind} {item.id}: {item.action}"
+                            if let Some(to) = item.to { " as {to}" }
+                            if let Some(error) = item.error { " ({error})" }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Labeling view for building training datasets from stored events
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::{export_labels, list_events, list_labels, save_label};
+
+/// Lists stored events and lets the user assign labels and export the dataset
+#[component]
+pub fn LabelingView() -> Element {
+    let mut refresh = use_signal(|| 0u32);
+    let mut custom_label = use_signal(|| String::new());
+    let mut export_output = use_signal(|| String::new());
+
+    let events = use_resource(move || async move {
+        refresh();
+        let events = list_events(Some(50)).await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<serde_json::Value>>(&events).unwrap_or_default()
+    });
+    let labels = use_resource(move || async move {
+        refresh();
+        let labels = list_labels().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<serde_json::Value>>(&labels).unwrap_or_default()
+    });
+
+    let label_for = move |event_id: &str| -> Option<String> {
+        labels().unwrap_or_default()
+            .iter()
+            .find(|l| l.get("event_id").and_then(|v| v.as_str()) == Some(event_id))
+            .and_then(|l| l.get("label").and_then(|v| v.as_str()).map(str::to_string))
+    };
+
+    rsx! {
+        div {
+            id: "labeling",
+            h5 { "Label Events" }
+            div {
+                button {
+                    onclick: move |_| refresh += 1,
+                    "Refresh"
+                }
+                input {
+                    placeholder: "Custom class",
+                    value: "{custom_label}",
+                    oninput: move |event| custom_label.set(event.value()),
+                }
+                button {
+                    onclick: move |_| {
+                        spawn(async move {
+                            match export_labels().await {
+                                Ok(jsonl) => export_output.set(jsonl),
+                                Err(e) => export_output.set(format!("Error: {}", e)),
+                            }
+                        });
+                    },
+                    "Export Dataset"
+                }
+            }
+            for event in events().unwrap_or_default() {
+                {
+                    let event_id = event.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                    let agent_id = event.get("agent_id").and_then(|v| v.as_u64()).unwrap_or_default();
+                    let data = event.get("data").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                    let current = label_for(&event_id).unwrap_or_else(|| "unlabeled".to_string());
+                    rsx! {
+                        div {
+                            key: "{event_id}",
+                            class: "label-row",
+                            span { "Agent{agent_id}: {data}" }
+                            span { class: "label-current", "[{current}]" }
+                            for label in crate::dataset::DEFAULT_LABELS.iter().map(|l| l.to_string()) {
+                                button {
+                                    onclick: {
+                                        let event_id = event_id.clone();
+                                        move |_| {
+                                            let event_id = event_id.clone();
+                                            let label = label.clone();
+                                            spawn(async move {
+                                                let _ = save_label(event_id, label).await;
+                                                refresh += 1;
+                                            });
+                                        }
+                                    },
+                                    "{label}"
+                                }
+                            }
+                            button {
+                                disabled: custom_label().is_empty(),
+                                onclick: {
+                                    let event_id = event_id.clone();
+                                    move |_| {
+                                        let event_id = event_id.clone();
+                                        spawn(async move {
+                                            let _ = save_label(event_id, custom_label()).await;
+                                            refresh += 1;
+                                        });
+                                    }
+                                },
+                                "Apply class"
+                            }
+                        }
+                    }
+                }
+            }
+            if !export_output().is_empty() {
+                pre { "{export_output}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Latency budget panel: per-stage percentiles with the slowest stage highlighted
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::get_latency_budget;
+use crate::latency::LatencyBudget;
+
+const SLOWEST_COLOR: &str = "#ff5252";
+const BAR_COLOR: &str = "#4fc3f7";
+const BAR_WIDTH: f64 = 160.0;
+
+/// Table of stage latencies (receive, dequeue, compute, delivery) over the last five minutes
+#[component]
+pub fn LatencyView() -> Element {
+    let mut refresh = use_signal(|| 0u32);
+
+    let budget = use_resource(move || async move {
+        refresh();
+        get_latency_budget()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|budget| serde_json::from_str::<LatencyBudget>(&budget).map_err(|e| e.to_string()))
+    });
+
+    rsx! {
+        div {
+            id: "latency",
+            h5 { "Latency Budget" }
+            button {
+                onclick: move |_| refresh += 1,
+                "Refresh"
+            }
+            match budget() {
+                None => rsx! { p { "Loading latencies..." } },
+                Some(Err(e)) => rsx! { p { "Latency unavailable: {e}" } },
+                Some(Ok(budget)) => {
+                    // Bars show each stage's share of the summed p95s
+                    let total_p95: f64 = budget.stages.iter().map(|s| s.p95_ms).sum();
+                    let minutes = budget.window_secs / 60;
+                    let slowest = budget.stages.iter().find(|s| Some(s.stage) == budget.slowest).cloned();
+                    rsx! {
+                        match slowest {
+                            Some(slowest) => rsx! {
+                                p {
+                                    "Slowest stage: "
+                                    strong { color: SLOWEST_COLOR, "{slowest.stage.name()}" }
+                                    " (p95 {slowest.p95_ms:.1} ms over the last {minutes} min)"
+                                }
+                            },
+                            None => rsx! { p { "No messages in the last {minutes} min." } },
+                        }
+                        table {
+                            thead {
+                                tr {
+                                    th { "Stage" }
+                                    th { "Samples" }
+                                    th { "p50 ms" }
+                                    th { "p95 ms" }
+                                    th { "p99 ms" }
+                                    th { "max ms" }
+                                    th { "" }
+                                }
+                            }
+                            tbody {
+                                for latency in budget.stages.clone() {
+                                    tr {
+                                        key: "{latency.stage.name()}",
+                                        color: if Some(latency.stage) == budget.slowest { SLOWEST_COLOR } else { "inherit" },
+                                        font_weight: if Some(latency.stage) == budget.slowest { "bold" } else { "normal" },
+                                        td { "{latency.stage.name()}" }
+                                        td { "{latency.samples}" }
+                                        td { "{latency.p50_ms:.1}" }
+                                        td { "{latency.p95_ms:.1}" }
+                                        td { "{latency.p99_ms:.1}" }
+                                        td { "{latency.max_ms:.1}" }
+                                        td {
+                                            div {
+                                                height: "8px",
+                                                width: "{bar_width(latency.p95_ms, total_p95):.0}px",
+                                                background_color: if Some(latency.stage) == budget.slowest { SLOWEST_COLOR } else { BAR_COLOR },
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Width of a stage's bar for its share of the summed p95s
+fn bar_width(p95_ms: f64, total_p95: f64) -> f64 {
+    if total_p95 <= 0.0 { 0.0 } else { p95_ms / total_p95 * BAR_WIDTH }
+}
@@ -1,14 +1,34 @@
 // Shared components and utilities used by both desktop and web platforms
 
 pub mod api;
+pub mod approvals;
+pub mod diagnostics;
+pub mod experiments;
+pub mod feedback;
+pub mod gallery;
+pub mod labeling;
+pub mod latency;
+pub mod personas;
+pub mod queue;
+pub mod rules;
 
 use dioxus::prelude::*;
 use serde_json;
 
 // Re-export API functions for convenience
 pub use api::*;
+pub use approvals::ApprovalsView;
+pub use diagnostics::DiagnosticsView;
+pub use experiments::ExperimentsView;
+pub use feedback::FeedbackView;
+pub use gallery::GalleryView;
+pub use labeling::LabelingView;
+pub use latency::LatencyView;
+pub use personas::PersonasView;
+pub use queue::QueueView;
+pub use rules::RulesView;
 
-/// System information component displaying CPU, GPU, and stack info
+/// System information component displaying CPU, GPU (with memory pressure), and stack info
 #[component]
 pub fn SystemInfo() -> Element {
     let mut system_info = use_resource(move || async move {
@@ -30,9 +50,14 @@ pub fn SystemInfo() -> Element {
                         if let Ok(info) = serde_json::from_str::<serde_json::Value>(&info_str) {
                             let cpu = info.get("cpu").and_then(|v| v.as_str()).unwrap_or("N/A");
                             let gpu = info.get("gpu").and_then(|v| v.as_str()).unwrap_or("N/A");
+                            let pressure = info.pointer("/gpu_memory/pressure").and_then(|v| v.as_str()).unwrap_or("normal");
+                            let oom_failures = info.pointer("/gpu_memory/oom_failures").and_then(|v| v.as_u64()).unwrap_or(0);
                             rsx! {
                                 div { "CPU: {cpu}" }
                                 div { "GPU: {gpu}" }
+                                if pressure != "normal" {
+                                    div { "GPU memory: {pressure} ({oom_failures} OOM)" }
+                                }
                             }
                         } else {
                             rsx! { div { "Loading system info" } }
@@ -132,6 +157,7 @@ pub async fn get_system_info() -> Result<String, ServerFnError> {
     let info = json!({
         "cpu": format!("{} ({} cores)", cpu_name, cpu_count),
         "gpu": gpu_info,
+        "gpu_memory": crate::gpu::status(),
     });
     
     Ok(info.to_string())
@@ -0,0 +1,129 @@
+// Per-agent persona editor: system prompt, model and temperature of its LLM calls
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::{delete_agent_persona, get_agent_persona, list_agents, set_agent_persona};
+use crate::connections::Persona;
+use crate::personas::AgentPersona;
+
+/// Pick an agent, edit its persona and save or clear it
+#[component]
+pub fn PersonasView() -> Element {
+    let mut agent_id = use_signal(|| 1u8);
+    let mut system_prompt = use_signal(|| String::new());
+    let mut model = use_signal(|| String::new());
+    let mut temperature = use_signal(|| String::new());
+    let mut status = use_signal(|| String::new());
+
+    let agents = use_resource(move || async move {
+        let agents = list_agents().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<u8>>(&agents).unwrap_or_default()
+    });
+
+    // Load the selected agent's persona into the form
+    let _loaded = use_resource(move || async move {
+        let id = agent_id();
+        let entry = get_agent_persona(id)
+            .await
+            .ok()
+            .and_then(|entry| serde_json::from_str::<Option<AgentPersona>>(&entry).ok())
+            .flatten();
+        let persona = entry.map(|entry| entry.persona).unwrap_or_default();
+        system_prompt.set(persona.system_prompt);
+        model.set(persona.model.unwrap_or_default());
+        temperature.set(persona.temperature.map(|t| t.to_string()).unwrap_or_default());
+    });
+
+    let current_persona = move || -> Result<Persona, String> {
+        let temperature = match temperature().trim() {
+            "" => None,
+            raw => Some(raw.parse::<f32>().map_err(|_| "temperature must be a number".to_string())?),
+        };
+        let persona = Persona {
+            system_prompt: system_prompt(),
+            model: Some(model().trim().to_string()).filter(|model| !model.is_empty()),
+            temperature,
+        };
+        persona.validate()?;
+        Ok(persona)
+    };
+
+    rsx! {
+        div {
+            id: "personas",
+            h5 { "Agent Personas" }
+            div {
+                class: "persona-form",
+                select {
+                    value: "{agent_id}",
+                    onchange: move |event| {
+                        if let Ok(id) = event.value().parse() {
+                            agent_id.set(id);
+                            status.set(String::new());
+                        }
+                    },
+                    for id in agents().unwrap_or_default() {
+                        option { key: "{id}", value: "{id}", "Agent{id}" }
+                    }
+                }
+                input {
+                    placeholder: "model (default)",
+                    value: "{model}",
+                    oninput: move |event| model.set(event.value()),
+                }
+                input {
+                    class: "persona-temperature",
+                    placeholder: "temperature",
+                    value: "{temperature}",
+                    oninput: move |event| temperature.set(event.value()),
+                }
+            }
+            textarea {
+                placeholder: "System prompt",
+                rows: "4",
+                value: "{system_prompt}",
+                oninput: move |event| system_prompt.set(event.value()),
+            }
+            div {
+                button {
+                    onclick: move |_| {
+                        let persona = match current_persona() {
+                            Ok(persona) => persona,
+                            Err(e) => {
+                                status.set(format!("Invalid persona: {}", e));
+                                return;
+                            }
+                        };
+                        spawn(async move {
+                            match set_agent_persona(agent_id(), persona).await {
+                                Ok(_) => status.set("Saved".to_string()),
+                                Err(e) => status.set(format!("Error: {}", e)),
+                            }
+                        });
+                    },
+                    "Save"
+                }
+                button {
+                    onclick: move |_| {
+                        spawn(async move {
+                            match delete_agent_persona(agent_id()).await {
+                                Ok(_) => {
+                                    system_prompt.set(String::new());
+                                    model.set(String::new());
+                                    temperature.set(String::new());
+                                    status.set("Cleared; the agent uses the default model".to_string());
+                                }
+                                Err(e) => status.set(format!("Error: {}", e)),
+                            }
+                        });
+                    },
+                    "Clear"
+                }
+            }
+            if !status().is_empty() {
+                div { class: "persona-status", "{status}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// Job queue per agent with cancel, requeue and move actions
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+use serde_json;
+
+use super::api::{cancel_job, list_agents, list_jobs, requeue_job};
+
+/// A job as returned by `/api/queue`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Job {
+    id: u64,
+    agent_id: u8,
+    kind: String,
+    preview: String,
+    status: String,
+    replayable: bool,
+}
+
+/// Pending, running and lost jobs grouped by agent
+#[component]
+pub fn QueueView() -> Element {
+    let mut status = use_signal(|| String::new());
+    let mut move_to = use_signal(|| 1u8);
+
+    let mut jobs = use_resource(move || async move {
+        let jobs = list_jobs(None).await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<Job>>(&jobs).unwrap_or_default()
+    });
+    let agents = use_resource(move || async move {
+        let agents = list_agents().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<u8>>(&agents).unwrap_or_default()
+    });
+
+    let act = move |id: u64, action: &'static str| {
+        spawn(async move {
+            let result = match action {
+                "cancel" => cancel_job(id).await,
+                "requeue" => requeue_job(id, None).await,
+                _ => requeue_job(id, Some(move_to())).await,
+            };
+            match result {
+                Ok(_) if action == "move" => status.set(format!("Moved job {} to Agent{}", id, move_to())),
+                Ok(_) => status.set(format!("Job {}: {}", id, action)),
+                Err(e) => status.set(format!("Error: {}", e)),
+            }
+            jobs.restart();
+        });
+    };
+
+    let all_jobs = jobs().unwrap_or_default();
+    let mut agent_ids: Vec<u8> = all_jobs.iter().map(|job| job.agent_id).collect();
+    agent_ids.sort();
+    agent_ids.dedup();
+
+    rsx! {
+        div {
+            id: "queue",
+            h5 { "Job Queue" }
+            button { onclick: move |_| jobs.restart(), "Refresh" }
+            label {
+                " Move to: "
+                select {
+                    value: "{move_to}",
+                    onchange: move |event| {
+                        if let Ok(id) = event.value().parse() {
+                            move_to.set(id);
+                        }
+                    },
+                    for id in agents().unwrap_or_default() {
+                        option { key: "{id}", value: "{id}", "Agent{id}" }
+                    }
+                }
+            }
+            if all_jobs.is_empty() {
+                p { "No queued jobs" }
+            }
+            for agent_id in agent_ids {
+                div {
+                    key: "{agent_id}",
+                    class: "queue-agent",
+                    strong { "Agent{agent_id}" }
+                    for job in all_jobs.iter().filter(|job| job.agent_id == agent_id).cloned() {
+                        div {
+                            key: "{job.id}",
+                            class: "queue-job",
+                            span { class: "queue-status", "#{job.id} {job.status} {job.kind}" }
+                            span { class: "queue-preview", " {job.preview}" }
+                            if job.status == "pending" || job.status == "lost" {
+                                button { onclick: move |_| act(job.id, "cancel"), "Cancel" }
+                            }
+                            if job.replayable && job.status != "cancelled" {
+                                button { onclick: move |_| act(job.id, "requeue"), "Requeue" }
+                                button { onclick: move |_| act(job.id, "move"), "Move" }
+                            }
+                        }
+                    }
+                }
+            }
+            if !status().is_empty() {
+                div { class: "queue-status-line", "{status}" }
+            }
+        }
+    }
+}
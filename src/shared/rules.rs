@@ -0,0 +1,277 @@
+// Pattern rule editor with local (in-browser) preview against series history
+
+use dioxus::prelude::*;
+use serde_json;
+
+use super::api::{list_rules, list_timeseries, query_timeseries, rollback_rule, rule_history, save_rule};
+use crate::rules::{Comparison, Detector, PatternRule, RuleChange, RuleMatch, RuleVersion};
+use crate::timeseries::Point;
+
+/// Detector choices offered by the editor: (value, label, first param, second param)
+const DETECTORS: &[(&str, &str, &str, &str)] = &[
+    ("threshold", "Threshold (>)", "value", ""),
+    ("zscore", "Z-score", "window", "threshold"),
+    ("rate", "Rate of change", "max per sec", ""),
+    ("ewma", "EWMA deviation", "alpha", "tolerance"),
+];
+
+/// Build a detector from the editor fields
+fn build_detector(kind: &str, first: &str, second: &str) -> Result<Detector, String> {
+    let number = |raw: &str, name: &str| raw.trim().parse::<f64>().map_err(|_| format!("{} must be a number", name));
+    match kind {
+        "threshold" => Ok(Detector::Threshold { op: Comparison::Gt, value: number(first, "value")? }),
+        "zscore" => Ok(Detector::ZScore {
+            window: first.trim().parse().map_err(|_| "window must be a whole number".to_string())?,
+            threshold: number(second, "threshold")?,
+        }),
+        "rate" => Ok(Detector::RateOfChange { max_per_sec: number(first, "max per sec")? }),
+        "ewma" => Ok(Detector::Ewma { alpha: number(first, "alpha")?, tolerance: number(second, "tolerance")? }),
+        other => Err(format!("unknown detector {}", other)),
+    }
+}
+
+/// Edit a pattern rule, preview its matches locally, then save it on the server
+#[component]
+pub fn RulesView() -> Element {
+    let mut refresh = use_signal(|| 0u32);
+    let mut rule_id = use_signal(|| String::new());
+    let mut name = use_signal(|| String::new());
+    let mut series = use_signal(|| String::new());
+    let mut detector = use_signal(|| "threshold".to_string());
+    let mut first_param = use_signal(|| String::new());
+    let mut second_param = use_signal(|| String::new());
+    let mut lookback_hours = use_signal(|| "24".to_string());
+    let mut matches = use_signal(|| Vec::<RuleMatch>::new());
+    let mut status = use_signal(|| String::new());
+    // Saved rule whose history is shown
+    let mut history_id = use_signal(|| String::new());
+
+    let series_names = use_resource(move || async move {
+        refresh();
+        let names = list_timeseries().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<String>>(&names).unwrap_or_default()
+    });
+    let rules = use_resource(move || async move {
+        refresh();
+        let rules = list_rules().await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<PatternRule>>(&rules).unwrap_or_default()
+    });
+    let history = use_resource(move || async move {
+        refresh();
+        let id = history_id();
+        if id.is_empty() {
+            return Vec::new();
+        }
+        let versions = rule_history(id).await.unwrap_or_else(|_| "[]".to_string());
+        serde_json::from_str::<Vec<RuleVersion>>(&versions).unwrap_or_default()
+    });
+
+    let current_rule = move || -> Result<PatternRule, String> {
+        let rule = PatternRule {
+            id: rule_id(),
+            name: name(),
+            series: series(),
+            tags: Default::default(),
+            detector: build_detector(&detector(), &first_param(), &second_param())?,
+            enabled: true,
+        };
+        rule.validate()?;
+        Ok(rule)
+    };
+
+    let (_, _, first_label, second_label) = DETECTORS
+        .iter()
+        .find(|(value, ..)| *value == detector())
+        .copied()
+        .unwrap_or(DETECTORS[0]);
+
+    rsx! {
+        div {
+            id: "rules",
+            h5 { "Pattern Rules" }
+            div {
+                class: "rule-form",
+                input {
+                    placeholder: "id",
+                    value: "{rule_id}",
+                    oninput: move |event| rule_id.set(event.value()),
+                }
+                input {
+                    placeholder: "name",
+                    value: "{name}",
+                    oninput: move |event| name.set(event.value()),
+                }
+                select {
+                    value: "{series}",
+                    onchange: move |event| series.set(event.value()),
+                    option { value: "", "series..." }
+                    for series_name in series_names().unwrap_or_default() {
+                        option { key: "{series_name}", value: "{series_name}", "{series_name}" }
+                    }
+                }
+                select {
+                    value: "{detector}",
+                    onchange: move |event| detector.set(event.value()),
+                    for (value, label, ..) in DETECTORS.iter() {
+                        option { key: "{value}", value: "{value}", "{label}" }
+                    }
+                }
+                input {
+                    placeholder: first_label,
+                    value: "{first_param}",
+                    oninput: move |event| first_param.set(event.value()),
+                }
+                if !second_label.is_empty() {
+                    input {
+                        placeholder: second_label,
+                        value: "{second_param}",
+                        oninput: move |event| second_param.set(event.value()),
+                    }
+                }
+                input {
+                    class: "rule-lookback",
+                    title: "Preview window (hours)",
+                    value: "{lookback_hours}",
+                    oninput: move |event| lookback_hours.set(event.value()),
+                }
+                button {
+                    onclick: move |_| {
+                        let rule = match current_rule() {
+                            Ok(rule) => rule,
+                            Err(e) => {
+                                status.set(format!("Invalid rule: {}", e));
+                                return;
+                            }
+                        };
+                        let hours = lookback_hours().trim().parse::<u64>().unwrap_or(24);
+                        spawn(async move {
+                            let now = now_millis();
+                            let from = now.saturating_sub(hours * 60 * 60 * 1000);
+                            let history = query_timeseries(rule.series.clone(), Some(from), None)
+                                .await
+                                .ok()
+                                .and_then(|points| serde_json::from_str::<Vec<Point>>(&points).ok())
+                                .unwrap_or_default();
+                            // Evaluated locally: same rule engine as the server
+                            let found = rule.evaluate(&history);
+                            status.set(format!("{} matches in {} points", found.len(), history.len()));
+                            matches.set(found);
+                        });
+                    },
+                    "Preview"
+                }
+                button {
+                    onclick: move |_| {
+                        let rule = match current_rule() {
+                            Ok(rule) => rule,
+                            Err(e) => {
+                                status.set(format!("Invalid rule: {}", e));
+                                return;
+                            }
+                        };
+                        spawn(async move {
+                            match save_rule(rule).await {
+                                Ok(()) => status.set("Saved".to_string()),
+                                Err(e) => status.set(format!("Error: {}", e)),
+                            }
+                            refresh += 1;
+                        });
+                    },
+                    "Save"
+                }
+            }
+            if !status().is_empty() {
+                div { class: "rule-status", "{status}" }
+            }
+            for rule_match in matches().iter().take(50) {
+                div {
+                    key: "{rule_match.ts}",
+                    class: "rule-match",
+                    "{rule_match.ts}: {rule_match.value} ({rule_match.reason})"
+                }
+            }
+            h6 { "Saved rules" }
+            for rule in rules().unwrap_or_default() {
+                div {
+                    key: "{rule.id}",
+                    class: "rule-row",
+                    onclick: {
+                        let rule = rule.clone();
+                        move |_| {
+                            rule_id.set(rule.id.clone());
+                            history_id.set(rule.id.clone());
+                            name.set(rule.name.clone());
+                            series.set(rule.series.clone());
+                            let (kind, first, second) = match &rule.detector {
+                                Detector::Threshold { value, .. } => ("threshold", value.to_string(), String::new()),
+                                Detector::ZScore { window, threshold } => ("zscore", window.to_string(), threshold.to_string()),
+                                Detector::RateOfChange { max_per_sec } => ("rate", max_per_sec.to_string(), String::new()),
+                                Detector::Ewma { alpha, tolerance } => ("ewma", alpha.to_string(), tolerance.to_string()),
+                            };
+                            detector.set(kind.to_string());
+                            first_param.set(first);
+                            second_param.set(second);
+                        }
+                    },
+                    "{rule.name} ({rule.series})"
+                }
+            }
+            if !history_id().is_empty() {
+                h6 { "History of {history_id}" }
+                for version in history().unwrap_or_default().into_iter().rev() {
+                    div {
+                        key: "{version.version}",
+                        class: "rule-version",
+                        span { "v{version.version} {describe_change(&version.change)} by {version.author}: " }
+                        span {
+                            class: "rule-diff",
+                            {version.diff.iter().map(|change| change.field.clone()).collect::<Vec<_>>().join(", ")}
+                        }
+                        if version.rule.is_some() {
+                            button {
+                                onclick: {
+                                    let (id, number) = (version.rule_id.clone(), version.version);
+                                    move |_| {
+                                        let id = id.clone();
+                                        spawn(async move {
+                                            match rollback_rule(id, number).await {
+                                                Ok(_) => status.set(format!("Restored version {}", number)),
+                                                Err(e) => status.set(format!("Error: {}", e)),
+                                            }
+                                            refresh += 1;
+                                        });
+                                    }
+                                },
+                                "Restore"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn describe_change(change: &RuleChange) -> String {
+    match change {
+        RuleChange::Saved => "saved".to_string(),
+        RuleChange::Deleted => "deleted".to_string(),
+        RuleChange::RolledBack { version } => format!("restored v{}", version),
+    }
+}
+
+/// Current time in milliseconds (`SystemTime` is unavailable in the browser)
+#[cfg(target_arch = "wasm32")]
+fn now_millis() -> u64 {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = Date, js_name = now)]
+        fn date_now() -> f64;
+    }
+    date_now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::storage::now_millis;
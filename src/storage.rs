@@ -0,0 +1,216 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::encryption::wrap_storage;
+
+// ============================================================================
+// Storage Layer
+// ============================================================================
+
+/// Default directory for persisted data
+pub const DEFAULT_DATA_DIR: &str = "data";
+
+/// Collections encrypted at rest when a key is set (see `encryption`); `blobs`
+/// also covers blob contents
+pub const DEFAULT_ENCRYPTED_COLLECTIONS: &[&str] =
+    &["events", "generations", "feedback", "agent_state", "documents", "document_chunks", "blobs"];
+
+/// Key-value document store grouped into named collections
+pub trait Storage: Send + Sync {
+    /// Insert or replace a document
+    fn put(&self, collection: &str, key: &str, value: &serde_json::Value) -> anyhow::Result<()>;
+    /// Fetch a document by key
+    fn get(&self, collection: &str, key: &str) -> anyhow::Result<Option<serde_json::Value>>;
+    /// All documents in a collection, ordered by key
+    fn list(&self, collection: &str) -> anyhow::Result<Vec<(String, serde_json::Value)>>;
+    /// Remove a document, returning whether it existed
+    fn delete(&self, collection: &str, key: &str) -> anyhow::Result<bool>;
+
+    /// Replace a document with `f(current)` and return the new value; `None`
+    /// from `f` deletes it (or leaves it missing)
+    ///
+    /// No other update of the same document runs in between. The default only
+    /// holds that within one process; backends shared between instances
+    /// override it. `f` must not call `update` itself.
+    fn update(&self, collection: &str, key: &str, f: &mut UpdateFn<'_>) -> anyhow::Result<Option<serde_json::Value>> {
+        static UPDATES: Mutex<()> = Mutex::new(());
+        let _guard = UPDATES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let next = f(self.get(collection, key)?)?;
+        match &next {
+            Some(value) => self.put(collection, key, value)?,
+            None => {
+                self.delete(collection, key)?;
+            }
+        }
+        Ok(next)
+    }
+}
+
+/// Read-modify-write step of `Storage::update`
+pub type UpdateFn<'a> = dyn FnMut(Option<serde_json::Value>) -> anyhow::Result<Option<serde_json::Value>> + Send + 'a;
+
+/// Storage backed by one JSON file per document: `<root>/<collection>/<key>.json`
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn collection_dir(&self, collection: &str) -> PathBuf {
+        self.root.join(sanitize(collection))
+    }
+
+    fn document_path(&self, collection: &str, key: &str) -> PathBuf {
+        self.collection_dir(collection).join(format!("{}.json", sanitize(key)))
+    }
+}
+
+/// Keep keys filesystem-safe
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+impl Storage for FileStorage {
+    fn put(&self, collection: &str, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        std::fs::create_dir_all(self.collection_dir(collection))?;
+        // Write to a temp file first so readers never see partial documents;
+        // the name is unique so concurrent puts of the same key don't mix
+        static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let path = self.document_path(collection, key);
+        let tmp_path = path.with_extension(format!(
+            "json.{}-{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let bytes = serde_json::to_vec_pretty(value)?;
+        if let Err(e) = std::fs::write(&tmp_path, bytes).and_then(|()| std::fs::rename(&tmp_path, &path)) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn get(&self, collection: &str, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        match std::fs::read(self.document_path(collection, key)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self, collection: &str) -> anyhow::Result<Vec<(String, serde_json::Value)>> {
+        let entries = match std::fs::read_dir(self.collection_dir(collection)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut documents = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(key) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+                continue;
+            };
+            documents.push((key, serde_json::from_slice(&std::fs::read(&path)?)?));
+        }
+        documents.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(documents)
+    }
+
+    fn delete(&self, collection: &str, key: &str) -> anyhow::Result<bool> {
+        match std::fs::remove_file(self.document_path(collection, key)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+static STORAGE: OnceLock<Box<dyn Storage>> = OnceLock::new();
+
+/// Get the process-wide storage (`PATTERN_CLOCK_DATA_DIR`, default `data/`, or
+/// Postgres when `PATTERN_CLOCK_DATABASE_URL` is set), sealing sensitive
+/// collections when `PATTERN_CLOCK_ENCRYPTION_KEY` is set (see `encryption`)
+///
+/// Panics if the database can't be reached: falling back to local files would
+/// split the state shared with other instances.
+pub fn storage() -> &'static dyn Storage {
+    STORAGE.get_or_init(|| wrap_storage(open_storage())).as_ref()
+}
+
+/// No encryption in the browser
+#[cfg(target_arch = "wasm32")]
+fn wrap_storage(storage: Box<dyn Storage>) -> Box<dyn Storage> {
+    storage
+}
+
+fn open_storage() -> Box<dyn Storage> {
+    let config = crate::config::config();
+    #[cfg(all(feature = "postgres", not(target_arch = "wasm32")))]
+    if let Some(url) = &config.database_url {
+        match crate::postgres::PostgresStorage::connect(url.expose(), config.db_pool_size) {
+            Ok(storage) => return Box::new(storage),
+            Err(e) => panic!("[Storage] Failed to connect to Postgres: {}", e),
+        }
+    }
+    Box::new(FileStorage::new(&config.data_dir))
+}
+
+/// Replace the process-wide storage; fails if it was already used or set
+pub fn set_storage(storage: Box<dyn Storage>) -> Result<(), Box<dyn Storage>> {
+    STORAGE.set(storage)
+}
+
+/// Store a serializable value
+pub fn put_typed<T: Serialize>(collection: &str, key: &str, value: &T) -> anyhow::Result<()> {
+    storage().put(collection, key, &serde_json::to_value(value)?)
+}
+
+/// Load and deserialize a value
+pub fn get_typed<T: DeserializeOwned>(collection: &str, key: &str) -> anyhow::Result<Option<T>> {
+    storage().get(collection, key)?
+        .map(|value| serde_json::from_value(value).map_err(Into::into))
+        .transpose()
+}
+
+/// Load and deserialize a whole collection, skipping documents that don't match `T`
+pub fn list_typed<T: DeserializeOwned>(collection: &str) -> anyhow::Result<Vec<T>> {
+    Ok(storage().list(collection)?
+        .into_iter()
+        .filter_map(|(_, value)| serde_json::from_value(value).ok())
+        .collect())
+}
+
+/// Replace a document with `f(current)` in one step (see `Storage::update`)
+pub fn update_typed<T, F>(collection: &str, key: &str, mut f: F) -> anyhow::Result<Option<T>>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnMut(Option<T>) -> anyhow::Result<Option<T>> + Send,
+{
+    let next = storage().update(collection, key, &mut |current| {
+        let current = current.map(serde_json::from_value).transpose()?;
+        f(current)?.map(|next| serde_json::to_value(next).map_err(Into::into)).transpose()
+    })?;
+    next.map(|value| serde_json::from_value(value).map_err(Into::into)).transpose()
+}
+
+/// Milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,63 @@
+use crate::config::ServiceConfig;
+use crate::connections::LlmProvider;
+
+// ============================================================================
+// Rolling History Summarizer
+// ============================================================================
+
+/// Settings for condensing agent history into a rolling summary
+///
+/// How often agents are asked to summarize is `ServiceConfig::summary_interval`.
+#[derive(Debug, Clone)]
+pub struct SummarizerConfig {
+    /// History length that triggers a summarization pass
+    pub max_history: usize,
+    /// Number of most recent entries kept verbatim after a pass
+    pub keep_recent: usize,
+    /// Number of entries sent to the LLM per map step
+    pub chunk_size: usize,
+}
+
+impl SummarizerConfig {
+    pub fn from_config(config: &ServiceConfig) -> Self {
+        Self {
+            max_history: config.summary_max_history,
+            keep_recent: config.summary_keep_recent,
+            chunk_size: config.summary_chunk_size,
+        }
+    }
+
+    /// The settings of the running configuration, so reloads apply to the next pass
+    pub fn current() -> Self {
+        Self::from_config(&crate::config::config())
+    }
+}
+
+/// Condense `entries` into a new rolling summary (map-reduce over chunks)
+///
+/// Each chunk is summarized independently (map), then the chunk summaries are
+/// merged together with the previous summary into a single paragraph (reduce).
+pub async fn summarize_history(
+    provider: &dyn LlmProvider,
+    previous_summary: Option<&str>,
+    entries: &[String],
+    chunk_size: usize,
+) -> anyhow::Result<String> {
+    let mut chunk_summaries = Vec::new();
+    for chunk in entries.chunks(chunk_size.max(1)) {
+        let prompt = format!(
+            "Summarize the following agent activity log in a few sentences. \
+             Keep names, numbers and recurring patterns.\n\n{}",
+            chunk.join("\n")
+        );
+        chunk_summaries.push(provider.generate(&prompt).await?);
+    }
+
+    let prompt = format!(
+        "Merge the previous summary and the new notes into one concise summary \
+         of the agent's history.\n\nPrevious summary:\n{}\n\nNew notes:\n{}",
+        previous_summary.unwrap_or("(none)"),
+        chunk_summaries.join("\n")
+    );
+    provider.generate(&prompt).await
+}
@@ -0,0 +1,179 @@
+use opentelemetry::metrics::{Counter, Gauge, Histogram, UpDownCounter};
+use opentelemetry::trace::{FutureExt, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use std::future::Future;
+use std::sync::OnceLock;
+
+// ============================================================================
+// OpenTelemetry Traces and Metrics
+// ============================================================================
+//
+// Export is enabled by setting `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g.
+// `http://localhost:4318`); the standard `OTEL_*` variables (headers,
+// timeouts, `OTEL_SERVICE_NAME`) are honored by the exporter. Without an
+// endpoint, spans and metrics go to the no-op providers.
+
+/// Instrumentation scope and default service name
+pub const SERVICE_NAME: &str = "pattern-clock";
+
+/// Instruments shared by the server, agents and LLM connections
+pub struct Instruments {
+    /// Messages handled by agents (`agent.id`, `message`)
+    pub agent_messages: Counter<u64>,
+    /// Time spent handling one agent message, in seconds
+    pub agent_message_duration: Histogram<f64>,
+    /// LLM requests (`model`, `status`)
+    pub llm_requests: Counter<u64>,
+    /// LLM request latency, in seconds
+    pub llm_duration: Histogram<f64>,
+    /// HTTP requests to the LLM server waiting for a response
+    pub llm_http_in_flight: UpDownCounter<i64>,
+    /// HTTP responses from the LLM server (`version`, `status`)
+    pub llm_http_responses: Counter<u64>,
+    /// Server function calls (`route`, `status`)
+    pub http_requests: Counter<u64>,
+    /// Messages skipped or lost by slow fan-out subscribers (`channel`, `policy`)
+    pub fanout_lagged: Counter<u64>,
+    /// GPU out-of-memory failures (`operation`, `action`)
+    pub gpu_oom: Counter<u64>,
+    /// Time spent loading a model into the pool, in seconds (`model`)
+    pub model_load_duration: Histogram<f64>,
+    /// Models evicted from the pool (`model`)
+    pub model_pool_evictions: Counter<u64>,
+    /// Time a job waited for a compute thread, in seconds (`job`)
+    pub compute_queue_wait: Histogram<f64>,
+    /// Compute threads running a job
+    pub compute_busy: Gauge<u64>,
+    /// Tasks alive on a runtime (`runtime`: workers, background)
+    pub runtime_tasks: Gauge<u64>,
+    /// Tasks or jobs waiting to run (`runtime`: workers, compute)
+    pub runtime_queue_depth: Gauge<u64>,
+    /// Compressed size over original size of compressed bodies and payloads (`kind`, `codec`)
+    pub compression_ratio: Histogram<f64>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Providers {
+    tracer: opentelemetry_sdk::trace::SdkTracerProvider,
+    meter: opentelemetry_sdk::metrics::SdkMeterProvider,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static PROVIDERS: OnceLock<Option<Providers>> = OnceLock::new();
+static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+
+/// Install the OTLP exporters if `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+///
+/// Must run before the first call to `instruments()`, since instruments bind
+/// to the meter provider installed at creation time.
+pub fn init() {
+    #[cfg(not(target_arch = "wasm32"))]
+    PROVIDERS.get_or_init(|| {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        match build_providers() {
+            Ok(providers) => {
+                global::set_tracer_provider(providers.tracer.clone());
+                global::set_meter_provider(providers.meter.clone());
+                log_info!("[Telemetry] Exporting traces and metrics to {}", endpoint);
+                Some(providers)
+            }
+            Err(e) => {
+                log_error!("[Telemetry] Failed to set up OTLP export to {}: {}", endpoint, e);
+                None
+            }
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn build_providers() -> anyhow::Result<Providers> {
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::Resource;
+
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE_NAME.to_string());
+    let resource = Resource::builder().with_service_name(service_name).build();
+
+    let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+        .build()?;
+    let tracer = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(span_exporter)
+        .with_resource(resource.clone())
+        .build();
+
+    let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+        .with_http()
+        .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+        .build()?;
+    let meter = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
+        .with_periodic_exporter(metric_exporter)
+        .with_resource(resource)
+        .build();
+
+    Ok(Providers { tracer, meter })
+}
+
+/// Flush pending spans and metrics (call before exiting)
+pub fn shutdown() {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(Some(providers)) = PROVIDERS.get() {
+        let _ = providers.tracer.shutdown();
+        let _ = providers.meter.shutdown();
+    }
+}
+
+/// Shared instruments, created on first use
+pub fn instruments() -> &'static Instruments {
+    INSTRUMENTS.get_or_init(|| {
+        init();
+        let meter = global::meter(SERVICE_NAME);
+        Instruments {
+            agent_messages: meter.u64_counter("agent.messages").build(),
+            agent_message_duration: meter.f64_histogram("agent.message.duration").with_unit("s").build(),
+            llm_requests: meter.u64_counter("llm.requests").build(),
+            llm_duration: meter.f64_histogram("llm.duration").with_unit("s").build(),
+            llm_http_in_flight: meter.i64_up_down_counter("llm.http.in_flight").build(),
+            llm_http_responses: meter.u64_counter("llm.http.responses").build(),
+            http_requests: meter.u64_counter("http.server.requests").build(),
+            fanout_lagged: meter.u64_counter("fanout.lagged").build(),
+            gpu_oom: meter.u64_counter("gpu.oom").build(),
+            model_load_duration: meter.f64_histogram("model.load.duration").with_unit("s").build(),
+            model_pool_evictions: meter.u64_counter("model.pool.evictions").build(),
+            compute_queue_wait: meter.f64_histogram("compute.queue.wait").with_unit("s").build(),
+            compute_busy: meter.u64_gauge("compute.busy").build(),
+            runtime_tasks: meter.u64_gauge("runtime.tasks").build(),
+            runtime_queue_depth: meter.u64_gauge("runtime.queue_depth").build(),
+            compression_ratio: meter.f64_histogram("compression.ratio").build(),
+        }
+    })
+}
+
+/// Run `future` inside a new span, marking the span as failed on `Err`
+pub async fn in_span<T, E, F>(name: &'static str, attributes: Vec<KeyValue>, future: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let tracer = global::tracer(SERVICE_NAME);
+    let span = tracer.span_builder(name).with_attributes(attributes).start(&tracer);
+    let cx = Context::current_with_span(span);
+    let result = future.with_context(cx.clone()).await;
+    if let Err(e) = &result {
+        cx.span().set_status(Status::error(e.to_string()));
+    }
+    cx.span().end();
+    result
+}
+
+/// Trace a server function call and count it under `route`
+pub async fn traced_request<T, E, F>(route: &'static str, future: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let result = in_span("http.request", vec![KeyValue::new("http.route", route)], future).await;
+    let status = if result.is_ok() { "ok" } else { "error" };
+    instruments().http_requests.add(1, &[KeyValue::new("route", route), KeyValue::new("status", status)]);
+    result
+}
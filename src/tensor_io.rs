@@ -0,0 +1,307 @@
+use burn::tensor::backend::Backend;
+use burn::tensor::{Int, Tensor, TensorData};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+// ============================================================================
+// Tensor Serialization (JSON <-> Burn)
+// ============================================================================
+//
+// Tensors cross the API and MCP boundaries in one of two forms:
+//
+//     {"shape": [2, 3], "dtype": "f32", "data": [1, 2, 3, 4, 5, 6]}
+//     [[1, 2, 3], [4, 5, 6]]
+//
+// Flat data is always row-major (C order, last dimension fastest), which is
+// also how nested arrays are flattened and how Burn lays out `TensorData`.
+// Everything is validated before a tensor is built, so a bad request gets a
+// message naming the offending dimension instead of a backend panic.
+
+/// Element type of a serialized tensor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DType {
+    #[default]
+    F32,
+    F64,
+    I32,
+    I64,
+    Bool,
+}
+
+impl DType {
+    fn is_integer(self) -> bool {
+        matches!(self, DType::I32 | DType::I64 | DType::Bool)
+    }
+}
+
+/// Why a payload could not be converted to (or from) a tensor
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorError {
+    /// The payload has a different number of dimensions than the model expects
+    RankMismatch { expected: usize, actual: usize },
+    /// One dimension has the wrong size
+    ShapeMismatch { dim: usize, name: &'static str, expected: usize, shape: Vec<usize> },
+    /// `data` does not hold `product(shape)` values
+    LengthMismatch { shape: Vec<usize>, expected: usize, actual: usize },
+    /// Nested arrays of different lengths at the same depth
+    Ragged { path: String, expected: usize, actual: usize },
+    /// A value that is not valid for the dtype (non-numeric, NaN, fractional integer, ...)
+    InvalidValue { index: usize, reason: String },
+    /// The payload is neither a nested array nor a `{shape, data}` object
+    Malformed(String),
+}
+
+impl fmt::Display for TensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TensorError::RankMismatch { expected, actual } => {
+                write!(f, "expected a {}-dimensional tensor, got {} dimensions", expected, actual)
+            }
+            TensorError::ShapeMismatch { dim, name, expected, shape } => write!(
+                f,
+                "dimension {} ({}) must be {}, got {} in shape {:?}",
+                dim, name, expected, shape[*dim], shape
+            ),
+            TensorError::LengthMismatch { shape, expected, actual } => write!(
+                f,
+                "shape {:?} needs {} values in row-major order, got {}",
+                shape, expected, actual
+            ),
+            TensorError::Ragged { path, expected, actual } => write!(
+                f,
+                "ragged array at {}: expected {} elements like its siblings, got {}",
+                path, expected, actual
+            ),
+            TensorError::InvalidValue { index, reason } => {
+                write!(f, "invalid value at flat index {}: {}", index, reason)
+            }
+            TensorError::Malformed(reason) => write!(f, "malformed tensor: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TensorError {}
+
+/// Tensor as exchanged over JSON (row-major `data`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonTensor {
+    pub shape: Vec<usize>,
+    #[serde(default)]
+    pub dtype: DType,
+    pub data: Vec<f64>,
+}
+
+impl JsonTensor {
+    /// Parse either a `{shape, dtype, data}` object or a nested array, and validate it
+    pub fn parse(value: &Value) -> Result<Self, TensorError> {
+        let tensor = match value {
+            Value::Object(_) => serde_json::from_value::<JsonTensor>(value.clone())
+                .map_err(|e| TensorError::Malformed(e.to_string()))?,
+            Value::Array(_) => Self::from_nested(value)?,
+            other => {
+                return Err(TensorError::Malformed(format!(
+                    "expected a nested array or a {{shape, data}} object, got {}",
+                    json_type(other)
+                )))
+            }
+        };
+        tensor.validate()?;
+        Ok(tensor)
+    }
+
+    /// Flatten a (rectangular) nested array in row-major order
+    ///
+    /// Booleans are accepted as 0/1 and make the dtype `bool` when every
+    /// element is a boolean.
+    pub fn from_nested(value: &Value) -> Result<Self, TensorError> {
+        let mut shape = Vec::new();
+        let mut cursor = value;
+        while let Value::Array(items) = cursor {
+            shape.push(items.len());
+            match items.first() {
+                Some(first) => cursor = first,
+                None => break,
+            }
+        }
+
+        let mut data = Vec::with_capacity(shape.iter().product());
+        let mut all_bool = true;
+        flatten(value, &shape, 0, &mut String::from("$"), &mut data, &mut all_bool)?;
+        let dtype = if all_bool && !data.is_empty() { DType::Bool } else { DType::F32 };
+        Ok(Self { shape, dtype, data })
+    }
+
+    /// Check the value count against the shape and each value against the dtype
+    pub fn validate(&self) -> Result<(), TensorError> {
+        let expected: usize = self.shape.iter().product();
+        if expected != self.data.len() {
+            return Err(TensorError::LengthMismatch {
+                shape: self.shape.clone(),
+                expected,
+                actual: self.data.len(),
+            });
+        }
+        for (index, value) in self.data.iter().enumerate() {
+            if !value.is_finite() {
+                return Err(TensorError::InvalidValue { index, reason: format!("{} is not finite", value) });
+            }
+            if self.dtype.is_integer() && value.fract() != 0.0 {
+                return Err(TensorError::InvalidValue {
+                    index,
+                    reason: format!("{} is not an integer but dtype is {:?}", value, self.dtype),
+                });
+            }
+            if self.dtype == DType::Bool && *value != 0.0 && *value != 1.0 {
+                return Err(TensorError::InvalidValue { index, reason: format!("{} is not 0 or 1", value) });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Check the shape against `(name, size)` per dimension; `None` accepts any size
+    pub fn expect_shape(&self, dims: &[(&'static str, Option<usize>)]) -> Result<(), TensorError> {
+        if self.rank() != dims.len() {
+            return Err(TensorError::RankMismatch { expected: dims.len(), actual: self.rank() });
+        }
+        for (dim, (name, size)) in dims.iter().enumerate() {
+            if let Some(expected) = size {
+                if self.shape[dim] != *expected {
+                    return Err(TensorError::ShapeMismatch {
+                        dim,
+                        name,
+                        expected: *expected,
+                        shape: self.shape.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepend a dimension of size 1 (e.g. a batch of one)
+    pub fn unsqueeze(mut self) -> Self {
+        self.shape.insert(0, 1);
+        self
+    }
+
+    /// Build a float tensor of rank `D`
+    pub fn to_tensor<B: Backend, const D: usize>(&self, device: &B::Device) -> Result<Tensor<B, D>, TensorError> {
+        let shape = self.dims::<D>()?;
+        let values: Vec<f32> = self.data.iter().map(|value| *value as f32).collect();
+        Ok(Tensor::from_data(TensorData::new(values, shape), device))
+    }
+
+    /// Build an integer tensor of rank `D` (dtype must be `i32`, `i64` or `bool`)
+    pub fn to_int_tensor<B: Backend, const D: usize>(&self, device: &B::Device) -> Result<Tensor<B, D, Int>, TensorError> {
+        if !self.dtype.is_integer() {
+            return Err(TensorError::Malformed(format!("expected an integer dtype, got {:?}", self.dtype)));
+        }
+        let shape = self.dims::<D>()?;
+        let values: Vec<i64> = self.data.iter().map(|value| *value as i64).collect();
+        Ok(Tensor::from_data(TensorData::new(values, shape), device))
+    }
+
+    /// Read back a float tensor (as `f32`)
+    pub fn from_tensor<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Self {
+        let shape = tensor.dims().to_vec();
+        let data = tensor.into_data().convert::<f32>().to_vec::<f32>().unwrap_or_default();
+        Self { shape, dtype: DType::F32, data: data.into_iter().map(f64::from).collect() }
+    }
+
+    /// Read back an integer tensor (as `i64`)
+    pub fn from_int_tensor<B: Backend, const D: usize>(tensor: Tensor<B, D, Int>) -> Self {
+        let shape = tensor.dims().to_vec();
+        let data = tensor.into_data().convert::<i64>().to_vec::<i64>().unwrap_or_default();
+        Self { shape, dtype: DType::I64, data: data.into_iter().map(|value| value as f64).collect() }
+    }
+
+    /// Nested-array form of the data (row-major), as accepted by `parse`
+    pub fn to_nested(&self) -> Value {
+        fn build(data: &[f64], shape: &[usize], dtype: DType) -> Value {
+            match shape.split_first() {
+                None => match dtype {
+                    DType::Bool => Value::Bool(data[0] != 0.0),
+                    DType::I32 | DType::I64 => Value::from(data[0] as i64),
+                    DType::F32 | DType::F64 => Value::from(data[0]),
+                },
+                Some((len, rest)) => {
+                    let stride: usize = rest.iter().product();
+                    Value::Array((0..*len).map(|i| build(&data[i * stride..(i + 1) * stride], rest, dtype)).collect())
+                }
+            }
+        }
+        build(&self.data, &self.shape, self.dtype)
+    }
+
+    fn dims<const D: usize>(&self) -> Result<[usize; D], TensorError> {
+        self.shape
+            .as_slice()
+            .try_into()
+            .map_err(|_| TensorError::RankMismatch { expected: D, actual: self.rank() })
+    }
+}
+
+fn flatten(
+    value: &Value,
+    shape: &[usize],
+    depth: usize,
+    path: &mut String,
+    data: &mut Vec<f64>,
+    all_bool: &mut bool,
+) -> Result<(), TensorError> {
+    match value {
+        Value::Array(items) => {
+            let Some(expected) = shape.get(depth) else {
+                return Err(TensorError::Malformed(format!("unexpected array at {}, expected a number", path)));
+            };
+            if items.len() != *expected {
+                return Err(TensorError::Ragged { path: path.clone(), expected: *expected, actual: items.len() });
+            }
+            for (i, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                flatten(item, shape, depth + 1, path, data, all_bool)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        _ if depth < shape.len() => Err(TensorError::Malformed(format!(
+            "expected an array of {} elements at {}, got {}",
+            shape[depth], path, json_type(value)
+        ))),
+        Value::Number(number) => {
+            *all_bool = false;
+            let value = number.as_f64().ok_or_else(|| TensorError::InvalidValue {
+                index: data.len(),
+                reason: format!("{} is not representable as a number", number),
+            })?;
+            data.push(value);
+            Ok(())
+        }
+        Value::Bool(flag) => {
+            data.push(if *flag { 1.0 } else { 0.0 });
+            Ok(())
+        }
+        other => Err(TensorError::InvalidValue {
+            index: data.len(),
+            reason: format!("expected a number at {}, got {}", path, json_type(other)),
+        }),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
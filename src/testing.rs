@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::agents::{ensure_agents_initialized, get_agent, AgentMessage};
+use crate::connections::{set_default_provider, LlmFuture, LlmProvider};
+use crate::dataset::{recent_events, StoredEvent};
+use crate::events::{subscribe, Event, SharedEvent};
+use crate::mailbox::Priority;
+use crate::storage::{set_storage, Storage};
+
+// ============================================================================
+// Integration Test Harness (cfg(test) or feature = "testing")
+// ============================================================================
+//
+// `TestServer::start()` brings up the agents in-process against an in-memory
+// store and a scripted `MockLlmProvider`, so endpoints and tools can be
+// exercised end-to-end without Ollama or a GUI. Server functions are plain
+// async functions on the server side and can be awaited directly:
+//
+//     let server = TestServer::start().await?;
+//     server.llm().push_response("summary");
+//     crate::shared::api::process_agent_dynamic(1, "hello".into()).await?;
+//     server.assert_agent_handled(1, "process_data").await;
+
+/// How long assertions wait for an expected event by default
+pub const DEFAULT_ASSERT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ----------------------------------------------------------------------------
+// In-memory storage
+// ----------------------------------------------------------------------------
+
+/// `Storage` kept in memory; clones share the same documents
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    collections: Arc<RwLock<BTreeMap<String, BTreeMap<String, serde_json::Value>>>>,
+}
+
+impl MemoryStorage {
+    /// Remove all documents
+    pub fn clear(&self) {
+        self.collections.write().unwrap().clear();
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn put(&self, collection: &str, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        self.collections.write().unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .insert(key.to_string(), value.clone());
+        Ok(())
+    }
+
+    fn get(&self, collection: &str, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(self.collections.read().unwrap()
+            .get(collection)
+            .and_then(|documents| documents.get(key))
+            .cloned())
+    }
+
+    fn list(&self, collection: &str) -> anyhow::Result<Vec<(String, serde_json::Value)>> {
+        Ok(self.collections.read().unwrap()
+            .get(collection)
+            .map(|documents| documents.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default())
+    }
+
+    fn delete(&self, collection: &str, key: &str) -> anyhow::Result<bool> {
+        Ok(self.collections.write().unwrap()
+            .get_mut(collection)
+            .is_some_and(|documents| documents.remove(key).is_some()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Scripted LLM
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct MockScript {
+    responses: VecDeque<Result<String, String>>,
+    fallback: Option<String>,
+    prompts: Vec<String>,
+}
+
+/// `LlmProvider` returning scripted responses in order; clones share the script
+///
+/// When the script is empty the fallback response is returned, or an error
+/// if no fallback is set. Every prompt is recorded for later assertions.
+#[derive(Debug, Clone, Default)]
+pub struct MockLlmProvider {
+    script: Arc<Mutex<MockScript>>,
+}
+
+impl MockLlmProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue responses returned by the next calls, in order
+    pub fn with_responses<I, S>(self, responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for response in responses {
+            self.push_response(response);
+        }
+        self
+    }
+
+    /// Response returned once the script is exhausted
+    pub fn with_fallback(self, response: impl Into<String>) -> Self {
+        self.script.lock().unwrap().fallback = Some(response.into());
+        self
+    }
+
+    /// Queue one successful response
+    pub fn push_response(&self, response: impl Into<String>) {
+        self.script.lock().unwrap().responses.push_back(Ok(response.into()));
+    }
+
+    /// Queue one failed request
+    pub fn push_error(&self, message: impl Into<String>) {
+        self.script.lock().unwrap().responses.push_back(Err(message.into()));
+    }
+
+    /// All prompts received so far, oldest first
+    pub fn prompts(&self) -> Vec<String> {
+        self.script.lock().unwrap().prompts.clone()
+    }
+
+    /// Drop queued responses, the fallback and recorded prompts
+    pub fn reset(&self) {
+        *self.script.lock().unwrap() = MockScript::default();
+    }
+
+    fn next(&self, prompt: &str) -> anyhow::Result<String> {
+        let mut script = self.script.lock().unwrap();
+        script.prompts.push(prompt.to_string());
+        match script.responses.pop_front() {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(message)) => Err(anyhow::anyhow!(message)),
+            None => script.fallback.clone()
+                .ok_or_else(|| anyhow::anyhow!("MockLlmProvider: no scripted response for prompt")),
+        }
+    }
+}
+
+impl LlmProvider for MockLlmProvider {
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    fn generate<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, String> {
+        let response = self.next(prompt);
+        Box::pin(async move { response })
+    }
+
+    fn generate_json<'a>(&'a self, prompt: &'a str) -> LlmFuture<'a, serde_json::Value> {
+        let response = self.next(prompt);
+        Box::pin(async move { Ok(serde_json::from_str(&response?)?) })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// In-process server
+// ----------------------------------------------------------------------------
+
+struct Harness {
+    storage: MemoryStorage,
+    llm: MockLlmProvider,
+    /// Serializes tests, since agents, storage and the provider are process-wide
+    lock: Arc<tokio::sync::Mutex<()>>,
+    /// Runs the agents and their background jobs for the life of the process;
+    /// on the runtime of the test that started them, they would stop with it
+    runtime: tokio::runtime::Runtime,
+}
+
+static HARNESS: OnceLock<Harness> = OnceLock::new();
+
+fn harness() -> &'static Harness {
+    HARNESS.get_or_init(|| {
+        let storage = MemoryStorage::default();
+        let llm = MockLlmProvider::new();
+        if set_storage(Box::new(storage.clone())).is_err() {
+            panic!("storage was initialized before the test harness; start TestServer first");
+        }
+        if set_default_provider(Box::new(llm.clone())).is_err() {
+            panic!("LLM provider was initialized before the test harness; start TestServer first");
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .thread_name("pattern-clock-test-agents")
+            .build()
+            .expect("failed to build the test harness runtime");
+        Harness { storage, llm, lock: Arc::new(tokio::sync::Mutex::new(())), runtime }
+    })
+}
+
+/// Running in-process agents wired to in-memory storage and a mock LLM
+///
+/// Holding a `TestServer` holds a process-wide lock, so tests using it run
+/// one at a time. Storage and the LLM script are reset on every start. The
+/// agents run on a runtime of the harness, so they outlive the test (and its
+/// runtime) that started them; tasks a test spawns itself still end with it.
+pub struct TestServer {
+    events: broadcast::Receiver<SharedEvent>,
+    _guard: tokio::sync::OwnedMutexGuard<()>,
+}
+
+impl TestServer {
+    /// Install the harness (once per process), reset it and start the agents
+    pub async fn start() -> anyhow::Result<Self> {
+        let harness = harness();
+        let guard = harness.lock.clone().lock_owned().await;
+        harness.storage.clear();
+        harness.llm.reset();
+
+        let events = subscribe();
+        harness.runtime
+            .spawn(async { ensure_agents_initialized().await.map_err(|e| e.to_string()) })
+            .await
+            .map_err(|e| anyhow::anyhow!("agent startup panicked: {}", e))?
+            .map_err(|e| anyhow::anyhow!("failed to initialize agents: {}", e))?;
+        Ok(Self { events, _guard: guard })
+    }
+
+    /// The scripted LLM used by agents, summarizer and extraction
+    pub fn llm(&self) -> &MockLlmProvider {
+        &harness().llm
+    }
+
+    /// The in-memory storage behind `storage::storage()`
+    pub fn storage(&self) -> &MemoryStorage {
+        &harness().storage
+    }
+
+    /// Send a message to an agent
+    pub fn send(&self, agent_id: u8, message: AgentMessage) -> anyhow::Result<()> {
+        get_agent(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))?
+            .send_message(message)
+            .map_err(|e| anyhow::anyhow!("failed to send to Agent{}: {}", agent_id, e))
+    }
+
+    /// Queue a payload on an agent, like `/api/agents/:id/process`
+    pub fn process(&self, agent_id: u8, data: &str) -> anyhow::Result<()> {
+        get_agent(agent_id)
+            .ok_or_else(|| anyhow::anyhow!("Agent{} is not available", agent_id))
+            .and_then(|actor_ref| {
+                let message = AgentMessage::ProcessData { data: data.to_string(), priority: Priority::Normal };
+                crate::agents::send_work(&actor_ref, message)
+                    .map_err(|e| anyhow::anyhow!("failed to send to Agent{}: {}", agent_id, e))
+            })
+    }
+
+    /// Wait for the next event of `kind` matching `predicate`
+    pub async fn wait_for_event(
+        &mut self,
+        kind: &str,
+        predicate: impl Fn(&Event) -> bool,
+        timeout: Duration,
+    ) -> anyhow::Result<Event> {
+        let wait = async {
+            loop {
+                match self.events.recv().await {
+                    Ok(event) if event.kind == kind && predicate(&event) => return Ok(event.event().clone()),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => anyhow::bail!("event bus closed"),
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| anyhow::anyhow!("no '{}' event within {:?}", kind, timeout))?
+    }
+
+    /// Assert that an agent finishes handling a message of the given kind (e.g. `process_data`)
+    pub async fn assert_agent_handled(&mut self, agent_id: u8, message_kind: &str) {
+        let result = self.wait_for_event(
+            "agent.handled",
+            |event| event.payload["agent_id"] == agent_id && event.payload["message"] == message_kind,
+            DEFAULT_ASSERT_TIMEOUT,
+        ).await;
+        if let Err(e) = result {
+            panic!("Agent{} did not handle '{}': {}", agent_id, message_kind, e);
+        }
+    }
+
+    /// Assert that an agent has stored exactly `expected` processed payloads
+    pub fn assert_agent_stored(&self, agent_id: u8, expected: usize) {
+        let stored = self.stored_events().iter().filter(|event| event.agent_id == agent_id).count();
+        assert_eq!(stored, expected, "Agent{} stored {} events, expected {}", agent_id, stored, expected);
+    }
+
+    /// Payloads stored by all agents, newest first
+    pub fn stored_events(&self) -> Vec<StoredEvent> {
+        recent_events(usize::MAX).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Process one payload on Agent1 and check it was handled and stored
+    async fn process_on_agent1() {
+        let mut server = TestServer::start().await.expect("test server did not start");
+        server.process(1, "cpu at 93%").expect("Agent1 did not accept data");
+        server.assert_agent_handled(1, "process_data").await;
+        server.assert_agent_stored(1, 1);
+    }
+
+    #[tokio::test]
+    async fn agents_handle_and_store_data() {
+        process_on_agent1().await;
+    }
+
+    #[test]
+    fn agents_outlive_the_runtime_that_started_them() {
+        // Like two `#[tokio::test]`s in a row: the first runtime is gone when the second starts
+        for _ in 0..2 {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(process_on_agent1());
+        }
+    }
+
+    #[tokio::test]
+    async fn summary_is_applied_while_the_mailbox_is_full() {
+        use crate::agents::{agent_status, Handoff, HistoryEntry};
+        use crate::summarizer::SummarizerConfig;
+
+        let mut server = TestServer::start().await.expect("test server did not start");
+        // Clones share the script
+        let _ = server.llm().clone().with_fallback("condensed");
+        let agent = get_agent(2).expect("Agent2 is not available");
+        server.send(2, AgentMessage::Pause).expect("Agent2 did not accept Pause");
+
+        // Past the history limit; straight to the actor, since a paused queue only hands out control messages
+        let config = SummarizerConfig::current();
+        let history = (0..=config.max_history)
+            .map(|i| HistoryEntry { data: format!("reading {}", i), extraction: Default::default() })
+            .collect();
+        let handoff = Handoff { from: 1, to: 2, reason: "test".into(), history, summary: None, ts: 0 };
+        (*agent).send_message(AgentMessage::AcceptHandoff(Box::new(handoff))).unwrap();
+        while server.process(2, "waiting").is_ok() {}
+        (*agent).send_message(AgentMessage::Summarize).unwrap();
+
+        server.assert_agent_handled(2, "summarized").await;
+        let state = agent_status(2).await;
+        crate::mailbox::clear(2);
+        let state = state.expect("Agent2 did not report its state");
+        assert!(!state.summarizing, "the summarizing flag is cleared");
+        assert_eq!(state.summary.as_deref(), Some("condensed"));
+        assert_eq!(state.history.len(), config.keep_recent);
+    }
+
+    #[tokio::test]
+    async fn mock_llm_replays_its_script_then_the_fallback() {
+        let llm = MockLlmProvider::new().with_responses(["first"]).with_fallback("again");
+        llm.push_error("down");
+        assert_eq!(llm.generate("a").await.unwrap(), "first");
+        assert!(llm.generate("b").await.is_err());
+        assert_eq!(llm.generate("c").await.unwrap(), "again");
+        assert_eq!(llm.prompts(), ["a", "b", "c"]);
+
+        llm.reset();
+        assert!(llm.generate("d").await.is_err(), "reset drops the fallback");
+    }
+
+    #[test]
+    fn memory_storage_updates_and_deletes_in_place() {
+        let storage = MemoryStorage::default();
+        storage.put("counters", "a", &json!(1)).unwrap();
+        let next = storage
+            .update("counters", "a", &mut |current| Ok(current.map(|v| json!(v.as_i64().unwrap_or(0) + 1))))
+            .unwrap();
+        assert_eq!(next, Some(json!(2)));
+        assert_eq!(storage.get("counters", "a").unwrap(), Some(json!(2)));
+
+        storage.update("counters", "a", &mut |_| Ok(None)).unwrap();
+        assert_eq!(storage.get("counters", "a").unwrap(), None);
+        assert!(storage.list("counters").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::config::config;
+
+// ============================================================================
+// Thread Topology
+// ============================================================================
+//
+// Three kinds of threads share the machine:
+//
+//   workers     the Tokio runtime serving HTTP requests, agents and event
+//               streams (`PATTERN_CLOCK_WORKER_THREADS`, one per core)
+//   compute     dedicated threads running Burn inference, training and
+//               quantization (`PATTERN_CLOCK_COMPUTE_THREADS`, see `compute`)
+//   background  with `PATTERN_CLOCK_PIN_BACKGROUND=true`, one thread with its
+//               own runtime for the scheduler, time-series compaction and
+//               retention loops
+//
+// Heavy training keeps the compute threads busy but never a worker, so the
+// server still answers; fewer compute threads than cores leave room for the
+// workers. Pinning the periodic jobs to the background thread keeps a large
+// compaction or purge from competing with requests for a worker, and keeps
+// schedules firing on time while the workers are saturated.
+//
+// `stats()` reports the workers' task count and global queue depth, the
+// compute queue and busy threads, and the background thread's tasks. They
+// are part of `/api/stats/live` and sampled every `SAMPLE_INTERVAL` into the
+// `runtime.tasks`, `runtime.queue_depth` and `compute.busy` metrics.
+
+/// How often runtime stats are recorded as metrics
+#[cfg(not(target_arch = "wasm32"))]
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Load of the runtimes and the compute pool, at the time of the snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuntimeStats {
+    /// Tokio worker threads
+    pub workers: usize,
+    /// Tasks alive on the worker runtime
+    pub tasks: usize,
+    /// Tasks waiting in the worker runtime's global queue
+    pub queue_depth: usize,
+    pub compute_threads: usize,
+    /// Jobs waiting for a compute thread
+    pub compute_queued: usize,
+    /// Compute threads running a job
+    pub compute_busy: usize,
+    /// Tasks alive on the background thread, if the periodic jobs are pinned to it
+    pub background_tasks: Option<usize>,
+}
+
+/// Apply `worker_threads` to the Tokio runtime the server starts next
+///
+/// Call before the runtime is built; runtimes built with the default
+/// builder read `TOKIO_WORKER_THREADS`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn configure_workers() {
+    if let Some(threads) = config().worker_threads {
+        std::env::set_var("TOKIO_WORKER_THREADS", threads.to_string());
+        log_info!("[Threads] {} worker threads", threads);
+    }
+}
+
+/// The runtime of the background thread, started on first use
+#[cfg(not(target_arch = "wasm32"))]
+fn background() -> &'static tokio::runtime::Handle {
+    static BACKGROUND: OnceLock<tokio::runtime::Handle> = OnceLock::new();
+    BACKGROUND.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the background runtime");
+        let handle = runtime.handle().clone();
+        std::thread::Builder::new()
+            .name("pattern-clock-background".to_string())
+            .spawn(move || runtime.block_on(std::future::pending::<()>()))
+            .expect("failed to spawn the background thread");
+        log_info!("[Threads] Scheduler, compaction and retention run on the background thread");
+        handle
+    })
+}
+
+/// Spawn a periodic job: on the background thread with `pin_background`,
+/// else on the current runtime
+pub fn spawn_background<F>(job: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    if config().pin_background {
+        background().spawn(job);
+        return;
+    }
+    tokio::spawn(job);
+}
+
+/// Current load of the worker runtime (if called on one), the compute pool and the background thread
+#[cfg(not(target_arch = "wasm32"))]
+pub fn stats() -> RuntimeStats {
+    let (compute_queued, compute_busy) = crate::compute::stats();
+    let mut stats = RuntimeStats {
+        compute_threads: crate::compute::worker_count(),
+        compute_queued,
+        compute_busy,
+        background_tasks: config().pin_background.then(|| background().metrics().num_alive_tasks()),
+        ..Default::default()
+    };
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        let metrics = handle.metrics();
+        stats.workers = metrics.num_workers();
+        stats.tasks = metrics.num_alive_tasks();
+        stats.queue_depth = metrics.global_queue_depth();
+    }
+    stats
+}
+
+/// Nothing to measure in the browser
+#[cfg(target_arch = "wasm32")]
+pub fn stats() -> RuntimeStats {
+    RuntimeStats::default()
+}
+
+/// Record runtime stats as metrics every `SAMPLE_INTERVAL` (once; requires a Tokio runtime)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn ensure_started() {
+    use opentelemetry::KeyValue;
+
+    static STARTED: OnceLock<()> = OnceLock::new();
+    STARTED.get_or_init(|| {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(SAMPLE_INTERVAL);
+            loop {
+                ticks.tick().await;
+                let stats = stats();
+                let instruments = crate::telemetry::instruments();
+                let workers = [KeyValue::new("runtime", "workers")];
+                let compute = [KeyValue::new("runtime", "compute")];
+                instruments.runtime_tasks.record(stats.tasks as u64, &workers);
+                instruments.runtime_queue_depth.record(stats.queue_depth as u64, &workers);
+                instruments.runtime_queue_depth.record(stats.compute_queued as u64, &compute);
+                instruments.compute_busy.record(stats.compute_busy as u64, &[]);
+                if let Some(tasks) = stats.background_tasks {
+                    instruments.runtime_tasks.record(tasks as u64, &[KeyValue::new("runtime", "background")]);
+                }
+            }
+        });
+    });
+}
@@ -0,0 +1,954 @@
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use crate::storage::{get_typed, list_typed, now_millis, update_typed};
+
+// ============================================================================
+// Time-Series Store
+// ============================================================================
+//
+// A series is one small document holding its retention policy and the index
+// of its chunks, and its points and rollups are stored one document per
+// hour (`CHUNK_MS`). An append rewrites only the chunks its points fall in,
+// each in one `Storage::update`, so its cost doesn't grow with the series'
+// history and instances sharing a database don't overwrite each other's
+// points. Apart from the rate limiters nothing is kept in memory: queries
+// read the chunks they cover, and a storage error is returned rather than
+// taken for an empty series.
+
+/// Collection holding one document per series: its policy and chunk index
+pub const TIMESERIES_COLLECTION: &str = "timeseries";
+/// Collection holding the points and rollups of every series, one document per chunk
+pub const TIMESERIES_CHUNKS_COLLECTION: &str = "timeseries_chunks";
+/// Time span of one chunk (ms)
+pub const CHUNK_MS: u64 = 60 * 60 * 1000;
+
+/// Maximum points accepted in one append call
+pub const MAX_BATCH_POINTS: usize = 10_000;
+/// Maximum tags per point
+pub const MAX_TAGS: usize = 16;
+/// Points older than the newest stored point by more than this are rejected (ms)
+pub const MAX_LATENESS_MS: u64 = 60 * 60 * 1000;
+/// Sustained points per second accepted per series
+pub const RATE_LIMIT_POINTS_PER_SEC: f64 = 5_000.0;
+/// Burst size of the per-series rate limiter
+pub const RATE_LIMIT_BURST: f64 = 20_000.0;
+/// How often the background task applies retention policies
+pub const COMPACTION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Cycle ticks between the forecasts of every series (a 1/100 sub-clock of the cycle)
+pub const FORECAST_EVERY_TICKS: u64 = 100;
+/// Values forecast for each series on the sub-clock
+const FORECAST_CLOCK_HORIZON: usize = 10;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// A single sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    /// Milliseconds since the Unix epoch
+    pub ts: u64,
+    pub value: f64,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Aggregate reported for rolled-up buckets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupFn {
+    Min,
+    Max,
+    #[default]
+    Avg,
+    Sum,
+}
+
+/// How long raw points and rollups are kept for a series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Raw points older than this are folded into rollups (ms)
+    pub raw_retention_ms: u64,
+    /// Width of a rollup bucket (ms)
+    pub rollup_interval_ms: u64,
+    /// Rollups older than this are deleted (ms)
+    pub rollup_retention_ms: u64,
+    pub rollup: RollupFn,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            raw_retention_ms: 7 * DAY_MS,
+            rollup_interval_ms: 60 * 1000,
+            rollup_retention_ms: 90 * DAY_MS,
+            rollup: RollupFn::Avg,
+        }
+    }
+}
+
+/// Aggregated raw points of one bucket and tag set
+///
+/// All aggregates are kept so late points can be merged and the reported
+/// function can be changed after the fact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rollup {
+    /// Bucket start, milliseconds since the Unix epoch
+    pub ts: u64,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Rollup {
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// The bucket's value under the given rollup function
+    pub fn value(&self, rollup: RollupFn) -> f64 {
+        match rollup {
+            RollupFn::Min => self.min,
+            RollupFn::Max => self.max,
+            RollupFn::Sum => self.sum,
+            RollupFn::Avg => self.sum / self.count.max(1) as f64,
+        }
+    }
+}
+
+/// A named series: its retention policy and the index of its chunks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Series {
+    pub name: String,
+    #[serde(default)]
+    pub policy: RetentionPolicy,
+    /// Start of every stored chunk (ms)
+    #[serde(default)]
+    pub chunks: BTreeSet<u64>,
+    /// Points of a series stored as one document, moved into chunks when first loaded
+    #[serde(default, rename = "points", skip_serializing)]
+    legacy_points: Vec<Point>,
+    #[serde(default, rename = "rollups", skip_serializing)]
+    legacy_rollups: Vec<Rollup>,
+}
+
+impl Series {
+    fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+}
+
+/// Points and rollups of one series within `[start, start + CHUNK_MS)`
+///
+/// A rollup is kept in the chunk its raw points were in, so a bucket that
+/// straddles two chunks has a partial rollup in each; they are merged when read.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Chunk {
+    pub series: String,
+    /// Milliseconds since the Unix epoch, a multiple of `CHUNK_MS`
+    pub start: u64,
+    /// Sorted by timestamp
+    #[serde(default)]
+    pub points: Vec<Point>,
+    /// Sorted by bucket start
+    #[serde(default)]
+    pub rollups: Vec<Rollup>,
+}
+
+impl Chunk {
+    fn new(series: &str, start: u64) -> Self {
+        Self { series: series.to_string(), start, ..Default::default() }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.points.is_empty() && self.rollups.is_empty()
+    }
+
+    /// Insert a point at its sorted position, replacing one with the same
+    /// timestamp and tags; returns whether it replaced one
+    fn insert(&mut self, point: Point) -> bool {
+        let first = self.points.partition_point(|p| p.ts < point.ts);
+        let position = self.points.partition_point(|p| p.ts <= point.ts);
+        match self.points[first..position].iter().position(|p| p.tags == point.tags) {
+            Some(index) => {
+                self.points[first + index] = point;
+                true
+            }
+            None => {
+                self.points.insert(position, point);
+                false
+            }
+        }
+    }
+
+    /// Fold points older than `cutoff` into rollups of `interval` and drop
+    /// rollups older than `expire_before`; returns the points rolled up and
+    /// the rollups dropped
+    fn compact(&mut self, interval: u64, cutoff: u64, expire_before: u64) -> (usize, usize) {
+        let split = self.points.partition_point(|p| p.ts < cutoff);
+        let mut buckets = BTreeMap::new();
+        for rollup in self.rollups.drain(..) {
+            merge_rollup(&mut buckets, rollup);
+        }
+        for point in self.points.drain(..split) {
+            let bucket = point.ts / interval * interval;
+            buckets
+                .entry((bucket, point.tags.clone()))
+                .or_insert_with(|| Rollup {
+                    ts: bucket,
+                    tags: point.tags,
+                    min: f64::INFINITY,
+                    max: f64::NEG_INFINITY,
+                    sum: 0.0,
+                    count: 0,
+                })
+                .add(point.value);
+        }
+        let total = buckets.len();
+        self.rollups = buckets.into_values().filter(|r| r.ts >= expire_before).collect();
+        (split, total - self.rollups.len())
+    }
+
+    /// Points and rollups older than `cutoff`
+    fn count_before(&self, cutoff: u64) -> usize {
+        self.points.partition_point(|p| p.ts < cutoff) + self.rollups.iter().filter(|r| r.ts < cutoff).count()
+    }
+
+    /// Drop points and rollups older than `cutoff`; returns how many
+    fn drop_before(&mut self, cutoff: u64) -> usize {
+        let dropped = self.count_before(cutoff);
+        self.points.drain(..self.points.partition_point(|p| p.ts < cutoff));
+        self.rollups.retain(|r| r.ts >= cutoff);
+        dropped
+    }
+}
+
+type RollupKey = (u64, BTreeMap<String, String>);
+
+/// Add `rollup` to the bucket with the same start and tags
+fn merge_rollup(buckets: &mut BTreeMap<RollupKey, Rollup>, rollup: Rollup) {
+    match buckets.entry((rollup.ts, rollup.tags.clone())) {
+        Entry::Vacant(entry) => {
+            entry.insert(rollup);
+        }
+        Entry::Occupied(mut entry) => {
+            let bucket = entry.get_mut();
+            bucket.min = bucket.min.min(rollup.min);
+            bucket.max = bucket.max.max(rollup.max);
+            bucket.sum += rollup.sum;
+            bucket.count += rollup.count;
+        }
+    }
+}
+
+fn chunk_start(ts: u64) -> u64 {
+    ts / CHUNK_MS * CHUNK_MS
+}
+
+/// Chunk keys end in a fixed-width start, so they are unique whatever the series name
+fn chunk_key(series: &str, start: u64) -> String {
+    format!("{}-{:013}", series, start)
+}
+
+fn get_chunk(series: &str, start: u64) -> anyhow::Result<Option<Chunk>> {
+    get_typed(TIMESERIES_CHUNKS_COLLECTION, &chunk_key(series, start))
+}
+
+/// Result of compacting one series
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactionReport {
+    pub series: String,
+    /// Raw points folded into rollups
+    pub rolled_up: usize,
+    /// Rollups deleted for exceeding the rollup retention
+    pub expired: usize,
+}
+
+/// Result of an append call
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppendReport {
+    pub accepted: usize,
+    /// Points that arrived older than the newest stored point but within the lateness window
+    pub out_of_order: usize,
+    /// Points replacing an existing point with the same timestamp and tags
+    pub replaced: usize,
+    /// Points dropped for being older than the lateness window
+    pub too_late: usize,
+}
+
+/// Why an append was refused
+#[derive(Debug)]
+pub enum AppendError {
+    /// The request was malformed (bad name, too many points, non-finite value, ...)
+    Invalid(String),
+    /// The series' rate limit was exceeded; retry after the given delay
+    RateLimited { retry_after_ms: u64 },
+    /// Persisting the series failed
+    Storage(anyhow::Error),
+}
+
+impl std::fmt::Display for AppendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppendError::Invalid(reason) => write!(f, "invalid points: {}", reason),
+            AppendError::RateLimited { retry_after_ms } => {
+                write!(f, "rate limit exceeded, retry after {} ms", retry_after_ms)
+            }
+            AppendError::Storage(e) => write!(f, "failed to store series: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppendError {}
+
+/// Token bucket limiting points per second for one series
+#[derive(Debug, Clone)]
+struct RateLimiter {
+    tokens: f64,
+    last_refill_ms: u64,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self { tokens: RATE_LIMIT_BURST, last_refill_ms: now_millis() }
+    }
+
+    /// Add the tokens earned since the last refill
+    fn refill(&mut self) {
+        let now = now_millis();
+        let elapsed = now.saturating_sub(self.last_refill_ms) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed * RATE_LIMIT_POINTS_PER_SEC).min(RATE_LIMIT_BURST);
+        self.last_refill_ms = now;
+    }
+
+    /// Milliseconds until `count` tokens are available; zero if they are now
+    fn wait_ms(&self, count: usize) -> u64 {
+        let missing = count as f64 - self.tokens;
+        if missing <= 0.0 {
+            0
+        } else {
+            (missing / RATE_LIMIT_POINTS_PER_SEC * 1000.0).ceil() as u64
+        }
+    }
+}
+
+/// Rate limiters of the series appended to by this process
+fn limiters() -> MutexGuard<'static, HashMap<String, RateLimiter>> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, RateLimiter>>> = OnceLock::new();
+    LIMITERS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Take `count` tokens from the limiter of every named series, or from none
+/// of them if any series has too few
+fn take_rate_limits(counts: &[(&str, usize)]) -> Result<(), AppendError> {
+    let mut limiters = limiters();
+    let mut retry_after_ms = 0;
+    for &(name, count) in counts {
+        let limiter = limiters.entry(name.to_string()).or_insert_with(RateLimiter::new);
+        limiter.refill();
+        retry_after_ms = retry_after_ms.max(limiter.wait_ms(count));
+    }
+    if retry_after_ms > 0 {
+        return Err(AppendError::RateLimited { retry_after_ms });
+    }
+    for &(name, count) in counts {
+        if let Some(limiter) = limiters.get_mut(name) {
+            limiter.tokens -= count as f64;
+        }
+    }
+    Ok(())
+}
+
+/// Load a series, moving the data of one stored as a single document into chunks
+fn load_series(name: &str) -> anyhow::Result<Option<Series>> {
+    let Some(series) = get_typed::<Series>(TIMESERIES_COLLECTION, name)? else {
+        return Ok(None);
+    };
+    if series.legacy_points.is_empty() && series.legacy_rollups.is_empty() {
+        return Ok(Some(series));
+    }
+    migrate_legacy(series).map(Some)
+}
+
+fn migrate_legacy(mut series: Series) -> anyhow::Result<Series> {
+    let mut chunks: BTreeMap<u64, Chunk> = BTreeMap::new();
+    for point in series.legacy_points.drain(..) {
+        let start = chunk_start(point.ts);
+        chunks.entry(start).or_insert_with(|| Chunk::new(&series.name, start)).points.push(point);
+    }
+    for rollup in series.legacy_rollups.drain(..) {
+        let start = chunk_start(rollup.ts);
+        chunks.entry(start).or_insert_with(|| Chunk::new(&series.name, start)).rollups.push(rollup);
+    }
+
+    // Points and rollups replace their copies, so an interrupted move can run again
+    for (&start, moved) in &chunks {
+        update_typed(TIMESERIES_CHUNKS_COLLECTION, &chunk_key(&series.name, start), |current: Option<Chunk>| {
+            let mut chunk = current.unwrap_or_else(|| Chunk::new(&series.name, start));
+            for point in &moved.points {
+                chunk.insert(point.clone());
+            }
+            for rollup in &moved.rollups {
+                chunk.rollups.retain(|r| r.ts != rollup.ts || r.tags != rollup.tags);
+                chunk.rollups.push(rollup.clone());
+            }
+            chunk.rollups.sort_by_key(|r| r.ts);
+            Ok(Some(chunk))
+        })?;
+    }
+
+    let name = series.name.clone();
+    let migrated = update_typed(TIMESERIES_COLLECTION, &name, |current: Option<Series>| {
+        let mut current = current.unwrap_or_else(|| series.clone());
+        current.legacy_points.clear();
+        current.legacy_rollups.clear();
+        current.chunks.extend(chunks.keys());
+        Ok(Some(current))
+    })?;
+    log_info!("[Timeseries] Moved {} into {} chunks", name, chunks.len());
+    migrated.ok_or_else(|| anyhow::anyhow!("series {} disappeared while moving it into chunks", name))
+}
+
+/// Series names may only contain ASCII letters, digits, `_`, `-` and `.`
+pub fn validate_name(name: &str) -> Result<(), AppendError> {
+    if name.is_empty() || name.len() > 128 {
+        return Err(AppendError::Invalid("series name must be 1-128 characters".to_string()));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        return Err(AppendError::Invalid(format!("series name '{}' contains invalid characters", name)));
+    }
+    Ok(())
+}
+
+fn validate_points(points: &[Point]) -> Result<(), AppendError> {
+    if points.len() > MAX_BATCH_POINTS {
+        return Err(AppendError::Invalid(format!(
+            "batch has {} points, maximum is {}", points.len(), MAX_BATCH_POINTS
+        )));
+    }
+    for (i, point) in points.iter().enumerate() {
+        if !point.value.is_finite() {
+            return Err(AppendError::Invalid(format!("point {} has a non-finite value", i)));
+        }
+        if point.ts == 0 {
+            return Err(AppendError::Invalid(format!("point {} has no timestamp", i)));
+        }
+        if point.tags.len() > MAX_TAGS {
+            return Err(AppendError::Invalid(format!("point {} has more than {} tags", i, MAX_TAGS)));
+        }
+    }
+    Ok(())
+}
+
+/// Append a batch of points to a series, creating it if needed
+///
+/// Points may arrive in any order: late points are inserted at their sorted
+/// position as long as they are within `MAX_LATENESS_MS` of the newest point.
+/// Only the chunks the points fall in are rewritten.
+pub fn append(name: &str, points: Vec<Point>) -> Result<AppendReport, AppendError> {
+    validate_name(name)?;
+    validate_points(&points)?;
+    take_rate_limits(&[(name, points.len())])?;
+    store_points(name, points)
+}
+
+/// Append to several series at once, like `append`
+///
+/// Every series is validated and rate limited before any is written, so a
+/// refused request leaves all of them unchanged.
+pub fn append_all(series: BTreeMap<String, Vec<Point>>) -> Result<BTreeMap<String, AppendReport>, AppendError> {
+    for (name, points) in &series {
+        validate_name(name)?;
+        validate_points(points)?;
+    }
+    let counts: Vec<(&str, usize)> = series.iter().map(|(name, points)| (name.as_str(), points.len())).collect();
+    take_rate_limits(&counts)?;
+
+    series
+        .into_iter()
+        .map(|(name, points)| {
+            let report = store_points(&name, points)?;
+            Ok((name, report))
+        })
+        .collect()
+}
+
+/// Write validated, rate-limited points to their chunks
+fn store_points(name: &str, mut points: Vec<Point>) -> Result<AppendReport, AppendError> {
+    let series = load_series(name).map_err(AppendError::Storage)?;
+    let known = series.as_ref().map(|s| s.chunks.clone()).unwrap_or_default();
+    let newest = match known.last() {
+        Some(&start) => get_chunk(name, start)
+            .map_err(AppendError::Storage)?
+            .map(|chunk| chunk.points.last().map_or(chunk.start, |p| p.ts))
+            .unwrap_or(0),
+        None => 0,
+    };
+
+    points.sort_by_key(|p| p.ts);
+    let mut report = AppendReport::default();
+    let mut batches: BTreeMap<u64, Vec<Point>> = BTreeMap::new();
+    for point in points {
+        if point.ts < newest {
+            if newest - point.ts > MAX_LATENESS_MS {
+                report.too_late += 1;
+                continue;
+            }
+            report.out_of_order += 1;
+        }
+        report.accepted += 1;
+        batches.entry(chunk_start(point.ts)).or_default().push(point);
+    }
+
+    // Index new chunks before writing them: a chunk missing from the index is never read
+    let new_chunks: Vec<u64> = batches.keys().filter(|start| !known.contains(start)).copied().collect();
+    if series.is_none() || !new_chunks.is_empty() {
+        update_typed(TIMESERIES_COLLECTION, name, |current: Option<Series>| {
+            let mut current = current.unwrap_or_else(|| Series::new(name));
+            current.chunks.extend(&new_chunks);
+            Ok(Some(current))
+        }).map_err(AppendError::Storage)?;
+    }
+
+    for (start, batch) in batches {
+        let mut replaced = 0;
+        update_typed(TIMESERIES_CHUNKS_COLLECTION, &chunk_key(name, start), |current: Option<Chunk>| {
+            let mut chunk = current.unwrap_or_else(|| Chunk::new(name, start));
+            replaced = batch.iter().filter(|&point| chunk.insert(point.clone())).count();
+            Ok(Some(chunk))
+        }).map_err(AppendError::Storage)?;
+        report.replaced += replaced;
+    }
+    Ok(report)
+}
+
+/// Points of a series within `[from, to]` (inclusive, milliseconds)
+///
+/// Rolled-up buckets are returned as points (valued by the series' rollup
+/// function) ahead of the raw points.
+pub fn query(name: &str, from: Option<u64>, to: Option<u64>) -> anyhow::Result<Vec<Point>> {
+    let Some(series) = load_series(name)? else {
+        return Ok(Vec::new());
+    };
+    let from = from.unwrap_or(0);
+    let to = to.unwrap_or(u64::MAX);
+
+    // A chunk holds rollups of buckets starting up to one interval before it
+    let reach = series.policy.rollup_interval_ms;
+    let mut rollups = BTreeMap::new();
+    let mut points = Vec::new();
+    for &start in series.chunks.iter().filter(|&&start| start.saturating_add(CHUNK_MS) > from && start <= to.saturating_add(reach)) {
+        let Some(chunk) = get_chunk(name, start)? else {
+            continue;
+        };
+        for rollup in chunk.rollups.into_iter().filter(|r| r.ts >= from && r.ts <= to) {
+            merge_rollup(&mut rollups, rollup);
+        }
+        points.extend(chunk.points.into_iter().filter(|p| p.ts >= from && p.ts <= to));
+    }
+
+    let rollup = series.policy.rollup;
+    Ok(rollups
+        .into_values()
+        .map(|r| Point { ts: r.ts, value: r.value(rollup), tags: r.tags })
+        .chain(points)
+        .collect())
+}
+
+/// Names of all stored series
+pub fn series_names() -> anyhow::Result<Vec<String>> {
+    let mut names: Vec<String> = list_typed::<Series>(TIMESERIES_COLLECTION)?
+        .into_iter()
+        .map(|series| series.name)
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Retention policy of a series (the default if the series does not exist)
+pub fn get_policy(name: &str) -> anyhow::Result<RetentionPolicy> {
+    Ok(load_series(name)?.map(|series| series.policy).unwrap_or_default())
+}
+
+/// Set the retention policy of a series, creating it if needed
+pub fn set_policy(name: &str, policy: RetentionPolicy) -> Result<(), AppendError> {
+    validate_name(name)?;
+    if policy.rollup_interval_ms == 0 {
+        return Err(AppendError::Invalid("rollup interval must be positive".to_string()));
+    }
+    load_series(name).map_err(AppendError::Storage)?;
+    update_typed(TIMESERIES_COLLECTION, name, |current: Option<Series>| {
+        let mut series = current.unwrap_or_else(|| Series::new(name));
+        series.policy = policy.clone();
+        Ok(Some(series))
+    }).map_err(AppendError::Storage)?;
+    Ok(())
+}
+
+/// Fold raw points past their retention into rollups and expire old rollups,
+/// one chunk at a time
+fn compact_series(series: &Series, now: u64) -> anyhow::Result<CompactionReport> {
+    let policy = &series.policy;
+    let interval = policy.rollup_interval_ms.max(1);
+    // Only whole buckets are rolled up
+    let cutoff = now.saturating_sub(policy.raw_retention_ms) / interval * interval;
+    let expire_before = now.saturating_sub(policy.rollup_retention_ms);
+
+    let mut report = CompactionReport { series: series.name.clone(), ..Default::default() };
+    let mut emptied = Vec::new();
+    for &start in series.chunks.range(..cutoff.max(expire_before)) {
+        let (mut rolled_up, mut expired) = (0, 0);
+        let chunk = update_typed(TIMESERIES_CHUNKS_COLLECTION, &chunk_key(&series.name, start), |current: Option<Chunk>| {
+            let Some(mut chunk) = current else {
+                return Ok(None);
+            };
+            (rolled_up, expired) = chunk.compact(interval, cutoff, expire_before);
+            Ok((!chunk.is_empty()).then_some(chunk))
+        })?;
+        report.rolled_up += rolled_up;
+        report.expired += expired;
+        if chunk.is_none() {
+            emptied.push(start);
+        }
+    }
+    if !emptied.is_empty() {
+        update_typed(TIMESERIES_COLLECTION, &series.name, |current: Option<Series>| {
+            Ok(current.map(|mut current| {
+                for start in &emptied {
+                    current.chunks.remove(start);
+                }
+                current
+            }))
+        })?;
+    }
+    Ok(report)
+}
+
+/// Apply every series' retention policy once
+pub fn compact_all() -> anyhow::Result<Vec<CompactionReport>> {
+    let now = now_millis();
+    let mut reports = Vec::new();
+    for name in series_names()? {
+        let compacted = load_series(&name).and_then(|series| series.map(|s| compact_series(&s, now)).transpose());
+        match compacted {
+            Ok(Some(report)) if report.rolled_up > 0 || report.expired > 0 => reports.push(report),
+            Ok(_) => {}
+            Err(e) => log_error!("[Timeseries] Failed to compact series {}: {}", name, e),
+        }
+    }
+    Ok(reports)
+}
+
+/// Drop raw points and rollups older than `cutoff` (ms) from every series,
+/// whatever their policy, and delete series left empty; returns how many
+/// points and rollups were (or, with `dry_run`, would be) dropped
+pub fn purge_before(cutoff: u64, dry_run: bool) -> anyhow::Result<usize> {
+    let mut purged = 0;
+    for name in series_names()? {
+        let Some(series) = load_series(&name)? else {
+            continue;
+        };
+        // Rollups of a bucket straddling two chunks sit in the later one
+        let reach = cutoff.saturating_add(series.policy.rollup_interval_ms);
+        let mut emptied = Vec::new();
+        for &start in series.chunks.range(..reach) {
+            if dry_run {
+                purged += get_chunk(&name, start)?.map_or(0, |chunk| chunk.count_before(cutoff));
+                continue;
+            }
+            let mut dropped = 0;
+            let chunk = update_typed(TIMESERIES_CHUNKS_COLLECTION, &chunk_key(&name, start), |current: Option<Chunk>| {
+                let Some(mut chunk) = current else {
+                    return Ok(None);
+                };
+                dropped = chunk.drop_before(cutoff);
+                Ok((!chunk.is_empty()).then_some(chunk))
+            })?;
+            purged += dropped;
+            if chunk.is_none() {
+                emptied.push(start);
+            }
+        }
+        if emptied.is_empty() {
+            continue;
+        }
+        let remaining = update_typed(TIMESERIES_COLLECTION, &name, |current: Option<Series>| {
+            Ok(current.and_then(|mut current| {
+                for start in &emptied {
+                    current.chunks.remove(start);
+                }
+                (!current.chunks.is_empty()).then_some(current)
+            }))
+        })?;
+        if remaining.is_none() {
+            limiters().remove(&name);
+        }
+    }
+    Ok(purged)
+}
+
+/// Forecast the next `horizon` values with Holt's linear (double exponential) smoothing
+///
+/// `alpha` smooths the level and `beta` the trend, both in `(0, 1]`.
+pub fn forecast_holt(values: &[f64], horizon: usize, alpha: f64, beta: f64) -> Vec<f64> {
+    let (Some(&first), Some(&second)) = (values.first(), values.get(1)) else {
+        return vec![values.first().copied().unwrap_or(0.0); horizon];
+    };
+    let mut level = first;
+    let mut trend = second - first;
+    for &value in &values[1..] {
+        let previous_level = level;
+        level = alpha * value + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - previous_level) + (1.0 - beta) * trend;
+    }
+    (1..=horizon).map(|step| level + step as f64 * trend).collect()
+}
+
+/// Longest forecast `forecast` computes
+pub const MAX_FORECAST_HORIZON: usize = 10_000;
+
+/// Forecast the next `horizon` values of a stored series (raw points only, tags ignored)
+pub fn forecast(name: &str, horizon: usize) -> anyhow::Result<Vec<f64>> {
+    anyhow::ensure!(horizon <= MAX_FORECAST_HORIZON, "horizon must be at most {}, got {}", MAX_FORECAST_HORIZON, horizon);
+    let mut values = Vec::new();
+    if let Some(series) = load_series(name)? {
+        for &start in &series.chunks {
+            if let Some(chunk) = get_chunk(name, start)? {
+                values.extend(chunk.points.iter().map(|p| p.value));
+            }
+        }
+    }
+    Ok(forecast_holt(&values, horizon, 0.5, 0.3))
+}
+
+/// Forecast every series and publish each forecast as `timeseries.forecast`
+fn forecast_all() -> anyhow::Result<()> {
+    for name in series_names()? {
+        let values = forecast(&name, FORECAST_CLOCK_HORIZON)?;
+        crate::events::publish("timeseries.forecast", serde_json::json!({
+            "series": name,
+            "next": values.first(),
+            "values": values,
+        }));
+    }
+    Ok(())
+}
+
+/// Forecast every series on a `FORECAST_EVERY_TICKS` sub-clock of the cognitive cycle
+/// (once; requires a Tokio runtime)
+///
+/// Forecasts go out as `timeseries.forecast` events, so pattern rules can
+/// watch the `timeseries.forecast.next` series. They run at the cycle's pace
+/// and stop while it is stopped.
+pub fn ensure_forecasts_started() {
+    static FORECASTS: OnceLock<()> = OnceLock::new();
+    FORECASTS.get_or_init(|| {
+        let mut clock = crate::cycle::subscribe_clock(crate::cycle::Schedule::every(FORECAST_EVERY_TICKS));
+        crate::threads::spawn_background(async move {
+            loop {
+                match clock.recv().await {
+                    Ok(_) => {}
+                    // The next tick forecasts from the latest points anyway
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+                // Followers leave forecasting the shared series to the leader
+                if !crate::leader::is_leader() {
+                    continue;
+                }
+                if let Ok(Err(e)) = tokio::task::spawn_blocking(forecast_all).await {
+                    log_warn!("[Timeseries] Forecasting failed: {}", e);
+                }
+            }
+        });
+    });
+}
+
+static COMPACTION: OnceLock<()> = OnceLock::new();
+
+/// Start the background compaction task (once; requires a Tokio runtime)
+pub fn ensure_compaction_started() {
+    COMPACTION.get_or_init(|| {
+        crate::threads::spawn_background(async {
+            let mut interval = tokio::time::interval(COMPACTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                // Followers leave compaction of the shared series to the leader
+                if !crate::leader::is_leader() {
+                    continue;
+                }
+                let reports = match tokio::task::spawn_blocking(compact_all).await {
+                    Ok(Ok(reports)) => reports,
+                    Ok(Err(e)) => {
+                        log_error!("[Timeseries] Compaction failed: {}", e);
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                for report in reports {
+                    log_info!(
+                        "[Timeseries] Compacted {}: {} points rolled up, {} rollups expired",
+                        report.series, report.rolled_up, report.expired
+                    );
+                }
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestServer;
+
+    fn point(ts: u64, value: f64) -> Point {
+        Point { ts, value, tags: BTreeMap::new() }
+    }
+
+    #[test]
+    fn non_finite_values_and_missing_timestamps_are_rejected() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let error = validate_points(&[point(1, 1.0), point(2, value)]).unwrap_err();
+            assert!(matches!(&error, AppendError::Invalid(reason) if reason.contains("point 1")), "{}", error);
+        }
+        assert!(matches!(validate_points(&[point(0, 1.0)]), Err(AppendError::Invalid(_))));
+        assert!(matches!(append("bad name!", vec![point(1, 1.0)]), Err(AppendError::Invalid(_))));
+        assert!(validate_points(&[point(1, -0.5), point(1, f64::MAX)]).is_ok());
+    }
+
+    #[test]
+    fn the_rate_limiter_refills_at_the_sustained_rate_up_to_the_burst() {
+        let mut limiter = RateLimiter::new();
+        assert_eq!(limiter.wait_ms(RATE_LIMIT_BURST as usize), 0);
+        // One second short of a further 5000 points
+        assert_eq!(limiter.wait_ms(RATE_LIMIT_BURST as usize + RATE_LIMIT_POINTS_PER_SEC as usize), 1000);
+
+        limiter.tokens = 0.0;
+        limiter.last_refill_ms = now_millis() - 1000;
+        limiter.refill();
+        assert!(limiter.tokens >= RATE_LIMIT_POINTS_PER_SEC && limiter.tokens < RATE_LIMIT_POINTS_PER_SEC * 1.1);
+
+        limiter.last_refill_ms = now_millis() - 60_000;
+        limiter.refill();
+        assert_eq!(limiter.tokens, RATE_LIMIT_BURST);
+    }
+
+    #[test]
+    fn rate_limits_are_taken_from_every_series_or_none() {
+        take_rate_limits(&[("limited.a", RATE_LIMIT_BURST as usize)]).unwrap();
+        match take_rate_limits(&[("limited.b", 1), ("limited.a", RATE_LIMIT_POINTS_PER_SEC as usize)]) {
+            Err(AppendError::RateLimited { retry_after_ms }) => assert!(retry_after_ms > 0 && retry_after_ms <= 1000),
+            other => panic!("expected a rate limit, got {:?}", other),
+        }
+        assert_eq!(limiters()["limited.b"].tokens, RATE_LIMIT_BURST);
+    }
+
+    #[tokio::test]
+    async fn late_points_are_inserted_in_order_within_the_lateness_window() {
+        let _server = TestServer::start().await.expect("test server did not start");
+        let newest = 10 * CHUNK_MS + 5_000;
+        append("late.points", vec![point(newest, 1.0)]).unwrap();
+
+        let report = append("late.points", vec![
+            point(newest + 10, 2.0),
+            point(newest - 1_000, 3.0),
+            point(newest, 4.0),
+            point(newest - MAX_LATENESS_MS, 5.0),
+            point(newest - MAX_LATENESS_MS - 1, 6.0),
+        ]).unwrap();
+        assert_eq!(
+            (report.accepted, report.out_of_order, report.replaced, report.too_late),
+            (4, 2, 1, 1)
+        );
+
+        let stored: Vec<(u64, f64)> = query("late.points", None, None).unwrap().iter().map(|p| (p.ts, p.value)).collect();
+        assert_eq!(stored, [
+            (newest - MAX_LATENESS_MS, 5.0),
+            (newest - 1_000, 3.0),
+            (newest, 4.0),
+            (newest + 10, 2.0),
+        ]);
+    }
+
+    #[test]
+    fn raw_points_are_rolled_up_per_bucket_and_tag_set() {
+        let mut chunk = Chunk::new("rollups", 0);
+        let tagged = Point { tags: BTreeMap::from([("host".to_string(), "b".to_string())]), ..point(100_000, 10.0) };
+        for p in [point(60_000, 1.0), point(90_000, 3.0), point(119_999, 2.0), tagged, point(120_000, 7.0)] {
+            chunk.insert(p);
+        }
+
+        // Points before the cutoff are rolled up; the one at it stays raw
+        assert_eq!(chunk.compact(60_000, 120_000, 0), (4, 0));
+        assert_eq!(chunk.points, [point(120_000, 7.0)]);
+        assert_eq!(chunk.rollups.len(), 2);
+        let untagged = chunk.rollups.iter().find(|r| r.tags.is_empty()).unwrap();
+        assert_eq!((untagged.ts, untagged.min, untagged.max, untagged.sum, untagged.count), (60_000, 1.0, 3.0, 6.0, 3));
+        assert_eq!(untagged.value(RollupFn::Avg), 2.0);
+        assert_eq!(untagged.value(RollupFn::Max), 3.0);
+
+        // A late point merges into the existing bucket
+        chunk.insert(point(61_000, 6.0));
+        assert_eq!(chunk.compact(60_000, 120_000, 0), (1, 0));
+        let untagged = chunk.rollups.iter().find(|r| r.tags.is_empty()).unwrap();
+        assert_eq!((untagged.sum, untagged.count), (12.0, 4));
+    }
+
+    #[test]
+    fn retention_keeps_what_starts_at_the_cutoff() {
+        let mut chunk = Chunk::new("retention", 0);
+        chunk.insert(point(60_000, 1.0));
+        chunk.compact(60_000, 120_000, 0);
+        assert_eq!(chunk.compact(60_000, 120_000, 60_000), (0, 0));
+        assert_eq!(chunk.rollups.len(), 1);
+        assert_eq!(chunk.compact(60_000, 120_000, 60_001), (0, 1));
+        assert!(chunk.is_empty());
+
+        let mut chunk = Chunk::new("retention", 0);
+        chunk.insert(point(60_000, 1.0));
+        chunk.compact(60_000, 120_000, 0);
+        chunk.insert(point(119_999, 2.0));
+        chunk.insert(point(120_000, 3.0));
+        assert_eq!(chunk.count_before(120_000), 2);
+        assert_eq!(chunk.drop_before(120_000), 2);
+        assert_eq!(chunk.points, [point(120_000, 3.0)]);
+        assert!(chunk.rollups.is_empty());
+    }
+
+    #[tokio::test]
+    async fn compaction_rolls_up_whole_buckets_and_expires_old_rollups() {
+        let _server = TestServer::start().await.expect("test server did not start");
+        let policy = RetentionPolicy {
+            raw_retention_ms: CHUNK_MS,
+            rollup_interval_ms: 60_000,
+            rollup_retention_ms: 2 * CHUNK_MS,
+            rollup: RollupFn::Avg,
+        };
+        set_policy("compacted", policy).unwrap();
+        let hour = 99 * CHUNK_MS;
+        for ts in [hour - CHUNK_MS + 29_999, hour - 60_000, hour - 1, hour + 10_000] {
+            append("compacted", vec![point(ts, (ts % 10) as f64)]).unwrap();
+        }
+
+        // Raw retention ends at `hour + 30_000`, inside the bucket starting at `hour`
+        let now = hour + CHUNK_MS + 30_000;
+        let series = load_series("compacted").unwrap().unwrap();
+        let report = compact_series(&series, now).unwrap();
+        assert_eq!((report.rolled_up, report.expired), (3, 1));
+
+        let stored: Vec<(u64, f64)> = query("compacted", None, None).unwrap().iter().map(|p| (p.ts, p.value)).collect();
+        assert_eq!(stored, [(hour - 60_000, 4.5), (hour + 10_000, 0.0)]);
+        assert_eq!(load_series("compacted").unwrap().unwrap().chunks, BTreeSet::from([chunk_start(hour - 1), hour]));
+    }
+}
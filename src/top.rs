@@ -0,0 +1,142 @@
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::time::Duration;
+
+use crate::api_client::ApiClient;
+use crate::live_stats::LiveStats;
+
+// ============================================================================
+// Terminal Dashboard (`pattern-clock top [URL]`, feature = "tui")
+// ============================================================================
+//
+// Full-screen view of a running instance for headless servers: per-agent
+// throughput, LLM latency, monitor tick rate and the latest events, polled
+// from `GET /api/stats/live` once a second. `q` or Esc quits.
+
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// Run the dashboard until the user quits
+pub fn run(base_url: &str) -> anyhow::Result<()> {
+    let client = ApiClient::new(base_url);
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, &client);
+    ratatui::restore();
+    result
+}
+
+fn run_loop(terminal: &mut DefaultTerminal, client: &ApiClient) -> anyhow::Result<()> {
+    let runtime = crate::runtime::background_runtime();
+    loop {
+        let stats = runtime.block_on(fetch(client));
+        terminal.draw(|frame| draw(frame, client.base_url(), &stats))?;
+
+        if event::poll(REFRESH)? {
+            if let TermEvent::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn fetch(client: &ApiClient) -> Result<LiveStats, String> {
+    let value = client.get("/api/stats/live").await.map_err(|e| e.to_string())?;
+    serde_json::from_value(value).map_err(|e| format!("unexpected stats: {}", e))
+}
+
+fn draw(frame: &mut Frame, base_url: &str, stats: &Result<LiveStats, String>) {
+    let [header, body, feed] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(8),
+        Constraint::Percentage(45),
+    ])
+    .areas(frame.area());
+
+    let stats = match stats {
+        Ok(stats) => stats,
+        Err(e) => {
+            let line = Line::from(vec![
+                Span::styled(format!("pattern-clock top  {}  ", base_url), Style::new().add_modifier(Modifier::BOLD)),
+                Span::styled(e.as_str(), Style::new().fg(Color::Red)),
+            ]);
+            frame.render_widget(Paragraph::new(line), header);
+            return;
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(format!("pattern-clock top  {}", base_url), Style::new().add_modifier(Modifier::BOLD)),
+            Span::raw(format!("  last {}s  (q to quit)", stats.window_secs)),
+        ])),
+        header,
+    );
+
+    let [agents_area, side] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+    let rows = stats.agents.iter().map(|agent| {
+        let style = if agent.errors > 0 { Style::new().fg(Color::Yellow) } else { Style::new() };
+        Row::new(vec![
+            format!("Agent{}", agent.agent_id),
+            agent.handled.to_string(),
+            format!("{:.2}", agent.per_sec),
+            agent.errors.to_string(),
+            format!("{:.0}", agent.avg_ms),
+        ])
+        .style(style)
+    });
+    let table = Table::new(rows, [Constraint::Length(8), Constraint::Length(8), Constraint::Length(8), Constraint::Length(7), Constraint::Length(8)])
+        .header(Row::new(vec!["agent", "handled", "msg/s", "errors", "avg ms"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title("Agents"));
+    frame.render_widget(table, agents_area);
+
+    let [llm_area, ticks_area] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(side);
+    let llm = &stats.llm;
+    let llm_style = if llm.errors > 0 { Style::new().fg(Color::Red) } else { Style::new() };
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(format!("requests  {}", llm.requests)),
+            Line::styled(format!("errors    {}", llm.errors), llm_style),
+            Line::from(format!("avg       {:.0} ms", llm.avg_ms)),
+            Line::from(format!("p95       {} ms", llm.p95_ms)),
+            Line::from(format!("in flight {} (peak {})", llm.pool.in_flight, llm.pool.peak_in_flight)),
+            Line::from(format!("http/2    {} of {}", llm.pool.http2_responses, llm.pool.responses)),
+        ])
+        .block(Block::default().borders(Borders::ALL).title("LLM")),
+        llm_area,
+    );
+    let ticks = &stats.ticks;
+    let runtime = &stats.runtime;
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(format!("ticks      {}", ticks.ticks)),
+            Line::from(format!("rate       {:.2}/s", ticks.per_sec)),
+            Line::from(format!("max delay  {} ms", ticks.max_delay_ms)),
+            Line::from(format!("tasks      {} on {} workers ({} queued)", runtime.tasks, runtime.workers, runtime.queue_depth)),
+            Line::from(format!("compute    {} of {} busy ({} queued)", runtime.compute_busy, runtime.compute_threads, runtime.compute_queued)),
+        ])
+        .block(Block::default().borders(Borders::ALL).title("Ticks and runtime")),
+        ticks_area,
+    );
+
+    let items = stats.events.iter().map(|event| {
+        let age = stats.ts.saturating_sub(event.ts) / 1000;
+        let color = match event.kind.as_str() {
+            "monitor.alert" | "gpu.out_of_memory" | "agent.deadline_exceeded" => Color::Red,
+            kind if kind.starts_with("llm.") => Color::Cyan,
+            _ => Color::Reset,
+        };
+        ListItem::new(Line::from(vec![
+            Span::raw(format!("{:>4}s  ", age)),
+            Span::styled(format!("{:<24}", event.kind), Style::new().fg(color)),
+            Span::raw(event.payload.to_string()),
+        ]))
+    });
+    frame.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL).title("Events")),
+        feed,
+    );
+}
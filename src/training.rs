@@ -0,0 +1,802 @@
+use burn::backend::{Autodiff, NdArray, wgpu::Wgpu};
+use burn::module::{AutodiffModule, Module};
+use burn::record::CompactRecorder;
+use burn::nn::loss::CrossEntropyLossConfig;
+use burn::grad_clipping::GradientClippingConfig;
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::tensor::backend::{AutodiffBackend, Backend};
+use burn::tensor::{ElementConversion, Int, Tensor, TensorData};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+
+use crate::classifier::{tokenize, TextClassifier, TextClassifierConfig};
+use crate::dataset::{register_dataset_version, LabeledExample};
+use crate::events::publish;
+use crate::experiments::{attach_artifact, attach_artifact_blob, hash_dataset, record_run, set_metric, EpochMetrics};
+use crate::gpu::{self, GpuError};
+use crate::registry::{find_model, ModelEntry, ModelKind};
+use crate::storage::now_millis;
+use crate::seq2seq::{tokens_to_tensor, Seq2Seq, Seq2SeqConfig, END_TOKEN, FIRST_EVENT_TOKEN, PAD_TOKEN, START_TOKEN};
+
+// ============================================================================
+// Training Loop
+// ============================================================================
+
+/// Hyperparameters shared by all training jobs
+#[derive(Debug, Clone, Serialize)]
+pub struct TrainingConfig {
+    /// Number of passes over the dataset
+    pub epochs: usize,
+    /// Number of examples per optimizer step
+    pub batch_size: usize,
+    /// Adam learning rate
+    pub learning_rate: f64,
+    /// Number of cross-validation folds (values below 2 disable cross-validation)
+    pub k_folds: usize,
+    /// Directory for per-fold checkpoints (none saves nothing)
+    pub checkpoint_dir: Option<String>,
+    /// Clip the global gradient L2 norm to this value (none disables clipping)
+    pub grad_clip_norm: Option<f32>,
+    /// What to do when the loss becomes NaN or infinite
+    pub divergence_policy: DivergencePolicy,
+    /// Seed for weight init, data shuffling and dropout (recorded with the experiment)
+    pub seed: u64,
+    /// Origin of the training data, recorded in the dataset version
+    pub dataset_source: String,
+    /// Parameters of the transform that produced the training data
+    pub dataset_transform: serde_json::Value,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 10,
+            batch_size: 16,
+            learning_rate: 1e-3,
+            k_folds: 0,
+            checkpoint_dir: None,
+            grad_clip_norm: Some(1.0),
+            divergence_policy: DivergencePolicy::default(),
+            seed: 42,
+            dataset_source: "inline".to_string(),
+            dataset_transform: serde_json::Value::Null,
+        }
+    }
+}
+
+impl TrainingConfig {
+    /// Adam optimizer config with the configured gradient clipping
+    fn optimizer(&self) -> AdamConfig {
+        AdamConfig::new().with_grad_clipping(self.grad_clip_norm.map(GradientClippingConfig::Norm))
+    }
+
+    /// Build a model with the backend RNG seeded, so its initial weights are reproducible
+    ///
+    /// The backend RNG is global, so models built at the same time (tuning
+    /// trials on the compute threads) are built one at a time: otherwise one
+    /// could reseed the RNG between another's seeding and its weight init.
+    pub fn init_seeded<B: Backend, M>(&self, device: &B::Device, init: impl FnOnce() -> M) -> M {
+        static SEEDED_INIT: Mutex<()> = Mutex::new(());
+        let _guard = SEEDED_INIT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        B::seed(device, self.seed);
+        init()
+    }
+}
+
+/// Small deterministic RNG (xorshift64) for shuffling and sampling
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift must never start from zero
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Uniform index in `0..len` (`len` must be non-zero)
+    pub fn next_index(&mut self, len: usize) -> usize {
+        (self.next_u64() % len as u64) as usize
+    }
+
+    /// Fisher-Yates shuffled copy of `data`
+    pub fn shuffled<T: Clone>(&mut self, data: &[T]) -> Vec<T> {
+        let mut shuffled = data.to_vec();
+        for i in (1..shuffled.len()).rev() {
+            let j = self.next_index(i + 1);
+            shuffled.swap(i, j);
+        }
+        shuffled
+    }
+}
+
+/// Reaction to a NaN/Inf loss
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergencePolicy {
+    /// Skip the batch and multiply the learning rate by `factor`, aborting after `max_reductions`
+    ReduceLr { factor: f64, max_reductions: usize },
+    /// Stop training immediately
+    Abort,
+}
+
+impl Default for DivergencePolicy {
+    fn default() -> Self {
+        DivergencePolicy::ReduceLr { factor: 0.5, max_reductions: 3 }
+    }
+}
+
+/// Outcome of checking a batch loss
+enum StepDecision {
+    /// Loss is finite, apply the optimizer step
+    Step,
+    /// Loss diverged, skip this batch
+    Skip,
+    /// Loss diverged too often, stop training
+    Abort(String),
+}
+
+/// Tracks the effective learning rate and reacts to non-finite losses
+struct DivergenceGuard<'a> {
+    model: &'a str,
+    policy: &'a DivergencePolicy,
+    learning_rate: f64,
+    reductions: usize,
+}
+
+impl<'a> DivergenceGuard<'a> {
+    fn new(model: &'a str, config: &'a TrainingConfig) -> Self {
+        Self {
+            model,
+            policy: &config.divergence_policy,
+            learning_rate: config.learning_rate,
+            reductions: 0,
+        }
+    }
+
+    /// Decide what to do with a batch loss, emitting a `training.diverged` event when it is not finite
+    fn check(&mut self, epoch: usize, batch: usize, loss: f32) -> StepDecision {
+        if loss.is_finite() {
+            return StepDecision::Step;
+        }
+
+        let decision = match self.policy {
+            DivergencePolicy::ReduceLr { factor, max_reductions } if self.reductions < *max_reductions => {
+                self.reductions += 1;
+                self.learning_rate *= factor;
+                StepDecision::Skip
+            }
+            DivergencePolicy::ReduceLr { max_reductions, .. } => StepDecision::Abort(format!(
+                "loss diverged after {} learning rate reductions", max_reductions
+            )),
+            DivergencePolicy::Abort => StepDecision::Abort(format!("loss diverged ({})", loss)),
+        };
+
+        log_warn!("[Training] {} diverged at epoch {} batch {} (loss {}), learning rate now {:e}",
+            self.model, epoch, batch, loss, self.learning_rate);
+        publish("training.diverged", json!({
+            "model": self.model,
+            "epoch": epoch,
+            "batch": batch,
+            "loss": loss.to_string(),
+            "learning_rate": self.learning_rate,
+            "reductions": self.reductions,
+            "aborted": matches!(decision, StepDecision::Abort(_)),
+        }));
+        decision
+    }
+}
+
+/// Summary of a finished training run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TrainingReport {
+    pub epochs: Vec<EpochMetrics>,
+    /// Experiment run this training was recorded as
+    pub run_id: Option<String>,
+    /// Why training stopped early, if it did
+    pub aborted: Option<String>,
+}
+
+impl TrainingReport {
+    /// Loss of the last completed epoch
+    pub fn final_loss(&self) -> Option<f32> {
+        self.epochs.last().map(|m| m.loss)
+    }
+
+    /// Record the run in the experiments store, logging (not failing) on error
+    fn record<T: Serialize>(
+        &mut self,
+        model: &str,
+        model_config: serde_json::Value,
+        dataset: &[T],
+        config: &TrainingConfig,
+        started_at: u64,
+    ) {
+        let dataset_version = match register_dataset_version(dataset, &config.dataset_source, config.dataset_transform.clone()) {
+            Ok(version) => Some(version.id),
+            Err(e) => {
+                log_error!("[Training] Failed to register dataset version: {}", e);
+                None
+            }
+        };
+        match record_run(model, model_config, self.epochs.clone(), hash_dataset(dataset), dataset_version, config.seed, started_at) {
+            Ok(run_id) => self.run_id = Some(run_id),
+            Err(e) => log_error!("[Training] Failed to record experiment: {}", e),
+        }
+    }
+
+    /// Set a final metric on the recorded run, if any
+    pub fn set_metric(&self, name: &str, value: f32) {
+        if let Some(run_id) = &self.run_id {
+            if let Err(e) = set_metric(run_id, name, value) {
+                log_error!("[Training] Failed to update experiment {}: {}", run_id, e);
+            }
+        }
+    }
+
+    /// Attach an artifact path to the recorded run, if any, and keep a copy in the blob store
+    ///
+    /// `path` is as passed to the recorder, which adds the `.mpk` extension.
+    pub fn attach_artifact(&self, path: &str) {
+        if let Some(run_id) = &self.run_id {
+            if let Err(e) = attach_artifact(run_id, path) {
+                log_error!("[Training] Failed to update experiment {}: {}", run_id, e);
+            }
+            let file = std::path::Path::new(path).with_extension("mpk");
+            if let Err(e) = attach_artifact_blob(run_id, &file) {
+                log_error!("[Training] Failed to store artifact {} of {}: {}", file.display(), run_id, e);
+            }
+        }
+    }
+}
+
+/// A (source sequence, target sequence) training example
+pub type SequencePair = (Vec<usize>, Vec<usize>);
+
+/// One line of a sequence dataset: `{"source": [3, 4], "target": [5]}`
+#[derive(Debug, Deserialize)]
+struct SequenceLine {
+    source: Vec<usize>,
+    target: Vec<usize>,
+}
+
+/// Parse a JSON Lines dataset of source and target token sequences
+///
+/// Tokens must be event tokens (from `FIRST_EVENT_TOKEN`) below `vocab_size`,
+/// and sources must not be empty.
+pub fn parse_sequence_jsonl(jsonl: &str, vocab_size: usize) -> anyhow::Result<Vec<SequencePair>> {
+    jsonl
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line: SequenceLine = serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("line {}: {}", index + 1, e))?;
+            anyhow::ensure!(!line.source.is_empty(), "line {}: empty source", index + 1);
+            let tokens = line.source.iter().chain(&line.target);
+            if let Some(token) = tokens.copied().find(|token| !(FIRST_EVENT_TOKEN..vocab_size).contains(token)) {
+                anyhow::bail!("line {}: token {} is outside {}..{}", index + 1, token, FIRST_EVENT_TOKEN, vocab_size);
+            }
+            Ok((line.source, line.target))
+        })
+        .collect()
+}
+
+/// Train a sequence-to-sequence model with teacher forcing
+///
+/// Targets are wrapped as `START, t1..tn` for the decoder input and
+/// `t1..tn, END` for the expected output. Padding is excluded from the loss.
+pub fn train_seq2seq<B: AutodiffBackend>(
+    mut model: Seq2Seq<B>,
+    dataset: &[SequencePair],
+    config: &TrainingConfig,
+    device: &B::Device,
+) -> (Seq2Seq<B>, TrainingReport) {
+    let started_at = now_millis();
+    let mut optimizer = config.optimizer().init::<B, Seq2Seq<B>>();
+    let loss_fn = CrossEntropyLossConfig::new()
+        .with_pad_tokens(Some(vec![PAD_TOKEN]))
+        .init(device);
+    let mut report = TrainingReport::default();
+    let mut guard = DivergenceGuard::new("seq2seq", config);
+    let mut rng = SeededRng::new(config.seed);
+
+    'training: for epoch in 1..=config.epochs {
+        let mut total_loss = 0.0;
+        let mut batches = 0;
+        let epoch_data = rng.shuffled(dataset);
+
+        for (batch_idx, batch) in epoch_data.chunks(config.batch_size.max(1)).enumerate() {
+            let sources: Vec<Vec<usize>> = batch.iter().map(|(source, _)| source.clone()).collect();
+            let decoder_inputs: Vec<Vec<usize>> = batch
+                .iter()
+                .map(|(_, target)| std::iter::once(START_TOKEN).chain(target.iter().copied()).collect())
+                .collect();
+            let expected: Vec<Vec<usize>> = batch
+                .iter()
+                .map(|(_, target)| target.iter().copied().chain(std::iter::once(END_TOKEN)).collect())
+                .collect();
+
+            let logits = model.forward(
+                tokens_to_tensor(&sources, device),
+                tokens_to_tensor(&decoder_inputs, device),
+            );
+            let [batch_size, target_len, vocab_size] = logits.dims();
+            let logits = logits.reshape([batch_size * target_len, vocab_size]);
+            let targets = tokens_to_tensor::<B>(&expected, device).reshape([batch_size * target_len]);
+
+            let loss = loss_fn.forward(logits, targets);
+            let loss_value = loss.clone().into_scalar().elem::<f32>();
+            match guard.check(epoch, batch_idx, loss_value) {
+                StepDecision::Step => {}
+                StepDecision::Skip => continue,
+                StepDecision::Abort(reason) => {
+                    report.aborted = Some(reason);
+                    break 'training;
+                }
+            }
+            total_loss += loss_value;
+            batches += 1;
+
+            let grads = GradientsParams::from_grads(loss.backward(), &model);
+            model = optimizer.step(guard.learning_rate, model, grads);
+        }
+
+        let loss = if batches > 0 { total_loss / batches as f32 } else { 0.0 };
+        log_info!("[Training] Epoch {}/{} - loss: {:.4}", epoch, config.epochs, loss);
+        report.epochs.push(EpochMetrics { epoch, loss });
+    }
+
+    report.record("seq2seq", json!({ "training": config }), dataset, config, started_at);
+    (model, report)
+}
+
+/// Train the text classifier on labeled examples (e.g. from `dataset::parse_jsonl`)
+///
+/// Examples whose label is not in `classifier_config.labels` are skipped.
+pub fn train_classifier<B: AutodiffBackend>(
+    mut model: TextClassifier<B>,
+    examples: &[LabeledExample],
+    classifier_config: &TextClassifierConfig,
+    config: &TrainingConfig,
+    device: &B::Device,
+) -> (TextClassifier<B>, TrainingReport) {
+    let encoded: Vec<(Vec<usize>, usize)> = examples
+        .iter()
+        .filter_map(|example| {
+            let class = classifier_config.labels.iter().position(|l| *l == example.label)?;
+            Some((tokenize(&example.text, classifier_config.vocab_size), class))
+        })
+        .collect();
+
+    let started_at = now_millis();
+    let mut optimizer = config.optimizer().init::<B, TextClassifier<B>>();
+    let loss_fn = CrossEntropyLossConfig::new().init(device);
+    let mut report = TrainingReport::default();
+    let mut guard = DivergenceGuard::new("classifier", config);
+    let mut rng = SeededRng::new(config.seed);
+
+    'training: for epoch in 1..=config.epochs {
+        let mut total_loss = 0.0;
+        let mut batches = 0;
+        let epoch_data = rng.shuffled(&encoded);
+
+        for (batch_idx, batch) in epoch_data.chunks(config.batch_size.max(1)).enumerate() {
+            let tokens: Vec<Vec<usize>> = batch.iter().map(|(tokens, _)| tokens.clone()).collect();
+            let classes: Vec<i64> = batch.iter().map(|(_, class)| *class as i64).collect();
+            let targets = Tensor::<B, 1, Int>::from_data(TensorData::new(classes, [batch.len()]), device);
+
+            let logits = model.forward(tokens_to_tensor(&tokens, device));
+            let loss = loss_fn.forward(logits, targets);
+            let loss_value = loss.clone().into_scalar().elem::<f32>();
+            match guard.check(epoch, batch_idx, loss_value) {
+                StepDecision::Step => {}
+                StepDecision::Skip => continue,
+                StepDecision::Abort(reason) => {
+                    report.aborted = Some(reason);
+                    break 'training;
+                }
+            }
+            total_loss += loss_value;
+            batches += 1;
+
+            let grads = GradientsParams::from_grads(loss.backward(), &model);
+            model = optimizer.step(guard.learning_rate, model, grads);
+        }
+
+        let loss = if batches > 0 { total_loss / batches as f32 } else { 0.0 };
+        log_info!("[Training] Classifier epoch {}/{} - loss: {:.4}", epoch, config.epochs, loss);
+        report.epochs.push(EpochMetrics { epoch, loss });
+    }
+
+    let model_config = json!({
+        "training": config,
+        "hidden_size": classifier_config.hidden_size,
+        "num_layers": classifier_config.num_layers,
+        "embedding_size": classifier_config.embedding_size,
+        "labels": classifier_config.labels,
+    });
+    report.record("classifier", model_config, examples, config, started_at);
+    (model, report)
+}
+
+/// Validation loss and accuracy of a classifier on held-out examples
+pub fn evaluate_classifier<B: AutodiffBackend>(
+    model: &TextClassifier<B>,
+    examples: &[LabeledExample],
+    classifier_config: &TextClassifierConfig,
+    device: &B::Device,
+) -> (f32, f32) {
+    let model = model.valid();
+    let encoded: Vec<(Vec<usize>, i64)> = examples
+        .iter()
+        .filter_map(|example| {
+            let class = classifier_config.labels.iter().position(|l| *l == example.label)?;
+            Some((tokenize(&example.text, classifier_config.vocab_size), class as i64))
+        })
+        .collect();
+    if encoded.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let tokens: Vec<Vec<usize>> = encoded.iter().map(|(tokens, _)| tokens.clone()).collect();
+    let classes: Vec<i64> = encoded.iter().map(|(_, class)| *class).collect();
+    let targets = Tensor::<B::InnerBackend, 1, Int>::from_data(TensorData::new(classes.clone(), [classes.len()]), device);
+
+    let logits = model.forward(tokens_to_tensor(&tokens, device));
+    let predictions: Vec<i64> = logits.clone().argmax(1).into_data().convert::<i64>().to_vec().unwrap_or_default();
+    let loss = CrossEntropyLossConfig::new().init(device).forward(logits, targets);
+
+    let correct = predictions.iter().zip(&classes).filter(|(p, c)| p == c).count();
+    (loss.into_scalar().elem::<f32>(), correct as f32 / classes.len() as f32)
+}
+
+// ============================================================================
+// K-Fold Cross-Validation
+// ============================================================================
+
+/// Metrics for one cross-validation fold
+#[derive(Debug, Clone, Serialize)]
+pub struct FoldMetrics {
+    pub fold: usize,
+    pub train_loss: f32,
+    pub val_loss: f32,
+    pub val_accuracy: f32,
+    /// Where the fold's model was saved, if checkpointing is enabled
+    pub checkpoint: Option<String>,
+    /// Experiment run the fold was recorded as
+    pub run_id: Option<String>,
+}
+
+/// Aggregated cross-validation results
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossValidationReport {
+    pub folds: Vec<FoldMetrics>,
+    pub mean_val_loss: f32,
+    pub std_val_loss: f32,
+    pub mean_val_accuracy: f32,
+    pub std_val_accuracy: f32,
+}
+
+/// Mean and (population) standard deviation
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    (mean, variance.sqrt())
+}
+
+/// Shuffle `data` with `seed`, split it into `k` folds and run `run_fold(fold, train, validation)` on each
+///
+/// Exported datasets are usually sorted by label, so unshuffled folds would
+/// each validate on mostly one class. Fold sizes differ by at most one, and
+/// `k` is capped at the number of examples, so every fold runs.
+pub fn cross_validate<T: Clone>(
+    data: &[T],
+    k: usize,
+    seed: u64,
+    mut run_fold: impl FnMut(usize, &[T], &[T]) -> FoldMetrics,
+) -> CrossValidationReport {
+    let data = SeededRng::new(seed).shuffled(data);
+    let k = k.clamp(2, data.len().max(2));
+    let (fold_size, remainder) = (data.len() / k, data.len() % k);
+
+    let mut folds = Vec::with_capacity(k);
+    let mut start = 0;
+    for fold in 0..k {
+        let end = start + fold_size + usize::from(fold < remainder);
+        if start == end {
+            continue;
+        }
+        let validation = &data[start..end];
+        let train: Vec<T> = data[..start].iter().chain(&data[end..]).cloned().collect();
+        let metrics = run_fold(fold, &train, validation);
+        log_info!("[Training] Fold {}/{} - val_loss: {:.4}, val_accuracy: {:.3}",
+            fold + 1, k, metrics.val_loss, metrics.val_accuracy);
+        folds.push(metrics);
+        start = end;
+    }
+
+    let (mean_val_loss, std_val_loss) = mean_std(&folds.iter().map(|f| f.val_loss).collect::<Vec<_>>());
+    let (mean_val_accuracy, std_val_accuracy) = mean_std(&folds.iter().map(|f| f.val_accuracy).collect::<Vec<_>>());
+    CrossValidationReport {
+        folds,
+        mean_val_loss,
+        std_val_loss,
+        mean_val_accuracy,
+        std_val_accuracy,
+    }
+}
+
+/// Run k-fold cross-validation of the text classifier using `config.k_folds`
+pub fn cross_validate_classifier<B: AutodiffBackend>(
+    examples: &[LabeledExample],
+    classifier_config: &TextClassifierConfig,
+    config: &TrainingConfig,
+    device: &B::Device,
+) -> CrossValidationReport {
+    cross_validate(examples, config.k_folds, config.seed, |fold, train, validation| {
+        let fold_config = TrainingConfig {
+            dataset_transform: json!({ "base": config.dataset_transform, "k_folds": config.k_folds, "fold": fold + 1 }),
+            ..config.clone()
+        };
+        let model = fold_config.init_seeded::<B, _>(device, || TextClassifier::<B>::new(classifier_config, device));
+        let (model, report) = train_classifier(model, train, classifier_config, &fold_config, device);
+        let (val_loss, val_accuracy) = evaluate_classifier(&model, validation, classifier_config, device);
+        report.set_metric("val_loss", val_loss);
+        report.set_metric("val_accuracy", val_accuracy);
+        report.set_metric("cv_fold", (fold + 1) as f32);
+        if let Some(run_id) = &report.run_id {
+            log_info!("[Training] Fold {}/{} recorded as run {}", fold + 1, config.k_folds, run_id);
+        }
+
+        let checkpoint = config.checkpoint_dir.as_deref().and_then(|dir| {
+            let path = match fold_checkpoint_path(dir, fold + 1) {
+                Ok(path) => path,
+                Err(e) => {
+                    log_error!("[Training] Not saving fold checkpoint: {}", e);
+                    return None;
+                }
+            };
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                log_error!("[Training] Failed to create checkpoint dir {}: {}", dir, e);
+                return None;
+            }
+            let path = path.to_string_lossy().into_owned();
+            match model.clone().save_file(path.clone(), &CompactRecorder::new()) {
+                Ok(()) => {
+                    report.attach_artifact(&path);
+                    Some(path)
+                }
+                Err(e) => {
+                    log_error!("[Training] Failed to save fold checkpoint {}: {:?}", path, e);
+                    None
+                }
+            }
+        });
+
+        FoldMetrics {
+            fold: fold + 1,
+            train_loss: report.final_loss().unwrap_or_default(),
+            val_loss,
+            val_accuracy,
+            checkpoint,
+            run_id: report.run_id.clone(),
+        }
+    })
+}
+
+/// Where fold `fold` of a cross-validation is saved under `dir`
+///
+/// Like the registry's weight paths, `dir` must be relative and stay inside
+/// the working directory.
+pub fn fold_checkpoint_path(dir: &str, fold: usize) -> anyhow::Result<std::path::PathBuf> {
+    use std::path::{Component, Path};
+
+    let dir = Path::new(dir);
+    anyhow::ensure!(
+        !dir.as_os_str().is_empty() && dir.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)),
+        "checkpoint dir {} must be a relative path without '..'", dir.display()
+    );
+    Ok(dir.join(format!("fold-{}", fold)))
+}
+
+// ============================================================================
+// Training Registered Models
+// ============================================================================
+
+/// What `train_registered` produced
+#[derive(Debug, Clone, Serialize)]
+pub struct TrainingOutcome {
+    pub model: String,
+    /// The run on all examples, whose weights were saved
+    pub report: TrainingReport,
+    /// Per-fold and aggregated metrics, when `k_folds` is at least 2
+    pub cross_validation: Option<CrossValidationReport>,
+    /// Where the weights were saved (without recorder extension)
+    pub weights_path: String,
+}
+
+/// Examples for `train_registered`, in the form the model takes
+#[derive(Debug, Clone)]
+pub enum TrainingData {
+    /// Texts with their label, for the classifier
+    Labeled(Vec<LabeledExample>),
+    /// Source and target token sequences, for the sequence-to-sequence model
+    Sequences(Vec<SequencePair>),
+}
+
+/// Train a registered model by name and save it where inference loads it from
+///
+/// Runs on the GPU; out-of-memory failures shrink the batch and eventually
+/// move training to the CPU, as for tuning trials.
+pub fn train_registered(name: &str, data: &TrainingData, config: &TrainingConfig) -> anyhow::Result<TrainingOutcome> {
+    let entry = find_model(name).ok_or_else(|| anyhow::anyhow!("unknown model {}", name))?;
+    loop {
+        if gpu::cpu_fallback() {
+            return train_entry::<Autodiff<NdArray>>(&entry, data, config);
+        }
+        let config = TrainingConfig {
+            batch_size: gpu::effective_batch_size(config.batch_size),
+            ..config.clone()
+        };
+        match gpu::guarded("training", || train_entry::<Autodiff<Wgpu>>(&entry, data, &config)) {
+            Ok(outcome) => return outcome,
+            Err(GpuError::OutOfMemory { .. }) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn train_entry<B: AutodiffBackend>(entry: &ModelEntry, data: &TrainingData, config: &TrainingConfig) -> anyhow::Result<TrainingOutcome> {
+    match (entry.kind, data) {
+        (ModelKind::TextClassifier, TrainingData::Labeled(examples)) => train_registered_classifier::<B>(entry, examples, config),
+        (ModelKind::Seq2Seq, TrainingData::Sequences(pairs)) => train_registered_seq2seq::<B>(entry, pairs, config),
+        (ModelKind::TextClassifier | ModelKind::Seq2Seq, _) => anyhow::bail!("{} does not train on this kind of example", entry.name),
+        (kind, _) => anyhow::bail!("training is not supported for {:?} models", kind),
+    }
+}
+
+/// Train the sequence-to-sequence model on all pairs (it has no cross-validation)
+fn train_registered_seq2seq<B: AutodiffBackend>(
+    entry: &ModelEntry,
+    pairs: &[SequencePair],
+    config: &TrainingConfig,
+) -> anyhow::Result<TrainingOutcome> {
+    anyhow::ensure!(!pairs.is_empty(), "no sequence pairs to train on");
+    anyhow::ensure!(config.k_folds < 2, "cross-validation is only supported for the classifier");
+
+    let device = Default::default();
+    let seq2seq_config = Seq2SeqConfig::default();
+    let model = config.init_seeded::<B, _>(&device, || Seq2Seq::<B>::new(&seq2seq_config, &device));
+    let (model, report) = train_seq2seq(model, pairs, config, &device);
+    save_registered(entry, model, &report)?;
+    Ok(TrainingOutcome {
+        model: entry.name.to_string(),
+        report,
+        cross_validation: None,
+        weights_path: entry.weights_path.to_string(),
+    })
+}
+
+/// Train the classifier on the examples it has a label for, cross-validating
+/// first when `config.k_folds` is at least 2
+fn train_registered_classifier<B: AutodiffBackend>(
+    entry: &ModelEntry,
+    examples: &[LabeledExample],
+    config: &TrainingConfig,
+) -> anyhow::Result<TrainingOutcome> {
+    let classifier_config = TextClassifierConfig::default();
+    let examples: Vec<LabeledExample> = examples
+        .iter()
+        .filter(|example| classifier_config.labels.contains(&example.label))
+        .cloned()
+        .collect();
+    anyhow::ensure!(
+        !examples.is_empty(),
+        "no examples labeled {}", classifier_config.labels.join(", ")
+    );
+    anyhow::ensure!(
+        config.k_folds < 2 || examples.len() >= config.k_folds,
+        "{} examples are too few for {} folds", examples.len(), config.k_folds
+    );
+    if let Some(dir) = &config.checkpoint_dir {
+        fold_checkpoint_path(dir, 1)?;
+    }
+
+    let device = Default::default();
+    let cross_validation = (config.k_folds >= 2)
+        .then(|| cross_validate_classifier::<B>(&examples, &classifier_config, config, &device));
+    let model = config.init_seeded::<B, _>(&device, || TextClassifier::<B>::new(&classifier_config, &device));
+    let (model, report) = train_classifier(model, &examples, &classifier_config, config, &device);
+    if let Some(cross_validation) = &cross_validation {
+        report.set_metric("cv_mean_val_loss", cross_validation.mean_val_loss);
+        report.set_metric("cv_std_val_loss", cross_validation.std_val_loss);
+        report.set_metric("cv_mean_val_accuracy", cross_validation.mean_val_accuracy);
+        report.set_metric("cv_std_val_accuracy", cross_validation.std_val_accuracy);
+    }
+    save_registered(entry, model, &report)?;
+    Ok(TrainingOutcome {
+        model: entry.name.to_string(),
+        report,
+        cross_validation,
+        weights_path: entry.weights_path.to_string(),
+    })
+}
+
+/// Save trained weights to the registry path and drop the pooled copy, so the next request loads them
+fn save_registered<B: Backend, M: Module<B>>(entry: &ModelEntry, model: M, report: &TrainingReport) -> anyhow::Result<()> {
+    if let Some(dir) = std::path::Path::new(entry.weights_path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    model.save_file(entry.weights_path, &CompactRecorder::new())
+        .map_err(|e| anyhow::anyhow!("failed to save {}: {:?}", entry.weights_path, e))?;
+    report.attach_artifact(entry.weights_path);
+    // An int8 copy of the previous weights would otherwise keep serving CPU fallback
+    let _ = std::fs::remove_file(format!("{}.mpk", crate::quantization::quantized_path(entry.weights_path)));
+    crate::model_pool::evict(entry.name);
+    crate::model_pool::evict(&format!("{}@cpu", entry.name));
+    log_info!("[Training] Saved {} to {}", entry.name, entry.weights_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold_metrics(fold: usize, val_loss: f32, val_accuracy: f32) -> FoldMetrics {
+        FoldMetrics { fold: fold + 1, train_loss: 0.0, val_loss, val_accuracy, checkpoint: None, run_id: None }
+    }
+
+    #[test]
+    fn every_fold_runs_when_the_data_does_not_divide_evenly() {
+        let data: Vec<usize> = (0..9).collect();
+        let mut validated = Vec::new();
+        let report = cross_validate(&data, 6, 42, |fold, train, validation| {
+            assert_eq!(train.len() + validation.len(), data.len());
+            validated.extend_from_slice(validation);
+            fold_metrics(fold, validation.len() as f32, 0.0)
+        });
+        let sizes: Vec<f32> = report.folds.iter().map(|f| f.val_loss).collect();
+        assert_eq!(sizes, [2.0, 2.0, 2.0, 1.0, 1.0, 1.0]);
+
+        validated.sort();
+        assert_eq!(validated, data, "every example is validated exactly once");
+    }
+
+    #[test]
+    fn folds_of_label_sorted_data_mix_labels() {
+        // Sorted by label, as exported datasets usually are
+        let data: Vec<bool> = (0..20).map(|i| i >= 10).collect();
+        let report = cross_validate(&data, 4, 42, |fold, _, validation| {
+            let positive = validation.iter().filter(|&&label| label).count();
+            fold_metrics(fold, 0.0, positive as f32 / validation.len() as f32)
+        });
+        assert_eq!(report.folds.len(), 4);
+        assert!(
+            report.folds.iter().all(|f| f.val_accuracy > 0.0 && f.val_accuracy < 1.0),
+            "every validation fold holds both labels"
+        );
+    }
+
+    #[test]
+    fn fold_checkpoints_stay_under_a_relative_dir() {
+        let path = fold_checkpoint_path("models/cv", 2).unwrap();
+        assert_eq!(path, std::path::Path::new("models/cv").join("fold-2"));
+        assert!(fold_checkpoint_path("./cv", 1).is_ok());
+        for dir in ["", "/tmp/cv", "../cv", "models/../../cv"] {
+            assert!(fold_checkpoint_path(dir, 1).is_err(), "{} is refused", dir);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use burn::backend::{Autodiff, NdArray, wgpu::Wgpu};
+use burn::tensor::backend::AutodiffBackend;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::classifier::{TextClassifier, TextClassifierConfig};
+use crate::dataset::LabeledExample;
+use crate::compute;
+use crate::events::publish;
+use crate::gpu::{self, GpuError};
+use crate::storage::{now_millis, put_typed};
+use crate::training::{evaluate_classifier, train_classifier, SeededRng, TrainingConfig};
+
+// ============================================================================
+// Hyperparameter Search
+// ============================================================================
+
+/// Collection holding one document per finished trial
+pub const TUNING_COLLECTION: &str = "tuning";
+
+/// Candidate values for each tuned hyperparameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSpace {
+    pub hidden_sizes: Vec<usize>,
+    pub num_layers: Vec<usize>,
+    pub learning_rates: Vec<f64>,
+}
+
+impl Default for SearchSpace {
+    fn default() -> Self {
+        Self {
+            hidden_sizes: vec![64, 128, 256],
+            num_layers: vec![1, 2],
+            learning_rates: vec![1e-2, 1e-3, 1e-4],
+        }
+    }
+}
+
+/// How trials are drawn from the search space
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStrategy {
+    /// Every combination
+    Grid,
+    /// `trials` combinations sampled uniformly
+    Random { trials: usize, seed: u64 },
+}
+
+/// Settings for a search run
+#[derive(Debug, Clone)]
+pub struct TuningConfig {
+    pub space: SearchSpace,
+    pub strategy: SearchStrategy,
+    /// Base training settings; tuned fields are overridden per trial
+    pub training: TrainingConfig,
+    /// Maximum trials training at the same time
+    pub max_concurrent: usize,
+    /// GPU memory available to trials (MB); trials wait until their estimate fits
+    pub gpu_memory_budget_mb: usize,
+    /// Fraction of examples held out for validation
+    pub validation_split: f32,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            space: SearchSpace::default(),
+            strategy: SearchStrategy::Grid,
+            training: TrainingConfig::default(),
+            max_concurrent: 2,
+            gpu_memory_budget_mb: 1024,
+            validation_split: 0.2,
+        }
+    }
+}
+
+/// Hyperparameters of one trial
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrialParams {
+    pub hidden_size: usize,
+    pub num_layers: usize,
+    pub learning_rate: f64,
+}
+
+/// Outcome of one trial
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrialResult {
+    pub search_id: String,
+    pub trial: usize,
+    pub params: TrialParams,
+    pub train_loss: f32,
+    pub val_loss: f32,
+    pub val_accuracy: f32,
+}
+
+/// Outcome of a whole search
+#[derive(Debug, Clone, Serialize)]
+pub struct TuningReport {
+    pub search_id: String,
+    pub trials: Vec<TrialResult>,
+    /// Trial with the lowest validation loss
+    pub best: Option<TrialResult>,
+}
+
+/// Expand the search space into the trials to run
+pub fn generate_trials(space: &SearchSpace, strategy: &SearchStrategy) -> Vec<TrialParams> {
+    let mut grid = Vec::new();
+    for &hidden_size in &space.hidden_sizes {
+        for &num_layers in &space.num_layers {
+            for &learning_rate in &space.learning_rates {
+                grid.push(TrialParams { hidden_size, num_layers, learning_rate });
+            }
+        }
+    }
+
+    match strategy {
+        SearchStrategy::Grid => grid,
+        SearchStrategy::Random { trials, seed } => {
+            if grid.is_empty() {
+                return grid;
+            }
+            let mut rng = SeededRng::new(*seed);
+            (0..*trials).map(|_| grid[rng.next_index(grid.len())]).collect()
+        }
+    }
+}
+
+/// Rough GPU memory needed to train a classifier: weights, gradients and two Adam moments
+pub fn estimate_trial_memory_mb(config: &TextClassifierConfig) -> usize {
+    let lstm_input = config.embedding_size + config.hidden_size;
+    let lstm_params = config.num_layers * 4 * config.hidden_size * (lstm_input + 2);
+    let params = config.vocab_size * config.embedding_size
+        + lstm_params
+        + config.hidden_size * config.labels.len();
+    (params * 4 * 4).div_ceil(1024 * 1024).max(1)
+}
+
+/// Shuffle `examples` with the training seed and hold out `validation_split` of them
+///
+/// Any positive split holds out at least one example. Fails when nothing is
+/// left to train on, or when no held-out example has a label the classifier
+/// knows: such a trial would validate on nothing, report a zero loss and win
+/// the search.
+pub fn split_examples(examples: &[LabeledExample], config: &TuningConfig) -> anyhow::Result<(Vec<LabeledExample>, Vec<LabeledExample>)> {
+    let mut train = SeededRng::new(config.training.seed).shuffled(examples);
+    let held_out = ((examples.len() as f32) * config.validation_split.clamp(0.0, 1.0)).ceil() as usize;
+    let validation = train.split_off(examples.len() - held_out.min(examples.len()));
+
+    anyhow::ensure!(!train.is_empty(), "no examples left to train on with validation_split {}", config.validation_split);
+    let labels = TextClassifierConfig::default().labels;
+    anyhow::ensure!(
+        validation.iter().any(|example| labels.contains(&example.label)),
+        "no labeled examples to validate on ({} held out with validation_split {})",
+        validation.len(), config.validation_split
+    );
+    Ok((train, validation))
+}
+
+/// Train and validate one trial on backend `B`; returns (train loss, validation loss, validation accuracy)
+fn train_trial<B: AutodiffBackend>(
+    train: &[LabeledExample],
+    validation: &[LabeledExample],
+    classifier_config: &TextClassifierConfig,
+    training_config: &TrainingConfig,
+) -> (f32, f32, f32) {
+    let device = Default::default();
+    let model = training_config.init_seeded::<B, _>(&device, || TextClassifier::<B>::new(classifier_config, &device));
+    let (model, report) = train_classifier(model, train, classifier_config, training_config, &device);
+    let (val_loss, val_accuracy) = evaluate_classifier(&model, validation, classifier_config, &device);
+    report.set_metric("val_loss", val_loss);
+    report.set_metric("val_accuracy", val_accuracy);
+    (report.final_loss().unwrap_or_default(), val_loss, val_accuracy)
+}
+
+/// Run a hyperparameter search over the classifier, storing every trial and streaming progress events
+///
+/// Fails before running any trial if the examples can't be split (see `split_examples`).
+pub async fn run_search(examples: Vec<LabeledExample>, config: TuningConfig) -> anyhow::Result<TuningReport> {
+    let (train, validation) = split_examples(&examples, &config)?;
+    let search_id = format!("search-{}", now_millis());
+    let trials = generate_trials(&config.space, &config.strategy);
+    publish("tuning.started", json!({
+        "search_id": search_id,
+        "trials": trials.len(),
+        "train_examples": train.len(),
+        "validation_examples": validation.len(),
+    }));
+
+    let examples = Arc::new((train, validation));
+    let concurrency = Arc::new(Semaphore::new(config.max_concurrent.max(1)));
+    let memory = Arc::new(Semaphore::new(config.gpu_memory_budget_mb.max(1)));
+
+    let mut handles = Vec::with_capacity(trials.len());
+    for (trial, params) in trials.into_iter().enumerate() {
+        let classifier_config = TextClassifierConfig {
+            hidden_size: params.hidden_size,
+            num_layers: params.num_layers,
+            ..TextClassifierConfig::default()
+        };
+        let training_config = TrainingConfig {
+            learning_rate: params.learning_rate,
+            dataset_transform: json!({ "split": "train", "validation_split": config.validation_split }),
+            ..config.training.clone()
+        };
+        let memory_mb = estimate_trial_memory_mb(&classifier_config).min(config.gpu_memory_budget_mb.max(1));
+        let (examples, concurrency, memory) = (examples.clone(), concurrency.clone(), memory.clone());
+        let search_id = search_id.clone();
+
+        handles.push(tokio::spawn(async move {
+            let _slot = concurrency.acquire_owned().await.ok()?;
+            let _memory = memory.acquire_many_owned(memory_mb as u32).await.ok()?;
+            publish("tuning.trial_started", json!({ "search_id": search_id, "trial": trial, "params": params }));
+
+            let result = compute::run_bulk("tuning.trial", move || {
+                let (train, validation) = (&examples.0, &examples.1);
+                // Out-of-memory failures shrink the batch and eventually move the trial to the CPU
+                let (train_loss, val_loss, val_accuracy) = loop {
+                    if gpu::cpu_fallback() {
+                        break train_trial::<Autodiff<NdArray>>(train, validation, &classifier_config, &training_config);
+                    }
+                    let training_config = TrainingConfig {
+                        batch_size: gpu::effective_batch_size(training_config.batch_size),
+                        ..training_config.clone()
+                    };
+                    let attempt = gpu::guarded("tuning.trial", || {
+                        train_trial::<Autodiff<Wgpu>>(train, validation, &classifier_config, &training_config)
+                    });
+                    match attempt {
+                        Ok(metrics) => break metrics,
+                        Err(GpuError::OutOfMemory { .. }) => continue,
+                        Err(e) => {
+                            log_error!("[Tuning] Trial {} of {} failed: {}", trial, search_id, e);
+                            return None;
+                        }
+                    }
+                };
+                Some(TrialResult { search_id, trial, params, train_loss, val_loss, val_accuracy })
+            }).await.ok()??;
+
+            let key = format!("{}-{:03}", result.search_id, result.trial);
+            if let Err(e) = put_typed(TUNING_COLLECTION, &key, &result) {
+                log_error!("[Tuning] Failed to store trial {}: {}", key, e);
+            }
+            publish("tuning.trial_finished", json!(result));
+            Some(result)
+        }));
+    }
+
+    let mut results = Vec::new();
+    for handle in handles {
+        if let Ok(Some(result)) = handle.await {
+            results.push(result);
+        }
+    }
+
+    let best = results
+        .iter()
+        .filter(|result| result.val_loss.is_finite())
+        .min_by(|a, b| a.val_loss.total_cmp(&b.val_loss))
+        .cloned();
+    publish("tuning.finished", json!({ "search_id": search_id, "best": best }));
+    log_info!("[Tuning] {} finished {} trials, best: {:?}", search_id, results.len(), best.as_ref().map(|b| b.params));
+
+    Ok(TuningReport { search_id, trials: results, best })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn examples(count: usize) -> Vec<LabeledExample> {
+        // Sorted by label, as exported datasets usually are
+        (0..count)
+            .map(|i| LabeledExample {
+                text: format!("example {}", i),
+                label: if i < count / 2 { "negative" } else { "positive" }.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_is_shuffled_with_the_training_seed() {
+        let examples = examples(20);
+        let config = TuningConfig::default();
+        let (train, validation) = split_examples(&examples, &config).unwrap();
+        assert_eq!((train.len(), validation.len()), (16, 4));
+        assert!(validation.iter().any(|e| e.label == "negative"), "validation is the unshuffled tail");
+
+        let (again, _) = split_examples(&examples, &config).unwrap();
+        assert_eq!(train.iter().map(|e| &e.text).collect::<Vec<_>>(), again.iter().map(|e| &e.text).collect::<Vec<_>>());
+
+        let mut all: Vec<_> = train.iter().chain(&validation).map(|e| e.text.clone()).collect();
+        all.sort();
+        let mut expected: Vec<_> = examples.iter().map(|e| e.text.clone()).collect();
+        expected.sort();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn small_datasets_keep_one_validation_example() {
+        let (train, validation) = split_examples(&examples(2), &TuningConfig::default()).unwrap();
+        assert_eq!((train.len(), validation.len()), (1, 1));
+    }
+
+    #[test]
+    fn split_without_validation_examples_is_rejected() {
+        let config = TuningConfig { validation_split: 0.0, ..TuningConfig::default() };
+        assert!(split_examples(&examples(10), &config).is_err());
+
+        let unknown = vec![LabeledExample { text: "a".into(), label: "other".into() }; 10];
+        assert!(split_examples(&unknown, &TuningConfig::default()).is_err());
+
+        assert!(split_examples(&examples(1), &TuningConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::config;
+
+// ============================================================================
+// Update Check
+// ============================================================================
+//
+// Disabled unless `PATTERN_CLOCK_UPDATE_URL` (or `update_url` in the config
+// file) points at a releases feed in GitHub's format, e.g.
+//
+//     https://api.github.com/repos/vecnode/pattern-clock/releases/latest
+//
+// Either a single release object or the release list works; drafts and
+// pre-releases are skipped. A release is offered when its tag (`v1.2.3` or
+// `1.2.3`) is newer than the running version.
+
+/// Version of this build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A release newer than the running version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseInfo {
+    pub version: String,
+    pub name: String,
+    /// Changelog in Markdown
+    pub notes: String,
+    /// Release page
+    pub url: String,
+    pub published_at: Option<String>,
+}
+
+/// Release as returned by the GitHub API (only the fields used here)
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    html_url: String,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ReleaseFeed {
+    Latest(GithubRelease),
+    List(Vec<GithubRelease>),
+}
+
+/// Numeric `major.minor.patch` parts of a version tag (`v` prefix and `-pre` / `+build` suffixes ignored)
+fn parse_version(tag: &str) -> Option<Vec<u64>> {
+    let version = tag.trim().trim_start_matches(['v', 'V']);
+    let version = version.split(['-', '+']).next()?;
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Whether `candidate` is a newer version than `current`
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => false,
+    }
+}
+
+/// Fetch the configured releases feed; `None` when disabled or already up to date
+pub async fn check_for_update() -> anyhow::Result<Option<ReleaseInfo>> {
+    let Some(url) = config().update_url.clone() else {
+        return Ok(None);
+    };
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("User-Agent", concat!("pattern-clock/", env!("CARGO_PKG_VERSION")))
+        .header("Accept", "application/vnd.github+json")
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?;
+    let releases = match response.json::<ReleaseFeed>().await? {
+        ReleaseFeed::Latest(release) => vec![release],
+        ReleaseFeed::List(releases) => releases,
+    };
+
+    let newest = releases
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter_map(|release| parse_version(&release.tag_name).map(|version| (version, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release);
+
+    Ok(newest
+        .filter(|release| is_newer(&release.tag_name, CURRENT_VERSION))
+        .map(|release| ReleaseInfo {
+            version: release.tag_name.trim_start_matches(['v', 'V']).to_string(),
+            name: release.name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| release.tag_name.clone()),
+            notes: release.body.unwrap_or_default(),
+            url: release.html_url,
+            published_at: release.published_at,
+        }))
+}